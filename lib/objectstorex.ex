@@ -28,6 +28,33 @@ defmodule ObjectStoreX do
         ]
       end
 
+  ## Configuration
+
+  Requests to storage providers run concurrently, however many stores are
+  open. To cap the requests in flight across all stores, for example to stay
+  under a provider's connection limits, set `:max_concurrent_requests`:
+
+      config :objectstorex, max_concurrent_requests: 256
+
+  Requests beyond the limit wait for a free slot. A download or listing holds
+  a slot only while its next chunk is fetched, so keep the limit well above
+  the number of streams you pipe into uploads. The setting is read when the
+  NIF is loaded; by default requests aren't limited.
+
+  Async work runs on a multi-threaded Tokio runtime inside the NIF, with one
+  worker thread per core by default. Tune it for your workload with
+  `:runtime`:
+
+      config :objectstorex, :runtime,
+        worker_threads: 4,
+        max_blocking_threads: 64,
+        thread_name: "objectstorex"
+
+  `:worker_threads` run async requests, `:max_blocking_threads` caps the
+  threads used for blocking work such as local file access (default 512), and
+  `:thread_name` names the threads in tools like `top -H`. Like
+  `:max_concurrent_requests`, these are read when the NIF is loaded.
+
   ## Quick Start
 
       # Create a store (S3 example)
@@ -56,6 +83,7 @@ defmodule ObjectStoreX do
   - **`:gcs`** - Google Cloud Storage
   - **`:local`** - Local filesystem
   - **`:memory`** - In-memory storage (for testing)
+  - **`:custom`** - A backend implemented in Elixir (see `ObjectStoreX.Backend`)
 
   See `new/2` for provider-specific configuration options.
 
@@ -88,7 +116,7 @@ defmodule ObjectStoreX do
       end
 
   ### Optimistic Locking
-  Compare-and-swap for concurrent updates:
+  Compare-and-swap for concurrent updates (`update/4` retries for you):
 
       {:ok, meta} = ObjectStoreX.head(store, "counter.json")
       case ObjectStoreX.put(store, "counter.json", new_data,
@@ -121,6 +149,26 @@ defmodule ObjectStoreX do
   For detailed information about error types, context, and retry strategies,
   see `ObjectStoreX.Error`.
 
+  ## Timeouts and Cancellation
+
+  Operations accepting `:profile` also accept:
+
+  - `:timeout_ms` - Fail with `{:error, {:timeout, details}}` once the
+    operation has taken this long, dropping its requests. For streaming
+    downloads and listings the timeout covers the whole stream.
+  - `:op_ref` - Token from `new_op_ref/0`; `cancel_op/1` makes the operation
+    fail with `{:error, :cancelled}`
+
+      case ObjectStoreX.get(store, "huge.bin", timeout_ms: 5_000) do
+        {:ok, data} -> {:ok, data}
+        {:error, {:timeout, _details}} -> :retry_later
+        {:error, reason} -> {:error, reason}
+      end
+
+  Calls to provider APIs object_store doesn't expose, such as S3 tagging,
+  paged listings and appends, are bounded too. Upload sessions and readers or
+  writers opened with these options stay bounded for as long as they are used.
+
   ## Architecture
 
   ObjectStoreX uses Rustler NIFs to call the Rust `object_store` library,
@@ -147,10 +195,18 @@ defmodule ObjectStoreX do
 
   @type store :: reference()
   @type path :: String.t()
-  @type provider :: :s3 | :azure | :gcs | :local | :memory
+  @type provider :: :s3 | :azure | :gcs | :http | :local | :memory | :custom
+  @typedoc """
+  Metadata of an object.
+
+  `:last_modified` is a human-readable string; `:last_modified_us` is the
+  same time in Unix microseconds, for
+  `DateTime.from_unix!(meta.last_modified_us, :microsecond)`.
+  """
   @type metadata :: %{
           location: String.t(),
           last_modified: String.t(),
+          last_modified_us: integer(),
           size: non_neg_integer(),
           etag: String.t() | nil
         }
@@ -177,6 +233,20 @@ defmodule ObjectStoreX do
         secret_access_key: System.get_env("AWS_SECRET_ACCESS_KEY")
       )
 
+      # Requester-pays public dataset
+      {:ok, store} = ObjectStoreX.new(:s3,
+        bucket: "requester-pays-dataset",
+        region: "us-east-1",
+        request_payer: true
+      )
+
+      # S3 with uploads encrypted by a KMS key
+      {:ok, store} = ObjectStoreX.new(:s3,
+        bucket: "my-bucket",
+        region: "us-east-1",
+        sse: {:kms, "arn:aws:kms:us-east-1:111122223333:key/my-key"}
+      )
+
       # Azure
       {:ok, store} = ObjectStoreX.new(:azure,
         account: "myaccount",
@@ -190,23 +260,209 @@ defmodule ObjectStoreX do
         service_account_key: File.read!("credentials.json")
       )
 
+      # GCS with short-lived OAuth tokens from a token broker
+      {:ok, store} = ObjectStoreX.new(:gcs,
+        bucket: "my-gcs-bucket",
+        token_fun: fn -> TokenBroker.fetch(:gcs) end
+      )
+
+      # HTTP(S) or WebDAV server
+      {:ok, store} = ObjectStoreX.new(:http,
+        url: "https://artifacts.example.com/releases",
+        headers: %{"authorization" => "Bearer " <> token}
+      )
+
       # Local filesystem
       {:ok, store} = ObjectStoreX.new(:local, path: "/tmp/storage")
 
+      # Local filesystem shared with other system users
+      {:ok, store} = ObjectStoreX.new(:local,
+        path: "/srv/exports",
+        file_mode: 0o644,
+        dir_mode: 0o755
+      )
+
+      # Local filesystem, created on demand and pruned of empty directories
+      {:ok, store} = ObjectStoreX.new(:local,
+        path: "/var/spool/uploads",
+        create_path: true,
+        automatic_cleanup: true
+      )
+
       # In-memory (for testing)
       {:ok, store} = ObjectStoreX.new(:memory)
+
+      # In-memory, shared by name and seeded with fixtures
+      {:ok, store} = ObjectStoreX.new(:memory,
+        name: "fixtures",
+        objects: %{"users.json" => ~s([{"id": 1}])}
+      )
+
+      # Custom backend implemented in Elixir
+      {:ok, store} = ObjectStoreX.new(:custom, backend: MyApp.BlobBackend, arg: opts)
+
+  ## Common Options
+
+  - `:trace` - Pid of a logger process receiving a trace of every request.
+    Each request gets an operation id and sends, in order:
+
+        {:objectstorex_trace, op_id, :start, %{operation: op, path: path}}
+        {:objectstorex_trace, op_id, :retry, %{attempt: n, status: status, message: msg}}
+        {:objectstorex_trace, op_id, :finish,
+         %{operation: op, path: path, duration_us: us, result: :ok | error, status: status}}
+
+    `:retry` is sent for every retry the client makes after a server or
+    transport error. `status` is the HTTP status of the failed response when
+    there was one, otherwise `nil`. Resumable upload sessions and
+    provider-specific requests (tagging, conditional deletes, server-side part
+    copies) are not traced.
+
+  ## S3 Options
+
+  - `:bucket` - Bucket name (required)
+  - `:region` - Bucket region
+  - `:access_key_id`, `:secret_access_key` - Static credentials
+  - `:endpoint` - Endpoint of an S3-compatible service
+  - `:virtual_hosted_style` - Address the bucket by host name
+    (`https://bucket.host`) instead of by path (`https://host/bucket`). With
+    `:endpoint`, the endpoint must already include the bucket. Path-style is
+    the default, as most S3-compatible services (MinIO, Ceph RGW) expect it.
+  - `:allow_http` - Allow a plain `http://` endpoint (default: `false`)
+  - `:sse` - Server-side encryption requested for every upload, copy and
+    multipart upload:
+    - `:s3` - SSE-S3, with keys managed by S3
+    - `:kms` or `{:kms, key_id}` - SSE-KMS, with the account's default key or
+      the given KMS key id or ARN
+    - `:dsse_kms` or `{:dsse_kms, key_id}` - Dual-layer SSE-KMS
+    - `{:customer_key, key}` - SSE-C, with a 32-byte binary key. The key is
+      sent with every request, including reads, so objects written through
+      the store can only be read through a store with the same key.
+  - `:sse_bucket_key` - Enable or disable S3 Bucket Keys for SSE-KMS,
+    overriding the bucket's default
+  - `:request_payer` - Agree to pay for requests to requester-pays buckets,
+    such as many public datasets; without it, they are denied (default: `false`)
+
+  ## GCS and Azure Options
+
+  - `:bucket` - GCS bucket name (required for GCS)
+  - `:service_account_key` - GCS service account key, as JSON
+  - `:account`, `:container` - Azure storage account and container (required
+    for Azure)
+  - `:access_key` - Azure storage account key
+  - `:token` - Pre-fetched OAuth bearer token, as a string or as
+    `{token, expires_at}` with a `DateTime` or Unix timestamp
+  - `:token_fun` - Function fetching a new token, returning `{:ok, token,
+    expires_at}`, `{:ok, token}` or `{:error, reason}`. It is called shortly
+    before the current token expires, or on the first request without
+    `:token`, from a `ObjectStoreX.TokenServer` linked to the caller.
+  - `:token_server` - Pid or name of an already started
+    `ObjectStoreX.TokenServer`, instead of `:token_fun`
+
+  With `:token`, `:token_fun` or `:token_server`, requests are authorized
+  with bearer tokens only, for environments where key files are prohibited
+  and only a token broker is available. Without a token function, requests
+  fail once the token has expired.
+
+  ## Custom Options
+
+  - `:backend` - Module implementing `ObjectStoreX.Backend`; a backend server
+    linked to the caller is started for it
+  - `:arg` - Argument passed to the backend's `init/1`
+  - `:server` - Pid or name of an already started `ObjectStoreX.Backend`
+    server, instead of `:backend`
+  - `:timeout` - Milliseconds to wait for the server's reply to each
+    operation (default: `30_000`)
+
+  ## HTTP Options
+
+  - `:url` - Base URL; object paths are resolved below it
+  - `:headers` - Map or list of `{name, value}` headers sent with every request
+
+  Any HTTP server supports reads (`get`, `head`, ranges). Writes, deletes,
+  copies and listings require a WebDAV server. Plain `http://` URLs are
+  allowed.
+
+  ## Memory Options
+
+  - `:name` - Share the store by name: every `new(:memory, name: name)` call
+    returns a handle to the same contents while any handle to it is alive, so
+    concurrent test processes can share fixtures
+  - `:objects` - Map or list of `{path, binary}` contents to seed the store
+    with. Objects are only written when the store is created, not when an
+    existing named store is returned.
+
+  ## Local Options
+
+  - `:path` - Root directory; relative paths are resolved against the current
+    directory, and `/` separators are accepted on Windows (including `\\\\?\\`
+    long-path roots)
+  - `:file_mode` - Mode bits set on every written file
+  - `:dir_mode` - Mode bits set on directories created by writes
+  - `:create_path` - Create the root directory if it doesn't exist, instead of
+    failing (default: `false`)
+  - `:automatic_cleanup` - Remove directories left empty by deletes (default: `false`)
+  - `:copy_strategy` - How `copy/3`, `copy_prefix/4` and friends copy files:
+    `:auto` clones with a reflink where the filesystem supports it (btrfs,
+    XFS, APFS), then tries a hard link, then copies the bytes; `:reflink`
+    never hard links, so copies don't share an inode with their source;
+    `:copy` always copies the bytes (default: `:auto`)
+  - `:no_prefix` - Create a store without a root directory, addressing the whole
+    filesystem with absolute locations such as `"tmp/file.txt"` for
+    `/tmp/file.txt`. Mutually exclusive with `:path` (default: `false`)
+
+  Modes are applied explicitly after each write, so they are not reduced by the
+  process umask. They are ignored on platforms without Unix permissions.
+
+  Reflinked and hard-linked copies take no extra space or time, which makes
+  prefix snapshots of local stores near-instant. Writes through the store
+  replace files rather than modifying them, so such copies still behave like
+  independent objects.
+
+  Object locations of local stores always use `/` separators, on every
+  platform, so keys listed on Windows match keys listed elsewhere. Pass keys
+  with `/` separators as well.
   """
   @spec new(provider(), keyword()) :: {:ok, store()} | {:error, term()}
   @spec new(provider()) :: {:ok, store()} | {:error, term()}
+  def new(provider, opts) do
+    {logger, opts} = Keyword.pop(opts, :trace)
+
+    case build(provider, opts) do
+      {:ok, store} when is_pid(logger) -> {:ok, Native.with_tracing(store, logger)}
+      result -> result
+    end
+  end
 
-  def new(:s3, opts) do
+  defp build(:s3, opts) do
     bucket = Keyword.fetch!(opts, :bucket)
     region = Keyword.get(opts, :region)
     access_key_id = Keyword.get(opts, :access_key_id)
     secret_access_key = Keyword.get(opts, :secret_access_key)
     endpoint = Keyword.get(opts, :endpoint)
 
-    case Native.new_s3(bucket, region, access_key_id, secret_access_key, endpoint) do
+    options = %{
+      sse: Keyword.get(opts, :sse),
+      sse_bucket_key: Keyword.get(opts, :sse_bucket_key),
+      request_payer: Keyword.get(opts, :request_payer),
+      virtual_hosted_style: Keyword.get(opts, :virtual_hosted_style),
+      allow_http: Keyword.get(opts, :allow_http)
+    }
+
+    result =
+      if Enum.all?(Map.values(options), &is_nil/1) do
+        Native.new_s3(bucket, region, access_key_id, secret_access_key, endpoint)
+      else
+        Native.new_s3_with_options(
+          bucket,
+          region,
+          access_key_id,
+          secret_access_key,
+          endpoint,
+          options
+        )
+      end
+
+    case result do
       store when is_reference(store) -> {:ok, store}
       error -> {:error, error}
     end
@@ -214,24 +470,103 @@ defmodule ObjectStoreX do
     e -> {:error, Exception.message(e)}
   end
 
-  def new(:azure, opts) do
+  defp build(:azure, opts) do
     account = Keyword.fetch!(opts, :account)
     container = Keyword.fetch!(opts, :container)
     access_key = Keyword.get(opts, :access_key)
 
-    case Native.new_azure(account, container, access_key) do
+    result =
+      case bearer_token(opts) do
+        nil -> Native.new_azure(account, container, access_key)
+        {:ok, token, server} -> Native.new_azure_with_token(account, container, token, server)
+        error -> error
+      end
+
+    case result do
       store when is_reference(store) -> {:ok, store}
+      {:error, reason} -> {:error, reason}
       error -> {:error, error}
     end
   rescue
     e -> {:error, Exception.message(e)}
   end
 
-  def new(:gcs, opts) do
+  defp build(:gcs, opts) do
     bucket = Keyword.fetch!(opts, :bucket)
     service_account_key = Keyword.get(opts, :service_account_key)
 
-    case Native.new_gcs(bucket, service_account_key) do
+    result =
+      case bearer_token(opts) do
+        nil -> Native.new_gcs(bucket, service_account_key)
+        {:ok, token, server} -> Native.new_gcs_with_token(bucket, token, server)
+        error -> error
+      end
+
+    case result do
+      store when is_reference(store) -> {:ok, store}
+      {:error, reason} -> {:error, reason}
+      error -> {:error, error}
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  defp build(:http, opts) do
+    url = Keyword.fetch!(opts, :url)
+
+    headers =
+      opts
+      |> Keyword.get(:headers, [])
+      |> Enum.map(fn {name, value} -> {to_string(name), to_string(value)} end)
+
+    case Native.new_http(url, headers) do
+      store when is_reference(store) -> {:ok, store}
+      error -> {:error, error}
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  defp build(:custom, opts) do
+    server =
+      case Keyword.fetch(opts, :server) do
+        {:ok, server} ->
+          {:ok, server}
+
+        :error ->
+          ObjectStoreX.Backend.start_link(Keyword.take(opts, [:backend, :arg]))
+      end
+
+    with {:ok, server} <- server,
+         pid when is_pid(pid) <- GenServer.whereis(server) do
+      {:ok, Native.new_backend(pid, Keyword.get(opts, :timeout, 30_000))}
+    else
+      nil -> {:error, :not_found}
+      error -> error
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  defp build(:local, opts) do
+    path = local_path(opts)
+
+    options = %{
+      file_mode: Keyword.get(opts, :file_mode),
+      dir_mode: Keyword.get(opts, :dir_mode),
+      create_path: Keyword.get(opts, :create_path, false),
+      automatic_cleanup: Keyword.get(opts, :automatic_cleanup, false),
+      copy_strategy: Keyword.get(opts, :copy_strategy)
+    }
+
+    result =
+      if is_binary(path) and Enum.all?(Map.values(options), &(&1 in [nil, false])) do
+        Native.new_local(path)
+      else
+        Native.new_local_with_options(path, options)
+      end
+
+    case result do
       store when is_reference(store) -> {:ok, store}
       error -> {:error, error}
     end
@@ -239,10 +574,15 @@ defmodule ObjectStoreX do
     e -> {:error, Exception.message(e)}
   end
 
-  def new(:local, opts) do
-    path = Keyword.fetch!(opts, :path)
+  defp build(:memory, opts) do
+    name = Keyword.get(opts, :name)
+
+    objects =
+      opts
+      |> Keyword.get(:objects, [])
+      |> Enum.map(fn {path, data} -> {to_string(path), IO.iodata_to_binary(data)} end)
 
-    case Native.new_local(path) do
+    case Native.new_memory_with_options(name && to_string(name), objects) do
       store when is_reference(store) -> {:ok, store}
       error -> {:error, error}
     end
@@ -250,6 +590,44 @@ defmodule ObjectStoreX do
     e -> {:error, Exception.message(e)}
   end
 
+  # Initial token and token server of a store authorized with bearer tokens,
+  # or nil when the store uses its other credentials
+  defp bearer_token(opts) do
+    token =
+      case Keyword.get(opts, :token) do
+        nil -> nil
+        token when is_binary(token) -> {token, nil}
+        {token, expires_at} -> ObjectStoreX.TokenServer.token(token, expires_at)
+      end
+
+    server =
+      case Keyword.take(opts, [:token_fun, :token_server]) do
+        [] ->
+          {:ok, nil}
+
+        [token_fun: token_fun] when is_function(token_fun, 0) ->
+          ObjectStoreX.TokenServer.start_link(token_fun: token_fun)
+
+        [token_server: server] ->
+          case GenServer.whereis(server) do
+            pid when is_pid(pid) -> {:ok, pid}
+            nil -> {:error, :not_found}
+          end
+
+        [token_fun: _] ->
+          {:error, ":token_fun must be a function of arity 0"}
+
+        _ ->
+          {:error, "Only one of :token_fun and :token_server can be given"}
+      end
+
+    case {token, server} do
+      {nil, {:ok, nil}} -> nil
+      {token, {:ok, pid}} -> {:ok, token, pid}
+      {_token, error} -> error
+    end
+  end
+
   @doc """
   Create an in-memory storage provider (shorthand for testing).
 
@@ -269,228 +647,2485 @@ defmodule ObjectStoreX do
     e -> {:error, Exception.message(e)}
   end
 
-  @type put_result :: %{
-          etag: String.t(),
-          version: String.t()
-        }
-
-  @doc """
-  Upload an object to storage.
+  defp local_path(opts) do
+    if Keyword.get(opts, :no_prefix, false) do
+      if Keyword.has_key?(opts, :path) do
+        raise ArgumentError, ":path and :no_prefix are mutually exclusive"
+      end
 
-  ## Options
+      nil
+    else
+      Keyword.fetch!(opts, :path)
+    end
+  end
 
-  - `:mode` - Write mode (default: `:overwrite`)
-    - `:overwrite` - Always write, overwriting any existing object
-    - `:create` - Only write if the object doesn't exist (returns `{:error, :already_exists}` if exists)
-    - `{:update, %{etag: ..., version: ...}}` - CAS operation, only write if version matches
-  - `:content_type` - MIME type (e.g., "application/json")
-  - `:content_encoding` - Encoding (e.g., "gzip")
-  - `:content_disposition` - Download behavior (e.g., "attachment; filename=file.pdf")
-  - `:cache_control` - Cache directives (e.g., "max-age=3600")
-  - `:content_language` - Language (e.g., "en-US")
-  - `:tags` - Object tags as a map (AWS/GCS only)
+  @doc """
+  Derive a restricted child handle from a store.
 
-  ## Examples
+  The child handle shares the parent's underlying client, so deriving is cheap.
+  It is the way to hand restricted handles to tenant processes: every
+  restriction is enforced natively and can't be bypassed through the child.
 
-      # Simple put (overwrite)
-      :ok = ObjectStoreX.put(store, "file.txt", "Hello, World!")
+  ## Options
 
-      # Create only (distributed lock)
-      case ObjectStoreX.put(store, "lock.txt", "locked", mode: :create) do
-        {:ok, _meta} -> :acquired
-        {:error, :already_exists} -> :already_locked
-      end
+  - `:prefix` - Scope all paths under this key prefix. The child sees paths
+    relative to the prefix and can never read or write outside it.
+  - `:read_only` - Reject all writes, deletes, copies and renames with
+    `{:error, :permission_denied}` (default: `false`)
+  - `:quota` - Maximum total bytes stored through the child. Usage starts at
+    the size of the objects already visible to the child; writes that would
+    exceed the quota fail with `{:error, :quota_exceeded}` before any data is
+    sent. Writes made through other handles are not tracked.
 
-      # Compare-and-swap (optimistic locking)
-      {:ok, meta} = ObjectStoreX.head(store, "counter.json")
-      case ObjectStoreX.put(store, "counter.json", new_data,
-                            mode: {:update, %{etag: meta[:etag], version: meta[:version]}}) do
-        {:ok, _meta} -> :success
-        {:error, :precondition_failed} -> :retry
-      end
+  Options can be given as a keyword list or a map.
 
-      # Upload with content type
-      ObjectStoreX.put(store, "data.json", json_data, content_type: "application/json")
+  ## Examples
 
-      # Upload with attributes
-      ObjectStoreX.put(store, "report.pdf", pdf_data,
-        content_type: "application/pdf",
-        content_disposition: "attachment; filename=report.pdf",
-        cache_control: "max-age=3600"
-      )
+      {:ok, tenant} = ObjectStoreX.derive(store, prefix: "tenants/acme", quota: 100_000_000)
+      :ok = ObjectStoreX.put(tenant, "report.csv", data)
+      # Stored at "tenants/acme/report.csv" in the parent store
 
-      # Upload with tags (AWS/GCS)
-      ObjectStoreX.put(store, "backup.zip", data,
-        tags: %{"environment" => "production", "backup-type" => "daily"}
-      )
+      {:ok, viewer} = ObjectStoreX.derive(store, %{prefix: "tenants/acme", read_only: true})
+      {:error, :permission_denied} = ObjectStoreX.delete(viewer, "report.csv")
   """
-  @spec put(store(), path(), binary(), keyword()) ::
-          :ok | {:ok, put_result()} | {:error, term()}
-  def put(store, path, data, opts \\ [])
-
-  def put(store, path, data, []) when is_binary(data) do
-    case Native.put(store, path, data) do
-      :ok -> :ok
+  @spec derive(store(), keyword() | map()) :: {:ok, store()} | {:error, term()}
+  def derive(store, opts) when is_list(opts) or is_map(opts) do
+    opts = Map.new(opts)
+    prefix = Map.get(opts, :prefix)
+    read_only = Map.get(opts, :read_only, false)
+    quota = Map.get(opts, :quota)
+
+    case Native.derive(store, prefix, read_only, quota) do
+      child when is_reference(child) -> {:ok, child}
       error -> {:error, error}
     end
   rescue
     e -> {:error, Exception.message(e)}
   end
 
-  def put(store, path, data, opts) when is_binary(data) and is_list(opts) do
-    mode = Keyword.get(opts, :mode, :overwrite)
-
-    result =
-      if has_attributes?(opts) do
-        put_with_attributes_internal(store, path, data, mode, opts)
-      else
-        Native.put_with_mode(store, path, data, mode)
-      end
-
-    normalize_put_result(result)
-  rescue
-    e -> {:error, Exception.message(e)}
-  end
-
-  defp has_attributes?(opts) do
-    Keyword.has_key?(opts, :content_type) or
-      Keyword.has_key?(opts, :content_encoding) or
-      Keyword.has_key?(opts, :content_disposition) or
-      Keyword.has_key?(opts, :cache_control) or
-      Keyword.has_key?(opts, :content_language) or
-      Keyword.has_key?(opts, :tags)
-  end
+  @doc """
+  Scope a store under a key prefix.
 
-  defp put_with_attributes_internal(store, path, data, mode, opts) do
-    attributes = %ObjectStoreX.Attributes{
-      content_type: Keyword.get(opts, :content_type),
-      content_encoding: Keyword.get(opts, :content_encoding),
-      content_disposition: Keyword.get(opts, :content_disposition),
-      cache_control: Keyword.get(opts, :cache_control),
-      content_language: Keyword.get(opts, :content_language)
-    }
+  Shorthand for `derive(store, prefix: prefix)`. The returned handle sees
+  paths relative to the prefix and can never read or write outside it, so it
+  can be handed to a tenant as is.
 
-    tags =
-      Keyword.get(opts, :tags, %{})
-      |> Map.to_list()
+  ## Examples
 
-    Native.put_with_attributes(store, path, data, mode, attributes, tags)
+      {:ok, tenant} = ObjectStoreX.with_prefix(store, "tenants/acme")
+      :ok = ObjectStoreX.put(tenant, "report.csv", data)
+      # Stored at "tenants/acme/report.csv" in the parent store
+  """
+  @spec with_prefix(store(), String.t()) :: {:ok, store()} | {:error, term()}
+  def with_prefix(store, prefix) when is_binary(prefix) do
+    derive(store, prefix: prefix)
   end
 
-  defp normalize_put_result({:ok, etag, version}), do: {:ok, %{etag: etag, version: version}}
-  defp normalize_put_result(:already_exists), do: {:error, :already_exists}
-  defp normalize_put_result(:precondition_failed), do: {:error, :precondition_failed}
-  defp normalize_put_result(error), do: {:error, error}
+  @throttle_keys [
+    :delete_per_call,
+    :get_per_byte,
+    :get_per_call,
+    :list_per_call,
+    :list_per_entry,
+    :list_with_delimiter_per_call,
+    :list_with_delimiter_per_entry,
+    :put_per_call
+  ]
 
   @doc """
-  Download an object from storage with optional conditional requests.
-
-  ## Options
-
-  Supports HTTP-style conditional requests for caching and consistency:
-  - `:if_match` - Only return if ETag matches (HTTP If-Match)
-  - `:if_none_match` - Only return if ETag differs (HTTP If-None-Match)
-  - `:if_modified_since` - Only return if modified after date (DateTime or Unix timestamp)
-  - `:if_unmodified_since` - Only return if not modified since date (DateTime or Unix timestamp)
-  - `:range` - Byte range `{start, end}` or `%ObjectStoreX.Range{}`
-  - `:version` - Specific object version
-  - `:head` - Return metadata only (no content)
+  Wrap a store with simulated latency.
 
-  ## Examples
+  Every operation on the returned handle sleeps before it runs (and, for
+  `:get_per_byte` and the listing options, while it streams), which lets
+  tests exercise timeouts and progress reporting against memory or local
+  stores, and lets careful callers slow down background jobs. The handle
+  shares the parent's underlying client.
 
-      # Simple get
-      {:ok, data} = ObjectStoreX.get(store, "file.txt")
+  ## Options
 
-      # HTTP cache validation
-      case ObjectStoreX.get(store, "file.txt", if_none_match: cached_etag) do
-        {:ok, data, meta} -> {:modified, data, meta}
-        {:error, :not_modified} -> :use_cache
-      end
+  All latencies are in milliseconds (fractions allowed) and default to `0`:
 
-      # Consistent read with ETag
-      {:ok, meta} = ObjectStoreX.head(store, "config.json")
-      case ObjectStoreX.get(store, "config.json", if_match: meta[:etag]) do
-        {:ok, data, _meta} -> {:ok, data}
-        {:error, :precondition_failed} -> :retry
-      end
+  - `:delete_per_call` - Before each delete
+  - `:get_per_call` - Before each get
+  - `:get_per_byte` - Per byte returned by a get
+  - `:list_per_call` - Before each listing
+  - `:list_per_entry` - Per object returned by a listing
+  - `:list_with_delimiter_per_call` - Before each delimited listing
+  - `:list_with_delimiter_per_entry` - Per entry returned by a delimited listing
+  - `:put_per_call` - Before each put
 
-      # Range read
-      {:ok, data, meta} = ObjectStoreX.get(store, "large.bin", range: {0, 1000})
+  ## Examples
 
-      # Head-only (metadata without content)
-      {:ok, _empty, meta} = ObjectStoreX.get(store, "file.txt", head: true)
+      {:ok, slow} = ObjectStoreX.with_throttle(store, get_per_call: 200, get_per_byte: 0.001)
+      # Reading 1MB from `slow` now takes about 1.2s
   """
-  @spec get(store(), path(), keyword()) ::
-          {:ok, binary()} | {:ok, binary(), metadata()} | {:error, term()}
-  def get(store, path, opts \\ [])
+  @spec with_throttle(store(), keyword() | map()) :: {:ok, store()} | {:error, term()}
+  def with_throttle(store, config) when is_list(config) or is_map(config) do
+    config = Map.new(config)
 
-  def get(store, path, []) do
-    case Native.get(store, path) do
-      data when is_binary(data) -> {:ok, data}
-      :not_found -> {:error, :not_found}
-      error -> {:error, error}
+    case Map.keys(config) -- @throttle_keys do
+      [] ->
+        config = Map.new(@throttle_keys, &{&1, to_micros(Map.get(config, &1, 0))})
+        {:ok, Native.with_throttle(store, config)}
+
+      unknown ->
+        {:error, "Unknown throttle options: #{inspect(unknown)}"}
     end
   rescue
     e -> {:error, Exception.message(e)}
   end
 
-  def get(store, path, opts) when is_list(opts) do
-    # Convert keyword options to GetOptions struct
-    get_options = %ObjectStoreX.GetOptions{
-      if_match: Keyword.get(opts, :if_match),
-      if_none_match: Keyword.get(opts, :if_none_match),
-      if_modified_since: convert_datetime_to_timestamp(Keyword.get(opts, :if_modified_since)),
-      if_unmodified_since: convert_datetime_to_timestamp(Keyword.get(opts, :if_unmodified_since)),
-      range: convert_range(Keyword.get(opts, :range)),
-      version: Keyword.get(opts, :version),
-      head: Keyword.get(opts, :head, false)
-    }
+  defp to_micros(ms) when is_number(ms) and ms >= 0, do: round(ms * 1000)
 
-    case Native.get_with_options(store, path, get_options) do
-      {:ok, data, meta} when is_map(meta) ->
-        # Convert charlist to binary if needed
-        binary_data = if is_list(data), do: :erlang.list_to_binary(data), else: data
-        {:ok, binary_data, meta}
+  @doc """
+  Cap the number of outstanding requests to a store.
 
-      :not_found ->
-        {:error, :not_found}
+  Operations on the returned handle wait for a free slot once `max_requests`
+  are in flight, which keeps bulk jobs from tripping provider rate limits.
+  Share the handle between the processes that should count against the same
+  limit; the parent store and other handles stay unlimited. Streaming
+  downloads and listings hold their slot until they finish.
 
-      :not_modified ->
-        {:error, :not_modified}
+  ## Examples
 
-      :precondition_failed ->
-        {:error, :precondition_failed}
+      {:ok, limited} = ObjectStoreX.with_concurrency_limit(store, 16)
 
-      error ->
-        {:error, error}
-    end
+      paths
+      |> Task.async_stream(&ObjectStoreX.get(limited, &1), max_concurrency: 100)
+      |> Stream.run()
+  """
+  @spec with_concurrency_limit(store(), pos_integer()) :: {:ok, store()} | {:error, term()}
+  def with_concurrency_limit(store, max_requests)
+      when is_integer(max_requests) and max_requests > 0 do
+    {:ok, Native.with_concurrency_limit(store, max_requests)}
   rescue
     e -> {:error, Exception.message(e)}
   end
 
-  # Convert DateTime to Unix timestamp, or pass through integer timestamps
-  defp convert_datetime_to_timestamp(nil), do: nil
-  defp convert_datetime_to_timestamp(%DateTime{} = dt), do: DateTime.to_unix(dt)
-  defp convert_datetime_to_timestamp(ts) when is_integer(ts), do: ts
+  @doc """
+  Limit the rate of requests and transferred bytes to a store.
 
-  # Convert range tuple or Range struct to Range struct
-  defp convert_range(nil), do: nil
-  defp convert_range({start, end_pos}), do: %ObjectStoreX.Range{start: start, end: end_pos}
-  defp convert_range(%ObjectStoreX.Range{} = range), do: range
+  Operations on the returned handle wait for tokens from two buckets: one
+  refilled at `ops_per_sec` requests per second and one at `bytes_per_sec`
+  bytes per second. Either limit may be `nil` to leave it unlimited. The
+  buckets live in the native layer, so every process using the handle shares
+  them, which keeps bulk jobs within provider request quotas.
 
-  @doc """
-  Delete an object from storage.
+  - Every call counts as one request; so does each part of a multipart
+    upload, and a whole listing
+  - Uploaded bytes are counted before they are sent; downloaded bytes as the
+    response streams in, so large downloads slow down rather than stall
+  - Buckets start full and hold one second's worth of tokens, so short bursts
+    up to the rate go through immediately
+
+  Rates may be fractional. Returns `{:error, _}` if both limits are `nil` or
+  a limit isn't positive.
 
   ## Examples
 
-      :ok = ObjectStoreX.delete(store, "file.txt")
+      # At most 100 requests and 50 MB per second across all callers
+      {:ok, limited} = ObjectStoreX.with_rate_limit(store, 100, 50_000_000)
+
+      # Only the request rate
+      {:ok, limited} = ObjectStoreX.with_rate_limit(store, 3_500, nil)
   """
-  @spec delete(store(), path()) :: :ok | {:error, term()}
-  def delete(store, path) do
-    case Native.delete(store, path) do
-      :ok -> :ok
-      error -> {:error, error}
-    end
+  @spec with_rate_limit(store(), number() | nil, number() | nil) ::
+          {:ok, store()} | {:error, term()}
+  def with_rate_limit(store, ops_per_sec, bytes_per_sec) do
+    {:ok, Native.with_rate_limit(store, to_rate(ops_per_sec), to_rate(bytes_per_sec))}
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  defp to_rate(nil), do: nil
+  defp to_rate(rate) when is_number(rate), do: rate / 1
+
+  @doc """
+  Apply default attributes and tags to every write through a store.
+
+  Defaults are merged into each put, streamed upload and multipart upload made
+  through the returned handle. An attribute, metadata key or tag given by the
+  write itself overrides the default with the same name. Copies keep the
+  attributes of their source.
+
+  ## Options
+
+  Takes the attribute and tag options of `put/4`: `:content_type`,
+  `:content_encoding`, `:content_disposition`, `:cache_control`,
+  `:content_language`, `:metadata` and `:tags`.
+
+  ## Examples
+
+      {:ok, assets} =
+        ObjectStoreX.with_defaults(store,
+          cache_control: "public, max-age=31536000, immutable",
+          tags: %{"team" => "web"}
+        )
+
+      # Gets the default cache control and tags
+      :ok = ObjectStoreX.put(assets, "app.js", js)
+
+      # Overrides the default cache control, keeps the tags
+      {:ok, _} = ObjectStoreX.put(assets, "index.html", html, cache_control: "no-cache")
+  """
+  @spec with_defaults(store(), keyword()) :: {:ok, store()} | {:error, term()}
+  def with_defaults(store, opts) do
+    {:ok, Native.with_defaults(store, put_attributes(opts), put_tags(opts))}
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Compress objects on write and decompress them on read.
+
+  Puts, streamed uploads and multipart uploads through the returned handle are
+  compressed natively with `codec` (`:gzip` or `:zstd`) and stored with a
+  matching `content_encoding`, so logs and JSON take less storage without
+  compressing in Elixir. Gets, range reads and download streams return the
+  decompressed data. A write that sets `:content_encoding` itself is stored
+  as given, for data that is already compressed.
+
+  Reads decompress objects whose data starts with the gzip or zstd magic bytes,
+  so objects written before compression was enabled are returned as stored.
+  Local stores keep no content encoding, so their objects are recognized by
+  their data alone.
+
+  Sizes returned by `head/3` and listings are the stored, compressed sizes.
+  Range reads address the decompressed data and fetch the whole object.
+
+  `level` is 0-9 for gzip (default: 6) and 1-22 for zstd (default: 3).
+
+  ## Examples
+
+      {:ok, logs} = ObjectStoreX.with_compression(store, :zstd)
+      :ok = ObjectStoreX.put(logs, "app/2025-01-01.log", log_lines)
+      {:ok, ^log_lines} = ObjectStoreX.get(logs, "app/2025-01-01.log")
+
+      {:ok, archive} = ObjectStoreX.with_compression(store, :gzip, 9)
+  """
+  @spec with_compression(store(), :gzip | :zstd, non_neg_integer() | nil) ::
+          {:ok, store()} | {:error, term()}
+  def with_compression(store, codec, level \\ nil) do
+    {:ok, Native.with_compression(store, codec, level)}
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Encrypt objects on write and decrypt them on read, client-side.
+
+  Uses envelope encryption: every object written through the returned handle
+  gets a random data key, and its data is encrypted with AES-256-GCM before it
+  leaves the process. The data key is stored in the object's metadata,
+  encrypted with a key encryption key from `key_provider`, so the provider
+  never sees plaintext or usable keys. Gets, range reads and download streams
+  decrypt natively; range reads only fetch the 64 KiB segments they need.
+
+  `key_provider` is a `{key_id, key}` pair of a string id and a 32-byte key,
+  or a list of them. The first key encrypts new objects; the others only
+  decrypt existing ones, so keys can be rotated by putting the new key first.
+
+  Reading an object that isn't encrypted, was encrypted with an unknown key or
+  was tampered with fails with `{:error, :integrity_error}`. The underlying
+  store must keep object metadata, which local stores don't. `head/3` returns
+  plaintext sizes; listings return the stored sizes, which are 16 bytes per
+  64 KiB larger.
+
+  ## Examples
+
+      key = :crypto.strong_rand_bytes(32)
+      {:ok, secure} = ObjectStoreX.with_encryption(store, {"2025-01", key})
+
+      :ok = ObjectStoreX.put(secure, "customers.csv", csv)
+      {:ok, ^csv} = ObjectStoreX.get(secure, "customers.csv")
+
+      # Rotate: encrypt with the new key, keep reading with both
+      {:ok, secure} =
+        ObjectStoreX.with_encryption(store, [{"2025-07", new_key}, {"2025-01", key}])
+  """
+  @spec with_encryption(store(), {String.t(), binary()} | [{String.t(), binary()}]) ::
+          {:ok, store()} | {:error, term()}
+  def with_encryption(store, key_provider) do
+    keys = key_provider |> List.wrap() |> Enum.map(fn {id, key} -> {to_string(id), key} end)
+    {:ok, Native.with_encryption(store, keys)}
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Serve reads of a remote store from a local cache.
+
+  A get through the returned handle that misses the cache fetches the object
+  from `remote_store`, writes a copy to `local_store` under the same path and
+  returns it; later gets, range reads and heads are served from the copy.
+  `local_store` is usually a `:local` or `:memory` store, which makes repeated
+  reads of reference data much cheaper than going to the provider each time.
+
+  Entries are keyed by path and ETag. An entry younger than `:ttl` is served
+  without contacting the remote store; after that, the next read revalidates
+  it with a conditional request, and a changed object is fetched again.
+  Without `:ttl`, every read revalidates, which still saves the transfer.
+
+  Heads and range reads that miss the cache, conditional gets and versioned
+  gets go straight to the remote store. Objects without an ETag aren't cached.
+  The cache index lives with the handle, so give each cache its own local
+  store or prefix (see `derive/2`).
+
+  ## Write Policies
+
+  - `:write_around` (default) - Writes go to the remote store and drop the
+    cached copies they replace
+  - `:write_through` - Puts go to the remote store, then the written object
+    is cached, so it can be read back without a round trip
+  - `:write_back` - Puts and streamed uploads return as soon as the local
+    store has the data and are uploaded in the background. Until then the
+    object is read from the local store, isn't evicted and isn't listed.
+    Uploads of the same path are applied in order. Copies and renames wait
+    for pending uploads first; conditional puts (`:mode`) are written
+    through. Call `flush_cache/1` before relying on the remote store, e.g.
+    before shutting down.
+
+  ## Options
+
+  - `:ttl` - How long an entry is served without revalidating it, in
+    milliseconds (default: revalidate every read)
+  - `:max_size` - Total bytes to keep cached. The least recently read entries
+    are evicted beyond it, and larger objects aren't cached (default:
+    unlimited)
+  - `:write_policy` - `:write_around`, `:write_through` or `:write_back` (see
+    above)
+
+  ## Examples
+
+      {:ok, local} = ObjectStoreX.new(:local, path: "/var/cache/reference")
+      {:ok, cached} = ObjectStoreX.with_cache(store, local, ttl: 60_000, max_size: 1_000_000_000)
+
+      # Fetched from the remote store once, then served locally
+      {:ok, data} = ObjectStoreX.get(cached, "reference/countries.json")
+  """
+  @spec with_cache(store(), store(), keyword()) :: {:ok, store()} | {:error, term()}
+  def with_cache(remote_store, local_store, opts \\ []) do
+    options = %{
+      ttl_ms: Keyword.get(opts, :ttl),
+      max_size: Keyword.get(opts, :max_size),
+      write_policy: Keyword.get(opts, :write_policy)
+    }
+
+    {:ok, Native.with_cache(remote_store, local_store, options)}
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Wait until every write-back write through a cache store has been uploaded.
+
+  Uploads that failed in the background are retried. Returns `:ok` once the
+  remote store has every write made through `store` so far, or the first
+  error. Stores not created with `with_cache/3` return
+  `{:error, :not_supported}`.
+
+  ## Examples
+
+      {:ok, spool} = ObjectStoreX.with_cache(store, local, write_policy: :write_back)
+      :ok = ObjectStoreX.put(spool, "events/batch-1.json", batch)
+      :ok = ObjectStoreX.flush_cache(spool)
+  """
+  @spec flush_cache(store()) :: :ok | {:error, term()}
+  def flush_cache(store) do
+    case Native.flush_cache(store) do
+      :ok -> :ok
+      error -> {:error, error}
+    end
+  end
+
+  @doc """
+  Combine stores into one store that routes each object by key prefix.
+
+  `routes` is a list of `{prefix, store}` pairs. Each key goes to the route
+  with the longest prefix it is under, matched by whole path segments (`"hot"`
+  routes `"hot/a.txt"` but not `"hotter/a.txt"`). An empty prefix routes every
+  key no other prefix matches; keys matching no route fail with an error.
+
+  Keys are passed to the routed store unchanged, so `"cold/a.txt"` is stored
+  as `"cold/a.txt"` in the cold store. The router is an ordinary store handle:
+
+  - Listings merge the routed stores whose prefixes overlap the listed prefix,
+    showing only the objects each route owns
+  - Copies and renames within one store are delegated to it; between stores
+    the object is read and written to the destination store, then deleted from
+    the source for renames
+
+  Returns `{:error, _}` if a prefix is given twice.
+
+  ## Examples
+
+      {:ok, hot} = ObjectStoreX.new(:memory)
+      {:ok, cold} = ObjectStoreX.new(:s3, bucket: "archive", region: "us-east-1")
+
+      {:ok, store} = ObjectStoreX.new_router([{"hot", hot}, {"", cold}])
+      :ok = ObjectStoreX.put(store, "hot/session.json", data)   # memory
+      :ok = ObjectStoreX.put(store, "reports/2025.csv", csv)    # S3
+  """
+  @spec new_router([{String.t(), store()}]) :: {:ok, store()} | {:error, term()}
+  def new_router(routes) when is_list(routes) do
+    {:ok, Native.new_router(routes)}
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Mirror every write to a primary store onto one or more replica stores.
+
+  Puts, deletes, copies, renames and multipart uploads through the returned
+  handle go to `primary` first, then to every replica. Conditional puts are
+  checked against the primary only; replicas are overwritten unconditionally.
+
+  Reads and listings are served by the primary. A read that fails because the
+  primary is unavailable (rather than because the object is missing or a
+  condition doesn't hold) is retried on each replica in turn.
+
+  ## Options
+
+  - `:consistency` - when a write returns:
+    - `:all` (default) - once the primary and every replica have it; a failed
+      replica write returns its error
+    - `:primary` - once the primary has it. Replicas are written in the
+      background, in write order; `flush_mirror/1` waits for them and returns
+      the first failure
+
+  ## Examples
+
+      {:ok, backup} = ObjectStoreX.new(:gcs, bucket: "backup")
+      {:ok, store} = ObjectStoreX.with_mirror(s3, [backup], consistency: :primary)
+
+      :ok = ObjectStoreX.put(store, "invoices/2025-001.pdf", pdf)
+      :ok = ObjectStoreX.flush_mirror(store)
+  """
+  @spec with_mirror(store(), [store(), ...], keyword()) :: {:ok, store()} | {:error, term()}
+  def with_mirror(primary, replicas, opts \\ []) when is_list(replicas) do
+    {:ok, Native.with_mirror(primary, replicas, Keyword.get(opts, :consistency))}
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Wait until the replicas of a mirror store have every write made so far.
+
+  Returns `:ok`, or the first background replication failure since the last
+  flush. Mirrors with `consistency: :all` return `:ok` right away. Stores not
+  created with `with_mirror/3` return `{:error, :not_supported}`.
+  """
+  @spec flush_mirror(store()) :: :ok | {:error, term()}
+  def flush_mirror(store) do
+    case Native.flush_mirror(store) do
+      :ok -> :ok
+      error -> {:error, error}
+    end
+  end
+
+  @doc """
+  Read objects from fallback stores when the primary store doesn't have them.
+
+  Gets (including ranges) and heads through the returned handle that fail
+  with `:not_found` or a network error are retried on each of `fallbacks` in
+  order; the first store that has the object answers. If none does, the
+  primary's error is returned. Conditional gets that don't match are not
+  retried.
+
+  Writes, deletes, listings, copies and renames only use the primary, so
+  objects read from a fallback are not copied to it. This suits serving from
+  an origin behind a CDN bucket, or reading through a migration while objects
+  are still being moved.
+
+  Returns `{:error, _}` without fallbacks.
+
+  ## Examples
+
+      {:ok, new} = ObjectStoreX.new(:gcs, bucket: "assets")
+      {:ok, old} = ObjectStoreX.new(:s3, bucket: "legacy-assets", region: "us-east-1")
+
+      {:ok, store} = ObjectStoreX.with_fallback(new, [old])
+      # Served by the legacy bucket until it has been migrated
+      {:ok, logo} = ObjectStoreX.get(store, "img/logo.png")
+  """
+  @spec with_fallback(store(), [store(), ...]) :: {:ok, store()} | {:error, term()}
+  def with_fallback(primary, fallbacks) when is_list(fallbacks) do
+    {:ok, Native.with_fallback(primary, fallbacks)}
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @typedoc """
+  Totals of one operation across all instrumented stores, as returned by
+  `get_metrics/0`.
+  """
+  @type operation_metrics :: %{
+          count: non_neg_integer(),
+          errors: non_neg_integer(),
+          bytes: non_neg_integer(),
+          duration_us: non_neg_integer()
+        }
+
+  @doc """
+  Measure every call made through a store.
+
+  Each call on the returned handle adds its duration, transferred bytes and
+  outcome to global per-operation counters, read with `get_metrics/0`. With
+  `:pid`, every call also sends the process an event:
+
+      {:objectstorex_event, operation, %{duration_us: us, bytes: n},
+       %{path: path, result: :ok | error}}
+
+  `operation` is one of `:put`, `:put_multipart`, `:get`, `:get_range`,
+  `:get_ranges`, `:head`, `:delete`, `:list`, `:list_with_delimiter`, `:copy`,
+  `:copy_if_not_exists`, `:rename` and `:rename_if_not_exists`. Gets are
+  measured until the response starts and count the bytes of the returned range;
+  multipart uploads are recorded when they complete or abort, and listings when
+  their stream is done. Operations are recorded as the calls the handle makes
+  to the provider, so e.g. `rename/4` on S3 shows up as a copy and a delete.
+
+  ## Options
+
+  - `:pid` - Process receiving an event for every call
+
+  ## Examples
+
+      {:ok, store} = ObjectStoreX.with_instrumentation(store)
+      :ok = ObjectStoreX.put(store, "file.txt", "data")
+      %{put: %{count: 1, bytes: 4}} = ObjectStoreX.get_metrics()
+
+      # Forward events to :telemetry
+      {:ok, store} = ObjectStoreX.with_instrumentation(store, pid: forwarder)
+
+      # in the forwarder process
+      def handle_info({:objectstorex_event, op, measurements, metadata}, state) do
+        :telemetry.execute([:objectstorex, op], measurements, metadata)
+        {:noreply, state}
+      end
+  """
+  @spec with_instrumentation(store(), keyword()) :: {:ok, store()} | {:error, term()}
+  def with_instrumentation(store, opts \\ []) do
+    {:ok, Native.with_instrumentation(store, Keyword.get(opts, :pid))}
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Totals of every operation made through instrumented stores.
+
+  Returns a map from operation (see `with_instrumentation/2`) to its call
+  count, failed calls, transferred bytes and total duration in microseconds,
+  summed over all instrumented stores since start or `reset_metrics/0`.
+
+  ## Examples
+
+      %{get: %{count: count, duration_us: us}} = ObjectStoreX.get_metrics()
+      average_us = div(us, count)
+  """
+  @spec get_metrics() :: %{atom() => operation_metrics()}
+  def get_metrics, do: Native.get_metrics()
+
+  @doc """
+  Reset the counters returned by `get_metrics/0`.
+  """
+  @spec reset_metrics() :: :ok
+  def reset_metrics, do: Native.reset_metrics()
+
+  @type inflight_operation :: %{
+          id: non_neg_integer(),
+          operation: atom(),
+          path: String.t(),
+          elapsed_ms: non_neg_integer(),
+          bytes: non_neg_integer()
+        }
+
+  @doc """
+  List the operations currently running on any store, oldest first.
+
+  Every call a store makes is listed while it runs, with a unique `:id`, the
+  `:operation` (`:get`, `:put`, `:list`, `:put_part`, `:list_versions`,
+  `:append`, ..., or `:s3_request` for S3 API requests such as tagging), the
+  `:path` it targets (the prefix for listings), the time since it started and
+  the bytes transferred so far. Downloads and listings run until their stream
+  is finished or dropped, so streams count their bytes as chunks arrive;
+  multipart uploads are one `:put_multipart` operation until they complete or
+  are aborted. A public function making several calls, such as
+  `copy_prefix/4`, shows up as each of them in turn.
+
+  ## Examples
+
+      for %{operation: op, path: path, elapsed_ms: ms} <-
+            ObjectStoreX.list_inflight_operations(),
+          ms > 60_000 do
+        Logger.warning("#{op} #{path} running for #{ms} ms")
+      end
+  """
+  @spec list_inflight_operations() :: [inflight_operation()]
+  def list_inflight_operations, do: Native.list_inflight_operations()
+
+  @doc """
+  Shut the native layer down, for node shutdown or before a hot upgrade.
+
+  From the moment it is called, operations no longer start: calls that need
+  the native runtime return `{:error, :shut_down}`. Then:
+
+  1. Running downloads and listings may finish until `:timeout`; streams still
+     running are aborted and their consumers receive an error
+  2. Unfinished multipart uploads (`ObjectStoreX.Stream.upload/4`,
+     `ObjectStoreX.Stream.start_upload_stream/3` and `put_stream/3`) are
+     aborted, so no orphaned parts are left behind
+  3. The runtime is shut down, giving its remaining tasks until `:timeout`
+
+  Shutting down can't be undone within the running VM; later calls return
+  `:ok` without doing anything. Call it from your application's
+  `c:Application.prep_stop/1`, after the processes using ObjectStoreX have
+  stopped.
+
+  ## Options
+
+  - `:timeout` - Milliseconds to wait for streams, aborts and runtime tasks
+    (default: `5_000`)
+  - `:keep_resumable` - Leave resumable uploads (S3, Azure, GCS) in place so
+    they can be resumed with the `:resume` option of
+    `ObjectStoreX.Stream.upload/4` after a restart (default: `false`)
+
+  ## Examples
+
+      def prep_stop(state) do
+        :ok = ObjectStoreX.shutdown(timeout: 10_000)
+        state
+      end
+  """
+  @spec shutdown(keyword()) :: :ok
+  def shutdown(opts \\ []) do
+    timeout = Keyword.get(opts, :timeout, 5_000)
+    keep_resumable = Keyword.get(opts, :keep_resumable, false)
+
+    Native.shutdown(timeout, keep_resumable)
+  end
+
+  @doc """
+  Register a named credential profile on a store.
+
+  A profile is another store, usually the same bucket built with different
+  credentials (e.g. read-only vs read-write). Operations select a profile with
+  the `:profile` option, so applications only need to pass one store handle
+  around. Registering an existing name replaces the previous profile.
+
+  ## Examples
+
+      {:ok, store} = ObjectStoreX.new(:s3, bucket: "data", access_key_id: rw_key, ...)
+      {:ok, reader} = ObjectStoreX.new(:s3, bucket: "data", access_key_id: ro_key, ...)
+
+      :ok = ObjectStoreX.register_profile(store, :read_only, reader)
+
+      # Uses the read-only credentials
+      {:ok, data} = ObjectStoreX.get(store, "report.csv", profile: :read_only)
+
+      # Uses the store's own credentials
+      :ok = ObjectStoreX.put(store, "report.csv", data)
+  """
+  @spec register_profile(store(), atom() | String.t(), store()) :: :ok | {:error, term()}
+  def register_profile(store, name, profile) do
+    case Native.register_profile(store, to_string(name), profile) do
+      :ok -> :ok
+      error -> {:error, error}
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Remove a named credential profile from a store.
+
+  Returns `{:error, :not_found}` if no profile with that name is registered.
+
+  ## Examples
+
+      :ok = ObjectStoreX.remove_profile(store, :read_only)
+  """
+  @spec remove_profile(store(), atom() | String.t()) :: :ok | {:error, term()}
+  def remove_profile(store, name) do
+    case Native.remove_profile(store, to_string(name)) do
+      :ok -> :ok
+      error -> {:error, error}
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  List the names of the credential profiles registered on a store.
+
+  ## Examples
+
+      {:ok, ["read_only"]} = ObjectStoreX.list_profiles(store)
+  """
+  @spec list_profiles(store()) :: {:ok, [String.t()]} | {:error, term()}
+  def list_profiles(store) do
+    case Native.list_profiles(store) do
+      names when is_list(names) -> {:ok, names}
+      error -> {:error, error}
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Create a cancellation token for operations.
+
+  Pass it to operations as `:op_ref` and cancel them from any process with
+  `cancel_op/1`. See "Timeouts and Cancellation" in the module docs.
+
+  ## Examples
+
+      op_ref = ObjectStoreX.new_op_ref()
+      task = Task.async(fn -> ObjectStoreX.get(store, "large.bin", op_ref: op_ref) end)
+
+      :ok = ObjectStoreX.cancel_op(op_ref)
+      {:error, :cancelled} = Task.await(task)
+  """
+  @spec new_op_ref() :: reference()
+  def new_op_ref, do: Native.new_op_ref()
+
+  @doc """
+  Cancel every operation given `op_ref`.
+
+  Running operations return `{:error, :cancelled}` right away, dropping their
+  requests. Cancelling is permanent, so operations started with the token
+  later fail too.
+  """
+  @spec cancel_op(reference()) :: :ok | {:error, term()}
+  def cancel_op(op_ref) do
+    Native.cancel_op(op_ref)
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  # Resolve the `:profile` option to the store handle an operation should use,
+  # bounded by the `:timeout_ms` and `:op_ref` options. Returns the remaining
+  # options with these removed.
+  @doc false
+  @spec resolve_profile(store(), keyword()) :: {:ok, store(), keyword()} | {:error, term()}
+  def resolve_profile(store, opts) do
+    {timeout_ms, opts} = Keyword.pop(opts, :timeout_ms)
+    {op_ref, opts} = Keyword.pop(opts, :op_ref)
+
+    with {:ok, store, opts} <- select_profile(store, opts) do
+      if timeout_ms == nil and op_ref == nil do
+        {:ok, store, opts}
+      else
+        {:ok, Native.with_deadline(store, timeout_ms, op_ref), opts}
+      end
+    end
+  end
+
+  defp select_profile(store, opts) do
+    case Keyword.pop(opts, :profile) do
+      {nil, opts} ->
+        {:ok, store, opts}
+
+      {name, opts} ->
+        case Native.select_profile(store, to_string(name)) do
+          profile when is_reference(profile) -> {:ok, profile, opts}
+          :not_found -> {:error, :profile_not_found}
+          error -> {:error, error}
+        end
+    end
+  end
+
+  @type put_result :: %{
+          required(:etag) => String.t(),
+          required(:version) => String.t(),
+          optional(:checksum) => String.t()
+        }
+
+  @doc """
+  Upload an object to storage.
+
+  `data` may be any iodata. Lists of binaries are passed to the store as
+  separate segments instead of being flattened into one binary first.
+
+  ## Options
+
+  - `:mode` - Write mode (default: `:overwrite`)
+    - `:overwrite` - Always write, overwriting any existing object
+    - `:create` - Only write if the object doesn't exist (returns `{:error, :already_exists}` if exists)
+    - `{:update, %{etag: ..., version: ...}}` - CAS operation, only write if version matches
+  - `:content_type` - MIME type (e.g., "application/json")
+  - `:content_encoding` - Encoding (e.g., "gzip")
+  - `:content_disposition` - Download behavior (e.g., "attachment; filename=file.pdf")
+  - `:cache_control` - Cache directives (e.g., "max-age=3600")
+  - `:content_language` - Language (e.g., "en-US")
+  - `:metadata` - Custom user metadata as a map (e.g. `x-amz-meta-*` on S3), returned
+    by `head/3` under `:metadata`
+  - `:tags` - Object tags as a map (S3/Azure; ignored by other providers)
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+  - `:progress_pid` - Process receiving `{:progress, op_id, bytes_done, bytes_total}`
+    messages. Plain puts of binaries report progress per uploaded part; otherwise
+    only completion is reported.
+  - `:progress_id` - `op_id` used in progress messages (default: the path)
+  - `:progress_interval` - Minimum milliseconds between progress messages (default: 100)
+  - `:idempotency_key` - Token stored in the object's metadata so a retried put can
+    recognise its own earlier write. If the put fails with a conflict or network error
+    and the stored object carries the same token, the earlier write's result is returned.
+    Pass `true` to derive the token from the path and data; concurrent writers of
+    identical content then look alike, so pass an explicit key to tell them apart.
+    Requires a store that keeps metadata (not `:local`).
+  - `:checksum` - Compute a checksum of the data, `:md5`, `:sha256` or `:crc32c`,
+    returned as a lowercase hex digest under `:checksum`. SHA-256 checksums are also sent to S3 as
+    `x-amz-checksum-sha256`, so S3 rejects data corrupted in transit, also when combined
+    with `:idempotency_key`, `:retention` or `:legal_hold`. See `verify_checksum/5`.
+  - `:retention` - S3 Object Lock retention as `{mode, retain_until}` (see
+    `t:retention/0`), applied in the same request as the upload
+  - `:legal_hold` - Whether to place an S3 Object Lock legal hold on the object. Puts
+    with `:retention` or `:legal_hold` need a bucket with Object Lock enabled, return
+    `{:error, :not_supported}` on other providers. They combine with `:checksum`
+    and `:idempotency_key`.
+
+  ## Provider Limits
+
+  S3, Azure and GCS stores check the provider's documented limits before sending
+  anything, and fail with an error naming the violated limit:
+
+  - `:key_too_long` - Keys over 1024 bytes
+  - `:metadata_too_large` - Metadata over 2 KiB (S3) or 8 KiB (Azure, GCS)
+  - `:too_many_tags` / `:tag_too_long` - More than 10 tags, or tag keys and values
+    over 128 and 256 characters (S3, Azure)
+  - `:too_many_parts` - Multipart uploads over 10,000 parts (50,000 on Azure)
+  - `:part_too_small` - Parts other than the last under 5 MiB (S3, GCS)
+
+  ## Examples
+
+      # Simple put (overwrite)
+      :ok = ObjectStoreX.put(store, "file.txt", "Hello, World!")
+
+      # Create only (distributed lock)
+      case ObjectStoreX.put(store, "lock.txt", "locked", mode: :create) do
+        {:ok, _meta} -> :acquired
+        {:error, :already_exists} -> :already_locked
+      end
+
+      # Compare-and-swap (optimistic locking)
+      {:ok, meta} = ObjectStoreX.head(store, "counter.json")
+      case ObjectStoreX.put(store, "counter.json", new_data,
+                            mode: {:update, %{etag: meta[:etag], version: meta[:version]}}) do
+        {:ok, _meta} -> :success
+        {:error, :precondition_failed} -> :retry
+      end
+
+      # Retry a create safely after a timeout
+      ObjectStoreX.put(store, "orders/42.json", order,
+        mode: :create,
+        idempotency_key: "order-42"
+      )
+
+      # Upload with content type
+      ObjectStoreX.put(store, "data.json", json_data, content_type: "application/json")
+
+      # Upload with attributes
+      ObjectStoreX.put(store, "report.pdf", pdf_data,
+        content_type: "application/pdf",
+        content_disposition: "attachment; filename=report.pdf",
+        cache_control: "max-age=3600"
+      )
+
+      # Upload with custom metadata
+      ObjectStoreX.put(store, "photo.jpg", jpeg,
+        content_type: "image/jpeg",
+        metadata: %{"camera" => "x100v", "album" => "holiday"}
+      )
+
+      # Upload with tags (S3/Azure)
+      ObjectStoreX.put(store, "backup.zip", data,
+        tags: %{"environment" => "production", "backup-type" => "daily"}
+      )
+
+      # Upload with a checksum
+      {:ok, %{checksum: sha256}} = ObjectStoreX.put(store, "backup.zip", data, checksum: :sha256)
+
+      # Write-once upload, kept for a year (S3 Object Lock)
+      until = DateTime.add(DateTime.utc_now(), 365, :day)
+      {:ok, _} = ObjectStoreX.put(store, "ledger/2024.csv", csv, retention: {:compliance, until})
+  """
+  @spec put(store(), path(), iodata(), keyword()) ::
+          :ok | {:ok, put_result()} | {:error, term()}
+  def put(store, path, data, opts \\ [])
+
+  def put(store, path, data, opts) when is_list(opts) do
+    case resolve_profile(store, opts) do
+      {:ok, store, opts} ->
+        case pop_progress(opts, path) do
+          {nil, opts} ->
+            do_put(store, path, data, opts)
+
+          {progress, []} when is_binary(data) ->
+            case Native.put_with_progress(store, path, data, progress) do
+              :ok -> :ok
+              error -> {:error, error}
+            end
+
+          {progress, opts} ->
+            store
+            |> do_put(path, data, opts)
+            |> report_completion(progress, IO.iodata_length(data))
+        end
+
+      error ->
+        error
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  defp do_put(store, path, data, []) do
+    case Native.put(store, path, data) do
+      :ok -> :ok
+      error -> {:error, error}
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  # Options handled together by `Native.put_with_options/7`
+  @put_guards [:checksum, :idempotency_key, :retention, :legal_hold]
+
+  defp do_put(store, path, data, opts) do
+    mode = Keyword.get(opts, :mode, :overwrite)
+
+    result =
+      cond do
+        Enum.any?(@put_guards, &Keyword.has_key?(opts, &1)) ->
+          attributes = put_attributes(opts)
+          guards = put_guards(opts, path, data)
+          Native.put_with_options(store, path, data, mode, attributes, put_tags(opts), guards)
+
+        has_attributes?(opts) ->
+          put_with_attributes_internal(store, path, data, mode, opts)
+
+        true ->
+          Native.put_with_mode(store, path, data, mode)
+      end
+
+    normalize_put_result(result)
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  defp put_guards(opts, path, data) do
+    %{
+      checksum: Keyword.get(opts, :checksum),
+      idempotency_token:
+        case Keyword.fetch(opts, :idempotency_key) do
+          {:ok, key} -> idempotency_token(key, path, data)
+          :error -> nil
+        end,
+      object_lock:
+        if Keyword.has_key?(opts, :retention) or Keyword.has_key?(opts, :legal_hold) do
+          %{
+            retention: opts |> Keyword.get(:retention) |> convert_retention(),
+            legal_hold: Keyword.get(opts, :legal_hold)
+          }
+        end
+    }
+  end
+
+  defp idempotency_token(true, path, data) do
+    :crypto.hash(:sha256, [path, 0, data]) |> Base.encode16(case: :lower)
+  end
+
+  defp idempotency_token(key, _path, _data) when is_binary(key), do: key
+
+  defp has_attributes?(opts) do
+    Keyword.has_key?(opts, :content_type) or
+      Keyword.has_key?(opts, :content_encoding) or
+      Keyword.has_key?(opts, :content_disposition) or
+      Keyword.has_key?(opts, :cache_control) or
+      Keyword.has_key?(opts, :content_language) or
+      Keyword.has_key?(opts, :metadata) or
+      Keyword.has_key?(opts, :tags)
+  end
+
+  defp put_with_attributes_internal(store, path, data, mode, opts) do
+    Native.put_with_attributes(store, path, data, mode, put_attributes(opts), put_tags(opts))
+  end
+
+  defp put_attributes(opts) do
+    %ObjectStoreX.Attributes{
+      content_type: Keyword.get(opts, :content_type),
+      content_encoding: Keyword.get(opts, :content_encoding),
+      content_disposition: Keyword.get(opts, :content_disposition),
+      cache_control: Keyword.get(opts, :cache_control),
+      content_language: Keyword.get(opts, :content_language),
+      metadata: opts |> Keyword.get(:metadata, %{}) |> stringify_map()
+    }
+  end
+
+  defp put_tags(opts) do
+    Keyword.get(opts, :tags, %{})
+    |> Map.to_list()
+  end
+
+  defp stringify_map(map) do
+    Map.new(map, fn {key, value} -> {to_string(key), to_string(value)} end)
+  end
+
+  defp normalize_put_result({:ok, etag, version}), do: {:ok, %{etag: etag, version: version}}
+
+  defp normalize_put_result({:ok, etag, version, checksum}),
+    do: {:ok, %{etag: etag, version: version, checksum: checksum}}
+
+  defp normalize_put_result(:already_exists), do: {:error, :already_exists}
+  defp normalize_put_result(:precondition_failed), do: {:error, :precondition_failed}
+  defp normalize_put_result(error), do: {:error, error}
+
+  @doc """
+  Start a streaming put with the options of `put/4`.
+
+  Returns a writable stream: push chunks with `write_put_stream/2`, then
+  `finish_put_stream/1` writes the object with the given mode, attributes and
+  tags applied. This brings single-shot put options to payloads that don't fit
+  in memory or arrive incrementally.
+
+  Objects up to 5MB are written with a single put. Larger `:overwrite` streams
+  switch to a multipart upload carrying the attributes and tags. Providers
+  can't apply `:create` or `{:update, ...}` to multipart uploads, so streams
+  with those modes buffer all data and finish with a single conditional put.
+
+  ## Options
+
+  The `put/4` options `:mode`, `:content_type`, `:content_encoding`,
+  `:content_disposition`, `:cache_control`, `:content_language`, `:metadata`
+  and `:tags`, plus:
+
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      {:ok, stream} =
+        ObjectStoreX.put_stream(store, "export.json",
+          content_type: "application/json",
+          mode: :create
+        )
+
+      Enum.each(chunks, &ObjectStoreX.write_put_stream(stream, &1))
+
+      {:ok, %{etag: etag}} = ObjectStoreX.finish_put_stream(stream)
+  """
+  @spec put_stream(store(), path(), keyword()) :: {:ok, reference()} | {:error, term()}
+  def put_stream(store, path, opts \\ []) do
+    with {:ok, store, opts} <- resolve_profile(store, opts) do
+      mode = Keyword.get(opts, :mode, :overwrite)
+
+      case Native.start_put_stream(store, path, mode, put_attributes(opts), put_tags(opts)) do
+        {:ok, stream} -> {:ok, stream}
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Write a chunk to a stream started with `put_stream/3`.
+
+  A failed write aborts the stream.
+  """
+  @spec write_put_stream(reference(), iodata()) :: :ok | {:error, term()}
+  def write_put_stream(stream, data) do
+    case Native.put_stream_write(stream, IO.iodata_to_binary(data)) do
+      :ok -> :ok
+      error -> {:error, error}
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Finish a stream started with `put_stream/3`, writing the object.
+
+  Returns the same results as `put/4` with options, e.g.
+  `{:error, :already_exists}` for a `:create` stream whose object exists.
+  """
+  @spec finish_put_stream(reference()) :: {:ok, put_result()} | {:error, term()}
+  def finish_put_stream(stream) do
+    stream
+    |> Native.put_stream_finish()
+    |> normalize_put_result()
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Abort a stream started with `put_stream/3`, discarding written data.
+  """
+  @spec abort_put_stream(reference()) :: :ok | {:error, term()}
+  def abort_put_stream(stream) do
+    case Native.put_stream_abort(stream) do
+      :ok -> :ok
+      error -> {:error, error}
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Open a buffered writer that writes an object as data arrives.
+
+  Up to `:capacity` bytes are buffered in memory. Objects that fit are
+  written with a single put on `writer_close/1`; once the buffer fills, the
+  writer switches to a multipart upload with parts of `:capacity` bytes,
+  uploading up to `:max_concurrency` parts at once. Unlike `put_stream/3`
+  and upload sessions, the buffer size and upload concurrency are
+  configurable.
+
+  Writers that are garbage collected before `writer_close/1` or
+  `writer_abort/1` abort their upload.
+
+  ## Options
+
+  - `:capacity` - Buffer and part size in bytes (default: 10MB). Providers
+    require parts of at least 5MB, except the last one
+  - `:max_concurrency` - Maximum parts uploading at once (default: 8)
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  The `put/4` attribute options (`:content_type`, `:metadata`, ...) and
+  `:tags` are applied to the object.
+
+  ## Examples
+
+      {:ok, writer} = ObjectStoreX.open_writer(store, "logs/app.log", capacity: 8_388_608)
+      :ok = ObjectStoreX.writer_write(writer, "line 1\n")
+      :ok = ObjectStoreX.writer_flush(writer)
+      :ok = ObjectStoreX.writer_close(writer)
+  """
+  @spec open_writer(store(), path(), keyword()) :: {:ok, reference()} | {:error, term()}
+  def open_writer(store, path, opts \\ []) do
+    with {:ok, store, opts} <- resolve_profile(store, opts) do
+      capacity = Keyword.get(opts, :capacity, 10 * 1024 * 1024)
+      max_concurrency = Keyword.get(opts, :max_concurrency, 8)
+      attributes = put_attributes(opts)
+      tags = put_tags(opts)
+
+      case Native.open_writer(store, path, capacity, max_concurrency, attributes, tags) do
+        {:ok, writer} -> {:ok, writer}
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Write data to a writer opened with `open_writer/3`.
+
+  Waits while `:max_concurrency` parts are uploading. A failed write aborts
+  the writer.
+  """
+  @spec writer_write(reference(), iodata()) :: :ok | {:error, term()}
+  def writer_write(writer, data) do
+    case Native.writer_write(writer, IO.iodata_to_binary(data)) do
+      :ok -> :ok
+      error -> {:error, error}
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Flush a writer opened with `open_writer/3`.
+
+  Waits until the multipart upload the writer switched to, if any, has been
+  started, so failures to start it are returned here rather than from
+  `writer_close/1`. Data below the capacity stays buffered until close.
+  """
+  @spec writer_flush(reference()) :: :ok | {:error, term()}
+  def writer_flush(writer) do
+    case Native.writer_flush(writer) do
+      :ok -> :ok
+      error -> {:error, error}
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Close a writer opened with `open_writer/3`, writing the object.
+  """
+  @spec writer_close(reference()) :: :ok | {:error, term()}
+  def writer_close(writer) do
+    case Native.writer_close(writer) do
+      :ok -> :ok
+      error -> {:error, error}
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Abort a writer opened with `open_writer/3`, discarding written data.
+  """
+  @spec writer_abort(reference()) :: :ok | {:error, term()}
+  def writer_abort(writer) do
+    case Native.writer_abort(writer) do
+      :ok -> :ok
+      error -> {:error, error}
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Open a reader for random access to an object.
+
+  Reads are served from a buffer of `:capacity` bytes, refilled with a ranged
+  get from the reader's position, so code parsing structured formats (zip,
+  Parquet, SQLite) can seek and read small pieces without fetching the whole
+  object. The object's size is looked up when the reader is opened.
+
+  ## Options
+
+  - `:capacity` - Bytes fetched per ranged get (default: 1MB)
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      {:ok, reader} = ObjectStoreX.open_reader(store, "data/archive.zip")
+
+      # Read the end of central directory record
+      {:ok, _position} = ObjectStoreX.reader_seek(reader, {:eof, -22})
+      {:ok, record} = ObjectStoreX.reader_read(reader, 22)
+  """
+  @spec open_reader(store(), path(), keyword()) :: {:ok, reference()} | {:error, term()}
+  def open_reader(store, path, opts \\ []) do
+    with {:ok, store, opts} <- resolve_profile(store, opts) do
+      case Native.open_reader(store, path, Keyword.get(opts, :capacity, 1024 * 1024)) do
+        {:ok, reader} -> {:ok, reader}
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Read up to `length` bytes at the position of a reader from `open_reader/3`.
+
+  Advances the position. Returns fewer bytes than requested only at the end
+  of the object, and `:eof` once there is nothing left to read.
+  """
+  @spec reader_read(reference(), non_neg_integer()) ::
+          {:ok, binary()} | :eof | {:error, term()}
+  def reader_read(reader, length) do
+    case Native.reader_read(reader, length) do
+      {:ok, data} -> {:ok, data}
+      :eof -> :eof
+      error -> {:error, error}
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Move the position of a reader from `open_reader/3`.
+
+  Positions are given like `:file.position/2`: an offset from the beginning,
+  or `{:bof, offset}`, `{:cur, offset}` or `{:eof, offset}`. Returns the new
+  position; `reader_seek(reader, {:eof, 0})` returns the object's size.
+  Positions past the end read as `:eof`.
+  """
+  @spec reader_seek(reference(), integer() | {:bof | :cur | :eof, integer()}) ::
+          {:ok, non_neg_integer()} | {:error, term()}
+  def reader_seek(reader, position) do
+    {whence, offset} =
+      case position do
+        offset when is_integer(offset) -> {:bof, offset}
+        {whence, offset} when whence in [:bof, :cur, :eof] -> {whence, offset}
+      end
+
+    case Native.reader_seek(reader, whence, offset) do
+      {:ok, position} -> {:ok, position}
+      error -> {:error, error}
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Read `length` bytes at `offset` of a reader from `open_reader/3`.
+
+  Issues a single ranged get and leaves the reader's position and buffer
+  alone, for reading regions known in advance, e.g. from a file's index.
+  Returns fewer bytes than requested if the range extends past the end.
+  """
+  @spec reader_read_range(reference(), non_neg_integer(), non_neg_integer()) ::
+          {:ok, binary()} | {:error, term()}
+  def reader_read_range(reader, offset, length) do
+    case Native.reader_read_range(reader, offset, length) do
+      {:ok, data} -> {:ok, data}
+      error -> {:error, error}
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Replace the bytes of an object starting at `offset`.
+
+  Small edits of large objects don't require a full re-upload on S3: the
+  unchanged regions are copied server-side as parts of a multipart upload,
+  and only the patch (widened to the 5MB minimum part size) is uploaded.
+  Other stores rewrite the object, streaming it through the client without
+  loading it into memory.
+
+  The object grows if the patch ends past its end. A gap between the end of
+  the object and `offset` is filled with zero bytes. Attributes such as the
+  content type are kept; tags are not.
+
+  ## Options
+
+  - `:if_match` - Only patch if the object's current ETag matches
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  Returns `{:error, :precondition_failed}` if `:if_match` doesn't match or the
+  object changes while it is being patched.
+
+  ## Examples
+
+      {:ok, %{etag: etag}} = ObjectStoreX.patch(store, "disk.img", 4096, block)
+  """
+  @spec patch(store(), path(), non_neg_integer(), binary(), keyword()) ::
+          {:ok, put_result()} | {:error, term()}
+  def patch(store, path, offset, data, opts \\ [])
+      when is_integer(offset) and offset >= 0 and is_binary(data) do
+    with {:ok, store, opts} <- resolve_profile(store, opts) do
+      store
+      |> Native.patch(path, offset, data, Keyword.get(opts, :if_match))
+      |> normalize_put_result()
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Append data to the end of an object, creating it if it doesn't exist.
+
+  Returns `{:ok, size}` with the object's size after the append. Meant for
+  log shipping and other writers that keep adding to one object.
+
+  Local stores append to the file in place, so appends from several processes
+  (or operating system processes) each land whole at the end of the file.
+  Memory stores rewrite the object with a conditional put, retrying if another
+  writer appended first. Files created by an append get the default modes,
+  regardless of the store's `:file_mode`.
+
+  Other stores, including stores returned by `with_*` wrappers and `derive/2`,
+  return `{:error, :not_supported}`: cloud object stores can't extend an object
+  without rewriting it.
+
+  ## Options
+
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      {:ok, store} = ObjectStoreX.new(:local, path: "/var/log/exports")
+      {:ok, _size} = ObjectStoreX.append(store, "app/2026-10-17.log", line <> "\n")
+  """
+  @spec append(store(), path(), binary(), keyword()) ::
+          {:ok, non_neg_integer()} | {:error, term()}
+  def append(store, path, data, opts \\ []) when is_binary(data) do
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.append(store, path, data) do
+        {:ok, size} -> {:ok, size}
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @typedoc """
+  Result of `split/5`.
+  """
+  @type split_result :: %{
+          manifest: String.t(),
+          parts: [String.t()],
+          size: non_neg_integer(),
+          sha256: String.t()
+        }
+
+  @doc """
+  Split an object into smaller objects of at most `part_size` bytes.
+
+  Fits large objects to downstream systems with object-size limits. The parts
+  are copied natively with ranged reads (and multipart writes for large parts)
+  to `part-00000`, `part-00001`, ... under `dest_prefix`, without loading the
+  object into memory. A `manifest.json` written last lists the parts in order
+  with their sizes and SHA-256 checksums, plus the size and checksum of the
+  whole object, for later reassembly.
+
+  Returns `{:error, :precondition_failed}` if the object changes during the
+  split.
+
+  ## Options
+
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      {:ok, %{manifest: manifest, parts: parts}} =
+        ObjectStoreX.split(store, "dumps/db.tar", "dumps/db.tar.parts", 1_000_000_000)
+  """
+  @spec split(store(), path(), String.t(), pos_integer(), keyword()) ::
+          {:ok, split_result()} | {:error, term()}
+  def split(store, path, dest_prefix, part_size, opts \\ [])
+      when is_integer(part_size) and part_size > 0 do
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.split(store, path, dest_prefix, part_size) do
+        {:ok, result} -> {:ok, result}
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Reassemble an object from the manifest written by `split/5`.
+
+  On S3, when every part but the last is at least 5MB, the parts are copied
+  server-side into a multipart upload, so no data is uploaded from the client.
+  Other stores stream the parts through a multipart upload. Part sizes are
+  checked against the manifest before anything is written, and the SHA-256
+  checksums of the parts and the whole object are verified before the upload
+  completes. A mismatch returns `{:error, :checksum_mismatch}` and leaves the
+  destination untouched.
+
+  ## Options
+
+  - `:verify` - Verify checksums (default: `true`). Streaming joins always
+    verify them; on S3 server-side joins, verifying reads the parts back, and
+    `false` skips that and only checks part sizes.
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      {:ok, %{manifest: manifest}} = ObjectStoreX.split(store, "db.tar", "db.tar.parts", size)
+      {:ok, _} = ObjectStoreX.join(store, manifest, "restored/db.tar")
+  """
+  @spec join(store(), path(), path(), keyword()) :: {:ok, put_result()} | {:error, term()}
+  def join(store, manifest_path, dest_path, opts \\ []) do
+    with {:ok, store, opts} <- resolve_profile(store, opts) do
+      store
+      |> Native.join(manifest_path, dest_path, Keyword.get(opts, :verify, true))
+      |> normalize_put_result()
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Download an object from storage with optional conditional requests.
+
+  Gets with conditions, `:range`, `:version`, `:head` or `:verify` return
+  `{:ok, data, meta}`, where `meta` has the metadata and attributes (content
+  type, user metadata, ...) that `head/3` returns.
+
+  ## Options
+
+  Supports HTTP-style conditional requests for caching and consistency:
+  - `:if_match` - Only return if ETag matches (HTTP If-Match)
+  - `:if_none_match` - Only return if ETag differs (HTTP If-None-Match)
+  - `:if_modified_since` - Only return if modified after date, a `DateTime` or a
+    `t:ObjectStoreX.GetOptions.timestamp/0`
+  - `:if_unmodified_since` - Only return if not modified since date, as `:if_modified_since`
+  - `:range` - Byte range `{start, end}` or `%ObjectStoreX.Range{}`. The returned
+    metadata then also has `:range`, the `{start, end}` actually returned (clamped
+    to the object's end), and `:total_size`, the size of the whole object
+  - `:version` - Specific object version
+  - `:head` - Return metadata only (no content)
+  - `:verify` - Check the received bytes before returning them, failing with
+    `{:error, :integrity_error}` if they don't match (see "Verification" below)
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+  - `:progress_pid` - Process receiving `{:progress, op_id, bytes_done, bytes_total}`
+    messages. Plain gets report progress as data arrives; combined with other
+    options only completion is reported.
+  - `:progress_id` - `op_id` used in progress messages (default: the path)
+  - `:progress_interval` - Minimum milliseconds between progress messages (default: 100)
+  - `:spill_threshold` - Size in bytes above which the object is downloaded into a
+    temporary file instead of memory (see "Spilling to disk" below)
+  - `:spill_dir` - Directory for spilled objects (default: `System.tmp_dir!/0`)
+
+  ## Verification
+
+  `:verify` protects against data corrupted in transit:
+
+  - `:etag` - Compare with the object's ETag. Single-part uploads to S3 and
+    compatible stores have the MD5 of their data as ETag; other ETags (multipart
+    uploads, other providers) aren't digests, and the data is returned unchecked.
+  - `{algorithm, digest}` - Compare with a hex digest, e.g. one returned by
+    `put/4` with `:checksum`. `algorithm` is `:md5`, `:sha256` or `:crc32c`.
+
+  A digest covers the whole object, so `:verify` can't be combined with `:range`.
+
+  ## Spilling to disk
+
+  With `:spill_threshold`, objects up to that size are returned as `{:ok, data}`
+  and larger ones are streamed into a new file in `:spill_dir`, returning
+  `{:ok, {:file, path}}`. The choice is made from the object's size before any
+  data is read, so an unexpectedly large object never lands on the BEAM heap.
+  The file belongs to the caller, who removes it when done; a failed download
+  leaves no file behind. Only `:verify`, `:profile` and the progress options
+  can be combined with `:spill_threshold`.
+
+  ## Examples
+
+      # Simple get
+      {:ok, data} = ObjectStoreX.get(store, "file.txt")
+
+      # HTTP cache validation
+      case ObjectStoreX.get(store, "file.txt", if_none_match: cached_etag) do
+        {:ok, data, meta} -> {:modified, data, meta}
+        {:error, :not_modified} -> :use_cache
+      end
+
+      # Consistent read with ETag
+      {:ok, meta} = ObjectStoreX.head(store, "config.json")
+      case ObjectStoreX.get(store, "config.json", if_match: meta[:etag]) do
+        {:ok, data, _meta} -> {:ok, data}
+        {:error, :precondition_failed} -> :retry
+      end
+
+      # Range read
+      {:ok, data, meta} = ObjectStoreX.get(store, "large.bin", range: {0, 1000})
+      %{range: {0, 1000}, total_size: total} = meta
+
+      # Head-only (metadata without content)
+      {:ok, _empty, meta} = ObjectStoreX.get(store, "file.txt", head: true)
+
+      # Verified read
+      {:ok, %{checksum: sha256}} = ObjectStoreX.put(store, "file.txt", data, checksum: :sha256)
+      {:ok, ^data, _meta} = ObjectStoreX.get(store, "file.txt", verify: {:sha256, sha256})
+
+      # Keep anything over 64MB off the heap
+      case ObjectStoreX.get(store, "uploads/unknown.bin", spill_threshold: 64 * 1024 * 1024) do
+        {:ok, {:file, path}} -> process_file(path)
+        {:ok, data} -> process_binary(data)
+      end
+  """
+  @spec get(store(), path(), keyword()) ::
+          {:ok, binary()}
+          | {:ok, {:file, Path.t()}}
+          | {:ok, binary(), metadata()}
+          | {:error, term()}
+  def get(store, path, opts \\ [])
+
+  def get(store, path, opts) when is_list(opts) do
+    case resolve_profile(store, opts) do
+      {:ok, store, opts} ->
+        {progress, opts} = pop_progress(opts, path)
+
+        cond do
+          Keyword.has_key?(opts, :spill_threshold) ->
+            get_spilling(store, path, progress, opts)
+
+          progress == nil ->
+            do_get(store, path, opts)
+
+          opts == [] ->
+            case Native.get_with_progress(store, path, progress) do
+              data when is_binary(data) -> {:ok, data}
+              error -> {:error, error}
+            end
+
+          true ->
+            case do_get(store, path, opts) do
+              {:ok, data, _meta} = result -> report_completion(result, progress, byte_size(data))
+              result -> result
+            end
+        end
+
+      error ->
+        error
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  defp get_spilling(store, path, progress, opts) do
+    {threshold, opts} = Keyword.pop!(opts, :spill_threshold)
+    {dir, opts} = Keyword.pop_lazy(opts, :spill_dir, &System.tmp_dir!/0)
+    {verify, opts} = Keyword.pop(opts, :verify)
+
+    unless is_integer(threshold) and threshold >= 0 do
+      raise ArgumentError, ":spill_threshold must be a non-negative integer"
+    end
+
+    if opts != [] do
+      raise ArgumentError,
+            ":spill_threshold can't be combined with #{inspect(Keyword.keys(opts))}"
+    end
+
+    case Native.get_spilling(store, path, threshold, to_string(dir), progress, verify) do
+      data when is_binary(data) -> {:ok, data}
+      {:file, _path} = file -> {:ok, file}
+      error -> {:error, error}
+    end
+  end
+
+  defp do_get(store, path, []) do
+    case Native.get(store, path) do
+      data when is_binary(data) -> {:ok, data}
+      :not_found -> {:error, :not_found}
+      error -> {:error, error}
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  defp do_get(store, path, opts) do
+    # Convert keyword options to GetOptions struct
+    get_options = %ObjectStoreX.GetOptions{
+      if_match: Keyword.get(opts, :if_match),
+      if_none_match: Keyword.get(opts, :if_none_match),
+      if_modified_since: convert_datetime_to_timestamp(Keyword.get(opts, :if_modified_since)),
+      if_unmodified_since: convert_datetime_to_timestamp(Keyword.get(opts, :if_unmodified_since)),
+      range: convert_range(Keyword.get(opts, :range)),
+      version: Keyword.get(opts, :version),
+      head: Keyword.get(opts, :head, false),
+      verify: Keyword.get(opts, :verify)
+    }
+
+    case Native.get_with_options(store, path, get_options) do
+      {:ok, data, meta} when is_map(meta) ->
+        # Convert charlist to binary if needed
+        binary_data = if is_list(data), do: :erlang.list_to_binary(data), else: data
+        {:ok, binary_data, meta}
+
+      :not_found ->
+        {:error, :not_found}
+
+      :not_modified ->
+        {:error, :not_modified}
+
+      :precondition_failed ->
+        {:error, :precondition_failed}
+
+      error ->
+        {:error, error}
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  # Split progress options into the `{pid, op_id, interval_ms}` tuple used by
+  # the native transfers, or nil when no progress was requested
+  defp pop_progress(opts, path) do
+    {pid, opts} = Keyword.pop(opts, :progress_pid)
+    {op_id, opts} = Keyword.pop(opts, :progress_id, path)
+    {interval, opts} = Keyword.pop(opts, :progress_interval, 100)
+
+    case pid do
+      nil -> {nil, opts}
+      pid when is_pid(pid) -> {{pid, to_string(op_id), interval}, opts}
+    end
+  end
+
+  # Send a single progress message for transfers without native progress
+  defp report_completion(result, {pid, op_id, _interval}, size) do
+    if result == :ok or elem(result, 0) == :ok do
+      send(pid, {:progress, op_id, size, size})
+    end
+
+    result
+  end
+
+  @doc """
+  Upload a local file without loading it into memory.
+
+  Files larger than 5MB are uploaded as a multipart upload.
+
+  ## Options
+
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+  - `:progress_pid` - Process receiving `{:progress, op_id, bytes_done, bytes_total}`
+    messages as parts are uploaded
+  - `:progress_id` - `op_id` used in progress messages (default: the path)
+  - `:progress_interval` - Minimum milliseconds between progress messages (default: 100)
+  - `:checksum` - Compute a checksum of the file while uploading, `:md5`, `:sha256`
+    or `:crc32c`, and return `{:ok, checksum}` with its lowercase hex digest.
+    SHA-256 checksums are also sent to S3 with every part (see `put/4`).
+
+  ## Examples
+
+      :ok = ObjectStoreX.put_from_file(store, "backups/db.dump", "/var/backups/db.dump")
+
+      {:ok, sha256} =
+        ObjectStoreX.put_from_file(store, "backups/db.dump", "/var/backups/db.dump",
+          checksum: :sha256
+        )
+
+      # Drive a LiveView progress bar
+      ObjectStoreX.put_from_file(store, "uploads/video.mp4", tmp_path,
+        progress_pid: self(),
+        progress_id: upload_ref
+      )
+  """
+  @spec put_from_file(store(), path(), Path.t(), keyword()) ::
+          :ok | {:ok, String.t()} | {:error, term()}
+  def put_from_file(store, path, file_path, opts \\ []) do
+    with {:ok, store, opts} <- resolve_profile(store, opts) do
+      {progress, opts} = pop_progress(opts, path)
+      checksum = Keyword.get(opts, :checksum)
+
+      case Native.put_from_file(store, path, to_string(file_path), progress, checksum) do
+        :ok -> :ok
+        {:ok, checksum} -> {:ok, checksum}
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Download an object into a local file without loading it into memory.
+
+  The file is created or truncated. If the download fails, the partially
+  written file is removed.
+
+  With `:verify`, the data is checked as it is written, as by `get/3`. A file
+  that doesn't match is removed and `{:error, :integrity_error}` returned.
+
+  ## Options
+
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+  - `:progress_pid` - Process receiving `{:progress, op_id, bytes_done, bytes_total}`
+    messages as data arrives
+  - `:progress_id` - `op_id` used in progress messages (default: the path)
+  - `:progress_interval` - Minimum milliseconds between progress messages (default: 100)
+  - `:verify` - `:etag` or `{algorithm, hex_digest}`, see `get/3`
+
+  ## Examples
+
+      :ok = ObjectStoreX.get_to_file(store, "backups/db.dump", "/tmp/db.dump")
+
+      :ok =
+        ObjectStoreX.get_to_file(store, "backups/db.dump", "/tmp/db.dump",
+          verify: {:sha256, sha256}
+        )
+  """
+  @spec get_to_file(store(), path(), Path.t(), keyword()) :: :ok | {:error, term()}
+  def get_to_file(store, path, file_path, opts \\ []) do
+    with {:ok, store, opts} <- resolve_profile(store, opts) do
+      {progress, opts} = pop_progress(opts, path)
+      verify = Keyword.get(opts, :verify)
+
+      case Native.get_to_file(store, path, to_string(file_path), progress, verify) do
+        :ok -> :ok
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Verify an object's checksum.
+
+  Streams the object and computes its `:md5`, `:sha256` or `:crc32c` digest
+  natively, without loading it into memory. `expected` is a hex digest, as returned by
+  `put/4` and `put_from_file/4` with `:checksum`.
+
+  Returns `:ok` if the digests match, `{:error, :checksum_mismatch}` if they
+  don't, or `{:error, reason}` if the object can't be read.
+
+  ## Options
+
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      {:ok, sha256} = ObjectStoreX.put_from_file(store, "db.dump", path, checksum: :sha256)
+
+      # Later, before restoring
+      :ok = ObjectStoreX.verify_checksum(store, "db.dump", :sha256, sha256)
+  """
+  @spec verify_checksum(store(), path(), :md5 | :sha256 | :crc32c, String.t(), keyword()) ::
+          :ok | {:error, term()}
+  def verify_checksum(store, path, algorithm, expected, opts \\ [])
+      when algorithm in [:md5, :sha256, :crc32c] and is_binary(expected) do
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.verify_checksum(store, path, algorithm, expected) do
+        :ok -> :ok
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  # Convert DateTime to a millisecond timestamp, or pass through timestamps
+  # the native side decodes (see `t:ObjectStoreX.GetOptions.timestamp/0`)
+  defp convert_datetime_to_timestamp(nil), do: nil
+
+  defp convert_datetime_to_timestamp(%DateTime{} = dt),
+    do: {DateTime.to_unix(dt, :millisecond), :millisecond}
+
+  defp convert_datetime_to_timestamp(ts), do: ts
+
+  # Convert a millisecond timestamp returned by the native side to a DateTime
+  defp convert_timestamp_to_datetime(nil), do: nil
+
+  defp convert_timestamp_to_datetime({ms, :millisecond}),
+    do: DateTime.from_unix!(ms, :millisecond)
+
+  # Convert range tuple or Range struct to Range struct
+  defp convert_range(nil), do: nil
+  defp convert_range({start, end_pos}), do: %ObjectStoreX.Range{start: start, end: end_pos}
+  defp convert_range(%ObjectStoreX.Range{} = range), do: range
+
+  @doc """
+  Get the tags of an object.
+
+  Tags are read with the provider's object tagging API, currently supported
+  on S3. Other providers return `{:error, :not_supported}`; GCS has no object
+  tagging.
+
+  ## Options
+
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      {:ok, %{"environment" => "production"}} = ObjectStoreX.get_tags(store, "backup.zip")
+  """
+  @spec get_tags(store(), path(), keyword()) ::
+          {:ok, %{String.t() => String.t()}} | {:error, term()}
+  def get_tags(store, path, opts \\ []) do
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.get_tags(store, path) do
+        tags when is_list(tags) -> {:ok, Map.new(tags)}
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Replace the tags of an existing object.
+
+  The object's previous tags are removed. Supported on S3; other providers
+  return `{:error, :not_supported}`.
+
+  ## Options
+
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      :ok = ObjectStoreX.put_tags(store, "backup.zip", %{"retention" => "30d"})
+  """
+  @spec put_tags(store(), path(), %{String.t() => String.t()} | keyword(), keyword()) ::
+          :ok | {:error, term()}
+  def put_tags(store, path, tags, opts \\ []) do
+    tags = Enum.map(tags, fn {key, value} -> {to_string(key), to_string(value)} end)
+
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.put_tags(store, path, tags) do
+        :ok -> :ok
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @typedoc """
+  S3 Object Lock retention of an object, as `{mode, retain_until}`.
+
+  Until `retain_until`, the object version can't be deleted or overwritten.
+  In `:governance` mode, users with the `s3:BypassGovernanceRetention`
+  permission may still shorten or remove the retention; in `:compliance` mode
+  nobody can, and it can only be extended. `retain_until` is a `DateTime`, or
+  any timestamp accepted by `ObjectStoreX.GetOptions`.
+  """
+  @type retention :: {:governance | :compliance, DateTime.t()}
+
+  @doc """
+  Get the S3 Object Lock retention of an object.
+
+  Returns `{:ok, nil}` if the object has no retention. Supported on S3 buckets
+  with Object Lock enabled; other providers return `{:error, :not_supported}`.
+
+  ## Options
+
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      {:ok, {:compliance, until}} = ObjectStoreX.get_retention(store, "ledger/2024.csv")
+  """
+  @spec get_retention(store(), path(), keyword()) :: {:ok, retention() | nil} | {:error, term()}
+  def get_retention(store, path, opts \\ []) do
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.get_retention(store, path) do
+        nil -> {:ok, nil}
+        {mode, {_, :millisecond} = until} -> {:ok, {mode, convert_timestamp_to_datetime(until)}}
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Set the S3 Object Lock retention of an existing object.
+
+  Retentions can always be extended. Shortening or removing a `:governance`
+  retention requires `bypass_governance: true`; `:compliance` retentions can't
+  be shortened. Supported on S3 buckets with Object Lock enabled; other
+  providers return `{:error, :not_supported}`. To lock objects as they are
+  written, pass `:retention` to `put/4` instead.
+
+  ## Options
+
+  - `:bypass_governance` - Allow shortening a governance retention (default: `false`)
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      until = DateTime.add(DateTime.utc_now(), 30, :day)
+      :ok = ObjectStoreX.put_retention(store, "invoice.pdf", {:governance, until})
+  """
+  @spec put_retention(store(), path(), retention(), keyword()) :: :ok | {:error, term()}
+  def put_retention(store, path, retention, opts \\ []) do
+    bypass_governance = Keyword.get(opts, :bypass_governance, false)
+
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.put_retention(store, path, convert_retention(retention), bypass_governance) do
+        :ok -> :ok
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  defp convert_retention(nil), do: nil
+
+  defp convert_retention({mode, retain_until}),
+    do: {mode, convert_datetime_to_timestamp(retain_until)}
+
+  @doc """
+  Check whether an object is under an S3 Object Lock legal hold.
+
+  Supported on S3 buckets with Object Lock enabled; other providers return
+  `{:error, :not_supported}`.
+
+  ## Options
+
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      {:ok, true} = ObjectStoreX.get_legal_hold(store, "evidence/case-17.zip")
+  """
+  @spec get_legal_hold(store(), path(), keyword()) :: {:ok, boolean()} | {:error, term()}
+  def get_legal_hold(store, path, opts \\ []) do
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.get_legal_hold(store, path) do
+        on when is_boolean(on) -> {:ok, on}
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Place (`true`) or remove (`false`) an S3 Object Lock legal hold on an object.
+
+  A legal hold prevents deleting or overwriting the object version until it
+  is removed, independently of any retention. Supported on S3 buckets with
+  Object Lock enabled; other providers return `{:error, :not_supported}`.
+
+  ## Options
+
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      :ok = ObjectStoreX.put_legal_hold(store, "evidence/case-17.zip", true)
+  """
+  @spec put_legal_hold(store(), path(), boolean(), keyword()) :: :ok | {:error, term()}
+  def put_legal_hold(store, path, on, opts \\ []) when is_boolean(on) do
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.put_legal_hold(store, path, on) do
+        :ok -> :ok
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @type restore_tier :: :expedited | :standard | :bulk
+
+  @typedoc """
+  Restore state of an object, as returned by `restore_status/3`.
+
+  `:status` is one of:
+
+  - `:available` - Readable without a restore (not in an archive storage class)
+  - `:archived` - Archived, with no restored copy; start one with `restore_object/5`
+  - `:in_progress` - A restore is running
+  - `:restored` - A restored copy is readable until `:expires_at`
+
+  `:storage_class` is the object's S3 storage class, such as `"GLACIER"` or
+  `"DEEP_ARCHIVE"`.
+  """
+  @type restore_status :: %{
+          status: :available | :archived | :in_progress | :restored,
+          storage_class: String.t(),
+          expires_at: DateTime.t() | nil
+        }
+
+  @doc """
+  Start restoring an object from an archive storage class (S3 Glacier
+  Flexible Retrieval and Deep Archive).
+
+  Archived objects can't be read until restored. A restore makes a temporary
+  readable copy that is kept for `days` days; the object itself stays archived.
+  `tier` trades speed for cost:
+
+  - `:expedited` - Minutes (not available for Deep Archive)
+  - `:standard` - Hours
+  - `:bulk` - The cheapest, within about half a day to two days
+
+  Returns `:ok` once the restore is accepted. Restoring an object that is already
+  being restored also returns `:ok`, and restoring a restored object moves its
+  expiry. Poll `restore_status/3` to know when the copy is readable. Supported on
+  S3; other providers return `{:error, :not_supported}`.
+
+  ## Options
+
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      :ok = ObjectStoreX.restore_object(store, "archive/2019.tar", :bulk, 7)
+  """
+  @spec restore_object(store(), path(), restore_tier(), pos_integer(), keyword()) ::
+          :ok | {:error, term()}
+  def restore_object(store, path, tier, days, opts \\ [])
+      when tier in [:expedited, :standard, :bulk] and is_integer(days) and days > 0 do
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.restore_object(store, path, tier, days) do
+        :ok -> :ok
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Get the restore state of an object (see `t:restore_status/0`).
+
+  Supported on S3; other providers return `{:error, :not_supported}`.
+
+  ## Options
+
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      # Wait for a restore started with restore_object/5
+      def await_restore(store, path) do
+        case ObjectStoreX.restore_status(store, path) do
+          {:ok, %{status: :restored}} ->
+            ObjectStoreX.get(store, path)
+
+          {:ok, %{status: :in_progress}} ->
+            Process.sleep(:timer.minutes(15))
+            await_restore(store, path)
+
+          other ->
+            other
+        end
+      end
+  """
+  @spec restore_status(store(), path(), keyword()) :: {:ok, restore_status()} | {:error, term()}
+  def restore_status(store, path, opts \\ []) do
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.restore_status(store, path) do
+        %{status: _} = status ->
+          {:ok, Map.update!(status, :expires_at, &convert_timestamp_to_datetime/1)}
+
+        error ->
+          {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Delete an object from storage.
+
+  ## Options
+
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      :ok = ObjectStoreX.delete(store, "file.txt")
+  """
+  @spec delete(store(), path(), keyword()) :: :ok | {:error, term()}
+  def delete(store, path, opts \\ []) do
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.delete(store, path) do
+        :ok -> :ok
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Upload an object that expires `ttl_secs` seconds from now.
+
+  Emulates lifecycle expiration rules on stores without them, such as local
+  filesystem, memory or MinIO stores. The object is written, then its expiry is
+  recorded at the same path under the reserved `.objectstorex-ttl/` prefix at
+  the root of the store, or of the `derive/2` handle it was written through.
+  Records are left out of that handle's listings, prefix copies and deletes,
+  and quota usage; keys with a `.objectstorex-ttl` segment deeper down are
+  ordinary objects. Nothing is deleted automatically: call `expire_now/3`
+  periodically, e.g. from a scheduled job.
+
+  Overwriting the object cancels its expiry, unless the new object is written
+  with a TTL too: only the object written by this call is expired.
+
+  ## Options
+
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      :ok = ObjectStoreX.put_with_ttl(store, "sessions/abc.json", session, 3600)
+  """
+  @spec put_with_ttl(store(), path(), iodata(), non_neg_integer(), keyword()) ::
+          :ok | {:error, term()}
+  def put_with_ttl(store, path, data, ttl_secs, opts \\ [])
+      when is_integer(ttl_secs) and ttl_secs >= 0 do
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.put_with_ttl(store, path, data, ttl_secs) do
+        :ok -> :ok
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Delete the expired objects written by `put_with_ttl/5` under `prefix`.
+
+  Objects whose TTL has passed are deleted along with their expiry records.
+  Objects deleted or replaced since their TTL was set are left alone, and only
+  their records are removed. On S3, the delete itself checks that the object is
+  unchanged; elsewhere a replacement written at the same moment may be deleted.
+
+  Returns `{:ok, paths}` with the deleted objects. Like the `:prefix` of
+  `ObjectStoreX.Stream.list_stream/2`, `prefix` matches whole path segments;
+  `""` covers the whole store.
+
+  ## Options
+
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      {:ok, expired} = ObjectStoreX.expire_now(store, "sessions")
+  """
+  @spec expire_now(store(), path(), keyword()) :: {:ok, [path()]} | {:error, term()}
+  def expire_now(store, prefix \\ "", opts \\ []) do
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.expire_now(store, prefix) do
+        paths when is_list(paths) -> {:ok, paths}
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Generate a pre-signed POST form so browsers can upload `key` directly to S3.
+
+  The form carries a policy signed with the store's credentials, so S3 only
+  accepts uploads meeting its conditions. Nothing is sent to S3. Returns
+  `{:ok, %{url: url, fields: fields}}`: the browser POSTs `fields` to `url`
+  as `multipart/form-data`, with the file last in a `file` field.
+
+  Supported on S3; other providers return `{:error, :not_supported}`.
+
+  ## Options
+
+  - `:expires_in` - Seconds the form stays valid (default: 3600)
+  - `:key_prefix` - Accept any key starting with this prefix instead of `key`
+    alone; `key` must start with it and may end in `${filename}`, which S3
+    replaces with the uploaded file's name
+  - `:content_length_range` - `{min, max}` accepted file size in bytes
+  - `:content_type` - Required content type, either exact or
+    `{:starts_with, prefix}`; exact types are added to `fields`, prefixes
+    leave the `Content-Type` field to the browser
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      {:ok, %{url: url, fields: fields}} =
+        ObjectStoreX.presign_post(store, "avatars/${filename}",
+          key_prefix: "avatars/",
+          content_length_range: {1, 5_000_000},
+          content_type: {:starts_with, "image/"},
+          expires_in: 600
+        )
+  """
+  @spec presign_post(store(), path(), keyword()) ::
+          {:ok, %{url: String.t(), fields: %{String.t() => String.t()}}} | {:error, term()}
+  def presign_post(store, key, opts \\ []) do
+    with {:ok, store, opts} <- resolve_profile(store, opts) do
+      {content_type, content_type_prefix} =
+        case Keyword.get(opts, :content_type) do
+          {:starts_with, prefix} -> {nil, prefix}
+          content_type -> {content_type, nil}
+        end
+
+      policy = %{
+        expires_in: Keyword.get(opts, :expires_in, 3600),
+        key_prefix: Keyword.get(opts, :key_prefix),
+        content_type: content_type,
+        content_type_prefix: content_type_prefix,
+        content_length_range: Keyword.get(opts, :content_length_range)
+      }
+
+      case Native.presign_post(store, key, policy) do
+        {:ok, form} -> {:ok, form}
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Delete an object only if it is unchanged, or delete a specific version.
+
+  Conditions map to provider conditional deletes, so a delete can't remove an
+  object that was concurrently replaced. Supported on S3; other providers
+  return `{:error, :not_supported}` when a condition is given. Without
+  conditions this behaves like `delete/3`.
+
+  ## Conditions
+
+  - `:if_match` - Only delete if the current ETag matches
+  - `:version` - Delete this object version (on versioned buckets)
+
+  ## Options
+
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      {:ok, meta} = ObjectStoreX.head(store, "lock.json")
+      :ok = ObjectStoreX.delete_with_options(store, "lock.json", %{if_match: meta.etag})
+
+      # Someone replaced the object in the meantime
+      {:error, :precondition_failed} =
+        ObjectStoreX.delete_with_options(store, "lock.json", %{if_match: meta.etag})
+  """
+  @spec delete_with_options(
+          store(),
+          path(),
+          %{optional(:if_match) => String.t(), optional(:version) => String.t()} | keyword(),
+          keyword()
+        ) :: :ok | {:error, term()}
+  def delete_with_options(store, path, conditions, opts \\ []) do
+    conditions = Map.new(conditions)
+
+    options = %{
+      if_match: Map.get(conditions, :if_match),
+      version: Map.get(conditions, :version)
+    }
+
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.delete_with_options(store, path, options) do
+        :ok -> :ok
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @typedoc """
+  A version of an object in a versioned bucket, as returned by `list_versions/3`.
+
+  `:version` is the S3 version id or the GCS generation. `:delete_marker` is
+  true for S3 delete markers, which have no content.
+  """
+  @type object_version :: %{
+          location: String.t(),
+          version: String.t(),
+          is_latest: boolean(),
+          delete_marker: boolean(),
+          last_modified: String.t(),
+          size: non_neg_integer(),
+          etag: String.t() | nil
+        }
+
+  @doc """
+  List all versions of the objects under a prefix in a versioned bucket.
+
+  Supported on S3 and GCS; other providers return `{:error, :not_supported}`.
+  Versions of each object are listed newest first. Together with
+  `delete_version/4` this allows retention and undelete workflows.
+
+  ## Options
+
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      {:ok, versions} = ObjectStoreX.list_versions(store, "reports/")
+
+      # Undelete on S3: remove the delete marker hiding the previous version
+      %{version: marker} =
+        Enum.find(versions, &(&1.location == "reports/q1.csv" and &1.delete_marker))
+
+      :ok = ObjectStoreX.delete_version(store, "reports/q1.csv", marker)
+  """
+  @spec list_versions(store(), path() | nil, keyword()) ::
+          {:ok, [object_version()]} | {:error, term()}
+  def list_versions(store, prefix \\ nil, opts \\ []) do
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.list_versions(store, prefix) do
+        versions when is_list(versions) -> {:ok, versions}
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Permanently delete one version of an object in a versioned bucket.
+
+  Deleting the current version makes the previous one current. Supported on
+  S3 and GCS; other providers return `{:error, :not_supported}`.
+
+  ## Options
+
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      :ok = ObjectStoreX.delete_version(store, "reports/q1.csv", version)
+  """
+  @spec delete_version(store(), path(), String.t(), keyword()) :: :ok | {:error, term()}
+  def delete_version(store, path, version, opts \\ []) do
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.delete_version(store, path, version) do
+        :ok -> :ok
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @typedoc """
+  A multipart upload that was started but never completed or aborted, as
+  returned by `list_incomplete_uploads/3`.
+  """
+  @type incomplete_upload :: %{
+          location: String.t(),
+          upload_id: String.t(),
+          initiated: String.t()
+        }
+
+  @doc """
+  List multipart uploads under a prefix that were never completed or aborted.
+
+  Uploads left behind by crashed processes keep their parts, which are billed
+  as storage until the upload is aborted. Supported on S3 and GCS; other
+  providers return `{:error, :not_supported}`. `:initiated` is formatted like
+  `:last_modified` in listings.
+
+  ## Options
+
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      {:ok, uploads} = ObjectStoreX.list_incomplete_uploads(store, "videos/")
+  """
+  @spec list_incomplete_uploads(store(), path() | nil, keyword()) ::
+          {:ok, [incomplete_upload()]} | {:error, term()}
+  def list_incomplete_uploads(store, prefix \\ nil, opts \\ []) do
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.list_incomplete_uploads(store, prefix) do
+        uploads when is_list(uploads) -> {:ok, uploads}
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Abort the incomplete multipart uploads under a prefix started before a time.
+
+  `older_than` is either an age in seconds or a `DateTime`: uploads initiated
+  before it are aborted and their parts deleted. Leave uploads that may still
+  be in progress alone by choosing an age longer than your longest upload.
+  Returns the aborted uploads; stops at the first abort that fails. Supported
+  on S3 and GCS; other providers return `{:error, :not_supported}`.
+
+  ## Options
+
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      # Clean up uploads abandoned for more than a day
+      {:ok, aborted} = ObjectStoreX.abort_incomplete_uploads(store, "", 86_400)
+  """
+  @spec abort_incomplete_uploads(
+          store(),
+          path() | nil,
+          non_neg_integer() | DateTime.t(),
+          keyword()
+        ) :: {:ok, [incomplete_upload()]} | {:error, term()}
+  def abort_incomplete_uploads(store, prefix, older_than, opts \\ []) do
+    cutoff =
+      case older_than do
+        %DateTime{} = time -> DateTime.to_unix(time)
+        seconds when is_integer(seconds) and seconds >= 0 -> System.os_time(:second) - seconds
+      end
+
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.abort_incomplete_uploads(store, prefix, cutoff) do
+        uploads when is_list(uploads) -> {:ok, uploads}
+        error -> {:error, error}
+      end
+    end
   rescue
     e -> {:error, Exception.message(e)}
   end
@@ -498,17 +3133,90 @@ defmodule ObjectStoreX do
   @doc """
   Get object metadata without downloading content.
 
+  ## Options
+
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
   ## Examples
 
       {:ok, meta} = ObjectStoreX.head(store, "file.txt")
-      # %{location: "file.txt", size: 1024, ...}
+      # %{location: "file.txt", size: 1024, metadata: %{}, ...}
   """
-  @spec head(store(), path()) :: {:ok, metadata()} | {:error, term()}
-  def head(store, path) do
-    case Native.head(store, path) do
-      meta when is_map(meta) -> {:ok, meta}
-      :not_found -> {:error, :not_found}
-      error -> {:error, error}
+  @spec head(store(), path(), keyword()) :: {:ok, metadata()} | {:error, term()}
+  def head(store, path, opts \\ []) do
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.head(store, path) do
+        meta when is_map(meta) -> {:ok, meta}
+        :not_found -> {:error, :not_found}
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Download an object together with its metadata.
+
+  Returns the data and the same metadata map as `head/3`, including the
+  content type and other attributes, from a single request instead of a
+  `head/3` followed by a `get/3`.
+
+  ## Options
+
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      {:ok, data, %{content_type: content_type}} =
+        ObjectStoreX.get_with_metadata(store, "report.pdf")
+  """
+  @spec get_with_metadata(store(), path(), keyword()) ::
+          {:ok, binary(), metadata()} | {:error, term()}
+  def get_with_metadata(store, path, opts \\ []) do
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.get_with_metadata(store, path) do
+        {:ok, data, meta} -> {:ok, data, meta}
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Get the metadata of many objects concurrently.
+
+  Heads run on the native runtime, at most `:max_concurrency` at a time, which
+  suits verifying large manifests against the store. Returns one
+  `{path, result}` entry per path, in the order of `paths`, with the same
+  metadata maps as `head/3`. A failed head doesn't stop the others.
+
+  ## Options
+
+  - `:max_concurrency` - Maximum number of heads in flight (default: 32)
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      {:ok, results} = ObjectStoreX.head_many(store, ["a.json", "missing.json"])
+      # [
+      #   {"a.json", {:ok, %{location: "a.json", size: 1024, ...}}},
+      #   {"missing.json", {:error, :not_found}}
+      # ]
+
+      missing = for {path, {:error, :not_found}} <- results, do: path
+  """
+  @spec head_many(store(), [path()], keyword()) ::
+          {:ok, [{path(), {:ok, metadata()} | {:error, term()}}]} | {:error, term()}
+  def head_many(store, paths, opts \\ []) when is_list(paths) do
+    max_concurrency = Keyword.get(opts, :max_concurrency, 32)
+
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.head_many(store, paths, max_concurrency) do
+        results when is_list(results) -> {:ok, results}
+        error -> {:error, error}
+      end
     end
   rescue
     e -> {:error, Exception.message(e)}
@@ -517,15 +3225,21 @@ defmodule ObjectStoreX do
   @doc """
   Copy an object within storage (server-side).
 
+  ## Options
+
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
   ## Examples
 
       :ok = ObjectStoreX.copy(store, "source.txt", "destination.txt")
   """
-  @spec copy(store(), path(), path()) :: :ok | {:error, term()}
-  def copy(store, from, to) do
-    case Native.copy(store, from, to) do
-      :ok -> :ok
-      error -> {:error, error}
+  @spec copy(store(), path(), path(), keyword()) :: :ok | {:error, term()}
+  def copy(store, from, to, opts \\ []) do
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.copy(store, from, to) do
+        :ok -> :ok
+        error -> {:error, error}
+      end
     end
   rescue
     e -> {:error, Exception.message(e)}
@@ -534,15 +3248,563 @@ defmodule ObjectStoreX do
   @doc """
   Rename an object (server-side move).
 
+  On local stores, renames across mount points (where the OS returns `EXDEV`)
+  fall back to copying the file and deleting the source.
+
+  ## Options
+
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+  - `:return_strategy` - Return `{:ok, strategy}` instead of `:ok`, where
+    strategy is `:rename` or `:copy_delete` (default: false)
+
+  ## Examples
+
+      :ok = ObjectStoreX.rename(store, "old.txt", "new.txt")
+
+      {:ok, :copy_delete} =
+        ObjectStoreX.rename(store, "scratch/big.bin", "archive/big.bin", return_strategy: true)
+  """
+  @spec rename(store(), path(), path(), keyword()) ::
+          :ok | {:ok, :rename | :copy_delete} | {:error, term()}
+  def rename(store, from, to, opts \\ []) do
+    with {:ok, store, opts} <- resolve_profile(store, opts) do
+      case Native.rename(store, from, to) do
+        {:ok, strategy} ->
+          if Keyword.get(opts, :return_strategy, false), do: {:ok, strategy}, else: :ok
+
+        error ->
+          {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Copy many objects concurrently (server-side).
+
+  Copies run on the native runtime, at most `:max_concurrency` at a time, for
+  reorganizations that touch thousands of keys. Returns one `{{from, to}, result}`
+  entry per pair, in the order of `pairs`. A failed copy doesn't stop the others.
+
+  ## Options
+
+  - `:max_concurrency` - Maximum number of copies in flight (default: 32)
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      pairs = for path <- paths, do: {path, "archive/" <> path}
+      {:ok, results} = ObjectStoreX.copy_many(store, pairs)
+      failed = for {{from, _to}, {:error, reason}} <- results, do: {from, reason}
+  """
+  @spec copy_many(store(), [{path(), path()}], keyword()) ::
+          {:ok, [{{path(), path()}, :ok | {:error, term()}}]} | {:error, term()}
+  def copy_many(store, pairs, opts \\ []) when is_list(pairs) do
+    run_many(store, pairs, opts, &Native.copy_many/3)
+  end
+
+  @doc """
+  Rename many objects concurrently (server-side moves).
+
+  Like `copy_many/3`, with each pair renamed as by `rename/4`.
+
+  ## Collision Policies
+
+  The `:on_conflict` option decides what happens when a destination exists:
+
+  - `:overwrite` - Replace the destination (default)
+  - `:fail` - Leave both objects and report `{:error, :already_exists}`
+  - `:skip` - Leave both objects and report `:skipped`
+  - `:suffix` - Rename to the first free `name-1.ext`, `name-2.ext`, ... and
+    report `{:ok, actual_to}`
+
+  Stores without an atomic rename-if-not-exists check the destination before
+  renaming, so concurrent writers can still race with `:fail`, `:skip` and
+  `:suffix`.
+
+  ## Options
+
+  - `:on_conflict` - Collision policy, see above (default: `:overwrite`)
+  - `:max_concurrency` - Maximum number of renames in flight (default: 32)
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      pairs = for path <- paths, do: {path, String.replace_prefix(path, "tmp/", "final/")}
+      {:ok, results} = ObjectStoreX.rename_many(store, pairs, max_concurrency: 64)
+
+      {:ok, [{{"a.txt", "b.txt"}, {:ok, "b-1.txt"}}]} =
+        ObjectStoreX.rename_many(store, [{"a.txt", "b.txt"}], on_conflict: :suffix)
+  """
+  @spec rename_many(store(), [{path(), path()}], keyword()) ::
+          {:ok, [{{path(), path()}, :ok | {:ok, path()} | :skipped | {:error, term()}}]}
+          | {:error, term()}
+  def rename_many(store, pairs, opts \\ []) when is_list(pairs) do
+    on_conflict = Keyword.get(opts, :on_conflict, :overwrite)
+
+    run_many(store, pairs, opts, &Native.rename_many(&1, &2, on_conflict, &3))
+  end
+
+  defp run_many(store, pairs, opts, fun) do
+    max_concurrency = Keyword.get(opts, :max_concurrency, 32)
+
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case fun.(store, pairs, max_concurrency) do
+        results when is_list(results) -> {:ok, results}
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Copy every object under a prefix to another prefix (server-side).
+
+  Objects are listed under `from_prefix` and copied with `from_prefix` replaced
+  by `to_prefix` in their keys, at most `:max_concurrency` at a time. Failed
+  copies don't stop the others; they are returned under `:errors`.
+
+  Local stores clone or hard link files where the filesystem allows, so a
+  prefix snapshot doesn't copy any data (see `:copy_strategy` in `new/2`).
+
+  Returns `{:ok, %{copied: n, skipped: n, errors: [{path, reason}]}}`, or
+  `{:error, reason}` if the source prefix can't be listed.
+
+  ## Options
+
+  - `:on_existing` - What to do when a destination key exists (default: `:overwrite`)
+    - `:overwrite` - Replace it
+    - `:skip` - Leave it and count the object as skipped. Atomic where the store
+      supports copy-if-not-exists, otherwise checked with a head first.
+  - `:max_concurrency` - Maximum number of copies in flight (default: 32)
+  - `:progress_pid` - Process receiving `{:progress, op_id, bytes_done, bytes_total}`
+    messages, counting the bytes of objects copied or skipped so far
+  - `:progress_id` - `op_id` used in progress messages (default: `from_prefix`)
+  - `:progress_interval` - Minimum milliseconds between progress messages (default: 100)
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      {:ok, %{copied: 1200, skipped: 0, errors: []}} =
+        ObjectStoreX.copy_prefix(store, "datasets/2024", "archive/datasets/2024")
+
+      # Resume an interrupted copy
+      {:ok, result} =
+        ObjectStoreX.copy_prefix(store, "datasets/2024", "archive/datasets/2024",
+          on_existing: :skip
+        )
+  """
+  @spec copy_prefix(store(), String.t(), String.t(), keyword()) ::
+          {:ok,
+           %{
+             copied: non_neg_integer(),
+             skipped: non_neg_integer(),
+             errors: [{path(), term()}]
+           }}
+          | {:error, term()}
+  def copy_prefix(store, from_prefix, to_prefix, opts \\ []) do
+    skip_existing =
+      case Keyword.get(opts, :on_existing, :overwrite) do
+        :overwrite -> false
+        :skip -> true
+      end
+
+    max_concurrency = Keyword.get(opts, :max_concurrency, 32)
+
+    with {:ok, store, opts} <- resolve_profile(store, opts) do
+      {progress, _opts} = pop_progress(opts, from_prefix)
+
+      case Native.copy_prefix(
+             store,
+             from_prefix,
+             to_prefix,
+             skip_existing,
+             max_concurrency,
+             progress
+           ) do
+        {:ok, result} -> {:ok, result}
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Extract a tar, gzipped tar or zip archive into a store under a prefix.
+
+  Entries are streamed from the archive to the destination entirely in
+  native code, so archives never pass through the BEAM. Tar archives are
+  read with a single streaming get; zip archives, whose index is at the end,
+  with ranged gets. `dest` is a store, or a local directory path that is
+  created if needed. Each file is written to `dest_prefix` joined with its
+  name in the archive; directories are implied by the keys.
+
+  Symlinks, hard links and other special entries are skipped, as are names
+  that are absolute or contain `..`, so an archive can't write outside the
+  prefix. Extraction stops at the first error, leaving the files extracted
+  so far in place.
+
+  ## Options
+
+  - `:format` - `:tar_gz`, `:tar` or `:zip` (default: detected from the
+    `.tar.gz`, `.tgz`, `.tar` or `.zip` extension of `path`)
+  - `:profile` - Credential profile to use for `store` (see `register_profile/3`)
+
+  ## Examples
+
+      {:ok, %{files: files, bytes: bytes, skipped: []}} =
+        ObjectStoreX.extract_archive(store, "uploads/site.zip", store, "sites/42")
+
+      # Extract to the local filesystem
+      {:ok, _} = ObjectStoreX.extract_archive(store, "backups/db.tar.gz", "/var/restore")
+  """
+  @spec extract_archive(store(), path(), store() | Path.t(), String.t(), keyword()) ::
+          {:ok, %{files: non_neg_integer(), bytes: non_neg_integer(), skipped: [String.t()]}}
+          | {:error, term()}
+  def extract_archive(store, path, dest, dest_prefix \\ "", opts \\ []) do
+    format = Keyword.get_lazy(opts, :format, fn -> archive_format(path) end)
+
+    with {:ok, store, _opts} <- resolve_profile(store, opts),
+         {:ok, dest} <- archive_destination(dest) do
+      case Native.extract_archive(store, path, format, dest, dest_prefix) do
+        {:ok, result} -> {:ok, result}
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Write every object under a prefix into a tar, gzipped tar or zip archive.
+
+  The archive is built in native code and streamed to `dest_path` in the
+  same store with a multipart upload as the objects are read, so neither the
+  objects nor the archive pass through the BEAM or are held in memory.
+  Objects are added one at a time in key order, named relative to the
+  prefix. An existing object at `dest_path` isn't included in the archive.
+  If an object fails to read, or changes size while it is archived, the
+  upload is aborted.
+
+  `format` is `:tar_gz`, `:tar` or `:zip`; zip entries are deflated.
+
+  ## Options
+
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      {:ok, %{files: files, size: size}} =
+        ObjectStoreX.archive_prefix(store, "projects/42", "exports/42.zip", :zip)
+  """
+  @spec archive_prefix(store(), String.t(), path(), :tar_gz | :tar | :zip, keyword()) ::
+          {:ok, %{files: non_neg_integer(), size: non_neg_integer()}} | {:error, term()}
+  def archive_prefix(store, prefix, dest_path, format, opts \\ []) do
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.archive_prefix(store, prefix, dest_path, format) do
+        {:ok, result} -> {:ok, result}
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  defp archive_format(path) do
+    cond do
+      String.ends_with?(path, [".tar.gz", ".tgz"]) -> :tar_gz
+      String.ends_with?(path, ".tar") -> :tar
+      String.ends_with?(path, ".zip") -> :zip
+      true -> raise ArgumentError, "can't detect the archive format of #{path}, pass :format"
+    end
+  end
+
+  defp archive_destination(dir) when is_binary(dir) do
+    File.mkdir_p!(dir)
+    new(:local, path: dir)
+  end
+
+  defp archive_destination(store), do: {:ok, store}
+
+  @doc """
+  Store data under its SHA-256 digest, returning the digest.
+
+  Content-addressed objects are written to
+  `<prefix>/<first two digest characters>/<digest>`. Data that is already
+  stored is not uploaded again: an existing digest only costs a HEAD request,
+  which makes this a building block for artifact and build caches.
+  Concurrent puts of the same data are safe.
+
+  ## Options
+
+  - `:prefix` - Prefix of the content-addressed objects (default: `"cas"`)
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      {:ok, digest} = ObjectStoreX.put_cas(store, File.read!("app.tar"))
+      {:ok, data} = ObjectStoreX.get_cas(store, digest)
+  """
+  @spec put_cas(store(), iodata(), keyword()) :: {:ok, String.t()} | {:error, term()}
+  def put_cas(store, data, opts \\ []) do
+    with {:ok, store, opts} <- resolve_profile(store, opts) do
+      prefix = Keyword.get(opts, :prefix, "cas")
+
+      case Native.put_cas(store, prefix, IO.iodata_to_binary(data)) do
+        {:ok, digest, _written} -> {:ok, digest}
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Get data stored with `put_cas/3` by its SHA-256 digest.
+
+  The data is verified against the digest; `{:error, :integrity_error}` means
+  the stored object was modified or corrupted. Accepts the same options as
+  `put_cas/3`.
+  """
+  @spec get_cas(store(), String.t(), keyword()) :: {:ok, binary()} | {:error, term()}
+  def get_cas(store, digest, opts \\ []) do
+    with {:ok, store, opts} <- resolve_profile(store, opts) do
+      prefix = Keyword.get(opts, :prefix, "cas")
+
+      case Native.get_cas(store, prefix, String.downcase(digest)) do
+        {:ok, data} -> {:ok, data}
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Upload a local file as content-defined chunks plus a manifest at `path`.
+
+  The file is split with FastCDC into variable-size chunks whose boundaries
+  follow the content, so inserting or changing bytes only changes the chunks
+  around the edit. Chunks are stored by SHA-256 digest like `put_cas/3`, and
+  chunks that are already stored aren't uploaded again: re-uploading a
+  modified file only transfers the chunks that changed, and files sharing
+  content share chunks. The manifest at `path` lists the chunks in order and
+  is written last, once every chunk is stored.
+
+  Returns a map with the number of `:chunks`, the `:uploaded_chunks` and
+  `:uploaded_bytes` actually transferred, the file's `:size` and its
+  `:sha256`. Read the file back with `get_chunked/4`.
+
+  ## Options
+
+  - `:chunk_prefix` - Prefix of the content-addressed chunks (default: `"cas"`)
+  - `:min_chunk_size` - Minimum chunk size in bytes (default: 256KB, 64B to 1MB)
+  - `:avg_chunk_size` - Average chunk size in bytes (default: 1MB, 256B to 4MB)
+  - `:max_chunk_size` - Maximum chunk size in bytes (default: 4MB, 1KB to 16MB)
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  Chunk sizes must be increasing from minimum to maximum. Files uploaded with
+  different chunk sizes don't share chunks.
+
+  ## Examples
+
+      {:ok, %{chunks: 512}} =
+        ObjectStoreX.put_chunked(store, "images/disk.img", "/var/images/disk.img")
+
+      # After changing a few blocks of the file
+      {:ok, %{uploaded_chunks: 3}} =
+        ObjectStoreX.put_chunked(store, "images/disk.img", "/var/images/disk.img")
+  """
+  @spec put_chunked(store(), path(), Path.t(), keyword()) ::
+          {:ok,
+           %{
+             chunks: non_neg_integer(),
+             uploaded_chunks: non_neg_integer(),
+             size: non_neg_integer(),
+             uploaded_bytes: non_neg_integer(),
+             sha256: String.t()
+           }}
+          | {:error, term()}
+  def put_chunked(store, path, file_path, opts \\ []) do
+    with {:ok, store, opts} <- resolve_profile(store, opts) do
+      prefix = Keyword.get(opts, :chunk_prefix, "cas")
+      min_size = Keyword.get(opts, :min_chunk_size, 256 * 1024)
+      avg_size = Keyword.get(opts, :avg_chunk_size, 1024 * 1024)
+      max_size = Keyword.get(opts, :max_chunk_size, 4 * 1024 * 1024)
+      file_path = to_string(file_path)
+
+      case Native.put_chunked(store, path, file_path, prefix, min_size, avg_size, max_size) do
+        {:ok, result} -> {:ok, result}
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Reassemble a file uploaded with `put_chunked/4` into a local file.
+
+  Chunks are fetched concurrently from the prefix recorded in the manifest and
+  written in order. Every chunk is verified against its digest, failing with
+  `{:error, :integrity_error}`, and the whole file against the manifest's
+  size and SHA-256, failing with `{:error, :checksum_mismatch}`. The
+  partially written file is removed on failure.
+
+  ## Options
+
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      :ok = ObjectStoreX.get_chunked(store, "images/disk.img", "/tmp/disk.img")
+  """
+  @spec get_chunked(store(), path(), Path.t(), keyword()) :: :ok | {:error, term()}
+  def get_chunked(store, path, file_path, opts \\ []) do
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.get_chunked(store, path, to_string(file_path)) do
+        :ok -> :ok
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Record every object under a prefix in a snapshot manifest.
+
+  The manifest written to `manifest_path` is a JSON document listing each
+  object's path relative to the prefix, size, ETag, version, modification
+  time and SHA-256 checksum. Checksums are computed by reading every object,
+  with the ETag and version it was listed with, so an object changing while
+  the snapshot is taken fails it. A manifest stored under the prefix isn't
+  included.
+
+  The manifest points at the objects rather than copying them: on versioned
+  buckets, `restore/4` reads the recorded versions, so the snapshot stays
+  restorable after objects are overwritten or deleted. On other stores, only
+  objects that are unchanged can be restored; copy the prefix elsewhere with
+  `restore/4` to keep a backup.
+
+  ## Options
+
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      {:ok, %{objects: 1204, size: size}} =
+        ObjectStoreX.snapshot(store, "app/data", "backups/2026-10-17.json")
+  """
+  @spec snapshot(store(), String.t(), path(), keyword()) ::
+          {:ok, %{manifest: String.t(), objects: non_neg_integer(), size: non_neg_integer()}}
+          | {:error, term()}
+  def snapshot(store, prefix, manifest_path, opts \\ []) do
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.snapshot(store, prefix, manifest_path) do
+        {:ok, result} -> {:ok, result}
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Copy the objects recorded by `snapshot/4` and verify them.
+
+  `dest` is where the objects are copied to:
+
+  - `nil` - The snapshot's own prefix in `store`, to roll it back
+  - a prefix - Another prefix in `store`
+  - a store - The same prefix in another store
+  - `{store, prefix}` - A prefix in another store
+
+  Objects are read at their recorded version on versioned stores, and
+  otherwise only if they still have their recorded ETag. Every copy is
+  checked against the recorded size and SHA-256 before its upload completes.
+  Objects that can't be restored don't stop the restore; they are listed in
+  `:failed` with the reason, `:precondition_failed` for objects changed since
+  the snapshot and `:checksum_mismatch` for data that doesn't match.
+
+  ## Options
+
+  - `:profile` - Credential profile to use for `store` (see `register_profile/3`)
+
+  ## Examples
+
+      {:ok, %{restored: 1204, failed: []}} =
+        ObjectStoreX.restore(store, "backups/2026-10-17.json", {backup_store, "app/data"})
+  """
+  @spec restore(store(), path(), nil | String.t() | store() | {store(), String.t()}, keyword()) ::
+          {:ok,
+           %{
+             restored: non_neg_integer(),
+             bytes: non_neg_integer(),
+             failed: [%{path: String.t(), reason: atom()}]
+           }}
+          | {:error, term()}
+  def restore(store, manifest_path, dest \\ nil, opts \\ []) do
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      {dest_store, dest_prefix} =
+        case dest do
+          nil -> {store, nil}
+          prefix when is_binary(prefix) -> {store, prefix}
+          {dest_store, prefix} -> {dest_store, prefix}
+          dest_store -> {dest_store, nil}
+        end
+
+      case Native.restore(store, manifest_path, dest_store, dest_prefix) do
+        {:ok, result} -> {:ok, result}
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Audit the objects recorded by `snapshot/4` against its manifest.
+
+  Every recorded object is streamed and checksummed natively, at its
+  recorded version on versioned stores, and the prefix is listed for objects
+  that weren't in the snapshot. Returns the count of `:verified` objects and
+  the paths, relative to the snapshot's prefix, of objects that are:
+
+  - `:missing` - Gone
+  - `:changed` - Overwritten with other data since the snapshot
+  - `:corrupted` - Different from their checksum under the recorded ETag or
+    version, which points at corruption rather than a new write
+  - `:extra` - Added under the prefix since the snapshot
+
+  ## Options
+
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
   ## Examples
 
-      :ok = ObjectStoreX.rename(store, "old.txt", "new.txt")
+      {:ok, %{missing: [], changed: [], corrupted: []}} =
+        ObjectStoreX.verify_prefix(store, "backups/2026-10-17.json")
   """
-  @spec rename(store(), path(), path()) :: :ok | {:error, term()}
-  def rename(store, from, to) do
-    case Native.rename(store, from, to) do
-      :ok -> :ok
-      error -> {:error, error}
+  @spec verify_prefix(store(), path(), keyword()) ::
+          {:ok,
+           %{
+             verified: non_neg_integer(),
+             missing: [String.t()],
+             changed: [String.t()],
+             corrupted: [String.t()],
+             extra: [String.t()]
+           }}
+          | {:error, term()}
+  def verify_prefix(store, manifest_path, opts \\ []) do
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.verify_prefix(store, manifest_path) do
+        {:ok, result} -> {:ok, result}
+        error -> {:error, error}
+      end
     end
   rescue
     e -> {:error, Exception.message(e)}
@@ -637,6 +3899,292 @@ defmodule ObjectStoreX do
     e -> {:error, Exception.message(e)}
   end
 
+  @doc """
+  Delete, copy or rename an object only if its ETag still matches.
+
+  Use this after listing or reading an object to act on exactly the version
+  you saw. Returns `{:error, :precondition_failed}` if the object has changed.
+
+  `action` is one of:
+
+  - `:delete` - Delete the object
+  - `{:copy, to}` - Copy the object to `to`
+  - `{:rename, to}` - Move the object to `to`
+
+  The result's `:atomic` field tells whether the ETag check and the action
+  happened in one request, so no concurrent write could slip in between:
+
+  - S3: delete and copy are atomic. A rename copies and then deletes, each
+    checked against the ETag; if the source changes in between, the copy is
+    removed and the rename fails, but `:atomic` is `false`.
+  - Other providers: the ETag is checked with a separate request before the
+    action, and `:atomic` is `false`.
+
+  ## Options
+
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      {:ok, meta} = ObjectStoreX.head(store, "inbox/job.json")
+
+      action = {:rename, "done/job.json"}
+
+      case ObjectStoreX.act_if_unchanged(store, "inbox/job.json", meta.etag, action) do
+        {:ok, %{atomic: _}} -> :moved
+        {:error, :precondition_failed} -> :changed_meanwhile
+      end
+  """
+  @spec act_if_unchanged(
+          store(),
+          path(),
+          String.t(),
+          :delete | {:copy, path()} | {:rename, path()},
+          keyword()
+        ) :: {:ok, %{atomic: boolean()}} | {:error, term()}
+  def act_if_unchanged(store, path, etag, action, opts \\ []) do
+    {action, to} =
+      case action do
+        :delete -> {:delete, nil}
+        {:copy, to} -> {:copy, to}
+        {:rename, to} -> {:rename, to}
+      end
+
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.act_if_unchanged(store, path, etag, action, to) do
+        {:ok, result} -> {:ok, result}
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Replace an object's data only if its ETag is still `expected_etag`.
+
+  With `expected_etag` set to `nil`, the object is only created if it doesn't
+  exist. The check and the write happen in one request. When the put is
+  rejected but the object still has the expected ETag, the write lost
+  against a concurrent conditional write that didn't go through, and it is
+  retried up to `:max_retries` times. If the object has actually changed,
+  `{:error, :precondition_failed}` is returned without retrying, since the
+  new data was computed from an outdated state; use `update/4` to recompute
+  it and try again.
+
+  Stores without conditional puts, such as the local filesystem, return
+  `{:error, :not_supported}`.
+
+  ## Options
+
+  - `:max_retries` - Retries after conflicting writes (default: `3`)
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      {:ok, meta} = ObjectStoreX.head(store, "config.json")
+
+      case ObjectStoreX.compare_and_swap(store, "config.json", meta.etag, new_config) do
+        {:ok, _put_result} -> :updated
+        {:error, :precondition_failed} -> :changed_meanwhile
+      end
+  """
+  @spec compare_and_swap(store(), path(), String.t() | nil, iodata(), keyword()) ::
+          {:ok, put_result()} | {:error, term()}
+  def compare_and_swap(store, path, expected_etag, new_data, opts \\ []) do
+    with {:ok, store, opts} <- resolve_profile(store, opts) do
+      max_retries = Keyword.get(opts, :max_retries, 3)
+      data = IO.iodata_to_binary(new_data)
+
+      case Native.compare_and_swap(store, path, expected_etag, data, max_retries) do
+        {:conflict, _data, _etag} -> {:error, :precondition_failed}
+        result -> normalize_put_result(result)
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Atomically update an object by applying `fun` to its current data.
+
+  `fun` receives the object's data, or `nil` if it doesn't exist, and returns
+  the new data. The result is written with `compare_and_swap/5`; if another
+  writer changed the object in between, `fun` is applied again to the data
+  that writer left, up to `:max_attempts` times, after which
+  `{:error, :precondition_failed}` is returned. `fun` may therefore run more
+  than once and should not have side effects. Return `{:abort, reason}` from
+  `fun` to stop without writing, which returns `{:error, reason}`.
+
+  Supports the stores and options of `compare_and_swap/5`.
+
+  ## Options
+
+  - `:max_attempts` - Times `fun` is applied before giving up (default: `10`)
+  - `:max_retries` - Retries after conflicting writes per attempt (default: `3`)
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      # Counter
+      ObjectStoreX.update(store, "counters/visits", fn
+        nil -> "1"
+        count -> Integer.to_string(String.to_integer(count) + 1)
+      end)
+
+      # JSON document
+      ObjectStoreX.update(store, "config.json", fn data ->
+        data
+        |> Jason.decode!()
+        |> Map.put("maintenance", true)
+        |> Jason.encode!()
+      end)
+  """
+  @spec update(store(), path(), (binary() | nil -> iodata() | {:abort, term()}), keyword()) ::
+          {:ok, put_result()} | {:error, term()}
+  def update(store, path, fun, opts \\ []) when is_function(fun, 1) do
+    with {:ok, store, opts} <- resolve_profile(store, opts),
+         {:ok, data, etag} <- read_for_update(store, path) do
+      max_attempts = Keyword.get(opts, :max_attempts, 10)
+      max_retries = Keyword.get(opts, :max_retries, 3)
+      do_update(store, path, fun, data, etag, max_attempts, max_retries)
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  defp read_for_update(store, path) do
+    case Native.get_with_options(store, path, %ObjectStoreX.GetOptions{}) do
+      {:ok, data, meta} -> {:ok, data, meta.etag}
+      :not_found -> {:ok, nil, nil}
+      error -> {:error, error}
+    end
+  end
+
+  defp do_update(_store, _path, _fun, _data, _etag, 0, _max_retries),
+    do: {:error, :precondition_failed}
+
+  defp do_update(store, path, fun, data, etag, attempts, max_retries) do
+    case fun.(data) do
+      {:abort, reason} ->
+        {:error, reason}
+
+      new_data ->
+        new_data = IO.iodata_to_binary(new_data)
+
+        case Native.compare_and_swap(store, path, etag, new_data, max_retries) do
+          {:conflict, data, etag} ->
+            do_update(store, path, fun, data, etag, attempts - 1, max_retries)
+
+          result ->
+            normalize_put_result(result)
+        end
+    end
+  end
+
+  @doc """
+  Get an object and decode it as JSON.
+
+  The document is parsed natively and returned with its ETag, which can be
+  passed to `put_json/4` with `mode: {:update, %{etag: etag}}` or to
+  `compare_and_swap/5`. Objects use binary keys, as with `Jason.decode/1`.
+  Returns `{:error, :invalid_json}` if the object isn't valid JSON.
+
+  ## Options
+
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      {:ok, %{"feature_flags" => flags}, _etag} = ObjectStoreX.get_json(store, "config.json")
+  """
+  @spec get_json(store(), path(), keyword()) :: {:ok, term(), String.t()} | {:error, term()}
+  def get_json(store, path, opts \\ []) do
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.get_json(store, path) do
+        {:ok, value, etag} -> {:ok, value, etag}
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Encode a value as JSON natively and upload it.
+
+  Maps, lists, strings, numbers, booleans and `nil` are encoded as with
+  `Jason.encode/1`; atoms other than booleans and `nil` become strings, and
+  map keys may be strings, atoms or integers. Other values return an error.
+
+  ## Options
+
+  - `:mode` - Write mode, as for `put/4` (default: `:overwrite`). Use
+    `{:update, %{etag: etag}}` with the ETag from `get_json/3` to only write
+    if the document hasn't changed.
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      {:ok, _} = ObjectStoreX.put_json(store, "config.json", %{feature_flags: ["beta"]})
+  """
+  @spec put_json(store(), path(), term(), keyword()) :: {:ok, put_result()} | {:error, term()}
+  def put_json(store, path, value, opts \\ []) do
+    with {:ok, store, opts} <- resolve_profile(store, opts) do
+      store
+      |> Native.put_json(path, value, Keyword.get(opts, :mode, :overwrite))
+      |> normalize_put_result()
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Merge a patch into a JSON document.
+
+  The patch is applied as a JSON Merge Patch (RFC 7386): maps are merged key
+  by key, `nil` values remove keys, and other values replace what was there.
+  A missing document is created from the patch. Returns the merged document
+  and its ETag.
+
+  With `cas: true` (the default), the merged document is written with a
+  conditional put. If another writer changed the document in between, the
+  patch is applied again to the current document, up to `:max_attempts`
+  times before returning `{:error, :precondition_failed}`, so concurrent
+  merges never lose each other's changes. Stores without conditional puts,
+  such as the local filesystem, return `{:error, :not_supported}` unless
+  `cas: false`, which reads, merges and overwrites.
+
+  ## Options
+
+  - `:cas` - Guard the write with the document's ETag (default: `true`)
+  - `:max_attempts` - Times the patch is applied before giving up (default: `10`)
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      {:ok, %{"maintenance" => true}, _etag} =
+        ObjectStoreX.merge_json(store, "config.json", %{maintenance: true})
+
+      # Remove a key
+      {:ok, _config, _etag} = ObjectStoreX.merge_json(store, "config.json", %{maintenance: nil})
+  """
+  @spec merge_json(store(), path(), term(), keyword()) ::
+          {:ok, term(), String.t()} | {:error, term()}
+  def merge_json(store, path, patch, opts \\ []) do
+    with {:ok, store, opts} <- resolve_profile(store, opts) do
+      cas = Keyword.get(opts, :cas, true)
+      max_attempts = Keyword.get(opts, :max_attempts, 10)
+
+      case Native.merge_json(store, path, patch, cas, max_attempts) do
+        {:ok, document, etag} -> {:ok, document, etag}
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
   @doc """
   Fetch multiple byte ranges from an object in a single operation.
 
@@ -670,6 +4218,45 @@ defmodule ObjectStoreX do
     e -> {:error, Exception.message(e)}
   end
 
+  @doc """
+  Fetch many objects concurrently.
+
+  Gets run on the native runtime, at most `:max_concurrency` at a time, which
+  is much faster than calling `get/3` in a loop for many small objects.
+  Returns one `{path, result}` entry per path, in the order of `paths`. A
+  failed get doesn't stop the others.
+
+  ## Options
+
+  - `:max_concurrency` - Maximum number of gets in flight (default: 32)
+  - `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      {:ok, results} = ObjectStoreX.get_many(store, ["a.json", "b.json", "missing.json"])
+      # [
+      #   {"a.json", {:ok, "{...}"}},
+      #   {"b.json", {:ok, "{...}"}},
+      #   {"missing.json", {:error, :not_found}}
+      # ]
+
+      blobs = for {path, {:ok, data}} <- results, into: %{}, do: {path, data}
+  """
+  @spec get_many(store(), [path()], keyword()) ::
+          {:ok, [{path(), {:ok, binary()} | {:error, term()}}]} | {:error, term()}
+  def get_many(store, paths, opts \\ []) when is_list(paths) do
+    max_concurrency = Keyword.get(opts, :max_concurrency, 32)
+
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.get_many(store, paths, max_concurrency) do
+        results when is_list(results) -> {:ok, results}
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
   @doc """
   Delete multiple objects in bulk with automatic batching.
 
@@ -725,6 +4312,9 @@ defmodule ObjectStoreX do
   ## Options
 
   * `:prefix` - Optional prefix to filter objects (default: nil)
+  * `:page_size` - Entries requested per listing request on S3 and GCS (see
+    `ObjectStoreX.Stream.list_stream/2`)
+  * `:profile` - Credential profile to use (see `register_profile/3`)
 
   ## Returns
 
@@ -736,15 +4326,307 @@ defmodule ObjectStoreX do
           {:ok, [metadata()], [String.t()]} | {:error, term()}
   def list_with_delimiter(store, opts \\ []) do
     prefix = Keyword.get(opts, :prefix)
+    page_size = Keyword.get(opts, :page_size)
 
-    case Native.list_with_delimiter(store, prefix) do
-      {objects, prefixes} when is_list(objects) and is_list(prefixes) ->
-        {:ok, objects, prefixes}
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.list_with_delimiter(store, prefix, page_size) do
+        {objects, prefixes} when is_list(objects) and is_list(prefixes) ->
+          {:ok, objects, prefixes}
 
-      error ->
-        {:error, error}
+        error ->
+          {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Count objects and bytes under a prefix, grouped natively in a single listing pass.
+
+  Returns a compact summary map of group key to `%{count: n, bytes: b}`, suitable
+  for dashboards over large prefixes without materializing the listing in Elixir.
+
+  ## Grouping
+
+  * `:directory` - First path segment below the prefix. Objects directly under
+    the prefix are grouped under `""`.
+  * `:extension` - File extension of the object name, `""` when there is none.
+  * `:date` - Last-modified date, bucketed by the `:bucket` option.
+
+  ## Options
+
+  * `:bucket` - Date bucket for `:date` grouping: `:day` (default, `"2025-01-31"`),
+    `:month` (`"2025-01"`) or `:year` (`"2025"`)
+  * `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      {:ok, summary} = ObjectStoreX.aggregate(store, "data/", :extension)
+      # %{"csv" => %{count: 3, bytes: 450}, "json" => %{count: 1, bytes: 200}}
+
+      {:ok, summary} = ObjectStoreX.aggregate(store, nil, :date, bucket: :month)
+      # %{"2025-01" => %{count: 12, bytes: 4096}}
+  """
+  @spec aggregate(store(), path() | nil, :directory | :extension | :date, keyword()) ::
+          {:ok, %{String.t() => %{count: non_neg_integer(), bytes: non_neg_integer()}}}
+          | {:error, term()}
+  def aggregate(store, prefix, group_by, opts \\ [])
+      when group_by in [:directory, :extension, :date] do
+    bucket = Keyword.get(opts, :bucket, :day)
+
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.aggregate(store, prefix, group_by, bucket) do
+        summary when is_map(summary) -> {:ok, summary}
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Find the `n` largest or most recently modified objects under a prefix.
+
+  The report is computed during a single native listing pass that keeps only
+  `n` entries in memory, so capacity investigations on large prefixes don't
+  require exporting the full inventory.
+
+  ## Options
+
+  * `:by` - `:size` (default) or `:last_modified`
+  * `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      # Ten largest objects under logs/
+      {:ok, largest} = ObjectStoreX.top_n(store, "logs/", 10)
+
+      # Five most recently modified objects in the store
+      {:ok, recent} = ObjectStoreX.top_n(store, nil, 5, by: :last_modified)
+
+  ## Returns
+
+  `{:ok, objects}` with metadata maps ordered largest or newest first.
+  """
+  @spec top_n(store(), path() | nil, non_neg_integer(), keyword()) ::
+          {:ok, [metadata()]} | {:error, term()}
+  def top_n(store, prefix, n, opts \\ []) when is_integer(n) and n >= 0 do
+    by = Keyword.get(opts, :by, :size)
+
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.top_n(store, prefix, by, n) do
+        objects when is_list(objects) -> {:ok, objects}
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @typedoc """
+  Result of `stat_prefix/3`.
+  """
+  @type prefix_stats :: %{
+          count: non_neg_integer(),
+          bytes: non_neg_integer(),
+          largest: metadata() | nil,
+          newest: metadata() | nil
+        }
+
+  @doc """
+  Summarize the objects under a prefix, like `du` for a folder.
+
+  The listing is consumed natively in a single pass, so only the summary
+  crosses into Elixir, however many objects the prefix holds.
+
+  Returns `{:ok, stats}` where `stats` has the object `:count`, the total
+  `:bytes`, and the metadata of the `:largest` and `:newest` (most recently
+  modified) objects. Both are `nil` for an empty prefix.
+
+  ## Options
+
+  * `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      {:ok, %{count: count, bytes: bytes, newest: newest}} =
+        ObjectStoreX.stat_prefix(store, "backups/")
+
+      IO.puts("\#{count} objects, \#{bytes} bytes, last written \#{newest.last_modified}")
+  """
+  @spec stat_prefix(store(), path() | nil, keyword()) :: {:ok, prefix_stats()} | {:error, term()}
+  def stat_prefix(store, prefix, opts \\ []) do
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.stat_prefix(store, prefix) do
+        stats when is_map(stats) -> {:ok, stats}
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  List the objects under a prefix modified at or after `since`.
+
+  `since` is a `DateTime` or a Unix timestamp in seconds. The listing is
+  filtered natively, so only matching objects cross into Elixir, which makes
+  incremental processing of large prefixes cheap.
+
+  Objects are returned oldest first. The comparison is at second precision
+  and inclusive, so when `since` is the time the previous run started,
+  objects written during that second are returned again rather than missed.
+
+  ## Options
+
+  * `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      started = DateTime.utc_now()
+      {:ok, objects} = ObjectStoreX.list_modified_since(store, "events/", last_run)
+      Enum.each(objects, &process/1)
+      last_run = started
+  """
+  @spec list_modified_since(store(), path() | nil, DateTime.t() | integer(), keyword()) ::
+          {:ok, [metadata()]} | {:error, term()}
+  def list_modified_since(store, prefix, since, opts \\ [])
+      when is_integer(since) or is_struct(since, DateTime) do
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.list_modified_since(store, prefix, convert_datetime_to_timestamp(since)) do
+        objects when is_list(objects) -> {:ok, objects}
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Read the first bytes of a random sample of objects under a prefix.
+
+  Objects are picked uniformly at random during a single native listing pass,
+  and only the first `bytes_per_object` bytes of each are fetched, which makes
+  format and health spot-checks over large datasets cheap.
+
+  ## Options
+
+  * `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      # Check that a sample of exports are gzip files
+      {:ok, samples} = ObjectStoreX.sample(store, "exports/", 20, 2)
+
+      for {meta, header} <- samples, header != <<0x1F, 0x8B>> do
+        IO.puts("Not gzip: \#{meta.location}")
+      end
+
+  ## Returns
+
+  `{:ok, [{metadata, data}]}` ordered by location, with at most `n` entries.
+  Objects deleted between listing and reading are left out.
+  """
+  @spec sample(store(), path() | nil, non_neg_integer(), non_neg_integer(), keyword()) ::
+          {:ok, [{metadata(), binary()}]} | {:error, term()}
+  def sample(store, prefix, n, bytes_per_object, opts \\ [])
+      when is_integer(n) and n >= 0 and is_integer(bytes_per_object) and bytes_per_object >= 0 do
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.sample(store, prefix, n, bytes_per_object) do
+        samples when is_list(samples) -> {:ok, samples}
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @bench_defaults [
+    objects: 100,
+    object_size: 64 * 1024,
+    iterations: 1,
+    concurrency: 8,
+    part_size: 5 * 1024 * 1024,
+    part_concurrency: 8,
+    cleanup: true
+  ]
+
+  @doc """
+  Run a standardized benchmark scenario against a store.
+
+  Operations are issued natively, without crossing into Elixir per request, so
+  the results reflect the provider and client options rather than the caller.
+  Use it to compare providers, part sizes or concurrency before settling on a
+  configuration.
+
+  ## Scenarios
+
+  * `:put` - Write `:objects` objects of `:object_size` bytes
+  * `:get` - Write the objects, then time reading them back in full
+  * `:list` - Write the objects, then time listing their prefix
+  * `:stream` - Write the objects through multipart uploads of `:part_size`
+    parts
+
+  Setup writes for `:get` and `:list` aren't measured. Failed operations are
+  counted in `:errors` and left out of the latency distribution.
+
+  ## Options
+
+  * `:objects` - Objects written or read per iteration (default: 100)
+  * `:object_size` - Size of each object in bytes (default: 64KB)
+  * `:iterations` - Rounds over the objects, or listings for `:list`
+    (default: 1)
+  * `:concurrency` - Operations in flight at once (default: 8)
+  * `:part_size` - Multipart part size for `:stream` (default: 5MB)
+  * `:part_concurrency` - Parts uploading at once per object for `:stream`
+    (default: 8)
+  * `:prefix` - Location of the benchmark objects (default: a random prefix
+    under `objectstorex-bench/`)
+  * `:cleanup` - Delete the benchmark objects afterwards (default: `true`)
+  * `:profile` - Credential profile to use (see `register_profile/3`)
+
+  ## Examples
+
+      {:ok, result} = ObjectStoreX.bench(store, :get, objects: 200, concurrency: 32)
+      result.ops_per_sec
+      result.latency_us.p99
+
+      # Compare part sizes for large uploads
+      for part_size <- [5, 16, 64] do
+        ObjectStoreX.bench(store, :stream,
+          objects: 4,
+          object_size: 256 * 1024 * 1024,
+          part_size: part_size * 1024 * 1024
+        )
+      end
+
+  ## Returns
+
+  `{:ok, result}` where `result` has `:scenario`, `:operations`, `:errors`,
+  `:bytes`, `:elapsed_us`, `:ops_per_sec`, `:bytes_per_sec` and `:latency_us`,
+  a map of `:min`, `:mean`, `:p50`, `:p90`, `:p99` and `:max` in microseconds.
+  """
+  @spec bench(store(), :put | :get | :list | :stream, keyword()) ::
+          {:ok, map()} | {:error, term()}
+  def bench(store, scenario, opts \\ []) when scenario in [:put, :get, :list, :stream] do
+    bench_opts =
+      @bench_defaults
+      |> Keyword.merge(Keyword.take(opts, Keyword.keys(@bench_defaults)))
+      |> Map.new()
+      |> Map.put(:prefix, Keyword.get_lazy(opts, :prefix, &bench_prefix/0))
+
+    with {:ok, store, _opts} <- resolve_profile(store, opts) do
+      case Native.bench(store, scenario, bench_opts) do
+        {:ok, result} -> {:ok, result}
+        error -> {:error, error}
+      end
     end
   rescue
     e -> {:error, Exception.message(e)}
   end
+
+  defp bench_prefix do
+    "objectstorex-bench/" <> Base.url_encode64(:crypto.strong_rand_bytes(6), padding: false)
+  end
 end
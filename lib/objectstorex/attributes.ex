@@ -9,6 +9,8 @@ defmodule ObjectStoreX.Attributes do
   - `content_disposition` - Download behavior (e.g., "attachment; filename=file.pdf")
   - `cache_control` - Cache directives (e.g., "max-age=3600")
   - `content_language` - Language code (e.g., "en-US")
+  - `metadata` - Custom user metadata as a string map, stored as
+    `x-amz-meta-*` (S3), `x-ms-meta-*` (Azure) or `x-goog-meta-*` (GCS) headers
 
   ## Examples
 
@@ -23,6 +25,12 @@ defmodule ObjectStoreX.Attributes do
         content_type: "application/pdf",
         content_disposition: "attachment; filename=report.pdf"
       }
+
+      # Attach custom metadata
+      %ObjectStoreX.Attributes{
+        content_type: "image/png",
+        metadata: %{"uploaded-by" => "alice"}
+      }
   """
 
   @type t :: %__MODULE__{
@@ -30,7 +38,8 @@ defmodule ObjectStoreX.Attributes do
           content_encoding: String.t() | nil,
           content_disposition: String.t() | nil,
           cache_control: String.t() | nil,
-          content_language: String.t() | nil
+          content_language: String.t() | nil,
+          metadata: %{String.t() => String.t()}
         }
 
   defstruct [
@@ -38,6 +47,7 @@ defmodule ObjectStoreX.Attributes do
     :content_encoding,
     :content_disposition,
     :cache_control,
-    :content_language
+    :content_language,
+    metadata: %{}
   ]
 end
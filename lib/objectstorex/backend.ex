@@ -0,0 +1,218 @@
+defmodule ObjectStoreX.Backend do
+  @moduledoc """
+  Custom storage backends implemented in Elixir.
+
+  A backend module implements the callbacks below for an exotic storage
+  service (an internal blob service, a database table, ...). A backend server
+  process runs the callbacks on behalf of a native store, so the resulting
+  store works with the whole `ObjectStoreX` API: conditional reads, ranges,
+  streaming, `ObjectStoreX.derive/2`, reports and so on.
+
+  Each call runs in its own task, so callbacks may run concurrently and must
+  not rely on mutable server state. The state returned by `c:init/1` is passed
+  to every callback.
+
+  ## Examples
+
+      defmodule MyApp.BlobBackend do
+        @behaviour ObjectStoreX.Backend
+
+        @impl true
+        def init(opts), do: {:ok, Keyword.fetch!(opts, :client)}
+
+        @impl true
+        def put(path, data, _mode, client), do: BlobClient.put(client, path, data)
+
+        @impl true
+        def get(path, client), do: BlobClient.get(client, path)
+
+        # ...
+      end
+
+      {:ok, store} = ObjectStoreX.new(:custom, backend: MyApp.BlobBackend, arg: [client: client])
+
+  In a supervision tree, start the server yourself and pass its pid:
+
+      children = [
+        {ObjectStoreX.Backend, backend: MyApp.BlobBackend, arg: opts, name: MyApp.Blobs}
+      ]
+
+      {:ok, store} = ObjectStoreX.new(:custom, server: MyApp.Blobs)
+
+  Store operations wait for the server's reply, so they must not be called
+  from within the backend callbacks of the same store. They fail with
+  `{:error, :timeout}` when no reply arrives within the store's `:timeout`,
+  and with an error as soon as the server exits.
+  """
+
+  use GenServer
+
+  alias ObjectStoreX.Native
+
+  @typedoc """
+  Object metadata returned by `c:head/2` and `c:list/2`.
+
+  `:last_modified` may be a `DateTime` or a Unix timestamp in seconds.
+  """
+  @type meta :: %{
+          required(:location) => String.t(),
+          required(:size) => non_neg_integer(),
+          optional(:last_modified) => DateTime.t() | integer() | nil,
+          optional(:etag) => String.t() | nil
+        }
+
+  @typedoc """
+  Error reasons. `:not_found`, `:already_exists`, `:precondition_failed`,
+  `:not_modified`, `:permission_denied` and `:not_supported` map to the
+  corresponding `ObjectStoreX` errors; anything else becomes `:error`.
+  """
+  @type reason :: atom() | String.t()
+
+  @callback init(arg :: term()) :: {:ok, state :: term()} | {:error, term()}
+
+  @doc "Store an object. With mode `:create`, fail with `:already_exists` if it exists."
+  @callback put(
+              path :: String.t(),
+              data :: binary(),
+              mode :: :overwrite | :create,
+              state :: term()
+            ) :: :ok | {:error, reason()}
+
+  @callback get(path :: String.t(), state :: term()) :: {:ok, binary()} | {:error, reason()}
+
+  @doc "Read bytes `start` (inclusive) to `end` (exclusive) of an object."
+  @callback get_range(
+              path :: String.t(),
+              start :: non_neg_integer(),
+              end_pos :: non_neg_integer(),
+              state :: term()
+            ) :: {:ok, binary()} | {:error, reason()}
+
+  @callback head(path :: String.t(), state :: term()) :: {:ok, meta()} | {:error, reason()}
+
+  @callback delete(path :: String.t(), state :: term()) :: :ok | {:error, reason()}
+
+  @doc "List all objects under a prefix (`nil` for all objects), recursively."
+  @callback list(prefix :: String.t() | nil, state :: term()) ::
+              {:ok, [meta()]} | {:error, reason()}
+
+  @callback copy(from :: String.t(), to :: String.t(), state :: term()) ::
+              :ok | {:error, reason()}
+
+  @callback copy_if_not_exists(from :: String.t(), to :: String.t(), state :: term()) ::
+              :ok | {:error, reason()}
+
+  @optional_callbacks get_range: 4, copy: 3, copy_if_not_exists: 3
+
+  @doc """
+  Start a backend server.
+
+  ## Options
+
+  - `:backend` - Module implementing the `ObjectStoreX.Backend` behaviour (required)
+  - `:arg` - Argument passed to `c:init/1` (default: `[]`)
+  - `:name` - Name to register the server under
+  """
+  @spec start_link(keyword()) :: GenServer.on_start()
+  def start_link(opts) do
+    backend = Keyword.fetch!(opts, :backend)
+    arg = Keyword.get(opts, :arg, [])
+
+    GenServer.start_link(__MODULE__, {backend, arg}, Keyword.take(opts, [:name]))
+  end
+
+  @impl GenServer
+  def init({backend, arg}) do
+    case backend.init(arg) do
+      {:ok, state} -> {:ok, {backend, state}}
+      {:error, reason} -> {:stop, reason}
+    end
+  end
+
+  @impl GenServer
+  def handle_info({:objectstorex_backend, call_id, request}, {backend, state} = server) do
+    Task.start(fn -> reply(call_id, backend, request, state) end)
+    {:noreply, server}
+  end
+
+  def handle_info(_message, server), do: {:noreply, server}
+
+  # The waiting call is failed by the NIF on a malformed reply; anything
+  # else raised here still answers it, so it never waits for its timeout
+  defp reply(call_id, backend, request, state) do
+    Native.backend_reply(call_id, dispatch(backend, request, state))
+  rescue
+    e -> Native.backend_reply(call_id, {:error, Exception.message(e)})
+  catch
+    kind, reason -> Native.backend_reply(call_id, {:error, Exception.format_banner(kind, reason)})
+  end
+
+  @doc false
+  def dispatch(backend, request, state) do
+    backend
+    |> call(request, state)
+    |> normalize_reply()
+  rescue
+    e -> {:error, Exception.message(e)}
+  catch
+    kind, reason -> {:error, Exception.format_banner(kind, reason)}
+  end
+
+  defp call(backend, {:put, path, data, mode}, state), do: backend.put(path, data, mode, state)
+  defp call(backend, {:get, path}, state), do: backend.get(path, state)
+
+  defp call(backend, {:get_range, path, start, end_pos}, state) do
+    if function_exported?(backend, :get_range, 4) do
+      backend.get_range(path, start, end_pos, state)
+    else
+      with {:ok, data} <- backend.get(path, state) do
+        start = min(start, byte_size(data))
+        {:ok, binary_part(data, start, min(end_pos, byte_size(data)) - start)}
+      end
+    end
+  end
+
+  defp call(backend, {:head, path}, state), do: backend.head(path, state)
+  defp call(backend, {:delete, path}, state), do: backend.delete(path, state)
+  defp call(backend, {:list, prefix}, state), do: backend.list(prefix, state)
+
+  defp call(backend, {:copy, from, to}, state) do
+    if function_exported?(backend, :copy, 3) do
+      backend.copy(from, to, state)
+    else
+      with {:ok, data} <- backend.get(from, state) do
+        backend.put(to, data, :overwrite, state)
+      end
+    end
+  end
+
+  defp call(backend, {:copy_if_not_exists, from, to}, state) do
+    if function_exported?(backend, :copy_if_not_exists, 3) do
+      backend.copy_if_not_exists(from, to, state)
+    else
+      with {:ok, data} <- backend.get(from, state) do
+        backend.put(to, data, :create, state)
+      end
+    end
+  end
+
+  defp normalize_reply({:ok, metas}) when is_list(metas), do: {:ok, Enum.map(metas, &meta/1)}
+  defp normalize_reply({:ok, %{} = meta}), do: {:ok, meta(meta)}
+  defp normalize_reply({:error, reason}) when is_atom(reason) or is_binary(reason),
+    do: {:error, reason}
+
+  defp normalize_reply({:error, reason}), do: {:error, inspect(reason)}
+  defp normalize_reply(reply), do: reply
+
+  defp meta(meta) do
+    %{
+      location: Map.fetch!(meta, :location),
+      size: Map.fetch!(meta, :size),
+      last_modified: timestamp(Map.get(meta, :last_modified)),
+      etag: Map.get(meta, :etag)
+    }
+  end
+
+  defp timestamp(%DateTime{} = datetime), do: DateTime.to_unix(datetime)
+  defp timestamp(timestamp), do: timestamp
+end
@@ -259,7 +259,9 @@ defmodule ObjectStoreX.Downloader do
 
       {:error, reason} ->
         if attempt < max_retries do
-          delay = (:math.pow(2, attempt) * 1000) |> round()
+          delay =
+            ObjectStoreX.Error.retry_after(reason) || (:math.pow(2, attempt) * 1000) |> round()
+
           Process.sleep(delay)
 
           download_chunk_with_retry(
@@ -18,7 +18,20 @@ defmodule ObjectStoreX.Error do
   - `:not_modified` - Object not modified (conditional GET)
   - `:permission_denied` - Insufficient permissions
   - `:not_supported` - Operation not supported by provider
+  - `:quota_exceeded` - Write would exceed a derived store's byte quota
+  - `:checksum_mismatch` - Data size or checksum doesn't match the expected value
+  - `:integrity_error` - Downloaded data doesn't match its ETag or expected checksum
+  - `:invalid_json` - Object isn't a valid JSON document
+  - `:key_too_long` - Object key exceeds the provider's maximum key length
+  - `:metadata_too_large` - User metadata exceeds the provider's size limit
+  - `:too_many_tags` - More tags than the provider allows per object
+  - `:tag_too_long` - A tag key or value exceeds the provider's length limit
+  - `:too_many_parts` - Multipart upload exceeds the provider's part count
+  - `:part_too_small` - A non-final part is below the provider's minimum part size
+  - `:throttled` - The provider asks for requests to slow down (HTTP 429, `SlowDown`)
   - `:timeout` - Operation timed out
+  - `:server_error` - The provider failed to handle the request (HTTP 5xx)
+  - `:cancelled` - The operation was cancelled with `ObjectStoreX.cancel_op/1`
   - `:network_error` - Network/connection error
   - `:invalid_input` - Invalid parameters
   - `{:unknown, message}` - Unknown error with details
@@ -34,6 +47,33 @@ defmodule ObjectStoreX.Error do
         message: "Access Denied"
       }}}
 
+  ## Error Details
+
+  Errors that don't map to a more specific reason, `:throttled`, `:timeout`,
+  `:server_error` and `:error`, come with details of what failed:
+
+      {:error, {:throttled, %{
+        status: 503,
+        code: "SlowDown",
+        retry_after_ms: 2000,
+        message: "Generic S3 error: Request failed with status 503: ...",
+        sources: ["Request failed with status 503: ..."]
+      }}}
+
+  - `:status` - HTTP status code of the response
+  - `:code` - Provider error code, such as `"SlowDown"`, `"RequestTimeout"` or
+    `"rateLimitExceeded"`
+  - `:retry_after_ms` - The response's `Retry-After` hint in milliseconds
+  - `:message` - The error message
+  - `:sources` - Messages of the errors that caused it, outermost first, such
+    as the panic of a failed background task
+
+  The HTTP fields are `nil` when unknown. The object_store clients don't expose
+  response headers, so `:retry_after_ms` is only set for requests the library
+  sends itself, such as conditional copies and object tagging. Use
+  `retry_after/1` to read it. Include `:message` and `:sources` when reporting
+  unexpected errors.
+
   ## Examples
 
       # Simple error handling
@@ -66,7 +106,20 @@ defmodule ObjectStoreX.Error do
           | :not_modified
           | :permission_denied
           | :not_supported
+          | :quota_exceeded
+          | :checksum_mismatch
+          | :integrity_error
+          | :invalid_json
+          | :key_too_long
+          | :metadata_too_large
+          | :too_many_tags
+          | :tag_too_long
+          | :too_many_parts
+          | :part_too_small
+          | :throttled
           | :timeout
+          | :server_error
+          | :cancelled
           | :network_error
           | :invalid_input
           | {:unknown, String.t()}
@@ -75,7 +128,11 @@ defmodule ObjectStoreX.Error do
           optional(:operation) => atom(),
           optional(:path) => String.t(),
           optional(:provider) => atom(),
-          optional(:message) => String.t()
+          optional(:message) => String.t(),
+          optional(:status) => non_neg_integer() | nil,
+          optional(:code) => String.t() | nil,
+          optional(:retry_after_ms) => non_neg_integer() | nil,
+          optional(:sources) => [String.t()]
         }
 
   @type detailed_error :: {error_reason(), error_context()}
@@ -106,7 +163,20 @@ defmodule ObjectStoreX.Error do
   def format_error(:not_modified), do: "Object not modified"
   def format_error(:permission_denied), do: "Permission denied"
   def format_error(:not_supported), do: "Operation not supported by this provider"
+  def format_error(:quota_exceeded), do: "Store quota exceeded"
+  def format_error(:checksum_mismatch), do: "Size or checksum mismatch"
+  def format_error(:integrity_error), do: "Downloaded data failed verification"
+  def format_error(:invalid_json), do: "Object is not valid JSON"
+  def format_error(:key_too_long), do: "Object key too long for this provider"
+  def format_error(:metadata_too_large), do: "Metadata too large for this provider"
+  def format_error(:too_many_tags), do: "Too many tags for this provider"
+  def format_error(:tag_too_long), do: "Tag too long for this provider"
+  def format_error(:too_many_parts), do: "Too many upload parts for this provider"
+  def format_error(:part_too_small), do: "Upload part below this provider's minimum size"
+  def format_error(:throttled), do: "Request throttled by the provider"
   def format_error(:timeout), do: "Operation timed out"
+  def format_error(:server_error), do: "Provider server error"
+  def format_error(:cancelled), do: "Operation cancelled"
   def format_error(:network_error), do: "Network error"
   def format_error(:invalid_input), do: "Invalid input parameters"
   def format_error({:unknown, msg}), do: "Unknown error: #{msg}"
@@ -126,16 +196,25 @@ defmodule ObjectStoreX.Error do
   Non-retryable errors are permanent and will not succeed on retry.
 
   ## Retryable Errors
+  - `:throttled` - Succeeds once requests slow down, see `retry_after/1`
   - `:timeout` - Operation may succeed on retry
+  - `:server_error` - The provider may recover
   - `:network_error` - Network may recover
   - `:precondition_failed` - For CAS retry with new ETag
+  - `:integrity_error` - Data corrupted in transit is downloaded again
 
   ## Non-Retryable Errors
   - `:not_found` - Object doesn't exist, retrying won't help
   - `:already_exists` - Object exists, retrying won't change that
   - `:permission_denied` - Credentials issue, won't fix on retry
   - `:not_supported` - Feature not supported, will never work
+  - `:quota_exceeded` - Quota is full until objects are deleted
+  - `:checksum_mismatch` - The stored data itself doesn't match
+  - `:invalid_json` - The stored document itself is malformed
+  - Provider limit violations (`:key_too_long`, `:too_many_parts`, ...) - The
+    request itself is outside the provider's limits
   - `:invalid_input` - Bad parameters, won't change on retry
+  - `:cancelled` - The caller asked for the operation to stop
 
   ## Examples
 
@@ -158,10 +237,13 @@ defmodule ObjectStoreX.Error do
       true
   """
   @spec retryable?(error_reason() | detailed_error()) :: boolean()
+  def retryable?(:throttled), do: true
   def retryable?(:timeout), do: true
+  def retryable?(:server_error), do: true
   def retryable?(:network_error), do: true
   # For CAS retry
   def retryable?(:precondition_failed), do: true
+  def retryable?(:integrity_error), do: true
 
   # Non-retryable errors
   def retryable?(:not_found), do: false
@@ -169,7 +251,17 @@ defmodule ObjectStoreX.Error do
   def retryable?(:not_modified), do: false
   def retryable?(:permission_denied), do: false
   def retryable?(:not_supported), do: false
+  def retryable?(:quota_exceeded), do: false
+  def retryable?(:checksum_mismatch), do: false
+  def retryable?(:invalid_json), do: false
+  def retryable?(:key_too_long), do: false
+  def retryable?(:metadata_too_large), do: false
+  def retryable?(:too_many_tags), do: false
+  def retryable?(:tag_too_long), do: false
+  def retryable?(:too_many_parts), do: false
+  def retryable?(:part_too_small), do: false
   def retryable?(:invalid_input), do: false
+  def retryable?(:cancelled), do: false
   def retryable?({:unknown, _}), do: false
 
   # Handle detailed errors with context
@@ -180,6 +272,25 @@ defmodule ObjectStoreX.Error do
   # Unknown errors are not retryable
   def retryable?(_), do: false
 
+  @doc """
+  Returns the provider's `Retry-After` hint of an error in milliseconds, or
+  `nil` if it gave none.
+
+  ## Examples
+
+      iex> ObjectStoreX.Error.retry_after({:throttled, %{status: 429, retry_after_ms: 2000}})
+      2000
+
+      iex> ObjectStoreX.Error.retry_after({:server_error, %{status: 500, retry_after_ms: nil}})
+      nil
+
+      iex> ObjectStoreX.Error.retry_after(:not_found)
+      nil
+  """
+  @spec retry_after(error_reason() | detailed_error() | any()) :: non_neg_integer() | nil
+  def retry_after({_reason, %{retry_after_ms: ms}}) when is_integer(ms), do: ms
+  def retry_after(_), do: nil
+
   @doc """
   Creates an error with context information.
 
@@ -237,7 +348,20 @@ defmodule ObjectStoreX.Error do
   def map_error(:not_modified), do: :not_modified
   def map_error(:permission_denied), do: :permission_denied
   def map_error(:not_supported), do: :not_supported
+  def map_error(:quota_exceeded), do: :quota_exceeded
+  def map_error(:checksum_mismatch), do: :checksum_mismatch
+  def map_error(:integrity_error), do: :integrity_error
+  def map_error(:invalid_json), do: :invalid_json
+  def map_error(:key_too_long), do: :key_too_long
+  def map_error(:metadata_too_large), do: :metadata_too_large
+  def map_error(:too_many_tags), do: :too_many_tags
+  def map_error(:tag_too_long), do: :tag_too_long
+  def map_error(:too_many_parts), do: :too_many_parts
+  def map_error(:part_too_small), do: :part_too_small
+  def map_error(:throttled), do: :throttled
   def map_error(:timeout), do: :timeout
+  def map_error(:server_error), do: :server_error
+  def map_error(:cancelled), do: :cancelled
   def map_error(:network_error), do: :network_error
   def map_error(:invalid_input), do: :invalid_input
 
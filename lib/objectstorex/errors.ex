@@ -15,6 +15,16 @@ defmodule ObjectStoreX.Errors do
   - `:not_modified` - Object was not modified (used for caching/conditional requests)
   - `:not_supported` - Operation is not supported by this storage provider
   - `:permission_denied` - Insufficient permissions to perform the operation
+  - `:quota_exceeded` - Write would exceed the byte quota of a derived store
+  - `:throttled` - The provider asks for requests to slow down
+  - `:timeout` - The request timed out
+  - `:server_error` - The provider failed to handle the request
+  - `:cancelled` - The operation was cancelled through its `:op_ref`
+
+  `:throttled`, `:timeout`, `:server_error` and `:error` are returned as
+  `{reason, details}`, with the HTTP `:status`, provider error `:code` and
+  `:retry_after_ms` hint of the failed response, and the error's `:message`
+  and `:sources`, see `ObjectStoreX.Error`.
 
   ## Error Descriptions
 
@@ -84,15 +94,53 @@ defmodule ObjectStoreX.Errors do
   **Example:**
       {:error, :permission_denied} = ObjectStoreX.put(store, "protected/file.txt", data)
 
+  ### `:quota_exceeded`
+  Returned when a write through a store derived with a `:quota` would exceed it.
+  Nothing is sent to the provider.
+
+  **Example:**
+      {:ok, tenant} = ObjectStoreX.derive(store, prefix: "tenant", quota: 1024)
+      {:error, :quota_exceeded} = ObjectStoreX.put(tenant, "big.bin", large_data)
+
+  ### `:throttled`
+  Returned when the provider rejects a request because of its request rate:
+  HTTP 429, or error codes such as S3's `SlowDown` and GCS's `rateLimitExceeded`.
+
+  **Example:**
+      case ObjectStoreX.put(store, path, data) do
+        {:error, {:throttled, %{retry_after_ms: ms}}} when is_integer(ms) ->
+          Process.sleep(ms)
+          ObjectStoreX.put(store, path, data)
+
+        result ->
+          result
+      end
+
+  ### `:timeout`
+  Returned for HTTP 408 and 504 responses, `RequestTimeout` error codes,
+  requests that time out on the client and operations running past their
+  `:timeout_ms`.
+
+  ### `:cancelled`
+  Returned when the `:op_ref` of an operation is cancelled with
+  `ObjectStoreX.cancel_op/1`.
+
+  ### `:server_error`
+  Returned for other HTTP 5xx responses, after the client's own retries.
+
   ### `:error`
-  Generic error atom returned for unexpected errors that don't fit other categories.
+  Generic error returned for unexpected errors that don't fit other categories,
+  as `{:error, details}`. Its `:message` and `:sources` describe what failed.
 
   **Common causes:**
-  - Network errors (connection timeouts, DNS failures)
+  - Network errors (connection failures, DNS failures)
   - Malformed requests
-  - Internal server errors (500s)
   - Serialization/deserialization failures
 
+  **Example:**
+      {:error, {:error, %{message: message, sources: sources}}} =
+        ObjectStoreX.join(store, "invalid-manifest.json", "joined.bin")
+
   ## Error Handling Patterns
 
   ### Pattern Matching
@@ -141,6 +189,11 @@ defmodule ObjectStoreX.Errors do
           | :not_modified
           | :not_supported
           | :permission_denied
+          | :quota_exceeded
+          | :throttled
+          | :timeout
+          | :server_error
+          | :cancelled
 
   @doc """
   Returns a human-readable description of an error atom.
@@ -168,5 +221,11 @@ defmodule ObjectStoreX.Errors do
   def describe(:permission_denied),
     do: "Insufficient permissions to perform the operation"
 
+  def describe(:quota_exceeded), do: "Write would exceed the store's byte quota"
+  def describe(:throttled), do: "The provider asks for requests to slow down"
+  def describe(:timeout), do: "The request timed out"
+  def describe(:server_error), do: "The provider failed to handle the request"
+  def describe(:cancelled), do: "The operation was cancelled"
+
   def describe(other), do: "Unknown error: #{inspect(other)}"
 end
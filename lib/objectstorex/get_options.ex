@@ -9,11 +9,13 @@ defmodule ObjectStoreX.GetOptions do
 
   * `:if_match` - Only return if ETag matches (HTTP If-Match header)
   * `:if_none_match` - Only return if ETag differs (HTTP If-None-Match header)
-  * `:if_modified_since` - Only return if modified after date (Unix timestamp)
-  * `:if_unmodified_since` - Only return if not modified since date (Unix timestamp)
+  * `:if_modified_since` - Only return if modified after date (see `t:timestamp/0`)
+  * `:if_unmodified_since` - Only return if not modified since date (see `t:timestamp/0`)
   * `:range` - Byte range to fetch (see `ObjectStoreX.Range`)
   * `:version` - Specific object version (provider-specific)
   * `:head` - Return metadata only, no content (boolean)
+  * `:verify` - Check the data against the object's ETag (`:etag`) or a hex
+    digest (`{:md5 | :sha256 | :crc32c, digest}`); whole-object reads only
 
   ## Examples
 
@@ -40,14 +42,23 @@ defmodule ObjectStoreX.GetOptions do
       }
   """
 
+  @typedoc """
+  A point in time: Unix seconds, `{value, :second | :millisecond}`, or an
+  RFC 3339 (ISO 8601) string with an offset, like `"2025-01-01T00:00:00.250Z"`.
+
+  Invalid or out of range timestamps are rejected with an error.
+  """
+  @type timestamp :: integer() | {integer(), :second | :millisecond} | String.t()
+
   @type t :: %__MODULE__{
           if_match: String.t() | nil,
           if_none_match: String.t() | nil,
-          if_modified_since: integer() | nil,
-          if_unmodified_since: integer() | nil,
+          if_modified_since: timestamp() | nil,
+          if_unmodified_since: timestamp() | nil,
           range: ObjectStoreX.Range.t() | nil,
           version: String.t() | nil,
-          head: boolean()
+          head: boolean(),
+          verify: :etag | {:md5 | :sha256 | :crc32c, String.t()} | nil
         }
 
   defstruct [
@@ -57,6 +68,7 @@ defmodule ObjectStoreX.GetOptions do
     :if_unmodified_since,
     :range,
     :version,
+    :verify,
     head: false
   ]
 
@@ -73,7 +85,8 @@ defmodule ObjectStoreX.GetOptions do
         if_unmodified_since: nil,
         range: nil,
         version: nil,
-        head: false
+        head: false,
+        verify: nil
       }
   """
   @spec new() :: t()
@@ -24,16 +24,86 @@ defmodule ObjectStoreX.Native do
     ),
     nif_versions: ["2.15"],
     mode: mode,
+    load_data_fun: {ObjectStoreX.NativeConfig, :load_data},
     force_build: System.get_env("OBJECTSTOREX_BUILD") in ["1", "true"]
 
   # Provider builders
   def new_s3(_bucket, _region, _access_key_id, _secret_access_key, _endpoint),
     do: :erlang.nif_error(:nif_not_loaded)
 
+  def new_s3_with_options(
+        _bucket,
+        _region,
+        _access_key_id,
+        _secret_access_key,
+        _endpoint,
+        _options
+      ),
+      do: :erlang.nif_error(:nif_not_loaded)
+
   def new_azure(_account, _container, _access_key), do: :erlang.nif_error(:nif_not_loaded)
+
+  def new_azure_with_token(_account, _container, _token, _token_server),
+    do: :erlang.nif_error(:nif_not_loaded)
+
   def new_gcs(_bucket, _service_account_key), do: :erlang.nif_error(:nif_not_loaded)
+  def new_gcs_with_token(_bucket, _token, _token_server), do: :erlang.nif_error(:nif_not_loaded)
+  def token_reply(_call_id, _reply), do: :erlang.nif_error(:nif_not_loaded)
+  def new_http(_url, _headers), do: :erlang.nif_error(:nif_not_loaded)
   def new_local(_path), do: :erlang.nif_error(:nif_not_loaded)
+  def new_local_with_options(_path, _options), do: :erlang.nif_error(:nif_not_loaded)
   def new_memory, do: :erlang.nif_error(:nif_not_loaded)
+  def new_memory_with_options(_name, _objects), do: :erlang.nif_error(:nif_not_loaded)
+  def new_backend(_pid, _timeout_ms), do: :erlang.nif_error(:nif_not_loaded)
+  def backend_reply(_call_id, _reply), do: :erlang.nif_error(:nif_not_loaded)
+
+  # Listing snapshots
+  def snapshot_listing(_store, _prefix), do: :erlang.nif_error(:nif_not_loaded)
+  def snapshot_count(_snapshot), do: :erlang.nif_error(:nif_not_loaded)
+  def snapshot_query(_snapshot, _query), do: :erlang.nif_error(:nif_not_loaded)
+  def snapshot_aggregate(_snapshot, _query), do: :erlang.nif_error(:nif_not_loaded)
+
+  # Listing reports
+  def aggregate(_store, _prefix, _group_by, _bucket), do: :erlang.nif_error(:nif_not_loaded)
+  def top_n(_store, _prefix, _by, _n), do: :erlang.nif_error(:nif_not_loaded)
+  def stat_prefix(_store, _prefix), do: :erlang.nif_error(:nif_not_loaded)
+  def list_modified_since(_store, _prefix, _since), do: :erlang.nif_error(:nif_not_loaded)
+  def sample(_store, _prefix, _n, _bytes_per_object), do: :erlang.nif_error(:nif_not_loaded)
+
+  # Benchmarks
+  def bench(_store, _scenario, _opts), do: :erlang.nif_error(:nif_not_loaded)
+
+  # Store wrappers
+  def derive(_store, _prefix, _read_only, _quota), do: :erlang.nif_error(:nif_not_loaded)
+  def with_throttle(_store, _config), do: :erlang.nif_error(:nif_not_loaded)
+  def with_concurrency_limit(_store, _max_requests), do: :erlang.nif_error(:nif_not_loaded)
+  def with_defaults(_store, _attributes, _tags), do: :erlang.nif_error(:nif_not_loaded)
+  def with_compression(_store, _codec, _level), do: :erlang.nif_error(:nif_not_loaded)
+  def with_encryption(_store, _keys), do: :erlang.nif_error(:nif_not_loaded)
+  def with_cache(_remote, _local, _options), do: :erlang.nif_error(:nif_not_loaded)
+  def flush_cache(_store), do: :erlang.nif_error(:nif_not_loaded)
+  def new_router(_routes), do: :erlang.nif_error(:nif_not_loaded)
+  def with_mirror(_primary, _replicas, _consistency), do: :erlang.nif_error(:nif_not_loaded)
+  def flush_mirror(_store), do: :erlang.nif_error(:nif_not_loaded)
+  def with_fallback(_primary, _fallbacks), do: :erlang.nif_error(:nif_not_loaded)
+
+  def with_rate_limit(_store, _ops_per_sec, _bytes_per_sec),
+    do: :erlang.nif_error(:nif_not_loaded)
+  def with_tracing(_store, _logger), do: :erlang.nif_error(:nif_not_loaded)
+  def with_deadline(_store, _timeout_ms, _op_ref), do: :erlang.nif_error(:nif_not_loaded)
+  def new_op_ref, do: :erlang.nif_error(:nif_not_loaded)
+  def cancel_op(_op_ref), do: :erlang.nif_error(:nif_not_loaded)
+  def with_instrumentation(_store, _pid), do: :erlang.nif_error(:nif_not_loaded)
+  def get_metrics, do: :erlang.nif_error(:nif_not_loaded)
+  def reset_metrics, do: :erlang.nif_error(:nif_not_loaded)
+  def list_inflight_operations, do: :erlang.nif_error(:nif_not_loaded)
+  def shutdown(_timeout_ms, _keep_resumable), do: :erlang.nif_error(:nif_not_loaded)
+
+  # Credential profiles
+  def register_profile(_store, _name, _profile), do: :erlang.nif_error(:nif_not_loaded)
+  def remove_profile(_store, _name), do: :erlang.nif_error(:nif_not_loaded)
+  def list_profiles(_store), do: :erlang.nif_error(:nif_not_loaded)
+  def select_profile(_store, _name), do: :erlang.nif_error(:nif_not_loaded)
 
   # Operations
   def put(_store, _path, _data), do: :erlang.nif_error(:nif_not_loaded)
@@ -42,14 +112,149 @@ defmodule ObjectStoreX.Native do
   def put_with_attributes(_store, _path, _data, _mode, _attributes, _tags),
     do: :erlang.nif_error(:nif_not_loaded)
 
+  def put_with_options(_store, _path, _data, _mode, _attributes, _tags, _guards),
+    do: :erlang.nif_error(:nif_not_loaded)
+
   def get(_store, _path), do: :erlang.nif_error(:nif_not_loaded)
   def get_with_options(_store, _path, _options), do: :erlang.nif_error(:nif_not_loaded)
+  def get_with_metadata(_store, _path), do: :erlang.nif_error(:nif_not_loaded)
   def delete(_store, _path), do: :erlang.nif_error(:nif_not_loaded)
+  def delete_with_options(_store, _path, _options), do: :erlang.nif_error(:nif_not_loaded)
   def head(_store, _path), do: :erlang.nif_error(:nif_not_loaded)
   def copy(_store, _from, _to), do: :erlang.nif_error(:nif_not_loaded)
   def rename(_store, _from, _to), do: :erlang.nif_error(:nif_not_loaded)
   def copy_if_not_exists(_store, _from, _to), do: :erlang.nif_error(:nif_not_loaded)
   def rename_if_not_exists(_store, _from, _to), do: :erlang.nif_error(:nif_not_loaded)
+
+  def act_if_unchanged(_store, _path, _etag, _action, _to),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  def compare_and_swap(_store, _path, _expected_etag, _new_data, _max_retries),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  # JSON documents
+  def get_json(_store, _path), do: :erlang.nif_error(:nif_not_loaded)
+  def put_json(_store, _path, _value, _mode), do: :erlang.nif_error(:nif_not_loaded)
+
+  def merge_json(_store, _path, _patch, _cas, _max_attempts),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  # Streaming puts with put options
+  def start_put_stream(_store, _path, _mode, _attributes, _tags),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  def put_stream_write(_stream, _data), do: :erlang.nif_error(:nif_not_loaded)
+  def put_stream_finish(_stream), do: :erlang.nif_error(:nif_not_loaded)
+  def put_stream_abort(_stream), do: :erlang.nif_error(:nif_not_loaded)
+
+  # Buffered writers
+  def open_writer(_store, _path, _capacity, _max_concurrency, _attributes, _tags),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  def writer_write(_writer, _data), do: :erlang.nif_error(:nif_not_loaded)
+  def writer_flush(_writer), do: :erlang.nif_error(:nif_not_loaded)
+  def writer_close(_writer), do: :erlang.nif_error(:nif_not_loaded)
+  def writer_abort(_writer), do: :erlang.nif_error(:nif_not_loaded)
+
+  # Buffered readers
+  def open_reader(_store, _path, _capacity), do: :erlang.nif_error(:nif_not_loaded)
+  def reader_read(_reader, _length), do: :erlang.nif_error(:nif_not_loaded)
+  def reader_seek(_reader, _whence, _offset), do: :erlang.nif_error(:nif_not_loaded)
+  def reader_read_range(_reader, _offset, _length), do: :erlang.nif_error(:nif_not_loaded)
+
+  # Patching
+  def patch(_store, _path, _offset, _data, _if_match), do: :erlang.nif_error(:nif_not_loaded)
+
+  # Appending
+  def append(_store, _path, _data), do: :erlang.nif_error(:nif_not_loaded)
+
+  # Splitting
+  def split(_store, _path, _prefix, _part_size), do: :erlang.nif_error(:nif_not_loaded)
+  def join(_store, _manifest_path, _dest, _verify), do: :erlang.nif_error(:nif_not_loaded)
+
+  # Object tagging
+  def get_tags(_store, _path), do: :erlang.nif_error(:nif_not_loaded)
+  def put_tags(_store, _path, _tags), do: :erlang.nif_error(:nif_not_loaded)
+
+  # S3 Object Lock
+  def get_retention(_store, _path), do: :erlang.nif_error(:nif_not_loaded)
+
+  def put_retention(_store, _path, _retention, _bypass_governance),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  def get_legal_hold(_store, _path), do: :erlang.nif_error(:nif_not_loaded)
+  def put_legal_hold(_store, _path, _on), do: :erlang.nif_error(:nif_not_loaded)
+
+  # TTL expiry
+  def put_with_ttl(_store, _path, _data, _ttl_secs), do: :erlang.nif_error(:nif_not_loaded)
+  def expire_now(_store, _prefix), do: :erlang.nif_error(:nif_not_loaded)
+
+  # POST policies
+  def presign_post(_store, _key, _policy), do: :erlang.nif_error(:nif_not_loaded)
+
+  # Archive restores
+  def restore_object(_store, _path, _tier, _days), do: :erlang.nif_error(:nif_not_loaded)
+  def restore_status(_store, _path), do: :erlang.nif_error(:nif_not_loaded)
+
+  # Object versions
+  def list_versions(_store, _prefix), do: :erlang.nif_error(:nif_not_loaded)
+  def delete_version(_store, _path, _version), do: :erlang.nif_error(:nif_not_loaded)
+  def list_incomplete_uploads(_store, _prefix), do: :erlang.nif_error(:nif_not_loaded)
+
+  def abort_incomplete_uploads(_store, _prefix, _initiated_before),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  # Checksums
+  def verify_checksum(_store, _path, _algorithm, _expected),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  # Transfers with progress reporting
+  def put_with_progress(_store, _path, _data, _progress), do: :erlang.nif_error(:nif_not_loaded)
+  def get_with_progress(_store, _path, _progress), do: :erlang.nif_error(:nif_not_loaded)
+
+  def put_from_file(_store, _path, _file_path, _progress, _checksum),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  def get_to_file(_store, _path, _file_path, _progress, _verify),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  def get_spilling(_store, _path, _threshold, _dir, _progress, _verify),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  def copy_prefix(_store, _from, _to, _skip_existing, _max_concurrency, _progress),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  def extract_archive(_store, _path, _format, _dest, _dest_prefix),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  def archive_prefix(_store, _prefix, _dest_path, _format),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  # Content-addressed storage
+  def put_cas(_store, _prefix, _data), do: :erlang.nif_error(:nif_not_loaded)
+  def get_cas(_store, _prefix, _digest), do: :erlang.nif_error(:nif_not_loaded)
+
+  # Chunked uploads
+  def put_chunked(_store, _path, _file_path, _chunk_prefix, _min, _avg, _max),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  def get_chunked(_store, _path, _file_path), do: :erlang.nif_error(:nif_not_loaded)
+
+  # Snapshots and restores
+  def snapshot(_store, _prefix, _manifest_path), do: :erlang.nif_error(:nif_not_loaded)
+
+  def restore(_store, _manifest_path, _dest, _dest_prefix),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  def verify_prefix(_store, _manifest_path), do: :erlang.nif_error(:nif_not_loaded)
+
+  def get_many(_store, _paths, _max_concurrency), do: :erlang.nif_error(:nif_not_loaded)
+  def head_many(_store, _paths, _max_concurrency), do: :erlang.nif_error(:nif_not_loaded)
+  def copy_many(_store, _pairs, _max_concurrency), do: :erlang.nif_error(:nif_not_loaded)
+
+  def rename_many(_store, _pairs, _on_conflict, _max_concurrency),
+    do: :erlang.nif_error(:nif_not_loaded)
+
   def get_ranges(_store, _path, _ranges), do: :erlang.nif_error(:nif_not_loaded)
   def delete_many(_store, _paths), do: :erlang.nif_error(:nif_not_loaded)
 
@@ -57,13 +262,39 @@ defmodule ObjectStoreX.Native do
   def start_download_stream(_store, _path, _receiver_pid), do: :erlang.nif_error(:nif_not_loaded)
   def cancel_download_stream(_stream_id), do: :erlang.nif_error(:nif_not_loaded)
 
+  def start_credit_download_stream(_store, _path, _receiver_pid, _credit),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  def start_download_stream_with_options(_store, _path, _receiver_pid, _options),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  def grant_download_credit(_stream_id, _credit), do: :erlang.nif_error(:nif_not_loaded)
+
   # Upload streaming (multipart)
   def start_upload_session(_store, _path), do: :erlang.nif_error(:nif_not_loaded)
+  def start_resumable_upload_session(_store, _path), do: :erlang.nif_error(:nif_not_loaded)
+
+  def start_checksum_upload_session(_store, _path, _algorithm),
+    do: :erlang.nif_error(:nif_not_loaded)
+
   def upload_chunk(_session, _chunk), do: :erlang.nif_error(:nif_not_loaded)
   def complete_upload(_session), do: :erlang.nif_error(:nif_not_loaded)
   def abort_upload(_session), do: :erlang.nif_error(:nif_not_loaded)
+  def resume_upload_session(_store, _path, _state), do: :erlang.nif_error(:nif_not_loaded)
+  def upload_session_state(_session), do: :erlang.nif_error(:nif_not_loaded)
+
+  # Push-style upload streaming
+  def start_upload_stream(_store, _path, _receiver_pid, _part_size, _max_concurrency),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  def write_upload_stream(_session, _chunk), do: :erlang.nif_error(:nif_not_loaded)
+  def finish_upload_stream(_session), do: :erlang.nif_error(:nif_not_loaded)
+  def abort_upload_stream(_session), do: :erlang.nif_error(:nif_not_loaded)
 
   # List operations
-  def start_list_stream(_store, _prefix, _receiver_pid), do: :erlang.nif_error(:nif_not_loaded)
-  def list_with_delimiter(_store, _prefix), do: :erlang.nif_error(:nif_not_loaded)
+
+  def start_list_stream(_store, _prefix, _page_size, _batch_size, _filter, _receiver_pid),
+    do: :erlang.nif_error(:nif_not_loaded)
+
+  def list_with_delimiter(_store, _prefix, _page_size), do: :erlang.nif_error(:nif_not_loaded)
 end
@@ -0,0 +1,57 @@
+defmodule ObjectStoreX.NativeConfig do
+  @moduledoc false
+  # Library settings handed to the NIF when it is loaded
+
+  @runtime_keys [:worker_threads, :max_blocking_threads, :thread_name]
+
+  @doc false
+  def load_data do
+    %{max_concurrent_requests: max_concurrent_requests(), runtime: runtime()}
+  end
+
+  @doc false
+  def max_concurrent_requests do
+    case Application.get_env(:objectstorex, :max_concurrent_requests) do
+      nil ->
+        nil
+
+      max when is_integer(max) and max > 0 ->
+        max
+
+      other ->
+        raise ArgumentError,
+              ":max_concurrent_requests must be a positive integer, got: #{inspect(other)}"
+    end
+  end
+
+  @doc false
+  def runtime do
+    config = Application.get_env(:objectstorex, :runtime, [])
+
+    unless Keyword.keyword?(config) do
+      raise ArgumentError, ":runtime must be a keyword list, got: #{inspect(config)}"
+    end
+
+    case Keyword.keys(config) -- @runtime_keys do
+      [] -> Map.new(@runtime_keys, &{&1, runtime_setting(&1, Keyword.get(config, &1))})
+      unknown -> raise ArgumentError, "Unknown :runtime options: #{inspect(unknown)}"
+    end
+  end
+
+  defp runtime_setting(_key, nil), do: nil
+  defp runtime_setting(:thread_name, name) when is_binary(name) and name != "", do: name
+
+  defp runtime_setting(key, threads)
+       when key != :thread_name and is_integer(threads) and threads > 0,
+       do: threads
+
+  defp runtime_setting(:thread_name, other) do
+    raise ArgumentError,
+          ":runtime :thread_name must be a non-empty string, got: #{inspect(other)}"
+  end
+
+  defp runtime_setting(key, other) do
+    raise ArgumentError,
+          ":runtime #{inspect(key)} must be a positive integer, got: #{inspect(other)}"
+  end
+end
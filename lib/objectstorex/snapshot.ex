@@ -0,0 +1,119 @@
+defmodule ObjectStoreX.Snapshot do
+  @moduledoc """
+  Listing snapshots for repeated queries.
+
+  A snapshot lists a prefix once and keeps the result in native memory. It
+  can then be filtered, sorted, paginated and summarized any number of times
+  without listing the store again, and only the returned page is encoded into
+  Elixir terms. This suits interactive bucket browsers and reports that slice
+  the same listing several ways.
+
+  Snapshots are not refreshed; create a new one to see later changes. The
+  memory is released when the snapshot reference is garbage collected.
+
+  ## Examples
+
+      {:ok, snapshot} = ObjectStoreX.Snapshot.new(store, prefix: "logs/")
+
+      ObjectStoreX.Snapshot.count(snapshot)
+      #=> 12_345
+
+      # Largest CSV files, 20 per page
+      {:ok, total, page} =
+        ObjectStoreX.Snapshot.query(snapshot,
+          suffix: ".csv",
+          sort_by: :size,
+          order: :desc,
+          offset: 0,
+          limit: 20
+        )
+
+      {:ok, %{count: count, total_bytes: bytes}} =
+        ObjectStoreX.Snapshot.aggregate(snapshot, suffix: ".csv")
+  """
+
+  alias ObjectStoreX.Native
+  alias ObjectStoreX.SnapshotQuery
+
+  @type t :: reference()
+
+  @type summary :: %{
+          count: non_neg_integer(),
+          total_bytes: non_neg_integer(),
+          min_size: non_neg_integer() | nil,
+          max_size: non_neg_integer() | nil,
+          oldest: String.t() | nil,
+          newest: String.t() | nil
+        }
+
+  @doc """
+  List a prefix and keep the result as a snapshot.
+
+  ## Options
+
+  * `:prefix` - Only snapshot objects under this prefix (default: all objects)
+  """
+  @spec new(ObjectStoreX.store(), keyword()) :: {:ok, t()} | {:error, term()}
+  def new(store, opts \\ []) do
+    prefix = Keyword.get(opts, :prefix)
+
+    case Native.snapshot_listing(store, prefix) do
+      snapshot when is_reference(snapshot) -> {:ok, snapshot}
+      error -> {:error, error}
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Number of objects in a snapshot.
+  """
+  @spec count(t()) :: non_neg_integer()
+  def count(snapshot), do: Native.snapshot_count(snapshot)
+
+  @doc """
+  Filter, sort and paginate a snapshot.
+
+  Accepts a keyword list or `%ObjectStoreX.SnapshotQuery{}`; see
+  `ObjectStoreX.SnapshotQuery` for the available options.
+
+  Returns `{:ok, total, page}` where `total` is the number of objects matching
+  the filters and `page` the metadata maps in the requested window.
+  """
+  @spec query(t(), keyword() | SnapshotQuery.t()) ::
+          {:ok, non_neg_integer(), [ObjectStoreX.metadata()]} | {:error, term()}
+  def query(snapshot, query \\ [])
+
+  def query(snapshot, opts) when is_list(opts),
+    do: query(snapshot, SnapshotQuery.from_keyword(opts))
+
+  def query(snapshot, %SnapshotQuery{} = query) do
+    case Native.snapshot_query(snapshot, query) do
+      {total, page} when is_integer(total) -> {:ok, total, page}
+      error -> {:error, error}
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Summarize the objects matching a query's filters.
+
+  Sorting and pagination options are ignored. Returns a map with `:count`,
+  `:total_bytes`, `:min_size`, `:max_size`, `:oldest` and `:newest`.
+  """
+  @spec aggregate(t(), keyword() | SnapshotQuery.t()) :: {:ok, summary()} | {:error, term()}
+  def aggregate(snapshot, query \\ [])
+
+  def aggregate(snapshot, opts) when is_list(opts),
+    do: aggregate(snapshot, SnapshotQuery.from_keyword(opts))
+
+  def aggregate(snapshot, %SnapshotQuery{} = query) do
+    case Native.snapshot_aggregate(snapshot, query) do
+      summary when is_map(summary) -> {:ok, summary}
+      error -> {:error, error}
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+end
@@ -0,0 +1,77 @@
+defmodule ObjectStoreX.SnapshotQuery do
+  @moduledoc """
+  Query options for `ObjectStoreX.Snapshot`.
+
+  All filters are optional and combined with AND.
+
+  ## Fields
+
+  * `:prefix` - Only include locations starting with this prefix
+  * `:suffix` - Only include locations ending with this suffix (e.g. `".json"`)
+  * `:contains` - Only include locations containing this substring
+  * `:min_size` - Minimum size in bytes (inclusive)
+  * `:max_size` - Maximum size in bytes (inclusive)
+  * `:modified_after` - Only include objects modified at or after this Unix timestamp
+  * `:modified_before` - Only include objects modified before this Unix timestamp
+  * `:sort_by` - Sort by `:location`, `:size` or `:last_modified` (default: listing order)
+  * `:order` - `:asc` or `:desc` (default: `:asc`)
+  * `:offset` - Number of matching objects to skip (default: 0)
+  * `:limit` - Maximum number of objects to return (default: all)
+
+  ## Examples
+
+      %ObjectStoreX.SnapshotQuery{suffix: ".csv", sort_by: :size, order: :desc, limit: 10}
+  """
+
+  @type t :: %__MODULE__{
+          prefix: String.t() | nil,
+          suffix: String.t() | nil,
+          contains: String.t() | nil,
+          min_size: non_neg_integer() | nil,
+          max_size: non_neg_integer() | nil,
+          modified_after: integer() | nil,
+          modified_before: integer() | nil,
+          sort_by: :location | :size | :last_modified | nil,
+          order: :asc | :desc,
+          offset: non_neg_integer(),
+          limit: non_neg_integer() | nil
+        }
+
+  defstruct [
+    :prefix,
+    :suffix,
+    :contains,
+    :min_size,
+    :max_size,
+    :modified_after,
+    :modified_before,
+    :sort_by,
+    :limit,
+    order: :asc,
+    offset: 0
+  ]
+
+  @doc """
+  Create a SnapshotQuery struct from a keyword list.
+
+  `:modified_after` and `:modified_before` accept `DateTime` values or Unix
+  timestamps.
+
+  ## Examples
+
+      iex> ObjectStoreX.SnapshotQuery.from_keyword(suffix: ".json", limit: 5)
+      %ObjectStoreX.SnapshotQuery{suffix: ".json", limit: 5}
+  """
+  @spec from_keyword(keyword()) :: t()
+  def from_keyword(opts) do
+    opts =
+      opts
+      |> Keyword.update(:modified_after, nil, &to_unix/1)
+      |> Keyword.update(:modified_before, nil, &to_unix/1)
+
+    struct(__MODULE__, opts)
+  end
+
+  defp to_unix(%DateTime{} = dt), do: DateTime.to_unix(dt)
+  defp to_unix(ts), do: ts
+end
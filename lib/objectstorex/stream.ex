@@ -39,6 +39,24 @@ defmodule ObjectStoreX.Stream do
   ## Options
 
   * `:timeout` - Timeout in milliseconds for receiving each chunk (default: 30_000)
+  * `:credit` - Maximum number of chunks sent ahead of the consumer (default:
+    unlimited). The consumer grants another chunk each time it asks for the
+    next one, so a slow consumer holds back the download instead of filling
+    its mailbox.
+  * `:frame_size` - Emit chunks of exactly this many bytes, except for a
+    smaller last chunk. The data is re-chunked natively, for consumers such as
+    fixed-block hashing or chunked re-uploads that need exact frame sizes.
+  * `:decompress` - Set to `:gzip` to emit the decompressed data of a gzip
+    object. Objects of several concatenated gzip members, as appended by many
+    log shippers, are decoded as one stream. Combined with `:frame_size`, the
+    decompressed data is framed.
+  * `:if_none_match` - ETag of a cached copy. If the object still has this
+    ETag, nothing is downloaded and consuming the stream raises
+    `ObjectStoreX.Stream.NotModifiedError`.
+  * `:if_modified_since` - `DateTime` or `t:ObjectStoreX.GetOptions.timestamp/0`
+    of a cached copy. If the object hasn't changed since, the stream raises
+    `ObjectStoreX.Stream.NotModifiedError` like `:if_none_match`.
+  * `:profile` - Credential profile to use (see `ObjectStoreX.register_profile/3`)
 
   ## Examples
 
@@ -52,24 +70,199 @@ defmodule ObjectStoreX.Stream do
       # Count bytes
       total_bytes = stream |> Stream.map(&byte_size/1) |> Enum.sum()
 
+      # Decompress concatenated gzip logs into a local file
+      store
+      |> ObjectStoreX.Stream.download("logs/app.log.gz", decompress: :gzip)
+      |> Enum.into(File.stream!("app.log"))
+
+      # Hash fixed 4 MiB blocks
+      block_hashes =
+        store
+        |> ObjectStoreX.Stream.download("large-file.bin", frame_size: 4_194_304)
+        |> Enum.map(&:crypto.hash(:sha256, &1))
+
   ## Error Handling
 
   If an error occurs during streaming, the stream will raise an exception.
+
+  The native download is tied to the consuming process: if that process exits,
+  the download is cancelled automatically.
   """
   @spec download(store(), path(), keyword()) :: Enumerable.t()
   def download(store, path, opts \\ []) do
     timeout = Keyword.get(opts, :timeout, 30_000)
+    credit = Keyword.get(opts, :credit)
+    store = profile_store!(store, opts)
+
+    options = %{
+      credit: credit && max(credit, 1),
+      frame_size: Keyword.get(opts, :frame_size),
+      decompress: Keyword.get(opts, :decompress),
+      if_none_match: Keyword.get(opts, :if_none_match),
+      if_modified_since: to_timestamp(Keyword.get(opts, :if_modified_since))
+    }
 
     Stream.resource(
-      fn -> start_download(store, path) end,
-      fn stream_id -> receive_chunk(stream_id, timeout) end,
-      fn stream_id -> cleanup_download(stream_id) end
+      fn -> {start_download(store, path, options), credit && 0} end,
+      fn download -> receive_chunk(download, timeout) end,
+      fn {stream_id, _owed} -> cleanup_download(stream_id) end
     )
   end
 
+  @doc """
+  Download an object into a collectable sink with flow control.
+
+  The sink is any `Collectable`, typically a `File.Stream` from `File.stream!/3`.
+  The native downloader sends a chunk only after the previous ones have been
+  written to the sink (see the `:credit` option of `download/3`), so sinks
+  slower than the network don't buffer the object in memory.
+
+  Returns `:ok`, or `{:error, reason}` if the download fails. The sink may
+  hold partially written data after an error. With `:if_none_match` or
+  `:if_modified_since`, returns `{:error, :not_modified}` without writing to
+  the sink if the object is unchanged.
+
+  ## Options
+
+  * `:credit` - Maximum number of chunks sent ahead of the sink (default: 1)
+  * `:timeout` - Timeout in milliseconds for receiving each chunk (default: 30_000)
+  * `:if_none_match` - ETag of a cached copy (see `download/3`)
+  * `:if_modified_since` - Time of a cached copy (see `download/3`)
+  * `:profile` - Credential profile to use (see `ObjectStoreX.register_profile/3`)
+
+  Other `download/3` options are passed through.
+
+  ## Examples
+
+      :ok = ObjectStoreX.Stream.download_to(store, "large-file.bin", File.stream!("output.bin"))
+
+      # Refresh a cached file only if the object changed
+      case ObjectStoreX.Stream.download_to(store, "data.bin", File.stream!("cache/data.bin"),
+             if_none_match: cached_etag
+           ) do
+        :ok -> :refreshed
+        {:error, :not_modified} -> :cache_valid
+      end
+  """
+  @spec download_to(store(), path(), Collectable.t(), keyword()) :: :ok | {:error, term()}
+  def download_to(store, path, sink, opts \\ []) do
+    opts = Keyword.put_new(opts, :credit, 1)
+
+    store
+    |> download(path, opts)
+    |> Enum.into(sink)
+
+    :ok
+  rescue
+    ObjectStoreX.Stream.NotModifiedError -> {:error, :not_modified}
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Revalidate a cached copy and stream the object only if it changed.
+
+  Starts the download with the conditional options and waits for its first
+  message, so an unchanged object is reported as `{:error, :not_modified}`
+  before any stream is handed out. A streaming cache layer can revalidate
+  with a single request and no exception handling.
+
+  Returns `{:ok, stream}` with the object's chunks if it changed,
+  `{:error, :not_modified}` if it didn't, or `{:error, reason}` if the
+  download fails to start.
+
+  ## Options
+
+  * `:if_none_match` - ETag of the cached copy
+  * `:if_modified_since` - `DateTime` or `t:ObjectStoreX.GetOptions.timestamp/0`
+    of the cached copy
+  * `:credit` - Maximum number of chunks sent ahead of the consumer (default: 1)
+  * `:timeout` - Timeout in milliseconds for receiving each chunk (default: 30_000)
+  * `:profile` - Credential profile to use (see `ObjectStoreX.register_profile/3`)
+
+  Other `download/3` options are passed through. Errors after the first chunk
+  are raised by the stream, as with `download/3`.
+
+  ## Examples
+
+      case ObjectStoreX.Stream.download_if_changed(store, "data.bin", if_none_match: etag) do
+        {:ok, stream} -> Enum.into(stream, File.stream!("cache/data.bin"))
+        {:error, :not_modified} -> :cache_valid
+        {:error, reason} -> {:error, reason}
+      end
+  """
+  @spec download_if_changed(store(), path(), keyword()) ::
+          {:ok, Enumerable.t()} | {:error, :not_modified | term()}
+  def download_if_changed(store, path, opts \\ []) do
+    opts = Keyword.put_new(opts, :credit, 1)
+    timeout = Keyword.get(opts, :timeout, 30_000)
+    store = profile_store!(store, opts)
+
+    options = %{
+      credit: max(opts[:credit], 1),
+      frame_size: Keyword.get(opts, :frame_size),
+      decompress: Keyword.get(opts, :decompress),
+      if_none_match: Keyword.get(opts, :if_none_match),
+      if_modified_since: to_timestamp(Keyword.get(opts, :if_modified_since))
+    }
+
+    stream_id = start_download(store, path, options)
+
+    receive do
+      {:chunk, ^stream_id, data} ->
+        rest =
+          Stream.resource(
+            fn -> {stream_id, 1} end,
+            fn download -> receive_chunk(download, timeout) end,
+            fn {stream_id, _owed} -> cleanup_download(stream_id) end
+          )
+
+        {:ok, Stream.concat([data], rest)}
+
+      {:done, ^stream_id} ->
+        {:ok, []}
+
+      {:not_modified, ^stream_id} ->
+        {:error, :not_modified}
+
+      {:error, ^stream_id, reason} ->
+        {:error, reason}
+    after
+      timeout ->
+        cleanup_download(stream_id)
+        {:error, :timeout}
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  defp to_timestamp(nil), do: nil
+  defp to_timestamp(%DateTime{} = dt), do: {DateTime.to_unix(dt, :millisecond), :millisecond}
+  defp to_timestamp(ts), do: ts
+
+  # Resolve the `:profile` option, raising like other stream start failures
+  defp profile_store!(store, opts) do
+    case ObjectStoreX.resolve_profile(store, opts) do
+      {:ok, store, _opts} -> store
+      {:error, reason} -> raise "Stream failed to start: #{inspect(reason)}"
+    end
+  end
+
   # Start the download stream by calling the NIF
-  defp start_download(store, path) do
-    case Native.start_download_stream(store, path, self()) do
+  defp start_download(store, path, options) do
+    result =
+      cond do
+        options.frame_size || options.decompress || options.if_none_match ||
+            options.if_modified_since ->
+          Native.start_download_stream_with_options(store, path, self(), options)
+
+        options.credit ->
+          Native.start_credit_download_stream(store, path, self(), options.credit)
+
+        true ->
+          Native.start_download_stream(store, path, self())
+      end
+
+    case result do
       {:ok, stream_id} ->
         stream_id
 
@@ -78,16 +271,22 @@ defmodule ObjectStoreX.Stream do
     end
   end
 
-  # Receive a chunk from the stream
-  defp receive_chunk(stream_id, timeout) do
+  # Receive a chunk from the stream, first granting credit for the chunk the
+  # consumer has just finished with
+  defp receive_chunk({stream_id, owed}, timeout) do
+    if owed && owed > 0, do: Native.grant_download_credit(stream_id, owed)
+
     receive do
       {:chunk, ^stream_id, data} ->
         # Return the chunk and continue with the stream_id
-        {[data], stream_id}
+        {[data], {stream_id, owed && 1}}
 
       {:done, ^stream_id} ->
         # Stream is complete
-        {:halt, stream_id}
+        {:halt, {stream_id, owed}}
+
+      {:not_modified, ^stream_id} ->
+        raise ObjectStoreX.Stream.NotModifiedError
 
       {:error, ^stream_id, reason} ->
         # Error occurred, raise exception
@@ -114,7 +313,15 @@ defmodule ObjectStoreX.Stream do
 
   ## Options
 
-  None currently supported.
+  * `:profile` - Credential profile to use (see `ObjectStoreX.register_profile/3`)
+  * `:on_state` - Function called with the `t:upload_state/0` after each part
+    is uploaded, so it can be persisted for `:resume`
+  * `:resume` - Upload state saved by `:on_state`; continues that upload
+    instead of starting a new one
+  * `:checksum` - Compute a checksum of the data while uploading, `:md5`,
+    `:sha256` or `:crc32c`, and return `{:ok, checksum}` with its lowercase hex digest.
+    SHA-256 checksums are also sent to S3 with every part. Uploads with a
+    checksum can't be resumed, so `:on_state` is never called for them.
 
   ## Examples
 
@@ -122,29 +329,93 @@ defmodule ObjectStoreX.Stream do
       File.stream!("large-file.bin", [], 10_485_760)  # 10MB chunks
       |> ObjectStoreX.Stream.upload(store, "destination.bin")
 
+      # Upload iodata chunks without flattening them
+      rows
+      |> Stream.map(fn row -> [Enum.intersperse(row, ","), "\n"] end)
+      |> ObjectStoreX.Stream.upload(store, "export.csv")
+
       # Upload from generated data
       Stream.repeatedly(fn -> :crypto.strong_rand_bytes(1024) end)
       |> Stream.take(10_000)  # ~10MB total
       |> ObjectStoreX.Stream.upload(store, "random.dat")
 
+  ## Resuming
+
+  On S3, Azure, GCS and memory stores the upload can be resumed after the
+  uploading process crashes. Persist the state passed to `:on_state`, then
+  upload the remainder of the data starting at its `:bytes_uploaded` offset:
+
+      File.stream!("large-file.bin", [], 5_242_880)
+      |> ObjectStoreX.Stream.upload(store, "destination.bin",
+        on_state: &save_state/1
+      )
+
+      # After a crash
+      state = load_state()
+
+      File.stream!("large-file.bin", [], 5_242_880)
+      |> Stream.drop(div(state.bytes_uploaded, 5_242_880))
+      |> ObjectStoreX.Stream.upload(store, "destination.bin", resume: state)
+
+  The resumed stream must start exactly at `:bytes_uploaded`. Data written
+  after the last uploaded part is only buffered, so it isn't in the state and
+  has to be sent again. Parts always end on the boundary of a chunk of the
+  stream, so with chunks of equal size dropping whole chunks, as above, is
+  enough; streams with chunks of varying sizes must skip exactly
+  `:bytes_uploaded` bytes. Returns `{:error, :not_supported}` when resuming on
+  a store without resumable uploads (local stores and `ObjectStoreX.derive/2`
+  children).
+
   ## Error Handling
 
   If an error occurs during upload, the multipart upload will be aborted
-  automatically and an error tuple will be returned.
+  automatically and an error tuple will be returned. If the uploading process
+  crashes, the upload is aborted once its session is garbage collected, so
+  its parts aren't left behind; uploads using `:on_state` or `:resume` are
+  kept so they can be resumed.
   """
-  @spec upload(Enumerable.t(), store(), path(), keyword()) :: :ok | {:error, term()}
-  def upload(stream, store, path, _opts \\ []) do
-    case Native.start_upload_session(store, path) do
+  @spec upload(Enumerable.t(), store(), path(), keyword()) ::
+          :ok | {:ok, String.t()} | {:error, term()}
+  def upload(stream, store, path, opts \\ []) do
+    with {:ok, store, opts} <- ObjectStoreX.resolve_profile(store, opts) do
+      do_upload(stream, store, path, opts)
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @typedoc """
+  Resumable state of a multipart upload, as passed to the `:on_state` callback.
+  """
+  @type upload_state :: %{
+          upload_id: String.t(),
+          parts: [String.t()],
+          bytes_uploaded: non_neg_integer()
+        }
+
+  defp do_upload(stream, store, path, opts) do
+    on_state = Keyword.get(opts, :on_state)
+
+    session =
+      case {Keyword.get(opts, :resume), Keyword.get(opts, :checksum)} do
+        {nil, nil} when on_state != nil -> start_resumable_session(store, path)
+        {nil, nil} -> Native.start_upload_session(store, path)
+        {nil, algorithm} -> Native.start_checksum_upload_session(store, path, algorithm)
+        {state, nil} -> Native.resume_upload_session(store, path, state)
+        {_state, _algorithm} -> raise ArgumentError, ":checksum uploads can't be resumed"
+      end
+
+    case session do
       {:ok, session} ->
         try do
           # Consume the stream and upload chunks
           stream
-          |> Stream.each(fn chunk ->
+          |> Stream.transform(on_state && upload_parts(session), fn chunk, parts ->
             case Native.upload_chunk(session, chunk) do
               :ok ->
-                :ok
+                {[], notify_state(session, parts, on_state)}
 
-              {:error, reason} ->
+              reason ->
                 throw({:upload_error, reason})
             end
           end)
@@ -152,8 +423,9 @@ defmodule ObjectStoreX.Stream do
 
           # Complete the upload
           case Native.complete_upload(session) do
-            :ok -> :ok
-            {:error, reason} -> {:error, reason}
+            {:ok, _etag, _version} -> :ok
+            {:ok, _etag, _version, checksum} -> {:ok, checksum}
+            reason -> throw({:upload_error, reason})
           end
         catch
           {:upload_error, reason} ->
@@ -164,11 +436,175 @@ defmodule ObjectStoreX.Stream do
 
       {:error, reason} ->
         {:error, reason}
+
+      error ->
+        {:error, error}
+    end
+  end
+
+  # Session whose state `:on_state` can report, on stores that support one
+  defp start_resumable_session(store, path) do
+    case Native.start_resumable_upload_session(store, path) do
+      :not_supported -> Native.start_upload_session(store, path)
+      result -> result
+    end
+  end
+
+  # Number of parts uploaded so far, or nil for sessions that can't be resumed
+  defp upload_parts(session) do
+    case Native.upload_session_state(session) do
+      %{parts: parts} -> length(parts)
+      _ -> nil
+    end
+  end
+
+  # Report the session state when a chunk completed another part
+  defp notify_state(_session, parts, nil), do: parts
+  defp notify_state(_session, nil, _on_state), do: nil
+
+  defp notify_state(session, parts, on_state) do
+    state = Native.upload_session_state(session)
+    uploaded = length(state.parts)
+
+    if uploaded > parts, do: on_state.(state)
+    uploaded
+  end
+
+  @type upload_stream :: %{id: String.t(), ref: reference()}
+
+  @doc """
+  Start a push-style multipart upload.
+
+  Unlike `upload/4`, the caller pushes chunks with `write_upload_stream/2` and
+  parts are uploaded on the native async runtime, so writes don't wait for the
+  network until the upload falls behind: up to `:max_concurrency` parts upload
+  at once, and writes wait once a few more chunks are queued. Progress is
+  reported as messages to the receiver process:
+
+  * `{:part_uploaded, upload_id, part_number}` - after each part is uploaded
+  * `{:upload_done, upload_id, etag, version}` - after the upload completes
+  * `{:upload_error, upload_id, reason}` - if the upload fails or is aborted
+
+  `upload_id` is the `:id` of the returned session. If the session is garbage
+  collected without being finished (e.g. the owning process dies), the
+  multipart upload is aborted.
+
+  ## Options
+
+  * `:receiver` - Process receiving progress messages (default: `self()`)
+  * `:part_size` - Size in bytes of each uploaded part (default: 5MB)
+  * `:max_concurrency` - Maximum number of parts uploaded at once (default: 2)
+  * `:profile` - Credential profile to use (see `ObjectStoreX.register_profile/3`)
+
+  ## Examples
+
+      {:ok, upload} = ObjectStoreX.Stream.start_upload_stream(store, "video.mp4")
+
+      File.stream!("video.mp4", [], 1_048_576)
+      |> Enum.each(&ObjectStoreX.Stream.write_upload_stream(upload, &1))
+
+      {:ok, %{etag: etag}} = ObjectStoreX.Stream.finish_upload_stream(upload)
+  """
+  @spec start_upload_stream(store(), path(), keyword()) ::
+          {:ok, upload_stream()} | {:error, term()}
+  def start_upload_stream(store, path, opts \\ []) do
+    receiver = Keyword.get(opts, :receiver, self())
+    part_size = Keyword.get(opts, :part_size, 5 * 1024 * 1024)
+    max_concurrency = Keyword.get(opts, :max_concurrency, 2)
+
+    with {:ok, store, _opts} <- ObjectStoreX.resolve_profile(store, opts) do
+      case Native.start_upload_stream(store, path, receiver, part_size, max_concurrency) do
+        {:ok, id, ref} -> {:ok, %{id: id, ref: ref}}
+        error -> {:error, error}
+      end
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Queue a chunk for a push-style upload started with `start_upload_stream/3`.
+
+  Waits while the upload is behind by more than a few queued chunks, so a fast
+  producer can't buffer the whole object in memory. The wait happens in the
+  calling process, which receives an `{:upload_ready, upload_id}` message when
+  the upload catches up, so no scheduler is held meanwhile. Returns
+  `{:error, :closed}` if the upload has already finished, failed or been
+  aborted.
+  """
+  @spec write_upload_stream(upload_stream(), iodata()) :: :ok | {:error, term()}
+  def write_upload_stream(%{ref: ref} = upload, chunk) do
+    chunk = IO.iodata_to_binary(chunk)
+
+    case queue_upload_command(upload, fn -> Native.write_upload_stream(ref, chunk) end) do
+      :ok -> :ok
+      error -> {:error, error}
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  @doc """
+  Upload the remaining data of a push-style upload and complete it.
+
+  Waits for the `{:upload_done, ...}` or `{:upload_error, ...}` message, so it
+  must be called from the receiver process. `{:part_uploaded, ...}` messages
+  are left in the mailbox.
+
+  ## Options
+
+  * `:timeout` - Time in milliseconds to wait for completion (default: 60_000)
+  """
+  @spec finish_upload_stream(upload_stream(), keyword()) ::
+          {:ok, ObjectStoreX.put_result()} | {:error, term()}
+  def finish_upload_stream(%{id: id, ref: ref} = upload, opts \\ []) do
+    timeout = Keyword.get(opts, :timeout, 60_000)
+
+    case queue_upload_command(upload, fn -> Native.finish_upload_stream(ref) end) do
+      :ok -> await_upload_stream(id, timeout)
+      :closed -> await_upload_stream(id, 0)
+      error -> {:error, error}
     end
   rescue
     e -> {:error, Exception.message(e)}
   end
 
+  @doc """
+  Abort a push-style upload, discarding all uploaded parts.
+  """
+  @spec abort_upload_stream(upload_stream()) :: :ok | {:error, term()}
+  def abort_upload_stream(%{ref: ref}) do
+    case Native.abort_upload_stream(ref) do
+      :ok -> :ok
+      error -> {:error, error}
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  end
+
+  # Retry a write or finish turned away while the upload's queue is full once
+  # the native side reports room for it
+  defp queue_upload_command(%{id: id} = upload, command) do
+    case command.() do
+      :busy ->
+        receive do
+          {:upload_ready, ^id} -> queue_upload_command(upload, command)
+        end
+
+      result ->
+        result
+    end
+  end
+
+  defp await_upload_stream(id, timeout) do
+    receive do
+      {:upload_done, ^id, etag, version} -> {:ok, %{etag: etag, version: version}}
+      {:upload_error, ^id, reason} -> {:error, reason}
+    after
+      timeout -> {:error, :timeout}
+    end
+  end
+
   @doc """
   List objects as a stream with automatic pagination.
 
@@ -180,6 +616,33 @@ defmodule ObjectStoreX.Stream do
 
   * `:prefix` - Optional prefix to filter objects (default: nil, lists all objects)
   * `:timeout` - Timeout in milliseconds for receiving each object (default: 30_000)
+  * `:page_size` - Keys requested per listing request (S3 `max-keys`, GCS
+    `maxResults`). Smaller pages hold less in memory at once; larger pages need
+    fewer requests, up to the provider's own limit (1000 on S3 and GCS). Other
+    stores ignore it (default: the provider's default)
+  * `:batch_size` - Deliver objects from the native listing in messages of up to
+    this many, instead of one message per object. Large listings then cost far
+    fewer messages; the stream still yields one map per object (default: nil)
+  * `:profile` - Credential profile to use (see `ObjectStoreX.register_profile/3`)
+
+  ## Filtering
+
+  These options drop objects natively, before they are sent to the stream, so
+  a listing of millions of keys doesn't have to cross into Elixir to be
+  discarded. They are combined with AND:
+
+  * `:glob` - Glob the whole location must match. `*` and `?` match within a
+    path segment, `**` matches across segments (`"logs/**/*.gz"`)
+  * `:regex` - Regular expression the location must contain a match of, as a
+    string in Rust `regex` syntax (close to PCRE, without lookaround or
+    backreferences)
+  * `:suffix` - Location suffix (e.g. `".json"`)
+  * `:min_size` / `:max_size` - Size bounds in bytes (inclusive)
+  * `:modified_after` - Modified at or after this `DateTime` or Unix timestamp
+  * `:modified_before` - Modified before this `DateTime` or Unix timestamp
+
+  Listing requests still cover the whole prefix; use `:prefix` to narrow what
+  the provider lists.
 
   ## Examples
 
@@ -188,14 +651,18 @@ defmodule ObjectStoreX.Stream do
       |> Stream.map(& &1.location)
       |> Enum.take(100)
 
-      # Filter by size
-      ObjectStoreX.Stream.list_stream(store, prefix: "logs/")
-      |> Stream.filter(fn meta -> meta.size > 1_000_000 end)
+      # Large compressed logs from the last day, filtered natively
+      ObjectStoreX.Stream.list_stream(store,
+        prefix: "logs/",
+        glob: "logs/**/*.gz",
+        min_size: 1_000_000,
+        modified_after: DateTime.add(DateTime.utc_now(), -1, :day)
+      )
       |> Stream.map(& &1.location)
       |> Enum.to_list()
 
-      # Process in batches
-      ObjectStoreX.Stream.list_stream(store)
+      # Process in batches, received in batches of 1000
+      ObjectStoreX.Stream.list_stream(store, batch_size: 1000)
       |> Stream.chunk_every(100)
       |> Stream.each(&process_batch/1)
       |> Stream.run()
@@ -207,28 +674,60 @@ defmodule ObjectStoreX.Stream do
   * `:location` - String path of the object
   * `:size` - Size in bytes
   * `:last_modified` - ISO8601 timestamp string
+  * `:last_modified_us` - The same time in Unix microseconds, see
+    `DateTime.from_unix/2`
   * `:etag` - Optional ETag string
   * `:version` - Optional version string
 
   ## Error Handling
 
   If an error occurs during listing, the stream will raise an exception.
+
+  The native listing is tied to the consuming process: if that process exits,
+  the listing is cancelled automatically.
   """
   @spec list_stream(store(), keyword()) :: Enumerable.t()
   def list_stream(store, opts \\ []) do
     prefix = Keyword.get(opts, :prefix)
     timeout = Keyword.get(opts, :timeout, 30_000)
+    page_size = Keyword.get(opts, :page_size)
+    batch_size = Keyword.get(opts, :batch_size)
+    filter = list_filter(opts)
+    store = profile_store!(store, opts)
 
     Stream.resource(
-      fn -> start_list(store, prefix) end,
+      fn -> start_list(store, prefix, page_size, batch_size, filter) end,
       fn list_id -> receive_object(list_id, timeout) end,
       fn _list_id -> :ok end
     )
   end
 
   # Start the list stream by calling the NIF
-  defp start_list(store, prefix) do
-    case Native.start_list_stream(store, prefix, self()) do
+  @list_filter_keys [
+    :glob,
+    :regex,
+    :suffix,
+    :min_size,
+    :max_size,
+    :modified_after,
+    :modified_before
+  ]
+
+  # Native filter map, or nil when no filter option is given
+  defp list_filter(opts) do
+    if Enum.any?(@list_filter_keys, &Keyword.has_key?(opts, &1)) do
+      @list_filter_keys
+      |> Map.new(&{&1, Keyword.get(opts, &1)})
+      |> Map.update!(:modified_after, &to_unix/1)
+      |> Map.update!(:modified_before, &to_unix/1)
+    end
+  end
+
+  defp to_unix(%DateTime{} = dt), do: DateTime.to_unix(dt)
+  defp to_unix(ts), do: ts
+
+  defp start_list(store, prefix, page_size, batch_size, filter) do
+    case Native.start_list_stream(store, prefix, page_size, batch_size, filter, self()) do
       {:ok, list_id} ->
         list_id
 
@@ -244,6 +743,9 @@ defmodule ObjectStoreX.Stream do
         # Return the metadata and continue with the list_id
         {[meta], list_id}
 
+      {:objects, ^list_id, metas} ->
+        {metas, list_id}
+
       {:done, ^list_id} ->
         # Stream is complete
         {:halt, list_id}
@@ -257,3 +759,12 @@ defmodule ObjectStoreX.Stream do
     end
   end
 end
+
+defmodule ObjectStoreX.Stream.NotModifiedError do
+  @moduledoc """
+  Raised when a download stream started with `:if_none_match` or
+  `:if_modified_since` finds the object unchanged.
+  """
+
+  defexception message: "Object not modified"
+end
@@ -0,0 +1,78 @@
+defmodule ObjectStoreX.TokenServer do
+  @moduledoc """
+  Refreshes short-lived bearer tokens for GCS and Azure stores.
+
+  Stores created with `:token_fun` (see `ObjectStoreX.new/2`) serve their
+  current OAuth token until shortly before it expires, then ask their token
+  server for a new one. The server calls the token function in a task and
+  hands the result back to the store, so the token can come from any token
+  broker reachable from Elixir.
+
+  The token function returns `{:ok, token, expires_at}`, `{:ok, token}` for
+  tokens that don't expire, or `{:error, reason}`. `expires_at` is a
+  `DateTime` or a Unix timestamp in seconds.
+
+  `ObjectStoreX.new/2` starts a server linked to the caller. In a
+  supervision tree, start the server yourself and pass it as `:token_server`:
+
+      children = [
+        {ObjectStoreX.TokenServer, token_fun: &MyApp.Broker.gcs_token/0, name: MyApp.GcsToken}
+      ]
+
+      {:ok, store} = ObjectStoreX.new(:gcs, bucket: "my-bucket", token_server: MyApp.GcsToken)
+  """
+
+  use GenServer
+
+  alias ObjectStoreX.Native
+
+  @type token_fun ::
+          (-> {:ok, String.t(), DateTime.t() | integer() | nil}
+              | {:ok, String.t()}
+              | {:error, term()})
+
+  @doc """
+  Start a token server.
+
+  ## Options
+
+  - `:token_fun` - Zero-arity function fetching a new token (required)
+  - `:name` - Name to register the server under
+  """
+  @spec start_link(keyword()) :: GenServer.on_start()
+  def start_link(opts) do
+    token_fun = Keyword.fetch!(opts, :token_fun)
+    GenServer.start_link(__MODULE__, token_fun, Keyword.take(opts, [:name]))
+  end
+
+  @impl GenServer
+  def init(token_fun), do: {:ok, token_fun}
+
+  @impl GenServer
+  def handle_info({:objectstorex_token, call_id}, token_fun) do
+    Task.start(fn -> Native.token_reply(call_id, fetch(token_fun)) end)
+    {:noreply, token_fun}
+  end
+
+  def handle_info(_message, token_fun), do: {:noreply, token_fun}
+
+  @doc false
+  def fetch(token_fun) do
+    case token_fun.() do
+      {:ok, token, expires_at} when is_binary(token) -> {:ok, token(token, expires_at)}
+      {:ok, token} when is_binary(token) -> {:ok, {token, nil}}
+      {:error, reason} when is_atom(reason) or is_binary(reason) -> {:error, reason}
+      {:error, reason} -> {:error, inspect(reason)}
+      other -> {:error, "Invalid token function result: #{inspect(other)}"}
+    end
+  rescue
+    e -> {:error, Exception.message(e)}
+  catch
+    kind, reason -> {:error, Exception.format_banner(kind, reason)}
+  end
+
+  @doc false
+  def token(token, %DateTime{} = expires_at), do: {token, DateTime.to_unix(expires_at)}
+  def token(token, expires_at) when is_integer(expires_at) or is_nil(expires_at),
+    do: {token, expires_at}
+end
@@ -124,6 +124,7 @@ defmodule ObjectStoreX.MixProject do
       groups_for_modules: [
         "Core API": [ObjectStoreX],
         Streaming: [ObjectStoreX.Stream],
+        Listings: [ObjectStoreX.Snapshot, ObjectStoreX.SnapshotQuery],
         "Error Handling": [ObjectStoreX.Error],
         Internal: [
           ObjectStoreX.Native,
@@ -0,0 +1,140 @@
+//! Appending to objects on stores that can extend them
+
+use crate::atoms;
+use crate::errors::map_error;
+use crate::shutdown;
+use crate::store::StoreWrapper;
+use crate::RUNTIME;
+use async_trait::async_trait;
+use bytes::{Bytes, BytesMut};
+use object_store::local::LocalFileSystem;
+use object_store::path::Path;
+use object_store::{DynObjectStore, Error, PutMode, PutOptions, PutPayload, Result, UpdateVersion};
+use rustler::{Binary, Encoder, Env, NifResult, ResourceArc, Term};
+use std::io::Write;
+use std::sync::Arc;
+
+/// Appends data to the end of an object
+#[async_trait]
+pub trait Append: Send + Sync {
+    /// Append `data` to the object at `path`, creating it if it doesn't exist,
+    /// and return the object's new size
+    async fn append(&self, path: &Path, data: Bytes) -> Result<u64>;
+}
+
+/// Appends to local files in place
+pub struct LocalAppend {
+    fs: Arc<LocalFileSystem>,
+}
+
+impl LocalAppend {
+    pub fn new(fs: Arc<LocalFileSystem>) -> Self {
+        Self { fs }
+    }
+}
+
+fn local_error(path: &std::path::Path, source: std::io::Error) -> Error {
+    Error::Generic {
+        store: "LocalFileSystem",
+        source: format!("unable to append to {}: {}", path.display(), source).into(),
+    }
+}
+
+#[async_trait]
+impl Append for LocalAppend {
+    /// Opens the file in append mode, so concurrent appends through any
+    /// process each land whole at the end of the file
+    async fn append(&self, path: &Path, data: Bytes) -> Result<u64> {
+        let file_path = self.fs.path_to_filesystem(path)?;
+
+        if let Some(parent) = file_path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| local_error(parent, e))?;
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&file_path)
+            .map_err(|e| local_error(&file_path, e))?;
+        file.write_all(&data)
+            .map_err(|e| local_error(&file_path, e))?;
+
+        let size = file.metadata().map_err(|e| local_error(&file_path, e))?;
+        Ok(size.len())
+    }
+}
+
+/// Appends by rewriting the object with a conditional put, retrying when
+/// another writer got there first
+///
+/// Every append re-sends the whole object, so this is only used for the
+/// in-memory store, where that is a copy rather than a transfer.
+pub struct RewriteAppend {
+    store: Arc<DynObjectStore>,
+}
+
+impl RewriteAppend {
+    pub fn new(store: Arc<DynObjectStore>) -> Self {
+        Self { store }
+    }
+}
+
+#[async_trait]
+impl Append for RewriteAppend {
+    async fn append(&self, path: &Path, data: Bytes) -> Result<u64> {
+        loop {
+            let (current, mode) = match self.store.get(path).await {
+                Ok(result) => {
+                    let version = UpdateVersion {
+                        e_tag: result.meta.e_tag.clone(),
+                        version: result.meta.version.clone(),
+                    };
+                    (result.bytes().await?, PutMode::Update(version))
+                }
+                Err(Error::NotFound { .. }) => (Bytes::new(), PutMode::Create),
+                Err(e) => return Err(e),
+            };
+
+            let mut combined = BytesMut::with_capacity(current.len() + data.len());
+            combined.extend_from_slice(&current);
+            combined.extend_from_slice(&data);
+            let size = combined.len() as u64;
+
+            let opts = PutOptions {
+                mode,
+                ..Default::default()
+            };
+            match self
+                .store
+                .put_opts(path, PutPayload::from(combined.freeze()), opts)
+                .await
+            {
+                Ok(_) => return Ok(size),
+                Err(Error::Precondition { .. } | Error::AlreadyExists { .. }) => continue,
+                Err(e) => return Err(e),
+            }
+        }
+    }
+}
+
+/// Append data to an object, creating it if it doesn't exist
+///
+/// Returns `{:ok, size}` with the object's new size, or `:not_supported` for
+/// stores that can't append (cloud stores and wrapped stores).
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn append<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+    data: Binary,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let Some(appender) = store.append.clone() else {
+        return Ok(atoms::not_supported().to_term(env));
+    };
+    let data = Bytes::copy_from_slice(data.as_slice());
+
+    match RUNTIME.block_on(appender.append(&Path::from(path), data)) {
+        Ok(size) => Ok((atoms::ok(), size).encode(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
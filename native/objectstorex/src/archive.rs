@@ -0,0 +1,551 @@
+//! Extracting tar and zip archives from one store into another, and creating
+//! them from a prefix
+
+use crate::atoms;
+use crate::errors::{from_io_error, map_error};
+use crate::shutdown;
+use crate::store::StoreWrapper;
+use crate::RUNTIME;
+use bytes::Bytes;
+use chrono::{Datelike, Timelike};
+use flate2::read::MultiGzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use futures::stream::BoxStream;
+use futures::{StreamExt, TryStreamExt};
+use object_store::buffered::BufWriter;
+use object_store::path::Path;
+use object_store::{DynObjectStore, ObjectMeta};
+use rustler::{Atom, Encoder, Env, NifMap, NifResult, ResourceArc, Term};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::io::AsyncWrite;
+
+/// Size of the reads from archive entries, and of the ranged gets of zip
+/// archives
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+/// Buffer and part size of extracted files
+const PART_SIZE: usize = 5 * 1024 * 1024;
+
+/// Outcome of an archive creation
+#[derive(NifMap)]
+pub struct ArchiveResultNif {
+    files: u64,
+    /// Size of the archive
+    size: u64,
+}
+
+/// Outcome of an extraction
+#[derive(NifMap)]
+pub struct ExtractResultNif {
+    files: u64,
+    bytes: u64,
+    /// Entries that weren't extracted: links, special files and names that
+    /// would escape the destination prefix
+    skipped: Vec<String>,
+}
+
+enum Format {
+    Tar,
+    TarGz,
+    Zip,
+}
+
+impl Format {
+    fn from_atom(atom: Atom) -> NifResult<Self> {
+        match atom {
+            a if a == atoms::tar() => Ok(Format::Tar),
+            a if a == atoms::tar_gz() => Ok(Format::TarGz),
+            a if a == atoms::zip() => Ok(Format::Zip),
+            _ => Err(rustler::Error::BadArg),
+        }
+    }
+}
+
+/// Unwrap I/O errors of zip archives, which may carry a store error
+fn zip_error(error: zip::result::ZipError) -> io::Error {
+    match error {
+        zip::result::ZipError::Io(error) => error,
+        error => io::Error::other(error),
+    }
+}
+
+/// Blocking reader over the body of a get
+///
+/// Archives are parsed on the calling dirty scheduler thread, which waits on
+/// the runtime for each chunk.
+struct StreamReader {
+    stream: BoxStream<'static, object_store::Result<Bytes>>,
+    chunk: Bytes,
+}
+
+impl Read for StreamReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.chunk.is_empty() {
+            match RUNTIME.block_on(self.stream.next()) {
+                Some(Ok(chunk)) => self.chunk = chunk,
+                Some(Err(e)) => return Err(e.into()),
+                None => return Ok(0),
+            }
+        }
+
+        let len = buf.len().min(self.chunk.len());
+        buf[..len].copy_from_slice(&self.chunk.split_to(len));
+        Ok(len)
+    }
+}
+
+/// Blocking reader of an object issuing a ranged get per read, for formats
+/// that need to seek (zip)
+struct RangeReader {
+    store: Arc<DynObjectStore>,
+    path: Path,
+    size: u64,
+    position: u64,
+}
+
+impl Read for RangeReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let start = self.position.min(self.size);
+        let end = (start + buf.len() as u64).min(self.size);
+        if start == end {
+            return Ok(0);
+        }
+
+        let data = RUNTIME.block_on(
+            self.store
+                .get_range(&self.path, start as usize..end as usize),
+        )?;
+        buf[..data.len()].copy_from_slice(&data);
+        self.position += data.len() as u64;
+        Ok(data.len())
+    }
+}
+
+impl Seek for RangeReader {
+    fn seek(&mut self, position: SeekFrom) -> io::Result<u64> {
+        let position = match position {
+            SeekFrom::Start(offset) => Some(offset),
+            SeekFrom::End(offset) => self.size.checked_add_signed(offset),
+            SeekFrom::Current(offset) => self.position.checked_add_signed(offset),
+        };
+        self.position = position.ok_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "Seek before start of object")
+        })?;
+        Ok(self.position)
+    }
+}
+
+/// Destination of an entry name under the prefix, or `None` for names that
+/// are absolute or contain `..`
+fn entry_path(prefix: &Path, name: &str) -> Option<Path> {
+    if name.starts_with('/') || name.contains('\\') {
+        return None;
+    }
+
+    let mut path = prefix.clone();
+    for part in name.split('/') {
+        match part {
+            "" | "." => {}
+            ".." => return None,
+            part => path = path.child(part),
+        }
+    }
+    (path != *prefix).then_some(path)
+}
+
+/// Write an entry to the destination store, returning its size
+///
+/// A failed entry aborts its upload, so no parts are left behind.
+fn write_entry(dest: &Arc<DynObjectStore>, path: Path, entry: &mut dyn Read) -> io::Result<u64> {
+    let mut writer = BufWriter::with_capacity(dest.clone(), path, PART_SIZE);
+    let mut buf = vec![0; CHUNK_SIZE];
+    let mut size = 0;
+
+    let written = loop {
+        let len = match entry.read(&mut buf) {
+            Ok(0) => break Ok(()),
+            Ok(len) => len,
+            Err(e) => break Err(e),
+        };
+        if let Err(e) = RUNTIME.block_on(writer.put(Bytes::copy_from_slice(&buf[..len]))) {
+            break Err(e.into());
+        }
+        size += len as u64;
+    };
+
+    if let Err(e) = written {
+        let _ = RUNTIME.block_on(writer.abort());
+        return Err(e);
+    }
+
+    RUNTIME.block_on(futures::future::poll_fn(|cx| {
+        Pin::new(&mut writer).poll_shutdown(cx)
+    }))?;
+    Ok(size)
+}
+
+fn extract_tar(
+    archive: impl Read,
+    dest: &Arc<DynObjectStore>,
+    prefix: &Path,
+    result: &mut ExtractResultNif,
+) -> io::Result<()> {
+    let mut archive = tar::Archive::new(archive);
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = String::from_utf8_lossy(&entry.path_bytes()).into_owned();
+        let kind = entry.header().entry_type();
+
+        if kind.is_dir() {
+            continue;
+        }
+        match entry_path(prefix, &name) {
+            Some(path) if kind.is_file() => {
+                result.bytes += write_entry(dest, path, &mut entry)?;
+                result.files += 1;
+            }
+            _ => result.skipped.push(name),
+        }
+    }
+    Ok(())
+}
+
+fn extract_zip(
+    archive: impl Read + Seek,
+    dest: &Arc<DynObjectStore>,
+    prefix: &Path,
+    result: &mut ExtractResultNif,
+) -> io::Result<()> {
+    let mut archive = zip::ZipArchive::new(archive).map_err(zip_error)?;
+
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(zip_error)?;
+        let name = entry.name().to_string();
+
+        if entry.is_dir() {
+            continue;
+        }
+        match entry_path(prefix, &name) {
+            Some(path) if entry.is_file() && !entry.is_symlink() => {
+                result.bytes += write_entry(dest, path, &mut entry)?;
+                result.files += 1;
+            }
+            _ => result.skipped.push(name),
+        }
+    }
+    Ok(())
+}
+
+/// Start a streaming get of a tar archive
+fn stream_reader(store: &DynObjectStore, path: &Path) -> object_store::Result<StreamReader> {
+    let get = RUNTIME.block_on(store.get(path))?;
+    Ok(StreamReader {
+        stream: get.into_stream(),
+        chunk: Bytes::new(),
+    })
+}
+
+fn extract(
+    store: &Arc<DynObjectStore>,
+    path: Path,
+    format: Format,
+    dest: &Arc<DynObjectStore>,
+    prefix: &Path,
+    result: &mut ExtractResultNif,
+) -> object_store::Result<()> {
+    let extracted = match format {
+        Format::Tar => extract_tar(stream_reader(store, &path)?, dest, prefix, result),
+        Format::TarGz => {
+            let reader = MultiGzDecoder::new(stream_reader(store, &path)?);
+            extract_tar(reader, dest, prefix, result)
+        }
+        Format::Zip => {
+            let meta = RUNTIME.block_on(store.head(&path))?;
+            let reader = RangeReader {
+                store: store.clone(),
+                path,
+                size: meta.size as u64,
+                position: 0,
+            };
+            extract_zip(
+                io::BufReader::with_capacity(CHUNK_SIZE, reader),
+                dest,
+                prefix,
+                result,
+            )
+        }
+    };
+    extracted.map_err(|e| from_io_error("Archive", e))
+}
+
+/// Extract a tar, gzipped tar or zip archive into a store under a prefix
+///
+/// Entries are streamed from the archive to the destination without going
+/// through the BEAM. Tar archives are read with a single streaming get; zip
+/// archives, whose index is at the end, with ranged gets. Directories are
+/// implied by the keys and not written. Extraction stops at the first error,
+/// leaving the files extracted so far.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn extract_archive<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+    format: Atom,
+    dest: ResourceArc<StoreWrapper>,
+    dest_prefix: String,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let format = Format::from_atom(format)?;
+    let path = Path::from(path);
+    let prefix = Path::from(dest_prefix);
+    let mut result = ExtractResultNif {
+        files: 0,
+        bytes: 0,
+        skipped: Vec::new(),
+    };
+
+    let outcome = extract(
+        &store.inner,
+        path,
+        format,
+        &dest.inner,
+        &prefix,
+        &mut result,
+    );
+
+    match outcome {
+        Ok(()) => Ok((atoms::ok(), result).encode(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
+
+/// Blocking writer uploading an archive as it is written
+///
+/// Failed archives are aborted with `abort`. Dropping the writer before
+/// `complete` or `abort` starts aborting the upload in the background.
+struct UploadWriter {
+    writer: Option<BufWriter>,
+    size: u64,
+}
+
+impl UploadWriter {
+    fn new(store: &Arc<DynObjectStore>, path: &Path) -> Self {
+        Self {
+            writer: Some(BufWriter::with_capacity(
+                store.clone(),
+                path.clone(),
+                PART_SIZE,
+            )),
+            size: 0,
+        }
+    }
+
+    /// Write the remaining data and complete the upload, returning the size
+    ///
+    /// The upload is aborted if it can't be completed.
+    fn complete(mut self) -> io::Result<u64> {
+        let mut writer = self.writer.take().expect("upload is only completed once");
+        let completed = RUNTIME.block_on(futures::future::poll_fn(|cx| {
+            Pin::new(&mut writer).poll_shutdown(cx)
+        }));
+        if let Err(e) = completed {
+            let _ = RUNTIME.block_on(writer.abort());
+            return Err(e);
+        }
+        Ok(self.size)
+    }
+
+    /// Abort the upload, waiting for the parts written so far to be removed
+    fn abort(mut self) {
+        if let Some(mut writer) = self.writer.take() {
+            let _ = RUNTIME.block_on(writer.abort());
+        }
+    }
+}
+
+impl Write for UploadWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let writer = self.writer.as_mut().expect("upload is not completed");
+        RUNTIME.block_on(writer.put(Bytes::copy_from_slice(buf)))?;
+        self.size += buf.len() as u64;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+impl Drop for UploadWriter {
+    fn drop(&mut self) {
+        if let Some(mut writer) = self.writer.take() {
+            RUNTIME.try_spawn(async move {
+                let _ = writer.abort().await;
+            });
+        }
+    }
+}
+
+/// Reader of an object that fails unless it yields exactly the size it was
+/// listed with, since archive headers are written before the data
+struct SizedReader {
+    inner: io::Take<StreamReader>,
+    location: Path,
+}
+
+impl SizedReader {
+    fn open(store: &DynObjectStore, meta: &ObjectMeta) -> object_store::Result<Self> {
+        Ok(Self {
+            inner: stream_reader(store, &meta.location)?.take(meta.size as u64),
+            location: meta.location.clone(),
+        })
+    }
+}
+
+impl Read for SizedReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let len = self.inner.read(buf)?;
+        if len == 0 && !buf.is_empty() && self.inner.limit() > 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                format!("{} changed while it was archived", self.location),
+            ));
+        }
+        Ok(len)
+    }
+}
+
+/// Name of an object in an archive of its prefix
+pub(crate) fn entry_name(location: &Path, prefix: &Path) -> String {
+    match location.prefix_match(prefix) {
+        Some(parts) => parts
+            .map(|part| part.as_ref().to_string())
+            .collect::<Vec<_>>()
+            .join("/"),
+        None => location.to_string(),
+    }
+}
+
+fn archive_tar(
+    archive: impl Write,
+    store: &DynObjectStore,
+    objects: &[ObjectMeta],
+    prefix: &Path,
+) -> io::Result<()> {
+    let mut builder = tar::Builder::new(archive);
+
+    for meta in objects {
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Regular);
+        header.set_size(meta.size as u64);
+        header.set_mode(0o644);
+        header.set_mtime(meta.last_modified.timestamp().max(0) as u64);
+
+        let reader = SizedReader::open(store, meta)?;
+        builder.append_data(&mut header, entry_name(&meta.location, prefix), reader)?;
+    }
+
+    builder.into_inner()?.flush()
+}
+
+fn archive_zip(
+    archive: impl Write,
+    store: &DynObjectStore,
+    objects: &[ObjectMeta],
+    prefix: &Path,
+) -> io::Result<()> {
+    let mut zip = zip::ZipWriter::new_stream(archive);
+
+    for meta in objects {
+        let modified = meta.last_modified;
+        let options = zip::write::SimpleFileOptions::default()
+            .compression_method(zip::CompressionMethod::Deflated)
+            .large_file(meta.size as u64 >= u32::MAX as u64)
+            .unix_permissions(0o644);
+        // Zip timestamps start in 1980; older objects keep the default
+        let options = match zip::DateTime::from_date_and_time(
+            modified.year().try_into().unwrap_or(0),
+            modified.month() as u8,
+            modified.day() as u8,
+            modified.hour() as u8,
+            modified.minute() as u8,
+            modified.second() as u8,
+        ) {
+            Ok(time) => options.last_modified_time(time),
+            Err(_) => options,
+        };
+
+        zip.start_file(entry_name(&meta.location, prefix), options)
+            .map_err(zip_error)?;
+        io::copy(&mut SizedReader::open(store, meta)?, &mut zip)?;
+    }
+
+    zip.finish().map_err(zip_error)?.into_inner().flush()
+}
+
+fn archive(
+    store: &Arc<DynObjectStore>,
+    prefix: &Path,
+    dest: &Path,
+    format: Format,
+) -> object_store::Result<ArchiveResultNif> {
+    let mut objects: Vec<ObjectMeta> = RUNTIME.block_on(store.list(Some(prefix)).try_collect())?;
+    objects.retain(|meta| meta.location != *dest);
+    objects.sort_by(|a, b| a.location.cmp(&b.location));
+
+    let mut upload = UploadWriter::new(store, dest);
+    let written = {
+        let archive = io::BufWriter::with_capacity(CHUNK_SIZE, &mut upload);
+        match format {
+            Format::Tar => archive_tar(archive, store.as_ref(), &objects, prefix),
+            Format::TarGz => {
+                let mut encoder = GzEncoder::new(archive, Compression::default());
+                archive_tar(&mut encoder, store.as_ref(), &objects, prefix)
+                    .and_then(|()| encoder.finish()?.flush())
+            }
+            Format::Zip => archive_zip(archive, store.as_ref(), &objects, prefix),
+        }
+    };
+
+    if let Err(e) = written {
+        upload.abort();
+        return Err(from_io_error("Archive", e));
+    }
+
+    upload
+        .complete()
+        .map(|size| ArchiveResultNif {
+            files: objects.len() as u64,
+            size,
+        })
+        .map_err(|e| from_io_error("Archive", e))
+}
+
+/// Write every object under a prefix into a tar, gzipped tar or zip archive
+///
+/// The archive is streamed to `dest_path` with a multipart upload as the
+/// objects are read, one at a time, in key order. Entries are named relative
+/// to the prefix. An existing object at `dest_path` under the prefix isn't
+/// included. On failure the upload is aborted.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn archive_prefix<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    prefix: String,
+    dest_path: String,
+    format: Atom,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let format = Format::from_atom(format)?;
+    let prefix = Path::from(prefix);
+    let dest = Path::from(dest_path);
+
+    match archive(&store.inner, &prefix, &dest, format) {
+        Ok(result) => Ok((atoms::ok(), result).encode(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
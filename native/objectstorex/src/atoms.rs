@@ -8,8 +8,132 @@ rustler::atoms! {
     not_modified,
     not_supported,
     permission_denied,
+    quota_exceeded,
+    checksum_mismatch,
+    integrity_error,
+    invalid_json,
+    throttled,
+    timeout,
+    server_error,
+    cancelled,
+    shut_down,
+    // Provider limit violations
+    key_too_long,
+    metadata_too_large,
+    too_many_tags,
+    tag_too_long,
+    too_many_parts,
+    part_too_small,
     // Streaming atoms
     chunk,
     done,
     object,
+    objects,
+    part_uploaded,
+    upload_done,
+    upload_error,
+    upload_ready,
+    closed,
+    busy,
+    // Download stream options
+    gzip,
+    // Ranged get metadata
+    range,
+    total_size,
+    // Object metadata
+    last_modified_us,
+    // Snapshot query atoms
+    location,
+    size,
+    last_modified,
+    asc,
+    desc,
+    // Report atoms
+    directory,
+    extension,
+    date,
+    day,
+    month,
+    year,
+    count,
+    bytes,
+    largest,
+    newest,
+    // Transfer atoms
+    progress,
+    // Rename strategies
+    rename,
+    copy_delete,
+    // Rename collision policies
+    overwrite,
+    fail,
+    skip,
+    suffix,
+    skipped,
+    // Checksum algorithms
+    md5,
+    sha256,
+    crc32c,
+    etag,
+    // Conditional actions
+    delete,
+    copy,
+    // Compression codecs
+    zstd,
+    // Local copy strategies
+    auto,
+    reflink,
+    // S3 server-side encryption
+    s3,
+    kms,
+    dsse_kms,
+    customer_key,
+
+    // S3 Object Lock retention modes
+    governance,
+    compliance,
+
+    // Archive restore tiers and states
+    expedited,
+    standard,
+    bulk,
+    available,
+    archived,
+    in_progress,
+    restored,
+
+    // Cache write policies
+    write_around,
+    write_through,
+    write_back,
+
+    // Mirror consistency levels
+    all,
+    primary,
+
+    // Benchmark scenarios
+    put,
+    get,
+    list,
+    stream,
+
+    // Spilled get results
+    file,
+
+    // Archive formats
+    tar,
+    tar_gz,
+    zip,
+
+    // Reader positions, as in :file.position/2
+    bof,
+    cur,
+    eof,
+
+    // Compare-and-swap results
+    conflict,
+
+    // Timestamp units, as in System.time_unit()
+    second,
+    millisecond,
 }
@@ -0,0 +1,640 @@
+//! Object stores implemented in Elixir
+//!
+//! `ElixirStore` implements the ObjectStore trait by sending each operation to
+//! an Elixir backend process as `{:objectstorex_backend, call_id, request}` and
+//! waiting for the process to answer through `backend_reply/2`. Stores built
+//! on it go through the same `StoreWrapper` API, wrappers and streaming code as
+//! the native providers.
+
+use crate::atoms;
+use crate::builders::tracked;
+use crate::store::StoreWrapper;
+use crate::wrappers::deadline::TIMEOUT_STORE;
+use crate::RUNTIME;
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::{DateTime, TimeZone, Utc};
+use futures::stream::{self, BoxStream};
+use futures::{FutureExt, StreamExt};
+use object_store::path::Path;
+use object_store::{
+    Attributes, Error, GetOptions, GetRange, GetResult, GetResultPayload, ListResult,
+    MultipartUpload, ObjectMeta, ObjectStore, PutMode, PutMultipartOpts, PutOptions, PutPayload,
+    PutResult, Result, UploadPart,
+};
+use once_cell::sync::Lazy;
+use rustler::{
+    Atom, Encoder, Env, LocalPid, Monitor, NifMap, NifResult, OwnedBinary, Resource, ResourceArc,
+    Term,
+};
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::oneshot;
+
+const STORE: &str = "Elixir";
+
+mod backend_atoms {
+    rustler::atoms! {
+        objectstorex_backend,
+        put,
+        get,
+        get_range,
+        head,
+        delete,
+        list,
+        copy,
+        copy_if_not_exists,
+        overwrite,
+        create,
+    }
+}
+
+/// A call waiting for a reply from its backend process
+struct PendingCall {
+    backend: u64,
+    tx: oneshot::Sender<BackendReply>,
+}
+
+/// Calls waiting for a reply from their backend process, by call id
+static PENDING: Lazy<Mutex<HashMap<u64, PendingCall>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+static NEXT_CALL_ID: AtomicU64 = AtomicU64::new(1);
+static NEXT_BACKEND_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Monitor on a backend process, failing its pending calls once it exits
+pub struct BackendMonitor {
+    id: u64,
+    down: AtomicBool,
+}
+
+impl BackendMonitor {
+    /// Fail every pending call of this backend
+    fn fail_pending(&self) {
+        let mut pending = PENDING.lock().unwrap();
+        let ids: Vec<u64> = pending
+            .iter()
+            .filter(|(_, call)| call.backend == self.id)
+            .map(|(id, _)| *id)
+            .collect();
+        for id in ids {
+            if let Some(call) = pending.remove(&id) {
+                let _ = call
+                    .tx
+                    .send(BackendReply::Error("backend process exited".to_string()));
+            }
+        }
+    }
+}
+
+impl Resource for BackendMonitor {
+    const IMPLEMENTS_DOWN: bool = true;
+
+    fn down<'a>(&'a self, _env: Env<'a>, _pid: LocalPid, _monitor: Monitor) {
+        self.down.store(true, Ordering::SeqCst);
+        self.fail_pending();
+    }
+}
+
+/// Object metadata as replied by a backend process
+#[derive(Debug, Clone, NifMap)]
+pub struct BackendMetaNif {
+    pub location: String,
+    pub size: u64,
+    /// Unix timestamp in seconds
+    pub last_modified: Option<i64>,
+    pub etag: Option<String>,
+}
+
+impl TryFrom<BackendMetaNif> for ObjectMeta {
+    type Error = Error;
+
+    /// Fails for sizes that don't fit `usize`, which only happens on 32-bit
+    /// targets, instead of truncating them
+    fn try_from(meta: BackendMetaNif) -> Result<Self> {
+        let size = usize::try_from(meta.size).map_err(|_| {
+            generic(format!(
+                "Size {} of {} exceeds the platform's maximum object size",
+                meta.size, meta.location
+            ))
+        })?;
+
+        Ok(ObjectMeta {
+            location: Path::from(meta.location),
+            last_modified: meta
+                .last_modified
+                .and_then(|secs| Utc.timestamp_opt(secs, 0).single())
+                .unwrap_or(DateTime::<Utc>::MIN_UTC),
+            size,
+            e_tag: meta.etag,
+            version: None,
+        })
+    }
+}
+
+/// Reply of a backend process to one call
+#[derive(Debug)]
+pub enum BackendReply {
+    Ok,
+    Data(Bytes),
+    Meta(BackendMetaNif),
+    List(Vec<BackendMetaNif>),
+    Error(String),
+}
+
+impl<'a> rustler::Decoder<'a> for BackendReply {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        if let Ok(atom) = term.decode::<Atom>() {
+            if atom == atoms::ok() {
+                return Ok(BackendReply::Ok);
+            }
+            return Err(rustler::Error::BadArg);
+        }
+
+        let (tag, value): (Atom, Term<'a>) = term.decode()?;
+
+        if tag == atoms::error() {
+            let reason = match value.atom_to_string() {
+                Ok(reason) => reason,
+                Err(_) => value
+                    .decode::<String>()
+                    .unwrap_or_else(|_| format!("{:?}", value)),
+            };
+            return Ok(BackendReply::Error(reason));
+        }
+
+        if tag != atoms::ok() {
+            return Err(rustler::Error::BadArg);
+        }
+
+        if let Ok(data) = value.decode::<rustler::Binary>() {
+            Ok(BackendReply::Data(Bytes::copy_from_slice(data.as_slice())))
+        } else if let Ok(list) = value.decode::<Vec<BackendMetaNif>>() {
+            Ok(BackendReply::List(list))
+        } else {
+            Ok(BackendReply::Meta(value.decode()?))
+        }
+    }
+}
+
+/// One operation sent to a backend process
+enum Request {
+    Put(Path, Bytes, PutMode),
+    Get(Path),
+    GetRange(Path, Range<usize>),
+    Head(Path),
+    Delete(Path),
+    List(Option<Path>),
+    Copy(Path, Path),
+    CopyIfNotExists(Path, Path),
+}
+
+impl Request {
+    /// The location errors are reported for
+    fn path(&self) -> String {
+        match self {
+            Request::Put(path, ..)
+            | Request::Get(path)
+            | Request::GetRange(path, _)
+            | Request::Head(path)
+            | Request::Delete(path)
+            | Request::Copy(_, path)
+            | Request::CopyIfNotExists(_, path) => path.to_string(),
+            Request::List(prefix) => prefix.as_ref().map(Path::to_string).unwrap_or_default(),
+        }
+    }
+
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        use backend_atoms as a;
+
+        match self {
+            Request::Put(path, data, mode) => {
+                let mut binary = OwnedBinary::new(data.len()).unwrap();
+                binary.as_mut_slice().copy_from_slice(data);
+                let mode = match mode {
+                    PutMode::Create => a::create(),
+                    _ => a::overwrite(),
+                };
+                (a::put(), path.as_ref(), binary.release(env), mode).encode(env)
+            }
+            Request::Get(path) => (a::get(), path.as_ref()).encode(env),
+            Request::GetRange(path, range) => {
+                (a::get_range(), path.as_ref(), range.start, range.end).encode(env)
+            }
+            Request::Head(path) => (a::head(), path.as_ref()).encode(env),
+            Request::Delete(path) => (a::delete(), path.as_ref()).encode(env),
+            Request::List(prefix) => {
+                (a::list(), prefix.as_ref().map(|p| p.as_ref().to_string())).encode(env)
+            }
+            Request::Copy(from, to) => (a::copy(), from.as_ref(), to.as_ref()).encode(env),
+            Request::CopyIfNotExists(from, to) => {
+                (a::copy_if_not_exists(), from.as_ref(), to.as_ref()).encode(env)
+            }
+        }
+    }
+}
+
+/// Map an error reason replied by a backend process to an object_store error
+fn reply_error(path: String, reason: String) -> Error {
+    let source = reason.clone().into();
+
+    match reason.as_str() {
+        "not_found" => Error::NotFound { path, source },
+        "already_exists" => Error::AlreadyExists { path, source },
+        "precondition_failed" => Error::Precondition { path, source },
+        "not_modified" => Error::NotModified { path, source },
+        "permission_denied" => Error::PermissionDenied { path, source },
+        "not_supported" => Error::NotSupported { source },
+        _ => generic(format!("Backend error for {}: {}", path, reason)),
+    }
+}
+
+fn generic(message: String) -> Error {
+    Error::Generic {
+        store: STORE,
+        source: message.into(),
+    }
+}
+
+fn unexpected(reply: BackendReply) -> Error {
+    generic(format!("Unexpected backend reply: {:?}", reply))
+}
+
+/// Object store whose operations are implemented by an Elixir process
+#[derive(Clone)]
+pub struct ElixirStore {
+    pid: LocalPid,
+    monitor: ResourceArc<BackendMonitor>,
+    timeout: Duration,
+}
+
+impl ElixirStore {
+    /// Create a store for the backend process `pid`, monitoring it
+    ///
+    /// Calls fail once the process exits, or when it doesn't reply within
+    /// `timeout`.
+    pub fn new(env: Env, pid: LocalPid, timeout: Duration) -> Self {
+        let monitor = ResourceArc::new(BackendMonitor {
+            id: NEXT_BACKEND_ID.fetch_add(1, Ordering::Relaxed),
+            down: AtomicBool::new(false),
+        });
+        if env.monitor(&monitor, &pid).is_none() {
+            monitor.down.store(true, Ordering::SeqCst);
+        }
+
+        Self {
+            pid,
+            monitor,
+            timeout,
+        }
+    }
+
+    /// Send a request to the backend process and wait for its reply
+    async fn call(&self, request: Request) -> Result<BackendReply> {
+        let id = NEXT_CALL_ID.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        let backend = self.monitor.id;
+        PENDING
+            .lock()
+            .unwrap()
+            .insert(id, PendingCall { backend, tx });
+
+        // Checked after registering the call, so a concurrent exit either
+        // fails the call or is seen here
+        if self.monitor.down.load(Ordering::SeqCst) {
+            PENDING.lock().unwrap().remove(&id);
+            return Err(generic("Backend process is not alive".to_string()));
+        }
+
+        let path = request.path();
+        let pid = self.pid;
+
+        let sent = RUNTIME
+            .send(pid, None, move |env| {
+                (
+                    backend_atoms::objectstorex_backend(),
+                    id,
+                    request.encode(env),
+                )
+                    .encode(env)
+            })
+            .await
+            .unwrap_or(false);
+
+        if !sent {
+            PENDING.lock().unwrap().remove(&id);
+            return Err(generic("Backend process is not alive".to_string()));
+        }
+
+        match tokio::time::timeout(self.timeout, rx).await {
+            Ok(Ok(BackendReply::Error(reason))) => Err(reply_error(path, reason)),
+            Ok(Ok(reply)) => Ok(reply),
+            Ok(Err(_)) => Err(generic("Backend process dropped the call".to_string())),
+            Err(_) => {
+                PENDING.lock().unwrap().remove(&id);
+                Err(Error::Generic {
+                    store: TIMEOUT_STORE,
+                    source: format!(
+                        "backend process didn't reply within {} ms",
+                        self.timeout.as_millis()
+                    )
+                    .into(),
+                })
+            }
+        }
+    }
+
+    async fn head_meta(&self, location: &Path) -> Result<ObjectMeta> {
+        match self.call(Request::Head(location.clone())).await? {
+            BackendReply::Meta(meta) => meta.try_into(),
+            reply => Err(unexpected(reply)),
+        }
+    }
+
+    async fn put_bytes(&self, location: &Path, data: Bytes, mode: PutMode) -> Result<PutResult> {
+        match self
+            .call(Request::Put(location.clone(), data, mode))
+            .await?
+        {
+            BackendReply::Ok => Ok(PutResult {
+                e_tag: None,
+                version: None,
+            }),
+            reply => Err(unexpected(reply)),
+        }
+    }
+}
+
+impl fmt::Debug for ElixirStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ElixirStore").finish_non_exhaustive()
+    }
+}
+
+impl fmt::Display for ElixirStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ElixirStore")
+    }
+}
+
+/// Check conditional request options against an object's metadata
+fn check_preconditions(options: &GetOptions, meta: &ObjectMeta) -> Result<()> {
+    let etag = meta.e_tag.as_deref().unwrap_or("*");
+    let path = || meta.location.to_string();
+    let matches = |header: &str| header.split(',').map(str::trim).any(|x| x == etag);
+
+    if let Some(header) = &options.if_match {
+        if header != "*" && !matches(header) {
+            return Err(Error::Precondition {
+                path: path(),
+                source: format!("{} does not match {}", etag, header).into(),
+            });
+        }
+    } else if let Some(date) = options.if_unmodified_since {
+        if meta.last_modified > date {
+            return Err(Error::Precondition {
+                path: path(),
+                source: format!("modified after {}", date).into(),
+            });
+        }
+    }
+
+    if let Some(header) = &options.if_none_match {
+        if header == "*" || matches(header) {
+            return Err(Error::NotModified {
+                path: path(),
+                source: format!("{} matches {}", etag, header).into(),
+            });
+        }
+    } else if let Some(date) = options.if_modified_since {
+        if meta.last_modified <= date {
+            return Err(Error::NotModified {
+                path: path(),
+                source: format!("not modified since {}", date).into(),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolve a requested range against the object size
+pub(crate) fn resolve_range(range: &GetRange, size: usize, path: &Path) -> Result<Range<usize>> {
+    let range = match range {
+        GetRange::Bounded(r) => r.start..r.end.min(size),
+        GetRange::Offset(offset) => *offset..size,
+        GetRange::Suffix(n) => size.saturating_sub(*n)..size,
+    };
+
+    let past_end = size > 0 && range.start >= size;
+    if past_end || range.start > range.end {
+        return Err(Error::NotSupported {
+            source: format!("Invalid range {:?} for {} of {} bytes", range, path, size).into(),
+        });
+    }
+    Ok(range)
+}
+
+#[async_trait]
+impl ObjectStore for ElixirStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        if let PutMode::Update(_) = opts.mode {
+            return Err(Error::NotImplemented);
+        }
+
+        self.put_bytes(location, Bytes::from(payload), opts.mode)
+            .await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        _opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        Ok(Box::new(BufferedUpload {
+            store: self.clone(),
+            location: location.clone(),
+            parts: Vec::new(),
+        }))
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        let meta = self.head_meta(location).await?;
+        check_preconditions(&options, &meta)?;
+
+        let range = match &options.range {
+            Some(range) => resolve_range(range, meta.size, location)?,
+            None => 0..meta.size,
+        };
+
+        let data = if options.head {
+            Bytes::new()
+        } else if options.range.is_some() {
+            self.get_range(location, range.clone()).await?
+        } else {
+            match self.call(Request::Get(location.clone())).await? {
+                BackendReply::Data(data) => data,
+                reply => return Err(unexpected(reply)),
+            }
+        };
+
+        Ok(GetResult {
+            payload: GetResultPayload::Stream(stream::once(async move { Ok(data) }).boxed()),
+            meta,
+            range,
+            attributes: Attributes::default(),
+        })
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        match self
+            .call(Request::GetRange(location.clone(), range))
+            .await?
+        {
+            BackendReply::Data(data) => Ok(data),
+            reply => Err(unexpected(reply)),
+        }
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        self.head_meta(location).await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        match self.call(Request::Delete(location.clone())).await? {
+            BackendReply::Ok => Ok(()),
+            reply => Err(unexpected(reply)),
+        }
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        let prefix = prefix.cloned();
+
+        async move {
+            match self.call(Request::List(prefix)).await {
+                Ok(BackendReply::List(metas)) => {
+                    stream::iter(metas.into_iter().map(ObjectMeta::try_from)).boxed()
+                }
+                Ok(reply) => stream::once(async move { Err(unexpected(reply)) }).boxed(),
+                Err(e) => stream::once(async move { Err(e) }).boxed(),
+            }
+        }
+        .flatten_stream()
+        .boxed()
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        let depth = prefix.map(|p| p.parts().count()).unwrap_or(0);
+        let mut common_prefixes = BTreeSet::new();
+        let mut objects = Vec::new();
+
+        let mut listing = self.list(prefix);
+        while let Some(meta) = listing.next().await {
+            let meta = meta?;
+            let parts: Vec<_> = meta.location.parts().collect();
+
+            if parts.len() > depth + 1 {
+                common_prefixes.insert(Path::from_iter(parts.into_iter().take(depth + 1)));
+            } else {
+                objects.push(meta);
+            }
+        }
+
+        Ok(ListResult {
+            common_prefixes: common_prefixes.into_iter().collect(),
+            objects,
+        })
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        match self.call(Request::Copy(from.clone(), to.clone())).await? {
+            BackendReply::Ok => Ok(()),
+            reply => Err(unexpected(reply)),
+        }
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        match self
+            .call(Request::CopyIfNotExists(from.clone(), to.clone()))
+            .await?
+        {
+            BackendReply::Ok => Ok(()),
+            reply => Err(unexpected(reply)),
+        }
+    }
+}
+
+/// Multipart upload that buffers its parts and sends them as one put
+#[derive(Debug)]
+struct BufferedUpload {
+    store: ElixirStore,
+    location: Path,
+    parts: Vec<PutPayload>,
+}
+
+#[async_trait]
+impl MultipartUpload for BufferedUpload {
+    fn put_part(&mut self, data: PutPayload) -> UploadPart {
+        self.parts.push(data);
+        futures::future::ready(Ok(())).boxed()
+    }
+
+    async fn complete(&mut self) -> Result<PutResult> {
+        let payload: PutPayload = std::mem::take(&mut self.parts)
+            .into_iter()
+            .flat_map(|part| part.into_iter())
+            .collect();
+
+        self.store
+            .put_bytes(&self.location, Bytes::from(payload), PutMode::Overwrite)
+            .await
+    }
+
+    async fn abort(&mut self) -> Result<()> {
+        self.parts.clear();
+        Ok(())
+    }
+}
+
+/// Create a store whose operations are implemented by an Elixir process
+///
+/// The process receives `{:objectstorex_backend, call_id, request}` messages
+/// and must answer each with `backend_reply/2` within `timeout_ms`.
+#[rustler::nif]
+pub fn new_backend(env: Env, pid: LocalPid, timeout_ms: u64) -> ResourceArc<StoreWrapper> {
+    let store = ElixirStore::new(env, pid, Duration::from_millis(timeout_ms));
+    tracked(StoreWrapper::new(Arc::new(store)))
+}
+
+/// Answer a call made to a backend process
+///
+/// Returns `:not_found` when the call is no longer waiting for a reply. A
+/// malformed reply fails the call with an error and raises `ArgumentError`.
+#[rustler::nif]
+pub fn backend_reply<'a>(env: Env<'a>, call_id: u64, reply: Term<'a>) -> NifResult<Term<'a>> {
+    let Some(call) = PENDING.lock().unwrap().remove(&call_id) else {
+        return Ok(atoms::not_found().encode(env));
+    };
+
+    match reply.decode::<BackendReply>() {
+        Ok(reply) => {
+            let _ = call.tx.send(reply);
+            Ok(atoms::ok().encode(env))
+        }
+        Err(e) => {
+            let _ = call.tx.send(BackendReply::Error(format!(
+                "invalid backend reply: {:?}",
+                reply
+            )));
+            Err(e)
+        }
+    }
+}
@@ -0,0 +1,448 @@
+//! Point-in-time snapshots of a prefix recorded in a manifest, and restores
+//! that copy and verify the recorded objects
+
+use crate::archive::entry_name;
+use crate::atoms;
+use crate::errors::{error_kind, from_io_error, integrity_error, map_error};
+use crate::shutdown;
+use crate::split::hex;
+use crate::store::StoreWrapper;
+use crate::RUNTIME;
+use futures::{StreamExt, TryStreamExt};
+use object_store::buffered::BufWriter;
+use object_store::path::Path;
+use object_store::{DynObjectStore, GetOptions, ObjectMeta, Result};
+use ring::digest::{Context, SHA256};
+use rustler::{Atom, Encoder, Env, NifMap, NifResult, ResourceArc, Term};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::io::AsyncWrite;
+
+/// Maximum number of objects read or copied concurrently
+const MAX_CONCURRENCY: usize = 8;
+
+/// Size of the parts of restored objects uploaded as multipart uploads
+const PART_SIZE: usize = 5 * 1024 * 1024;
+
+/// Manifest of a snapshot
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    /// Prefix the objects were listed under
+    pub prefix: String,
+    /// When the snapshot was taken, RFC 3339
+    pub created_at: String,
+    /// Total size in bytes
+    pub size: u64,
+    /// Objects in key order
+    pub objects: Vec<SnapshotEntry>,
+}
+
+/// One object of a snapshot
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SnapshotEntry {
+    /// Location relative to the snapshot's prefix
+    pub path: String,
+    pub size: u64,
+    pub etag: Option<String>,
+    /// Version of the object, on versioned stores
+    pub version: Option<String>,
+    /// RFC 3339
+    pub last_modified: String,
+    /// Hex-encoded SHA-256 of the object's data
+    pub sha256: String,
+}
+
+/// Result of a snapshot, as returned to Elixir
+#[derive(Debug, NifMap)]
+pub struct SnapshotResultNif {
+    pub manifest: String,
+    pub objects: u64,
+    pub size: u64,
+}
+
+/// Object that couldn't be restored
+#[derive(Debug, NifMap)]
+pub struct RestoreFailureNif {
+    pub path: String,
+    pub reason: Atom,
+}
+
+/// Result of a restore, as returned to Elixir
+#[derive(Debug, NifMap)]
+pub struct RestoreResultNif {
+    pub restored: u64,
+    pub bytes: u64,
+    pub failed: Vec<RestoreFailureNif>,
+}
+
+/// Result of an audit, as returned to Elixir
+#[derive(Debug, NifMap)]
+pub struct VerifyResultNif {
+    pub verified: u64,
+    pub missing: Vec<String>,
+    pub changed: Vec<String>,
+    pub corrupted: Vec<String>,
+    pub extra: Vec<String>,
+}
+
+/// State of one object of a snapshot found by an audit
+enum Audit {
+    Verified,
+    Missing,
+    /// Different data under a different ETag, written since the snapshot
+    Changed,
+    /// Different data under the recorded ETag or version
+    Corrupted,
+}
+
+/// Read an object and record it with its SHA-256
+async fn snapshot_entry(
+    store: &DynObjectStore,
+    prefix: &Path,
+    meta: ObjectMeta,
+) -> Result<SnapshotEntry> {
+    let options = GetOptions {
+        if_match: meta.e_tag.clone(),
+        version: meta.version.clone(),
+        ..Default::default()
+    };
+    let mut stream = store.get_opts(&meta.location, options).await?.into_stream();
+    let mut digest = Context::new(&SHA256);
+    while let Some(chunk) = stream.next().await {
+        digest.update(&chunk?);
+    }
+
+    Ok(SnapshotEntry {
+        path: entry_name(&meta.location, prefix),
+        size: meta.size as u64,
+        etag: meta.e_tag,
+        version: meta.version,
+        last_modified: meta.last_modified.to_rfc3339(),
+        sha256: hex(digest.finish().as_ref()),
+    })
+}
+
+/// List a prefix, checksum every object and write the manifest
+///
+/// Objects are read with the ETag and version they were listed with, so an
+/// object changing during the snapshot fails it instead of recording data
+/// that doesn't match its metadata.
+async fn take_snapshot(
+    store: &DynObjectStore,
+    prefix: &Path,
+    manifest_path: &Path,
+) -> Result<SnapshotManifest> {
+    let created_at = chrono::Utc::now().to_rfc3339();
+    let mut objects: Vec<ObjectMeta> = store.list(Some(prefix)).try_collect().await?;
+    objects.retain(|meta| meta.location != *manifest_path);
+    objects.sort_by(|a, b| a.location.cmp(&b.location));
+
+    let objects: Vec<SnapshotEntry> = futures::stream::iter(objects)
+        .map(|meta| snapshot_entry(store, prefix, meta))
+        .buffered(MAX_CONCURRENCY)
+        .try_collect()
+        .await?;
+
+    let manifest = SnapshotManifest {
+        prefix: prefix.to_string(),
+        created_at,
+        size: objects.iter().map(|entry| entry.size).sum(),
+        objects,
+    };
+    let json = serde_json::to_vec_pretty(&manifest).map_err(|e| object_store::Error::Generic {
+        store: "Snapshot",
+        source: Box::new(e),
+    })?;
+    store.put(manifest_path, json.into()).await?;
+    Ok(manifest)
+}
+
+/// Read and parse a snapshot manifest
+async fn read_manifest(store: &DynObjectStore, path: &Path) -> Result<SnapshotManifest> {
+    let body = store.get(path).await?.bytes().await?;
+    serde_json::from_slice(&body).map_err(|e| object_store::Error::Generic {
+        store: "Snapshot",
+        source: format!("Invalid snapshot manifest {}: {}", path, e).into(),
+    })
+}
+
+/// Location of a snapshot entry under a prefix
+fn entry_location(prefix: &Path, entry: &SnapshotEntry) -> Path {
+    Path::from_iter(
+        prefix
+            .parts()
+            .chain(Path::from(entry.path.as_str()).parts()),
+    )
+}
+
+/// Copy one object of a snapshot to `dest`, checking it against the manifest
+///
+/// The source is read at its recorded version, or only if it still has its
+/// recorded ETag, so objects changed since the snapshot fail with
+/// `:precondition_failed`. Data that doesn't match the recorded size and
+/// SHA-256 fails with `:checksum_mismatch`; the upload is aborted then.
+async fn restore_entry(
+    source: &DynObjectStore,
+    source_prefix: &Path,
+    dest: &Arc<DynObjectStore>,
+    dest_prefix: &Path,
+    entry: &SnapshotEntry,
+) -> Result<u64> {
+    let options = GetOptions {
+        if_match: entry
+            .version
+            .is_none()
+            .then(|| entry.etag.clone())
+            .flatten(),
+        version: entry.version.clone(),
+        ..Default::default()
+    };
+    let mut stream = source
+        .get_opts(&entry_location(source_prefix, entry), options)
+        .await?
+        .into_stream();
+
+    let mut writer =
+        BufWriter::with_capacity(dest.clone(), entry_location(dest_prefix, entry), PART_SIZE);
+    let copied = async {
+        let mut digest = Context::new(&SHA256);
+        let mut size = 0u64;
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            digest.update(&chunk);
+            size += chunk.len() as u64;
+            writer.put(chunk).await?;
+        }
+
+        if size != entry.size || hex(digest.finish().as_ref()) != entry.sha256 {
+            return Err(integrity_error(format!(
+                "{} doesn't match the snapshot",
+                entry.path
+            )));
+        }
+        Ok(size)
+    }
+    .await;
+
+    match copied {
+        Ok(size) => {
+            futures::future::poll_fn(|cx| Pin::new(&mut writer).poll_shutdown(cx))
+                .await
+                .map_err(|e| from_io_error("Snapshot", e))?;
+            Ok(size)
+        }
+        Err(e) => {
+            let _ = writer.abort().await;
+            Err(e)
+        }
+    }
+}
+
+/// Restore every object of a snapshot under `dest_prefix` of `dest`
+///
+/// Objects that can't be restored are reported rather than stopping the
+/// restore.
+async fn restore_snapshot(
+    source: &DynObjectStore,
+    manifest_path: &Path,
+    dest: &Arc<DynObjectStore>,
+    dest_prefix: Option<Path>,
+) -> Result<RestoreResultNif> {
+    let manifest = read_manifest(source, manifest_path).await?;
+    let source_prefix = Path::from(manifest.prefix.as_str());
+    let dest_prefix = dest_prefix.unwrap_or_else(|| source_prefix.clone());
+
+    let results: Vec<(&SnapshotEntry, Result<u64>)> = futures::stream::iter(&manifest.objects)
+        .map(|entry| {
+            let (source_prefix, dest_prefix) = (&source_prefix, &dest_prefix);
+            async move {
+                let result = restore_entry(source, source_prefix, dest, dest_prefix, entry).await;
+                (entry, result)
+            }
+        })
+        .buffered(MAX_CONCURRENCY)
+        .collect()
+        .await;
+
+    let mut result = RestoreResultNif {
+        restored: 0,
+        bytes: 0,
+        failed: Vec::new(),
+    };
+    for (entry, restored) in results {
+        match restored {
+            Ok(size) => {
+                result.restored += 1;
+                result.bytes += size;
+            }
+            Err(e) => result.failed.push(RestoreFailureNif {
+                path: entry.path.clone(),
+                reason: error_kind(&e),
+            }),
+        }
+    }
+    Ok(result)
+}
+
+/// Read an object of a snapshot and compare it with its entry
+///
+/// Objects are read at their recorded version when there is one.
+async fn audit_entry(
+    store: &DynObjectStore,
+    prefix: &Path,
+    entry: &SnapshotEntry,
+) -> Result<Audit> {
+    let options = GetOptions {
+        version: entry.version.clone(),
+        ..Default::default()
+    };
+    let result = match store
+        .get_opts(&entry_location(prefix, entry), options)
+        .await
+    {
+        Ok(result) => result,
+        Err(object_store::Error::NotFound { .. }) => return Ok(Audit::Missing),
+        Err(e) => return Err(e),
+    };
+    let same_etag = result.meta.e_tag == entry.etag;
+
+    let mut stream = result.into_stream();
+    let mut digest = Context::new(&SHA256);
+    let mut size = 0u64;
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk?;
+        digest.update(&chunk);
+        size += chunk.len() as u64;
+    }
+
+    let matches = size == entry.size && hex(digest.finish().as_ref()) == entry.sha256;
+    Ok(match (matches, same_etag) {
+        (true, _) => Audit::Verified,
+        (false, true) => Audit::Corrupted,
+        (false, false) => Audit::Changed,
+    })
+}
+
+/// Check every object of a snapshot and look for objects added under its
+/// prefix since
+async fn audit(store: &DynObjectStore, manifest_path: &Path) -> Result<VerifyResultNif> {
+    let manifest = read_manifest(store, manifest_path).await?;
+    let prefix = Path::from(manifest.prefix.as_str());
+
+    let recorded: HashSet<&str> = manifest.objects.iter().map(|e| e.path.as_str()).collect();
+    let listed: Vec<ObjectMeta> = store.list(Some(&prefix)).try_collect().await?;
+    let mut extra: Vec<String> = listed
+        .iter()
+        .filter(|meta| meta.location != *manifest_path)
+        .map(|meta| entry_name(&meta.location, &prefix))
+        .filter(|name| !recorded.contains(name.as_str()))
+        .collect();
+    extra.sort();
+
+    let audits: Vec<Audit> = futures::stream::iter(&manifest.objects)
+        .map(|entry| audit_entry(store, &prefix, entry))
+        .buffered(MAX_CONCURRENCY)
+        .try_collect()
+        .await?;
+
+    let mut result = VerifyResultNif {
+        verified: 0,
+        missing: Vec::new(),
+        changed: Vec::new(),
+        corrupted: Vec::new(),
+        extra,
+    };
+    for (entry, audit) in manifest.objects.iter().zip(audits) {
+        let path = entry.path.clone();
+        match audit {
+            Audit::Verified => result.verified += 1,
+            Audit::Missing => result.missing.push(path),
+            Audit::Changed => result.changed.push(path),
+            Audit::Corrupted => result.corrupted.push(path),
+        }
+    }
+    Ok(result)
+}
+
+/// Record every object under a prefix in a manifest at `manifest_path`
+///
+/// The manifest lists the objects' paths relative to the prefix with their
+/// sizes, ETags, versions, modification times and SHA-256 checksums, which
+/// are computed by reading every object. A manifest under the prefix isn't
+/// included. Returns a map with the manifest location, object count and
+/// total size.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn snapshot<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    prefix: String,
+    manifest_path: String,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let manifest_path = Path::from(manifest_path);
+
+    match RUNTIME.block_on(take_snapshot(
+        store.inner.as_ref(),
+        &Path::from(prefix),
+        &manifest_path,
+    )) {
+        Ok(manifest) => {
+            let result = SnapshotResultNif {
+                manifest: manifest_path.to_string(),
+                objects: manifest.objects.len() as u64,
+                size: manifest.size,
+            };
+            Ok((atoms::ok(), result).encode(env))
+        }
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
+
+/// Copy the objects of a snapshot into `dest` under `dest_prefix`, or under
+/// the snapshot's prefix when nil
+///
+/// Every object is verified against the manifest, see `restore_entry`.
+/// Returns `{:ok, %{restored, bytes, failed}}`, where `failed` lists the
+/// objects that couldn't be restored with the reason, or an error if the
+/// manifest can't be read.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn restore<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    manifest_path: String,
+    dest: ResourceArc<StoreWrapper>,
+    dest_prefix: Option<String>,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    match RUNTIME.block_on(restore_snapshot(
+        store.inner.as_ref(),
+        &Path::from(manifest_path),
+        &dest.inner,
+        dest_prefix.map(Path::from),
+    )) {
+        Ok(result) => Ok((atoms::ok(), result).encode(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
+
+/// Audit the prefix of a snapshot against its manifest
+///
+/// Every recorded object is read and checksummed, at its recorded version on
+/// versioned stores. Returns `{:ok, %{verified, missing, changed, corrupted,
+/// extra}}` with the paths, relative to the prefix, of objects that are gone,
+/// were overwritten since the snapshot, no longer match their checksum under
+/// the recorded ETag or version, or weren't in the snapshot.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn verify_prefix<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    manifest_path: String,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    match RUNTIME.block_on(audit(store.inner.as_ref(), &Path::from(manifest_path))) {
+        Ok(result) => Ok((atoms::ok(), result).encode(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
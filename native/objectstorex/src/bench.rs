@@ -0,0 +1,286 @@
+//! Standardized benchmark scenarios run natively against a store
+
+use crate::atoms;
+use crate::errors::map_error;
+use crate::shutdown;
+use crate::store::StoreWrapper;
+use crate::RUNTIME;
+use bytes::Bytes;
+use futures::{StreamExt, TryStreamExt};
+use object_store::path::Path;
+use object_store::{ObjectStore, Result, WriteMultipart};
+use rand::RngCore;
+use rustler::{Atom, Encoder, Env, NifMap, NifResult, ResourceArc, Term};
+use std::time::{Duration, Instant};
+
+/// Objects deleted concurrently during cleanup
+const CLEANUP_CONCURRENCY: usize = 16;
+
+/// Options for `bench`, with defaults filled in by Elixir
+#[derive(Debug, NifMap)]
+pub struct BenchOptionsNif {
+    /// Location the benchmark objects are written under
+    pub prefix: String,
+    /// Number of objects written or read per round
+    pub objects: usize,
+    pub object_size: usize,
+    /// Rounds over the objects; listings of the prefix for `:list`
+    pub iterations: usize,
+    /// Operations in flight at once
+    pub concurrency: usize,
+    /// Multipart part size for `:stream`
+    pub part_size: usize,
+    /// Parts uploading at once per object for `:stream`
+    pub part_concurrency: usize,
+    /// Delete the benchmark objects afterwards
+    pub cleanup: bool,
+}
+
+/// Latency distribution of successful operations, in microseconds
+#[derive(Debug, Default, NifMap)]
+pub struct LatencyNif {
+    pub min: u64,
+    pub mean: u64,
+    pub p50: u64,
+    pub p90: u64,
+    pub p99: u64,
+    pub max: u64,
+}
+
+/// Result of a benchmark run, as returned to Elixir
+#[derive(Debug, NifMap)]
+pub struct BenchResultNif {
+    pub scenario: Atom,
+    /// Operations that succeeded
+    pub operations: u64,
+    /// Operations that failed
+    pub errors: u64,
+    /// Bytes written or read by successful operations
+    pub bytes: u64,
+    pub elapsed_us: u64,
+    pub ops_per_sec: f64,
+    pub bytes_per_sec: f64,
+    pub latency_us: LatencyNif,
+}
+
+/// Benchmark scenario
+#[derive(Clone, Copy)]
+enum Scenario {
+    Put,
+    Get,
+    List,
+    Stream,
+}
+
+impl Scenario {
+    fn decode(scenario: Atom) -> NifResult<Self> {
+        if scenario == atoms::put() {
+            Ok(Scenario::Put)
+        } else if scenario == atoms::get() {
+            Ok(Scenario::Get)
+        } else if scenario == atoms::list() {
+            Ok(Scenario::List)
+        } else if scenario == atoms::stream() {
+            Ok(Scenario::Stream)
+        } else {
+            Err(rustler::Error::BadArg)
+        }
+    }
+}
+
+/// Nearest-rank percentile of sorted samples
+fn percentile(sorted: &[u64], p: usize) -> u64 {
+    let rank = (sorted.len() * p).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+fn latency(mut samples: Vec<u64>) -> LatencyNif {
+    if samples.is_empty() {
+        return LatencyNif::default();
+    }
+    samples.sort_unstable();
+
+    LatencyNif {
+        min: samples[0],
+        mean: samples.iter().sum::<u64>() / samples.len() as u64,
+        p50: percentile(&samples, 50),
+        p90: percentile(&samples, 90),
+        p99: percentile(&samples, 99),
+        max: samples[samples.len() - 1],
+    }
+}
+
+/// Upload `data` in `part_size` parts through a multipart upload
+async fn put_streamed(
+    store: &dyn ObjectStore,
+    location: &Path,
+    data: &Bytes,
+    part_size: usize,
+    part_concurrency: usize,
+) -> Result<()> {
+    let upload = store.put_multipart(location).await?;
+    let mut writer = WriteMultipart::new_with_chunk_size(upload, part_size);
+
+    let written = async {
+        for start in (0..data.len()).step_by(part_size) {
+            writer.wait_for_capacity(part_concurrency).await?;
+            writer.put(data.slice(start..(start + part_size).min(data.len())));
+        }
+        Ok(())
+    }
+    .await;
+
+    match written {
+        Ok(()) => writer.finish().await.map(|_| ()),
+        Err(e) => {
+            let _ = writer.abort().await;
+            Err(e)
+        }
+    }
+}
+
+/// Run one operation of a scenario, returning the bytes it moved
+async fn run_op(
+    store: &dyn ObjectStore,
+    scenario: Scenario,
+    opts: &BenchOptionsNif,
+    prefix: &Path,
+    location: &Path,
+    data: &Bytes,
+) -> Result<u64> {
+    match scenario {
+        Scenario::Put => {
+            store.put(location, data.clone().into()).await?;
+            Ok(data.len() as u64)
+        }
+        Scenario::Get => {
+            let mut stream = store.get(location).await?.into_stream();
+            let mut read = 0;
+            while let Some(chunk) = stream.try_next().await? {
+                read += chunk.len() as u64;
+            }
+            Ok(read)
+        }
+        Scenario::List => {
+            store.list(Some(prefix)).try_collect::<Vec<_>>().await?;
+            Ok(0)
+        }
+        Scenario::Stream => {
+            put_streamed(store, location, data, opts.part_size, opts.part_concurrency).await?;
+            Ok(data.len() as u64)
+        }
+    }
+}
+
+async fn run(
+    store: &dyn ObjectStore,
+    scenario: Scenario,
+    scenario_atom: Atom,
+    opts: &BenchOptionsNif,
+) -> Result<BenchResultNif> {
+    let prefix = Path::from(opts.prefix.as_str());
+    let locations: Vec<Path> = (0..opts.objects)
+        .map(|i| prefix.child(format!("{:08}", i)))
+        .collect();
+
+    let mut data = vec![0u8; opts.object_size];
+    rand::thread_rng().fill_bytes(&mut data);
+    let data = Bytes::from(data);
+
+    // Reads need objects to read; they aren't part of the measurement
+    if matches!(scenario, Scenario::Get | Scenario::List) {
+        futures::stream::iter(&locations)
+            .map(|location| store.put(location, data.clone().into()))
+            .buffer_unordered(opts.concurrency)
+            .try_collect::<Vec<_>>()
+            .await?;
+    }
+
+    let ops: Vec<&Path> = match scenario {
+        Scenario::List => vec![&prefix; opts.iterations],
+        _ => (0..opts.iterations).flat_map(|_| &locations).collect(),
+    };
+
+    let started = Instant::now();
+    let outcomes: Vec<(Duration, Result<u64>)> = futures::stream::iter(ops)
+        .map(|location| {
+            let data = &data;
+            let prefix = &prefix;
+            async move {
+                let started = Instant::now();
+                let outcome = run_op(store, scenario, opts, prefix, location, data).await;
+                (started.elapsed(), outcome)
+            }
+        })
+        .buffer_unordered(opts.concurrency)
+        .collect()
+        .await;
+    let elapsed = started.elapsed();
+
+    if opts.cleanup {
+        futures::stream::iter(&locations)
+            .map(|location| store.delete(location))
+            .buffer_unordered(CLEANUP_CONCURRENCY)
+            .for_each(|_| async {})
+            .await;
+    }
+
+    let mut samples = Vec::with_capacity(outcomes.len());
+    let mut errors = 0;
+    let mut bytes = 0;
+    for (took, outcome) in outcomes {
+        match outcome {
+            Ok(moved) => {
+                samples.push(took.as_micros() as u64);
+                bytes += moved;
+            }
+            Err(_) => errors += 1,
+        }
+    }
+
+    let operations = samples.len() as u64;
+    let secs = elapsed.as_secs_f64();
+    let rate = |n: u64| if secs > 0.0 { n as f64 / secs } else { 0.0 };
+
+    Ok(BenchResultNif {
+        scenario: scenario_atom,
+        operations,
+        errors,
+        bytes,
+        elapsed_us: elapsed.as_micros() as u64,
+        ops_per_sec: rate(operations),
+        bytes_per_sec: rate(bytes),
+        latency_us: latency(samples),
+    })
+}
+
+/// Run a benchmark scenario against a store
+///
+/// `:put` writes `objects` objects of `object_size` bytes, `:get` reads them
+/// back after writing them unmeasured, `:list` lists the prefix holding them,
+/// and `:stream` writes them through multipart uploads of `part_size` parts.
+/// Writes and reads are repeated for each iteration. Failed operations are
+/// counted rather than aborting the run; failing to write the objects a read
+/// scenario needs returns the error.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn bench<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    scenario: Atom,
+    opts: BenchOptionsNif,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let kind = Scenario::decode(scenario)?;
+    if opts.objects == 0
+        || opts.concurrency == 0
+        || opts.part_size == 0
+        || opts.part_concurrency == 0
+    {
+        return Err(rustler::Error::BadArg);
+    }
+
+    match RUNTIME.block_on(run(store.inner.as_ref(), kind, scenario, &opts)) {
+        Ok(result) => Ok((atoms::ok(), result).encode(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
@@ -0,0 +1,180 @@
+//! Reader sessions with random access on top of `object_store::buffered::BufReader`
+
+use crate::atoms;
+use crate::errors::{from_io_error, map_error};
+use crate::shutdown;
+use crate::store::StoreWrapper;
+use crate::RUNTIME;
+use futures::future::poll_fn;
+use object_store::buffered::BufReader;
+use object_store::path::Path;
+use object_store::DynObjectStore;
+use rustler::{Atom, Binary, Encoder, Env, NifResult, OwnedBinary, ResourceArc, Term};
+use std::io::{ErrorKind, SeekFrom};
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::Poll;
+use tokio::io::{AsyncBufRead, AsyncSeek};
+use tokio::sync::Mutex as TokioMutex;
+
+/// Reader of an object, fetching `capacity` bytes at a time from its current
+/// position with ranged gets
+pub struct BufReaderWrapper {
+    reader: TokioMutex<BufReader>,
+    store: Arc<DynObjectStore>,
+    path: Path,
+    size: u64,
+}
+
+fn from_io(error: std::io::Error) -> object_store::Error {
+    from_io_error("BufReader", error)
+}
+
+/// Copy a read into a new binary term
+fn to_binary<'a>(env: Env<'a>, data: &[u8]) -> Binary<'a> {
+    let mut binary = OwnedBinary::new(data.len()).unwrap();
+    binary.as_mut_slice().copy_from_slice(data);
+    binary.release(env)
+}
+
+/// Open a reader on an object
+///
+/// The object's size is looked up once, so data appended later isn't read.
+/// Raises ArgumentError if `capacity` is zero.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn open_reader<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+    capacity: usize,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    if capacity == 0 {
+        return Err(rustler::Error::BadArg);
+    }
+
+    let path = Path::from(path);
+    let meta = match RUNTIME.block_on(store.inner.head(&path)) {
+        Ok(meta) => meta,
+        Err(e) => return Ok(map_error(e).to_term(env)),
+    };
+
+    let resource = ResourceArc::new(BufReaderWrapper {
+        reader: TokioMutex::new(BufReader::with_capacity(
+            store.inner.clone(),
+            &meta,
+            capacity,
+        )),
+        store: store.inner.clone(),
+        path,
+        size: meta.size as u64,
+    });
+    Ok((atoms::ok(), resource).encode(env))
+}
+
+/// Read up to `length` bytes at the reader's position, advancing it
+///
+/// Returns `{:ok, data}`, shorter than `length` only at the end of the
+/// object, or `:eof` once there is nothing left to read.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn reader_read<'a>(
+    env: Env<'a>,
+    session: ResourceArc<BufReaderWrapper>,
+    length: usize,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let result = RUNTIME.block_on(async {
+        let mut reader = session.reader.lock().await;
+        let mut data = Vec::with_capacity(length.min(session.size as usize));
+
+        while data.len() < length {
+            let read = poll_fn(|cx| {
+                let mut reader = Pin::new(&mut *reader);
+                let take = match reader.as_mut().poll_fill_buf(cx) {
+                    Poll::Ready(Ok(buf)) => {
+                        let take = buf.len().min(length - data.len());
+                        data.extend_from_slice(&buf[..take]);
+                        take
+                    }
+                    Poll::Ready(Err(e)) => return Poll::Ready(Err(e)),
+                    Poll::Pending => return Poll::Pending,
+                };
+                reader.consume(take);
+                Poll::Ready(Ok(take))
+            })
+            .await
+            .map_err(from_io)?;
+
+            if read == 0 {
+                break;
+            }
+        }
+        Ok(data)
+    });
+
+    match result {
+        Ok(data) if data.is_empty() && length > 0 => Ok(atoms::eof().to_term(env)),
+        Ok(data) => Ok((atoms::ok(), to_binary(env, &data)).encode(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
+
+/// Move the reader's position, relative to the beginning (`:bof`), the
+/// current position (`:cur`) or the end of the object (`:eof`)
+///
+/// Returns `{:ok, position}`. Positions past the end are allowed, and read as
+/// `:eof`. Raises ArgumentError for positions before the beginning.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn reader_seek<'a>(
+    env: Env<'a>,
+    session: ResourceArc<BufReaderWrapper>,
+    whence: Atom,
+    offset: i64,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let position = match whence {
+        w if w == atoms::bof() => {
+            SeekFrom::Start(u64::try_from(offset).map_err(|_| rustler::Error::BadArg)?)
+        }
+        w if w == atoms::cur() => SeekFrom::Current(offset),
+        w if w == atoms::eof() => SeekFrom::End(offset),
+        _ => return Err(rustler::Error::BadArg),
+    };
+
+    let result = RUNTIME.block_on(async {
+        let mut reader = session.reader.lock().await;
+        Pin::new(&mut *reader).start_seek(position)?;
+        poll_fn(|cx| Pin::new(&mut *reader).poll_complete(cx)).await
+    });
+
+    match result {
+        Ok(position) => Ok((atoms::ok(), position).encode(env)),
+        Err(e) if e.kind() == ErrorKind::InvalidInput => Err(rustler::Error::BadArg),
+        Err(e) => Ok(map_error(from_io(e)).to_term(env)),
+    }
+}
+
+/// Read `length` bytes starting at `offset` with a single ranged get, without
+/// moving the reader's position
+///
+/// Returns `{:ok, data}`, shorter than `length` if the range extends past the
+/// end of the object.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn reader_read_range<'a>(
+    env: Env<'a>,
+    session: ResourceArc<BufReaderWrapper>,
+    offset: u64,
+    length: u64,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let start = offset.min(session.size) as usize;
+    let end = offset.saturating_add(length).min(session.size) as usize;
+    if start == end {
+        return Ok((atoms::ok(), to_binary(env, &[])).encode(env));
+    }
+
+    match RUNTIME.block_on(session.store.get_range(&session.path, start..end)) {
+        Ok(data) => Ok((atoms::ok(), to_binary(env, &data)).encode(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
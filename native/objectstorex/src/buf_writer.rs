@@ -0,0 +1,202 @@
+//! Buffered writer sessions on top of `object_store::buffered::BufWriter`
+
+use crate::atoms;
+use crate::errors::{from_io_error, map_error};
+use crate::operations::{put_attributes, tag_set};
+use crate::shutdown::{self, UploadRegistration};
+use crate::store::StoreWrapper;
+use crate::types::AttributesNif;
+use crate::RUNTIME;
+use bytes::Bytes;
+use futures::FutureExt;
+use object_store::buffered::BufWriter;
+use object_store::path::Path;
+use rustler::{Binary, Encoder, Env, NifResult, ResourceArc, Term};
+use std::pin::Pin;
+use std::sync::Arc;
+use tokio::io::AsyncWrite;
+use tokio::sync::Mutex as TokioMutex;
+
+/// Writer session buffering up to `capacity` bytes before switching from a
+/// single put to a multipart upload with parts of `capacity` bytes
+///
+/// The writer is `None` once it is closed or aborted. Dropping an unfinished
+/// session aborts its upload, like dropping an upload session.
+pub struct BufWriterWrapper {
+    writer: Arc<TokioMutex<Option<BufWriter>>>,
+    /// Aborts the upload on shutdown until it is closed or aborted
+    registration: std::sync::Mutex<Option<UploadRegistration>>,
+}
+
+impl BufWriterWrapper {
+    /// Take the writer out of the session, which is then finished
+    async fn take(&self) -> object_store::Result<BufWriter> {
+        let writer = self.writer.lock().await.take().ok_or_else(closed)?;
+        self.registration.lock().unwrap().take();
+        Ok(writer)
+    }
+}
+
+impl Drop for BufWriterWrapper {
+    fn drop(&mut self) {
+        let unfinished = self.registration.get_mut().unwrap().take().is_some();
+        if !unfinished {
+            return;
+        }
+
+        let writer = self.writer.clone();
+        RUNTIME.try_spawn(async move {
+            if let Some(mut writer) = writer.lock().await.take() {
+                let _ = writer.abort().await;
+            }
+        });
+    }
+}
+
+fn closed() -> object_store::Error {
+    object_store::Error::Generic {
+        store: "BufWriter",
+        source: "Writer is already closed or aborted".into(),
+    }
+}
+
+fn from_io(error: std::io::Error) -> object_store::Error {
+    from_io_error("BufWriter", error)
+}
+
+/// Open a buffered writer session
+///
+/// Raises ArgumentError if `capacity` or `max_concurrency` is zero.
+#[rustler::nif]
+pub fn open_writer<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+    capacity: usize,
+    max_concurrency: usize,
+    attributes: AttributesNif,
+    tags: Vec<(String, String)>,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    if capacity == 0 || max_concurrency == 0 {
+        return Err(rustler::Error::BadArg);
+    }
+
+    let writer = BufWriter::with_capacity(store.inner.clone(), Path::from(path), capacity)
+        .with_max_concurrency(max_concurrency)
+        .with_attributes(put_attributes(attributes))
+        .with_tags(tag_set(&tags));
+
+    let writer = Arc::new(TokioMutex::new(Some(writer)));
+    let upload = Arc::downgrade(&writer);
+    let registration = shutdown::register_upload(false, move || {
+        let writer = upload.upgrade()?;
+        Some(
+            async move {
+                if let Some(mut writer) = writer.lock().await.take() {
+                    let _ = writer.abort().await;
+                }
+            }
+            .boxed(),
+        )
+    });
+
+    let resource = ResourceArc::new(BufWriterWrapper {
+        writer,
+        registration: std::sync::Mutex::new(Some(registration)),
+    });
+    Ok((atoms::ok(), resource).encode(env))
+}
+
+/// Write data to a writer session
+///
+/// Waits while `max_concurrency` parts are uploading. A failed write aborts
+/// the session.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn writer_write<'a>(
+    env: Env<'a>,
+    session: ResourceArc<BufWriterWrapper>,
+    data: Binary,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let data = Bytes::copy_from_slice(data.as_slice());
+
+    let result = RUNTIME.block_on(async {
+        let mut guard = session.writer.lock().await;
+        let writer = guard.as_mut().ok_or_else(closed)?;
+        let result = writer.put(data).await;
+        if result.is_err() {
+            let _ = writer.abort().await;
+            guard.take();
+        }
+        result
+    });
+    if result.is_err() {
+        session.registration.lock().unwrap().take();
+    }
+
+    match result {
+        Ok(()) => Ok(atoms::ok().to_term(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
+
+/// Flush a writer session
+///
+/// Waits until the multipart upload the writer switched to, if any, has been
+/// created, so failures to start it are reported here rather than on close.
+/// Data below the capacity stays buffered until close.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn writer_flush<'a>(
+    env: Env<'a>,
+    session: ResourceArc<BufWriterWrapper>,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let result = RUNTIME.block_on(async {
+        let mut guard = session.writer.lock().await;
+        let writer = guard.as_mut().ok_or_else(closed)?;
+        futures::future::poll_fn(|cx| Pin::new(&mut *writer).poll_flush(cx))
+            .await
+            .map_err(from_io)
+    });
+
+    match result {
+        Ok(()) => Ok(atoms::ok().to_term(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
+
+/// Close a writer session, writing buffered data and completing the upload
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn writer_close<'a>(
+    env: Env<'a>,
+    session: ResourceArc<BufWriterWrapper>,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let result = RUNTIME.block_on(async {
+        let mut writer = session.take().await?;
+        futures::future::poll_fn(|cx| Pin::new(&mut writer).poll_shutdown(cx))
+            .await
+            .map_err(from_io)
+    });
+
+    match result {
+        Ok(()) => Ok(atoms::ok().to_term(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
+
+/// Abort a writer session, discarding written data
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn writer_abort<'a>(
+    env: Env<'a>,
+    session: ResourceArc<BufWriterWrapper>,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let result = RUNTIME.block_on(async { session.take().await?.abort().await });
+
+    match result {
+        Ok(()) => Ok(atoms::ok().to_term(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
@@ -1,10 +1,80 @@
+use crate::append::{Append, LocalAppend, RewriteAppend};
+use crate::gcs_api::GcsApi;
+use crate::incomplete_uploads::IncompleteUploads;
+use crate::paging::PagedListing;
+use crate::s3_api::S3Api;
+use crate::shutdown;
 use crate::store::StoreWrapper;
+use crate::tokens::{Token, TokenProvider};
+use crate::types::{LocalOptionsNif, S3OptionsNif};
+use crate::versions::Versioning;
+use crate::wrappers::hidden_records::HiddenRecordsStore;
+use crate::wrappers::inflight::InflightStore;
+use crate::wrappers::local_copy::{CopyStrategy, LocalCopyStore};
+use crate::wrappers::permissions::PermissionsStore;
+use crate::wrappers::provider_limits::{self, ProviderLimits, ProviderLimitsStore};
+use crate::wrappers::request_limit::RequestLimitStore;
+use crate::RUNTIME;
 use object_store::{
-    aws::AmazonS3Builder, azure::MicrosoftAzureBuilder, gcp::GoogleCloudStorageBuilder,
-    local::LocalFileSystem, memory::InMemory,
+    aws::{AmazonS3Builder, Checksum},
+    azure::{AzureCredential, MicrosoftAzureBuilder},
+    gcp::{GcpCredential, GoogleCloudStorageBuilder},
+    http::HttpBuilder,
+    local::LocalFileSystem,
+    memory::InMemory,
+    multipart::MultipartStore,
+    path::Path,
+    ClientOptions, DynObjectStore, ObjectStore,
 };
-use rustler::{NifResult, ResourceArc};
-use std::sync::Arc;
+use once_cell::sync::Lazy;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use rustler::{Binary, LocalPid, NifResult, ResourceArc};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, Weak};
+
+/// Named in-memory stores, alive as long as any handle to them is
+static NAMED_MEMORY: Lazy<Mutex<HashMap<String, Weak<InMemory>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+/// Validate the provider's documented limits before requests are sent
+///
+/// Only the object store API is checked; the multipart and S3 handles of the
+/// wrapper talk to the client directly.
+fn with_limits(mut wrapper: StoreWrapper, limits: ProviderLimits) -> StoreWrapper {
+    wrapper.inner = Arc::new(ProviderLimitsStore::new(wrapper.inner, limits));
+    wrapper
+}
+
+/// Register the calls of a new store with `list_inflight_operations`
+///
+/// The store's listings also leave out the expiry records of `put_with_ttl`,
+/// so they aren't copied, deleted or counted as objects.
+pub(crate) fn tracked(mut wrapper: StoreWrapper) -> ResourceArc<StoreWrapper> {
+    wrapper.inner = Arc::new(InflightStore::new(Arc::new(HiddenRecordsStore::new(
+        wrapper.inner,
+    ))));
+    wrapper.multipart = wrapper
+        .multipart
+        .map(|multipart| Arc::new(InflightStore::new(multipart)) as Arc<dyn MultipartStore>);
+    wrapper.checksummed = wrapper
+        .checksummed
+        .map(|store| Arc::new(InflightStore::new(store)) as Arc<DynObjectStore>);
+    wrapper.versioning = wrapper
+        .versioning
+        .map(|versioning| Arc::new(InflightStore::new(versioning)) as Arc<dyn Versioning>);
+    wrapper.uploads = wrapper
+        .uploads
+        .map(|uploads| Arc::new(InflightStore::new(uploads)) as Arc<dyn IncompleteUploads>);
+    wrapper.paged = wrapper.paged.map(|paged| {
+        Arc::new(InflightStore::new(Arc::new(HiddenRecordsStore::new(paged))))
+            as Arc<dyn PagedListing>
+    });
+    wrapper.append = wrapper
+        .append
+        .map(|append| Arc::new(InflightStore::new(append)) as Arc<dyn Append>);
+    ResourceArc::new(wrapper)
+}
 
 /// Create a new S3 object store
 #[rustler::nif]
@@ -15,9 +85,54 @@ pub fn new_s3(
     secret_access_key: Option<String>,
     endpoint: Option<String>,
 ) -> NifResult<ResourceArc<StoreWrapper>> {
-    let mut builder = AmazonS3Builder::new().with_bucket_name(bucket);
+    build_s3(
+        bucket,
+        region,
+        access_key_id,
+        secret_access_key,
+        endpoint,
+        S3OptionsNif::default(),
+    )
+}
+
+/// Create a new S3 object store with server-side encryption and other options
+///
+/// Uploads, copies and multipart uploads request the configured encryption.
+/// With a customer key (SSE-C), the key is also sent with every read, so
+/// objects written by the store can be read back through it. With
+/// `request_payer`, every request agrees to be charged for requester-pays
+/// buckets. With `virtual_hosted_style`, the bucket is addressed by host name
+/// and a custom endpoint must include it.
+#[rustler::nif]
+pub fn new_s3_with_options(
+    bucket: String,
+    region: Option<String>,
+    access_key_id: Option<String>,
+    secret_access_key: Option<String>,
+    endpoint: Option<String>,
+    options: S3OptionsNif,
+) -> NifResult<ResourceArc<StoreWrapper>> {
+    build_s3(
+        bucket,
+        region,
+        access_key_id,
+        secret_access_key,
+        endpoint,
+        options,
+    )
+}
+
+fn build_s3(
+    bucket: String,
+    region: Option<String>,
+    access_key_id: Option<String>,
+    secret_access_key: Option<String>,
+    endpoint: Option<String>,
+    options: S3OptionsNif,
+) -> NifResult<ResourceArc<StoreWrapper>> {
+    let mut builder = AmazonS3Builder::new().with_bucket_name(&bucket);
 
-    if let Some(region) = region {
+    if let Some(region) = &region {
         builder = builder.with_region(region);
     }
 
@@ -29,15 +144,69 @@ pub fn new_s3(
         builder = builder.with_secret_access_key(secret);
     }
 
-    if let Some(ep) = endpoint {
+    if let Some(ep) = &endpoint {
         builder = builder.with_endpoint(ep);
     }
 
-    let store = builder
+    if let Some(sse) = &options.sse {
+        builder = sse.apply(builder, options.sse_bucket_key);
+    }
+
+    let virtual_hosted_style = options.virtual_hosted_style.unwrap_or(false);
+    builder = builder.with_virtual_hosted_style_request(virtual_hosted_style);
+
+    // Shared with the S3 API client, so its requests go through the same
+    // proxy, timeouts and TLS settings
+    let mut client_options = ClientOptions::new();
+    if let Some(allow_http) = options.allow_http {
+        client_options = client_options.with_allow_http(allow_http);
+    }
+    builder = builder.with_client_options(client_options.clone());
+
+    let request_payer = options.request_payer.unwrap_or(false);
+    if request_payer {
+        builder = builder.with_request_payer(true);
+    }
+
+    let build_error =
+        |e: object_store::Error| rustler::Error::Term(Box::new(format!("S3 build error: {}", e)));
+    let checksummed = builder
+        .clone()
+        .with_checksum_algorithm(Checksum::SHA256)
         .build()
-        .map_err(|e| rustler::Error::Term(Box::new(format!("S3 build error: {}", e))))?;
+        .map_err(build_error)?;
+    let client = Arc::new(builder.build().map_err(build_error)?);
+    let store = Arc::new(RequestLimitStore::new(client.clone()));
 
-    Ok(ResourceArc::new(StoreWrapper::new(Arc::new(store))))
+    let mut s3 = S3Api::new(
+        client,
+        &client_options,
+        &bucket,
+        region.as_deref().unwrap_or("us-east-1"),
+        endpoint.as_deref(),
+    )
+    .map_err(build_error)?;
+    if let Some(sse) = &options.sse {
+        s3 = s3.with_encryption(sse, options.sse_bucket_key);
+    }
+    if request_payer {
+        s3 = s3.with_request_payer();
+    }
+    if virtual_hosted_style {
+        s3 = s3.with_virtual_hosted_style(endpoint.as_deref());
+    }
+    let s3 = Arc::new(s3);
+    let mut wrapper = with_limits(StoreWrapper::with_multipart(store), provider_limits::S3);
+    wrapper.s3 = Some(s3.clone());
+    wrapper.versioning = Some(s3.clone());
+    wrapper.uploads = Some(s3.clone());
+    wrapper.paged = Some(s3);
+    wrapper.checksummed = Some(Arc::new(ProviderLimitsStore::new(
+        Arc::new(RequestLimitStore::new(Arc::new(checksummed))),
+        provider_limits::S3,
+    )));
+
+    Ok(tracked(wrapper))
 }
 
 /// Create a new Azure Blob Storage object store
@@ -55,11 +224,38 @@ pub fn new_azure(
         builder = builder.with_access_key(key);
     }
 
+    build_azure(builder)
+}
+
+/// Create an Azure Blob Storage object store authorized with bearer tokens
+///
+/// `token` is a pre-fetched `{token, expires_at}`. Shortly before it expires,
+/// `token_server` is asked for a new one (see `TokenProvider`).
+#[rustler::nif]
+pub fn new_azure_with_token(
+    account: String,
+    container: String,
+    token: Option<Token>,
+    token_server: Option<LocalPid>,
+) -> NifResult<ResourceArc<StoreWrapper>> {
+    let credentials = TokenProvider::new(token, token_server, AzureCredential::BearerToken);
+    let builder = MicrosoftAzureBuilder::new()
+        .with_account(account)
+        .with_container_name(container)
+        .with_credentials(Arc::new(credentials));
+
+    build_azure(builder)
+}
+
+fn build_azure(builder: MicrosoftAzureBuilder) -> NifResult<ResourceArc<StoreWrapper>> {
     let store = builder
         .build()
         .map_err(|e| rustler::Error::Term(Box::new(format!("Azure build error: {}", e))))?;
 
-    Ok(ResourceArc::new(StoreWrapper::new(Arc::new(store))))
+    Ok(tracked(with_limits(
+        StoreWrapper::with_multipart(Arc::new(RequestLimitStore::new(Arc::new(store)))),
+        provider_limits::AZURE,
+    )))
 }
 
 /// Create a new Google Cloud Storage object store
@@ -68,31 +264,222 @@ pub fn new_gcs(
     bucket: String,
     service_account_key: Option<String>,
 ) -> NifResult<ResourceArc<StoreWrapper>> {
-    let mut builder = GoogleCloudStorageBuilder::new().with_bucket_name(bucket);
+    let mut builder = GoogleCloudStorageBuilder::new().with_bucket_name(&bucket);
 
     if let Some(key) = service_account_key {
         builder = builder.with_service_account_key(key);
     }
 
+    build_gcs(builder, &bucket)
+}
+
+/// Create a Google Cloud Storage object store authorized with bearer tokens
+///
+/// `token` is a pre-fetched `{token, expires_at}`. Shortly before it expires,
+/// `token_server` is asked for a new one (see `TokenProvider`).
+#[rustler::nif]
+pub fn new_gcs_with_token(
+    bucket: String,
+    token: Option<Token>,
+    token_server: Option<LocalPid>,
+) -> NifResult<ResourceArc<StoreWrapper>> {
+    let credentials = TokenProvider::new(token, token_server, |bearer| GcpCredential { bearer });
+    let builder = GoogleCloudStorageBuilder::new()
+        .with_bucket_name(&bucket)
+        .with_credentials(Arc::new(credentials));
+
+    build_gcs(builder, &bucket)
+}
+
+fn build_gcs(
+    builder: GoogleCloudStorageBuilder,
+    bucket: &str,
+) -> NifResult<ResourceArc<StoreWrapper>> {
     let store = builder
         .build()
         .map_err(|e| rustler::Error::Term(Box::new(format!("GCS build error: {}", e))))?;
+    let client = Arc::new(store);
+
+    let mut wrapper = with_limits(
+        StoreWrapper::with_multipart(Arc::new(RequestLimitStore::new(client.clone()))),
+        provider_limits::GCS,
+    );
+    let gcs = Arc::new(GcsApi::new(client, bucket));
+    wrapper.versioning = Some(gcs.clone());
+    wrapper.uploads = Some(gcs.clone());
+    wrapper.paged = Some(gcs);
+
+    Ok(tracked(wrapper))
+}
+
+/// Create a new HTTP/WebDAV object store
+///
+/// Reads work against any HTTP server; writes, deletes and listings need
+/// WebDAV. Plain `http://` URLs are allowed since the caller chose them
+/// explicitly. `headers` are sent with every request (e.g. authorization).
+#[rustler::nif]
+pub fn new_http(
+    url: String,
+    headers: Vec<(String, String)>,
+) -> NifResult<ResourceArc<StoreWrapper>> {
+    let http_error = |e: String| rustler::Error::Term(Box::new(format!("HTTP build error: {}", e)));
+
+    let mut header_map = HeaderMap::new();
+    for (name, value) in headers {
+        let name = HeaderName::try_from(name).map_err(|e| http_error(e.to_string()))?;
+        let value = HeaderValue::try_from(value).map_err(|e| http_error(e.to_string()))?;
+        header_map.insert(name, value);
+    }
+
+    let options = ClientOptions::new()
+        .with_allow_http(url.starts_with("http://"))
+        .with_default_headers(header_map);
+
+    let store = HttpBuilder::new()
+        .with_url(url)
+        .with_client_options(options)
+        .build()
+        .map_err(|e| http_error(e.to_string()))?;
+
+    Ok(tracked(StoreWrapper::new(Arc::new(
+        RequestLimitStore::new(Arc::new(store)),
+    ))))
+}
+
+/// Use native separators in a Windows root
+///
+/// Windows doesn't normalize verbatim (`\\?\`) paths, so a long-path root
+/// written with `/` separators would otherwise name a non-existent directory.
+/// Deep locations below the root don't need the prefix: the standard library
+/// adds it itself when a path exceeds `MAX_PATH`.
+fn normalize_windows_root(path: &str) -> String {
+    path.replace('/', "\\")
+}
+
+/// Resolve the root directory of a local store
+fn local_root(path: &str) -> NifResult<PathBuf> {
+    let path = if cfg!(windows) {
+        normalize_windows_root(path)
+    } else {
+        path.to_string()
+    };
+
+    std::path::absolute(path)
+        .map_err(|e| rustler::Error::Term(Box::new(format!("Local FS error: {}", e))))
+}
 
-    Ok(ResourceArc::new(StoreWrapper::new(Arc::new(store))))
+/// Wrap a local store so copies use reflinks or hard links
+fn local_copies(fs: Arc<LocalFileSystem>, strategy: CopyStrategy) -> Arc<DynObjectStore> {
+    Arc::new(LocalCopyStore::new(fs.clone(), fs, strategy))
+}
+
+/// Wrap a local store, which appends to files in place
+fn local_wrapper(store: Arc<DynObjectStore>, fs: Arc<LocalFileSystem>) -> StoreWrapper {
+    let mut wrapper = StoreWrapper::new(store);
+    wrapper.append = Some(Arc::new(LocalAppend::new(fs)));
+    wrapper
 }
 
 /// Create a new local filesystem object store
+///
+/// Relative roots are resolved against the current directory. Object
+/// locations always use `/` separators, on every platform.
 #[rustler::nif]
 pub fn new_local(path: String) -> NifResult<ResourceArc<StoreWrapper>> {
-    let store = LocalFileSystem::new_with_prefix(path)
+    let fs = LocalFileSystem::new_with_prefix(local_root(&path)?)
         .map_err(|e| rustler::Error::Term(Box::new(format!("Local FS error: {}", e))))?;
+    let fs = Arc::new(fs);
+
+    let store = local_copies(fs.clone(), CopyStrategy::Auto);
+    Ok(tracked(local_wrapper(store, fs)))
+}
+
+/// Create a new local filesystem object store with options
+///
+/// Without a path the store has no prefix, and locations address the whole
+/// filesystem from `/`. `create_path` creates a missing root directory
+/// instead of failing, and `automatic_cleanup` removes directories that
+/// deletes leave empty.
+///
+/// Written files and the directories created for them get the configured mode
+/// bits regardless of the process umask, so other system users can consume
+/// them. `copy_strategy` picks how copies are made (see `LocalCopyStore`).
+#[rustler::nif]
+pub fn new_local_with_options(
+    path: Option<String>,
+    options: LocalOptionsNif,
+) -> NifResult<ResourceArc<StoreWrapper>> {
+    let strategy = CopyStrategy::from_atom(options.copy_strategy)?;
+    let local_error = |e: &dyn std::fmt::Display| {
+        rustler::Error::Term(Box::new(format!("Local FS error: {}", e)))
+    };
+
+    let (store, root) = match path {
+        Some(path) => {
+            let path = local_root(&path)?;
+            if options.create_path {
+                std::fs::create_dir_all(&path).map_err(|e| local_error(&e))?;
+            }
+            let store = LocalFileSystem::new_with_prefix(&path).map_err(|e| local_error(&e))?;
+            let root = std::fs::canonicalize(&path).map_err(|e| local_error(&e))?;
+            (store, root)
+        }
+        None => (LocalFileSystem::new(), PathBuf::from("/")),
+    };
+    let fs = Arc::new(store.with_automatic_cleanup(options.automatic_cleanup));
+    let store = local_copies(fs.clone(), strategy);
+
+    if options.file_mode.is_none() && options.dir_mode.is_none() {
+        return Ok(tracked(local_wrapper(store, fs)));
+    }
+
+    let store = PermissionsStore::new(store, fs.clone(), root, options.file_mode, options.dir_mode);
 
-    Ok(ResourceArc::new(StoreWrapper::new(Arc::new(store))))
+    Ok(tracked(local_wrapper(Arc::new(store), fs)))
+}
+
+/// Wrap an in-memory store, which appends by rewriting objects
+fn memory_wrapper(store: Arc<InMemory>) -> StoreWrapper {
+    let mut wrapper = StoreWrapper::with_multipart(store.clone());
+    wrapper.append = Some(Arc::new(RewriteAppend::new(store)));
+    wrapper
 }
 
 /// Create a new in-memory object store
 #[rustler::nif]
 pub fn new_memory() -> NifResult<ResourceArc<StoreWrapper>> {
-    let store = InMemory::new();
-    Ok(ResourceArc::new(StoreWrapper::new(Arc::new(store))))
+    Ok(tracked(memory_wrapper(Arc::new(InMemory::new()))))
+}
+
+/// Create an in-memory object store, optionally shared by name and seeded
+///
+/// Stores created with the same name share their contents for as long as a
+/// handle to them is alive. `objects` are written only when the store is
+/// created, not when an existing named store is returned.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn new_memory_with_options(
+    name: Option<String>,
+    objects: Vec<(String, Binary)>,
+) -> NifResult<ResourceArc<StoreWrapper>> {
+    shutdown::ensure_running()?;
+    let mut registry = NAMED_MEMORY.lock().unwrap();
+    registry.retain(|_, store| store.strong_count() > 0);
+
+    if let Some(store) = name.as_ref().and_then(|name| registry.get(name)?.upgrade()) {
+        return Ok(tracked(memory_wrapper(store)));
+    }
+
+    let store = Arc::new(InMemory::new());
+    for (path, data) in objects {
+        let payload = data.as_slice().to_vec().into();
+        RUNTIME
+            .block_on(store.put(&Path::from(path), payload))
+            .map_err(|e| rustler::Error::Term(Box::new(format!("Memory seed error: {}", e))))?;
+    }
+
+    if let Some(name) = name {
+        registry.insert(name, Arc::downgrade(&store));
+    }
+
+    Ok(tracked(memory_wrapper(store)))
 }
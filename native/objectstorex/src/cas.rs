@@ -0,0 +1,143 @@
+//! Content-addressed storage: objects named by the SHA-256 digest of their
+//! data
+
+use crate::atoms;
+use crate::checksum::{digest, ChecksumAlgorithm};
+use crate::errors::{map_error, verification_error};
+use crate::shutdown;
+use crate::store::StoreWrapper;
+use crate::RUNTIME;
+use bytes::Bytes;
+use object_store::path::Path;
+use object_store::{DynObjectStore, PutMode, PutPayload, Result};
+use rustler::{Binary, Encoder, Env, NifResult, OwnedBinary, ResourceArc, Term};
+use std::sync::Arc;
+
+/// Objects of a store under a prefix, keyed by the hex SHA-256 digest of
+/// their data
+///
+/// Objects are stored at `<prefix>/<first two digest characters>/<digest>`,
+/// which spreads them over directories on local stores and key ranges on S3.
+pub struct CasStore {
+    store: Arc<DynObjectStore>,
+    prefix: Path,
+}
+
+impl CasStore {
+    pub fn new(store: Arc<DynObjectStore>, prefix: &str) -> Self {
+        Self {
+            store,
+            prefix: Path::from(prefix),
+        }
+    }
+
+    /// Location of the object with a digest
+    pub fn path(&self, digest: &str) -> Path {
+        self.prefix.child(&digest[..2]).child(digest)
+    }
+
+    /// Store data under its digest unless an object with that digest exists,
+    /// returning the digest and whether the data was written
+    ///
+    /// The existence check makes duplicate puts cost a HEAD instead of an
+    /// upload. Concurrent puts of the same data are resolved with a
+    /// create-only put; stores without conditional puts overwrite, which is
+    /// harmless since the data is the same.
+    pub async fn put(&self, data: Bytes) -> Result<(String, bool)> {
+        let digest = digest(ChecksumAlgorithm::Sha256, &data);
+        let path = self.path(&digest);
+
+        match self.store.head(&path).await {
+            Ok(_) => return Ok((digest, false)),
+            Err(object_store::Error::NotFound { .. }) => {}
+            Err(e) => return Err(e),
+        }
+
+        let payload = PutPayload::from_bytes(data);
+        let written = match self
+            .store
+            .put_opts(&path, payload.clone(), PutMode::Create.into())
+            .await
+        {
+            Ok(_) => true,
+            Err(object_store::Error::AlreadyExists { .. }) => false,
+            Err(object_store::Error::NotImplemented) => {
+                self.store.put(&path, payload).await?;
+                true
+            }
+            Err(e) => return Err(e),
+        };
+        Ok((digest, written))
+    }
+
+    /// Get the data with a digest, verifying it still matches
+    pub async fn get(&self, digest: &str) -> Result<Bytes> {
+        let data = self.store.get(&self.path(digest)).await?.bytes().await?;
+
+        let actual = crate::checksum::digest(ChecksumAlgorithm::Sha256, &data);
+        if actual != digest {
+            return Err(verification_error(format!(
+                "Content of {} has digest {}",
+                digest, actual
+            )));
+        }
+        Ok(data)
+    }
+}
+
+/// Whether a string is a lowercase hex SHA-256 digest
+pub fn is_digest(digest: &str) -> bool {
+    digest.len() == 64
+        && digest
+            .bytes()
+            .all(|b| b.is_ascii_digit() || (b'a'..=b'f').contains(&b))
+}
+
+/// Store data under its SHA-256 digest
+///
+/// Returns `{:ok, digest, written}`, where `written` is false when an object
+/// with the digest already existed and nothing was uploaded.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn put_cas<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    prefix: String,
+    data: Binary,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let cas = CasStore::new(store.inner.clone(), &prefix);
+    let data = Bytes::copy_from_slice(data.as_slice());
+
+    match RUNTIME.block_on(cas.put(data)) {
+        Ok((digest, written)) => Ok((atoms::ok(), digest, written).encode(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
+
+/// Get the data stored under a SHA-256 digest
+///
+/// Returns `:integrity_error` if the stored data no longer matches its
+/// digest. Raises ArgumentError if `digest` isn't a lowercase hex SHA-256
+/// digest.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn get_cas<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    prefix: String,
+    digest: String,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    if !is_digest(&digest) {
+        return Err(rustler::Error::BadArg);
+    }
+    let cas = CasStore::new(store.inner.clone(), &prefix);
+
+    match RUNTIME.block_on(cas.get(&digest)) {
+        Ok(data) => {
+            let mut binary = OwnedBinary::new(data.len()).unwrap();
+            binary.as_mut_slice().copy_from_slice(&data);
+            Ok((atoms::ok(), binary.release(env)).encode(env))
+        }
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
@@ -0,0 +1,213 @@
+//! Checksums computed while uploading, and verification of stored and
+//! downloaded objects
+
+use crate::atoms;
+use crate::errors::{integrity_error, map_error, verification_error};
+use crate::shutdown;
+use crate::split::hex;
+use crate::store::StoreWrapper;
+use crate::RUNTIME;
+use futures::StreamExt;
+use md5::{Digest, Md5};
+use object_store::path::Path;
+use object_store::ObjectMeta;
+use ring::digest::{Context, SHA256};
+use rustler::{Atom, Decoder, Encoder, Env, NifResult, ResourceArc, Term};
+
+/// Digest algorithm requested from Elixir as `:md5`, `:sha256` or `:crc32c`
+#[derive(Debug, Clone, Copy)]
+pub enum ChecksumAlgorithm {
+    Md5,
+    Sha256,
+    Crc32c,
+}
+
+impl ChecksumAlgorithm {
+    pub fn from_atom(atom: Atom) -> NifResult<Self> {
+        match atom {
+            a if a == atoms::md5() => Ok(ChecksumAlgorithm::Md5),
+            a if a == atoms::sha256() => Ok(ChecksumAlgorithm::Sha256),
+            a if a == atoms::crc32c() => Ok(ChecksumAlgorithm::Crc32c),
+            _ => Err(rustler::Error::BadArg),
+        }
+    }
+
+    pub fn atom(&self) -> Atom {
+        match self {
+            ChecksumAlgorithm::Md5 => atoms::md5(),
+            ChecksumAlgorithm::Sha256 => atoms::sha256(),
+            ChecksumAlgorithm::Crc32c => atoms::crc32c(),
+        }
+    }
+}
+
+/// Incremental digest of data as it is uploaded or downloaded
+pub enum Hasher {
+    Md5(Md5),
+    Sha256(Context),
+    Crc32c(u32),
+}
+
+impl Hasher {
+    pub fn new(algorithm: ChecksumAlgorithm) -> Self {
+        match algorithm {
+            ChecksumAlgorithm::Md5 => Hasher::Md5(Md5::new()),
+            ChecksumAlgorithm::Sha256 => Hasher::Sha256(Context::new(&SHA256)),
+            ChecksumAlgorithm::Crc32c => Hasher::Crc32c(0),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Md5(md5) => md5.update(data),
+            Hasher::Sha256(sha256) => sha256.update(data),
+            Hasher::Crc32c(crc) => *crc = crc32c::crc32c_append(*crc, data),
+        }
+    }
+
+    /// Hex-encoded digest of everything passed to `update`
+    pub fn finish(self) -> String {
+        match self {
+            Hasher::Md5(md5) => hex(&md5.finalize()),
+            Hasher::Sha256(sha256) => hex(sha256.finish().as_ref()),
+            Hasher::Crc32c(crc) => hex(&crc.to_be_bytes()),
+        }
+    }
+}
+
+/// Hex-encoded digest of `data`
+pub fn digest(algorithm: ChecksumAlgorithm, data: &[u8]) -> String {
+    let mut hasher = Hasher::new(algorithm);
+    hasher.update(data);
+    hasher.finish()
+}
+
+/// What downloaded data is verified against
+///
+/// Decoded from `:etag` or `{algorithm, hex_digest}`.
+#[derive(Debug, Clone)]
+pub enum Verify {
+    /// The object's ETag, when it is the MD5 of the data
+    ETag,
+    /// A digest supplied by the caller
+    Digest(ChecksumAlgorithm, String),
+}
+
+impl<'a> Decoder<'a> for Verify {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        if let Ok(atom) = term.decode::<Atom>() {
+            return match atom == atoms::etag() {
+                true => Ok(Verify::ETag),
+                false => Err(rustler::Error::BadArg),
+            };
+        }
+
+        let (algorithm, expected): (Atom, String) = term.decode()?;
+        Ok(Verify::Digest(
+            ChecksumAlgorithm::from_atom(algorithm)?,
+            expected,
+        ))
+    }
+}
+
+impl Encoder for Verify {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        match self {
+            Verify::ETag => atoms::etag().encode(env),
+            Verify::Digest(algorithm, expected) => {
+                (algorithm.atom(), expected.as_str()).encode(env)
+            }
+        }
+    }
+}
+
+/// MD5 hex digest of the data an ETag was computed from, if it is one
+///
+/// Single-part uploads to S3 and compatible stores get the MD5 of their data
+/// as ETag. ETags of multipart uploads (`"<md5>-<parts>"`) and of other
+/// providers aren't digests of the data.
+fn etag_md5(e_tag: &str) -> Option<String> {
+    let e_tag = e_tag.trim_start_matches("W/").trim_matches('"');
+
+    (e_tag.len() == 32 && e_tag.bytes().all(|b| b.is_ascii_hexdigit()))
+        .then(|| e_tag.to_ascii_lowercase())
+}
+
+/// Checks downloaded data against its expected digest as it arrives
+pub struct Verifier {
+    hasher: Hasher,
+    expected: String,
+}
+
+impl Verifier {
+    /// Verifier for an object, or `None` when `verify` is `:etag` and the
+    /// object's ETag isn't an MD5 digest
+    pub fn new(verify: &Verify, meta: &ObjectMeta) -> Option<Self> {
+        let (algorithm, expected) = match verify {
+            Verify::ETag => (ChecksumAlgorithm::Md5, etag_md5(meta.e_tag.as_deref()?)?),
+            Verify::Digest(algorithm, expected) => (*algorithm, expected.trim().to_string()),
+        };
+
+        Some(Self {
+            hasher: Hasher::new(algorithm),
+            expected,
+        })
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.hasher.update(data);
+    }
+
+    /// Fail with `:integrity_error` if the data doesn't match
+    pub fn finish(self) -> object_store::Result<()> {
+        let actual = self.hasher.finish();
+        if actual.eq_ignore_ascii_case(&self.expected) {
+            Ok(())
+        } else {
+            Err(verification_error(format!(
+                "Downloaded data doesn't match: expected {}, got {}",
+                self.expected, actual
+            )))
+        }
+    }
+}
+
+/// Stream an object and check its digest against `expected`
+///
+/// `expected` is a hex digest, in either case. Returns `:ok`, or
+/// `:checksum_mismatch` if the object's digest differs.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn verify_checksum<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+    algorithm: Atom,
+    expected: String,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let algorithm = ChecksumAlgorithm::from_atom(algorithm)?;
+
+    let result = RUNTIME.block_on(async {
+        let mut hasher = Hasher::new(algorithm);
+        let mut stream = store.inner.get(&Path::from(path)).await?.into_stream();
+
+        while let Some(chunk) = stream.next().await {
+            hasher.update(&chunk?);
+        }
+
+        let actual = hasher.finish();
+        if actual.eq_ignore_ascii_case(expected.trim()) {
+            Ok(())
+        } else {
+            Err(integrity_error(format!(
+                "Checksum mismatch: expected {}, got {}",
+                expected, actual
+            )))
+        }
+    });
+
+    match result {
+        Ok(()) => Ok(atoms::ok().encode(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
@@ -0,0 +1,272 @@
+//! Deduplicated uploads of local files split into content-defined chunks
+
+use crate::atoms;
+use crate::cas::{is_digest, CasStore};
+use crate::errors::integrity_error;
+use crate::shutdown;
+use crate::split::hex;
+use crate::store::StoreWrapper;
+use crate::transfer::TransferError;
+use crate::RUNTIME;
+use bytes::Bytes;
+use fastcdc::v2020::{self, StreamCDC};
+use futures::{StreamExt, TryStreamExt};
+use object_store::path::Path;
+use object_store::{DynObjectStore, Result};
+use ring::digest::{Context, SHA256};
+use rustler::{Encoder, Env, NifMap, NifResult, ResourceArc, Term};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::Write;
+
+/// Maximum number of chunks uploading or downloading concurrently
+const MAX_CONCURRENCY: usize = 8;
+
+/// Manifest listing the chunks of a file, in order
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ChunkedManifest {
+    /// Prefix of the content-addressed store holding the chunks
+    pub chunk_prefix: String,
+    /// Total size in bytes
+    pub size: u64,
+    /// Hex-encoded SHA-256 of the whole file
+    pub sha256: String,
+    pub chunks: Vec<ManifestChunk>,
+}
+
+/// One chunk of a chunked upload
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ManifestChunk {
+    /// Hex-encoded SHA-256 of the chunk, which is also its key
+    pub sha256: String,
+    pub size: usize,
+}
+
+/// Result of a chunked upload, as returned to Elixir
+#[derive(Debug, NifMap)]
+pub struct ChunkedResultNif {
+    pub chunks: usize,
+    pub uploaded_chunks: usize,
+    pub size: u64,
+    pub uploaded_bytes: u64,
+    pub sha256: String,
+}
+
+/// Chunk size bounds, in bytes
+#[derive(Debug, Clone, Copy)]
+struct ChunkSizes {
+    min: u32,
+    avg: u32,
+    max: u32,
+}
+
+impl ChunkSizes {
+    /// Check the sizes against the limits of FastCDC, which panics on
+    /// sizes out of range
+    fn new(min: u32, avg: u32, max: u32) -> NifResult<Self> {
+        let valid = (v2020::MINIMUM_MIN..=v2020::MINIMUM_MAX).contains(&min)
+            && (v2020::AVERAGE_MIN..=v2020::AVERAGE_MAX).contains(&avg)
+            && (v2020::MAXIMUM_MIN..=v2020::MAXIMUM_MAX).contains(&max)
+            && min <= avg
+            && avg <= max;
+
+        if valid {
+            Ok(Self { min, avg, max })
+        } else {
+            Err(rustler::Error::BadArg)
+        }
+    }
+}
+
+/// Split a file into chunks, store the chunks that aren't in `cas` yet and
+/// build the manifest
+///
+/// Chunks are read and hashed in order while up to `MAX_CONCURRENCY` of them
+/// upload.
+async fn upload_chunks(
+    cas: &CasStore,
+    file: File,
+    sizes: ChunkSizes,
+) -> std::result::Result<(Vec<ManifestChunk>, String, usize, u64), TransferError> {
+    let mut file_digest = Context::new(&SHA256);
+    let mut chunks = Vec::new();
+    let mut uploaded_chunks = 0;
+    let mut uploaded_bytes = 0u64;
+
+    let mut puts = futures::stream::iter(StreamCDC::new(file, sizes.min, sizes.avg, sizes.max))
+        .map(|chunk| {
+            let data = chunk.map(|chunk| {
+                file_digest.update(&chunk.data);
+                Bytes::from(chunk.data)
+            });
+            async move {
+                let data = data.map_err(std::io::Error::from)?;
+                let size = data.len();
+                let (sha256, written) = cas.put(data).await?;
+                Ok::<_, TransferError>((ManifestChunk { sha256, size }, written))
+            }
+        })
+        .buffered(MAX_CONCURRENCY);
+
+    while let Some((chunk, written)) = puts.try_next().await? {
+        if written {
+            uploaded_chunks += 1;
+            uploaded_bytes += chunk.size as u64;
+        }
+        chunks.push(chunk);
+    }
+    drop(puts);
+
+    let sha256 = hex(file_digest.finish().as_ref());
+    Ok((chunks, sha256, uploaded_chunks, uploaded_bytes))
+}
+
+/// Read and parse a chunked manifest, checking that it lists valid digests
+async fn read_manifest(store: &DynObjectStore, path: &Path) -> Result<ChunkedManifest> {
+    let body = store.get(path).await?.bytes().await?;
+    let manifest: ChunkedManifest =
+        serde_json::from_slice(&body).map_err(|e| object_store::Error::Generic {
+            store: "Chunked",
+            source: format!("Invalid chunked manifest {}: {}", path, e).into(),
+        })?;
+
+    if let Some(chunk) = manifest.chunks.iter().find(|c| !is_digest(&c.sha256)) {
+        return Err(object_store::Error::Generic {
+            store: "Chunked",
+            source: format!("Invalid chunk digest in {}: {}", path, chunk.sha256).into(),
+        });
+    }
+    Ok(manifest)
+}
+
+/// Fetch the chunks of a manifest in order and write them to `out`, checking
+/// every chunk and the whole file against their SHA-256
+async fn download_chunks(
+    cas: &CasStore,
+    manifest: &ChunkedManifest,
+    out: &mut impl Write,
+) -> std::result::Result<(), TransferError> {
+    let mut file_digest = Context::new(&SHA256);
+    let mut size = 0u64;
+
+    let mut gets = futures::stream::iter(&manifest.chunks)
+        .map(|chunk| async move {
+            let data = cas.get(&chunk.sha256).await?;
+            if data.len() != chunk.size {
+                return Err(integrity_error(format!(
+                    "Chunk {} has {} bytes, manifest records {}",
+                    chunk.sha256,
+                    data.len(),
+                    chunk.size
+                )));
+            }
+            Ok(data)
+        })
+        .buffered(MAX_CONCURRENCY);
+
+    while let Some(data) = gets.try_next().await? {
+        file_digest.update(&data);
+        size += data.len() as u64;
+        out.write_all(&data)?;
+    }
+    out.flush()?;
+
+    if size != manifest.size || hex(file_digest.finish().as_ref()) != manifest.sha256 {
+        return Err(integrity_error("Checksum mismatch for chunked file".to_string()).into());
+    }
+    Ok(())
+}
+
+/// Upload a local file as content-defined chunks plus a manifest at `path`
+///
+/// The file is split with FastCDC into chunks of `min_size` to `max_size`
+/// bytes, averaging `avg_size`. Chunks are stored under `chunk_prefix` by
+/// their SHA-256 digest, as by `put_cas`, and only chunks missing there are
+/// uploaded; since boundaries follow the content, an edit to the file only
+/// changes the chunks around it. The manifest is written last, once every
+/// chunk is stored.
+///
+/// Returns a map with the number of chunks, the chunks and bytes actually
+/// uploaded, the total size and the SHA-256 of the file. Raises ArgumentError
+/// for chunk sizes out of FastCDC's limits.
+#[rustler::nif(schedule = "DirtyCpu")]
+#[allow(clippy::too_many_arguments)]
+pub fn put_chunked<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+    file_path: String,
+    chunk_prefix: String,
+    min_size: u32,
+    avg_size: u32,
+    max_size: u32,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let sizes = ChunkSizes::new(min_size, avg_size, max_size)?;
+    let cas = CasStore::new(store.inner.clone(), &chunk_prefix);
+
+    let result = RUNTIME.block_on(async {
+        let file = File::open(&file_path)?;
+        let (chunks, sha256, uploaded_chunks, uploaded_bytes) =
+            upload_chunks(&cas, file, sizes).await?;
+
+        let manifest = ChunkedManifest {
+            chunk_prefix,
+            size: chunks.iter().map(|chunk| chunk.size as u64).sum(),
+            sha256,
+            chunks,
+        };
+        let json =
+            serde_json::to_vec_pretty(&manifest).map_err(|e| object_store::Error::Generic {
+                store: "Chunked",
+                source: Box::new(e),
+            })?;
+        store.inner.put(&Path::from(path), json.into()).await?;
+
+        Ok::<_, TransferError>(ChunkedResultNif {
+            chunks: manifest.chunks.len(),
+            uploaded_chunks,
+            size: manifest.size,
+            uploaded_bytes,
+            sha256: manifest.sha256,
+        })
+    });
+
+    match result {
+        Ok(result) => Ok((atoms::ok(), result).encode(env)),
+        Err(e) => e.into_term(env),
+    }
+}
+
+/// Reassemble the file described by a chunked manifest into a local file
+///
+/// Chunks are fetched from the prefix recorded in the manifest. A chunk whose
+/// data no longer matches its digest fails with `:integrity_error`, a file
+/// that doesn't add up to the manifest's size and checksum with
+/// `:checksum_mismatch`; the partially written file is removed on failure.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn get_chunked<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+    file_path: String,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let result = RUNTIME.block_on(async {
+        let manifest = read_manifest(store.inner.as_ref(), &Path::from(path)).await?;
+        let cas = CasStore::new(store.inner.clone(), &manifest.chunk_prefix);
+        let mut file = File::create(&file_path)?;
+
+        let written = download_chunks(&cas, &manifest, &mut file).await;
+        if written.is_err() {
+            drop(file);
+            let _ = std::fs::remove_file(&file_path);
+        }
+        written
+    });
+
+    match result {
+        Ok(()) => Ok(atoms::ok().to_term(env)),
+        Err(e) => e.into_term(env),
+    }
+}
@@ -0,0 +1,238 @@
+//! Delete, copy, rename and put guarded by the object's ETag
+//!
+//! Puts are checked in the same request by every store that supports them.
+//! For the other actions, S3 checks the ETag in the same request that acts on the object. Other
+//! stores can only check it with a separate HEAD request, leaving a window in
+//! which the object can change; results report which of the two happened.
+
+use crate::atoms;
+use crate::errors::map_error;
+use crate::s3_api::S3Api;
+use crate::shutdown;
+use crate::store::StoreWrapper;
+use crate::RUNTIME;
+use bytes::Bytes;
+use object_store::path::Path;
+use object_store::{
+    Error, GetOptions, ObjectStore, PutMode, PutPayload, PutResult, Result, UpdateVersion,
+};
+use rustler::{Atom, Binary, Encoder, Env, NifMap, NifResult, OwnedBinary, ResourceArc, Term};
+
+/// Outcome of a conditional action
+///
+/// `atomic` is true when the ETag check and the action could not be
+/// separated by a concurrent write.
+#[derive(NifMap)]
+struct ConditionalResultNif {
+    atomic: bool,
+}
+
+/// Action to perform on an unchanged object
+enum Action {
+    Delete,
+    Copy(Path),
+    Rename(Path),
+}
+
+/// Fail with `Error::Precondition` unless the object's ETag is `etag`
+async fn check_unchanged(store: &dyn ObjectStore, path: &Path, etag: &str) -> Result<()> {
+    let options = GetOptions {
+        if_match: Some(etag.to_string()),
+        head: true,
+        ..Default::default()
+    };
+
+    store.get_opts(path, options).await.map(|_| ())
+}
+
+/// Act with the ETag checked in the same request (S3)
+async fn act_atomically(s3: &S3Api, path: &Path, etag: &str, action: &Action) -> Result<bool> {
+    match action {
+        Action::Delete => {
+            s3.delete(path, Some(etag), None).await?;
+            Ok(true)
+        }
+        Action::Copy(to) => {
+            s3.copy(path, to, etag).await?;
+            Ok(true)
+        }
+        Action::Rename(to) => {
+            // Copy and delete are checked separately. If the source changes in
+            // between, the copy is removed again and the rename fails.
+            s3.copy(path, to, etag).await?;
+            match s3.delete(path, Some(etag), None).await {
+                Err(e @ object_store::Error::Precondition { .. }) => {
+                    s3.store.delete(to).await?;
+                    return Err(e);
+                }
+                result => result?,
+            }
+            Ok(false)
+        }
+    }
+}
+
+/// Act after a separate ETag check
+async fn act_after_check(
+    store: &dyn ObjectStore,
+    path: &Path,
+    etag: &str,
+    action: &Action,
+) -> Result<bool> {
+    check_unchanged(store, path, etag).await?;
+
+    match action {
+        Action::Delete => store.delete(path).await?,
+        Action::Copy(to) => store.copy(path, to).await?,
+        Action::Rename(to) => store.rename(path, to).await?,
+    }
+    Ok(false)
+}
+
+/// Delete, copy or rename an object only if its ETag still matches
+///
+/// `action` is `:delete`, `:copy` or `:rename`; `to` is the destination of
+/// copies and renames. Returns `{:ok, %{atomic: boolean}}`, or
+/// `:precondition_failed` if the object has changed.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn act_if_unchanged<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+    etag: String,
+    action: Atom,
+    to: Option<String>,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let action = match (action, to) {
+        (action, None) if action == atoms::delete() => Action::Delete,
+        (action, Some(to)) if action == atoms::copy() => Action::Copy(Path::from(to)),
+        (action, Some(to)) if action == atoms::rename() => Action::Rename(Path::from(to)),
+        _ => return Err(rustler::Error::BadArg),
+    };
+    let path = Path::from(path);
+
+    let result = RUNTIME.block_on(async {
+        match &store.s3 {
+            Some(s3) => act_atomically(s3, &path, &etag, &action).await,
+            None => act_after_check(store.inner.as_ref(), &path, &etag, &action).await,
+        }
+    });
+
+    match result {
+        Ok(atomic) => Ok((atoms::ok(), ConditionalResultNif { atomic }).encode(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
+
+/// Outcome of a compare-and-swap
+pub(crate) enum Swap {
+    Done(PutResult),
+    /// The object's current data and ETag, or `None` if it doesn't exist
+    Conflict(Option<(Bytes, Option<String>)>),
+}
+
+/// Put `data` if the object's ETag is `expected`, or if it doesn't exist when
+/// `expected` is `None`
+///
+/// A rejected put is followed by a read of the object. If it is still in the
+/// expected state, the put lost against a concurrent conditional write that
+/// didn't go through (S3 rejects those with 409 Conflict) and is retried, up
+/// to `max_retries` times. Otherwise the current state is returned, so the
+/// caller can compute new data from it without reading again.
+pub(crate) async fn swap(
+    store: &dyn ObjectStore,
+    path: &Path,
+    expected: Option<&str>,
+    data: Bytes,
+    max_retries: u32,
+) -> Result<Swap> {
+    let mode = match expected {
+        Some(etag) => PutMode::Update(UpdateVersion {
+            e_tag: Some(etag.to_string()),
+            version: None,
+        }),
+        None => PutMode::Create,
+    };
+    let payload = PutPayload::from_bytes(data);
+    let mut retries = 0;
+
+    loop {
+        match store
+            .put_opts(path, payload.clone(), mode.clone().into())
+            .await
+        {
+            Ok(result) => return Ok(Swap::Done(result)),
+            Err(Error::Precondition { .. } | Error::AlreadyExists { .. }) => {}
+            Err(Error::NotFound { .. }) if expected.is_some() => {}
+            Err(e) => return Err(e),
+        }
+
+        let current = match store.get(path).await {
+            Ok(result) => {
+                let etag = result.meta.e_tag.clone();
+                Some((result.bytes().await?, etag))
+            }
+            Err(Error::NotFound { .. }) => None,
+            Err(e) => return Err(e),
+        };
+
+        let unchanged = match (&current, expected) {
+            (Some((_, etag)), Some(expected)) => etag.as_deref() == Some(expected),
+            (None, None) => true,
+            _ => false,
+        };
+        if !unchanged || retries >= max_retries {
+            return Ok(Swap::Conflict(current));
+        }
+        retries += 1;
+    }
+}
+
+/// Replace an object's data only if its ETag is `expected_etag`, or create it
+/// only if it doesn't exist when `expected_etag` is nil
+///
+/// Returns `{:ok, etag, version}` (missing identifiers are empty strings), or
+/// `{:conflict, data, etag}` with the object's current data and ETag when it
+/// has changed, both nil if it doesn't exist. Puts rejected by a concurrent
+/// write while the object still has the expected ETag are retried up to
+/// `max_retries` times. Stores without conditional puts return
+/// `:not_supported`.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn compare_and_swap<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+    expected_etag: Option<String>,
+    new_data: Binary,
+    max_retries: u32,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let data = Bytes::copy_from_slice(new_data.as_slice());
+
+    let result = RUNTIME.block_on(swap(
+        store.inner.as_ref(),
+        &Path::from(path),
+        expected_etag.as_deref(),
+        data,
+        max_retries,
+    ));
+
+    match result {
+        Ok(Swap::Done(result)) => {
+            let etag = result.e_tag.unwrap_or_default();
+            let version = result.version.unwrap_or_default();
+            Ok((atoms::ok(), etag, version).encode(env))
+        }
+        Ok(Swap::Conflict(Some((data, etag)))) => {
+            let mut binary = OwnedBinary::new(data.len()).unwrap();
+            binary.as_mut_slice().copy_from_slice(&data);
+            Ok((atoms::conflict(), binary.release(env), etag).encode(env))
+        }
+        Ok(Swap::Conflict(None)) => {
+            let nil = rustler::types::atom::nil();
+            Ok((atoms::conflict(), nil, nil).encode(env))
+        }
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
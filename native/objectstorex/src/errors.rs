@@ -1,6 +1,79 @@
 use crate::atoms;
+use crate::wrappers::deadline::{CANCELLED_STORE, TIMEOUT_STORE};
+use crate::wrappers::provider_limits::{LimitViolation, LIMITS_STORE};
+use crate::wrappers::quota::QUOTA_STORE;
 use object_store::Error as ObjectStoreError;
-use rustler::Atom;
+use rustler::{Atom, Encoder, Env, NifMap, Term};
+use std::fmt;
+use std::time::Duration;
+
+/// Store name used for data integrity errors, matched by `map_error`
+pub const INTEGRITY_STORE: &str = "Integrity";
+
+/// Error for data whose size or checksum doesn't match what was expected
+pub fn integrity_error(message: String) -> ObjectStoreError {
+    ObjectStoreError::Generic {
+        store: INTEGRITY_STORE,
+        source: message.into(),
+    }
+}
+
+/// Store name used for downloads that fail verification, matched by `map_error`
+pub const VERIFICATION_STORE: &str = "Verification";
+
+/// Error for downloaded data that doesn't match its ETag or expected checksum
+pub fn verification_error(message: String) -> ObjectStoreError {
+    ObjectStoreError::Generic {
+        store: VERIFICATION_STORE,
+        source: message.into(),
+    }
+}
+
+/// Store name used for objects that aren't valid JSON, matched by `map_error`
+pub const JSON_STORE: &str = "Json";
+
+/// Error for a JSON document that can't be parsed
+pub fn json_error(message: String) -> ObjectStoreError {
+    ObjectStoreError::Generic {
+        store: JSON_STORE,
+        source: message.into(),
+    }
+}
+
+/// Unsuccessful response of a provider API, kept as the source of the store
+/// error so its status, error code and `Retry-After` hint can be reported
+#[derive(Debug)]
+pub struct HttpError {
+    pub status: u16,
+    pub code: Option<String>,
+    pub retry_after: Option<Duration>,
+    pub body: String,
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Request failed with status {}: {}",
+            self.status, self.body
+        )
+    }
+}
+
+impl std::error::Error for HttpError {}
+
+/// Recover the store error behind an I/O error of the buffered reader or
+/// writer, which wrap store errors in `std::io::Error`
+pub fn from_io_error(store: &'static str, error: std::io::Error) -> ObjectStoreError {
+    match error.into_inner().map(|e| e.downcast::<ObjectStoreError>()) {
+        Some(Ok(error)) => *error,
+        Some(Err(source)) => ObjectStoreError::Generic { store, source },
+        None => ObjectStoreError::Generic {
+            store,
+            source: "I/O error".into(),
+        },
+    }
+}
 
 /// Map object_store errors to Elixir atoms for consistent error handling
 ///
@@ -13,27 +86,262 @@ use rustler::Atom;
 /// - `AlreadyExists` → `:already_exists` - Object already exists (conditional ops)
 /// - `Precondition` → `:precondition_failed` - Precondition not met (ETag mismatch, etc.)
 /// - `NotModified` → `:not_modified` - Object not modified (conditional requests)
-/// - `NotSupported` → `:not_supported` - Operation not supported by provider
+/// - `NotSupported`, `NotImplemented` → `:not_supported` - Operation not supported
+///   by provider
 /// - `PermissionDenied` → `:permission_denied` - Insufficient permissions
+/// - Quota wrapper errors → `:quota_exceeded` - Write would exceed the handle's byte quota
+/// - Integrity errors → `:checksum_mismatch` - Data size or checksum doesn't match
+/// - Verification errors → `:integrity_error` - Downloaded data doesn't match its
+///   ETag or the expected checksum
+/// - JSON errors → `:invalid_json` - Object isn't a valid JSON document
+/// - Provider limit violations → `:key_too_long`, `:metadata_too_large`,
+///   `:too_many_tags`, `:tag_too_long`, `:too_many_parts` or `:part_too_small`
+/// - Deadline errors → `:timeout` or `:cancelled` - The operation's
+///   `:timeout_ms` passed or its `:op_ref` was cancelled
+/// - HTTP 429 or a throttling error code (`SlowDown`, `Throttling`, ...) →
+///   `:throttled` - The provider asks for requests to slow down
+/// - HTTP 408 or 504, a `RequestTimeout` error code or a client timeout →
+///   `:timeout` - The request timed out
+/// - Other HTTP 5xx → `:server_error` - The provider failed to handle the request
 /// - All other errors → `:error` - Generic error (network, internal, etc.)
 ///
+/// Generic, throttling, timeout and server errors are returned as
+/// `{kind, details}`, where `details` has the HTTP `:status`, the provider's
+/// error `:code` and the `Retry-After` hint as `:retry_after_ms`, each `nil`
+/// when unknown, plus the error `:message` and the messages of its `:sources`
+/// so failures such as panicked tasks can be reported.
+///
+/// Statuses come from responses the library reads itself (see `HttpError`),
+/// reqwest errors and, since object_store keeps its own errors private, the
+/// status its request errors report in their messages (`reported_status`).
+///
 /// # Examples
 ///
 /// ```rust
 /// use object_store::Error as ObjectStoreError;
 ///
 /// let error = ObjectStoreError::NotFound { path: "test.txt".to_string(), source: ... };
-/// let atom = map_error(error);
-/// // atom is now :not_found
+/// let reason = map_error(error).to_term(env);
+/// // reason is now :not_found
 /// ```
-pub fn map_error(error: ObjectStoreError) -> Atom {
+pub fn map_error(error: ObjectStoreError) -> MappedError {
+    let kind = error_kind(&error);
+    let details = [
+        atoms::error(),
+        atoms::throttled(),
+        atoms::timeout(),
+        atoms::server_error(),
+    ]
+    .contains(&kind)
+    .then(|| error_details(&error));
+    MappedError { kind, details }
+}
+
+/// Error reason returned to Elixir, see `map_error`
+#[derive(Debug, Clone)]
+pub struct MappedError {
+    pub kind: Atom,
+    pub details: Option<ErrorDetails>,
+}
+
+impl MappedError {
+    pub fn to_term<'a>(&self, env: Env<'a>) -> Term<'a> {
+        self.encode(env)
+    }
+}
+
+impl Encoder for MappedError {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        match &self.details {
+            Some(details) => (self.kind, details).encode(env),
+            None => self.kind.encode(env),
+        }
+    }
+}
+
+/// Response details and messages of a failed operation
+#[derive(Debug, Clone, Default, NifMap)]
+pub struct ErrorDetails {
+    pub status: Option<u16>,
+    pub code: Option<String>,
+    pub retry_after_ms: Option<u64>,
+    pub message: String,
+    pub sources: Vec<String>,
+}
+
+/// Details of an error, searching its whole source chain
+pub fn error_details(error: &ObjectStoreError) -> ErrorDetails {
+    let mut details = ErrorDetails::default();
+    let chain = std::iter::successors(Some(error as &(dyn std::error::Error + 'static)), |e| {
+        e.source()
+    });
+    for (depth, error) in chain.enumerate() {
+        details.status = details.status.or_else(|| status_of(error));
+        if let Some(http) = error.downcast_ref::<HttpError>() {
+            details.code = details.code.or_else(|| http.code.clone());
+            details.retry_after_ms = http.retry_after.map(|d| d.as_millis() as u64);
+        }
+        let message = error.to_string();
+        details.code = details.code.or_else(|| provider_code(&message));
+        match depth {
+            0 => details.message = message,
+            _ => details.sources.push(message),
+        }
+    }
+    details
+}
+
+/// Whether any error in the chain is a client-side request timeout
+fn is_timeout(error: &ObjectStoreError) -> bool {
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(error);
+    while let Some(error) = source {
+        if error
+            .downcast_ref::<reqwest::Error>()
+            .is_some_and(reqwest::Error::is_timeout)
+        {
+            return true;
+        }
+        source = error.source();
+    }
+    false
+}
+
+/// Provider error codes asking clients to slow down
+const THROTTLING_CODES: &[&str] = &[
+    "SlowDown",
+    "Throttling",
+    "ThrottlingException",
+    "TooManyRequests",
+    "RequestLimitExceeded",
+    "ServerBusy",
+    "rateLimitExceeded",
+    "userRateLimitExceeded",
+];
+
+/// Provider error codes for requests that timed out
+const TIMEOUT_CODES: &[&str] = &["RequestTimeout", "OperationTimedOut"];
+
+/// Atom for an error not covered by a more specific variant, from its HTTP
+/// status and provider error code
+fn generic_kind(error: &ObjectStoreError) -> Atom {
+    let details = error_details(error);
+    let code = details.code.as_deref();
+    match details.status {
+        _ if code.is_some_and(|c| THROTTLING_CODES.contains(&c)) => atoms::throttled(),
+        _ if code.is_some_and(|c| TIMEOUT_CODES.contains(&c)) => atoms::timeout(),
+        Some(429) => atoms::throttled(),
+        Some(408 | 504) => atoms::timeout(),
+        Some(500..=599) => atoms::server_error(),
+        _ if is_timeout(error) => atoms::timeout(),
+        _ => atoms::error(),
+    }
+}
+
+/// Elixir atom of an error, as returned by `map_error`, without consuming it
+pub fn error_kind(error: &ObjectStoreError) -> Atom {
     match error {
         ObjectStoreError::NotFound { .. } => atoms::not_found(),
         ObjectStoreError::AlreadyExists { .. } => atoms::already_exists(),
         ObjectStoreError::Precondition { .. } => atoms::precondition_failed(),
         ObjectStoreError::NotModified { .. } => atoms::not_modified(),
-        ObjectStoreError::NotSupported { .. } => atoms::not_supported(),
+        ObjectStoreError::NotSupported { .. } | ObjectStoreError::NotImplemented => {
+            atoms::not_supported()
+        }
         ObjectStoreError::PermissionDenied { .. } => atoms::permission_denied(),
-        _ => atoms::error(),
+        ObjectStoreError::Generic { store, .. } if *store == QUOTA_STORE => atoms::quota_exceeded(),
+        ObjectStoreError::Generic { store, .. } if *store == INTEGRITY_STORE => {
+            atoms::checksum_mismatch()
+        }
+        ObjectStoreError::Generic { store, .. } if *store == VERIFICATION_STORE => {
+            atoms::integrity_error()
+        }
+        ObjectStoreError::Generic { store, .. } if *store == JSON_STORE => atoms::invalid_json(),
+        ObjectStoreError::Generic { store, .. } if *store == TIMEOUT_STORE => atoms::timeout(),
+        ObjectStoreError::Generic { store, .. } if *store == CANCELLED_STORE => atoms::cancelled(),
+        ObjectStoreError::Generic { store, source } if *store == LIMITS_STORE => source
+            .downcast_ref::<LimitViolation>()
+            .map_or_else(atoms::error, LimitViolation::atom),
+        _ => generic_kind(error),
+    }
+}
+
+/// HTTP status of a single error in a source chain
+///
+/// Statuses are typed fields of `HttpError` and reqwest errors. object_store's
+/// request errors are private, so their status is read from their message.
+fn status_of(error: &(dyn std::error::Error + 'static)) -> Option<u16> {
+    if let Some(http) = error.downcast_ref::<HttpError>() {
+        return Some(http.status);
+    }
+    if let Some(e) = error.downcast_ref::<reqwest::Error>() {
+        return e.status().map(|status| status.as_u16());
+    }
+    reported_status(&error.to_string())
+}
+
+/// Response status reported by the message of an object_store request error
+/// or retry
+///
+/// Only the wording of object_store's retry errors ("Client error with status
+/// 429 Too Many Requests: ...", "Server error, body contains Error, with status
+/// 500 ..."), of its retry log messages ("Encountered a response status of 500
+/// ...") and of reqwest's status errors ("HTTP status server error (503
+/// Service Unavailable) ...") is recognised, so numbers elsewhere in a message
+/// aren't mistaken for statuses.
+pub fn reported_status(message: &str) -> Option<u16> {
+    const PREFIXES: &[&str] = &[
+        "with status ",
+        "a response status of ",
+        "HTTP status client error (",
+        "HTTP status server error (",
+    ];
+    PREFIXES.iter().find_map(|prefix| {
+        let from = message.find(prefix)? + prefix.len();
+        let digits = message[from..].get(..3)?;
+        digits
+            .parse()
+            .ok()
+            .filter(|status| (100..600).contains(status))
+    })
+}
+
+/// HTTP status of an error, searching its whole source chain
+pub fn error_status(error: &ObjectStoreError) -> Option<u16> {
+    let mut source: Option<&(dyn std::error::Error + 'static)> = Some(error);
+    while let Some(error) = source {
+        if let Some(status) = status_of(error) {
+            return Some(status);
+        }
+        source = error.source();
+    }
+    None
+}
+
+/// Provider error code in a response body quoted by an error message: the
+/// `<Code>` of S3 and Azure XML errors, or the `"reason"` of GCS JSON errors
+pub fn provider_code(message: &str) -> Option<String> {
+    let between = |start: &str, end: &str| {
+        let from = message.find(start)? + start.len();
+        let to = message[from..].find(end)? + from;
+        Some(message[from..to].trim().to_string()).filter(|code| !code.is_empty())
+    };
+    between("<Code>", "</Code>").or_else(|| {
+        let from = message.find("\"reason\"")? + "\"reason\"".len();
+        let rest = message[from..].trim_start_matches([' ', ':']);
+        let rest = rest.strip_prefix('"')?;
+        Some(rest[..rest.find('"')?].to_string()).filter(|code| !code.is_empty())
+    })
+}
+
+/// Parse a `Retry-After` header given in seconds or as an HTTP date
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    match value.parse::<u64>() {
+        Ok(seconds) => Some(Duration::from_secs(seconds)),
+        Err(_) => chrono::DateTime::parse_from_rfc2822(value).ok().map(|at| {
+            (at.to_utc() - chrono::Utc::now())
+                .to_std()
+                .unwrap_or_default()
+        }),
     }
 }
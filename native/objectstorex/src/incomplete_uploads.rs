@@ -0,0 +1,230 @@
+//! Listing and aborting multipart uploads that were never completed
+
+use crate::atoms;
+use crate::errors::map_error;
+use crate::gcs_api::{self, GcsApi};
+use crate::s3_api::{self, S3Api};
+use crate::shutdown;
+use crate::store::StoreWrapper;
+use crate::versions::{format_timestamp, list_prefix};
+use crate::RUNTIME;
+use async_trait::async_trait;
+use chrono::NaiveDateTime;
+use object_store::path::Path;
+use object_store::Result;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use reqwest::Method;
+use rustler::{Encoder, Env, NifMap, NifResult, ResourceArc, Term};
+
+/// A multipart upload that was started but not completed or aborted
+#[derive(Debug, Clone, Default, NifMap)]
+pub struct IncompleteUploadNif {
+    pub location: String,
+    pub upload_id: String,
+    pub initiated: String,
+}
+
+/// Providers listing the multipart uploads in progress in a bucket
+#[async_trait]
+pub trait IncompleteUploads: Send + Sync {
+    /// List the incomplete uploads of objects under a prefix
+    async fn list_incomplete_uploads(&self, prefix: &Path) -> Result<Vec<IncompleteUploadNif>>;
+
+    /// Abort an incomplete upload, deleting its parts
+    async fn abort_incomplete_upload(&self, path: &Path, upload_id: &str) -> Result<()>;
+}
+
+/// One page of a `ListMultipartUploadsResult` document, as returned by S3 and
+/// the GCS XML API
+#[derive(Default)]
+struct UploadsPage {
+    uploads: Vec<IncompleteUploadNif>,
+    truncated: bool,
+    next_key_marker: Option<String>,
+    next_upload_id_marker: Option<String>,
+}
+
+fn parse_uploads(body: &[u8]) -> std::result::Result<UploadsPage, quick_xml::Error> {
+    let mut reader = Reader::from_reader(body);
+    let mut buf = Vec::new();
+    let mut page = UploadsPage::default();
+    let mut entry: Option<IncompleteUploadNif> = None;
+    let mut element = Vec::new();
+
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(start) => {
+                element = start.name().as_ref().to_vec();
+                if element == b"Upload" {
+                    entry = Some(IncompleteUploadNif::default());
+                }
+            }
+            Event::Text(text) => {
+                let text = text.unescape()?.into_owned();
+                match (&mut entry, element.as_slice()) {
+                    (Some(upload), b"Key") => upload.location = text,
+                    (Some(upload), b"UploadId") => upload.upload_id = text,
+                    (Some(upload), b"Initiated") => upload.initiated = format_timestamp(&text),
+                    (None, b"IsTruncated") => page.truncated = text == "true",
+                    (None, b"NextKeyMarker") => page.next_key_marker = Some(text),
+                    (None, b"NextUploadIdMarker") => page.next_upload_id_marker = Some(text),
+                    _ => {}
+                }
+            }
+            Event::End(end) => {
+                if end.name().as_ref() == b"Upload" {
+                    page.uploads.extend(entry.take());
+                }
+                element.clear();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    Ok(page)
+}
+
+/// List every page of `ListMultipartUploads`, sending each query with `send`
+async fn list_all<F, Fut>(
+    prefix: &Path,
+    generic: fn(String) -> object_store::Error,
+    send: F,
+) -> Result<Vec<IncompleteUploadNif>>
+where
+    F: Fn(String) -> Fut,
+    Fut: std::future::Future<Output = Result<bytes::Bytes>>,
+{
+    let prefix = list_prefix(prefix);
+    let mut uploads = Vec::new();
+    let mut markers: Option<(String, Option<String>)> = None;
+
+    loop {
+        let mut query = format!("uploads&prefix={}", s3_api::encode_query(&prefix));
+        if let Some((key, upload_id)) = &markers {
+            query.push_str(&format!("&key-marker={}", s3_api::encode_query(key)));
+            if let Some(upload_id) = upload_id {
+                query.push_str(&format!(
+                    "&upload-id-marker={}",
+                    s3_api::encode_query(upload_id)
+                ));
+            }
+        }
+
+        let body = send(query).await?;
+        let page = parse_uploads(&body)
+            .map_err(|e| generic(format!("Invalid uploads response: {}", e)))?;
+        uploads.extend(page.uploads);
+
+        match (page.truncated, page.next_key_marker) {
+            (true, Some(key)) => markers = Some((key, page.next_upload_id_marker)),
+            _ => return Ok(uploads),
+        }
+    }
+}
+
+#[async_trait]
+impl IncompleteUploads for S3Api {
+    async fn list_incomplete_uploads(&self, prefix: &Path) -> Result<Vec<IncompleteUploadNif>> {
+        list_all(prefix, s3_api::generic, |query| async move {
+            self.send(Method::GET, &Path::default(), Some(&query), &[], None)
+                .await
+        })
+        .await
+    }
+
+    async fn abort_incomplete_upload(&self, path: &Path, upload_id: &str) -> Result<()> {
+        let query = format!("uploadId={}", s3_api::encode_query(upload_id));
+        self.send(Method::DELETE, path, Some(&query), &[], None)
+            .await
+            .map(|_| ())
+    }
+}
+
+#[async_trait]
+impl IncompleteUploads for GcsApi {
+    async fn list_incomplete_uploads(&self, prefix: &Path) -> Result<Vec<IncompleteUploadNif>> {
+        list_all(prefix, gcs_api::generic, |query| async move {
+            self.send_xml(Method::GET, &Path::default(), &query).await
+        })
+        .await
+    }
+
+    async fn abort_incomplete_upload(&self, path: &Path, upload_id: &str) -> Result<()> {
+        let query = format!("uploadId={}", s3_api::encode_query(upload_id));
+        self.send_xml(Method::DELETE, path, &query)
+            .await
+            .map(|_| ())
+    }
+}
+
+/// Unix timestamp of an upload's `initiated` time, as formatted by
+/// `format_timestamp`
+fn initiated_at(upload: &IncompleteUploadNif) -> Option<i64> {
+    let initiated = upload.initiated.strip_suffix(" UTC")?;
+    NaiveDateTime::parse_from_str(initiated, "%Y-%m-%d %H:%M:%S%.f")
+        .ok()
+        .map(|t| t.and_utc().timestamp())
+}
+
+/// List the incomplete multipart uploads of objects under a prefix
+///
+/// Returns a list of upload maps, or `:not_supported` for stores that can't
+/// list uploads.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn list_incomplete_uploads<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    prefix: Option<String>,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let Some(uploads) = store.uploads.clone() else {
+        return Ok(atoms::not_supported().to_term(env));
+    };
+
+    let prefix = prefix.map(Path::from).unwrap_or_default();
+    match RUNTIME.block_on(uploads.list_incomplete_uploads(&prefix)) {
+        Ok(list) => Ok(list.encode(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
+
+/// Abort the incomplete multipart uploads under a prefix initiated before a
+/// Unix timestamp
+///
+/// Returns the aborted uploads, or `:not_supported` for stores that can't
+/// list uploads. Stops at the first abort that fails.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn abort_incomplete_uploads<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    prefix: Option<String>,
+    initiated_before: i64,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let Some(uploads) = store.uploads.clone() else {
+        return Ok(atoms::not_supported().to_term(env));
+    };
+
+    let prefix = prefix.map(Path::from).unwrap_or_default();
+    let result = RUNTIME.block_on(async {
+        let mut aborted = Vec::new();
+        for upload in uploads.list_incomplete_uploads(&prefix).await? {
+            if initiated_at(&upload).is_none_or(|at| at >= initiated_before) {
+                continue;
+            }
+            uploads
+                .abort_incomplete_upload(&Path::from(upload.location.as_str()), &upload.upload_id)
+                .await?;
+            aborted.push(upload);
+        }
+        Ok(aborted)
+    });
+
+    match result {
+        Ok(aborted) => Ok(aborted.encode(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
@@ -0,0 +1,277 @@
+//! JSON documents parsed and serialized natively, with merges guarded by
+//! the document's ETag
+
+use crate::atoms;
+use crate::conditional::{swap, Swap};
+use crate::errors::{json_error, map_error};
+use crate::operations::put_mode;
+use crate::shutdown;
+use crate::store::StoreWrapper;
+use crate::types::PutModeNif;
+use crate::RUNTIME;
+use bytes::Bytes;
+use object_store::path::Path;
+use object_store::{DynObjectStore, PutOptions, PutPayload, Result};
+use rustler::types::map::MapIterator;
+use rustler::{Decoder, Encoder, Env, NifResult, ResourceArc, Term, TermType};
+use serde_json::{Map, Number, Value};
+
+/// Retries of a merge's conditional put lost against a concurrent
+/// conditional write, see `swap`
+const MAX_SWAP_RETRIES: u32 = 3;
+
+/// A JSON value exchanged with Elixir
+///
+/// Decoded from maps, lists, binaries, numbers, booleans and `nil`; other
+/// atoms become strings and map keys may be binaries, atoms or integers, as
+/// with Jason. Encoded as maps with binary keys, lists, binaries, numbers,
+/// booleans and `nil`.
+pub struct JsonNif(pub Value);
+
+impl<'a> Decoder<'a> for JsonNif {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        decode_value(term).map(JsonNif)
+    }
+}
+
+impl Encoder for JsonNif {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        encode_value(env, &self.0)
+    }
+}
+
+fn decode_value(term: Term<'_>) -> NifResult<Value> {
+    match term.get_type() {
+        TermType::Atom => Ok(match term.atom_to_string()?.as_str() {
+            "nil" => Value::Null,
+            "true" => Value::Bool(true),
+            "false" => Value::Bool(false),
+            other => Value::String(other.to_string()),
+        }),
+        TermType::Binary => Ok(Value::String(term.decode()?)),
+        TermType::Integer => match term.decode::<i64>() {
+            Ok(integer) => Ok(integer.into()),
+            Err(_) => Ok(term.decode::<u64>()?.into()),
+        },
+        TermType::Float => Number::from_f64(term.decode()?)
+            .map(Value::Number)
+            .ok_or(rustler::Error::BadArg),
+        TermType::List => term
+            .decode::<Vec<Term>>()?
+            .into_iter()
+            .map(decode_value)
+            .collect::<NifResult<_>>()
+            .map(Value::Array),
+        TermType::Map => {
+            let mut object = Map::new();
+            for (key, value) in MapIterator::new(term).ok_or(rustler::Error::BadArg)? {
+                object.insert(decode_key(key)?, decode_value(value)?);
+            }
+            Ok(Value::Object(object))
+        }
+        _ => Err(rustler::Error::BadArg),
+    }
+}
+
+fn decode_key(term: Term<'_>) -> NifResult<String> {
+    match term.get_type() {
+        TermType::Binary => term.decode(),
+        TermType::Atom => term.atom_to_string(),
+        TermType::Integer => Ok(term.decode::<i64>()?.to_string()),
+        _ => Err(rustler::Error::BadArg),
+    }
+}
+
+fn encode_value<'a>(env: Env<'a>, value: &Value) -> Term<'a> {
+    match value {
+        Value::Null => rustler::types::atom::nil().encode(env),
+        Value::Bool(boolean) => boolean.encode(env),
+        Value::Number(number) => match (number.as_i64(), number.as_u64()) {
+            (Some(integer), _) => integer.encode(env),
+            (None, Some(integer)) => integer.encode(env),
+            _ => number.as_f64().unwrap_or_default().encode(env),
+        },
+        Value::String(string) => string.encode(env),
+        Value::Array(values) => values
+            .iter()
+            .map(|value| encode_value(env, value))
+            .collect::<Vec<_>>()
+            .encode(env),
+        Value::Object(object) => {
+            let keys: Vec<Term> = object.keys().map(|key| key.encode(env)).collect();
+            let values: Vec<Term> = object.values().map(|v| encode_value(env, v)).collect();
+            // Keys of a JSON object are unique, which is all this can fail on
+            Term::map_from_term_arrays(env, &keys, &values).unwrap()
+        }
+    }
+}
+
+/// Parse an object's data as JSON
+fn parse(path: &Path, data: &[u8]) -> Result<Value> {
+    serde_json::from_slice(data).map_err(|e| json_error(format!("{}: {}", path, e)))
+}
+
+/// Serialize a JSON value as an object's data
+fn serialize(value: &Value) -> Bytes {
+    // Values built from terms or parsed from JSON always serialize
+    Bytes::from(serde_json::to_vec(value).unwrap())
+}
+
+/// Apply a JSON Merge Patch (RFC 7386) to `target`
+///
+/// Objects in `patch` are merged into `target` key by key, `null` values
+/// remove keys, and any other value replaces the target.
+fn merge_patch(target: &mut Value, patch: Value) {
+    let Value::Object(patch) = patch else {
+        *target = patch;
+        return;
+    };
+    if !target.is_object() {
+        *target = Value::Object(Map::new());
+    }
+    let Value::Object(object) = target else {
+        unreachable!()
+    };
+
+    for (key, value) in patch {
+        if value.is_null() {
+            object.remove(&key);
+        } else {
+            merge_patch(object.entry(key).or_insert(Value::Null), value);
+        }
+    }
+}
+
+/// Merge a patch into a document, creating it from the patch if it doesn't
+/// exist
+///
+/// With `cas`, the document is written with a conditional put and the patch
+/// is applied again to the current document when another writer changed it
+/// in between, up to `max_attempts` times. Without, it is read, merged and
+/// overwritten.
+async fn merge(
+    store: &DynObjectStore,
+    path: &Path,
+    patch: Value,
+    cas: bool,
+    max_attempts: u32,
+) -> Result<(Value, String)> {
+    let mut current = match store.get(path).await {
+        Ok(result) => {
+            let etag = result.meta.e_tag.clone();
+            Some((result.bytes().await?, etag))
+        }
+        Err(object_store::Error::NotFound { .. }) => None,
+        Err(e) => return Err(e),
+    };
+
+    for _ in 0..max_attempts.max(1) {
+        let (mut document, etag) = match &current {
+            Some((data, etag)) => (parse(path, data)?, etag.clone()),
+            None => (Value::Null, None),
+        };
+        merge_patch(&mut document, patch.clone());
+        let data = serialize(&document);
+
+        if !cas {
+            let result = store.put(path, PutPayload::from_bytes(data)).await?;
+            return Ok((document, result.e_tag.unwrap_or_default()));
+        }
+
+        match swap(store, path, etag.as_deref(), data, MAX_SWAP_RETRIES).await? {
+            Swap::Done(result) => return Ok((document, result.e_tag.unwrap_or_default())),
+            Swap::Conflict(state) => current = state,
+        }
+    }
+
+    Err(object_store::Error::Precondition {
+        path: path.to_string(),
+        source: format!("Document changed on each of {} attempts", max_attempts).into(),
+    })
+}
+
+/// Get an object and parse it as JSON
+///
+/// Returns `{:ok, value, etag}` (empty if the store has no ETags), or
+/// `:invalid_json` if the object isn't valid JSON.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn get_json<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let path = Path::from(path);
+
+    let result = RUNTIME.block_on(async {
+        let result = store.inner.get(&path).await?;
+        let etag = result.meta.e_tag.clone().unwrap_or_default();
+        let value = parse(&path, &result.bytes().await?)?;
+        Ok((value, etag))
+    });
+
+    match result {
+        Ok((value, etag)) => Ok((atoms::ok(), JsonNif(value), etag).encode(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
+
+/// Serialize a value as JSON and put it with a put mode
+///
+/// Returns `{:ok, etag, version}`; missing identifiers are empty strings.
+/// Raises ArgumentError for values that can't be represented as JSON.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn put_json<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+    value: JsonNif,
+    mode: PutModeNif,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let options = PutOptions {
+        mode: put_mode(mode),
+        ..Default::default()
+    };
+    let payload = PutPayload::from_bytes(serialize(&value.0));
+
+    match RUNTIME.block_on(store.inner.put_opts(&Path::from(path), payload, options)) {
+        Ok(result) => {
+            let etag = result.e_tag.unwrap_or_default();
+            let version = result.version.unwrap_or_default();
+            Ok((atoms::ok(), etag, version).encode(env))
+        }
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
+
+/// Merge a JSON Merge Patch (RFC 7386) into a JSON document
+///
+/// Missing documents are created from the patch. With `cas`, concurrent
+/// changes are detected with the document's ETag and the patch is applied
+/// again to the current document, up to `max_attempts` times before failing
+/// with `:precondition_failed`; stores without conditional puts return
+/// `:not_supported`. Returns `{:ok, document, etag}` with the merged document.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn merge_json<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+    patch: JsonNif,
+    cas: bool,
+    max_attempts: u32,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let path = Path::from(path);
+
+    match RUNTIME.block_on(merge(
+        store.inner.as_ref(),
+        &path,
+        patch.0,
+        cas,
+        max_attempts,
+    )) {
+        Ok((document, etag)) => Ok((atoms::ok(), JsonNif(document), etag).encode(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
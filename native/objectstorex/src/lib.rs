@@ -1,28 +1,92 @@
 use once_cell::sync::Lazy;
-use rustler::Env;
-use tokio::runtime::Runtime;
+use rustler::{Env, NifMap};
 
+mod append;
+mod archive;
 mod atoms;
+mod backend;
+mod backup;
+mod bench;
+mod buf_reader;
+mod buf_writer;
 mod builders;
+mod cas;
+mod checksum;
+mod chunked;
+mod conditional;
 mod errors;
+mod gcs_api;
+mod incomplete_uploads;
+mod json;
+mod list_filter;
+mod object_lock;
 mod operations;
+mod paging;
+mod patch;
+mod post_policy;
+mod profiles;
+mod put_stream;
+mod reports;
+mod rest;
+mod restore;
+mod runtime;
+mod s3_api;
+mod shutdown;
+mod snapshot;
+mod split;
+mod sse;
 mod store;
 mod streaming;
+mod tagging;
+mod tokens;
+mod transfer;
+mod ttl;
 mod types;
+mod versions;
+mod wrappers;
 
+use buf_reader::BufReaderWrapper;
+use buf_writer::BufWriterWrapper;
+use put_stream::PutStreamWrapper;
+use snapshot::ListingSnapshot;
 use store::StoreWrapper;
-use streaming::UploadSessionWrapper;
+use streaming::{StreamMonitor, UploadSessionWrapper, UploadStreamWrapper};
 
-// Lazy static Tokio runtime for async operations
-pub(crate) static RUNTIME: Lazy<Runtime> =
-    Lazy::new(|| tokio::runtime::Runtime::new().expect("Failed to create Tokio runtime"));
+// Lazy static Tokio runtime for async operations, built on first use after
+// the NIF is loaded
+pub(crate) static RUNTIME: Lazy<runtime::NifRuntime> = Lazy::new(runtime::NifRuntime::new);
 
 // Initialize the NIF module
 rustler::init!("Elixir.ObjectStoreX.Native", load = on_load);
 
+/// Library settings passed by `ObjectStoreX.NativeConfig.load_data/0` when
+/// the NIF is loaded
+#[derive(NifMap)]
+struct LoadDataNif {
+    /// Limit on provider requests in flight across all stores
+    max_concurrent_requests: Option<usize>,
+    /// Thread settings of the Tokio runtime
+    runtime: runtime::RuntimeConfigNif,
+}
+
 #[allow(non_local_definitions)]
-fn on_load(env: Env, _info: rustler::Term) -> bool {
+fn on_load(env: Env, info: rustler::Term) -> bool {
+    if let Ok(config) = info.decode::<LoadDataNif>() {
+        if let Some(max) = config.max_concurrent_requests {
+            wrappers::request_limit::configure(max);
+        }
+        runtime::configure(config.runtime);
+    }
+
     let _ = rustler::resource!(StoreWrapper, env);
     let _ = rustler::resource!(UploadSessionWrapper, env);
+    let _ = rustler::resource!(UploadStreamWrapper, env);
+    let _ = rustler::resource!(ListingSnapshot, env);
+    let _ = rustler::resource!(PutStreamWrapper, env);
+    let _ = rustler::resource!(BufWriterWrapper, env);
+    let _ = rustler::resource!(BufReaderWrapper, env);
+    let _ = env.register::<StreamMonitor>();
+    let _ = env.register::<backend::BackendMonitor>();
+    let _ = env.register::<wrappers::deadline::OpRef>();
     true
 }
@@ -0,0 +1,90 @@
+//! Filters applied to listings in Rust, before objects cross the NIF boundary
+
+use crate::types::ListFilterNif;
+use object_store::ObjectMeta;
+use regex_lite::Regex;
+use rustler::NifResult;
+
+/// Compiled form of a `ListFilterNif`
+pub struct ListFilter {
+    glob: Option<Regex>,
+    regex: Option<Regex>,
+    suffix: Option<String>,
+    min_size: Option<u64>,
+    max_size: Option<u64>,
+    modified_after: Option<i64>,
+    modified_before: Option<i64>,
+}
+
+/// Translate a glob into an anchored regular expression
+///
+/// `*` and `?` match within one path segment, `**` matches across segments,
+/// and `**/` also matches no segment at all.
+fn glob_to_regex(glob: &str) -> String {
+    let mut regex = String::from("^");
+    let mut chars = glob.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    regex.push_str("(?:.*/)?");
+                } else {
+                    regex.push_str(".*");
+                }
+            }
+            '*' => regex.push_str("[^/]*"),
+            '?' => regex.push_str("[^/]"),
+            c => regex.push_str(&regex_lite::escape(c.encode_utf8(&mut [0; 4]))),
+        }
+    }
+
+    regex.push('$');
+    regex
+}
+
+fn compile(pattern: &str) -> NifResult<Regex> {
+    Regex::new(pattern)
+        .map_err(|e| rustler::Error::Term(Box::new(format!("Invalid list filter pattern: {}", e))))
+}
+
+impl ListFilter {
+    pub fn new(filter: ListFilterNif) -> NifResult<Self> {
+        Ok(Self {
+            glob: filter
+                .glob
+                .map(|glob| compile(&glob_to_regex(&glob)))
+                .transpose()?,
+            regex: filter.regex.map(|regex| compile(&regex)).transpose()?,
+            suffix: filter.suffix,
+            min_size: filter.min_size,
+            max_size: filter.max_size,
+            modified_after: filter.modified_after,
+            modified_before: filter.modified_before,
+        })
+    }
+
+    pub fn matches(&self, meta: &ObjectMeta) -> bool {
+        let location = meta.location.as_ref();
+        let size = meta.size as u64;
+        let modified = meta.last_modified.timestamp();
+
+        self.glob
+            .as_ref()
+            .is_none_or(|glob| glob.is_match(location))
+            && self
+                .regex
+                .as_ref()
+                .is_none_or(|regex| regex.is_match(location))
+            && self
+                .suffix
+                .as_ref()
+                .is_none_or(|s| location.ends_with(s.as_str()))
+            && self.min_size.is_none_or(|min| size >= min)
+            && self.max_size.is_none_or(|max| size <= max)
+            && self.modified_after.is_none_or(|after| modified >= after)
+            && self.modified_before.is_none_or(|before| modified < before)
+    }
+}
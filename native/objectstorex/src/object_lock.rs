@@ -0,0 +1,302 @@
+//! S3 Object Lock retention periods and legal holds, which object_store
+//! doesn't expose
+
+use crate::atoms;
+use crate::errors::map_error;
+use crate::s3_api::{generic, xml_element, S3Api};
+use crate::shutdown;
+use crate::store::StoreWrapper;
+use crate::types::TimestampNif;
+use crate::RUNTIME;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use chrono::{DateTime, SecondsFormat, Utc};
+use md5::{Digest, Md5};
+use object_store::path::Path;
+use object_store::{Error, Result};
+use reqwest::Method;
+use rustler::{Atom, Decoder, Encoder, Env, NifMap, NifResult, ResourceArc, Term};
+
+/// Error code of reads of a retention or legal hold the object doesn't have
+const NO_CONFIGURATION: &str = "NoSuchObjectLockConfiguration";
+
+/// Object Lock retention mode
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RetentionMode {
+    /// Users allowed to bypass governance mode may shorten or remove it
+    Governance,
+    /// Nobody can shorten or remove it, not even the root user
+    Compliance,
+}
+
+impl RetentionMode {
+    fn from_atom(atom: Atom) -> NifResult<Self> {
+        match atom {
+            a if a == atoms::governance() => Ok(RetentionMode::Governance),
+            a if a == atoms::compliance() => Ok(RetentionMode::Compliance),
+            _ => Err(rustler::Error::BadArg),
+        }
+    }
+
+    fn atom(&self) -> Atom {
+        match self {
+            RetentionMode::Governance => atoms::governance(),
+            RetentionMode::Compliance => atoms::compliance(),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            RetentionMode::Governance => "GOVERNANCE",
+            RetentionMode::Compliance => "COMPLIANCE",
+        }
+    }
+
+    fn parse(mode: &str) -> Option<Self> {
+        match mode {
+            "GOVERNANCE" => Some(RetentionMode::Governance),
+            "COMPLIANCE" => Some(RetentionMode::Compliance),
+            _ => None,
+        }
+    }
+}
+
+/// Retention period of an object version, which can't be deleted or
+/// overwritten before `retain_until`
+///
+/// Decoded from and encoded as `{mode, timestamp}`, with the mode
+/// `:governance` or `:compliance` and the timestamp as in `TimestampNif`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Retention {
+    pub mode: RetentionMode,
+    pub retain_until: DateTime<Utc>,
+}
+
+impl Retention {
+    /// `RetainUntilDate` value, in the format S3 returns
+    fn retain_until_date(&self) -> String {
+        self.retain_until
+            .to_rfc3339_opts(SecondsFormat::Millis, true)
+    }
+}
+
+impl<'a> Decoder<'a> for Retention {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        let (mode, retain_until): (Atom, TimestampNif) = term.decode()?;
+        Ok(Retention {
+            mode: RetentionMode::from_atom(mode)?,
+            retain_until: retain_until.0,
+        })
+    }
+}
+
+impl Encoder for Retention {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        (self.mode.atom(), TimestampNif(self.retain_until)).encode(env)
+    }
+}
+
+/// Object Lock settings applied by a put
+#[derive(Debug, NifMap)]
+pub struct ObjectLockNif {
+    pub retention: Option<Retention>,
+    pub legal_hold: Option<bool>,
+}
+
+impl ObjectLockNif {
+    /// Headers applying the settings to an uploaded object
+    pub(crate) fn headers(&self) -> Vec<(&'static str, String)> {
+        let mut headers = Vec::new();
+        if let Some(retention) = &self.retention {
+            headers.push(("x-amz-object-lock-mode", retention.mode.as_str().into()));
+            headers.push((
+                "x-amz-object-lock-retain-until-date",
+                retention.retain_until_date(),
+            ));
+        }
+        if let Some(legal_hold) = self.legal_hold {
+            headers.push((
+                "x-amz-object-lock-legal-hold",
+                legal_hold_status(legal_hold),
+            ));
+        }
+        headers
+    }
+}
+
+fn legal_hold_status(on: bool) -> String {
+    if on { "ON" } else { "OFF" }.to_string()
+}
+
+/// Whether a read failed because the object has no setting of its kind
+fn no_configuration(error: &Error) -> bool {
+    error.to_string().contains(NO_CONFIGURATION)
+}
+
+impl S3Api {
+    /// Get the retention period of an object, if it has one
+    pub async fn get_retention(&self, path: &Path) -> Result<Option<Retention>> {
+        let body = match self
+            .send(Method::GET, path, Some("retention"), &[], None)
+            .await
+        {
+            Err(e) if no_configuration(&e) => return Ok(None),
+            result => result?,
+        };
+
+        let mode = xml_element(&body, b"Mode").and_then(|mode| RetentionMode::parse(&mode));
+        let retain_until = xml_element(&body, b"RetainUntilDate")
+            .and_then(|date| DateTime::parse_from_rfc3339(&date).ok());
+        match (mode, retain_until) {
+            (Some(mode), Some(retain_until)) => Ok(Some(Retention {
+                mode,
+                retain_until: retain_until.with_timezone(&Utc),
+            })),
+            _ => Err(generic("Invalid retention response".to_string())),
+        }
+    }
+
+    /// Set the retention period of an object
+    ///
+    /// Shortening a governance retention requires `bypass_governance` and
+    /// the `s3:BypassGovernanceRetention` permission.
+    pub async fn put_retention(
+        &self,
+        path: &Path,
+        retention: &Retention,
+        bypass_governance: bool,
+    ) -> Result<()> {
+        let body = format!(
+            r#"<Retention xmlns="http://s3.amazonaws.com/doc/2006-03-01/"><Mode>{}</Mode><RetainUntilDate>{}</RetainUntilDate></Retention>"#,
+            retention.mode.as_str(),
+            retention.retain_until_date()
+        );
+        let mut headers = vec![("Content-MD5", content_md5(&body))];
+        if bypass_governance {
+            headers.push(("x-amz-bypass-governance-retention", "true".to_string()));
+        }
+
+        self.send(Method::PUT, path, Some("retention"), &headers, Some(body))
+            .await
+            .map(|_| ())
+    }
+
+    /// Whether an object is under legal hold
+    pub async fn get_legal_hold(&self, path: &Path) -> Result<bool> {
+        let body = match self
+            .send(Method::GET, path, Some("legal-hold"), &[], None)
+            .await
+        {
+            Err(e) if no_configuration(&e) => return Ok(false),
+            result => result?,
+        };
+
+        match xml_element(&body, b"Status").as_deref() {
+            Some("ON") => Ok(true),
+            Some("OFF") => Ok(false),
+            _ => Err(generic("Invalid legal hold response".to_string())),
+        }
+    }
+
+    /// Place or remove a legal hold on an object
+    pub async fn put_legal_hold(&self, path: &Path, on: bool) -> Result<()> {
+        let body = format!(
+            r#"<LegalHold xmlns="http://s3.amazonaws.com/doc/2006-03-01/"><Status>{}</Status></LegalHold>"#,
+            legal_hold_status(on)
+        );
+        let headers = [("Content-MD5", content_md5(&body))];
+
+        self.send(Method::PUT, path, Some("legal-hold"), &headers, Some(body))
+            .await
+            .map(|_| ())
+    }
+}
+
+/// `Content-MD5` header value, which Object Lock requests require
+fn content_md5(body: &str) -> String {
+    BASE64_STANDARD.encode(Md5::digest(body.as_bytes()))
+}
+
+/// Get the retention period of an object
+///
+/// Returns `{mode, timestamp}`, `nil` if the object has none, or
+/// `:not_supported` for stores without Object Lock.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn get_retention<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let Some(s3) = store.s3.clone() else {
+        return Ok(atoms::not_supported().to_term(env));
+    };
+
+    match RUNTIME.block_on(s3.get_retention(&Path::from(path))) {
+        Ok(retention) => Ok(retention.encode(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
+
+/// Set the retention period of an object
+///
+/// Returns `:not_supported` for stores without Object Lock.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn put_retention<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+    retention: Retention,
+    bypass_governance: bool,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let Some(s3) = store.s3.clone() else {
+        return Ok(atoms::not_supported().to_term(env));
+    };
+
+    let path = Path::from(path);
+    match RUNTIME.block_on(s3.put_retention(&path, &retention, bypass_governance)) {
+        Ok(()) => Ok(atoms::ok().to_term(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
+
+/// Whether an object is under legal hold
+///
+/// Returns a boolean, or `:not_supported` for stores without Object Lock.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn get_legal_hold<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let Some(s3) = store.s3.clone() else {
+        return Ok(atoms::not_supported().to_term(env));
+    };
+
+    match RUNTIME.block_on(s3.get_legal_hold(&Path::from(path))) {
+        Ok(on) => Ok(on.encode(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
+
+/// Place or remove a legal hold on an object
+///
+/// Returns `:not_supported` for stores without Object Lock.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn put_legal_hold<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+    on: bool,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let Some(s3) = store.s3.clone() else {
+        return Ok(atoms::not_supported().to_term(env));
+    };
+
+    match RUNTIME.block_on(s3.put_legal_hold(&Path::from(path), on)) {
+        Ok(()) => Ok(atoms::ok().to_term(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
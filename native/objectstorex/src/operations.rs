@@ -1,14 +1,22 @@
 use crate::atoms;
+use crate::checksum::{self, ChecksumAlgorithm, Verifier};
 use crate::errors::map_error;
+use crate::object_lock::ObjectLockNif;
+use crate::paging;
+use crate::shutdown;
 use crate::store::StoreWrapper;
-use crate::types::{AttributesNif, GetOptionsNif, PutModeNif};
+use crate::streaming::put_last_modified_us;
+use crate::types::{
+    to_usize, AttributesNif, DeleteOptionsNif, GetOptionsNif, IoDataNif, PutModeNif,
+};
 use crate::RUNTIME;
-use chrono::{DateTime, TimeZone, Utc};
+use base64::prelude::{Engine, BASE64_STANDARD};
+use bytes::Bytes;
 use object_store::{
-    path::Path, Attribute, Attributes, GetOptions, GetRange, PutMode, PutOptions, PutPayload,
-    UpdateVersion as ObjectStoreUpdateVersion,
+    path::Path, Attribute, Attributes, GetOptions, GetRange, PutMode, PutOptions, PutResult,
+    TagSet, UpdateVersion as ObjectStoreUpdateVersion,
 };
-use rustler::{Binary, Encoder, Env, NifResult, OwnedBinary, ResourceArc, Term};
+use rustler::{Atom, Encoder, Env, NifMap, NifResult, OwnedBinary, ResourceArc, Term};
 
 /// Upload an object to storage
 #[rustler::nif(schedule = "DirtyCpu")]
@@ -16,9 +24,10 @@ pub fn put<'a>(
     env: Env<'a>,
     store: ResourceArc<StoreWrapper>,
     path: String,
-    data: Binary,
+    data: IoDataNif,
 ) -> NifResult<Term<'a>> {
-    let payload = PutPayload::from(data.as_slice().to_vec());
+    shutdown::ensure_running()?;
+    let payload = data.into_payload();
 
     match RUNTIME.block_on(async { store.inner.put(&Path::from(path), payload).await }) {
         Ok(_) => Ok(atoms::ok().to_term(env)),
@@ -32,25 +41,16 @@ pub fn put_with_mode<'a>(
     env: Env<'a>,
     store: ResourceArc<StoreWrapper>,
     path: String,
-    data: Binary,
+    data: IoDataNif,
     mode: PutModeNif,
 ) -> NifResult<Term<'a>> {
-    // Convert PutModeNif to object_store::PutMode
-    let rust_mode = match mode {
-        PutModeNif::Overwrite => PutMode::Overwrite,
-        PutModeNif::Create => PutMode::Create,
-        PutModeNif::Update { etag, version } => PutMode::Update(ObjectStoreUpdateVersion {
-            e_tag: etag,
-            version,
-        }),
-    };
-
+    shutdown::ensure_running()?;
     let opts = PutOptions {
-        mode: rust_mode,
+        mode: put_mode(mode),
         ..Default::default()
     };
 
-    let payload = PutPayload::from(data.as_slice().to_vec());
+    let payload = data.into_payload();
 
     match RUNTIME.block_on(async { store.inner.put_opts(&Path::from(path), payload, opts).await }) {
         Ok(put_result) => {
@@ -70,6 +70,7 @@ pub fn get<'a>(
     store: ResourceArc<StoreWrapper>,
     path: String,
 ) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
     let result = RUNTIME.block_on(async { store.inner.get(&Path::from(path)).await });
 
     match result {
@@ -85,6 +86,34 @@ pub fn get<'a>(
     }
 }
 
+/// Download an object with its metadata and attributes in one request
+///
+/// Returns `{:ok, data, meta}` with the same metadata map as `head`.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn get_with_metadata<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let result = RUNTIME.block_on(async {
+        let result = store.inner.get(&Path::from(path)).await?;
+        let meta = result.meta.clone();
+        let attributes = result.attributes.clone();
+        Ok::<_, object_store::Error>((result.bytes().await?, meta, attributes))
+    });
+
+    match result {
+        Ok((bytes, meta, attributes)) => {
+            let mut binary = OwnedBinary::new(bytes.len()).unwrap();
+            binary.as_mut_slice().copy_from_slice(&bytes);
+            let meta = encode_object_meta_with_attributes(env, &meta, &attributes);
+            Ok((atoms::ok(), binary.release(env), meta).encode(env))
+        }
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
+
 /// Delete an object from storage
 #[rustler::nif(schedule = "DirtyCpu")]
 pub fn delete<'a>(
@@ -92,12 +121,42 @@ pub fn delete<'a>(
     store: ResourceArc<StoreWrapper>,
     path: String,
 ) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
     match RUNTIME.block_on(async { store.inner.delete(&Path::from(path)).await }) {
         Ok(_) => Ok(atoms::ok().to_term(env)),
         Err(e) => Ok(map_error(e).to_term(env)),
     }
 }
 
+/// Delete an object only if it matches an ETag and/or delete a specific version
+///
+/// Without conditions this is a plain delete. Conditions are sent as provider
+/// conditional deletes (S3); other stores return `:not_supported` rather than
+/// deleting unconditionally.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn delete_with_options<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+    options: DeleteOptionsNif,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let path = Path::from(path);
+
+    let result = match (&options.if_match, &options.version) {
+        (None, None) => RUNTIME.block_on(store.inner.delete(&path)),
+        (if_match, version) => match store.s3.clone() {
+            Some(s3) => RUNTIME.block_on(s3.delete(&path, if_match.as_deref(), version.as_deref())),
+            None => return Ok(atoms::not_supported().to_term(env)),
+        },
+    };
+
+    match result {
+        Ok(()) => Ok(atoms::ok().to_term(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
+
 /// Get object metadata without downloading content
 ///
 /// Uses get_opts with head: true to retrieve full metadata including attributes
@@ -107,6 +166,7 @@ pub fn head<'a>(
     store: ResourceArc<StoreWrapper>,
     path: String,
 ) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
     // Use get_opts with head: true to get attributes
     let opts = GetOptions {
         head: true,
@@ -137,6 +197,7 @@ pub fn copy<'a>(
     from: String,
     to: String,
 ) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
     match RUNTIME.block_on(async { store.inner.copy(&Path::from(from), &Path::from(to)).await }) {
         Ok(_) => Ok(atoms::ok().to_term(env)),
         Err(e) => Ok(map_error(e).to_term(env)),
@@ -144,6 +205,10 @@ pub fn copy<'a>(
 }
 
 /// Rename an object (server-side move)
+///
+/// Returns `{:ok, strategy}` where strategy is `:rename`, or `:copy_delete`
+/// when a local store had to copy the file because source and destination
+/// are on different devices.
 #[rustler::nif(schedule = "DirtyCpu")]
 pub fn rename<'a>(
     env: Env<'a>,
@@ -151,12 +216,290 @@ pub fn rename<'a>(
     from: String,
     to: String,
 ) -> NifResult<Term<'a>> {
-    match RUNTIME.block_on(async { store.inner.rename(&Path::from(from), &Path::from(to)).await }) {
-        Ok(_) => Ok(atoms::ok().to_term(env)),
+    shutdown::ensure_running()?;
+    let from = Path::from(from);
+    let to = Path::from(to);
+
+    let result = RUNTIME.block_on(rename_object(store.inner.as_ref(), &from, &to));
+
+    match result {
+        Ok(strategy) => Ok((atoms::ok(), strategy).encode(env)),
         Err(e) => Ok(map_error(e).to_term(env)),
     }
 }
 
+/// Rename an object, falling back to copy and delete across devices
+///
+/// Returns the strategy used, `:rename` or `:copy_delete`.
+async fn rename_object(
+    store: &object_store::DynObjectStore,
+    from: &Path,
+    to: &Path,
+) -> object_store::Result<Atom> {
+    match store.rename(from, to).await {
+        Ok(_) => Ok(atoms::rename()),
+        Err(e) if is_cross_device(&e) => {
+            copy_across_devices(store, from, to).await?;
+            store.delete(from).await?;
+            Ok(atoms::copy_delete())
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Rename an object unless the destination exists, returning whether it was
+/// renamed
+///
+/// Stores without an atomic rename-if-not-exists check the destination first.
+async fn rename_unless_exists(
+    store: &object_store::DynObjectStore,
+    from: &Path,
+    to: &Path,
+) -> object_store::Result<bool> {
+    match store.rename_if_not_exists(from, to).await {
+        Ok(()) => Ok(true),
+        Err(object_store::Error::AlreadyExists { .. }) => Ok(false),
+        Err(e) if matches!(e, object_store::Error::NotSupported { .. }) || is_cross_device(&e) => {
+            match store.head(to).await {
+                Ok(_) => Ok(false),
+                Err(object_store::Error::NotFound { .. }) => {
+                    rename_object(store, from, to).await.map(|_| true)
+                }
+                Err(e) => Err(e),
+            }
+        }
+        Err(e) => Err(e),
+    }
+}
+
+/// Attempts at finding a free suffixed destination before giving up
+const MAX_SUFFIX: usize = 1000;
+
+/// How `rename_many` handles destinations that already exist
+#[derive(Debug, Clone, Copy)]
+enum Collision {
+    /// Replace the destination
+    Overwrite,
+    /// Fail the pair with `:already_exists`
+    Fail,
+    /// Leave both objects alone
+    Skip,
+    /// Rename to the first free `name-N.ext` instead
+    Suffix,
+}
+
+impl Collision {
+    fn from_atom(atom: Atom) -> NifResult<Self> {
+        match atom {
+            a if a == atoms::overwrite() => Ok(Collision::Overwrite),
+            a if a == atoms::fail() => Ok(Collision::Fail),
+            a if a == atoms::skip() => Ok(Collision::Skip),
+            a if a == atoms::suffix() => Ok(Collision::Suffix),
+            _ => Err(rustler::Error::BadArg),
+        }
+    }
+}
+
+/// `to` with `-n` inserted before the extension of its file name
+fn suffixed(to: &Path, n: usize) -> Path {
+    let (dir, name) = match to.as_ref().rsplit_once('/') {
+        Some((dir, name)) => (Some(dir), name),
+        None => (None, to.as_ref()),
+    };
+    let name = match name.rfind('.').filter(|&dot| dot > 0) {
+        Some(dot) => format!("{}-{}{}", &name[..dot], n, &name[dot..]),
+        None => format!("{}-{}", name, n),
+    };
+
+    match dir {
+        Some(dir) => Path::from(format!("{}/{}", dir, name)),
+        None => Path::from(name),
+    }
+}
+
+/// Rename one pair of `rename_many`, returning where the object went, or
+/// `None` if it was skipped
+async fn rename_with_policy(
+    store: &object_store::DynObjectStore,
+    from: &Path,
+    to: &Path,
+    collision: Collision,
+) -> object_store::Result<Option<Path>> {
+    let exists = |path: &Path| object_store::Error::AlreadyExists {
+        path: path.to_string(),
+        source: "Destination already exists".into(),
+    };
+
+    match collision {
+        Collision::Overwrite => rename_object(store, from, to)
+            .await
+            .map(|_| Some(to.clone())),
+        Collision::Fail => match rename_unless_exists(store, from, to).await? {
+            true => Ok(Some(to.clone())),
+            false => Err(exists(to)),
+        },
+        Collision::Skip => match rename_unless_exists(store, from, to).await? {
+            true => Ok(Some(to.clone())),
+            false => Ok(None),
+        },
+        Collision::Suffix => {
+            for n in 0..=MAX_SUFFIX {
+                let target = if n == 0 { to.clone() } else { suffixed(to, n) };
+                if rename_unless_exists(store, from, &target).await? {
+                    return Ok(Some(target));
+                }
+            }
+            Err(exists(to))
+        }
+    }
+}
+
+/// Copy many objects concurrently (server-side)
+///
+/// At most `max_concurrency` copies are in flight at once. Returns a list of
+/// `{{from, to}, :ok | {:error, reason}}` in the order of `pairs`; a failed
+/// copy doesn't stop the others.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn copy_many<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    pairs: Vec<(String, String)>,
+    max_concurrency: usize,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let results = RUNTIME.block_on(for_each_pair(&pairs, max_concurrency, |from, to| {
+        let store = &store.inner;
+        async move { store.copy(&from, &to).await }
+    }));
+
+    Ok(encode_pair_results(env, &pairs, results, |_, ()| {
+        atoms::ok().encode(env)
+    }))
+}
+
+/// Rename many objects concurrently (server-side)
+///
+/// Like `copy_many`, with each pair renamed as by `rename`. `on_conflict`
+/// decides what happens when a destination exists: `:overwrite` replaces it,
+/// `:fail` fails the pair with `:already_exists`, `:skip` leaves both objects
+/// and `:suffix` renames to the first free `name-N.ext`. Successful pairs
+/// report `:ok`, `{:ok, suffixed_to}` or `:skipped`.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn rename_many<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    pairs: Vec<(String, String)>,
+    on_conflict: Atom,
+    max_concurrency: usize,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let collision = Collision::from_atom(on_conflict)?;
+
+    let results = RUNTIME.block_on(for_each_pair(&pairs, max_concurrency, |from, to| {
+        let store = &store.inner;
+        async move { rename_with_policy(store.as_ref(), &from, &to, collision).await }
+    }));
+
+    Ok(encode_pair_results(
+        env,
+        &pairs,
+        results,
+        |(_, to), target| match target {
+            Some(target) if target.as_ref() == Path::from(to.as_str()).as_ref() => {
+                atoms::ok().encode(env)
+            }
+            Some(target) => (atoms::ok(), target.to_string()).encode(env),
+            None => atoms::skipped().encode(env),
+        },
+    ))
+}
+
+/// Run `action` on every `{from, to}` pair, at most `max_concurrency` at once
+async fn for_each_pair<T, F, Fut>(
+    pairs: &[(String, String)],
+    max_concurrency: usize,
+    action: F,
+) -> Vec<object_store::Result<T>>
+where
+    F: Fn(Path, Path) -> Fut,
+    Fut: std::future::Future<Output = object_store::Result<T>>,
+{
+    use futures::stream::{self, StreamExt};
+
+    stream::iter(pairs)
+        .map(|(from, to)| action(Path::from(from.as_str()), Path::from(to.as_str())))
+        .buffered(max_concurrency.max(1))
+        .collect()
+        .await
+}
+
+/// Encode `{{from, to}, result}` entries, with successes encoded by `encode_ok`
+/// and failures as `{:error, reason}`
+fn encode_pair_results<'a, T>(
+    env: Env<'a>,
+    pairs: &[(String, String)],
+    results: Vec<object_store::Result<T>>,
+    encode_ok: impl Fn(&(String, String), T) -> Term<'a>,
+) -> Term<'a> {
+    let entries: Vec<Term<'a>> = pairs
+        .iter()
+        .zip(results)
+        .map(|(pair, result)| {
+            let result = match result {
+                Ok(value) => encode_ok(pair, value),
+                Err(e) => (atoms::error(), map_error(e)).encode(env),
+            };
+            (pair, result).encode(env)
+        })
+        .collect();
+
+    entries.encode(env)
+}
+
+/// Whether an error was caused by moving a file across devices (EXDEV)
+fn is_cross_device(error: &object_store::Error) -> bool {
+    let mut source: Option<&(dyn std::error::Error + 'static)> = std::error::Error::source(error);
+
+    while let Some(error) = source {
+        if let Some(io) = error.downcast_ref::<std::io::Error>() {
+            return io.kind() == std::io::ErrorKind::CrossesDevices;
+        }
+        source = error.source();
+    }
+
+    false
+}
+
+/// Copy an object by streaming its content, for when the filesystem can
+/// neither rename nor hard link between the two paths
+async fn copy_across_devices(
+    store: &object_store::DynObjectStore,
+    from: &Path,
+    to: &Path,
+) -> object_store::Result<()> {
+    use futures::StreamExt;
+    use object_store::WriteMultipart;
+
+    let mut stream = store.get(from).await?.into_stream();
+    let mut writer = WriteMultipart::new(store.put_multipart(to).await?);
+
+    while let Some(chunk) = stream.next().await {
+        match chunk {
+            Ok(chunk) => {
+                writer.wait_for_capacity(8).await?;
+                writer.write(&chunk);
+            }
+            Err(e) => {
+                writer.abort().await?;
+                return Err(e);
+            }
+        }
+    }
+
+    writer.finish().await?;
+    Ok(())
+}
+
 /// Fetch multiple byte ranges from an object in a single operation
 #[rustler::nif(schedule = "DirtyCpu")]
 pub fn get_ranges<'a>(
@@ -165,13 +508,14 @@ pub fn get_ranges<'a>(
     path: String,
     ranges: Vec<(u64, u64)>,
 ) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
     use std::ops::Range;
 
     // Convert Vec<(u64, u64)> to Vec<Range<usize>>
     let range_objects: Vec<Range<usize>> = ranges
         .into_iter()
-        .map(|(start, end)| (start as usize)..(end as usize))
-        .collect();
+        .map(|(start, end)| Ok(to_usize(start)?..to_usize(end)?))
+        .collect::<NifResult<_>>()?;
 
     let results = RUNTIME.block_on(async {
         store
@@ -198,6 +542,98 @@ pub fn get_ranges<'a>(
     }
 }
 
+/// Fetch many objects concurrently
+///
+/// At most `max_concurrency` gets are in flight at once. Returns a list of
+/// `{path, {:ok, binary} | {:error, reason}}` in the order of `paths`; a
+/// failed get doesn't stop the others.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn get_many<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    paths: Vec<String>,
+    max_concurrency: usize,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    use futures::stream::{self, StreamExt};
+
+    let results: Vec<object_store::Result<bytes::Bytes>> = RUNTIME.block_on(
+        stream::iter(paths.iter())
+            .map(|path| {
+                let store = &store.inner;
+                async move { store.get(&Path::from(path.as_str())).await?.bytes().await }
+            })
+            .buffered(max_concurrency.max(1))
+            .collect(),
+    );
+
+    let entries: Vec<Term<'a>> = paths
+        .iter()
+        .zip(results)
+        .map(|(path, result)| {
+            let result = match result {
+                Ok(bytes) => {
+                    let mut binary = OwnedBinary::new(bytes.len()).unwrap();
+                    binary.as_mut_slice().copy_from_slice(&bytes);
+                    (atoms::ok(), binary.release(env)).encode(env)
+                }
+                Err(e) => (atoms::error(), map_error(e)).encode(env),
+            };
+            (path, result).encode(env)
+        })
+        .collect();
+
+    Ok(entries.encode(env))
+}
+
+/// Fetch the metadata of many objects concurrently
+///
+/// At most `max_concurrency` heads are in flight at once. Returns a list of
+/// `{path, {:ok, metadata} | {:error, reason}}` in the order of `paths`, with
+/// the same metadata maps as `head`.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn head_many<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    paths: Vec<String>,
+    max_concurrency: usize,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    use futures::stream::{self, StreamExt};
+
+    let results: Vec<object_store::Result<object_store::GetResult>> = RUNTIME.block_on(
+        stream::iter(paths.iter())
+            .map(|path| {
+                let store = &store.inner;
+                let opts = GetOptions {
+                    head: true,
+                    ..Default::default()
+                };
+                async move { store.get_opts(&Path::from(path.as_str()), opts).await }
+            })
+            .buffered(max_concurrency.max(1))
+            .collect(),
+    );
+
+    let entries: Vec<Term<'a>> = paths
+        .iter()
+        .zip(results)
+        .map(|(path, result)| {
+            let result = match result {
+                Ok(head) => {
+                    let meta =
+                        encode_object_meta_with_attributes(env, &head.meta, &head.attributes);
+                    (atoms::ok(), meta).encode(env)
+                }
+                Err(e) => (atoms::error(), map_error(e)).encode(env),
+            };
+            (path, result).encode(env)
+        })
+        .collect();
+
+    Ok(entries.encode(env))
+}
+
 /// Delete multiple objects in bulk with automatic batching
 #[rustler::nif(schedule = "DirtyCpu")]
 pub fn delete_many<'a>(
@@ -205,6 +641,7 @@ pub fn delete_many<'a>(
     store: ResourceArc<StoreWrapper>,
     paths: Vec<String>,
 ) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
     use futures::stream::{self, StreamExt};
 
     // Create a stream of paths
@@ -248,7 +685,7 @@ fn encode_object_meta_for_list<'a>(env: Env<'a>, meta: &object_store::ObjectMeta
     let map = map
         .map_put(
             Atom::from_str(env, "size").unwrap().to_term(env),
-            meta.size.encode(env),
+            (meta.size as u64).encode(env),
         )
         .unwrap();
 
@@ -259,6 +696,8 @@ fn encode_object_meta_for_list<'a>(env: Env<'a>, meta: &object_store::ObjectMeta
         )
         .unwrap();
 
+    let map = put_last_modified_us(env, map, meta);
+
     let map = if let Some(ref etag) = meta.e_tag {
         map.map_put(
             Atom::from_str(env, "etag").unwrap().to_term(env),
@@ -280,16 +719,27 @@ fn encode_object_meta_for_list<'a>(env: Env<'a>, meta: &object_store::ObjectMeta
 }
 
 /// List objects with delimiter, returning objects and common prefixes separately
+///
+/// With `page_size`, S3 and GCS are asked for that many entries per request.
 #[rustler::nif(schedule = "DirtyCpu")]
 pub fn list_with_delimiter<'a>(
     env: Env<'a>,
     store: ResourceArc<StoreWrapper>,
     prefix: Option<String>,
+    page_size: Option<usize>,
 ) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
     let prefix_path = prefix.map(Path::from);
+    let paged = paging::paged(&store, page_size)?;
 
-    let result =
-        RUNTIME.block_on(async { store.inner.list_with_delimiter(prefix_path.as_ref()).await });
+    let result = RUNTIME.block_on(async {
+        match paged {
+            Some((listing, page_size)) => {
+                paging::list_with_delimiter(listing.as_ref(), prefix_path.as_ref(), page_size).await
+            }
+            None => store.inner.list_with_delimiter(prefix_path.as_ref()).await,
+        }
+    });
 
     match result {
         Ok(list_result) => {
@@ -314,19 +764,6 @@ pub fn list_with_delimiter<'a>(
     }
 }
 
-/// Convert Unix timestamp (seconds) to chrono DateTime<Utc>
-///
-/// # Arguments
-/// * `timestamp` - Unix timestamp in seconds since epoch
-///
-/// # Returns
-/// DateTime<Utc> representation of the timestamp
-fn timestamp_to_datetime(timestamp: i64) -> DateTime<Utc> {
-    Utc.timestamp_opt(timestamp, 0)
-        .single()
-        .expect("Invalid timestamp")
-}
-
 /// Download an object from storage with conditional options
 ///
 /// Supports HTTP-style conditional requests for caching and consistency:
@@ -337,6 +774,10 @@ fn timestamp_to_datetime(timestamp: i64) -> DateTime<Utc> {
 /// - range: Fetch specific byte range
 /// - version: Fetch specific object version
 /// - head: Return metadata only
+/// - verify: Check the data against its ETag or a digest (`:integrity_error`)
+///
+/// Returns `{:ok, data, meta}` with the metadata and attributes `head`
+/// returns, plus the range served and the total size for ranged gets.
 #[rustler::nif(schedule = "DirtyCpu")]
 pub fn get_with_options<'a>(
     env: Env<'a>,
@@ -344,6 +785,7 @@ pub fn get_with_options<'a>(
     path: String,
     options: GetOptionsNif,
 ) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
     // Convert GetOptionsNif to object_store::GetOptions
     let mut rust_options = GetOptions::default();
 
@@ -356,15 +798,15 @@ pub fn get_with_options<'a>(
     }
 
     if let Some(timestamp) = options.if_modified_since {
-        rust_options.if_modified_since = Some(timestamp_to_datetime(timestamp));
+        rust_options.if_modified_since = Some(timestamp.0);
     }
 
     if let Some(timestamp) = options.if_unmodified_since {
-        rust_options.if_unmodified_since = Some(timestamp_to_datetime(timestamp));
+        rust_options.if_unmodified_since = Some(timestamp.0);
     }
 
-    if let Some(range) = options.range {
-        rust_options.range = Some(GetRange::Bounded(range.start as usize..range.end as usize));
+    if let Some(range) = &options.range {
+        rust_options.range = Some(GetRange::Bounded(range.to_range()?));
     }
 
     if let Some(version) = options.version {
@@ -373,6 +815,11 @@ pub fn get_with_options<'a>(
 
     rust_options.head = options.head;
 
+    // A digest covers the whole object, so ranges can't be verified
+    if options.verify.is_some() && options.range.is_some() {
+        return Err(rustler::Error::BadArg);
+    }
+
     // Perform the get operation
     let result =
         RUNTIME.block_on(async { store.inner.get_opts(&Path::from(path), rust_options).await });
@@ -381,6 +828,8 @@ pub fn get_with_options<'a>(
         Ok(get_result) => {
             // Get metadata
             let meta = get_result.meta.clone();
+            let range = get_result.range.clone();
+            let attributes = get_result.attributes.clone();
 
             // If head-only request or if we should return data
             let data = if options.head {
@@ -392,8 +841,36 @@ pub fn get_with_options<'a>(
                 }
             };
 
-            // Encode metadata to Elixir map
-            let meta_map = encode_object_meta_with_version(env, &meta);
+            let verifier = options
+                .verify
+                .as_ref()
+                .filter(|_| !options.head)
+                .and_then(|verify| Verifier::new(verify, &meta));
+            if let Some(mut verifier) = verifier {
+                verifier.update(&data);
+                if let Err(e) = verifier.finish() {
+                    return Ok(map_error(e).to_term(env));
+                }
+            }
+
+            // Encode metadata and attributes to Elixir map, as for head
+            let mut meta_map = encode_object_meta_with_attributes(env, &meta, &attributes);
+
+            // Ranged gets also report the satisfied range and the object's
+            // total size, so callers paging through it don't need a head
+            if options.range.is_some() {
+                meta_map = meta_map
+                    .map_put(
+                        atoms::range().encode(env),
+                        (range.start as u64, range.end as u64).encode(env),
+                    )
+                    .unwrap()
+                    .map_put(
+                        atoms::total_size().encode(env),
+                        (meta.size as u64).encode(env),
+                    )
+                    .unwrap();
+            }
 
             // Return {:ok, data, metadata}
             Ok((atoms::ok(), data, meta_map).encode(env))
@@ -402,54 +879,6 @@ pub fn get_with_options<'a>(
     }
 }
 
-/// Helper function to encode ObjectMeta with version information to Elixir map
-fn encode_object_meta_with_version<'a>(env: Env<'a>, meta: &object_store::ObjectMeta) -> Term<'a> {
-    use rustler::types::atom::Atom;
-    use rustler::types::map;
-
-    let map = map::map_new(env);
-
-    let map = map
-        .map_put(
-            Atom::from_str(env, "location").unwrap().to_term(env),
-            meta.location.to_string().encode(env),
-        )
-        .unwrap();
-
-    let map = map
-        .map_put(
-            Atom::from_str(env, "size").unwrap().to_term(env),
-            meta.size.encode(env),
-        )
-        .unwrap();
-
-    let map = map
-        .map_put(
-            Atom::from_str(env, "last_modified").unwrap().to_term(env),
-            meta.last_modified.to_string().encode(env),
-        )
-        .unwrap();
-
-    let map = if let Some(ref etag) = meta.e_tag {
-        map.map_put(
-            Atom::from_str(env, "etag").unwrap().to_term(env),
-            etag.encode(env),
-        )
-        .unwrap()
-    } else {
-        map
-    };
-
-    let map = map
-        .map_put(
-            Atom::from_str(env, "version").unwrap().to_term(env),
-            meta.version.as_ref().map(|v| v.to_string()).encode(env),
-        )
-        .unwrap();
-
-    map
-}
-
 /// Helper function to encode ObjectMeta with Attributes to Elixir map
 fn encode_object_meta_with_attributes<'a>(
     env: Env<'a>,
@@ -472,7 +901,7 @@ fn encode_object_meta_with_attributes<'a>(
     let map = map
         .map_put(
             Atom::from_str(env, "size").unwrap().to_term(env),
-            meta.size.encode(env),
+            (meta.size as u64).encode(env),
         )
         .unwrap();
 
@@ -483,6 +912,8 @@ fn encode_object_meta_with_attributes<'a>(
         )
         .unwrap();
 
+    let map = put_last_modified_us(env, map, meta);
+
     let map = if let Some(ref etag) = meta.e_tag {
         map.map_put(
             Atom::from_str(env, "etag").unwrap().to_term(env),
@@ -557,39 +988,36 @@ fn encode_object_meta_with_attributes<'a>(
         map
     };
 
-    map
+    // Custom user metadata as a string map
+    let metadata: std::collections::HashMap<&str, &str> = attributes
+        .iter()
+        .filter_map(|(attribute, value)| match attribute {
+            Attribute::Metadata(key) => Some((key.as_ref(), value.as_ref())),
+            _ => None,
+        })
+        .collect();
+
+    map.map_put(
+        Atom::from_str(env, "metadata").unwrap().to_term(env),
+        metadata.encode(env),
+    )
+    .unwrap()
 }
 
-/// Upload an object to storage with attributes and optional tags
-///
-/// Supports setting HTTP headers and metadata:
-/// - content_type: MIME type
-/// - content_encoding: Encoding (e.g., "gzip")
-/// - content_disposition: Download behavior
-/// - cache_control: Cache directives
-/// - content_language: Language code
-/// - tags: Object tags (AWS/GCS only)
-#[rustler::nif(schedule = "DirtyCpu")]
-pub fn put_with_attributes<'a>(
-    env: Env<'a>,
-    store: ResourceArc<StoreWrapper>,
-    path: String,
-    data: Binary,
-    mode: PutModeNif,
-    attributes: AttributesNif,
-    _tags: Vec<(String, String)>,
-) -> NifResult<Term<'a>> {
-    // Convert PutModeNif to object_store::PutMode
-    let rust_mode = match mode {
+/// Convert an Elixir put mode to object_store's PutMode
+pub(crate) fn put_mode(mode: PutModeNif) -> PutMode {
+    match mode {
         PutModeNif::Overwrite => PutMode::Overwrite,
         PutModeNif::Create => PutMode::Create,
         PutModeNif::Update { etag, version } => PutMode::Update(ObjectStoreUpdateVersion {
             e_tag: etag,
             version,
         }),
-    };
+    }
+}
 
-    // Convert AttributesNif to object_store::Attributes using insert API
+/// Convert Elixir attributes to object_store Attributes
+pub(crate) fn put_attributes(attributes: AttributesNif) -> Attributes {
     let mut rust_attributes = Attributes::new();
 
     if let Some(content_type) = attributes.content_type {
@@ -612,17 +1040,52 @@ pub fn put_with_attributes<'a>(
         rust_attributes.insert(Attribute::ContentLanguage, content_language.into());
     }
 
-    // Build PutOptions with mode and attributes
+    for (key, value) in attributes.metadata {
+        rust_attributes.insert(Attribute::Metadata(key.into()), value.into());
+    }
+
+    rust_attributes
+}
+
+/// Build a TagSet from key/value pairs
+///
+/// Tags are applied by providers that support tagging on upload (S3, Azure).
+pub(crate) fn tag_set(tags: &[(String, String)]) -> TagSet {
+    let mut tag_set = TagSet::default();
+    for (key, value) in tags {
+        tag_set.push(key, value);
+    }
+    tag_set
+}
+
+/// Upload an object to storage with attributes and optional tags
+///
+/// Supports setting HTTP headers and metadata:
+/// - content_type: MIME type
+/// - content_encoding: Encoding (e.g., "gzip")
+/// - content_disposition: Download behavior
+/// - cache_control: Cache directives
+/// - content_language: Language code
+/// - metadata: Custom user metadata
+/// - tags: Object tags (S3/Azure)
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn put_with_attributes<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+    data: IoDataNif,
+    mode: PutModeNif,
+    attributes: AttributesNif,
+    tags: Vec<(String, String)>,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
     let opts = PutOptions {
-        mode: rust_mode,
-        attributes: rust_attributes,
-        ..Default::default()
+        mode: put_mode(mode),
+        attributes: put_attributes(attributes),
+        tags: tag_set(&tags),
     };
 
-    // Note: Tags are not easily constructible in object_store 0.11.2
-    // The API accepts them, but we'll skip setting them for now
-
-    let payload = PutPayload::from(data.as_slice().to_vec());
+    let payload = data.into_payload();
 
     // Perform the put operation
     match RUNTIME.block_on(async { store.inner.put_opts(&Path::from(path), payload, opts).await }) {
@@ -636,6 +1099,165 @@ pub fn put_with_attributes<'a>(
     }
 }
 
+/// Metadata key holding the idempotency token of a put
+pub(crate) const IDEMPOTENCY_KEY: &str = "objectstorex-idempotency-key";
+
+/// Whether a put error may hide a write that actually happened
+///
+/// A put retried after a lost response fails with `AlreadyExists` or
+/// `Precondition` in create and update modes; a put whose response never
+/// arrived fails with a generic (network) error.
+fn ambiguous_put_error(error: &object_store::Error) -> bool {
+    matches!(
+        error,
+        object_store::Error::AlreadyExists { .. }
+            | object_store::Error::Precondition { .. }
+            | object_store::Error::Generic { .. }
+    )
+}
+
+/// Result of the put that stored `token` at `path`, if it is the current object
+async fn stored_put(
+    store: &dyn object_store::ObjectStore,
+    path: &Path,
+    token: &str,
+) -> Option<PutResult> {
+    let options = GetOptions {
+        head: true,
+        ..Default::default()
+    };
+    let result = store.get_opts(path, options).await.ok()?;
+    let stored = result
+        .attributes
+        .get(&Attribute::Metadata(IDEMPOTENCY_KEY.into()))?;
+
+    (stored.as_ref() == token).then_some(PutResult {
+        e_tag: result.meta.e_tag,
+        version: result.meta.version,
+    })
+}
+
+/// Safeguards of a put that go beyond its attributes and tags
+///
+/// Matches Elixir map: %{checksum: algorithm, idempotency_token: token, object_lock: lock}
+#[derive(Debug, NifMap)]
+pub struct PutGuardsNif {
+    /// Algorithm of the checksum to compute and return
+    pub checksum: Option<Atom>,
+    /// Token stored in the object's metadata to recognise retried writes
+    pub idempotency_token: Option<String>,
+    /// S3 Object Lock retention and legal hold applied by the upload
+    pub object_lock: Option<ObjectLockNif>,
+}
+
+/// Upload an object with attributes and tags, plus any combination of a
+/// checksum, an idempotency token and an S3 Object Lock
+///
+/// With a checksum, the digest of the data is computed and returned as
+/// `{:ok, etag, version, checksum}`. SHA-256 checksums are also sent to
+/// providers that verify them on upload (S3, as `x-amz-checksum-sha256`).
+///
+/// With an idempotency token, the token is stored in the object's metadata.
+/// If the put fails ambiguously (a conflict in create/update mode, or a
+/// network error) and the current object carries the same token, an earlier
+/// attempt with this token succeeded, and its result is returned instead of
+/// the error. Requires a store that keeps user metadata.
+///
+/// With an Object Lock, the object is written and locked in one request, so
+/// it is never stored unprotected. Returns `:not_supported` for stores
+/// without Object Lock; the bucket must have Object Lock enabled.
+#[rustler::nif(schedule = "DirtyCpu")]
+#[allow(clippy::too_many_arguments)]
+pub fn put_with_options<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+    data: IoDataNif,
+    mode: PutModeNif,
+    attributes: AttributesNif,
+    tags: Vec<(String, String)>,
+    guards: PutGuardsNif,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let algorithm = guards
+        .checksum
+        .map(ChecksumAlgorithm::from_atom)
+        .transpose()?;
+    let checksum = algorithm.map(|algorithm| {
+        let mut hasher = checksum::Hasher::new(algorithm);
+        data.segments().for_each(|segment| hasher.update(segment));
+        hasher.finish()
+    });
+
+    let mut attributes = put_attributes(attributes);
+    if let Some(token) = &guards.idempotency_token {
+        attributes.insert(
+            Attribute::Metadata(IDEMPOTENCY_KEY.into()),
+            token.clone().into(),
+        );
+    }
+    let opts = PutOptions {
+        mode: put_mode(mode),
+        attributes,
+        tags: tag_set(&tags),
+    };
+
+    let path = Path::from(path);
+    let put = async {
+        match &guards.object_lock {
+            Some(lock) => {
+                let Some(s3) = store.s3.clone() else {
+                    return Ok(None);
+                };
+                let data = Bytes::from(data.segments().collect::<Vec<_>>().concat());
+                let mut headers = lock.headers();
+                if matches!(algorithm, Some(ChecksumAlgorithm::Sha256)) {
+                    let digest = ring::digest::digest(&ring::digest::SHA256, &data);
+                    headers.push(("x-amz-checksum-sha256", BASE64_STANDARD.encode(digest)));
+                }
+                s3.put(&path, data, &opts.mode, &opts.attributes, &tags, &headers)
+                    .await
+                    .map(Some)
+            }
+            None => {
+                let target = match algorithm {
+                    Some(algorithm) => store.checksum_store(algorithm),
+                    None => store.inner.clone(),
+                };
+                target
+                    .put_opts(&path, data.into_payload(), opts)
+                    .await
+                    .map(Some)
+            }
+        }
+    };
+
+    let result = RUNTIME.block_on(async {
+        match (put.await, &guards.idempotency_token) {
+            (Err(e), Some(token)) if ambiguous_put_error(&e) => {
+                stored_put(store.inner.as_ref(), &path, token)
+                    .await
+                    .map(Some)
+                    .ok_or(e)
+            }
+            (result, _) => result,
+        }
+    });
+
+    match result {
+        Ok(None) => Ok(atoms::not_supported().to_term(env)),
+        Ok(Some(put_result)) => {
+            let etag = put_result.e_tag.unwrap_or_default();
+            let version = put_result.version.unwrap_or_default();
+            match checksum {
+                Some(checksum) => Ok((atoms::ok(), etag, version, checksum).encode(env)),
+                None => Ok((atoms::ok(), etag, version).encode(env)),
+            }
+        }
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
+
 /// Copy an object only if the destination doesn't exist (atomic where supported)
 ///
 /// Provider support:
@@ -652,6 +1274,7 @@ pub fn copy_if_not_exists<'a>(
     from: String,
     to: String,
 ) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
     match RUNTIME.block_on(async {
         store
             .inner
@@ -680,6 +1303,7 @@ pub fn rename_if_not_exists<'a>(
     from: String,
     to: String,
 ) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
     let from_path = Path::from(from.clone());
     let to_path = Path::from(to);
 
@@ -0,0 +1,309 @@
+//! Listings with a caller-chosen page size
+//!
+//! object_store always requests the provider's default page size. These
+//! listings go through the provider APIs directly, so each request can ask for
+//! fewer or more keys (S3 `max-keys`, GCS `maxResults`).
+
+use crate::gcs_api::{self, GcsApi};
+use crate::s3_api::{self, S3Api};
+use crate::store::StoreWrapper;
+use crate::versions::list_prefix;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use object_store::path::Path;
+use object_store::{ListResult, ObjectMeta, Result};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use reqwest::Method;
+use rustler::NifResult;
+use serde::Deserialize;
+use std::sync::Arc;
+
+/// One page of a listing
+#[derive(Default)]
+pub struct ListPage {
+    pub objects: Vec<ObjectMeta>,
+    /// Common prefixes, for listings with a delimiter
+    pub common_prefixes: Vec<Path>,
+    /// Token requesting the next page, if there is one
+    pub next: Option<String>,
+}
+
+/// Providers whose listings can be paged with a chosen page size
+#[async_trait]
+pub trait PagedListing: Send + Sync {
+    /// List up to `page_size` entries under `prefix`, starting at `token`
+    ///
+    /// With `delimiter`, only objects directly under `prefix` are returned,
+    /// and deeper keys are grouped into common prefixes.
+    async fn list_page(
+        &self,
+        prefix: &Path,
+        delimiter: bool,
+        page_size: usize,
+        token: Option<String>,
+    ) -> Result<ListPage>;
+}
+
+/// Paged listing of `store` with `page_size`, if one was requested and the
+/// store supports it
+///
+/// Other stores list with their default page size; local and in-memory stores
+/// don't page at all.
+pub fn paged(
+    store: &StoreWrapper,
+    page_size: Option<usize>,
+) -> NifResult<Option<(Arc<dyn PagedListing>, usize)>> {
+    match page_size {
+        Some(0) => Err(rustler::Error::BadArg),
+        Some(page_size) => Ok(store.paged.clone().map(|listing| (listing, page_size))),
+        None => Ok(None),
+    }
+}
+
+/// Stream the objects under `prefix`, requesting `page_size` keys at a time
+pub fn list(
+    listing: Arc<dyn PagedListing>,
+    prefix: Option<Path>,
+    page_size: usize,
+) -> BoxStream<'static, Result<ObjectMeta>> {
+    let prefix = prefix.unwrap_or_default();
+
+    stream::try_unfold(Some(None), move |token: Option<Option<String>>| {
+        let listing = listing.clone();
+        let prefix = prefix.clone();
+        async move {
+            let Some(token) = token else {
+                return Ok::<_, object_store::Error>(None);
+            };
+            let page = listing.list_page(&prefix, false, page_size, token).await?;
+            Ok(Some((page.objects, page.next.map(Some))))
+        }
+    })
+    .map_ok(|objects| stream::iter(objects.into_iter().map(Ok)))
+    .try_flatten()
+    .boxed()
+}
+
+/// List the objects and common prefixes directly under `prefix`, requesting
+/// `page_size` entries at a time
+pub async fn list_with_delimiter(
+    listing: &dyn PagedListing,
+    prefix: Option<&Path>,
+    page_size: usize,
+) -> Result<ListResult> {
+    let prefix = prefix.cloned().unwrap_or_default();
+    let mut result = ListResult {
+        objects: Vec::new(),
+        common_prefixes: Vec::new(),
+    };
+    let mut token = None;
+
+    loop {
+        let page = listing.list_page(&prefix, true, page_size, token).await?;
+        result.objects.extend(page.objects);
+        result.common_prefixes.extend(page.common_prefixes);
+
+        match page.next {
+            Some(next) => token = Some(next),
+            None => return Ok(result),
+        }
+    }
+}
+
+fn parse_timestamp(timestamp: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .map(|t| t.with_timezone(&Utc))
+        .unwrap_or_default()
+}
+
+/// Location of a listed key, or of a common prefix without its trailing `/`
+fn parse_location(key: &str, store: fn(String) -> object_store::Error) -> Result<Path> {
+    Path::parse(key.trim_end_matches('/'))
+        .map_err(|e| store(format!("Invalid key {:?} in listing: {}", key, e)))
+}
+
+fn invalid_s3_page(e: impl std::fmt::Display) -> object_store::Error {
+    s3_api::generic(format!("Invalid list response: {}", e))
+}
+
+/// An S3 `Contents` entry, before its key is parsed
+#[derive(Default)]
+struct S3Object {
+    key: String,
+    last_modified: String,
+    size: u64,
+    e_tag: Option<String>,
+}
+
+/// Parse an S3 `ListBucketResult` document
+fn parse_s3_page(body: &[u8]) -> Result<ListPage> {
+    let mut reader = Reader::from_reader(body);
+    let mut buf = Vec::new();
+    let mut objects = Vec::new();
+    let mut prefixes = Vec::new();
+    let mut entry: Option<S3Object> = None;
+    let mut in_prefixes = false;
+    let mut truncated = false;
+    let mut next = None;
+    let mut element = Vec::new();
+
+    loop {
+        match reader.read_event_into(&mut buf).map_err(invalid_s3_page)? {
+            Event::Start(start) => {
+                element = start.name().as_ref().to_vec();
+                match element.as_slice() {
+                    b"Contents" => entry = Some(S3Object::default()),
+                    b"CommonPrefixes" => in_prefixes = true,
+                    _ => {}
+                }
+            }
+            Event::Text(text) => {
+                let text = text.unescape().map_err(invalid_s3_page)?.into_owned();
+                match (&mut entry, element.as_slice()) {
+                    (Some(object), b"Key") => object.key = text,
+                    (Some(object), b"LastModified") => object.last_modified = text,
+                    (Some(object), b"Size") => object.size = text.parse().unwrap_or(0),
+                    (Some(object), b"ETag") => object.e_tag = Some(text),
+                    (None, b"Prefix") if in_prefixes => prefixes.push(text),
+                    (None, b"IsTruncated") => truncated = text == "true",
+                    (None, b"NextContinuationToken") => next = Some(text),
+                    _ => {}
+                }
+            }
+            Event::End(end) => {
+                match end.name().as_ref() {
+                    b"Contents" => objects.extend(entry.take()),
+                    b"CommonPrefixes" => in_prefixes = false,
+                    _ => {}
+                }
+                element.clear();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    Ok(ListPage {
+        objects: objects
+            .into_iter()
+            .map(|object| {
+                Ok(ObjectMeta {
+                    location: parse_location(&object.key, s3_api::generic)?,
+                    last_modified: parse_timestamp(&object.last_modified),
+                    size: object.size as usize,
+                    e_tag: object.e_tag,
+                    version: None,
+                })
+            })
+            .collect::<Result<_>>()?,
+        common_prefixes: prefixes
+            .iter()
+            .map(|prefix| parse_location(prefix, s3_api::generic))
+            .collect::<Result<_>>()?,
+        next: next.filter(|_| truncated),
+    })
+}
+
+#[async_trait]
+impl PagedListing for S3Api {
+    async fn list_page(
+        &self,
+        prefix: &Path,
+        delimiter: bool,
+        page_size: usize,
+        token: Option<String>,
+    ) -> Result<ListPage> {
+        let mut query = format!(
+            "list-type=2&max-keys={}&prefix={}",
+            page_size,
+            s3_api::encode_query(&list_prefix(prefix))
+        );
+        if delimiter {
+            query.push_str("&delimiter=%2F");
+        }
+        if let Some(token) = token {
+            query.push_str(&format!(
+                "&continuation-token={}",
+                s3_api::encode_query(&token)
+            ));
+        }
+
+        let body = self
+            .send(Method::GET, &Path::default(), Some(&query), &[], None)
+            .await?;
+        parse_s3_page(&body)
+    }
+}
+
+/// One page of a GCS `objects.list` response
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GcsListPage {
+    #[serde(default)]
+    items: Vec<GcsObject>,
+    #[serde(default)]
+    prefixes: Vec<String>,
+    next_page_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GcsObject {
+    name: String,
+    size: String,
+    updated: String,
+    etag: Option<String>,
+}
+
+#[async_trait]
+impl PagedListing for GcsApi {
+    async fn list_page(
+        &self,
+        prefix: &Path,
+        delimiter: bool,
+        page_size: usize,
+        token: Option<String>,
+    ) -> Result<ListPage> {
+        let prefix = list_prefix(prefix);
+        let page_size = page_size.to_string();
+        let mut query = vec![
+            ("prefix", prefix.as_str()),
+            ("maxResults", page_size.as_str()),
+        ];
+        if delimiter {
+            query.push(("delimiter", "/"));
+        }
+        if let Some(token) = &token {
+            query.push(("pageToken", token.as_str()));
+        }
+
+        let body = self.send(Method::GET, &Path::default(), &query).await?;
+        let page: GcsListPage = serde_json::from_slice(&body)
+            .map_err(|e| gcs_api::generic(format!("Invalid list response: {}", e)))?;
+
+        Ok(ListPage {
+            objects: page
+                .items
+                .into_iter()
+                .map(|object| {
+                    Ok(ObjectMeta {
+                        location: parse_location(&object.name, gcs_api::generic)?,
+                        last_modified: parse_timestamp(&object.updated),
+                        size: object.size.parse().unwrap_or(0),
+                        e_tag: object.etag,
+                        version: None,
+                    })
+                })
+                .collect::<Result<_>>()?,
+            common_prefixes: page
+                .prefixes
+                .iter()
+                .map(|prefix| parse_location(prefix, gcs_api::generic))
+                .collect::<Result<_>>()?,
+            next: page.next_page_token,
+        })
+    }
+}
@@ -0,0 +1,303 @@
+//! Patching a byte region of an object without re-sending the whole object
+
+use crate::atoms;
+use crate::errors::map_error;
+use crate::s3_api::S3Api;
+use crate::shutdown;
+use crate::store::StoreWrapper;
+use crate::types::to_usize;
+use crate::RUNTIME;
+use bytes::Bytes;
+use object_store::multipart::MultipartStore;
+use object_store::path::Path;
+use object_store::{
+    Attributes, GetOptions, ObjectStore, PutMultipartOpts, PutResult, Result, WriteMultipart,
+};
+use rustler::{Binary, Encoder, Env, NifResult, ResourceArc, Term};
+use std::ops::Range;
+
+/// Minimum size of every part but the last in a multipart upload
+const MIN_PART_SIZE: usize = 5 * 1024 * 1024;
+
+/// Maximum size of a part copied with UploadPartCopy
+const MAX_COPY_PART_SIZE: usize = 5 * 1024 * 1024 * 1024;
+
+/// Size of the pieces read and written when rewriting an object
+const CHUNK_SIZE: usize = 8 * 1024 * 1024;
+
+/// Maximum number of parts uploading concurrently during a rewrite
+const MAX_CONCURRENCY: usize = 8;
+
+/// The object being patched, as seen by a head request
+pub(crate) struct Original {
+    pub size: usize,
+    pub e_tag: Option<String>,
+    pub attributes: Attributes,
+}
+
+impl Original {
+    pub async fn head(
+        store: &dyn ObjectStore,
+        path: &Path,
+        if_match: Option<String>,
+    ) -> Result<Self> {
+        let options = GetOptions {
+            if_match,
+            head: true,
+            ..Default::default()
+        };
+        let result = store.get_opts(path, options).await?;
+
+        Ok(Self {
+            size: result.meta.size,
+            e_tag: result.meta.e_tag,
+            attributes: result.attributes,
+        })
+    }
+
+    /// Read a range of the object, failing if it changed since the head request
+    pub async fn read(
+        &self,
+        store: &dyn ObjectStore,
+        path: &Path,
+        range: Range<usize>,
+    ) -> Result<Bytes> {
+        if range.is_empty() {
+            return Ok(Bytes::new());
+        }
+
+        let options = GetOptions {
+            if_match: self.e_tag.clone(),
+            range: Some(range.into()),
+            ..Default::default()
+        };
+        store.get_opts(path, options).await?.bytes().await
+    }
+}
+
+/// Rewrite an object through a multipart upload with a region replaced
+///
+/// Works on every store, but streams the whole object through the client.
+/// Bytes between the end of the object and `offset` are filled with zeros.
+async fn rewrite(
+    store: &dyn ObjectStore,
+    path: &Path,
+    offset: usize,
+    data: Bytes,
+    if_match: Option<String>,
+) -> Result<PutResult> {
+    let original = Original::head(store, path, if_match).await?;
+    let options = PutMultipartOpts {
+        attributes: original.attributes.clone(),
+        ..Default::default()
+    };
+    let upload = store.put_multipart_opts(path, options).await?;
+    let mut writer = WriteMultipart::new_with_chunk_size(upload, CHUNK_SIZE);
+
+    let patch_end = offset + data.len();
+    let result = async {
+        copy_through(
+            store,
+            path,
+            &original,
+            0..offset.min(original.size),
+            &mut writer,
+        )
+        .await?;
+        for start in (original.size..offset).step_by(CHUNK_SIZE) {
+            writer.put(Bytes::from(vec![0; CHUNK_SIZE.min(offset - start)]));
+        }
+        writer.put(data);
+        copy_through(
+            store,
+            path,
+            &original,
+            patch_end..original.size,
+            &mut writer,
+        )
+        .await
+    }
+    .await;
+
+    match result {
+        Ok(()) => writer.finish().await,
+        Err(e) => {
+            let _ = writer.abort().await;
+            Err(e)
+        }
+    }
+}
+
+/// Read a range of the original object into a multipart writer
+async fn copy_through(
+    store: &dyn ObjectStore,
+    path: &Path,
+    original: &Original,
+    range: Range<usize>,
+    writer: &mut WriteMultipart,
+) -> Result<()> {
+    for start in range.clone().step_by(CHUNK_SIZE) {
+        let end = (start + CHUNK_SIZE).min(range.end);
+        writer.wait_for_capacity(MAX_CONCURRENCY).await?;
+        writer.put(original.read(store, path, start..end).await?);
+    }
+    Ok(())
+}
+
+/// A part of a patched S3 object
+enum PatchPart {
+    /// Copied server-side from this range of the original object
+    Copy(Range<usize>),
+    /// Uploaded from the client: the patch plus neighbouring original bytes
+    Upload(Range<usize>),
+}
+
+/// Split a patched object into server-side copies and one uploaded part
+///
+/// The uploaded part covers the patch and is widened with original bytes
+/// until every part but the last meets the minimum part size.
+fn plan_parts(size: usize, offset: usize, len: usize) -> Vec<PatchPart> {
+    let new_size = size.max(offset + len);
+    let kept = offset.min(size);
+    let start = if kept >= MIN_PART_SIZE { kept } else { 0 };
+
+    let mut end = (offset + len).max(start + MIN_PART_SIZE).min(new_size);
+    if new_size - end < MIN_PART_SIZE {
+        end = new_size;
+    }
+
+    let mut parts = copy_parts(0..start);
+    parts.push(PatchPart::Upload(start..end));
+    parts.extend(copy_parts(end..new_size));
+    parts
+}
+
+/// Split a range into evenly sized copy parts within the copy size limit
+fn copy_parts(range: Range<usize>) -> Vec<PatchPart> {
+    if range.is_empty() {
+        return Vec::new();
+    }
+
+    let count = range.len().div_ceil(MAX_COPY_PART_SIZE);
+    let part_size = range.len().div_ceil(count);
+    range
+        .clone()
+        .step_by(part_size)
+        .map(|start| PatchPart::Copy(start..(start + part_size).min(range.end)))
+        .collect()
+}
+
+impl S3Api {
+    /// Patch an object, copying its unchanged regions server-side
+    ///
+    /// Only the patch and up to one minimum part size of original bytes
+    /// around it pass through the client.
+    async fn patch(
+        &self,
+        path: &Path,
+        offset: usize,
+        data: Bytes,
+        if_match: Option<String>,
+    ) -> Result<PutResult> {
+        let store = self.store.as_ref();
+        let original = Original::head(store, path, if_match).await?;
+        let upload_id = self.create_upload(path, &original.attributes).await?;
+
+        let mut parts = Vec::new();
+        for (index, part) in plan_parts(original.size, offset, data.len())
+            .into_iter()
+            .enumerate()
+        {
+            let result = match part {
+                PatchPart::Copy(range) => {
+                    let e_tag = original.e_tag.as_deref();
+                    self.copy_part(path, &upload_id, index, path, e_tag, range)
+                        .await
+                }
+                PatchPart::Upload(range) => {
+                    let body = self
+                        .patched_region(path, &original, range, offset, &data)
+                        .await;
+                    match body {
+                        Ok(body) => store.put_part(path, &upload_id, index, body.into()).await,
+                        Err(e) => Err(e),
+                    }
+                }
+            };
+
+            match result {
+                Ok(part) => parts.push(part),
+                Err(e) => {
+                    let _ = store.abort_multipart(path, &upload_id).await;
+                    return Err(e);
+                }
+            }
+        }
+
+        store.complete_multipart(path, &upload_id, parts).await
+    }
+
+    /// Build the uploaded part: original bytes around the patch and the patch
+    async fn patched_region(
+        &self,
+        path: &Path,
+        original: &Original,
+        range: Range<usize>,
+        offset: usize,
+        data: &Bytes,
+    ) -> Result<Bytes> {
+        let store = self.store.as_ref();
+        let patch_end = offset + data.len();
+        let mut region = Vec::with_capacity(range.len());
+
+        let before = range.start..offset.min(original.size);
+        region.extend_from_slice(&original.read(store, path, before).await?);
+        region.resize(region.len() + offset.saturating_sub(original.size), 0);
+        region.extend_from_slice(data);
+        if patch_end < range.end {
+            region.extend_from_slice(&original.read(store, path, patch_end..range.end).await?);
+        }
+
+        Ok(region.into())
+    }
+}
+
+/// Replace the bytes of an object starting at `offset`
+///
+/// The object grows if the patch ends past its end; a gap between the end of
+/// the object and `offset` is filled with zeros. S3 stores copy the unchanged
+/// regions server-side, other stores rewrite the object. Attributes are kept,
+/// tags are not. Fails with `:precondition_failed` if `if_match` doesn't
+/// match or the object changes while it is being patched.
+///
+/// Returns `{:ok, etag, version}`; missing identifiers are empty strings.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn patch<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+    offset: u64,
+    data: Binary,
+    if_match: Option<String>,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let path = Path::from(path);
+    let offset = to_usize(offset)?;
+    let data = Bytes::copy_from_slice(data.as_slice());
+
+    let result = RUNTIME.block_on(async {
+        match &store.s3 {
+            Some(s3) => s3.patch(&path, offset, data, if_match).await,
+            None => rewrite(store.inner.as_ref(), &path, offset, data, if_match).await,
+        }
+    });
+
+    match result {
+        Ok(put_result) => {
+            let etag = put_result.e_tag.unwrap_or_default();
+            let version = put_result.version.unwrap_or_default();
+            Ok((atoms::ok(), etag, version).encode(env))
+        }
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
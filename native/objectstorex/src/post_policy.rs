@@ -0,0 +1,147 @@
+//! Pre-signed S3 POST policies, letting browsers upload with HTML forms
+
+use crate::atoms;
+use crate::errors::map_error;
+use crate::s3_api::S3Api;
+use crate::shutdown;
+use crate::split::hex;
+use crate::store::StoreWrapper;
+use crate::RUNTIME;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use chrono::{Duration, Utc};
+use object_store::Result;
+use ring::hmac;
+use rustler::{Encoder, Env, NifMap, NifResult, ResourceArc, Term};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+
+const ALGORITHM: &str = "AWS4-HMAC-SHA256";
+
+/// Conditions of a POST policy, as passed from Elixir
+///
+/// The key must equal `key`, or only start with `key_prefix` when given. The
+/// content type must equal `content_type` or start with `content_type_prefix`,
+/// and the size must lie within `content_length_range` (inclusive bytes).
+#[derive(Debug, NifMap)]
+pub struct PostPolicyNif {
+    pub expires_in: u64,
+    pub key_prefix: Option<String>,
+    pub content_type: Option<String>,
+    pub content_type_prefix: Option<String>,
+    pub content_length_range: Option<(u64, u64)>,
+}
+
+/// Form to upload an object with, as returned to Elixir
+///
+/// The browser POSTs `fields` to `url` as `multipart/form-data`, followed by
+/// the file in a `file` field.
+#[derive(Debug, NifMap)]
+pub struct PostFormNif {
+    pub url: String,
+    pub fields: HashMap<String, String>,
+}
+
+fn hmac_sha256(key: &[u8], data: &str) -> hmac::Tag {
+    hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, key), data.as_bytes())
+}
+
+impl S3Api {
+    /// Sign a POST policy for uploads of `key` with the store's credentials
+    ///
+    /// The signature covers every condition, so S3 rejects forms whose
+    /// fields or file break them. Temporary credentials add their session
+    /// token to the form.
+    pub async fn presign_post(&self, key: &str, policy: &PostPolicyNif) -> Result<PostFormNif> {
+        let credential = self.store.credentials().get_credential().await?;
+        let now = Utc::now();
+        let date = now.format("%Y%m%d").to_string();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let expiration = now + Duration::seconds(i64::try_from(policy.expires_in).unwrap_or(0));
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+
+        let mut fields = HashMap::from([
+            ("key".to_string(), key.to_string()),
+            ("x-amz-algorithm".to_string(), ALGORITHM.to_string()),
+            (
+                "x-amz-credential".to_string(),
+                format!("{}/{}", credential.key_id, scope),
+            ),
+            ("x-amz-date".to_string(), amz_date),
+        ]);
+        if let Some(token) = &credential.token {
+            fields.insert("x-amz-security-token".to_string(), token.clone());
+        }
+        if let Some(content_type) = &policy.content_type {
+            fields.insert("Content-Type".to_string(), content_type.clone());
+        }
+
+        let mut conditions: Vec<Value> = vec![json!({ "bucket": self.bucket })];
+        match &policy.key_prefix {
+            Some(prefix) => conditions.push(json!(["starts-with", "$key", prefix])),
+            None => conditions.push(json!({ "key": key })),
+        }
+        if let Some(prefix) = &policy.content_type_prefix {
+            conditions.push(json!(["starts-with", "$Content-Type", prefix]));
+        }
+        if let Some((min, max)) = policy.content_length_range {
+            conditions.push(json!(["content-length-range", min, max]));
+        }
+        // The key and its prefix are covered above; every other field is
+        // required as is
+        for (name, value) in &fields {
+            if name != "key" {
+                conditions.push(json!({ name: value }));
+            }
+        }
+
+        let document = json!({
+            "expiration": expiration.format("%Y-%m-%dT%H:%M:%S%.3fZ").to_string(),
+            "conditions": conditions,
+        });
+        let encoded = BASE64_STANDARD.encode(document.to_string());
+
+        let mut signing_key =
+            hmac_sha256(format!("AWS4{}", credential.secret_key).as_bytes(), &date);
+        for part in [self.region.as_str(), "s3", "aws4_request"] {
+            signing_key = hmac_sha256(signing_key.as_ref(), part);
+        }
+        let signature = hex(hmac_sha256(signing_key.as_ref(), &encoded).as_ref());
+
+        fields.insert("policy".to_string(), encoded);
+        fields.insert("x-amz-signature".to_string(), signature);
+
+        Ok(PostFormNif {
+            url: self.bucket_endpoint.clone(),
+            fields,
+        })
+    }
+}
+
+/// Generate a pre-signed POST form for browser uploads of `key`
+///
+/// Returns `{:ok, %{url:, fields:}}`, or `:not_supported` for stores
+/// without POST policies. Nothing is sent to S3: the policy is signed
+/// locally.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn presign_post<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    key: String,
+    policy: PostPolicyNif,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    // A form for a key outside the prefix would be refused by S3 anyway
+    if let Some(prefix) = &policy.key_prefix {
+        if !key.starts_with(prefix.as_str()) {
+            return Err(rustler::Error::BadArg);
+        }
+    }
+    let Some(s3) = store.s3.clone() else {
+        return Ok(atoms::not_supported().to_term(env));
+    };
+
+    match RUNTIME.block_on(s3.presign_post(&key, &policy)) {
+        Ok(form) => Ok((atoms::ok(), form).encode(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
@@ -0,0 +1,61 @@
+use crate::atoms;
+use crate::store::StoreWrapper;
+use rustler::{Encoder, Env, NifResult, ResourceArc, Term};
+
+/// Register a credential profile on a store
+///
+/// The profile is another store (typically the same bucket built with different
+/// credentials) that operations can select by name. Registering an existing
+/// name replaces the previous profile.
+#[rustler::nif]
+pub fn register_profile<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    name: String,
+    profile: ResourceArc<StoreWrapper>,
+) -> NifResult<Term<'a>> {
+    store
+        .profiles
+        .write()
+        .unwrap()
+        .insert(name, profile.detached());
+
+    Ok(atoms::ok().to_term(env))
+}
+
+/// Remove a credential profile from a store
+#[rustler::nif]
+pub fn remove_profile<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    name: String,
+) -> NifResult<Term<'a>> {
+    match store.profiles.write().unwrap().remove(&name) {
+        Some(_) => Ok(atoms::ok().to_term(env)),
+        None => Ok(atoms::not_found().to_term(env)),
+    }
+}
+
+/// List the names of all profiles registered on a store
+#[rustler::nif]
+pub fn list_profiles(store: ResourceArc<StoreWrapper>) -> Vec<String> {
+    let mut names: Vec<String> = store.profiles.read().unwrap().keys().cloned().collect();
+    names.sort();
+    names
+}
+
+/// Return a store handle that performs operations with the named profile
+///
+/// The returned handle shares the underlying client with the profile, so it
+/// is cheap to create per operation.
+#[rustler::nif]
+pub fn select_profile<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    name: String,
+) -> NifResult<Term<'a>> {
+    match store.profile(&name) {
+        Some(profile) => Ok(ResourceArc::new(profile).encode(env)),
+        None => Ok(atoms::not_found().to_term(env)),
+    }
+}
@@ -0,0 +1,220 @@
+//! Streaming puts with the options of single-shot puts
+
+use crate::atoms;
+use crate::errors::map_error;
+use crate::operations::{put_attributes, put_mode, tag_set};
+use crate::shutdown::{self, UploadRegistration};
+use crate::store::StoreWrapper;
+use crate::types::{AttributesNif, PutModeNif};
+use crate::RUNTIME;
+use bytes::Bytes;
+use futures::FutureExt;
+use object_store::path::Path;
+use object_store::{
+    DynObjectStore, PutMode, PutMultipartOpts, PutOptions, PutPayload, PutResult, WriteMultipart,
+};
+use rustler::{Binary, Encoder, Env, NifResult, ResourceArc, Term};
+use std::sync::Arc;
+use tokio::sync::Mutex as TokioMutex;
+
+/// Data buffered before a put stream switches to a multipart upload
+const PART_SIZE: usize = 5 * 1024 * 1024;
+
+/// Maximum number of parts uploading concurrently
+const MAX_CONCURRENCY: usize = 8;
+
+/// Writable stream that finishes as a put with mode, attributes and tags
+///
+/// Data is buffered until it exceeds one part, so small objects are written
+/// with a single put. Larger overwrites switch to a multipart upload that
+/// carries the attributes and tags. Conditional modes (create, update) can't
+/// be applied to multipart uploads, so those streams buffer all data and
+/// finish with a single conditional put.
+pub struct PutStreamWrapper {
+    state: Arc<TokioMutex<PutStreamState>>,
+    /// Aborts a multipart upload the stream switched to on shutdown
+    _registration: UploadRegistration,
+}
+
+enum PutStreamState {
+    Buffering {
+        store: Arc<DynObjectStore>,
+        path: Path,
+        opts: PutOptions,
+        chunks: Vec<Bytes>,
+        size: usize,
+    },
+    Uploading(WriteMultipart),
+    Closed,
+}
+
+impl PutStreamState {
+    async fn write(&mut self, data: Bytes) -> object_store::Result<()> {
+        match self {
+            PutStreamState::Buffering {
+                store,
+                path,
+                opts,
+                chunks,
+                size,
+            } => {
+                *size += data.len();
+                chunks.push(data);
+
+                if *size <= PART_SIZE || !matches!(opts.mode, PutMode::Overwrite) {
+                    return Ok(());
+                }
+
+                let multipart_opts = PutMultipartOpts {
+                    tags: std::mem::take(&mut opts.tags),
+                    attributes: std::mem::take(&mut opts.attributes),
+                };
+                let upload = store.put_multipart_opts(path, multipart_opts).await?;
+                let mut writer = WriteMultipart::new_with_chunk_size(upload, PART_SIZE);
+                for chunk in chunks.drain(..) {
+                    writer.put(chunk);
+                }
+                *self = PutStreamState::Uploading(writer);
+                Ok(())
+            }
+            PutStreamState::Uploading(writer) => {
+                writer.wait_for_capacity(MAX_CONCURRENCY).await?;
+                writer.put(data);
+                Ok(())
+            }
+            PutStreamState::Closed => Err(closed()),
+        }
+    }
+
+    async fn finish(&mut self) -> object_store::Result<PutResult> {
+        match std::mem::replace(self, PutStreamState::Closed) {
+            PutStreamState::Buffering {
+                store,
+                path,
+                opts,
+                chunks,
+                ..
+            } => {
+                let payload: PutPayload = chunks.into_iter().collect();
+                store.put_opts(&path, payload, opts).await
+            }
+            PutStreamState::Uploading(writer) => writer.finish().await,
+            PutStreamState::Closed => Err(closed()),
+        }
+    }
+
+    async fn abort(&mut self) -> object_store::Result<()> {
+        match std::mem::replace(self, PutStreamState::Closed) {
+            PutStreamState::Uploading(writer) => writer.abort().await,
+            _ => Ok(()),
+        }
+    }
+}
+
+fn closed() -> object_store::Error {
+    object_store::Error::Generic {
+        store: "PutStream",
+        source: "Put stream is already finished or aborted".into(),
+    }
+}
+
+/// Start a put stream with the given mode, attributes and tags
+#[rustler::nif]
+pub fn start_put_stream<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+    mode: PutModeNif,
+    attributes: AttributesNif,
+    tags: Vec<(String, String)>,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let state = PutStreamState::Buffering {
+        store: store.inner.clone(),
+        path: Path::from(path),
+        opts: PutOptions {
+            mode: put_mode(mode),
+            attributes: put_attributes(attributes),
+            tags: tag_set(&tags),
+        },
+        chunks: Vec::new(),
+        size: 0,
+    };
+
+    let state = Arc::new(TokioMutex::new(state));
+    let upload = Arc::downgrade(&state);
+    let registration = shutdown::register_upload(false, move || {
+        let state = upload.upgrade()?;
+        Some(
+            async move {
+                let _ = state.lock().await.abort().await;
+            }
+            .boxed(),
+        )
+    });
+
+    let resource = ResourceArc::new(PutStreamWrapper {
+        state,
+        _registration: registration,
+    });
+    Ok((atoms::ok(), resource).encode(env))
+}
+
+/// Write a chunk to a put stream
+///
+/// A failed write aborts the stream.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn put_stream_write<'a>(
+    env: Env<'a>,
+    stream: ResourceArc<PutStreamWrapper>,
+    data: Binary,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let data = Bytes::copy_from_slice(data.as_slice());
+
+    let result = RUNTIME.block_on(async {
+        let mut state = stream.state.lock().await;
+        let result = state.write(data).await;
+        if result.is_err() {
+            let _ = state.abort().await;
+        }
+        result
+    });
+
+    match result {
+        Ok(()) => Ok(atoms::ok().to_term(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
+
+/// Finish a put stream, applying its mode, attributes and tags
+///
+/// Returns `{:ok, etag, version}`; missing identifiers are empty strings.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn put_stream_finish<'a>(
+    env: Env<'a>,
+    stream: ResourceArc<PutStreamWrapper>,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    match RUNTIME.block_on(async { stream.state.lock().await.finish().await }) {
+        Ok(put_result) => {
+            let etag = put_result.e_tag.unwrap_or_default();
+            let version = put_result.version.unwrap_or_default();
+            Ok((atoms::ok(), etag, version).encode(env))
+        }
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
+
+/// Abort a put stream, discarding written data
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn put_stream_abort<'a>(
+    env: Env<'a>,
+    stream: ResourceArc<PutStreamWrapper>,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    match RUNTIME.block_on(async { stream.state.lock().await.abort().await }) {
+        Ok(()) => Ok(atoms::ok().to_term(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
@@ -0,0 +1,377 @@
+//! Reports computed natively during a single listing pass
+
+use crate::atoms;
+use crate::errors::map_error;
+use crate::shutdown;
+use crate::store::StoreWrapper;
+use crate::streaming::encode_object_meta;
+use crate::RUNTIME;
+use bytes::Bytes;
+use futures::{StreamExt, TryStreamExt};
+use object_store::path::Path;
+use object_store::ObjectMeta;
+use rand::Rng;
+use rustler::types::map;
+use rustler::{Atom, Encoder, Env, NifResult, OwnedBinary, ResourceArc, Term};
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BTreeMap, BinaryHeap};
+
+/// How objects are grouped by `aggregate`
+enum GroupBy {
+    Directory,
+    Extension,
+    Date(&'static str),
+}
+
+impl GroupBy {
+    fn decode(group_by: Atom, bucket: Atom) -> NifResult<Self> {
+        if group_by == atoms::directory() {
+            Ok(GroupBy::Directory)
+        } else if group_by == atoms::extension() {
+            Ok(GroupBy::Extension)
+        } else if group_by == atoms::date() {
+            let format = if bucket == atoms::day() {
+                "%Y-%m-%d"
+            } else if bucket == atoms::month() {
+                "%Y-%m"
+            } else if bucket == atoms::year() {
+                "%Y"
+            } else {
+                return Err(rustler::Error::BadArg);
+            };
+            Ok(GroupBy::Date(format))
+        } else {
+            Err(rustler::Error::BadArg)
+        }
+    }
+
+    /// Group key of an object listed under `prefix`
+    fn key(&self, prefix: Option<&Path>, meta: &ObjectMeta) -> String {
+        match self {
+            GroupBy::Directory => {
+                let mut parts = match prefix {
+                    Some(prefix) => match meta.location.prefix_match(prefix) {
+                        Some(parts) => parts.collect::<Vec<_>>(),
+                        None => meta.location.parts().collect(),
+                    },
+                    None => meta.location.parts().collect(),
+                };
+                // Objects directly under the prefix have no directory
+                if parts.len() > 1 {
+                    parts.swap_remove(0).as_ref().to_string()
+                } else {
+                    String::new()
+                }
+            }
+            GroupBy::Extension => meta
+                .location
+                .extension()
+                .map(|ext| ext.to_string())
+                .unwrap_or_default(),
+            GroupBy::Date(format) => meta.last_modified.format(format).to_string(),
+        }
+    }
+}
+
+/// Count objects and bytes under a prefix, grouped by directory, extension
+/// or modification date
+///
+/// Returns a map of group key to `%{count: n, bytes: b}`. Directory groups are
+/// the first path segment below the prefix (`""` for objects directly under
+/// it); extension groups are `""` for objects without an extension.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn aggregate<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    prefix: Option<String>,
+    group_by: Atom,
+    bucket: Atom,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let group_by = GroupBy::decode(group_by, bucket)?;
+    let prefix_path = prefix.map(Path::from);
+
+    let result = RUNTIME.block_on(async {
+        let mut groups: BTreeMap<String, (u64, u64)> = BTreeMap::new();
+        let mut stream = store.inner.list(prefix_path.as_ref());
+
+        while let Some(meta) = stream.next().await {
+            let meta = meta?;
+            let entry = groups
+                .entry(group_by.key(prefix_path.as_ref(), &meta))
+                .or_default();
+            entry.0 += 1;
+            entry.1 += meta.size as u64;
+        }
+
+        Ok::<_, object_store::Error>(groups)
+    });
+
+    match result {
+        Ok(groups) => {
+            let mut summary = map::map_new(env);
+            for (key, (count, bytes)) in groups {
+                let group = map::map_new(env)
+                    .map_put(atoms::count().encode(env), count.encode(env))?
+                    .map_put(atoms::bytes().encode(env), bytes.encode(env))?;
+                summary = summary.map_put(key.encode(env), group)?;
+            }
+            Ok(summary)
+        }
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
+
+/// Object ranked by a sort key, ties broken by location
+struct Ranked {
+    key: i64,
+    meta: ObjectMeta,
+}
+
+impl Ord for Ranked {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key
+            .cmp(&other.key)
+            .then_with(|| self.meta.location.cmp(&other.meta.location))
+    }
+}
+
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Ranked {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Ranked {}
+
+/// Find the `n` largest or most recently modified objects under a prefix
+///
+/// Only `n` entries are kept in memory while listing, so this works on prefixes
+/// far larger than the returned report. Results are ordered largest or newest
+/// first.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn top_n<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    prefix: Option<String>,
+    by: Atom,
+    n: usize,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let key: fn(&ObjectMeta) -> i64 = if by == atoms::size() {
+        |meta| meta.size as i64
+    } else if by == atoms::last_modified() {
+        |meta| meta.last_modified.timestamp_micros()
+    } else {
+        return Err(rustler::Error::BadArg);
+    };
+    let prefix_path = prefix.map(Path::from);
+
+    let result = RUNTIME.block_on(async {
+        // Min-heap holding the current top `n`
+        let mut heap: BinaryHeap<Reverse<Ranked>> = BinaryHeap::with_capacity(n + 1);
+        let mut stream = store.inner.list(prefix_path.as_ref());
+
+        while let Some(meta) = stream.next().await {
+            let meta = meta?;
+            if n == 0 {
+                continue;
+            }
+            heap.push(Reverse(Ranked {
+                key: key(&meta),
+                meta,
+            }));
+            if heap.len() > n {
+                heap.pop();
+            }
+        }
+
+        Ok::<_, object_store::Error>(heap.into_sorted_vec())
+    });
+
+    match result {
+        Ok(ranked) => Ok(ranked
+            .iter()
+            .map(|Reverse(ranked)| encode_object_meta(env, &ranked.meta))
+            .collect::<Vec<_>>()
+            .encode(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
+
+/// Summarize a prefix: object count, total bytes, and its largest and most
+/// recently modified objects
+///
+/// Returns `%{count: n, bytes: b, largest: meta, newest: meta}`, with `nil`
+/// objects for an empty prefix. Ties go to the first object listed.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn stat_prefix<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    prefix: Option<String>,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let prefix_path = prefix.map(Path::from);
+
+    let result = RUNTIME.block_on(async {
+        let mut count = 0u64;
+        let mut bytes = 0u64;
+        let mut largest: Option<ObjectMeta> = None;
+        let mut newest: Option<ObjectMeta> = None;
+        let mut stream = store.inner.list(prefix_path.as_ref());
+
+        while let Some(meta) = stream.next().await {
+            let meta = meta?;
+            count += 1;
+            bytes += meta.size as u64;
+            if largest.as_ref().is_none_or(|l| meta.size > l.size) {
+                largest = Some(meta.clone());
+            }
+            if newest
+                .as_ref()
+                .is_none_or(|n| meta.last_modified > n.last_modified)
+            {
+                newest = Some(meta);
+            }
+        }
+
+        Ok::<_, object_store::Error>((count, bytes, largest, newest))
+    });
+
+    match result {
+        Ok((count, bytes, largest, newest)) => {
+            let encode = |meta: Option<ObjectMeta>| match meta {
+                Some(meta) => encode_object_meta(env, &meta),
+                None => rustler::types::atom::nil().encode(env),
+            };
+
+            Ok(map::map_new(env)
+                .map_put(atoms::count().encode(env), count.encode(env))?
+                .map_put(atoms::bytes().encode(env), bytes.encode(env))?
+                .map_put(atoms::largest().encode(env), encode(largest))?
+                .map_put(atoms::newest().encode(env), encode(newest))?)
+        }
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
+
+/// List the objects under a prefix modified at or after a Unix timestamp
+/// (seconds)
+///
+/// Only matching objects are kept and encoded. Results are ordered oldest
+/// first, ties broken by location, so the last entry's `last_modified` can be
+/// the checkpoint of the next call.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn list_modified_since<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    prefix: Option<String>,
+    since: i64,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let prefix_path = prefix.map(Path::from);
+
+    let result = RUNTIME.block_on(
+        store
+            .inner
+            .list(prefix_path.as_ref())
+            .try_filter(|meta| futures::future::ready(meta.last_modified.timestamp() >= since))
+            .try_collect::<Vec<_>>(),
+    );
+
+    match result {
+        Ok(mut objects) => {
+            objects.sort_by(|a, b| {
+                a.last_modified
+                    .cmp(&b.last_modified)
+                    .then_with(|| a.location.cmp(&b.location))
+            });
+            Ok(objects
+                .iter()
+                .map(|meta| encode_object_meta(env, meta))
+                .collect::<Vec<_>>()
+                .encode(env))
+        }
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
+
+/// Number of sampled objects read concurrently
+const SAMPLE_CONCURRENCY: usize = 8;
+
+/// Read the first bytes of a random sample of objects under a prefix
+///
+/// Objects are picked with reservoir sampling during a single listing pass, so
+/// every object has the same chance of being chosen regardless of prefix size.
+/// Returns `[{meta, data}]` ordered by location; objects deleted between
+/// listing and reading are skipped.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn sample<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    prefix: Option<String>,
+    n: usize,
+    bytes_per_object: usize,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let prefix_path = prefix.map(Path::from);
+
+    let result = RUNTIME.block_on(async {
+        let mut rng = rand::thread_rng();
+        let mut reservoir: Vec<ObjectMeta> = Vec::with_capacity(n);
+        let mut seen = 0usize;
+        let mut stream = store.inner.list(prefix_path.as_ref());
+
+        while let Some(meta) = stream.next().await {
+            let meta = meta?;
+            seen += 1;
+            if reservoir.len() < n {
+                reservoir.push(meta);
+            } else {
+                let slot = rng.gen_range(0..seen);
+                if slot < n {
+                    reservoir[slot] = meta;
+                }
+            }
+        }
+        reservoir.sort_by(|a, b| a.location.cmp(&b.location));
+
+        let inner = &store.inner;
+        let samples: Vec<Option<(ObjectMeta, Bytes)>> = futures::stream::iter(reservoir)
+            .map(|meta| async move {
+                let len = bytes_per_object.min(meta.size);
+                if len == 0 {
+                    return Ok(Some((meta, Bytes::new())));
+                }
+                match inner.get_range(&meta.location, 0..len).await {
+                    Ok(data) => Ok(Some((meta, data))),
+                    Err(object_store::Error::NotFound { .. }) => Ok(None),
+                    Err(e) => Err(e),
+                }
+            })
+            .buffered(SAMPLE_CONCURRENCY)
+            .try_collect()
+            .await?;
+
+        Ok::<_, object_store::Error>(samples.into_iter().flatten().collect::<Vec<_>>())
+    });
+
+    match result {
+        Ok(samples) => Ok(samples
+            .iter()
+            .map(|(meta, data)| {
+                let mut binary = OwnedBinary::new(data.len()).unwrap();
+                binary.as_mut_slice().copy_from_slice(data);
+                (encode_object_meta(env, meta), binary.release(env)).encode(env)
+            })
+            .collect::<Vec<_>>()
+            .encode(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
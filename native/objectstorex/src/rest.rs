@@ -0,0 +1,175 @@
+//! Helpers shared by the REST clients for provider APIs object_store doesn't
+//! expose
+
+use crate::errors::{parse_retry_after, provider_code, HttpError};
+use bytes::Bytes;
+use object_store::path::Path;
+use object_store::{ClientConfigKey, ClientOptions, Error, Result};
+use reqwest::header::RETRY_AFTER;
+use reqwest::{Certificate, ClientBuilder, NoProxy, Proxy, Response, StatusCode};
+use std::time::Duration;
+
+/// Generic store error with a message
+pub fn generic(store: &'static str, message: String) -> Error {
+    Error::Generic {
+        store,
+        source: message.into(),
+    }
+}
+
+/// Store error for a request that couldn't be sent or whose response couldn't
+/// be read, keeping the reqwest error as its source so timeouts are recognized
+pub fn request_error(store: &'static str, error: reqwest::Error) -> Error {
+    Error::Generic {
+        store,
+        source: Box::new(error),
+    }
+}
+
+/// HTTP client configured like the one object_store builds from `options`
+///
+/// object_store keeps its client private, so requests it doesn't expose
+/// would otherwise ignore the store's proxy, timeouts, TLS settings and
+/// `allow_http`. Only settings readable back from the options are applied:
+/// root certificates and default headers are not.
+pub fn http_client(store: &'static str, options: &ClientOptions) -> Result<reqwest::Client> {
+    let value = |key| options.get_config_value(&key);
+    let flag = |key| {
+        value(key).is_some_and(|v| matches!(v.to_lowercase().as_str(), "true" | "1" | "yes" | "on"))
+    };
+    let invalid =
+        |key: ClientConfigKey, v: &str| generic(store, format!("Invalid {:?}: {}", key, v));
+    let duration = |key: ClientConfigKey| {
+        value(key)
+            .map(|v| humantime::parse_duration(&v).map_err(|_| invalid(key, &v)))
+            .transpose()
+    };
+    let number = |key: ClientConfigKey| {
+        value(key)
+            .map(|v| v.parse::<u32>().map_err(|_| invalid(key, &v)))
+            .transpose()
+    };
+
+    let mut builder = ClientBuilder::new();
+
+    if let Some(agent) = value(ClientConfigKey::UserAgent) {
+        builder = builder.user_agent(agent);
+    }
+
+    if let Some(url) = value(ClientConfigKey::ProxyUrl) {
+        let mut proxy = Proxy::all(&url).map_err(|e| request_error(store, e))?;
+
+        if let Some(pem) = value(ClientConfigKey::ProxyCaCertificate) {
+            let certificate =
+                Certificate::from_pem(pem.as_bytes()).map_err(|e| request_error(store, e))?;
+            builder = builder.add_root_certificate(certificate);
+        }
+        if let Some(excludes) = value(ClientConfigKey::ProxyExcludes) {
+            proxy = proxy.no_proxy(NoProxy::from_string(&excludes));
+        }
+
+        builder = builder.proxy(proxy);
+    }
+
+    if let Some(timeout) = duration(ClientConfigKey::Timeout)? {
+        builder = builder.timeout(timeout);
+    }
+    if let Some(timeout) = duration(ClientConfigKey::ConnectTimeout)? {
+        builder = builder.connect_timeout(timeout);
+    }
+    if let Some(timeout) = duration(ClientConfigKey::PoolIdleTimeout)? {
+        builder = builder.pool_idle_timeout(timeout);
+    }
+    if let Some(max) = number(ClientConfigKey::PoolMaxIdlePerHost)? {
+        builder = builder.pool_max_idle_per_host(max as usize);
+    }
+    if let Some(interval) = duration(ClientConfigKey::Http2KeepAliveInterval)? {
+        builder = builder.http2_keep_alive_interval(interval);
+    }
+    if let Some(timeout) = duration(ClientConfigKey::Http2KeepAliveTimeout)? {
+        builder = builder.http2_keep_alive_timeout(timeout);
+    }
+    if flag(ClientConfigKey::Http2KeepAliveWhileIdle) {
+        builder = builder.http2_keep_alive_while_idle(true);
+    }
+    if let Some(size) = number(ClientConfigKey::Http2MaxFrameSize)? {
+        builder = builder.http2_max_frame_size(Some(size));
+    }
+    if flag(ClientConfigKey::Http1Only) {
+        builder = builder.http1_only();
+    }
+    if flag(ClientConfigKey::Http2Only) {
+        builder = builder.http2_prior_knowledge();
+    }
+    if flag(ClientConfigKey::AllowInvalidCertificates) {
+        builder = builder.danger_accept_invalid_certs(true);
+    }
+
+    builder
+        .https_only(!flag(ClientConfigKey::AllowHttp))
+        .build()
+        .map_err(|e| request_error(store, e))
+}
+
+/// Read a response and map it to its body, or to the object_store error for
+/// its status
+pub async fn read_response(
+    store: &'static str,
+    path: &Path,
+    response: Response,
+) -> Result<Bytes, Error> {
+    let status = response.status();
+    let retry_after = response
+        .headers()
+        .get(RETRY_AFTER)
+        .and_then(|value| value.to_str().ok())
+        .and_then(parse_retry_after);
+    let body = response
+        .bytes()
+        .await
+        .map_err(|e| request_error(store, e))?;
+
+    check_response(store, path, status, retry_after, body)
+}
+
+/// Map a response to its body, or to the object_store error for its status
+///
+/// Unexpected statuses become generic errors whose source is an `HttpError`
+/// carrying the status, the provider's error code and the `Retry-After` hint.
+pub fn check_response(
+    store: &'static str,
+    path: &Path,
+    status: StatusCode,
+    retry_after: Option<Duration>,
+    body: Bytes,
+) -> Result<Bytes, Error> {
+    let source = || String::from_utf8_lossy(&body).into_owned().into();
+
+    match status {
+        status if status.is_success() => Ok(body),
+        StatusCode::NOT_FOUND => Err(Error::NotFound {
+            path: path.to_string(),
+            source: source(),
+        }),
+        StatusCode::FORBIDDEN => Err(Error::PermissionDenied {
+            path: path.to_string(),
+            source: source(),
+        }),
+        StatusCode::PRECONDITION_FAILED => Err(Error::Precondition {
+            path: path.to_string(),
+            source: source(),
+        }),
+        status => {
+            let body = String::from_utf8_lossy(&body).into_owned();
+            Err(Error::Generic {
+                store,
+                source: Box::new(HttpError {
+                    status: status.as_u16(),
+                    code: provider_code(&body),
+                    retry_after,
+                    body,
+                }),
+            })
+        }
+    }
+}
@@ -0,0 +1,165 @@
+//! Restores of archived S3 objects (Glacier storage classes), which
+//! object_store doesn't expose
+
+use crate::atoms;
+use crate::errors::map_error;
+use crate::s3_api::S3Api;
+use crate::shutdown;
+use crate::store::StoreWrapper;
+use crate::types::TimestampNif;
+use crate::RUNTIME;
+use chrono::{DateTime, Utc};
+use object_store::path::Path;
+use object_store::Result;
+use reqwest::Method;
+use rustler::{Atom, Encoder, Env, NifMap, NifResult, ResourceArc, Term};
+
+/// Error code of restore requests for objects already being restored
+const ALREADY_IN_PROGRESS: &str = "RestoreAlreadyInProgress";
+
+/// Retrieval tier of a restore, trading speed for cost
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RestoreTier {
+    Expedited,
+    Standard,
+    Bulk,
+}
+
+impl RestoreTier {
+    fn from_atom(atom: Atom) -> NifResult<Self> {
+        match atom {
+            a if a == atoms::expedited() => Ok(RestoreTier::Expedited),
+            a if a == atoms::standard() => Ok(RestoreTier::Standard),
+            a if a == atoms::bulk() => Ok(RestoreTier::Bulk),
+            _ => Err(rustler::Error::BadArg),
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            RestoreTier::Expedited => "Expedited",
+            RestoreTier::Standard => "Standard",
+            RestoreTier::Bulk => "Bulk",
+        }
+    }
+}
+
+/// Restore state of an object, as returned by `restore_status`
+///
+/// `status` is `:available` for objects readable without a restore,
+/// `:archived` for archived objects without a restored copy, `:in_progress`
+/// while a restore runs and `:restored` while the restored copy is readable,
+/// until `expires_at`.
+#[derive(Debug, NifMap)]
+pub struct RestoreStatusNif {
+    pub status: Atom,
+    pub storage_class: String,
+    pub expires_at: Option<TimestampNif>,
+}
+
+/// Value of a `name="value"` parameter of the `x-amz-restore` header
+fn restore_param<'a>(header: &'a str, name: &str) -> Option<&'a str> {
+    let start = header.find(&format!("{}=\"", name))? + name.len() + 2;
+    let len = header[start..].find('"')?;
+    Some(&header[start..start + len])
+}
+
+impl S3Api {
+    /// Start restoring an archived object, keeping the restored copy readable
+    /// for `days` days
+    ///
+    /// Restoring an object that is already being restored succeeds without
+    /// effect; restoring a restored object moves its expiry.
+    pub async fn restore(&self, path: &Path, tier: RestoreTier, days: u32) -> Result<()> {
+        let body = format!(
+            r#"<RestoreRequest xmlns="http://s3.amazonaws.com/doc/2006-03-01/"><Days>{}</Days><GlacierJobParameters><Tier>{}</Tier></GlacierJobParameters></RestoreRequest>"#,
+            days,
+            tier.as_str()
+        );
+
+        match self
+            .send(Method::POST, path, Some("restore"), &[], Some(body))
+            .await
+        {
+            Err(e) if e.to_string().contains(ALREADY_IN_PROGRESS) => Ok(()),
+            result => result.map(|_| ()),
+        }
+    }
+
+    /// Get the restore state of an object from its `x-amz-restore`,
+    /// `x-amz-storage-class` and `x-amz-archive-status` headers
+    pub async fn restore_status(&self, path: &Path) -> Result<RestoreStatusNif> {
+        let headers = self.head(path).await?;
+        let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+
+        // S3 omits the storage class of standard objects
+        let storage_class = header("x-amz-storage-class").unwrap_or("STANDARD");
+        let archived = matches!(storage_class, "GLACIER" | "DEEP_ARCHIVE")
+            || header("x-amz-archive-status").is_some();
+        let restore = header("x-amz-restore");
+
+        let (status, expires_at) = match restore.and_then(|r| restore_param(r, "ongoing-request")) {
+            Some("true") => (atoms::in_progress(), None),
+            Some(_) => {
+                let expires_at = restore
+                    .and_then(|r| restore_param(r, "expiry-date"))
+                    .and_then(|date| DateTime::parse_from_rfc2822(date).ok())
+                    .map(|date| TimestampNif(date.with_timezone(&Utc)));
+                (atoms::restored(), expires_at)
+            }
+            None if archived => (atoms::archived(), None),
+            None => (atoms::available(), None),
+        };
+
+        Ok(RestoreStatusNif {
+            status,
+            storage_class: storage_class.to_string(),
+            expires_at,
+        })
+    }
+}
+
+/// Start restoring an archived object for `days` days with the given tier
+/// (`:expedited`, `:standard` or `:bulk`)
+///
+/// Returns `:not_supported` for stores without archive restores.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn restore_object<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+    tier: Atom,
+    days: u32,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let tier = RestoreTier::from_atom(tier)?;
+    let Some(s3) = store.s3.clone() else {
+        return Ok(atoms::not_supported().to_term(env));
+    };
+
+    match RUNTIME.block_on(s3.restore(&Path::from(path), tier, days)) {
+        Ok(()) => Ok(atoms::ok().to_term(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
+
+/// Get the restore state of an object
+///
+/// Returns a `RestoreStatusNif` map, or `:not_supported` for stores without
+/// archive restores.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn restore_status<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let Some(s3) = store.s3.clone() else {
+        return Ok(atoms::not_supported().to_term(env));
+    };
+
+    match RUNTIME.block_on(s3.restore_status(&Path::from(path))) {
+        Ok(status) => Ok(status.encode(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
@@ -0,0 +1,132 @@
+//! The Tokio runtime every NIF runs its async work on
+
+use crate::shutdown;
+use once_cell::sync::OnceCell;
+use rustler::{Env, LocalPid, NifMap, OwnedEnv, Term};
+use std::future::Future;
+use std::sync::Mutex;
+use std::time::Instant;
+use tokio::runtime::{Builder, Handle, Runtime};
+use tokio::task::JoinHandle;
+
+/// Runtime settings from the `:runtime` application environment, passed when
+/// the NIF is loaded; unset fields keep Tokio's defaults
+#[derive(Debug, Clone, NifMap)]
+pub struct RuntimeConfigNif {
+    /// Threads running async tasks (default: one per core)
+    pub worker_threads: Option<usize>,
+    /// Limit on threads running blocking work, such as local file access
+    pub max_blocking_threads: Option<usize>,
+    /// Name given to the runtime's threads
+    pub thread_name: Option<String>,
+}
+
+static CONFIG: OnceCell<RuntimeConfigNif> = OnceCell::new();
+
+/// Set the settings the runtime is built with
+///
+/// Set once, when the library is loaded; later calls are ignored.
+pub fn configure(config: RuntimeConfigNif) {
+    let _ = CONFIG.set(config);
+}
+
+/// Build the multi-threaded runtime with the configured settings
+fn build() -> Runtime {
+    let mut builder = Builder::new_multi_thread();
+    builder.enable_all();
+
+    if let Some(config) = CONFIG.get() {
+        if let Some(threads) = config.worker_threads {
+            builder.worker_threads(threads);
+        }
+        if let Some(threads) = config.max_blocking_threads {
+            builder.max_blocking_threads(threads);
+        }
+        if let Some(name) = &config.thread_name {
+            builder.thread_name(name);
+        }
+    }
+
+    builder.build().expect("Failed to create Tokio runtime")
+}
+
+/// Runtime that can be shut down while NIFs hold a reference to it
+///
+/// It doesn't refuse work itself: NIFs call `shutdown::ensure_running` on
+/// entry, so tasks and wrappers finishing while `shutdown` drains can keep
+/// spawning and sending.
+pub struct NifRuntime {
+    handle: Handle,
+    /// The runtime itself, until it is shut down
+    runtime: Mutex<Option<Runtime>>,
+}
+
+impl NifRuntime {
+    pub fn new() -> Self {
+        let runtime = build();
+        Self {
+            handle: runtime.handle().clone(),
+            runtime: Mutex::new(Some(runtime)),
+        }
+    }
+
+    pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.handle.block_on(future)
+    }
+
+    pub fn spawn<F>(&self, future: F) -> JoinHandle<F::Output>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        self.handle.spawn(future)
+    }
+
+    /// Spawn `future` unless `shutdown` has been called
+    ///
+    /// For cleanup started by destructors, which run whenever the VM collects
+    /// a resource and must neither block nor panic. Tokio cancels tasks
+    /// spawned on a runtime that stopped in the meantime.
+    pub fn try_spawn<F>(&self, future: F) -> Option<JoinHandle<F::Output>>
+    where
+        F: Future + Send + 'static,
+        F::Output: Send + 'static,
+    {
+        if shutdown::is_shut_down() {
+            return None;
+        }
+        Some(self.handle.spawn(future))
+    }
+
+    /// Send the message built by `message` to `pid` from a runtime thread
+    ///
+    /// NIFs usually run on VM threads, where OwnedEnv can't send. The message
+    /// goes out once `after` has been sent, keeping related messages in
+    /// order; the returned task resolves to whether `pid` was alive.
+    pub fn send<F>(
+        &self,
+        pid: LocalPid,
+        after: Option<JoinHandle<bool>>,
+        message: F,
+    ) -> JoinHandle<bool>
+    where
+        F: for<'a> FnOnce(Env<'a>) -> Term<'a> + Send + 'static,
+    {
+        self.spawn(async move {
+            if let Some(previous) = after {
+                let _ = previous.await;
+            }
+            OwnedEnv::new().send_and_clear(&pid, message).is_ok()
+        })
+    }
+
+    /// Run `drain` to completion, then shut the runtime down, waiting for
+    /// its tasks until `deadline`
+    pub fn shut_down(&self, drain: impl Future<Output = ()>, deadline: Instant) {
+        self.handle.block_on(drain);
+
+        if let Some(runtime) = self.runtime.lock().unwrap().take() {
+            runtime.shutdown_timeout(deadline.saturating_duration_since(Instant::now()));
+        }
+    }
+}
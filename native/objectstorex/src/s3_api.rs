@@ -0,0 +1,432 @@
+//! Signed S3 requests for APIs object_store doesn't expose
+
+use crate::rest;
+use crate::sse::ServerSideEncryption;
+use crate::wrappers::deadline::Deadline;
+use crate::wrappers::inflight::Operation;
+use crate::wrappers::request_limit;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use bytes::Bytes;
+use md5::{Digest, Md5};
+use object_store::aws::{AmazonS3, AwsAuthorizer};
+use object_store::multipart::PartId;
+use object_store::path::Path;
+use object_store::{
+    Attribute, Attributes, ClientOptions, Error, PutMode, PutResult, Result, UpdateVersion,
+};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use reqwest::header::HeaderMap;
+use reqwest::{Body, Method};
+use std::ops::Range;
+use std::sync::Arc;
+
+const STORE: &str = "S3";
+
+/// Characters escaped in object keys, matching object_store's S3 client
+const PATH_ENCODE_SET: AsciiSet = NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~')
+    .remove(b'/');
+
+/// Characters escaped in query parameter values
+const QUERY_ENCODE_SET: AsciiSet = NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+/// Sends requests signed with the store's credentials to its bucket
+#[derive(Clone)]
+pub struct S3Api {
+    pub(crate) store: Arc<AmazonS3>,
+    client: reqwest::Client,
+    pub(crate) bucket: String,
+    /// URL of the bucket, which object URLs extend with the key
+    pub(crate) bucket_endpoint: String,
+    pub(crate) region: String,
+    /// Server-side encryption headers sent with requests writing objects
+    encryption_headers: Vec<(&'static str, String)>,
+    /// Headers decrypting the source of server-side copies
+    copy_source_headers: Vec<(&'static str, String)>,
+    /// Whether requests agree to pay for requester-pays buckets
+    request_payer: bool,
+    /// Timeout and cancellation token of the operation using the client
+    deadline: Option<Deadline>,
+}
+
+impl S3Api {
+    /// Create a client addressing the bucket the same way as the store, with
+    /// the client options the store was built with
+    pub fn new(
+        store: Arc<AmazonS3>,
+        options: &ClientOptions,
+        bucket: &str,
+        region: &str,
+        endpoint: Option<&str>,
+    ) -> Result<Self> {
+        let bucket_endpoint = match endpoint {
+            Some(endpoint) => format!("{}/{}", endpoint.trim_end_matches('/'), bucket),
+            None => format!("https://s3.{}.amazonaws.com/{}", region, bucket),
+        };
+
+        Ok(Self {
+            store,
+            client: rest::http_client(STORE, options)?,
+            bucket: bucket.to_string(),
+            bucket_endpoint,
+            region: region.to_string(),
+            encryption_headers: Vec::new(),
+            copy_source_headers: Vec::new(),
+            request_payer: false,
+            deadline: None,
+        })
+    }
+
+    /// Address the bucket by host name, like a store built with
+    /// `with_virtual_hosted_style_request`
+    ///
+    /// A custom endpoint is then expected to include the bucket already.
+    pub fn with_virtual_hosted_style(mut self, endpoint: Option<&str>) -> Self {
+        self.bucket_endpoint = match endpoint {
+            Some(endpoint) => endpoint.trim_end_matches('/').to_string(),
+            None => format!("https://{}.s3.{}.amazonaws.com", self.bucket, self.region),
+        };
+        self
+    }
+
+    /// Send `x-amz-request-payer` with every request, like the store
+    pub fn with_request_payer(mut self) -> Self {
+        self.request_payer = true;
+        self
+    }
+
+    /// Encrypt objects written by copies and multipart uploads the same way
+    /// as the store's own uploads
+    pub fn with_encryption(mut self, sse: &ServerSideEncryption, bucket_key: Option<bool>) -> Self {
+        self.encryption_headers = sse.headers(bucket_key);
+        self.copy_source_headers = sse.copy_source_headers();
+        self
+    }
+
+    /// Copy of the client whose requests are bounded by `deadline`
+    pub fn bounded(&self, deadline: Deadline) -> Self {
+        Self {
+            deadline: Some(deadline),
+            ..self.clone()
+        }
+    }
+
+    /// Delete an object, only if its ETag matches and/or a specific version
+    ///
+    /// A mismatching `if_match` fails with `Error::Precondition`.
+    pub async fn delete(
+        &self,
+        path: &Path,
+        if_match: Option<&str>,
+        version: Option<&str>,
+    ) -> Result<()> {
+        let query = version.map(|version| format!("versionId={}", encode_query(version)));
+        let headers: Vec<(&str, String)> = if_match
+            .map(|etag| ("If-Match", etag.to_string()))
+            .into_iter()
+            .collect();
+
+        self.send(Method::DELETE, path, query.as_deref(), &headers, None)
+            .await
+            .map(|_| ())
+    }
+
+    /// Copy an object server-side, only if the source's ETag matches
+    ///
+    /// A mismatching `if_match` fails with `Error::Precondition`.
+    pub async fn copy(&self, from: &Path, to: &Path, if_match: &str) -> Result<()> {
+        let mut headers = vec![
+            ("x-amz-copy-source", self.copy_source(from)),
+            ("x-amz-copy-source-if-match", if_match.to_string()),
+        ];
+        headers.extend(self.encryption_headers.iter().cloned());
+        headers.extend(self.copy_source_headers.iter().cloned());
+
+        self.send(Method::PUT, to, None, &headers, None)
+            .await
+            .map(|_| ())
+    }
+
+    /// Upload an object in one request, sending `extra` headers along
+    ///
+    /// Like the store's own puts, the object gets the attributes, tags and
+    /// encryption, and the write mode's conditions. The body's `Content-MD5`
+    /// is always sent, which some headers (Object Lock) require.
+    pub async fn put(
+        &self,
+        path: &Path,
+        data: Bytes,
+        mode: &PutMode,
+        attributes: &Attributes,
+        tags: &[(String, String)],
+        extra: &[(&str, String)],
+    ) -> Result<PutResult> {
+        let attributes = attribute_headers(attributes);
+        let mut headers: Vec<(&str, String)> = attributes
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.clone()))
+            .chain(self.encryption_headers.iter().cloned())
+            .chain(extra.iter().cloned())
+            .collect();
+        headers.push(("Content-MD5", BASE64_STANDARD.encode(Md5::digest(&data))));
+
+        if !tags.is_empty() {
+            let tagging: Vec<String> = tags
+                .iter()
+                .map(|(key, value)| format!("{}={}", encode_query(key), encode_query(value)))
+                .collect();
+            headers.push(("x-amz-tagging", tagging.join("&")));
+        }
+        match mode {
+            PutMode::Overwrite => {}
+            PutMode::Create => headers.push(("If-None-Match", "*".to_string())),
+            PutMode::Update(UpdateVersion {
+                e_tag: Some(e_tag), ..
+            }) => headers.push(("If-Match", e_tag.clone())),
+            PutMode::Update(_) => {
+                return Err(generic("S3 conditional puts require an ETag".to_string()))
+            }
+        }
+
+        let (headers, _) = match self
+            .request(Method::PUT, path, None, &headers, Some(data.into()))
+            .await
+        {
+            Err(Error::Precondition { path, source }) if matches!(mode, PutMode::Create) => {
+                return Err(Error::AlreadyExists { path, source })
+            }
+            result => result?,
+        };
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+
+        Ok(PutResult {
+            e_tag: header("ETag"),
+            version: header("x-amz-version-id"),
+        })
+    }
+
+    /// Response headers of a HEAD request for an object, for headers
+    /// object_store doesn't report
+    pub async fn head(&self, path: &Path) -> Result<HeaderMap> {
+        // Reads only take encryption headers for customer keys
+        let headers = if self.copy_source_headers.is_empty() {
+            Vec::new()
+        } else {
+            self.encryption_headers.clone()
+        };
+
+        let (headers, _) = self
+            .request(Method::HEAD, path, None, &headers, None)
+            .await?;
+        Ok(headers)
+    }
+
+    /// Value of the `x-amz-copy-source` header for an object in the bucket
+    pub fn copy_source(&self, path: &Path) -> String {
+        utf8_percent_encode(&format!("{}/{}", self.bucket, path), &PATH_ENCODE_SET).to_string()
+    }
+
+    /// Start a multipart upload with the given attributes
+    ///
+    /// Returns the upload id. Parts uploaded with `MultipartStore::put_part`
+    /// and copied with `copy_part` can be mixed in the same upload.
+    pub async fn create_upload(&self, path: &Path, attributes: &Attributes) -> Result<String> {
+        let headers = attribute_headers(attributes);
+        let headers: Vec<(&str, String)> = headers
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.clone()))
+            .chain(self.encryption_headers.iter().cloned())
+            .collect();
+
+        let body = self
+            .send(Method::POST, path, Some("uploads"), &headers, None)
+            .await?;
+        xml_element(&body, b"UploadId")
+            .ok_or_else(|| generic("Invalid CreateMultipartUpload response".to_string()))
+    }
+
+    /// Copy a range of `source` server-side as part `index` of an upload
+    ///
+    /// With `e_tag`, the copy fails with `Error::Precondition` if the source
+    /// has changed.
+    pub async fn copy_part(
+        &self,
+        path: &Path,
+        upload_id: &str,
+        index: usize,
+        source: &Path,
+        e_tag: Option<&str>,
+        range: Range<usize>,
+    ) -> Result<PartId> {
+        let query = format!(
+            "partNumber={}&uploadId={}",
+            index + 1,
+            encode_query(upload_id)
+        );
+        let mut headers = vec![
+            ("x-amz-copy-source", self.copy_source(source)),
+            (
+                "x-amz-copy-source-range",
+                format!("bytes={}-{}", range.start, range.end - 1),
+            ),
+        ];
+        if let Some(e_tag) = e_tag {
+            headers.push(("x-amz-copy-source-if-match", e_tag.to_string()));
+        }
+        // Parts only take encryption headers for customer keys, which are
+        // needed for both the upload and the source
+        if !self.copy_source_headers.is_empty() {
+            headers.extend(self.encryption_headers.iter().cloned());
+            headers.extend(self.copy_source_headers.iter().cloned());
+        }
+
+        let body = self
+            .send(Method::PUT, path, Some(&query), &headers, None)
+            .await?;
+        let content_id = xml_element(&body, b"ETag")
+            .ok_or_else(|| generic("Invalid UploadPartCopy response".to_string()))?;
+        Ok(PartId { content_id })
+    }
+
+    /// Send a signed request for an object
+    ///
+    /// `query` is appended to the object URL as is; callers encode it.
+    pub async fn send(
+        &self,
+        method: Method,
+        path: &Path,
+        query: Option<&str>,
+        headers: &[(&str, String)],
+        body: Option<String>,
+    ) -> Result<Bytes> {
+        let (_, body) = self
+            .request(method, path, query, headers, body.map(Body::from))
+            .await?;
+        Ok(body)
+    }
+
+    /// Send a signed request for an object, returning the response headers
+    /// along with the body
+    pub async fn request(
+        &self,
+        method: Method,
+        path: &Path,
+        query: Option<&str>,
+        headers: &[(&str, String)],
+        body: Option<Body>,
+    ) -> Result<(HeaderMap, Bytes)> {
+        let request = self.execute(method, path, query, headers, body);
+        match &self.deadline {
+            Some(deadline) => deadline.run(request).await,
+            None => request.await,
+        }
+    }
+
+    async fn execute(
+        &self,
+        method: Method,
+        path: &Path,
+        query: Option<&str>,
+        headers: &[(&str, String)],
+        body: Option<Body>,
+    ) -> Result<(HeaderMap, Bytes)> {
+        let operation = Operation::start("s3_request", path);
+        let _permit = request_limit::acquire().await;
+        let credential = self.store.credentials().get_credential().await?;
+        let mut url = format!(
+            "{}/{}",
+            self.bucket_endpoint,
+            utf8_percent_encode(path.as_ref(), &PATH_ENCODE_SET)
+        );
+        if let Some(query) = query {
+            url.push('?');
+            url.push_str(query);
+        }
+
+        let mut builder = self.client.request(method, url);
+        for (name, value) in headers {
+            builder = builder.header(*name, value);
+        }
+        if self.request_payer {
+            builder = builder.header("x-amz-request-payer", "requester");
+        }
+        if let Some(body) = body {
+            builder = builder.body(body);
+        }
+
+        let mut request = builder.build().map_err(|e| generic(e.to_string()))?;
+        AwsAuthorizer::new(&credential, "s3", &self.region).authorize(&mut request, None);
+
+        let response = self
+            .client
+            .execute(request)
+            .await
+            .map_err(|e| rest::request_error(STORE, e))?;
+
+        let headers = response.headers().clone();
+        let body = rest::read_response(STORE, path, response).await?;
+        operation.transferred(body.len());
+        Ok((headers, body))
+    }
+}
+
+/// Headers setting the given attributes on a written object
+pub fn attribute_headers(attributes: &Attributes) -> Vec<(String, String)> {
+    attributes
+        .iter()
+        .filter_map(|(attribute, value)| {
+            let name = match attribute {
+                Attribute::ContentDisposition => "Content-Disposition".to_string(),
+                Attribute::ContentEncoding => "Content-Encoding".to_string(),
+                Attribute::ContentLanguage => "Content-Language".to_string(),
+                Attribute::ContentType => "Content-Type".to_string(),
+                Attribute::CacheControl => "Cache-Control".to_string(),
+                Attribute::Metadata(key) => format!("x-amz-meta-{}", key),
+                _ => return None,
+            };
+            Some((name, value.to_string()))
+        })
+        .collect()
+}
+
+/// Text of the first element with the given name in an XML document
+pub fn xml_element(body: &[u8], name: &[u8]) -> Option<String> {
+    let mut reader = Reader::from_reader(body);
+    let mut buf = Vec::new();
+    let mut inside = false;
+
+    loop {
+        match reader.read_event_into(&mut buf).ok()? {
+            Event::Start(start) => inside = start.name().as_ref() == name,
+            Event::Text(text) if inside => return text.unescape().ok().map(|t| t.into_owned()),
+            Event::End(_) => inside = false,
+            Event::Eof => return None,
+            _ => {}
+        }
+        buf.clear();
+    }
+}
+
+/// Percent-encode a query parameter value
+pub fn encode_query(value: &str) -> String {
+    utf8_percent_encode(value, &QUERY_ENCODE_SET).to_string()
+}
+
+/// Generic S3 error with a message
+pub fn generic(message: String) -> object_store::Error {
+    rest::generic(STORE, message)
+}
@@ -0,0 +1,111 @@
+//! Shutting the library down: refusing new work, draining streams, aborting
+//! unfinished multipart uploads and stopping the runtime
+
+use crate::atoms;
+use crate::streaming;
+use crate::RUNTIME;
+use futures::future::BoxFuture;
+use once_cell::sync::Lazy;
+use rustler::{Encoder, Env, NifResult, Term};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+static SHUT_DOWN: AtomicBool = AtomicBool::new(false);
+
+/// Whether `shutdown` has been called
+pub fn is_shut_down() -> bool {
+    SHUT_DOWN.load(Ordering::SeqCst)
+}
+
+/// Refuse to start a NIF's work once `shutdown` has been called
+///
+/// NIFs running async work call it on entry. The call then returns the bare
+/// `:shut_down` reason, which the Elixir wrappers return as
+/// `{:error, :shut_down}` like any other error reason.
+pub fn ensure_running() -> NifResult<()> {
+    if is_shut_down() {
+        return Err(rustler::Error::Atom("shut_down"));
+    }
+    Ok(())
+}
+
+/// Abort of an unfinished upload, `None` once the upload is gone
+type AbortUpload = Box<dyn Fn() -> Option<BoxFuture<'static, ()>> + Send>;
+
+struct UploadEntry {
+    /// Uploads that can be resumed after a restart
+    resumable: bool,
+    abort: AbortUpload,
+}
+
+static UPLOADS: Lazy<Mutex<HashMap<u64, UploadEntry>>> = Lazy::new(|| Mutex::new(HashMap::new()));
+static NEXT_UPLOAD: AtomicU64 = AtomicU64::new(0);
+
+/// An unfinished upload that `shutdown` aborts; dropping it unregisters the
+/// upload
+pub struct UploadRegistration(u64);
+
+impl Drop for UploadRegistration {
+    fn drop(&mut self) {
+        UPLOADS.lock().unwrap().remove(&self.0);
+    }
+}
+
+/// Register an unfinished multipart upload to abort on shutdown
+pub fn register_upload(
+    resumable: bool,
+    abort: impl Fn() -> Option<BoxFuture<'static, ()>> + Send + 'static,
+) -> UploadRegistration {
+    let id = NEXT_UPLOAD.fetch_add(1, Ordering::Relaxed);
+    UPLOADS.lock().unwrap().insert(
+        id,
+        UploadEntry {
+            resumable,
+            abort: Box::new(abort),
+        },
+    );
+    UploadRegistration(id)
+}
+
+/// Abort every registered upload, giving up at `deadline`
+async fn abort_uploads(deadline: tokio::time::Instant, keep_resumable: bool) {
+    let aborts: Vec<_> = UPLOADS
+        .lock()
+        .unwrap()
+        .values()
+        .filter(|entry| !(keep_resumable && entry.resumable))
+        .filter_map(|entry| (entry.abort)())
+        .collect();
+
+    let _ = tokio::time::timeout_at(deadline, futures::future::join_all(aborts)).await;
+}
+
+/// Shut the library down
+///
+/// Once called, every NIF running async work returns `:shut_down` (see
+/// `ensure_running`). Running downloads and listings may finish until the
+/// timeout, after which they are aborted and their receivers sent
+/// `{:error, id, reason}`. Unfinished multipart uploads
+/// are aborted, except resumable sessions with `keep_resumable`. Then the
+/// runtime is shut down, waiting for its remaining tasks until the timeout.
+/// Calling it again does nothing.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn shutdown(env: Env<'_>, timeout_ms: u64, keep_resumable: bool) -> Term<'_> {
+    if SHUT_DOWN.swap(true, Ordering::SeqCst) {
+        return atoms::ok().encode(env);
+    }
+    let deadline = Instant::now() + Duration::from_millis(timeout_ms);
+
+    RUNTIME.shut_down(
+        async move {
+            let deadline = tokio::time::Instant::from_std(deadline);
+            streaming::drain_streams(deadline).await;
+            abort_uploads(deadline, keep_resumable).await;
+        },
+        deadline,
+    );
+
+    atoms::ok().encode(env)
+}
@@ -0,0 +1,180 @@
+use crate::atoms;
+use crate::errors::map_error;
+use crate::shutdown;
+use crate::store::StoreWrapper;
+use crate::streaming::encode_object_meta;
+use crate::types::SnapshotQueryNif;
+use crate::RUNTIME;
+use futures::TryStreamExt;
+use object_store::path::Path;
+use object_store::ObjectMeta;
+use rustler::types::map;
+use rustler::{Encoder, Env, NifResult, ResourceArc, Term};
+use std::cmp::Ordering;
+
+/// A listing materialized in native memory
+///
+/// Queries run against the snapshot without re-listing the store or
+/// re-encoding objects that aren't returned.
+pub struct ListingSnapshot {
+    objects: Vec<ObjectMeta>,
+}
+
+impl ListingSnapshot {
+    /// Objects matching the query's filters, in listing order
+    fn matching<'s>(&'s self, query: &SnapshotQueryNif) -> Vec<&'s ObjectMeta> {
+        self.objects
+            .iter()
+            .filter(|meta| matches_query(meta, query))
+            .collect()
+    }
+}
+
+fn matches_query(meta: &ObjectMeta, query: &SnapshotQueryNif) -> bool {
+    let location = meta.location.as_ref();
+    let size = meta.size as u64;
+    let modified = meta.last_modified.timestamp();
+
+    query
+        .prefix
+        .as_ref()
+        .is_none_or(|p| location.starts_with(p.as_str()))
+        && query
+            .suffix
+            .as_ref()
+            .is_none_or(|s| location.ends_with(s.as_str()))
+        && query
+            .contains
+            .as_ref()
+            .is_none_or(|c| location.contains(c.as_str()))
+        && query.min_size.is_none_or(|min| size >= min)
+        && query.max_size.is_none_or(|max| size <= max)
+        && query.modified_after.is_none_or(|after| modified >= after)
+        && query.modified_before.is_none_or(|before| modified < before)
+}
+
+/// List a prefix and keep the result as a native snapshot resource
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn snapshot_listing<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    prefix: Option<String>,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let prefix_path = prefix.map(Path::from);
+
+    let result = RUNTIME.block_on(async {
+        store
+            .inner
+            .list(prefix_path.as_ref())
+            .try_collect::<Vec<_>>()
+            .await
+    });
+
+    match result {
+        Ok(objects) => Ok(ResourceArc::new(ListingSnapshot { objects }).encode(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
+
+/// Number of objects in a snapshot
+#[rustler::nif]
+pub fn snapshot_count(snapshot: ResourceArc<ListingSnapshot>) -> usize {
+    snapshot.objects.len()
+}
+
+/// Filter, sort and paginate a snapshot
+///
+/// Returns `{total_matching, page}` where `page` holds the metadata maps of
+/// the requested window of matching objects.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn snapshot_query<'a>(
+    env: Env<'a>,
+    snapshot: ResourceArc<ListingSnapshot>,
+    query: SnapshotQueryNif,
+) -> NifResult<Term<'a>> {
+    let mut matching = snapshot.matching(&query);
+    let total = matching.len();
+
+    if let Some(sort_by) = query.sort_by {
+        let compare: fn(&ObjectMeta, &ObjectMeta) -> Ordering = if sort_by == atoms::size() {
+            |a, b| a.size.cmp(&b.size)
+        } else if sort_by == atoms::last_modified() {
+            |a, b| a.last_modified.cmp(&b.last_modified)
+        } else if sort_by == atoms::location() {
+            |a, b| a.location.cmp(&b.location)
+        } else {
+            return Err(rustler::Error::BadArg);
+        };
+
+        if query.order == atoms::desc() {
+            matching.sort_by(|a, b| compare(b, a));
+        } else {
+            matching.sort_by(|a, b| compare(a, b));
+        }
+    }
+
+    let page: Vec<Term> = matching
+        .into_iter()
+        .skip(query.offset)
+        .take(query.limit.unwrap_or(usize::MAX))
+        .map(|meta| encode_object_meta(env, meta))
+        .collect();
+
+    Ok((total, page).encode(env))
+}
+
+/// Summarize the objects in a snapshot matching the query's filters
+///
+/// Returns a map with `count`, `total_bytes`, `min_size`, `max_size`,
+/// `oldest` and `newest` (timestamps are nil when nothing matches).
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn snapshot_aggregate<'a>(
+    env: Env<'a>,
+    snapshot: ResourceArc<ListingSnapshot>,
+    query: SnapshotQueryNif,
+) -> NifResult<Term<'a>> {
+    let matching = snapshot.matching(&query);
+
+    let total_bytes: u64 = matching.iter().map(|meta| meta.size as u64).sum();
+    let min_size = matching.iter().map(|meta| meta.size as u64).min();
+    let max_size = matching.iter().map(|meta| meta.size as u64).max();
+    let oldest = matching
+        .iter()
+        .map(|meta| meta.last_modified)
+        .min()
+        .map(|t| t.to_string());
+    let newest = matching
+        .iter()
+        .map(|meta| meta.last_modified)
+        .max()
+        .map(|t| t.to_string());
+
+    let summary = map::map_new(env)
+        .map_put(
+            rustler::Atom::from_str(env, "count")?.to_term(env),
+            matching.len().encode(env),
+        )?
+        .map_put(
+            rustler::Atom::from_str(env, "total_bytes")?.to_term(env),
+            total_bytes.encode(env),
+        )?
+        .map_put(
+            rustler::Atom::from_str(env, "min_size")?.to_term(env),
+            min_size.encode(env),
+        )?
+        .map_put(
+            rustler::Atom::from_str(env, "max_size")?.to_term(env),
+            max_size.encode(env),
+        )?
+        .map_put(
+            rustler::Atom::from_str(env, "oldest")?.to_term(env),
+            oldest.encode(env),
+        )?
+        .map_put(
+            rustler::Atom::from_str(env, "newest")?.to_term(env),
+            newest.encode(env),
+        )?;
+
+    Ok(summary)
+}
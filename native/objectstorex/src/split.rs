@@ -0,0 +1,408 @@
+//! Splitting objects into parts with a manifest for reassembly
+
+use crate::atoms;
+use crate::errors::{integrity_error, map_error};
+use crate::patch::Original;
+use crate::s3_api::S3Api;
+use crate::shutdown;
+use crate::store::StoreWrapper;
+use crate::types::to_usize;
+use crate::RUNTIME;
+use futures::StreamExt;
+use object_store::multipart::MultipartStore;
+use object_store::path::Path;
+use object_store::{Attributes, ObjectStore, PutResult, Result, WriteMultipart};
+use ring::digest::{Context, SHA256};
+use rustler::{Encoder, Env, NifMap, NifResult, ResourceArc, Term};
+use serde::{Deserialize, Serialize};
+
+/// Size of the ranged reads and multipart chunks used to copy a part
+const CHUNK_SIZE: usize = 8 * 1024 * 1024;
+
+/// Maximum number of chunks uploading concurrently per part
+const MAX_CONCURRENCY: usize = 8;
+
+/// Minimum size of every part but the last in an S3 multipart upload
+const MIN_PART_SIZE: usize = 5 * 1024 * 1024;
+
+/// Maximum size of a part copied with UploadPartCopy
+const MAX_COPY_PART_SIZE: usize = 5 * 1024 * 1024 * 1024;
+
+/// Name of the manifest written next to the parts
+const MANIFEST_NAME: &str = "manifest.json";
+
+/// Manifest describing how to reassemble a split object
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SplitManifest {
+    /// Location of the object that was split
+    pub source: String,
+    /// Total size in bytes
+    pub size: usize,
+    /// Hex-encoded SHA-256 of the whole object
+    pub sha256: String,
+    /// Parts in order
+    pub parts: Vec<SplitPart>,
+}
+
+/// One part of a split object
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SplitPart {
+    pub location: String,
+    pub size: usize,
+    /// Hex-encoded SHA-256 of the part
+    pub sha256: String,
+}
+
+/// Result of a split, as returned to Elixir
+#[derive(Debug, NifMap)]
+pub struct SplitResultNif {
+    pub manifest: String,
+    pub parts: Vec<String>,
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// Hex-encode a digest
+pub(crate) fn hex(digest: &[u8]) -> String {
+    digest.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Location of the manifest for parts written under a prefix
+fn manifest_location(prefix: &Path) -> Path {
+    prefix.child(MANIFEST_NAME)
+}
+
+/// Split an object into parts of `part_size` bytes under `prefix`
+///
+/// Parts are copied one after another with ranged reads, so the whole-object
+/// checksum can be computed on the way; the reads fail if the object changes
+/// during the split. The manifest is written last.
+async fn split_object(
+    store: &dyn ObjectStore,
+    path: &Path,
+    prefix: &Path,
+    part_size: usize,
+) -> Result<(Path, SplitManifest)> {
+    let original = Original::head(store, path, None).await?;
+    let mut object_digest = Context::new(&SHA256);
+    let mut parts = Vec::new();
+
+    for (index, start) in (0..original.size).step_by(part_size).enumerate() {
+        let end = (start + part_size).min(original.size);
+        let location = prefix.child(format!("part-{:05}", index));
+        let mut part_digest = Context::new(&SHA256);
+
+        if end - start <= CHUNK_SIZE {
+            let data = original.read(store, path, start..end).await?;
+            object_digest.update(&data);
+            part_digest.update(&data);
+            store.put(&location, data.into()).await?;
+        } else {
+            let upload = store.put_multipart(&location).await?;
+            let mut writer = WriteMultipart::new_with_chunk_size(upload, CHUNK_SIZE);
+
+            let copied = async {
+                for chunk_start in (start..end).step_by(CHUNK_SIZE) {
+                    let chunk_end = (chunk_start + CHUNK_SIZE).min(end);
+                    let data = original.read(store, path, chunk_start..chunk_end).await?;
+                    object_digest.update(&data);
+                    part_digest.update(&data);
+                    writer.wait_for_capacity(MAX_CONCURRENCY).await?;
+                    writer.put(data);
+                }
+                Ok(())
+            }
+            .await;
+
+            match copied {
+                Ok(()) => writer.finish().await?,
+                Err(e) => {
+                    let _ = writer.abort().await;
+                    return Err(e);
+                }
+            };
+        }
+
+        parts.push(SplitPart {
+            location: location.to_string(),
+            size: end - start,
+            sha256: hex(part_digest.finish().as_ref()),
+        });
+    }
+
+    let manifest = SplitManifest {
+        source: path.to_string(),
+        size: original.size,
+        sha256: hex(object_digest.finish().as_ref()),
+        parts,
+    };
+    let json = serde_json::to_vec_pretty(&manifest).map_err(|e| object_store::Error::Generic {
+        store: "Split",
+        source: Box::new(e),
+    })?;
+
+    let location = manifest_location(prefix);
+    store.put(&location, json.into()).await?;
+    Ok((location, manifest))
+}
+
+/// Read and parse a split manifest
+async fn read_manifest(store: &dyn ObjectStore, path: &Path) -> Result<SplitManifest> {
+    let body = store.get(path).await?.bytes().await?;
+    serde_json::from_slice(&body).map_err(|e| object_store::Error::Generic {
+        store: "Split",
+        source: format!("Invalid split manifest {}: {}", path, e).into(),
+    })
+}
+
+/// Check that the parts of a manifest exist with their recorded sizes
+///
+/// Returns the current ETag of each part.
+async fn check_part_sizes(
+    store: &dyn ObjectStore,
+    manifest: &SplitManifest,
+) -> Result<Vec<Option<String>>> {
+    let total: usize = manifest.parts.iter().map(|part| part.size).sum();
+    if total != manifest.size {
+        return Err(integrity_error(format!(
+            "Parts add up to {} bytes, manifest records {}",
+            total, manifest.size
+        )));
+    }
+
+    let mut e_tags = Vec::with_capacity(manifest.parts.len());
+    for part in &manifest.parts {
+        let meta = store.head(&Path::from(part.location.as_str())).await?;
+        if meta.size != part.size {
+            return Err(integrity_error(format!(
+                "Part {} has {} bytes, manifest records {}",
+                part.location, meta.size, part.size
+            )));
+        }
+        e_tags.push(meta.e_tag);
+    }
+    Ok(e_tags)
+}
+
+/// Stream the parts of a manifest in order, checking their checksums
+///
+/// Each chunk is passed to `sink`. Fails with an integrity error once a part
+/// or the whole object doesn't match its recorded SHA-256.
+async fn read_parts<F>(store: &dyn ObjectStore, manifest: &SplitManifest, mut sink: F) -> Result<()>
+where
+    F: AsyncChunkSink,
+{
+    let mut object_digest = Context::new(&SHA256);
+
+    for part in &manifest.parts {
+        let mut part_digest = Context::new(&SHA256);
+        let mut stream = store
+            .get(&Path::from(part.location.as_str()))
+            .await?
+            .into_stream();
+
+        while let Some(chunk) = stream.next().await {
+            let chunk = chunk?;
+            object_digest.update(&chunk);
+            part_digest.update(&chunk);
+            sink.put(chunk).await?;
+        }
+
+        if hex(part_digest.finish().as_ref()) != part.sha256 {
+            return Err(integrity_error(format!(
+                "Checksum mismatch for part {}",
+                part.location
+            )));
+        }
+    }
+
+    if hex(object_digest.finish().as_ref()) != manifest.sha256 {
+        return Err(integrity_error(
+            "Checksum mismatch for joined object".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// Destination for the chunks read by `read_parts`
+trait AsyncChunkSink {
+    async fn put(&mut self, chunk: bytes::Bytes) -> Result<()>;
+}
+
+/// Discards chunks, for checksum verification only
+struct Discard;
+
+impl AsyncChunkSink for Discard {
+    async fn put(&mut self, _chunk: bytes::Bytes) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl AsyncChunkSink for &mut WriteMultipart {
+    async fn put(&mut self, chunk: bytes::Bytes) -> Result<()> {
+        self.wait_for_capacity(MAX_CONCURRENCY).await?;
+        WriteMultipart::put(self, chunk);
+        Ok(())
+    }
+}
+
+/// Join parts by streaming them through a multipart upload
+async fn join_by_rewrite(
+    store: &dyn ObjectStore,
+    manifest: &SplitManifest,
+    dest: &Path,
+) -> Result<PutResult> {
+    check_part_sizes(store, manifest).await?;
+
+    let upload = store.put_multipart(dest).await?;
+    let mut writer = WriteMultipart::new_with_chunk_size(upload, CHUNK_SIZE);
+
+    match read_parts(store, manifest, &mut writer).await {
+        Ok(()) => writer.finish().await,
+        Err(e) => {
+            let _ = writer.abort().await;
+            Err(e)
+        }
+    }
+}
+
+/// Whether S3 can join these parts with UploadPartCopy alone
+fn copyable_parts(manifest: &SplitManifest) -> bool {
+    let Some((last, rest)) = manifest.parts.split_last() else {
+        return false;
+    };
+
+    last.size > 0
+        && last.size <= MAX_COPY_PART_SIZE
+        && rest
+            .iter()
+            .all(|part| (MIN_PART_SIZE..=MAX_COPY_PART_SIZE).contains(&part.size))
+}
+
+impl S3Api {
+    /// Join parts server-side with UploadPartCopy
+    ///
+    /// With `verify`, the parts are read back and checksummed before the
+    /// upload is completed; nothing is uploaded from the client either way.
+    async fn join(&self, manifest: &SplitManifest, dest: &Path, verify: bool) -> Result<PutResult> {
+        let store = self.store.as_ref();
+        let e_tags = check_part_sizes(store, manifest).await?;
+        let upload_id = self.create_upload(dest, &Attributes::new()).await?;
+
+        let result = async {
+            let mut parts = Vec::with_capacity(manifest.parts.len());
+            for (index, (part, e_tag)) in manifest.parts.iter().zip(&e_tags).enumerate() {
+                let source = Path::from(part.location.as_str());
+                parts.push(
+                    self.copy_part(
+                        dest,
+                        &upload_id,
+                        index,
+                        &source,
+                        e_tag.as_deref(),
+                        0..part.size,
+                    )
+                    .await?,
+                );
+            }
+
+            if verify {
+                read_parts(store, manifest, Discard).await?;
+            }
+            Ok(parts)
+        }
+        .await;
+
+        match result {
+            Ok(parts) => store.complete_multipart(dest, &upload_id, parts).await,
+            Err(e) => {
+                let _ = store.abort_multipart(dest, &upload_id).await;
+                Err(e)
+            }
+        }
+    }
+}
+
+/// Split an object into parts of at most `part_size` bytes
+///
+/// Parts are written as `part-00000`, `part-00001`, ... under `prefix`,
+/// followed by a `manifest.json` listing them with their sizes and SHA-256
+/// checksums. Returns a map with the manifest location, part locations,
+/// total size and checksum.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn split<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+    prefix: String,
+    part_size: u64,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    if part_size == 0 {
+        return Err(rustler::Error::Term(Box::new(
+            "Part size must be positive".to_string(),
+        )));
+    }
+
+    let path = Path::from(path);
+    let prefix = Path::from(prefix);
+    let result = RUNTIME.block_on(split_object(
+        store.inner.as_ref(),
+        &path,
+        &prefix,
+        to_usize(part_size)?,
+    ));
+
+    match result {
+        Ok((location, manifest)) => {
+            let result = SplitResultNif {
+                manifest: location.to_string(),
+                parts: manifest.parts.into_iter().map(|p| p.location).collect(),
+                size: manifest.size as u64,
+                sha256: manifest.sha256,
+            };
+            Ok((atoms::ok(), result).encode(env))
+        }
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
+
+/// Reassemble an object from the manifest written by `split`
+///
+/// S3 stores copy the parts server-side when every part but the last meets
+/// the minimum part size; other stores stream the parts through a multipart
+/// upload. Part sizes are always checked against the manifest. Checksums are
+/// checked while streaming, and on S3 by reading the parts back when
+/// `verify` is set. Mismatches fail with `:checksum_mismatch` before the
+/// destination is written.
+///
+/// Returns `{:ok, etag, version}`; missing identifiers are empty strings.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn join<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    manifest_path: String,
+    dest: String,
+    verify: bool,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let dest = Path::from(dest);
+
+    let result = RUNTIME.block_on(async {
+        let manifest = read_manifest(store.inner.as_ref(), &Path::from(manifest_path)).await?;
+
+        match &store.s3 {
+            Some(s3) if copyable_parts(&manifest) => s3.join(&manifest, &dest, verify).await,
+            _ => join_by_rewrite(store.inner.as_ref(), &manifest, &dest).await,
+        }
+    });
+
+    match result {
+        Ok(put_result) => {
+            let etag = put_result.e_tag.unwrap_or_default();
+            let version = put_result.version.unwrap_or_default();
+            Ok((atoms::ok(), etag, version).encode(env))
+        }
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
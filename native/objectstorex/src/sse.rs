@@ -0,0 +1,166 @@
+//! Server-side encryption of S3 uploads
+
+use crate::atoms;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use md5::{Digest, Md5};
+use object_store::aws::{AmazonS3Builder, AmazonS3ConfigKey};
+use rustler::{Atom, Binary, Decoder, Encoder, Env, NifResult, OwnedBinary, Term};
+
+/// Server-side encryption requested for every upload to an S3 store
+///
+/// Decoded from `:s3`, `:kms`, `{:kms, key_id}`, `:dsse_kms`,
+/// `{:dsse_kms, key_id}` or `{:customer_key, key}`.
+#[derive(Debug, Clone)]
+pub enum ServerSideEncryption {
+    /// SSE-S3, with keys managed by S3 (`AES256`)
+    S3,
+    /// SSE-KMS, with the given KMS key or the account's default key
+    Kms(Option<String>),
+    /// Dual-layer SSE-KMS
+    DsseKms(Option<String>),
+    /// SSE-C, with a 256-bit key supplied on every request
+    CustomerKey(Vec<u8>),
+}
+
+impl<'a> Decoder<'a> for ServerSideEncryption {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        if let Ok(atom) = term.decode::<Atom>() {
+            return match atom {
+                a if a == atoms::s3() => Ok(ServerSideEncryption::S3),
+                a if a == atoms::kms() => Ok(ServerSideEncryption::Kms(None)),
+                a if a == atoms::dsse_kms() => Ok(ServerSideEncryption::DsseKms(None)),
+                _ => Err(rustler::Error::BadArg),
+            };
+        }
+
+        let (kind, key): (Atom, Binary) = term.decode()?;
+        match kind {
+            k if k == atoms::kms() => Ok(ServerSideEncryption::Kms(Some(key_id(&key)?))),
+            k if k == atoms::dsse_kms() => Ok(ServerSideEncryption::DsseKms(Some(key_id(&key)?))),
+            k if k == atoms::customer_key() && key.len() == 32 => {
+                Ok(ServerSideEncryption::CustomerKey(key.to_vec()))
+            }
+            _ => Err(rustler::Error::BadArg),
+        }
+    }
+}
+
+impl Encoder for ServerSideEncryption {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        match self {
+            ServerSideEncryption::S3 => atoms::s3().encode(env),
+            ServerSideEncryption::Kms(None) => atoms::kms().encode(env),
+            ServerSideEncryption::Kms(Some(key_id)) => (atoms::kms(), key_id).encode(env),
+            ServerSideEncryption::DsseKms(None) => atoms::dsse_kms().encode(env),
+            ServerSideEncryption::DsseKms(Some(key_id)) => (atoms::dsse_kms(), key_id).encode(env),
+            ServerSideEncryption::CustomerKey(key) => {
+                let mut binary = OwnedBinary::new(key.len()).expect("allocate binary");
+                binary.as_mut_slice().copy_from_slice(key);
+                (atoms::customer_key(), binary.release(env)).encode(env)
+            }
+        }
+    }
+}
+
+fn key_id(key: &Binary) -> NifResult<String> {
+    String::from_utf8(key.to_vec()).map_err(|_| rustler::Error::BadArg)
+}
+
+fn config_key(key: &str) -> AmazonS3ConfigKey {
+    key.parse().expect("valid S3 config key")
+}
+
+impl ServerSideEncryption {
+    /// Value of the `x-amz-server-side-encryption` header
+    fn algorithm(&self) -> &'static str {
+        match self {
+            ServerSideEncryption::S3 => "AES256",
+            ServerSideEncryption::Kms(_) => "aws:kms",
+            ServerSideEncryption::DsseKms(_) => "aws:kms:dsse",
+            ServerSideEncryption::CustomerKey(_) => "sse-c",
+        }
+    }
+
+    /// Configure `builder` to encrypt uploads, and to send the customer key
+    /// with reads of SSE-C objects
+    pub fn apply(&self, builder: AmazonS3Builder, bucket_key: Option<bool>) -> AmazonS3Builder {
+        let mut builder =
+            builder.with_config(config_key("aws_server_side_encryption"), self.algorithm());
+
+        match self {
+            ServerSideEncryption::Kms(Some(key_id))
+            | ServerSideEncryption::DsseKms(Some(key_id)) => {
+                builder = builder.with_config(config_key("aws_sse_kms_key_id"), key_id);
+            }
+            ServerSideEncryption::CustomerKey(key) => {
+                builder = builder.with_config(
+                    config_key("aws_sse_customer_key_base64"),
+                    BASE64_STANDARD.encode(key),
+                );
+            }
+            _ => {}
+        }
+
+        match bucket_key {
+            Some(enabled) => builder.with_bucket_key(enabled),
+            None => builder,
+        }
+    }
+
+    /// Headers requesting this encryption for an object written by a request
+    pub fn headers(&self, bucket_key: Option<bool>) -> Vec<(&'static str, String)> {
+        if let ServerSideEncryption::CustomerKey(key) = self {
+            return customer_key_headers(
+                key,
+                [
+                    "x-amz-server-side-encryption-customer-algorithm",
+                    "x-amz-server-side-encryption-customer-key",
+                    "x-amz-server-side-encryption-customer-key-MD5",
+                ],
+            );
+        }
+
+        let mut headers = vec![("x-amz-server-side-encryption", self.algorithm().into())];
+        if let ServerSideEncryption::Kms(Some(key_id))
+        | ServerSideEncryption::DsseKms(Some(key_id)) = self
+        {
+            headers.push((
+                "x-amz-server-side-encryption-aws-kms-key-id",
+                key_id.clone(),
+            ));
+        }
+        if let Some(enabled) = bucket_key {
+            headers.push((
+                "x-amz-server-side-encryption-bucket-key-enabled",
+                enabled.to_string(),
+            ));
+        }
+        headers
+    }
+
+    /// Headers decrypting the source of a server-side copy
+    ///
+    /// Only SSE-C sources need them; S3 decrypts other sources itself.
+    pub fn copy_source_headers(&self) -> Vec<(&'static str, String)> {
+        match self {
+            ServerSideEncryption::CustomerKey(key) => customer_key_headers(
+                key,
+                [
+                    "x-amz-copy-source-server-side-encryption-customer-algorithm",
+                    "x-amz-copy-source-server-side-encryption-customer-key",
+                    "x-amz-copy-source-server-side-encryption-customer-key-MD5",
+                ],
+            ),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Algorithm, key and key digest headers of an SSE-C key
+fn customer_key_headers(key: &[u8], names: [&'static str; 3]) -> Vec<(&'static str, String)> {
+    vec![
+        (names[0], "AES256".to_string()),
+        (names[1], BASE64_STANDARD.encode(key)),
+        (names[2], BASE64_STANDARD.encode(Md5::digest(key))),
+    ]
+}
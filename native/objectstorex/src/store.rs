@@ -1,16 +1,129 @@
-use object_store::DynObjectStore;
+use crate::append::Append;
+use crate::checksum::ChecksumAlgorithm;
+use crate::incomplete_uploads::IncompleteUploads;
+use crate::paging::PagedListing;
+use crate::s3_api::S3Api;
+use crate::versions::Versioning;
+use crate::wrappers::cached::CacheStore;
+use crate::wrappers::mirror::MirrorStore;
+use object_store::multipart::MultipartStore;
+use object_store::{DynObjectStore, ObjectStore};
+use std::collections::HashMap;
 use std::panic::RefUnwindSafe;
-use std::sync::Arc;
+use std::sync::{Arc, RwLock};
 
 /// Wrapper around the object_store DynObjectStore trait object
 /// This is registered as a Rustler resource to be passed between Elixir and Rust
 pub struct StoreWrapper {
     pub inner: Arc<DynObjectStore>,
+    /// Low-level multipart API of the underlying client, used for resumable
+    /// upload sessions. Wrapped stores leave this unset, since uploads through
+    /// it would bypass the wrapper.
+    pub multipart: Option<Arc<dyn MultipartStore>>,
+    /// Signed S3 requests for APIs object_store doesn't expose (tagging,
+    /// conditional deletes)
+    pub s3: Option<Arc<S3Api>>,
+    /// Object version listing and deletion, for versioned providers
+    pub versioning: Option<Arc<dyn Versioning>>,
+    /// Listing and aborting incomplete multipart uploads (S3, GCS)
+    pub uploads: Option<Arc<dyn IncompleteUploads>>,
+    /// Client sending `x-amz-checksum-sha256` with every write, used by puts
+    /// that request SHA-256 checksums (S3)
+    pub checksummed: Option<Arc<DynObjectStore>>,
+    /// Listings with a caller-chosen page size (S3, GCS)
+    pub paged: Option<Arc<dyn PagedListing>>,
+    /// Cache store behind `inner`, whose write-back uploads `flush_cache`
+    /// waits for
+    pub cache: Option<Arc<CacheStore>>,
+    /// Mirror store behind `inner`, whose background replication
+    /// `flush_mirror` waits for
+    pub mirror: Option<Arc<MirrorStore>>,
+    /// Appends to objects in place (local filesystem, memory)
+    pub append: Option<Arc<dyn Append>>,
+    /// Named credential profiles registered on this store
+    pub profiles: RwLock<HashMap<String, StoreWrapper>>,
 }
 
 impl StoreWrapper {
     pub fn new(store: Arc<DynObjectStore>) -> Self {
-        Self { inner: store }
+        Self {
+            inner: store,
+            multipart: None,
+            s3: None,
+            versioning: None,
+            uploads: None,
+            checksummed: None,
+            paged: None,
+            cache: None,
+            mirror: None,
+            append: None,
+            profiles: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Wrap a client that also supports the low-level multipart API
+    pub fn with_multipart<T: ObjectStore + MultipartStore>(store: Arc<T>) -> Self {
+        Self {
+            inner: store.clone(),
+            multipart: Some(store),
+            s3: None,
+            versioning: None,
+            uploads: None,
+            checksummed: None,
+            paged: None,
+            cache: None,
+            mirror: None,
+            append: None,
+            profiles: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Handle sharing this store's client, without its profiles
+    pub fn detached(&self) -> Self {
+        Self {
+            inner: self.inner.clone(),
+            multipart: self.multipart.clone(),
+            s3: self.s3.clone(),
+            versioning: self.versioning.clone(),
+            uploads: self.uploads.clone(),
+            checksummed: self.checksummed.clone(),
+            paged: self.paged.clone(),
+            cache: self.cache.clone(),
+            mirror: self.mirror.clone(),
+            append: self.append.clone(),
+            profiles: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Handle to `store`, a wrapper that only observes calls to this store
+    ///
+    /// Unlike policy wrappers, observers keep the low-level APIs of the
+    /// underlying client; calls made through them are not observed.
+    pub fn observed(&self, store: Arc<DynObjectStore>) -> Self {
+        Self {
+            inner: store,
+            ..self.detached()
+        }
+    }
+
+    /// Store for writes computing a checksum with `algorithm`
+    ///
+    /// SHA-256 writes go through the checksummed client when the provider
+    /// verifies checksums, and through the regular client otherwise.
+    pub fn checksum_store(&self, algorithm: ChecksumAlgorithm) -> Arc<DynObjectStore> {
+        match (algorithm, &self.checksummed) {
+            (ChecksumAlgorithm::Sha256, Some(checksummed)) => checksummed.clone(),
+            _ => self.inner.clone(),
+        }
+    }
+
+    /// Look up the store registered under a profile name
+    pub fn profile(&self, name: &str) -> Option<StoreWrapper> {
+        self.profiles
+            .read()
+            .unwrap()
+            .get(name)
+            .map(StoreWrapper::detached)
     }
 }
 
@@ -1,24 +1,157 @@
 use crate::atoms;
+use crate::checksum::{ChecksumAlgorithm, Hasher};
+use crate::errors::map_error;
+use crate::list_filter::ListFilter;
+use crate::paging;
+use crate::shutdown::{self, UploadRegistration};
 use crate::store::StoreWrapper;
+use crate::types::{DownloadOptionsNif, IoDataNif, ListFilterNif, UploadStateNif};
 use crate::RUNTIME;
-use bytes::Bytes;
-use futures::StreamExt;
+use bytes::{Bytes, BytesMut};
+use flate2::write::MultiGzDecoder;
+use futures::stream::BoxStream;
+use futures::{FutureExt, StreamExt, TryStreamExt};
+use object_store::multipart::{MultipartStore, PartId};
 use object_store::path::Path;
-use object_store::{MultipartUpload, PutPayload};
-use rustler::{Binary, Encoder, Env, LocalPid, NifResult, OwnedEnv, ResourceArc, Term};
-use std::collections::HashMap;
-use std::sync::{Arc, Mutex};
-use tokio::sync::Mutex as TokioMutex;
+use object_store::{GetOptions, MultipartId, MultipartUpload, PutPayload, PutResult};
+use rustler::{
+    Atom, Binary, Encoder, Env, LocalPid, Monitor, NifResult, OwnedEnv, Resource, ResourceArc, Term,
+};
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use tokio::sync::{Mutex as TokioMutex, Semaphore};
 use tokio::task::JoinHandle;
 use uuid::Uuid;
 
+/// A running stream task together with the monitor on its receiver process
+struct StreamEntry {
+    handle: JoinHandle<()>,
+    receiver: LocalPid,
+    monitor: ResourceArc<StreamMonitor>,
+    monitor_ref: Monitor,
+    /// Chunks the receiver is willing to accept, for credit-based downloads
+    credit: Option<Arc<Semaphore>>,
+}
+
 // Type alias to reduce complexity
-type StreamRegistry = Arc<Mutex<HashMap<String, JoinHandle<()>>>>;
+type StreamRegistry = Arc<Mutex<HashMap<String, StreamEntry>>>;
 
 // Global registry to track active download streams for cancellation
 static STREAM_REGISTRY: once_cell::sync::Lazy<StreamRegistry> =
     once_cell::sync::Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
 
+/// Resource used to monitor the process receiving a stream's messages
+///
+/// When the receiver exits, the `down` callback removes the stream from its
+/// registry and aborts the spawned task, so downloads and listings don't keep
+/// running on behalf of a dead process.
+pub struct StreamMonitor {
+    stream_id: String,
+    registry: &'static once_cell::sync::Lazy<StreamRegistry>,
+}
+
+impl Resource for StreamMonitor {
+    const IMPLEMENTS_DOWN: bool = true;
+
+    fn down<'a>(&'a self, _env: Env<'a>, _pid: LocalPid, _monitor: Monitor) {
+        cancel_stream(self.registry, &self.stream_id);
+    }
+}
+
+/// Spawn a stream task, register it and monitor its receiver process
+///
+/// The task removes its own entry when it finishes. It only starts once its
+/// entry is inserted, so that removal can't run before the insert, without
+/// spawning while the registry is locked. If the receiver is already dead,
+/// the task isn't spawned at all.
+fn spawn_stream<F>(
+    env: Env,
+    registry: &'static once_cell::sync::Lazy<StreamRegistry>,
+    stream_id: &str,
+    receiver_pid: &LocalPid,
+    credit: Option<Arc<Semaphore>>,
+    task: F,
+) where
+    F: std::future::Future<Output = ()> + Send + 'static,
+{
+    let monitor = ResourceArc::new(StreamMonitor {
+        stream_id: stream_id.to_string(),
+        registry,
+    });
+
+    let Some(monitor_ref) = env.monitor(&monitor, receiver_pid) else {
+        return;
+    };
+
+    let (registered, on_registered) = tokio::sync::oneshot::channel::<()>();
+    let id = stream_id.to_string();
+    let handle = RUNTIME.spawn(async move {
+        if on_registered.await.is_ok() {
+            task.await;
+            remove_stream(registry, &id);
+        }
+    });
+    registry.lock().unwrap().insert(
+        stream_id.to_string(),
+        StreamEntry {
+            handle,
+            receiver: *receiver_pid,
+            monitor,
+            monitor_ref,
+            credit,
+        },
+    );
+    let _ = registered.send(());
+}
+
+/// Remove a stream from its registry and stop monitoring its receiver
+fn remove_stream(
+    registry: &once_cell::sync::Lazy<StreamRegistry>,
+    stream_id: &str,
+) -> Option<StreamEntry> {
+    let entry = registry.lock().unwrap().remove(stream_id)?;
+    entry.monitor.demonitor(None, &entry.monitor_ref);
+    Some(entry)
+}
+
+/// Remove a stream from its registry and abort its task
+fn cancel_stream(registry: &once_cell::sync::Lazy<StreamRegistry>, stream_id: &str) {
+    if let Some(entry) = remove_stream(registry, stream_id) {
+        entry.handle.abort();
+    }
+}
+
+/// Let running downloads and listings finish until `deadline`, then abort
+/// the rest and send their receivers an error
+pub(crate) async fn drain_streams(deadline: tokio::time::Instant) {
+    let entries: Vec<(String, StreamEntry)> = [&STREAM_REGISTRY, &LIST_REGISTRY]
+        .into_iter()
+        .flat_map(|registry| registry.lock().unwrap().drain().collect::<Vec<_>>())
+        .collect();
+
+    futures::future::join_all(
+        entries
+            .into_iter()
+            .map(|(stream_id, mut entry)| async move {
+                entry.monitor.demonitor(None, &entry.monitor_ref);
+                if tokio::time::timeout_at(deadline, &mut entry.handle)
+                    .await
+                    .is_err()
+                {
+                    entry.handle.abort();
+                    send_error(
+                        &entry.receiver,
+                        &stream_id,
+                        "ObjectStoreX is shutting down".to_string(),
+                    );
+                }
+            }),
+    )
+    .await;
+}
+
 /// Start a download stream that sends chunks to the receiver process
 #[rustler::nif]
 pub fn start_download_stream<'a>(
@@ -26,24 +159,164 @@ pub fn start_download_stream<'a>(
     store: ResourceArc<StoreWrapper>,
     path: String,
     receiver_pid: LocalPid,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    spawn_download(
+        env,
+        store,
+        path,
+        receiver_pid,
+        None,
+        DownloadOptions::default(),
+    )
+}
+
+/// Start a download stream that only sends chunks the receiver has credit for
+///
+/// The receiver starts with `credit` chunks and grants more with
+/// `grant_download_credit/2` as it finishes writing them, so a slow sink
+/// holds back the download instead of filling its mailbox.
+#[rustler::nif]
+pub fn start_credit_download_stream<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+    receiver_pid: LocalPid,
+    credit: usize,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let credit = Arc::new(Semaphore::new(credit));
+    spawn_download(
+        env,
+        store,
+        path,
+        receiver_pid,
+        Some(credit),
+        DownloadOptions::default(),
+    )
+}
+
+/// Start a download stream with options
+///
+/// With `credit`, chunks are sent as by `start_credit_download_stream`. With
+/// `decompress: :gzip`, the object is decoded as gzip, including files of
+/// several concatenated gzip members. With `frame_size`, the (decompressed)
+/// data is sent in chunks of exactly that many bytes; only the last chunk may
+/// be smaller. With `if_none_match` or `if_modified_since`, an unchanged
+/// object is not downloaded and `{:not_modified, stream_id}` is sent instead
+/// of chunks.
+#[rustler::nif]
+pub fn start_download_stream_with_options<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+    receiver_pid: LocalPid,
+    options: DownloadOptionsNif,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let gunzip = match options.decompress {
+        None => false,
+        Some(format) if format == atoms::gzip() => true,
+        Some(_) => return Err(rustler::Error::BadArg),
+    };
+    if options.frame_size == Some(0) {
+        return Err(rustler::Error::BadArg);
+    }
+
+    let credit = options
+        .credit
+        .map(|credit| Arc::new(Semaphore::new(credit)));
+    let options = DownloadOptions {
+        get: GetOptions {
+            if_none_match: options.if_none_match,
+            if_modified_since: options.if_modified_since.map(|timestamp| timestamp.0),
+            ..Default::default()
+        },
+        gunzip,
+        frame_size: options.frame_size,
+    };
+    spawn_download(env, store, path, receiver_pid, credit, options)
+}
+
+/// Grant a credit-based download stream permission to send more chunks
+///
+/// Unknown or finished streams are ignored.
+#[rustler::nif]
+pub fn grant_download_credit<'a>(
+    env: Env<'a>,
+    stream_id: String,
+    credit: usize,
+) -> NifResult<Term<'a>> {
+    let registry = STREAM_REGISTRY.lock().unwrap();
+    if let Some(semaphore) = registry.get(&stream_id).and_then(|e| e.credit.as_ref()) {
+        semaphore.add_permits(credit);
+    }
+
+    Ok(atoms::ok().encode(env))
+}
+
+/// How a download stream fetches and processes its object
+#[derive(Debug, Default)]
+struct DownloadOptions {
+    /// Conditions of the get request
+    get: GetOptions,
+    /// Decode the data as (multi-member) gzip
+    gunzip: bool,
+    /// Re-chunk the data into frames of this many bytes
+    frame_size: Option<usize>,
+}
+
+impl DownloadOptions {
+    /// Apply the decoding and framing to the object's data
+    fn apply(
+        &self,
+        mut stream: BoxStream<'static, object_store::Result<Bytes>>,
+    ) -> BoxStream<'static, object_store::Result<Bytes>> {
+        if self.gunzip {
+            stream = gunzip(stream);
+        }
+        if let Some(frame_size) = self.frame_size {
+            stream = frames(stream, frame_size);
+        }
+        stream
+    }
+}
+
+/// Spawn a download task, waiting for credit before each chunk if given
+fn spawn_download<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+    receiver_pid: LocalPid,
+    credit: Option<Arc<Semaphore>>,
+    options: DownloadOptions,
 ) -> NifResult<Term<'a>> {
     let stream_id = Uuid::new_v4().to_string();
     let stream_id_clone = stream_id.clone();
     let store = store.inner.clone();
     let path_obj = Path::from(path);
+    let task_credit = credit.clone();
 
-    // Spawn async task to stream chunks
-    let handle = RUNTIME.spawn(async move {
-        let result = store.get(&path_obj).await;
+    // Async task to stream chunks
+    let task = async move {
+        let result = store.get_opts(&path_obj, options.get.clone()).await;
 
         match result {
             Ok(get_result) => {
-                let mut stream = get_result.into_stream();
+                let mut stream = options.apply(get_result.into_stream());
 
                 // Stream chunks to Elixir process
                 while let Some(chunk_result) = stream.next().await {
                     match chunk_result {
                         Ok(bytes) => {
+                            // Wait until the receiver has written earlier chunks
+                            if let Some(credit) = &task_credit {
+                                match credit.acquire().await {
+                                    Ok(permit) => permit.forget(),
+                                    Err(_) => return,
+                                }
+                            }
+
                             // Send chunk message to Elixir process
                             if !send_chunk(&receiver_pid, &stream_id_clone, bytes) {
                                 // If send fails, process is dead, stop streaming
@@ -60,42 +333,119 @@ pub fn start_download_stream<'a>(
                 // Send completion message
                 send_done(&receiver_pid, &stream_id_clone);
             }
+            Err(object_store::Error::NotModified { .. }) => {
+                send_not_modified(&receiver_pid, &stream_id_clone);
+            }
             Err(e) => {
                 send_error(&receiver_pid, &stream_id_clone, format!("{}", e));
             }
         }
-    });
+    };
 
-    // Register the task handle for cancellation
-    {
-        let mut registry = STREAM_REGISTRY.lock().unwrap();
-        registry.insert(stream_id.clone(), handle);
-    }
+    // Spawn the task, registered for cancellation, and monitor the receiver
+    spawn_stream(
+        env,
+        &STREAM_REGISTRY,
+        &stream_id,
+        &receiver_pid,
+        credit,
+        task,
+    );
 
     // Return {:ok, stream_id}
     Ok((atoms::ok(), stream_id).encode(env))
 }
 
+/// Decode a stream of gzip data into chunks of decompressed data
+///
+/// Handles files of several concatenated gzip members, as written by log
+/// shippers that append a member per batch, by starting a new member where
+/// the previous one ends. Truncated or corrupt data ends the stream with an
+/// error.
+fn gunzip(
+    stream: BoxStream<'static, object_store::Result<Bytes>>,
+) -> BoxStream<'static, object_store::Result<Bytes>> {
+    let mut decoder = MultiGzDecoder::new(Vec::new());
+
+    stream
+        .map(Some)
+        .chain(futures::stream::once(async { None }))
+        .flat_map(move |item| {
+            let decoded = match item {
+                Some(Ok(bytes)) => decoder.write_all(&bytes).and_then(|_| decoder.flush()),
+                Some(Err(e)) => return futures::stream::iter(vec![Err(e)]),
+                None => decoder.try_finish(),
+            };
+
+            let out = match decoded {
+                Ok(()) if decoder.get_ref().is_empty() => vec![],
+                Ok(()) => vec![Ok(Bytes::from(std::mem::take(decoder.get_mut())))],
+                Err(e) => vec![Err(object_store::Error::Generic {
+                    store: "Gzip",
+                    source: Box::new(e),
+                })],
+            };
+            futures::stream::iter(out)
+        })
+        .boxed()
+}
+
+/// Re-chunk a byte stream into frames of exactly `frame_size` bytes
+///
+/// The last frame holds the remainder and may be smaller. Frames within a
+/// single source chunk are slices of it; only frames spanning chunks are
+/// copied.
+fn frames(
+    stream: BoxStream<'static, object_store::Result<Bytes>>,
+    frame_size: usize,
+) -> BoxStream<'static, object_store::Result<Bytes>> {
+    let mut buffer = BytesMut::new();
+
+    stream
+        .map(Some)
+        .chain(futures::stream::once(async { None }))
+        .flat_map(move |item| {
+            let mut out = Vec::new();
+
+            match item {
+                Some(Ok(mut bytes)) => {
+                    if !buffer.is_empty() {
+                        let needed = (frame_size - buffer.len()).min(bytes.len());
+                        buffer.extend_from_slice(&bytes.split_to(needed));
+                        if buffer.len() == frame_size {
+                            out.push(Ok(buffer.split().freeze()));
+                        }
+                    }
+                    while bytes.len() >= frame_size {
+                        out.push(Ok(bytes.split_to(frame_size)));
+                    }
+                    buffer.extend_from_slice(&bytes);
+                }
+                Some(Err(e)) => out.push(Err(e)),
+                None if !buffer.is_empty() => out.push(Ok(buffer.split().freeze())),
+                None => {}
+            }
+
+            futures::stream::iter(out)
+        })
+        .boxed()
+}
+
 /// Cancel an active download stream
 #[rustler::nif]
 pub fn cancel_download_stream<'a>(env: Env<'a>, stream_id: String) -> NifResult<Term<'a>> {
-    let handle_opt = {
-        let mut registry = STREAM_REGISTRY.lock().unwrap();
-        registry.remove(&stream_id)
-    };
-
-    if let Some(handle) = handle_opt {
-        handle.abort();
-    }
+    cancel_stream(&STREAM_REGISTRY, &stream_id);
 
     Ok(atoms::ok().encode(env))
 }
 
 // Helper function to send chunk message to Elixir process
+//
+// Returns false when the receiver is no longer alive.
 fn send_chunk(receiver_pid: &LocalPid, stream_id: &str, bytes: Bytes) -> bool {
     let mut env = OwnedEnv::new();
 
-    let _ = env.send_and_clear(receiver_pid, |env| {
+    env.send_and_clear(receiver_pid, |env| {
         let chunk_atom = atoms::chunk().encode(env);
         let id_term = stream_id.encode(env);
 
@@ -105,9 +455,8 @@ fn send_chunk(receiver_pid: &LocalPid, stream_id: &str, bytes: Bytes) -> bool {
         let data = binary.release(env);
 
         (chunk_atom, id_term, data).encode(env)
-    });
-
-    true
+    })
+    .is_ok()
 }
 
 // Helper function to send done message to Elixir process
@@ -121,6 +470,17 @@ fn send_done(receiver_pid: &LocalPid, stream_id: &str) {
     });
 }
 
+// Helper function to send not_modified message to Elixir process
+fn send_not_modified(receiver_pid: &LocalPid, stream_id: &str) {
+    let mut env = OwnedEnv::new();
+
+    let _ = env.send_and_clear(receiver_pid, |env| {
+        let not_modified_atom = atoms::not_modified().encode(env);
+        let id_term = stream_id.encode(env);
+        (not_modified_atom, id_term).encode(env)
+    });
+}
+
 // Helper function to send error message to Elixir process
 fn send_error(receiver_pid: &LocalPid, stream_id: &str, error_msg: String) {
     let mut env = OwnedEnv::new();
@@ -140,167 +500,765 @@ fn send_error(receiver_pid: &LocalPid, stream_id: &str, error_msg: String) {
 /// Wrapper for multipart upload session
 pub struct UploadSessionWrapper {
     _session_id: String,
-    multipart: Arc<TokioMutex<Box<dyn MultipartUpload>>>,
-    buffer: Arc<Mutex<Vec<u8>>>,
+    multipart: Arc<TokioMutex<SessionUpload>>,
+    buffer: Mutex<PartBuffer>,
     part_size: usize,
+    /// Digest of the data written so far, for sessions started with a checksum
+    hasher: Mutex<Option<Hasher>>,
+    /// Aborts the upload on shutdown until it is completed or aborted
+    registration: Mutex<Option<UploadRegistration>>,
+    /// Set once the session's state is saved or restored, so the upload can be
+    /// resumed after the session is dropped
+    resumable: AtomicBool,
+    /// Upload id and progress of resumable sessions, read by
+    /// `upload_session_state` without waiting for a part upload to finish
+    progress: Option<(MultipartId, Arc<Mutex<UploadProgress>>)>,
+}
+
+/// Data written to an upload session that isn't part of an uploaded part yet
+///
+/// Chunks are kept as separate segments and handed to the part payload as
+/// they are, so filling a part copies each chunk only once, out of the
+/// Erlang binary.
+#[derive(Default)]
+struct PartBuffer {
+    segments: Vec<Bytes>,
+    len: usize,
+}
+
+impl PartBuffer {
+    fn push(&mut self, chunk: Bytes) {
+        self.len += chunk.len();
+        self.segments.push(chunk);
+    }
+
+    /// Take the buffered data as a part payload, leaving the buffer empty
+    fn take(&mut self) -> PutPayload {
+        self.len = 0;
+        std::mem::take(&mut self.segments).into_iter().collect()
+    }
+}
+
+/// Multipart upload backing an upload session
+enum SessionUpload {
+    /// Upload through the store's `put_multipart` API
+    Streaming(Box<dyn MultipartUpload>),
+    /// Upload through the low-level multipart API, tracking completed parts
+    /// so the session can be resumed
+    Resumable(ResumableUpload),
+}
+
+struct ResumableUpload {
+    store: Arc<dyn MultipartStore>,
+    path: Path,
+    upload_id: MultipartId,
+    progress: Arc<Mutex<UploadProgress>>,
+}
+
+/// Parts of a resumable upload completed so far
+///
+/// Parts are only recorded once uploaded, so `bytes_uploaded` always ends on
+/// the boundary of a chunk passed to `upload_chunk`.
+#[derive(Default)]
+struct UploadProgress {
+    parts: Vec<PartId>,
+    bytes_uploaded: u64,
+}
+
+impl SessionUpload {
+    async fn put_part(&mut self, payload: PutPayload) -> object_store::Result<()> {
+        match self {
+            SessionUpload::Streaming(upload) => upload.put_part(payload).await,
+            SessionUpload::Resumable(upload) => {
+                let len = payload.content_length() as u64;
+                // Parts are put one at a time, under the session's upload lock
+                let index = upload.progress.lock().unwrap().parts.len();
+                let part = upload
+                    .store
+                    .put_part(&upload.path, &upload.upload_id, index, payload)
+                    .await?;
+                let mut progress = upload.progress.lock().unwrap();
+                progress.parts.push(part);
+                progress.bytes_uploaded += len;
+                Ok(())
+            }
+        }
+    }
+
+    async fn complete(&mut self) -> object_store::Result<PutResult> {
+        match self {
+            SessionUpload::Streaming(upload) => upload.complete().await,
+            SessionUpload::Resumable(upload) => {
+                let parts = upload.progress.lock().unwrap().parts.clone();
+                upload
+                    .store
+                    .complete_multipart(&upload.path, &upload.upload_id, parts)
+                    .await
+            }
+        }
+    }
+
+    async fn abort(&mut self) -> object_store::Result<()> {
+        match self {
+            SessionUpload::Streaming(upload) => upload.abort().await,
+            SessionUpload::Resumable(upload) => {
+                upload
+                    .store
+                    .abort_multipart(&upload.path, &upload.upload_id)
+                    .await
+            }
+        }
+    }
+}
+
+impl UploadSessionWrapper {
+    fn new(multipart: SessionUpload) -> Self {
+        let progress = match &multipart {
+            SessionUpload::Resumable(upload) => {
+                Some((upload.upload_id.clone(), upload.progress.clone()))
+            }
+            SessionUpload::Streaming(_) => None,
+        };
+        let resumable = progress.is_some();
+        let multipart = Arc::new(TokioMutex::new(multipart));
+        let upload = Arc::downgrade(&multipart);
+        let registration = shutdown::register_upload(resumable, move || {
+            let upload = upload.upgrade()?;
+            Some(
+                async move {
+                    let _ = upload.lock().await.abort().await;
+                }
+                .boxed(),
+            )
+        });
+
+        Self {
+            _session_id: Uuid::new_v4().to_string(),
+            multipart,
+            buffer: Mutex::new(PartBuffer::default()),
+            part_size: 5 * 1024 * 1024, // 5MB minimum part size
+            hasher: Mutex::new(None),
+            registration: Mutex::new(Some(registration)),
+            resumable: AtomicBool::new(false),
+            progress,
+        }
+    }
+
+    /// The buffered data of the session
+    ///
+    /// The buffer is consistent after every operation on it, so it stays
+    /// usable even if a thread panicked while holding it.
+    fn buffer(&self) -> MutexGuard<'_, PartBuffer> {
+        self.buffer.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Stop aborting the upload on shutdown, once it is finished
+    fn finished(&self) {
+        self.registration.lock().unwrap().take();
+    }
+}
+
+/// Abort the upload of a session dropped before it was completed or aborted,
+/// e.g. because the process holding it crashed
+///
+/// Sessions whose state was saved with `upload_session_state/1`, or that were
+/// resumed from one, are left alone so they can still be resumed. After
+/// `shutdown` the upload was already taken care of.
+impl Drop for UploadSessionWrapper {
+    fn drop(&mut self) {
+        let unfinished = self.registration.get_mut().unwrap().take().is_some();
+        if !unfinished || *self.resumable.get_mut() {
+            return;
+        }
+
+        let multipart = self.multipart.clone();
+        RUNTIME.try_spawn(async move {
+            let _ = multipart.lock().await.abort().await;
+        });
+    }
 }
 
 /// Start a new multipart upload session
+///
+/// Returns `{:ok, session}` or an error atom. The upload goes through the
+/// store's wrappers (tracing, rate limits, quotas, deadlines, ...); sessions
+/// that can be resumed are started with `start_resumable_upload_session/2`.
 #[rustler::nif(schedule = "DirtyCpu")]
 pub fn start_upload_session<'a>(
     env: Env<'a>,
     store: ResourceArc<StoreWrapper>,
     path: String,
 ) -> NifResult<Term<'a>> {
-    let session_id = Uuid::new_v4().to_string();
+    shutdown::ensure_running()?;
     let path_obj = Path::from(path);
 
-    // Initialize multipart upload
-    let multipart = RUNTIME
-        .block_on(async { store.inner.put_multipart(&path_obj).await })
-        .map_err(|e| {
-            rustler::Error::Term(Box::new(format!(
-                "Failed to initialize multipart upload: {}",
-                e
-            )))
-        })?;
-
-    let session = UploadSessionWrapper {
-        _session_id: session_id.clone(),
-        multipart: Arc::new(TokioMutex::new(multipart)),
-        buffer: Arc::new(Mutex::new(Vec::new())),
-        part_size: 5 * 1024 * 1024, // 5MB minimum part size
+    let multipart = match RUNTIME.block_on(store.inner.put_multipart(&path_obj)) {
+        Ok(upload) => SessionUpload::Streaming(upload),
+        Err(e) => return Ok(map_error(e).to_term(env)),
     };
 
-    let resource = ResourceArc::new(session);
+    let resource = ResourceArc::new(UploadSessionWrapper::new(multipart));
 
     // Return {:ok, resource}
     Ok((atoms::ok(), resource).encode(env))
 }
 
+/// Start a multipart upload session whose state can be saved and resumed
+///
+/// Returns `{:ok, session}`, an error atom, or `:not_supported` unless the
+/// store was built with an S3, Azure, GCS or memory backend. The upload uses
+/// the provider's low-level multipart API, see `upload_session_state/1`.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn start_resumable_upload_session<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let Some(multipart_store) = store.multipart.clone() else {
+        return Ok(atoms::not_supported().to_term(env));
+    };
+    let path_obj = Path::from(path);
+
+    let upload_id = match RUNTIME.block_on(multipart_store.create_multipart(&path_obj)) {
+        Ok(upload_id) => upload_id,
+        Err(e) => return Ok(map_error(e).to_term(env)),
+    };
+    let multipart = SessionUpload::Resumable(ResumableUpload {
+        store: multipart_store,
+        path: path_obj,
+        upload_id,
+        progress: Arc::default(),
+    });
+
+    let resource = ResourceArc::new(UploadSessionWrapper::new(multipart));
+    Ok((atoms::ok(), resource).encode(env))
+}
+
+/// Start a multipart upload session that computes a checksum of its data
+///
+/// SHA-256 checksums are also sent with every part to providers that verify
+/// them (S3). `complete_upload/1` then returns the hex-encoded digest as a
+/// fourth element. These sessions can't be resumed, since the digest of
+/// earlier parts can't be restored.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn start_checksum_upload_session<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+    algorithm: Atom,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let algorithm = ChecksumAlgorithm::from_atom(algorithm)?;
+    let target = store.checksum_store(algorithm);
+
+    let upload = match RUNTIME.block_on(async { target.put_multipart(&Path::from(path)).await }) {
+        Ok(upload) => upload,
+        Err(e) => return Ok(map_error(e).to_term(env)),
+    };
+
+    let session = UploadSessionWrapper::new(SessionUpload::Streaming(upload));
+    *session.hasher.lock().unwrap() = Some(Hasher::new(algorithm));
+
+    Ok((atoms::ok(), ResourceArc::new(session)).encode(env))
+}
+
+/// Resume a multipart upload session from state saved with `upload_session_state/1`
+///
+/// Returns `{:ok, session}`, or `:not_supported` if the store has no
+/// resumable multipart API. Data is appended after the parts recorded in
+/// the state, so the caller must continue exactly from `bytes_uploaded`,
+/// re-sending any data it wrote after the last recorded part.
+#[rustler::nif]
+pub fn resume_upload_session<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+    state: UploadStateNif,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let Some(multipart_store) = store.multipart.clone() else {
+        return Ok(atoms::not_supported().to_term(env));
+    };
+
+    let upload = ResumableUpload {
+        store: multipart_store,
+        path: Path::from(path),
+        upload_id: state.upload_id,
+        progress: Arc::new(Mutex::new(UploadProgress {
+            parts: state
+                .parts
+                .into_iter()
+                .map(|content_id| PartId { content_id })
+                .collect(),
+            bytes_uploaded: state.bytes_uploaded,
+        })),
+    };
+
+    let session = UploadSessionWrapper::new(SessionUpload::Resumable(upload));
+    session.resumable.store(true, Ordering::Relaxed);
+    Ok((atoms::ok(), ResourceArc::new(session)).encode(env))
+}
+
+/// Serializable state of an upload session
+///
+/// Returns `%{upload_id: id, parts: [part_id], bytes_uploaded: n}` covering the
+/// parts uploaded so far, or `:not_supported` for sessions that can't be
+/// resumed. Data still buffered in the session is not included: it is lost
+/// with the session and has to be written again after resuming. A part being
+/// uploaded is only included once it is done. Once its state is saved,
+/// dropping the session no longer aborts the upload.
+#[rustler::nif]
+pub fn upload_session_state<'a>(
+    env: Env<'a>,
+    session: ResourceArc<UploadSessionWrapper>,
+) -> NifResult<Term<'a>> {
+    let Some((upload_id, progress)) = &session.progress else {
+        return Ok(atoms::not_supported().to_term(env));
+    };
+
+    session.resumable.store(true, Ordering::Relaxed);
+    let progress = progress.lock().unwrap();
+    Ok(UploadStateNif {
+        upload_id: upload_id.clone(),
+        parts: progress
+            .parts
+            .iter()
+            .map(|part| part.content_id.clone())
+            .collect(),
+        bytes_uploaded: progress.bytes_uploaded,
+    }
+    .encode(env))
+}
+
 /// Upload a chunk of data to the multipart upload session
+///
+/// Returns `:ok`, or an error atom if uploading a completed part failed.
 #[rustler::nif(schedule = "DirtyCpu")]
 pub fn upload_chunk<'a>(
     env: Env<'a>,
     session: ResourceArc<UploadSessionWrapper>,
-    chunk: Binary,
+    chunk: IoDataNif,
 ) -> NifResult<Term<'a>> {
-    // Append chunk to buffer
-    {
-        let mut buffer = session
-            .buffer
-            .lock()
-            .map_err(|e| rustler::Error::Term(Box::new(format!("Buffer lock error: {}", e))))?;
-        buffer.extend_from_slice(chunk.as_slice());
+    shutdown::ensure_running()?;
+    if let Some(hasher) = session.hasher.lock().unwrap().as_mut() {
+        chunk.segments().for_each(|segment| hasher.update(segment));
     }
 
-    // Check if we need to upload a part
-    let should_upload = {
-        let buffer = session
-            .buffer
-            .lock()
-            .map_err(|e| rustler::Error::Term(Box::new(format!("Buffer lock error: {}", e))))?;
-        buffer.len() >= session.part_size
+    // Append the chunk, taking a full part out of the buffer once there is one
+    let part = {
+        let mut buffer = session.buffer();
+        chunk.0.into_iter().for_each(|segment| buffer.push(segment));
+        (buffer.len >= session.part_size).then(|| buffer.take())
     };
 
-    if should_upload {
-        // Extract data from buffer
-        let data = {
-            let mut buffer = session
-                .buffer
-                .lock()
-                .map_err(|e| rustler::Error::Term(Box::new(format!("Buffer lock error: {}", e))))?;
-            buffer.drain(..).collect::<Vec<u8>>()
-        };
-
+    if let Some(payload) = part {
         // Upload the part
-        let payload = PutPayload::from(data);
         let multipart_clone = session.multipart.clone();
 
-        RUNTIME
-            .block_on(async move {
-                let mut multipart = multipart_clone.lock().await;
-                multipart.put_part(payload).await
-            })
-            .map_err(|e| rustler::Error::Term(Box::new(format!("Failed to upload part: {}", e))))?;
+        let result = RUNTIME.block_on(async move {
+            let mut multipart = multipart_clone.lock().await;
+            multipart.put_part(payload).await
+        });
+        if let Err(e) = result {
+            return Ok(map_error(e).to_term(env));
+        }
     }
 
     Ok(atoms::ok().encode(env))
 }
 
 /// Complete the multipart upload
+///
+/// Returns `{:ok, etag, version}`, or `{:ok, etag, version, checksum}` for
+/// sessions started with a checksum; missing identifiers are empty strings.
+/// Failures return an error atom and leave the upload to be aborted.
 #[rustler::nif(schedule = "DirtyCpu")]
 pub fn complete_upload<'a>(
     env: Env<'a>,
     session: ResourceArc<UploadSessionWrapper>,
 ) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
     // Upload any remaining data in the buffer as the final part
-    let remaining_data = {
-        let mut buffer = session
-            .buffer
-            .lock()
-            .map_err(|e| rustler::Error::Term(Box::new(format!("Buffer lock error: {}", e))))?;
-        buffer.drain(..).collect::<Vec<u8>>()
-    };
-
-    // Upload final part if there's remaining data
-    if !remaining_data.is_empty() {
-        let payload = PutPayload::from(remaining_data);
-        let multipart_clone = session.multipart.clone();
+    let payload = session.buffer().take();
 
-        RUNTIME
-            .block_on(async move {
-                let mut multipart = multipart_clone.lock().await;
-                multipart.put_part(payload).await
-            })
-            .map_err(|e| {
-                rustler::Error::Term(Box::new(format!("Failed to upload final part: {}", e)))
-            })?;
-    }
-
-    // Complete the multipart upload
     let multipart_clone = session.multipart.clone();
-    RUNTIME
-        .block_on(async move {
-            let mut multipart = multipart_clone.lock().await;
-            multipart.complete().await
-        })
-        .map_err(|e| rustler::Error::Term(Box::new(format!("Failed to complete upload: {}", e))))?;
+    let result = RUNTIME.block_on(async move {
+        let mut multipart = multipart_clone.lock().await;
+        // Upload final part if there's remaining data
+        if payload.content_length() > 0 {
+            multipart.put_part(payload).await?;
+        }
+        multipart.complete().await
+    });
+    let put_result = match result {
+        Ok(put_result) => put_result,
+        Err(e) => return Ok(map_error(e).to_term(env)),
+    };
+    session.finished();
 
-    Ok(atoms::ok().encode(env))
+    // Return {:ok, etag, version}, plus the checksum if one was requested
+    let etag = put_result.e_tag.unwrap_or_default();
+    let version = put_result.version.unwrap_or_default();
+    match session.hasher.lock().unwrap().take() {
+        Some(hasher) => Ok((atoms::ok(), etag, version, hasher.finish()).encode(env)),
+        None => Ok((atoms::ok(), etag, version).encode(env)),
+    }
 }
 
 /// Abort the multipart upload
+///
+/// Returns `:ok` or an error atom.
 #[rustler::nif(schedule = "DirtyCpu")]
 pub fn abort_upload<'a>(
     env: Env<'a>,
     session: ResourceArc<UploadSessionWrapper>,
 ) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
     let multipart_clone = session.multipart.clone();
 
-    RUNTIME
-        .block_on(async move {
-            let mut multipart = multipart_clone.lock().await;
-            multipart.abort().await
-        })
-        .map_err(|e| rustler::Error::Term(Box::new(format!("Failed to abort upload: {}", e))))?;
+    let result = RUNTIME.block_on(async move {
+        let mut multipart = multipart_clone.lock().await;
+        multipart.abort().await
+    });
+    if let Err(e) = result {
+        return Ok(map_error(e).to_term(env));
+    }
+    session.finished();
 
     Ok(atoms::ok().encode(env))
 }
 
 // ============================================================================
-// List Operations (Streaming)
+// Push-style Upload Streaming
 // ============================================================================
 
-// Type alias for list stream registry
-type ListRegistry = Arc<Mutex<HashMap<String, JoinHandle<()>>>>;
+/// Commands sent from the upload stream NIFs to the upload task
+enum UploadCommand {
+    Chunk(Bytes),
+    Finish,
+}
+
+/// Chunks queued for a push-style upload before writers wait for it
+const UPLOAD_QUEUE_CHUNKS: usize = 8;
+
+/// Handle to a push-style upload driven by the async runtime
+///
+/// Chunks are handed to the upload task over a bounded channel, so writers
+/// are only turned away when the upload falls behind. Dropping the handle
+/// without finishing (e.g. when the owning process dies) aborts the multipart
+/// upload.
+pub struct UploadStreamWrapper {
+    id: String,
+    sender: tokio::sync::mpsc::Sender<UploadCommand>,
+    /// Aborts the upload, bypassing the chunks still queued
+    abort: Arc<tokio::sync::Notify>,
+}
+
+/// Start a push-style multipart upload
+///
+/// The upload task sends `{:part_uploaded, upload_id, part_number}` to the
+/// receiver after each part, then `{:upload_done, upload_id, etag, version}`
+/// or `{:upload_error, upload_id, reason}` when the upload ends, including
+/// when it is aborted.
+#[rustler::nif]
+pub fn start_upload_stream<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+    receiver_pid: LocalPid,
+    part_size: usize,
+    max_concurrency: usize,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let upload_id = Uuid::new_v4().to_string();
+    let (sender, receiver) = tokio::sync::mpsc::channel(UPLOAD_QUEUE_CHUNKS);
+    let abort = Arc::new(tokio::sync::Notify::new());
+    let store = store.inner.clone();
+    let path_obj = Path::from(path);
+    let id = upload_id.clone();
+
+    // Ask the task to abort on shutdown, and wait until it has
+    let commands = sender.downgrade();
+    let shutdown_abort = abort.clone();
+    let registration = shutdown::register_upload(false, move || {
+        let sender = commands.upgrade()?;
+        shutdown_abort.notify_one();
+        Some(async move { sender.closed().await }.boxed())
+    });
+
+    let task_abort = abort.clone();
+    RUNTIME.spawn(async move {
+        let _registration = registration;
+        let result = run_upload_stream(
+            store,
+            path_obj,
+            receiver,
+            &task_abort,
+            &receiver_pid,
+            &id,
+            part_size.max(1),
+            max_concurrency.max(1),
+        )
+        .await;
+
+        match result {
+            Ok(Some(put_result)) => send_upload_done(&receiver_pid, &id, put_result),
+            Ok(None) => send_upload_error(&receiver_pid, &id, "upload aborted".to_string()),
+            Err(e) => send_upload_error(&receiver_pid, &id, format!("{}", e)),
+        }
+    });
+
+    let resource = ResourceArc::new(UploadStreamWrapper {
+        id: upload_id.clone(),
+        sender,
+        abort,
+    });
+
+    Ok((atoms::ok(), upload_id, resource).encode(env))
+}
+
+/// Queue a command for a push-style upload without waiting
+///
+/// Returns `:busy` when the queue is full, i.e. while the upload is behind by
+/// `max_concurrency` parts plus the queued chunks, and sends the calling
+/// process `{:upload_ready, upload_id}` once the command can be retried.
+/// Returns `:closed` if the upload has already finished, failed or been
+/// aborted.
+fn queue_upload_command<'a>(
+    env: Env<'a>,
+    session: &UploadStreamWrapper,
+    command: UploadCommand,
+) -> Term<'a> {
+    use tokio::sync::mpsc::error::TrySendError;
+
+    match session.sender.try_send(command) {
+        Ok(()) => atoms::ok().encode(env),
+        Err(TrySendError::Closed(_)) => atoms::closed().encode(env),
+        Err(TrySendError::Full(_)) => {
+            let sender = session.sender.clone();
+            let caller = env.pid();
+            let id = session.id.clone();
+            RUNTIME.spawn(async move {
+                // A closed upload is ready too: the retry reports it
+                drop(sender.reserve().await);
+                drop(sender);
+                let mut env = OwnedEnv::new();
+                let _ = env.send_and_clear(&caller, |env| {
+                    (atoms::upload_ready(), id.as_str()).encode(env)
+                });
+            });
+            atoms::busy().encode(env)
+        }
+    }
+}
+
+/// Queue a chunk for a push-style upload
+///
+/// Returns `:ok`, `:busy` or `:closed` (see `queue_upload_command`).
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn write_upload_stream<'a>(
+    env: Env<'a>,
+    session: ResourceArc<UploadStreamWrapper>,
+    chunk: Binary,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let bytes = Bytes::copy_from_slice(chunk.as_slice());
+    Ok(queue_upload_command(
+        env,
+        &session,
+        UploadCommand::Chunk(bytes),
+    ))
+}
+
+/// Ask a push-style upload to upload its remaining data and complete
+///
+/// Returns `:ok`, `:busy` or `:closed` (see `queue_upload_command`).
+#[rustler::nif]
+pub fn finish_upload_stream<'a>(
+    env: Env<'a>,
+    session: ResourceArc<UploadStreamWrapper>,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    Ok(queue_upload_command(env, &session, UploadCommand::Finish))
+}
+
+/// Abort a push-style upload, discarding all uploaded parts
+#[rustler::nif]
+pub fn abort_upload_stream<'a>(
+    env: Env<'a>,
+    session: ResourceArc<UploadStreamWrapper>,
+) -> NifResult<Term<'a>> {
+    session.abort.notify_one();
+    Ok(atoms::ok().encode(env))
+}
+
+/// Part uploads running on the runtime, resolving to their part number
+type PartUploads = futures::stream::FuturesUnordered<JoinHandle<object_store::Result<usize>>>;
+
+/// Drive a push-style upload until it is finished or aborted
+///
+/// Returns `Ok(None)` when the upload was aborted, either explicitly or because
+/// every handle to it was dropped. The multipart upload is aborted whenever it
+/// isn't completed, including on errors.
+#[allow(clippy::too_many_arguments)]
+async fn run_upload_stream(
+    store: Arc<object_store::DynObjectStore>,
+    path: Path,
+    mut receiver: tokio::sync::mpsc::Receiver<UploadCommand>,
+    abort: &tokio::sync::Notify,
+    receiver_pid: &LocalPid,
+    upload_id: &str,
+    part_size: usize,
+    max_concurrency: usize,
+) -> object_store::Result<Option<object_store::PutResult>> {
+    let mut multipart = store.put_multipart(&path).await?;
+    let mut in_flight = PartUploads::new();
+
+    let outcome = upload_parts(
+        multipart.as_mut(),
+        &mut in_flight,
+        &mut receiver,
+        abort,
+        receiver_pid,
+        upload_id,
+        part_size,
+        max_concurrency,
+    )
+    .await;
+    for upload in in_flight.iter() {
+        upload.abort();
+    }
+
+    let result = match outcome {
+        Ok(true) => multipart.complete().await.map(Some),
+        Ok(false) => Ok(None),
+        Err(e) => Err(e),
+    };
+    if !matches!(result, Ok(Some(_))) {
+        let _ = multipart.abort().await;
+    }
+    result
+}
+
+/// Upload the parts of a push-style upload as its chunks arrive
+///
+/// Each part is spawned on the runtime, so it progresses while more chunks
+/// are awaited. Chunks are only taken from the channel while fewer than
+/// `max_concurrency` parts are uploading or a part is still being filled,
+/// leaving writers waiting on the bounded channel otherwise. Returns whether
+/// the upload was finished rather than aborted.
+#[allow(clippy::too_many_arguments)]
+async fn upload_parts(
+    multipart: &mut dyn MultipartUpload,
+    in_flight: &mut PartUploads,
+    receiver: &mut tokio::sync::mpsc::Receiver<UploadCommand>,
+    abort: &tokio::sync::Notify,
+    receiver_pid: &LocalPid,
+    upload_id: &str,
+    part_size: usize,
+    max_concurrency: usize,
+) -> object_store::Result<bool> {
+    let mut buffer: VecDeque<Bytes> = VecDeque::new();
+    let mut buffered = 0usize;
+    let mut part_number = 0usize;
+    let mut finishing = false;
+
+    loop {
+        while in_flight.len() < max_concurrency
+            && (buffered >= part_size || (finishing && buffered > 0))
+        {
+            let payload = take_part(&mut buffer, &mut buffered, part_size);
+            part_number += 1;
+            let number = part_number;
+            let upload = multipart.put_part(payload);
+            in_flight.push(tokio::spawn(async move { upload.await.map(|_| number) }));
+        }
+
+        if finishing && buffered == 0 && in_flight.is_empty() {
+            return Ok(true);
+        }
+        let wants_data = !finishing && buffered < part_size;
+
+        tokio::select! {
+            _ = abort.notified() => return Ok(false),
+            Some(result) = in_flight.next(), if !in_flight.is_empty() => {
+                let number = result.map_err(|source| object_store::Error::JoinError { source })??;
+                send_part_uploaded(receiver_pid, upload_id, number);
+            }
+            command = receiver.recv(), if wants_data => match command {
+                Some(UploadCommand::Chunk(bytes)) => {
+                    buffered += bytes.len();
+                    buffer.push_back(bytes);
+                }
+                Some(UploadCommand::Finish) => finishing = true,
+                None => return Ok(false),
+            },
+        }
+    }
+}
+
+/// Split up to `part_size` bytes off the front of the buffered segments
+fn take_part(buffer: &mut VecDeque<Bytes>, buffered: &mut usize, part_size: usize) -> PutPayload {
+    let mut part = Vec::new();
+    let mut size = 0usize;
+
+    while let Some(mut segment) = buffer.pop_front() {
+        let wanted = part_size - size;
+        if segment.len() > wanted {
+            buffer.push_front(segment.split_off(wanted));
+        }
+        size += segment.len();
+        part.push(segment);
+
+        if size >= part_size {
+            break;
+        }
+    }
+
+    *buffered -= size;
+    PutPayload::from_iter(part)
+}
+
+// Helper function to send part progress message to Elixir process
+fn send_part_uploaded(receiver_pid: &LocalPid, upload_id: &str, part_number: usize) {
+    let mut env = OwnedEnv::new();
+
+    let _ = env.send_and_clear(receiver_pid, |env| {
+        (atoms::part_uploaded(), upload_id, part_number).encode(env)
+    });
+}
+
+// Helper function to send upload completion message to Elixir process
+fn send_upload_done(receiver_pid: &LocalPid, upload_id: &str, result: object_store::PutResult) {
+    let mut env = OwnedEnv::new();
+    let etag = result.e_tag.unwrap_or_default();
+    let version = result.version.unwrap_or_default();
+
+    let _ = env.send_and_clear(receiver_pid, |env| {
+        (atoms::upload_done(), upload_id, etag, version).encode(env)
+    });
+}
+
+// Helper function to send upload error message to Elixir process
+fn send_upload_error(receiver_pid: &LocalPid, upload_id: &str, error_msg: String) {
+    let mut env = OwnedEnv::new();
+
+    let _ = env.send_and_clear(receiver_pid, |env| {
+        (atoms::upload_error(), upload_id, error_msg).encode(env)
+    });
+}
+
+// ============================================================================
+// List Operations (Streaming)
+// ============================================================================
 
 // Global registry to track active list streams for potential cancellation
-static LIST_REGISTRY: once_cell::sync::Lazy<ListRegistry> =
+static LIST_REGISTRY: once_cell::sync::Lazy<StreamRegistry> =
     once_cell::sync::Lazy::new(|| Arc::new(Mutex::new(HashMap::new())));
 
 /// Helper function to encode ObjectMeta to an Elixir map
-fn encode_object_meta<'a>(env: Env<'a>, meta: &object_store::ObjectMeta) -> Term<'a> {
+pub(crate) fn encode_object_meta<'a>(env: Env<'a>, meta: &object_store::ObjectMeta) -> Term<'a> {
     use rustler::types::atom::Atom;
     use rustler::types::map;
 
@@ -316,7 +1274,7 @@ fn encode_object_meta<'a>(env: Env<'a>, meta: &object_store::ObjectMeta) -> Term
     let map = map
         .map_put(
             Atom::from_str(env, "size").unwrap().to_term(env),
-            meta.size.encode(env),
+            (meta.size as u64).encode(env),
         )
         .unwrap();
 
@@ -327,6 +1285,8 @@ fn encode_object_meta<'a>(env: Env<'a>, meta: &object_store::ObjectMeta) -> Term
         )
         .unwrap();
 
+    let map = put_last_modified_us(env, map, meta);
+
     let map = if let Some(ref etag) = meta.e_tag {
         map.map_put(
             Atom::from_str(env, "etag").unwrap().to_term(env),
@@ -347,65 +1307,156 @@ fn encode_object_meta<'a>(env: Env<'a>, meta: &object_store::ObjectMeta) -> Term
     map
 }
 
+/// Add the modification time of an object in Unix microseconds to its
+/// metadata map, as `:last_modified_us`
+pub(crate) fn put_last_modified_us<'a>(
+    env: Env<'a>,
+    map: Term<'a>,
+    meta: &object_store::ObjectMeta,
+) -> Term<'a> {
+    map.map_put(
+        atoms::last_modified_us().encode(env),
+        meta.last_modified.timestamp_micros().encode(env),
+    )
+    .unwrap()
+}
+
 /// Start a list stream that sends object metadata to the receiver process
+///
+/// With `page_size`, S3 and GCS are asked for that many keys per request.
+/// Objects are sent one per `{:object, list_id, meta}` message, or with
+/// `batch_size` in `{:objects, list_id, [meta]}` messages of up to that many;
+/// a partial batch is sent before `:done` or an error. Objects not matching
+/// `filter` are dropped before they are encoded.
 #[rustler::nif]
 pub fn start_list_stream<'a>(
     env: Env<'a>,
     store: ResourceArc<StoreWrapper>,
     prefix: Option<String>,
+    page_size: Option<usize>,
+    batch_size: Option<usize>,
+    filter: Option<ListFilterNif>,
     receiver_pid: LocalPid,
 ) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    if batch_size == Some(0) {
+        return Err(rustler::Error::BadArg);
+    }
+    let filter = filter.map(ListFilter::new).transpose()?;
+
     let list_id = Uuid::new_v4().to_string();
     let list_id_clone = list_id.clone();
+    let paged = paging::paged(&store, page_size)?;
     let store = store.inner.clone();
     let prefix_path = prefix.map(Path::from);
 
-    // Spawn async task to list objects
-    let handle = RUNTIME.spawn(async move {
-        let mut stream = store.list(prefix_path.as_ref());
+    // Async task to list objects
+    let task = async move {
+        let mut stream = match paged {
+            Some((listing, page_size)) => paging::list(listing, prefix_path.clone(), page_size),
+            None => store.list(prefix_path.as_ref()),
+        };
+        if let Some(filter) = filter {
+            stream = stream
+                .try_filter(move |meta| futures::future::ready(filter.matches(meta)))
+                .boxed();
+        }
 
-        // Iterate over the stream and send each object metadata
+        let Some(batch_size) = batch_size else {
+            // Iterate over the stream and send each object metadata
+            while let Some(meta_result) = stream.next().await {
+                match meta_result {
+                    Ok(meta) => {
+                        // Send object metadata to Elixir process
+                        if !send_object(&receiver_pid, &list_id_clone, meta) {
+                            // If send fails, process is dead, stop listing
+                            return;
+                        }
+                    }
+                    Err(e) => {
+                        send_error(&receiver_pid, &list_id_clone, format!("{}", e));
+                        return;
+                    }
+                }
+            }
+
+            send_done(&receiver_pid, &list_id_clone);
+            return;
+        };
+
+        let mut batch = Vec::with_capacity(batch_size);
         while let Some(meta_result) = stream.next().await {
             match meta_result {
                 Ok(meta) => {
-                    // Send object metadata to Elixir process
-                    if !send_object(&receiver_pid, &list_id_clone, meta) {
-                        // If send fails, process is dead, stop listing
+                    batch.push(meta);
+                    if batch.len() == batch_size
+                        && !send_objects(&receiver_pid, &list_id_clone, &mut batch)
+                    {
                         return;
                     }
                 }
                 Err(e) => {
-                    send_error(&receiver_pid, &list_id_clone, format!("{}", e));
+                    if send_objects(&receiver_pid, &list_id_clone, &mut batch) {
+                        send_error(&receiver_pid, &list_id_clone, format!("{}", e));
+                    }
                     return;
                 }
             }
         }
 
+        if !send_objects(&receiver_pid, &list_id_clone, &mut batch) {
+            return;
+        }
+
         // Send completion message
         send_done(&receiver_pid, &list_id_clone);
-    });
+    };
 
-    // Register the task handle
-    {
-        let mut registry = LIST_REGISTRY.lock().unwrap();
-        registry.insert(list_id.clone(), handle);
-    }
+    // Spawn the task, registered for cancellation, and monitor the receiver
+    spawn_stream(env, &LIST_REGISTRY, &list_id, &receiver_pid, None, task);
 
     // Return {:ok, list_id}
     Ok((atoms::ok(), list_id).encode(env))
 }
 
+/// Send a batch of object metadata to the Elixir process, emptying it
+///
+/// Empty batches aren't sent. Returns false when the receiver is no longer
+/// alive.
+fn send_objects(
+    receiver_pid: &LocalPid,
+    list_id: &str,
+    batch: &mut Vec<object_store::ObjectMeta>,
+) -> bool {
+    if batch.is_empty() {
+        return true;
+    }
+
+    let mut env = OwnedEnv::new();
+    let sent = env.send_and_clear(receiver_pid, |env| {
+        let metas: Vec<Term> = batch
+            .iter()
+            .map(|meta| encode_object_meta(env, meta))
+            .collect();
+
+        (atoms::objects(), list_id, metas).encode(env)
+    });
+    batch.clear();
+    sent.is_ok()
+}
+
 /// Helper function to send object metadata message to Elixir process
+///
+/// Returns false when the receiver is no longer alive.
 fn send_object(receiver_pid: &LocalPid, list_id: &str, meta: object_store::ObjectMeta) -> bool {
     let mut env = OwnedEnv::new();
 
-    let _ = env.send_and_clear(receiver_pid, |env| {
+    env.send_and_clear(receiver_pid, |env| {
         let object_atom = atoms::object().encode(env);
         let id_term = list_id.encode(env);
         let meta_map = encode_object_meta(env, &meta);
 
         (object_atom, id_term, meta_map).encode(env)
-    });
-
-    true
+    })
+    .is_ok()
 }
@@ -0,0 +1,132 @@
+//! Object tagging for providers whose tagging API object_store doesn't expose
+
+use crate::atoms;
+use crate::errors::map_error;
+use crate::s3_api::{generic, S3Api};
+use crate::shutdown;
+use crate::store::StoreWrapper;
+use crate::RUNTIME;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use md5::{Digest, Md5};
+use object_store::path::Path;
+use object_store::Result;
+use quick_xml::escape::escape;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use reqwest::Method;
+use rustler::{Encoder, Env, NifResult, ResourceArc, Term};
+
+impl S3Api {
+    /// Get the tags of an object
+    pub async fn get_tags(&self, path: &Path) -> Result<Vec<(String, String)>> {
+        let body = self
+            .send(Method::GET, path, Some("tagging"), &[], None)
+            .await?;
+        parse_tagging(&body).map_err(|e| generic(format!("Invalid tagging response: {}", e)))
+    }
+
+    /// Replace the tags of an object
+    pub async fn put_tags(&self, path: &Path, tags: &[(String, String)]) -> Result<()> {
+        let body = encode_tagging(tags);
+        // PutObjectTagging requires a body checksum
+        let md5 = BASE64_STANDARD.encode(Md5::digest(body.as_bytes()));
+
+        self.send(
+            Method::PUT,
+            path,
+            Some("tagging"),
+            &[("Content-MD5", md5)],
+            Some(body),
+        )
+        .await
+        .map(|_| ())
+    }
+}
+
+/// Build a `Tagging` request document
+fn encode_tagging(tags: &[(String, String)]) -> String {
+    let mut xml =
+        String::from(r#"<Tagging xmlns="http://s3.amazonaws.com/doc/2006-03-01/"><TagSet>"#);
+    for (key, value) in tags {
+        xml.push_str(&format!(
+            "<Tag><Key>{}</Key><Value>{}</Value></Tag>",
+            escape(key.as_str()),
+            escape(value.as_str())
+        ));
+    }
+    xml.push_str("</TagSet></Tagging>");
+    xml
+}
+
+/// Parse the key/value pairs of a `Tagging` response document
+fn parse_tagging(body: &[u8]) -> std::result::Result<Vec<(String, String)>, quick_xml::Error> {
+    let mut reader = Reader::from_reader(body);
+    let mut buf = Vec::new();
+    let mut tags = Vec::new();
+    let mut element = Vec::new();
+    let (mut key, mut value) = (String::new(), String::new());
+
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(start) => element = start.name().as_ref().to_vec(),
+            Event::Text(text) => match element.as_slice() {
+                b"Key" => key.push_str(&text.unescape()?),
+                b"Value" => value.push_str(&text.unescape()?),
+                _ => {}
+            },
+            Event::End(end) => {
+                if end.name().as_ref() == b"Tag" {
+                    tags.push((std::mem::take(&mut key), std::mem::take(&mut value)));
+                }
+                element.clear();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    Ok(tags)
+}
+
+/// Get the tags of an object
+///
+/// Returns a list of `{key, value}` tuples, or `:not_supported` for stores
+/// without object tagging.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn get_tags<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let Some(s3) = store.s3.clone() else {
+        return Ok(atoms::not_supported().to_term(env));
+    };
+
+    match RUNTIME.block_on(s3.get_tags(&Path::from(path))) {
+        Ok(tags) => Ok(tags.encode(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
+
+/// Replace the tags of an object
+///
+/// Returns `:not_supported` for stores without object tagging.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn put_tags<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+    tags: Vec<(String, String)>,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let Some(s3) = store.s3.clone() else {
+        return Ok(atoms::not_supported().to_term(env));
+    };
+
+    match RUNTIME.block_on(s3.put_tags(&Path::from(path), &tags)) {
+        Ok(()) => Ok(atoms::ok().to_term(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
@@ -0,0 +1,216 @@
+//! Short-lived bearer tokens supplied by Elixir
+//!
+//! `TokenProvider` serves a pre-fetched OAuth token to GCS and Azure stores
+//! until shortly before it expires, then asks an Elixir token server for a
+//! new one by sending it `{:objectstorex_token, call_id}`. The server answers
+//! through `token_reply/2`, like backend processes answer `backend_reply/2`.
+
+use crate::atoms;
+use crate::RUNTIME;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use object_store::{CredentialProvider, Error, Result};
+use once_cell::sync::Lazy;
+use rustler::{Atom, Encoder, Env, LocalPid, NifResult, Term};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+
+const STORE: &str = "Token";
+
+/// Tokens are refreshed this long before they expire, so requests signed
+/// with them don't fail in flight
+const REFRESH_MARGIN: Duration = Duration::seconds(60);
+
+/// How long to wait for the token server to answer
+const REFRESH_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(60);
+
+mod token_atoms {
+    rustler::atoms! {
+        objectstorex_token,
+    }
+}
+
+/// Refreshes waiting for a reply from their token server, by call id
+static PENDING: Lazy<Mutex<HashMap<u64, oneshot::Sender<TokenReply>>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+static NEXT_CALL_ID: AtomicU64 = AtomicU64::new(1);
+
+/// A bearer token and when it expires
+///
+/// Decoded from `{token, expires_at}`, with `expires_at` a Unix timestamp in
+/// seconds or `nil` for tokens that don't expire.
+#[derive(Clone)]
+pub struct Token {
+    pub bearer: String,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl<'a> rustler::Decoder<'a> for Token {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        let (bearer, expires_at): (String, Option<i64>) = term.decode()?;
+        let expires_at = match expires_at {
+            Some(secs) => Some(
+                Utc.timestamp_opt(secs, 0)
+                    .single()
+                    .ok_or(rustler::Error::BadArg)?,
+            ),
+            None => None,
+        };
+
+        Ok(Token { bearer, expires_at })
+    }
+}
+
+impl fmt::Debug for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Token")
+            .field("expires_at", &self.expires_at)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Reply of a token server to one refresh
+#[derive(Debug)]
+pub enum TokenReply {
+    Token(Token),
+    Error(String),
+}
+
+impl<'a> rustler::Decoder<'a> for TokenReply {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        let (tag, value): (Atom, Term<'a>) = term.decode()?;
+
+        if tag == atoms::ok() {
+            Ok(TokenReply::Token(value.decode()?))
+        } else if tag == atoms::error() {
+            let reason = match value.atom_to_string() {
+                Ok(reason) => reason,
+                Err(_) => value
+                    .decode::<String>()
+                    .unwrap_or_else(|_| format!("{:?}", value)),
+            };
+            Ok(TokenReply::Error(reason))
+        } else {
+            Err(rustler::Error::BadArg)
+        }
+    }
+}
+
+fn generic(message: String) -> Error {
+    Error::Generic {
+        store: STORE,
+        source: message.into(),
+    }
+}
+
+/// A credential and when it expires
+type Cached<T> = (Arc<T>, Option<DateTime<Utc>>);
+
+/// Credential provider serving tokens pre-fetched and refreshed by Elixir
+///
+/// `wrap` turns a bearer token into the provider's credential type. Without a
+/// token server, the initial token is served until it expires.
+pub struct TokenProvider<T> {
+    server: Option<LocalPid>,
+    wrap: fn(String) -> T,
+    cached: tokio::sync::Mutex<Option<Cached<T>>>,
+}
+
+impl<T> TokenProvider<T> {
+    pub fn new(token: Option<Token>, server: Option<LocalPid>, wrap: fn(String) -> T) -> Self {
+        let cached = token.map(|token| (Arc::new(wrap(token.bearer)), token.expires_at));
+
+        Self {
+            server,
+            wrap,
+            cached: tokio::sync::Mutex::new(cached),
+        }
+    }
+
+    /// Ask the token server for a new token
+    async fn refresh(&self, server: LocalPid) -> Result<Token> {
+        let id = NEXT_CALL_ID.fetch_add(1, Ordering::Relaxed);
+        let (tx, rx) = oneshot::channel();
+        PENDING.lock().unwrap().insert(id, tx);
+
+        let sent = RUNTIME
+            .send(server, None, move |env| {
+                (token_atoms::objectstorex_token(), id).encode(env)
+            })
+            .await
+            .unwrap_or(false);
+
+        if !sent {
+            PENDING.lock().unwrap().remove(&id);
+            return Err(generic("Token server is not alive".to_string()));
+        }
+
+        match tokio::time::timeout(REFRESH_TIMEOUT, rx).await {
+            Ok(Ok(TokenReply::Token(token))) => Ok(token),
+            Ok(Ok(TokenReply::Error(reason))) => {
+                Err(generic(format!("Token refresh failed: {}", reason)))
+            }
+            Ok(Err(_)) => Err(generic("Token server dropped the refresh".to_string())),
+            Err(_) => {
+                PENDING.lock().unwrap().remove(&id);
+                Err(generic("Token refresh timed out".to_string()))
+            }
+        }
+    }
+}
+
+impl<T> fmt::Debug for TokenProvider<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TokenProvider").finish_non_exhaustive()
+    }
+}
+
+#[async_trait]
+impl<T: Send + Sync + 'static> CredentialProvider for TokenProvider<T> {
+    type Credential = T;
+
+    /// Serialized, so concurrent requests wait for one refresh
+    async fn get_credential(&self) -> Result<Arc<T>> {
+        let mut cached = self.cached.lock().await;
+        let now = Utc::now();
+
+        if let Some((credential, expires_at)) = cached.as_ref() {
+            let fresh = match (expires_at, self.server) {
+                (None, _) => true,
+                (Some(expires_at), Some(_)) => *expires_at - REFRESH_MARGIN > now,
+                (Some(expires_at), None) => *expires_at > now,
+            };
+            if fresh {
+                return Ok(credential.clone());
+            }
+        }
+
+        let Some(server) = self.server else {
+            return Err(generic("Bearer token expired".to_string()));
+        };
+
+        let token = self.refresh(server).await?;
+        let credential = Arc::new((self.wrap)(token.bearer));
+        *cached = Some((credential.clone(), token.expires_at));
+        Ok(credential)
+    }
+}
+
+/// Answer a refresh requested from a token server
+///
+/// `reply` is `{:ok, {token, expires_at}}` or `{:error, reason}`. Returns
+/// `:not_found` when the refresh is no longer waiting for a reply.
+#[rustler::nif]
+pub fn token_reply<'a>(env: Env<'a>, call_id: u64, reply: TokenReply) -> Term<'a> {
+    match PENDING.lock().unwrap().remove(&call_id) {
+        Some(tx) => {
+            let _ = tx.send(reply);
+            atoms::ok().encode(env)
+        }
+        None => atoms::not_found().encode(env),
+    }
+}
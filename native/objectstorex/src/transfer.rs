@@ -0,0 +1,535 @@
+//! Whole-object and prefix transfers with progress reporting
+
+use crate::atoms;
+use crate::checksum::{ChecksumAlgorithm, Hasher, Verifier, Verify};
+use crate::errors::{error_kind, map_error};
+use crate::shutdown;
+use crate::store::StoreWrapper;
+use crate::RUNTIME;
+use bytes::Bytes;
+use futures::{StreamExt, TryStreamExt};
+use object_store::path::Path;
+use object_store::{DynObjectStore, GetResult, ObjectMeta, PutPayload};
+use rustler::{
+    Atom, Binary, Encoder, Env, LocalPid, NifMap, NifResult, OwnedBinary, ResourceArc, Term,
+};
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+/// Size of the parts used when a transfer is split into a multipart upload
+const PART_SIZE: usize = 5 * 1024 * 1024;
+
+/// Progress options passed from Elixir as `{pid, op_id, interval_ms}`
+type ProgressNif = Option<(LocalPid, String, u64)>;
+
+/// Sends `{:progress, op_id, bytes_done, bytes_total}` messages, at most once
+/// per interval except for the final update
+///
+/// Transfers run on the calling dirty scheduler thread, where the VM only
+/// allows sending through the calling process' environment.
+struct Progress<'a> {
+    env: Env<'a>,
+    pid: LocalPid,
+    op_id: String,
+    interval: Duration,
+    total: u64,
+    last_sent: Option<Instant>,
+}
+
+impl<'a> Progress<'a> {
+    fn new(env: Env<'a>, progress: ProgressNif, total: u64) -> Option<Self> {
+        progress.map(|(pid, op_id, interval_ms)| Self {
+            env,
+            pid,
+            op_id,
+            interval: Duration::from_millis(interval_ms),
+            total,
+            last_sent: None,
+        })
+    }
+
+    fn report(&mut self, done: u64) {
+        let due = self
+            .last_sent
+            .is_none_or(|sent| sent.elapsed() >= self.interval);
+
+        if due {
+            self.send(done);
+        }
+    }
+
+    fn finish(&mut self) {
+        self.send(self.total);
+    }
+
+    fn send(&mut self, done: u64) {
+        let _ = self.env.send(
+            &self.pid,
+            (atoms::progress(), self.op_id.as_str(), done, self.total),
+        );
+        self.last_sent = Some(Instant::now());
+    }
+}
+
+/// Report progress if it was requested
+fn report(progress: &mut Option<Progress<'_>>, done: u64) {
+    if let Some(progress) = progress {
+        progress.report(done);
+    }
+}
+
+/// Send the final progress update if it was requested
+fn finish(progress: &mut Option<Progress<'_>>) {
+    if let Some(progress) = progress {
+        progress.finish();
+    }
+}
+
+/// Errors from transfers that touch the local filesystem
+pub(crate) enum TransferError {
+    Store(object_store::Error),
+    Io(std::io::Error),
+}
+
+impl From<object_store::Error> for TransferError {
+    fn from(e: object_store::Error) -> Self {
+        TransferError::Store(e)
+    }
+}
+
+impl From<std::io::Error> for TransferError {
+    fn from(e: std::io::Error) -> Self {
+        TransferError::Io(e)
+    }
+}
+
+impl TransferError {
+    /// Store errors are returned as atoms; file errors raise
+    pub(crate) fn into_term(self, env: Env<'_>) -> NifResult<Term<'_>> {
+        match self {
+            TransferError::Store(e) => Ok(map_error(e).to_term(env)),
+            TransferError::Io(e) => {
+                Err(rustler::Error::Term(Box::new(format!("File error: {}", e))))
+            }
+        }
+    }
+}
+
+/// Upload parts produced by `next_part`, as a single put when the data fits
+/// in one part
+async fn upload_parts<E>(
+    store: &DynObjectStore,
+    path: &Path,
+    total: u64,
+    mut next_part: impl FnMut() -> Result<Bytes, E>,
+    progress: &mut Option<Progress<'_>>,
+) -> Result<(), E>
+where
+    E: From<object_store::Error>,
+{
+    report(progress, 0);
+
+    if total <= PART_SIZE as u64 {
+        store.put(path, PutPayload::from(next_part()?)).await?;
+        finish(progress);
+        return Ok(());
+    }
+
+    let mut upload = store.put_multipart(path).await?;
+    let mut done = 0u64;
+
+    while done < total {
+        let part = match next_part() {
+            Ok(part) if !part.is_empty() => part,
+            Ok(_) => break,
+            Err(e) => {
+                let _ = upload.abort().await;
+                return Err(e);
+            }
+        };
+        done += part.len() as u64;
+
+        if let Err(e) = upload.put_part(PutPayload::from(part)).await {
+            let _ = upload.abort().await;
+            return Err(e.into());
+        }
+        report(progress, done);
+    }
+
+    upload.complete().await?;
+    finish(progress);
+    Ok(())
+}
+
+/// Read up to `PART_SIZE` bytes from a file
+fn read_part(file: &mut File) -> std::io::Result<Bytes> {
+    let mut part = Vec::with_capacity(PART_SIZE);
+    Read::by_ref(file)
+        .take(PART_SIZE as u64)
+        .read_to_end(&mut part)?;
+    Ok(Bytes::from(part))
+}
+
+/// Upload an object, reporting progress to a process
+///
+/// Data larger than one part is uploaded in parts so progress can be reported
+/// as each part completes.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn put_with_progress<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+    data: Binary,
+    progress: ProgressNif,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let data = Bytes::copy_from_slice(data.as_slice());
+    let total = data.len() as u64;
+    let mut progress = Progress::new(env, progress, total);
+    let mut offset = 0;
+
+    let result = RUNTIME.block_on(upload_parts(
+        store.inner.as_ref(),
+        &Path::from(path),
+        total,
+        || {
+            let end = (offset + PART_SIZE).min(data.len());
+            let part = data.slice(offset..end);
+            offset = end;
+            Ok::<_, object_store::Error>(part)
+        },
+        &mut progress,
+    ));
+
+    match result {
+        Ok(()) => Ok(atoms::ok().to_term(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
+
+/// Download an object, reporting progress to a process
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn get_with_progress<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+    progress: ProgressNif,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let result = RUNTIME.block_on(async {
+        let result = store.inner.get(&Path::from(path)).await?;
+        let total = result.meta.size as u64;
+        let mut progress = Progress::new(env, progress, total);
+        let mut data = Vec::with_capacity(result.meta.size);
+        let mut stream = result.into_stream();
+
+        report(&mut progress, 0);
+        while let Some(chunk) = stream.next().await {
+            data.extend_from_slice(&chunk?);
+            report(&mut progress, data.len() as u64);
+        }
+        finish(&mut progress);
+
+        Ok::<_, object_store::Error>(data)
+    });
+
+    match result {
+        Ok(data) => {
+            let mut binary = OwnedBinary::new(data.len()).unwrap();
+            binary.as_mut_slice().copy_from_slice(&data);
+            Ok(binary.release(env).encode(env))
+        }
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
+
+/// Upload a local file without loading it into memory
+///
+/// Files larger than one part are uploaded as a multipart upload. Progress is
+/// reported when requested. With a checksum algorithm, the digest of the file
+/// is computed while uploading and returned as `{:ok, checksum}`.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn put_from_file<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+    file_path: String,
+    progress: ProgressNif,
+    checksum: Option<Atom>,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let algorithm = checksum.map(ChecksumAlgorithm::from_atom).transpose()?;
+    let target = match algorithm {
+        Some(algorithm) => store.checksum_store(algorithm),
+        None => store.inner.clone(),
+    };
+    let mut hasher = algorithm.map(Hasher::new);
+
+    let result = RUNTIME.block_on(async {
+        let mut file = File::open(&file_path)?;
+        let total = file.metadata()?.len();
+        let mut progress = Progress::new(env, progress, total);
+
+        upload_parts(
+            target.as_ref(),
+            &Path::from(path),
+            total,
+            || {
+                let part = read_part(&mut file)?;
+                if let Some(hasher) = &mut hasher {
+                    hasher.update(&part);
+                }
+                Ok::<_, TransferError>(part)
+            },
+            &mut progress,
+        )
+        .await
+    });
+
+    match (result, hasher) {
+        (Ok(()), Some(hasher)) => Ok((atoms::ok(), hasher.finish()).encode(env)),
+        (Ok(()), None) => Ok(atoms::ok().to_term(env)),
+        (Err(e), _) => e.into_term(env),
+    }
+}
+
+/// Write an object's data to `out` as it arrives, checking it against
+/// `verify` and reporting progress
+async fn write_object(
+    result: GetResult,
+    out: &mut impl Write,
+    verify: Option<&Verify>,
+    progress: &mut Option<Progress<'_>>,
+) -> Result<(), TransferError> {
+    let mut verifier = verify.and_then(|verify| Verifier::new(verify, &result.meta));
+    let mut stream = result.into_stream();
+    let mut done = 0u64;
+
+    report(progress, 0);
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk?;
+        if let Some(verifier) = &mut verifier {
+            verifier.update(&chunk);
+        }
+        out.write_all(&chunk)?;
+        done += chunk.len() as u64;
+        report(progress, done);
+    }
+    out.flush()?;
+    if let Some(verifier) = verifier {
+        verifier.finish()?;
+    }
+    Ok(())
+}
+
+/// Download an object into a local file without loading it into memory
+///
+/// A partially written file is removed if the download fails. Progress is
+/// reported when requested. With `verify`, the data is checked against the
+/// object's ETag or a digest as it is written, and the file is removed if it
+/// doesn't match.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn get_to_file<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+    file_path: String,
+    progress: ProgressNif,
+    verify: Option<Verify>,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let result = RUNTIME.block_on(async {
+        let result = store.inner.get(&Path::from(path)).await?;
+        let mut progress = Progress::new(env, progress, result.meta.size as u64);
+        let mut file = File::create(&file_path)?;
+
+        let written = write_object(result, &mut file, verify.as_ref(), &mut progress).await;
+        if written.is_err() {
+            drop(file);
+            let _ = std::fs::remove_file(&file_path);
+        } else {
+            finish(&mut progress);
+        }
+        written
+    });
+
+    match result {
+        Ok(()) => Ok(atoms::ok().to_term(env)),
+        Err(e) => e.into_term(env),
+    }
+}
+
+/// Where `get_spilling` put an object's data
+enum Spilled {
+    Memory(Vec<u8>),
+    File(PathBuf),
+}
+
+/// Download an object into memory, or into a new file under `dir` when it is
+/// larger than `threshold` bytes
+///
+/// The decision is made from the object's size before any data is read, so a
+/// large object never passes through memory. The file is named after a random
+/// UUID and belongs to the caller; it is removed if the download fails.
+/// Progress and `verify` work as for `get_to_file`.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn get_spilling<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+    threshold: u64,
+    dir: String,
+    progress: ProgressNif,
+    verify: Option<Verify>,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let result = RUNTIME.block_on(async {
+        let result = store.inner.get(&Path::from(path)).await?;
+        let total = result.meta.size as u64;
+        let mut progress = Progress::new(env, progress, total);
+
+        if total <= threshold {
+            let mut data = Vec::with_capacity(result.meta.size);
+            write_object(result, &mut data, verify.as_ref(), &mut progress).await?;
+            finish(&mut progress);
+            return Ok(Spilled::Memory(data));
+        }
+
+        let file_path =
+            std::path::Path::new(&dir).join(format!("objectstorex-{}", uuid::Uuid::new_v4()));
+        let mut file = File::create_new(&file_path)?;
+
+        match write_object(result, &mut file, verify.as_ref(), &mut progress).await {
+            Ok(()) => {
+                finish(&mut progress);
+                Ok(Spilled::File(file_path))
+            }
+            Err(e) => {
+                drop(file);
+                let _ = std::fs::remove_file(&file_path);
+                Err(e)
+            }
+        }
+    });
+
+    match result {
+        Ok(Spilled::Memory(data)) => {
+            let mut binary = OwnedBinary::new(data.len()).unwrap();
+            binary.as_mut_slice().copy_from_slice(&data);
+            Ok(binary.release(env).encode(env))
+        }
+        Ok(Spilled::File(file_path)) => {
+            Ok((atoms::file(), file_path.to_string_lossy().into_owned()).encode(env))
+        }
+        Err(e) => e.into_term(env),
+    }
+}
+
+/// Outcome of a prefix copy
+#[derive(NifMap)]
+struct CopyPrefixResultNif {
+    copied: u64,
+    skipped: u64,
+    errors: Vec<(String, Atom)>,
+}
+
+/// Destination of `location` when `from` is rewritten to `to`
+fn rewrite_key(location: &Path, from: &Path, to: &Path) -> Path {
+    match location.prefix_match(from) {
+        Some(parts) => parts.fold(to.clone(), |path, part| path.child(part)),
+        None => location.clone(),
+    }
+}
+
+/// Copy an object unless the destination exists, returning whether it was
+/// copied
+///
+/// Stores without an atomic copy-if-not-exists check the destination first.
+async fn copy_unless_exists(
+    store: &DynObjectStore,
+    from: &Path,
+    to: &Path,
+) -> object_store::Result<bool> {
+    match store.copy_if_not_exists(from, to).await {
+        Ok(()) => Ok(true),
+        Err(object_store::Error::AlreadyExists { .. }) => Ok(false),
+        Err(object_store::Error::NotSupported { .. }) => match store.head(to).await {
+            Ok(_) => Ok(false),
+            Err(object_store::Error::NotFound { .. }) => store.copy(from, to).await.map(|_| true),
+            Err(e) => Err(e),
+        },
+        Err(e) => Err(e),
+    }
+}
+
+/// Copy every object under a prefix to another prefix (server-side)
+///
+/// Keys are rewritten by replacing `from_prefix` with `to_prefix`. With
+/// `skip_existing`, objects whose destination already exists are left alone.
+/// At most `max_concurrency` copies run at once; progress counts the bytes of
+/// the objects copied or skipped so far. Failed copies are collected in
+/// `errors` instead of stopping the others.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn copy_prefix<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    from_prefix: String,
+    to_prefix: String,
+    skip_existing: bool,
+    max_concurrency: usize,
+    progress: ProgressNif,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let from = Path::from(from_prefix);
+    let to = Path::from(to_prefix);
+    let store = store.inner.as_ref();
+
+    let result = RUNTIME.block_on(async {
+        let objects: Vec<ObjectMeta> = store.list(Some(&from)).try_collect().await?;
+        let total = objects.iter().map(|meta| meta.size as u64).sum();
+        let mut progress = Progress::new(env, progress, total);
+        let mut outcome = CopyPrefixResultNif {
+            copied: 0,
+            skipped: 0,
+            errors: Vec::new(),
+        };
+        let mut done = 0u64;
+
+        report(&mut progress, 0);
+        let mut copies = futures::stream::iter(&objects)
+            .map(|meta| {
+                let target = rewrite_key(&meta.location, &from, &to);
+                async move {
+                    let result = if skip_existing {
+                        copy_unless_exists(store, &meta.location, &target).await
+                    } else {
+                        store.copy(&meta.location, &target).await.map(|_| true)
+                    };
+                    (meta, result)
+                }
+            })
+            .buffer_unordered(max_concurrency.max(1));
+
+        while let Some((meta, result)) = copies.next().await {
+            match result {
+                Ok(true) => outcome.copied += 1,
+                Ok(false) => outcome.skipped += 1,
+                Err(e) => outcome
+                    .errors
+                    .push((meta.location.to_string(), error_kind(&e))),
+            }
+            done += meta.size as u64;
+            report(&mut progress, done);
+        }
+        finish(&mut progress);
+
+        Ok::<_, object_store::Error>(outcome)
+    });
+
+    match result {
+        Ok(outcome) => Ok((atoms::ok(), outcome).encode(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
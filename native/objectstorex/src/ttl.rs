@@ -0,0 +1,174 @@
+//! Lifecycle-style expiry of objects, for stores without lifecycle rules
+
+use crate::atoms;
+use crate::errors::map_error;
+use crate::s3_api::S3Api;
+use crate::shutdown;
+use crate::store::StoreWrapper;
+use crate::types::IoDataNif;
+use crate::RUNTIME;
+use chrono::Utc;
+use futures::TryStreamExt;
+use object_store::path::Path;
+use object_store::{Error, ObjectStore, Result};
+use rustler::{Encoder, Env, NifResult, ResourceArc, Term};
+use serde::{Deserialize, Serialize};
+
+const STORE: &str = "Ttl";
+
+/// Prefix of the expiry records, stored in the same store as the objects
+const RECORD_PREFIX: &str = ".objectstorex-ttl";
+
+/// Expiry of an object written with a TTL, stored at the object's path under
+/// `RECORD_PREFIX`
+#[derive(Debug, Serialize, Deserialize)]
+struct ExpiryRecord {
+    /// Unix seconds from which the object is expired
+    expires_at: i64,
+    /// ETag of the object written with the TTL, so an object replaced since
+    /// isn't expired with it
+    e_tag: Option<String>,
+}
+
+/// Whether `location` is an expiry record, or the directory of them, at the
+/// root of the handle it is used with
+///
+/// Keys with a `RECORD_PREFIX` segment further down are ordinary objects.
+/// Handles derived with a prefix write their records under it, and hide them
+/// with their own `HiddenRecordsStore`.
+pub(crate) fn is_record(location: &Path) -> bool {
+    location
+        .parts()
+        .next()
+        .is_some_and(|part| part.as_ref() == RECORD_PREFIX)
+}
+
+fn record_location(path: &Path) -> Path {
+    Path::from_iter(std::iter::once(RECORD_PREFIX.into()).chain(path.parts()))
+}
+
+/// Write an object and record when it expires
+///
+/// The object is written first, so a failure can't leave a record expiring
+/// an earlier object.
+async fn put_expiring(
+    store: &dyn ObjectStore,
+    path: &Path,
+    data: IoDataNif,
+    ttl_secs: u64,
+) -> Result<()> {
+    let result = store.put(path, data.into_payload()).await?;
+    let ttl_secs = i64::try_from(ttl_secs).unwrap_or(i64::MAX);
+    let record = ExpiryRecord {
+        expires_at: Utc::now().timestamp().saturating_add(ttl_secs),
+        e_tag: result.e_tag,
+    };
+    let json = serde_json::to_vec(&record).map_err(|e| Error::Generic {
+        store: STORE,
+        source: Box::new(e),
+    })?;
+
+    store.put(&record_location(path), json.into()).await?;
+    Ok(())
+}
+
+/// Delete `path` if it is still the object written with the TTL
+///
+/// On S3 the ETag is checked by the delete itself; elsewhere the object is
+/// checked first, leaving a short window in which a replacement written in
+/// between is deleted.
+async fn delete_expired(
+    store: &dyn ObjectStore,
+    s3: Option<&S3Api>,
+    path: &Path,
+    e_tag: Option<&str>,
+) -> Result<bool> {
+    if let (Some(s3), Some(e_tag)) = (s3, e_tag) {
+        return match s3.delete(path, Some(e_tag), None).await {
+            Ok(()) => Ok(true),
+            Err(Error::NotFound { .. } | Error::Precondition { .. }) => Ok(false),
+            Err(e) => Err(e),
+        };
+    }
+
+    match store.head(path).await {
+        Ok(meta) if e_tag.is_none() || meta.e_tag.as_deref() == e_tag => {
+            store.delete(path).await?;
+            Ok(true)
+        }
+        Ok(_) | Err(Error::NotFound { .. }) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+/// Delete the expired objects under `prefix` and their records
+///
+/// Records of objects deleted or replaced since their TTL was set are removed
+/// without touching the objects. Returns the locations of deleted objects.
+async fn expire(store: &dyn ObjectStore, s3: Option<&S3Api>, prefix: &Path) -> Result<Vec<String>> {
+    let now = Utc::now().timestamp();
+    let records: Vec<_> = store
+        .list(Some(&record_location(prefix)))
+        .try_collect()
+        .await?;
+    let mut expired = Vec::new();
+
+    for meta in records {
+        let body = store.get(&meta.location).await?.bytes().await?;
+        let record: ExpiryRecord = serde_json::from_slice(&body).map_err(|e| Error::Generic {
+            store: STORE,
+            source: format!("Invalid expiry record {}: {}", meta.location, e).into(),
+        })?;
+        if record.expires_at > now {
+            continue;
+        }
+
+        let path = Path::from_iter(meta.location.parts().skip(1));
+        if delete_expired(store, s3, &path, record.e_tag.as_deref()).await? {
+            expired.push(path.to_string());
+        }
+        match store.delete(&meta.location).await {
+            Ok(()) | Err(Error::NotFound { .. }) => {}
+            Err(e) => return Err(e),
+        }
+    }
+
+    Ok(expired)
+}
+
+/// Upload an object that `expire_now` deletes once `ttl_secs` have passed
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn put_with_ttl<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+    data: IoDataNif,
+    ttl_secs: u64,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let path = Path::from(path);
+
+    match RUNTIME.block_on(put_expiring(store.inner.as_ref(), &path, data, ttl_secs)) {
+        Ok(()) => Ok(atoms::ok().to_term(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
+
+/// Delete the objects under `prefix` whose TTL has passed
+///
+/// Returns the list of deleted locations.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn expire_now<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    prefix: String,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let prefix = Path::from(prefix);
+    let s3 = store.s3.as_deref();
+
+    match RUNTIME.block_on(expire(store.inner.as_ref(), s3, &prefix)) {
+        Ok(expired) => Ok(expired.encode(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
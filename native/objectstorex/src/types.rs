@@ -1,4 +1,15 @@
-use rustler::{Decoder, Error as RustlerError, NifResult, NifStruct, Term};
+use crate::atoms;
+use crate::checksum::Verify;
+use crate::sse::ServerSideEncryption;
+use bytes::Bytes;
+use chrono::{DateTime, TimeZone, Utc};
+use object_store::PutPayload;
+use rustler::{
+    Atom, Binary, Decoder, Encoder, Env, Error as RustlerError, NifMap, NifResult, NifStruct, Term,
+    TermType,
+};
+use std::collections::HashMap;
+use std::ops::Range;
 
 /// Elixir representation of PutMode for conditional writes
 ///
@@ -26,16 +37,19 @@ pub struct GetOptionsNif {
     pub if_match: Option<String>,
     /// Only return if ETag differs (HTTP If-None-Match)
     pub if_none_match: Option<String>,
-    /// Only return if modified after date (Unix timestamp in seconds)
-    pub if_modified_since: Option<i64>,
-    /// Only return if not modified since date (Unix timestamp in seconds)
-    pub if_unmodified_since: Option<i64>,
+    /// Only return if modified after date
+    pub if_modified_since: Option<TimestampNif>,
+    /// Only return if not modified since date
+    pub if_unmodified_since: Option<TimestampNif>,
     /// Byte range to fetch
     pub range: Option<RangeNif>,
     /// Specific object version
     pub version: Option<String>,
     /// Return metadata only (no content)
     pub head: bool,
+    /// Check the data against the ETag (`:etag`) or a digest
+    /// (`{algorithm, hex}`); whole-object reads only
+    pub verify: Option<Verify>,
 }
 
 /// Elixir representation of a byte range for partial reads
@@ -50,6 +64,22 @@ pub struct RangeNif {
     pub end: u64,
 }
 
+impl RangeNif {
+    /// The range as byte offsets, see `to_usize`
+    pub fn to_range(&self) -> NifResult<Range<usize>> {
+        Ok(to_usize(self.start)?..to_usize(self.end)?)
+    }
+}
+
+/// Convert a size or offset passed from Elixir to `usize`
+///
+/// Sizes are exchanged with Elixir as unsigned 64-bit integers. Values that
+/// don't fit `usize`, which only happens on 32-bit targets, are rejected
+/// instead of truncated.
+pub fn to_usize(value: u64) -> NifResult<usize> {
+    usize::try_from(value).map_err(|_| RustlerError::BadArg)
+}
+
 impl<'a> Decoder<'a> for PutModeNif {
     fn decode(term: Term<'a>) -> NifResult<Self> {
         // Try to decode as atom first
@@ -96,6 +126,122 @@ impl<'a> Decoder<'a> for PutModeNif {
     }
 }
 
+/// A point in time passed from Elixir, as in conditional request headers
+///
+/// Decoded from Unix seconds, `{integer, :second | :millisecond}` tuples or
+/// RFC 3339 (ISO 8601) strings with an offset, such as the ones
+/// `DateTime.to_iso8601/1` returns. Values out of range or unparseable are
+/// rejected as bad arguments. Encoded as `{milliseconds, :millisecond}`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimestampNif(pub DateTime<Utc>);
+
+impl<'a> Decoder<'a> for TimestampNif {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        let datetime = match term.get_type() {
+            TermType::Integer => Utc.timestamp_opt(term.decode()?, 0).single(),
+            TermType::Tuple => {
+                let (value, unit): (i64, Atom) = term.decode()?;
+                if unit == atoms::second() {
+                    Utc.timestamp_opt(value, 0).single()
+                } else if unit == atoms::millisecond() {
+                    Utc.timestamp_millis_opt(value).single()
+                } else {
+                    None
+                }
+            }
+            TermType::Binary => DateTime::parse_from_rfc3339(term.decode()?)
+                .ok()
+                .map(|datetime| datetime.with_timezone(&Utc)),
+            _ => None,
+        };
+
+        datetime.map(TimestampNif).ok_or(RustlerError::BadArg)
+    }
+}
+
+impl Encoder for TimestampNif {
+    fn encode<'a>(&self, env: Env<'a>) -> Term<'a> {
+        (self.0.timestamp_millis(), atoms::millisecond()).encode(env)
+    }
+}
+
+/// Elixir iodata passed as the data of an upload
+///
+/// Decoded from a binary or an iolist (nested lists of binaries and bytes,
+/// possibly with a binary tail) without flattening it: each binary becomes
+/// its own `Bytes` segment, so the data is copied once, into the segments,
+/// and uploaded as a `PutPayload` built from them.
+#[derive(Debug, Clone, Default)]
+pub struct IoDataNif(pub Vec<Bytes>);
+
+impl IoDataNif {
+    /// The segments of the data, in order
+    pub fn segments(&self) -> impl Iterator<Item = &[u8]> {
+        self.0.iter().map(|segment| segment.as_ref())
+    }
+
+    pub fn into_payload(self) -> PutPayload {
+        PutPayload::from_iter(self.0)
+    }
+
+    /// Append the segments of `term`
+    ///
+    /// Lists are walked with an explicit stack of the terms left to visit,
+    /// since `[acc, chunk]` accumulators nest as deep as they have chunks.
+    fn push(&mut self, term: Term<'_>, bytes: &mut Vec<u8>) -> NifResult<()> {
+        let mut pending = vec![term];
+        while let Some(term) = pending.pop() {
+            match term.get_type() {
+                TermType::Binary => self.push_binary(term, bytes)?,
+                TermType::Integer => bytes.push(term.decode()?),
+                // Only the empty list has no cell
+                TermType::List => {
+                    if let Ok((head, tail)) = term.list_get_cell() {
+                        // The tail of an improper iolist must be a binary
+                        match tail.get_type() {
+                            TermType::List | TermType::Binary => pending.push(tail),
+                            _ => return Err(RustlerError::BadArg),
+                        }
+                        pending.push(head);
+                    }
+                }
+                _ => return Err(RustlerError::BadArg),
+            }
+        }
+        Ok(())
+    }
+
+    fn push_binary(&mut self, term: Term<'_>, bytes: &mut Vec<u8>) -> NifResult<()> {
+        let binary: Binary = term.decode()?;
+        self.flush(bytes);
+        if !binary.is_empty() {
+            self.0.push(Bytes::copy_from_slice(binary.as_slice()));
+        }
+        Ok(())
+    }
+
+    /// Move bytes collected from integers in an iolist into a segment
+    fn flush(&mut self, bytes: &mut Vec<u8>) {
+        if !bytes.is_empty() {
+            self.0.push(Bytes::from(std::mem::take(bytes)));
+        }
+    }
+}
+
+impl<'a> Decoder<'a> for IoDataNif {
+    fn decode(term: Term<'a>) -> NifResult<Self> {
+        // Bytes are only iodata inside a list
+        if term.get_type() == TermType::Integer {
+            return Err(RustlerError::BadArg);
+        }
+        let mut data = IoDataNif::default();
+        let mut bytes = Vec::new();
+        data.push(term, &mut bytes)?;
+        data.flush(&mut bytes);
+        Ok(data)
+    }
+}
+
 /// Elixir representation of object attributes for metadata
 ///
 /// Matches Elixir struct: %ObjectStoreX.Attributes{}
@@ -112,4 +258,159 @@ pub struct AttributesNif {
     pub cache_control: Option<String>,
     /// Content language (e.g., "en-US")
     pub content_language: Option<String>,
+    /// Custom user metadata (e.g., `x-amz-meta-*` headers on S3)
+    pub metadata: HashMap<String, String>,
+}
+
+/// Elixir representation of a query against a listing snapshot
+///
+/// Matches Elixir struct: %ObjectStoreX.SnapshotQuery{}
+#[derive(Debug, Clone, NifStruct)]
+#[module = "ObjectStoreX.SnapshotQuery"]
+pub struct SnapshotQueryNif {
+    /// Only include locations starting with this prefix
+    pub prefix: Option<String>,
+    /// Only include locations ending with this suffix
+    pub suffix: Option<String>,
+    /// Only include locations containing this substring
+    pub contains: Option<String>,
+    /// Minimum object size in bytes (inclusive)
+    pub min_size: Option<u64>,
+    /// Maximum object size in bytes (inclusive)
+    pub max_size: Option<u64>,
+    /// Only include objects modified at or after this Unix timestamp (seconds)
+    pub modified_after: Option<i64>,
+    /// Only include objects modified before this Unix timestamp (seconds)
+    pub modified_before: Option<i64>,
+    /// Sort field (:location, :size or :last_modified)
+    pub sort_by: Option<Atom>,
+    /// Sort direction (:asc or :desc)
+    pub order: Atom,
+    /// Number of matching objects to skip
+    pub offset: usize,
+    /// Maximum number of objects to return
+    pub limit: Option<usize>,
+}
+
+/// Filters applied to a list stream before objects are sent to Elixir
+///
+/// All filters are optional and combined with AND.
+#[derive(Debug, NifMap)]
+pub struct ListFilterNif {
+    /// Glob the whole location must match (`*`, `**`, `?`)
+    pub glob: Option<String>,
+    /// Regular expression the location must contain a match of
+    pub regex: Option<String>,
+    /// Only include locations ending with this suffix
+    pub suffix: Option<String>,
+    /// Minimum object size in bytes (inclusive)
+    pub min_size: Option<u64>,
+    /// Maximum object size in bytes (inclusive)
+    pub max_size: Option<u64>,
+    /// Only include objects modified at or after this Unix timestamp (seconds)
+    pub modified_after: Option<i64>,
+    /// Only include objects modified before this Unix timestamp (seconds)
+    pub modified_before: Option<i64>,
+}
+
+/// Serializable state of a resumable multipart upload session
+#[derive(Debug, Clone, NifMap)]
+pub struct UploadStateNif {
+    pub upload_id: String,
+    pub parts: Vec<String>,
+    pub bytes_uploaded: u64,
+}
+
+/// Conditions for a delete
+///
+/// Matches Elixir map: %{if_match: etag, version: version}
+#[derive(Debug, Clone, NifMap)]
+pub struct DeleteOptionsNif {
+    /// Only delete if the current ETag matches (HTTP If-Match)
+    pub if_match: Option<String>,
+    /// Delete this specific object version
+    pub version: Option<String>,
+}
+
+/// Options for a download stream
+///
+/// Matches Elixir map: %{credit: n, frame_size: bytes, decompress: :gzip, if_none_match: etag,
+/// if_modified_since: timestamp}
+#[derive(Debug, Clone, NifMap)]
+pub struct DownloadOptionsNif {
+    /// Chunks sent ahead of the receiver, unlimited if nil
+    pub credit: Option<usize>,
+    /// Re-chunk the data into frames of exactly this many bytes
+    pub frame_size: Option<usize>,
+    /// Decompress the data; only `:gzip` is supported
+    pub decompress: Option<Atom>,
+    /// Skip the download if the object's ETag matches (HTTP If-None-Match)
+    pub if_none_match: Option<String>,
+    /// Skip the download unless the object changed after this time (HTTP
+    /// If-Modified-Since)
+    pub if_modified_since: Option<TimestampNif>,
+}
+
+/// Options for local filesystem stores
+///
+/// Mode bits are applied explicitly after each write, so they are not reduced
+/// by the process umask.
+#[derive(Debug, Clone, NifMap)]
+pub struct LocalOptionsNif {
+    /// Mode bits for written files (e.g. 0o640)
+    pub file_mode: Option<u32>,
+    /// Mode bits for directories created by writes (e.g. 0o750)
+    pub dir_mode: Option<u32>,
+    /// Create the root directory if it doesn't exist
+    pub create_path: bool,
+    /// Remove directories left empty by deletes
+    pub automatic_cleanup: bool,
+    /// How copies are made: `:auto`, `:reflink` or `:copy` (default `:auto`)
+    pub copy_strategy: Option<Atom>,
+}
+
+/// Options for S3 stores
+///
+/// Matches Elixir map: %{sse: :kms | {:kms, key_id} | ..., sse_bucket_key: bool,
+/// request_payer: bool, virtual_hosted_style: bool, allow_http: bool}
+#[derive(Debug, Clone, Default, NifMap)]
+pub struct S3OptionsNif {
+    /// Server-side encryption requested for uploads
+    pub sse: Option<ServerSideEncryption>,
+    /// Use an S3 Bucket Key for SSE-KMS, overriding the bucket's setting
+    pub sse_bucket_key: Option<bool>,
+    /// Agree to pay for requests to requester-pays buckets
+    pub request_payer: Option<bool>,
+    /// Address the bucket by host name instead of by path
+    pub virtual_hosted_style: Option<bool>,
+    /// Allow plain `http://` endpoints
+    pub allow_http: Option<bool>,
+}
+
+/// Options for a read-through cache store
+///
+/// Matches Elixir map: %{ttl_ms: ms | nil, max_size: bytes | nil,
+/// write_policy: :write_around | :write_through | :write_back | nil}
+#[derive(Debug, Clone, NifMap)]
+pub struct CacheOptionsNif {
+    /// How long entries are served without revalidating them
+    pub ttl_ms: Option<u64>,
+    /// Total size of the cached objects
+    pub max_size: Option<u64>,
+    pub write_policy: Option<Atom>,
+}
+
+/// Simulated latencies for a throttled store, in microseconds
+///
+/// Matches Elixir map: %{delete_per_call: us, get_per_byte: us, ...}
+#[derive(Debug, Clone, NifMap)]
+pub struct ThrottleConfigNif {
+    pub delete_per_call: u64,
+    pub get_per_byte: u64,
+    pub get_per_call: u64,
+    pub list_per_call: u64,
+    pub list_per_entry: u64,
+    pub list_with_delimiter_per_call: u64,
+    pub list_with_delimiter_per_entry: u64,
+    pub put_per_call: u64,
 }
@@ -0,0 +1,273 @@
+//! Object version listing and deletion for versioned buckets
+
+use crate::atoms;
+use crate::errors::map_error;
+use crate::gcs_api::{self, GcsApi};
+use crate::s3_api::{self, S3Api};
+use crate::shutdown;
+use crate::store::StoreWrapper;
+use crate::RUNTIME;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use object_store::path::Path;
+use object_store::Result;
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use reqwest::Method;
+use rustler::{Encoder, Env, NifMap, NifResult, ResourceArc, Term};
+use serde::Deserialize;
+
+/// A version of an object, as returned to Elixir
+#[derive(Debug, Clone, Default, NifMap)]
+pub struct ObjectVersionNif {
+    pub location: String,
+    pub version: String,
+    /// Whether this is the current version of the object
+    pub is_latest: bool,
+    /// Whether this version is a delete marker (S3)
+    pub delete_marker: bool,
+    pub last_modified: String,
+    pub size: u64,
+    pub etag: Option<String>,
+}
+
+/// Providers that keep previous versions of objects
+#[async_trait]
+pub trait Versioning: Send + Sync {
+    /// List all versions of the objects under a prefix, newest first per object
+    async fn list_versions(&self, prefix: &Path) -> Result<Vec<ObjectVersionNif>>;
+
+    /// Permanently delete one version of an object
+    async fn delete_version(&self, path: &Path, version: &str) -> Result<()>;
+}
+
+/// List prefix for a location prefix, matching whole path segments like `list`
+pub(crate) fn list_prefix(prefix: &Path) -> String {
+    if prefix.as_ref().is_empty() {
+        String::new()
+    } else {
+        format!("{}/", prefix)
+    }
+}
+
+/// Format a timestamp the same way as listing metadata
+pub(crate) fn format_timestamp(timestamp: &str) -> String {
+    DateTime::parse_from_rfc3339(timestamp)
+        .map(|t| t.with_timezone(&Utc).to_string())
+        .unwrap_or_else(|_| timestamp.to_string())
+}
+
+/// One page of an S3 `ListVersionsResult` document
+#[derive(Default)]
+struct S3VersionsPage {
+    versions: Vec<ObjectVersionNif>,
+    truncated: bool,
+    next_key_marker: Option<String>,
+    next_version_marker: Option<String>,
+}
+
+fn parse_s3_versions(body: &[u8]) -> std::result::Result<S3VersionsPage, quick_xml::Error> {
+    let mut reader = Reader::from_reader(body);
+    let mut buf = Vec::new();
+    let mut page = S3VersionsPage::default();
+    let mut entry: Option<ObjectVersionNif> = None;
+    let mut element = Vec::new();
+
+    loop {
+        match reader.read_event_into(&mut buf)? {
+            Event::Start(start) => {
+                element = start.name().as_ref().to_vec();
+                match element.as_slice() {
+                    b"Version" => entry = Some(ObjectVersionNif::default()),
+                    b"DeleteMarker" => {
+                        entry = Some(ObjectVersionNif {
+                            delete_marker: true,
+                            ..Default::default()
+                        })
+                    }
+                    _ => {}
+                }
+            }
+            Event::Text(text) => {
+                let text = text.unescape()?.into_owned();
+                match (&mut entry, element.as_slice()) {
+                    (Some(version), b"Key") => version.location = text,
+                    (Some(version), b"VersionId") => version.version = text,
+                    (Some(version), b"IsLatest") => version.is_latest = text == "true",
+                    (Some(version), b"LastModified") => {
+                        version.last_modified = format_timestamp(&text)
+                    }
+                    (Some(version), b"ETag") => version.etag = Some(text),
+                    (Some(version), b"Size") => version.size = text.parse().unwrap_or(0),
+                    (None, b"IsTruncated") => page.truncated = text == "true",
+                    (None, b"NextKeyMarker") => page.next_key_marker = Some(text),
+                    (None, b"NextVersionIdMarker") => page.next_version_marker = Some(text),
+                    _ => {}
+                }
+            }
+            Event::End(end) => {
+                if matches!(end.name().as_ref(), b"Version" | b"DeleteMarker") {
+                    page.versions.extend(entry.take());
+                }
+                element.clear();
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+        buf.clear();
+    }
+
+    Ok(page)
+}
+
+#[async_trait]
+impl Versioning for S3Api {
+    async fn list_versions(&self, prefix: &Path) -> Result<Vec<ObjectVersionNif>> {
+        let prefix = list_prefix(prefix);
+        let mut versions = Vec::new();
+        let mut markers: Option<(String, Option<String>)> = None;
+
+        loop {
+            let mut query = format!("versions&prefix={}", s3_api::encode_query(&prefix));
+            if let Some((key, version)) = &markers {
+                query.push_str(&format!("&key-marker={}", s3_api::encode_query(key)));
+                if let Some(version) = version {
+                    query.push_str(&format!(
+                        "&version-id-marker={}",
+                        s3_api::encode_query(version)
+                    ));
+                }
+            }
+
+            let body = self
+                .send(Method::GET, &Path::default(), Some(&query), &[], None)
+                .await?;
+            let page = parse_s3_versions(&body)
+                .map_err(|e| s3_api::generic(format!("Invalid versions response: {}", e)))?;
+            versions.extend(page.versions);
+
+            match (page.truncated, page.next_key_marker) {
+                (true, Some(key)) => markers = Some((key, page.next_version_marker)),
+                _ => return Ok(versions),
+            }
+        }
+    }
+
+    async fn delete_version(&self, path: &Path, version: &str) -> Result<()> {
+        self.delete(path, None, Some(version)).await
+    }
+}
+
+/// One page of a GCS `objects.list` response
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GcsVersionsPage {
+    #[serde(default)]
+    items: Vec<GcsObject>,
+    next_page_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GcsObject {
+    name: String,
+    generation: String,
+    size: String,
+    updated: String,
+    etag: Option<String>,
+    /// Set on noncurrent generations
+    time_deleted: Option<String>,
+}
+
+#[async_trait]
+impl Versioning for GcsApi {
+    async fn list_versions(&self, prefix: &Path) -> Result<Vec<ObjectVersionNif>> {
+        let prefix = list_prefix(prefix);
+        let mut versions = Vec::new();
+        let mut page_token: Option<String> = None;
+
+        loop {
+            let mut query = vec![("versions", "true"), ("prefix", prefix.as_str())];
+            if let Some(token) = &page_token {
+                query.push(("pageToken", token.as_str()));
+            }
+
+            let body = self.send(Method::GET, &Path::default(), &query).await?;
+            let page: GcsVersionsPage = serde_json::from_slice(&body)
+                .map_err(|e| gcs_api::generic(format!("Invalid versions response: {}", e)))?;
+
+            versions.extend(page.items.into_iter().map(|object| ObjectVersionNif {
+                location: object.name,
+                version: object.generation,
+                is_latest: object.time_deleted.is_none(),
+                delete_marker: false,
+                last_modified: format_timestamp(&object.updated),
+                size: object.size.parse().unwrap_or(0),
+                etag: object.etag,
+            }));
+
+            match page.next_page_token {
+                Some(token) => page_token = Some(token),
+                None => break,
+            }
+        }
+
+        // GCS lists generations oldest first; match S3's newest-first order
+        versions.sort_by(|a, b| {
+            a.location.cmp(&b.location).then_with(|| {
+                let generation = |v: &ObjectVersionNif| v.version.parse::<u64>().unwrap_or(0);
+                generation(b).cmp(&generation(a))
+            })
+        });
+        Ok(versions)
+    }
+
+    async fn delete_version(&self, path: &Path, version: &str) -> Result<()> {
+        self.send(Method::DELETE, path, &[("generation", version)])
+            .await
+            .map(|_| ())
+    }
+}
+
+/// List all versions of the objects under a prefix
+///
+/// Returns a list of version maps, or `:not_supported` for stores without
+/// object versioning.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn list_versions<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    prefix: Option<String>,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let Some(versioning) = store.versioning.clone() else {
+        return Ok(atoms::not_supported().to_term(env));
+    };
+
+    let prefix = prefix.map(Path::from).unwrap_or_default();
+    match RUNTIME.block_on(versioning.list_versions(&prefix)) {
+        Ok(versions) => Ok(versions.encode(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
+
+/// Permanently delete one version of an object
+///
+/// Returns `:not_supported` for stores without object versioning.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn delete_version<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    path: String,
+    version: String,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let Some(versioning) = store.versioning.clone() else {
+        return Ok(atoms::not_supported().to_term(env));
+    };
+
+    match RUNTIME.block_on(versioning.delete_version(&Path::from(path), &version)) {
+        Ok(()) => Ok(atoms::ok().to_term(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
@@ -0,0 +1,740 @@
+use crate::atoms;
+use crate::RUNTIME;
+use async_trait::async_trait;
+use bytes::Bytes;
+use chrono::Utc;
+use futures::stream::{self, BoxStream};
+use futures::{StreamExt, TryStreamExt};
+use object_store::path::Path;
+use object_store::{
+    Attributes, DynObjectStore, Error, GetOptions, GetResult, GetResultPayload, ListResult,
+    MultipartUpload, ObjectMeta, ObjectStore, PutMode, PutMultipartOpts, PutOptions, PutPayload,
+    PutResult, Result, UploadPart,
+};
+use rustler::{Atom, NifResult};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+
+/// Stripes of the locks ordering background uploads of the same location
+const UPLOAD_LOCKS: usize = 64;
+
+/// How writes through a cache store reach the remote and local stores
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WritePolicy {
+    /// Write to the remote store and drop the cached copy
+    Around,
+    /// Write to the remote store, then cache what was written
+    Through,
+    /// Write to the local store and upload in the background
+    Back,
+}
+
+impl WritePolicy {
+    /// Policy for `:write_around` (the default), `:write_through` or
+    /// `:write_back`
+    pub fn from_atom(atom: Option<Atom>) -> NifResult<Self> {
+        match atom {
+            None => Ok(WritePolicy::Around),
+            Some(a) if a == atoms::write_around() => Ok(WritePolicy::Around),
+            Some(a) if a == atoms::write_through() => Ok(WritePolicy::Through),
+            Some(a) if a == atoms::write_back() => Ok(WritePolicy::Back),
+            Some(_) => Err(rustler::Error::BadArg),
+        }
+    }
+}
+
+/// Remote object held in the local cache
+#[derive(Debug, Clone)]
+struct Entry {
+    /// Metadata of the remote object when it was cached
+    meta: ObjectMeta,
+    attributes: Attributes,
+    /// When the entry was cached or last revalidated
+    validated_at: Instant,
+    last_access: Instant,
+    /// Options of a write-back upload that hasn't reached the remote store
+    upload: Option<PutOptions>,
+    /// Changes every time the location is cached again
+    generation: u64,
+}
+
+impl Entry {
+    fn new(meta: ObjectMeta, attributes: Attributes, upload: Option<PutOptions>) -> Self {
+        let now = Instant::now();
+        Self {
+            meta,
+            attributes,
+            validated_at: now,
+            last_access: now,
+            upload,
+            generation: 0,
+        }
+    }
+}
+
+/// Entries held in the local store, by location
+#[derive(Debug, Default)]
+struct Index {
+    entries: HashMap<Path, Entry>,
+    /// Total size of the entries
+    size: u64,
+    next_generation: u64,
+}
+
+impl Index {
+    /// Add an entry, returning its generation
+    fn insert(&mut self, mut entry: Entry) -> u64 {
+        self.remove(&entry.meta.location);
+        self.next_generation += 1;
+        entry.generation = self.next_generation;
+        self.size += entry.meta.size as u64;
+        self.entries.insert(entry.meta.location.clone(), entry);
+        self.next_generation
+    }
+
+    fn remove(&mut self, location: &Path) -> Option<Entry> {
+        let entry = self.entries.remove(location)?;
+        self.size -= entry.meta.size as u64;
+        Some(entry)
+    }
+
+    /// Drop least recently used entries until the total fits in `max_size`,
+    /// returning their locations. Entries waiting to be uploaded are kept.
+    fn evict(&mut self, max_size: u64) -> Vec<Path> {
+        let mut evicted = Vec::new();
+        while self.size > max_size {
+            let Some(oldest) = self
+                .entries
+                .values()
+                .filter(|entry| entry.upload.is_none())
+                .min_by_key(|entry| entry.last_access)
+                .map(|entry| entry.meta.location.clone())
+            else {
+                break;
+            };
+            self.remove(&oldest);
+            evicted.push(oldest);
+        }
+        evicted
+    }
+}
+
+/// State of a cache, shared with its uploads and background tasks
+#[derive(Debug)]
+struct Cache {
+    remote: Arc<DynObjectStore>,
+    local: Arc<DynObjectStore>,
+    index: Mutex<Index>,
+    max_size: Option<u64>,
+    upload_locks: Vec<tokio::sync::Mutex<()>>,
+    /// Background uploads that haven't been waited for
+    pending: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl Cache {
+    fn fits(&self, size: usize) -> bool {
+        self.max_size.is_none_or(|max_size| size as u64 <= max_size)
+    }
+
+    /// Lock ordering uploads and deletes of `location`
+    fn upload_lock(&self, location: &Path) -> &tokio::sync::Mutex<()> {
+        let mut hasher = DefaultHasher::new();
+        location.hash(&mut hasher);
+        &self.upload_locks[hasher.finish() as usize % UPLOAD_LOCKS]
+    }
+
+    /// Forget the entry for `location` and delete its local copy, returning
+    /// the entry
+    async fn invalidate(&self, location: &Path) -> Option<Entry> {
+        let entry = self.index.lock().unwrap().remove(location)?;
+        let _ = self.local.delete(location).await;
+        Some(entry)
+    }
+
+    /// Index an entry whose data is in the local store, evicting entries
+    /// beyond the maximum size; returns its generation
+    async fn index(&self, entry: Entry) -> u64 {
+        let (generation, evicted) = {
+            let mut index = self.index.lock().unwrap();
+            let generation = index.insert(entry);
+            let evicted = self
+                .max_size
+                .map(|max_size| index.evict(max_size))
+                .unwrap_or_default();
+            (generation, evicted)
+        };
+        for location in evicted {
+            let _ = self.local.delete(&location).await;
+        }
+        generation
+    }
+
+    /// Write `data` to the local store and index it, or drop the entry for
+    /// its location if it can't be cached; returns the generation
+    async fn store(&self, entry: Entry, data: PutPayload) -> Option<u64> {
+        let location = entry.meta.location.clone();
+        if !self.fits(entry.meta.size) {
+            self.invalidate(&location).await;
+            return None;
+        }
+
+        match self.local.put(&location, data).await {
+            Ok(_) => Some(self.index(entry).await),
+            Err(_) => {
+                self.invalidate(&location).await;
+                None
+            }
+        }
+    }
+
+    /// Upload the local copy of `location` if it is still the one cached as
+    /// `generation`
+    async fn upload(&self, location: &Path, generation: u64) -> Result<()> {
+        let _lock = self.upload_lock(location).lock().await;
+
+        let opts = {
+            let index = self.index.lock().unwrap();
+            match index.entries.get(location) {
+                Some(entry) if entry.generation == generation => entry.upload.clone(),
+                _ => None,
+            }
+        };
+        let Some(opts) = opts else {
+            return Ok(());
+        };
+
+        let data = self.local.get(location).await?.bytes().await?;
+        let result = self.remote.put_opts(location, data.into(), opts).await?;
+
+        if let Some(entry) = self.index.lock().unwrap().entries.get_mut(location) {
+            if entry.generation == generation {
+                entry.upload = None;
+                entry.meta.e_tag = result.e_tag;
+                entry.meta.version = result.version;
+                entry.validated_at = Instant::now();
+            }
+        }
+        Ok(())
+    }
+
+    /// Upload `location` in the background
+    ///
+    /// Failed uploads stay pending and are retried by `flush`.
+    fn upload_later(self: &Arc<Self>, location: Path, generation: u64) {
+        let cache = self.clone();
+        let handle = RUNTIME.spawn(async move {
+            let _ = cache.upload(&location, generation).await;
+        });
+
+        let mut pending = self.pending.lock().unwrap();
+        pending.retain(|handle| !handle.is_finished());
+        pending.push(handle);
+    }
+
+    /// Wait for background uploads, then upload every entry still pending
+    async fn flush(&self) -> Result<()> {
+        loop {
+            let handles = std::mem::take(&mut *self.pending.lock().unwrap());
+            if handles.is_empty() {
+                break;
+            }
+            for handle in handles {
+                let _ = handle.await;
+            }
+        }
+
+        let waiting: Vec<(Path, u64)> = self
+            .index
+            .lock()
+            .unwrap()
+            .entries
+            .values()
+            .filter(|entry| entry.upload.is_some())
+            .map(|entry| (entry.meta.location.clone(), entry.generation))
+            .collect();
+
+        for (location, generation) in waiting {
+            self.upload(&location, generation).await?;
+        }
+        Ok(())
+    }
+}
+
+/// Whether the cache may answer a get made with `options`
+///
+/// Conditional and versioned gets go to the remote store, which knows about
+/// versions the cache doesn't hold.
+fn cacheable(options: &GetOptions) -> bool {
+    options.if_match.is_none()
+        && options.if_none_match.is_none()
+        && options.if_modified_since.is_none()
+        && options.if_unmodified_since.is_none()
+        && options.version.is_none()
+}
+
+/// Metadata of an object just written
+fn written_meta(location: &Path, size: usize, result: Option<PutResult>) -> ObjectMeta {
+    let (e_tag, version) = match result {
+        Some(result) => (result.e_tag, result.version),
+        None => (None, None),
+    };
+    ObjectMeta {
+        location: location.clone(),
+        last_modified: Utc::now(),
+        size,
+        e_tag,
+        version,
+    }
+}
+
+/// Store wrapper serving reads of a remote store from a local cache
+///
+/// A full get that misses the cache reads the object from the remote store,
+/// writes it to the local store under the same location and returns it. Later
+/// gets, range reads and heads are served from the local copy. Entries are
+/// keyed by location and ETag: once an entry is older than `ttl`, the next
+/// read revalidates it with a conditional head, and an object whose ETag
+/// changed is fetched again. Without a `ttl`, every read revalidates.
+///
+/// With `max_size`, the least recently read entries are evicted once the
+/// cached objects add up to more than `max_size` bytes; larger objects aren't
+/// cached. Objects without an ETag aren't cached either. Failures of the local
+/// store fall back to the remote store rather than failing reads.
+///
+/// Writes follow the `WritePolicy`. Write-back puts and multipart uploads
+/// return once the local store has the data; uploads of the same location are
+/// ordered, and an upload superseded by a newer write is skipped. Until it is
+/// uploaded, an object is served from the local store without revalidation,
+/// isn't evicted and doesn't show up in listings. Copies and renames flush
+/// pending uploads first. Conditional puts are always written through.
+#[derive(Debug)]
+pub struct CacheStore {
+    cache: Arc<Cache>,
+    ttl: Option<Duration>,
+    policy: WritePolicy,
+}
+
+impl CacheStore {
+    pub fn new(
+        remote: Arc<DynObjectStore>,
+        local: Arc<DynObjectStore>,
+        ttl: Option<Duration>,
+        max_size: Option<u64>,
+        policy: WritePolicy,
+    ) -> Self {
+        Self {
+            cache: Arc::new(Cache {
+                remote,
+                local,
+                index: Mutex::new(Index::default()),
+                max_size,
+                upload_locks: (0..UPLOAD_LOCKS)
+                    .map(|_| tokio::sync::Mutex::new(()))
+                    .collect(),
+                pending: Mutex::new(Vec::new()),
+            }),
+            ttl,
+            policy,
+        }
+    }
+
+    fn remote(&self) -> &DynObjectStore {
+        self.cache.remote.as_ref()
+    }
+
+    /// Upload every write-back write that hasn't reached the remote store
+    pub async fn flush(&self) -> Result<()> {
+        self.cache.flush().await
+    }
+
+    /// Entry for `location`, revalidated against the remote store if it is
+    /// older than the TTL; `None` if there is no entry or it is outdated
+    async fn lookup(&self, location: &Path) -> Result<Option<Entry>> {
+        let Some(entry) = self
+            .cache
+            .index
+            .lock()
+            .unwrap()
+            .entries
+            .get(location)
+            .cloned()
+        else {
+            return Ok(None);
+        };
+
+        if entry.upload.is_some() {
+            return Ok(Some(entry));
+        }
+        if let Some(ttl) = self.ttl {
+            if entry.validated_at.elapsed() < ttl {
+                return Ok(Some(entry));
+            }
+        }
+
+        let options = GetOptions {
+            head: true,
+            if_none_match: entry.meta.e_tag.clone(),
+            ..Default::default()
+        };
+        match self.remote().get_opts(location, options).await {
+            Err(Error::NotModified { .. }) => {
+                if let Some(entry) = self.cache.index.lock().unwrap().entries.get_mut(location) {
+                    entry.validated_at = Instant::now();
+                }
+                Ok(Some(entry))
+            }
+            Ok(_) | Err(Error::NotFound { .. }) => {
+                self.cache.invalidate(location).await;
+                Ok(None)
+            }
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Read a cached entry from the local store, or `None` if that fails and
+    /// the remote store has the object
+    async fn serve(&self, entry: Entry, options: &GetOptions) -> Result<Option<GetResult>> {
+        let location = &entry.meta.location;
+        let local_options = GetOptions {
+            range: options.range.clone(),
+            head: options.head,
+            ..Default::default()
+        };
+
+        match self.cache.local.get_opts(location, local_options).await {
+            Ok(local) => {
+                if let Some(cached) = self.cache.index.lock().unwrap().entries.get_mut(location) {
+                    cached.last_access = Instant::now();
+                }
+                let range = local.range.clone();
+                Ok(Some(GetResult {
+                    payload: GetResultPayload::Stream(local.into_stream()),
+                    meta: entry.meta,
+                    range,
+                    attributes: entry.attributes,
+                }))
+            }
+            // The only copy of an object waiting to be uploaded
+            Err(e) if entry.upload.is_some() => Err(e),
+            Err(_) => {
+                self.cache.index.lock().unwrap().remove(location);
+                Ok(None)
+            }
+        }
+    }
+
+    /// Get an object from the remote store, caching it if it fits
+    async fn fill(&self, location: &Path) -> Result<GetResult> {
+        let result = self
+            .remote()
+            .get_opts(location, GetOptions::default())
+            .await?;
+        if result.meta.e_tag.is_none() || !self.cache.fits(result.meta.size) {
+            return Ok(result);
+        }
+
+        let meta = result.meta.clone();
+        let attributes = result.attributes.clone();
+        let range = result.range.clone();
+        let data: Bytes = result.bytes().await?;
+
+        let entry = Entry::new(meta.clone(), attributes.clone(), None);
+        self.cache.store(entry, data.clone().into()).await;
+
+        Ok(GetResult {
+            payload: GetResultPayload::Stream(stream::once(async { Ok(data) }).boxed()),
+            meta,
+            range,
+            attributes,
+        })
+    }
+
+    /// Write to the remote store, caching the written object
+    async fn put_through(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        let attributes = opts.attributes.clone();
+        let result = match self
+            .remote()
+            .put_opts(location, payload.clone(), opts)
+            .await
+        {
+            Ok(result) => result,
+            Err(e) => {
+                self.cache.invalidate(location).await;
+                return Err(e);
+            }
+        };
+
+        if result.e_tag.is_some() {
+            let meta = written_meta(location, payload.content_length(), Some(result.clone()));
+            self.cache
+                .store(Entry::new(meta, attributes, None), payload)
+                .await;
+        } else {
+            self.cache.invalidate(location).await;
+        }
+        Ok(result)
+    }
+
+    /// Write to the local store, uploading in the background
+    async fn put_back(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        let meta = written_meta(location, payload.content_length(), None);
+        let entry = Entry::new(meta, opts.attributes.clone(), Some(opts.clone()));
+
+        match self.cache.store(entry, payload.clone()).await {
+            Some(generation) => {
+                self.cache.upload_later(location.clone(), generation);
+                Ok(PutResult {
+                    e_tag: None,
+                    version: None,
+                })
+            }
+            // Too large to cache
+            None => self.remote().put_opts(location, payload, opts).await,
+        }
+    }
+}
+
+impl fmt::Display for CacheStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CacheStore({}, {})", self.cache.remote, self.cache.local)
+    }
+}
+
+#[async_trait]
+impl ObjectStore for CacheStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        match self.policy {
+            WritePolicy::Back if opts.mode == PutMode::Overwrite => {
+                self.put_back(location, payload, opts).await
+            }
+            WritePolicy::Back | WritePolicy::Through => {
+                self.put_through(location, payload, opts).await
+            }
+            WritePolicy::Around => {
+                let result = self.remote().put_opts(location, payload, opts).await;
+                self.cache.invalidate(location).await;
+                result
+            }
+        }
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        if self.policy == WritePolicy::Back {
+            let upload = self
+                .cache
+                .local
+                .put_multipart_opts(location, PutMultipartOpts::default())
+                .await?;
+
+            return Ok(Box::new(WriteBackUpload {
+                inner: upload,
+                cache: self.cache.clone(),
+                location: location.clone(),
+                opts: PutOptions {
+                    mode: PutMode::Overwrite,
+                    tags: opts.tags,
+                    attributes: opts.attributes,
+                },
+                size: 0,
+            }));
+        }
+
+        let upload = self.remote().put_multipart_opts(location, opts).await?;
+        Ok(Box::new(CacheUpload {
+            inner: upload,
+            cache: self.cache.clone(),
+            location: location.clone(),
+        }))
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        if !cacheable(&options) {
+            return self.remote().get_opts(location, options).await;
+        }
+
+        if let Some(entry) = self.lookup(location).await? {
+            if let Some(result) = self.serve(entry, &options).await? {
+                return Ok(result);
+            }
+        }
+
+        // Heads and range reads don't fetch the whole object to cache it
+        if options.head || options.range.is_some() {
+            return self.remote().get_opts(location, options).await;
+        }
+        self.fill(location).await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        let options = GetOptions {
+            head: true,
+            ..Default::default()
+        };
+        Ok(self.get_opts(location, options).await?.meta)
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        let _lock = self.cache.upload_lock(location).lock().await;
+        let entry = self.cache.invalidate(location).await;
+
+        match self.remote().delete(location).await {
+            // Deleted before it was uploaded
+            Err(Error::NotFound { .. }) if entry.is_some_and(|e| e.upload.is_some()) => Ok(()),
+            result => result,
+        }
+    }
+
+    fn delete_stream<'a>(
+        &'a self,
+        locations: BoxStream<'a, Result<Path>>,
+    ) -> BoxStream<'a, Result<Path>> {
+        if self.policy == WritePolicy::Back {
+            return locations
+                .and_then(move |location| async move {
+                    self.delete(&location).await?;
+                    Ok(location)
+                })
+                .boxed();
+        }
+
+        self.remote()
+            .delete_stream(locations)
+            .and_then(move |location| async move {
+                self.cache.invalidate(&location).await;
+                Ok(location)
+            })
+            .boxed()
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.remote().list(prefix)
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.remote().list_with_offset(prefix, offset)
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        self.remote().list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        self.flush().await?;
+        let result = self.remote().copy(from, to).await;
+        self.cache.invalidate(to).await;
+        result
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        self.flush().await?;
+        let result = self.remote().rename(from, to).await;
+        self.cache.invalidate(from).await;
+        self.cache.invalidate(to).await;
+        result
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.flush().await?;
+        let result = self.remote().copy_if_not_exists(from, to).await;
+        self.cache.invalidate(to).await;
+        result
+    }
+
+    async fn rename_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.flush().await?;
+        let result = self.remote().rename_if_not_exists(from, to).await;
+        self.cache.invalidate(from).await;
+        self.cache.invalidate(to).await;
+        result
+    }
+}
+
+/// Multipart upload invalidating the cached copy of its object on completion
+#[derive(Debug)]
+struct CacheUpload {
+    inner: Box<dyn MultipartUpload>,
+    cache: Arc<Cache>,
+    location: Path,
+}
+
+#[async_trait]
+impl MultipartUpload for CacheUpload {
+    fn put_part(&mut self, data: PutPayload) -> UploadPart {
+        self.inner.put_part(data)
+    }
+
+    async fn complete(&mut self) -> Result<PutResult> {
+        let result = self.inner.complete().await;
+        self.cache.invalidate(&self.location).await;
+        result
+    }
+
+    async fn abort(&mut self) -> Result<()> {
+        self.inner.abort().await
+    }
+}
+
+/// Multipart upload to the local store, uploaded to the remote store in the
+/// background once completed
+#[derive(Debug)]
+struct WriteBackUpload {
+    inner: Box<dyn MultipartUpload>,
+    cache: Arc<Cache>,
+    location: Path,
+    opts: PutOptions,
+    size: usize,
+}
+
+#[async_trait]
+impl MultipartUpload for WriteBackUpload {
+    fn put_part(&mut self, data: PutPayload) -> UploadPart {
+        self.size += data.content_length();
+        self.inner.put_part(data)
+    }
+
+    async fn complete(&mut self) -> Result<PutResult> {
+        self.inner.complete().await?;
+
+        let meta = written_meta(&self.location, self.size, None);
+        let entry = Entry::new(meta, self.opts.attributes.clone(), Some(self.opts.clone()));
+        let generation = self.cache.index(entry).await;
+        self.cache.upload_later(self.location.clone(), generation);
+
+        Ok(PutResult {
+            e_tag: None,
+            version: None,
+        })
+    }
+
+    async fn abort(&mut self) -> Result<()> {
+        self.inner.abort().await
+    }
+}
@@ -0,0 +1,505 @@
+use crate::atoms;
+use crate::backend::resolve_range;
+use async_trait::async_trait;
+use bytes::{Bytes, BytesMut};
+use flate2::write::{GzEncoder, MultiGzDecoder};
+use flate2::Compression;
+use futures::stream::{self, BoxStream};
+use futures::{StreamExt, TryStreamExt};
+use object_store::path::Path;
+use object_store::{
+    Attribute, DynObjectStore, Error, GetOptions, GetResult, GetResultPayload, ListResult,
+    MultipartUpload, ObjectMeta, ObjectStore, PutMultipartOpts, PutOptions, PutPayload, PutResult,
+    Result, UploadPart,
+};
+use rustler::{Atom, NifResult};
+use std::fmt;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+const STORE: &str = "Compression";
+
+/// Compressed bytes buffered before a multipart upload sends them as a part
+const PART_SIZE: usize = 5 * 1024 * 1024;
+
+/// Compression format of a `CompressedStore`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Codec {
+    Gzip,
+    Zstd,
+}
+
+impl Codec {
+    /// Codec for `:gzip` or `:zstd`, checking `level` against its range
+    /// (0-9 for gzip, 1-22 for zstd)
+    pub fn from_atom(atom: Atom, level: Option<i32>) -> NifResult<(Self, i32)> {
+        let (codec, levels, default) = match atom {
+            a if a == atoms::gzip() => (Codec::Gzip, 0..=9, 6),
+            a if a == atoms::zstd() => (Codec::Zstd, 1..=22, 3),
+            _ => return Err(rustler::Error::BadArg),
+        };
+
+        match level {
+            None => Ok((codec, default)),
+            Some(level) if levels.contains(&level) => Ok((codec, level)),
+            Some(_) => Err(rustler::Error::BadArg),
+        }
+    }
+
+    /// `Content-Encoding` of data in this format
+    fn encoding(&self) -> &'static str {
+        match self {
+            Codec::Gzip => "gzip",
+            Codec::Zstd => "zstd",
+        }
+    }
+
+    /// Format of data starting with `prefix`, judged by its magic bytes
+    fn sniff(prefix: &[u8]) -> Option<Self> {
+        match prefix {
+            [0x1f, 0x8b, ..] => Some(Codec::Gzip),
+            [0x28, 0xb5, 0x2f, 0xfd, ..] => Some(Codec::Zstd),
+            _ => None,
+        }
+    }
+}
+
+fn compression_error(e: std::io::Error) -> Error {
+    Error::Generic {
+        store: STORE,
+        source: Box::new(e),
+    }
+}
+
+/// Streaming compressor writing into a buffer that can be drained as it fills
+enum Encoder {
+    Gzip(GzEncoder<Vec<u8>>),
+    Zstd(zstd::stream::write::Encoder<'static, Vec<u8>>),
+}
+
+impl Encoder {
+    fn new(codec: Codec, level: i32) -> Result<Self> {
+        Ok(match codec {
+            Codec::Gzip => {
+                Encoder::Gzip(GzEncoder::new(Vec::new(), Compression::new(level as u32)))
+            }
+            Codec::Zstd => Encoder::Zstd(
+                zstd::stream::write::Encoder::new(Vec::new(), level).map_err(compression_error)?,
+            ),
+        })
+    }
+
+    fn write(&mut self, data: &[u8]) -> Result<()> {
+        match self {
+            Encoder::Gzip(encoder) => encoder.write_all(data),
+            Encoder::Zstd(encoder) => encoder.write_all(data),
+        }
+        .map_err(compression_error)
+    }
+
+    /// Number of compressed bytes not taken yet
+    fn buffered(&self) -> usize {
+        match self {
+            Encoder::Gzip(encoder) => encoder.get_ref().len(),
+            Encoder::Zstd(encoder) => encoder.get_ref().len(),
+        }
+    }
+
+    /// Take the compressed bytes produced so far
+    fn take(&mut self) -> Vec<u8> {
+        match self {
+            Encoder::Gzip(encoder) => std::mem::take(encoder.get_mut()),
+            Encoder::Zstd(encoder) => std::mem::take(encoder.get_mut()),
+        }
+    }
+
+    /// Finish the stream, returning the compressed bytes not taken yet
+    fn finish(self) -> Result<Vec<u8>> {
+        match self {
+            Encoder::Gzip(encoder) => encoder.finish(),
+            Encoder::Zstd(encoder) => encoder.finish(),
+        }
+        .map_err(compression_error)
+    }
+}
+
+/// Streaming decompressor writing into a buffer, like `Encoder`
+enum Decoder {
+    Gzip(MultiGzDecoder<Vec<u8>>),
+    Zstd(zstd::stream::write::Decoder<'static, Vec<u8>>),
+}
+
+impl Decoder {
+    fn new(codec: Codec) -> Result<Self> {
+        Ok(match codec {
+            Codec::Gzip => Decoder::Gzip(MultiGzDecoder::new(Vec::new())),
+            Codec::Zstd => Decoder::Zstd(
+                zstd::stream::write::Decoder::new(Vec::new()).map_err(compression_error)?,
+            ),
+        })
+    }
+
+    /// Decompress a chunk, returning the data decoded so far
+    fn write(&mut self, data: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Decoder::Gzip(decoder) => decoder.write_all(data).and_then(|_| decoder.flush()),
+            Decoder::Zstd(decoder) => decoder.write_all(data).and_then(|_| decoder.flush()),
+        }
+        .map_err(compression_error)?;
+        Ok(self.take())
+    }
+
+    /// Check that the data was complete, returning what's left to decode
+    fn finish(&mut self) -> Result<Vec<u8>> {
+        match self {
+            Decoder::Gzip(decoder) => decoder.try_finish(),
+            Decoder::Zstd(decoder) => decoder.flush(),
+        }
+        .map_err(compression_error)?;
+        Ok(self.take())
+    }
+
+    fn take(&mut self) -> Vec<u8> {
+        match self {
+            Decoder::Gzip(decoder) => std::mem::take(decoder.get_mut()),
+            Decoder::Zstd(decoder) => std::mem::take(decoder.get_mut()),
+        }
+    }
+}
+
+/// Decompress a stream of compressed data chunk by chunk
+fn decode(
+    stream: BoxStream<'static, Result<Bytes>>,
+    mut decoder: Decoder,
+) -> BoxStream<'static, Result<Bytes>> {
+    stream
+        .map(Some)
+        .chain(stream::once(async { None }))
+        .map(move |item| match item {
+            Some(Ok(bytes)) => decoder.write(&bytes),
+            Some(Err(e)) => Err(e),
+            None => decoder.finish(),
+        })
+        .try_filter(|decoded| futures::future::ready(!decoded.is_empty()))
+        .map_ok(Bytes::from)
+        .boxed()
+}
+
+/// Read at least `n` bytes off the front of a stream, unless it ends first
+///
+/// Returns the bytes read and a stream yielding them followed by the rest.
+async fn peek(
+    mut stream: BoxStream<'static, Result<Bytes>>,
+    n: usize,
+) -> Result<(Bytes, BoxStream<'static, Result<Bytes>>)> {
+    let mut prefix = BytesMut::new();
+
+    while prefix.len() < n {
+        match stream.next().await {
+            Some(chunk) => prefix.extend_from_slice(&chunk?),
+            None => break,
+        }
+    }
+
+    let prefix = prefix.freeze();
+    let replay = stream::once(futures::future::ready(Ok(prefix.clone())));
+    Ok((prefix, replay.chain(stream).boxed()))
+}
+
+/// Store wrapper compressing objects on write and decompressing them on read
+///
+/// Writes are compressed with the store's codec and tagged with a matching
+/// `Content-Encoding`, unless the write sets a content encoding itself, in
+/// which case its data is stored as given. Reads decompress objects whose data
+/// is gzip or zstd, judged by its magic bytes, when their content encoding is
+/// unset (stores without attributes) or names the same format; everything else
+/// is returned as stored. Decoded reads drop the content encoding attribute.
+///
+/// Sizes from listings and heads are the stored, compressed sizes. Ranges
+/// address the decompressed data, so range reads fetch and decompress the
+/// whole object.
+#[derive(Debug)]
+pub struct CompressedStore {
+    inner: Arc<DynObjectStore>,
+    codec: Codec,
+    level: i32,
+    /// Cleared once the inner store rejects the content encoding attribute
+    /// (local stores), after which writes are stored without it
+    attributes_supported: AtomicBool,
+}
+
+impl CompressedStore {
+    pub fn new(inner: Arc<DynObjectStore>, codec: Codec, level: i32) -> Self {
+        Self {
+            inner,
+            codec,
+            level,
+            attributes_supported: AtomicBool::new(true),
+        }
+    }
+
+    /// Whether a write with `attributes` should be compressed, after adding
+    /// the content encoding to them if the inner store supports it
+    fn tag(&self, attributes: &mut object_store::Attributes) -> bool {
+        if attributes.get(&Attribute::ContentEncoding).is_some() {
+            return false;
+        }
+        if self.attributes_supported.load(Ordering::Relaxed) {
+            attributes.insert(Attribute::ContentEncoding, self.codec.encoding().into());
+        }
+        true
+    }
+
+    /// Retry a write rejected because of the content encoding attribute
+    /// without it, if that's the only attribute
+    fn retry_untagged(
+        &self,
+        result: &Result<impl Sized>,
+        attributes: &object_store::Attributes,
+    ) -> bool {
+        let rejected = matches!(result, Err(Error::NotImplemented))
+            && attributes.len() == 1
+            && attributes.get(&Attribute::ContentEncoding).is_some();
+        if rejected {
+            self.attributes_supported.store(false, Ordering::Relaxed);
+        }
+        rejected
+    }
+
+    fn compress(&self, payload: &PutPayload) -> Result<PutPayload> {
+        let mut encoder = Encoder::new(self.codec, self.level)?;
+        for chunk in payload.iter() {
+            encoder.write(chunk)?;
+        }
+        Ok(PutPayload::from(encoder.finish()?))
+    }
+
+    /// Read the whole decompressed object and cut `range` out of it
+    async fn get_range_decoded(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        let range = options.range.clone();
+        let result = self
+            .get_opts(
+                location,
+                GetOptions {
+                    range: None,
+                    ..options
+                },
+            )
+            .await?;
+        let meta = result.meta.clone();
+        let attributes = result.attributes.clone();
+        let data = result.bytes().await?;
+
+        let range = match &range {
+            Some(range) => resolve_range(range, data.len(), location)?,
+            None => 0..data.len(),
+        };
+        let data = data.slice(range.clone());
+
+        Ok(GetResult {
+            payload: GetResultPayload::Stream(stream::once(async { Ok(data) }).boxed()),
+            meta,
+            range,
+            attributes,
+        })
+    }
+}
+
+impl fmt::Display for CompressedStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "CompressedStore({}, {})",
+            self.codec.encoding(),
+            self.inner
+        )
+    }
+}
+
+#[async_trait]
+impl ObjectStore for CompressedStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        mut opts: PutOptions,
+    ) -> Result<PutResult> {
+        if !self.tag(&mut opts.attributes) {
+            return self.inner.put_opts(location, payload, opts).await;
+        }
+
+        let payload = self.compress(&payload)?;
+        let result = self
+            .inner
+            .put_opts(location, payload.clone(), opts.clone())
+            .await;
+
+        if self.retry_untagged(&result, &opts.attributes) {
+            opts.attributes = Default::default();
+            return self.inner.put_opts(location, payload, opts).await;
+        }
+        result
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        mut opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        if !self.tag(&mut opts.attributes) {
+            return self.inner.put_multipart_opts(location, opts).await;
+        }
+
+        let mut result = self.inner.put_multipart_opts(location, opts.clone()).await;
+        if self.retry_untagged(&result, &opts.attributes) {
+            opts.attributes = Default::default();
+            result = self.inner.put_multipart_opts(location, opts).await;
+        }
+
+        Ok(Box::new(CompressedUpload {
+            inner: result?,
+            encoder: Some(Encoder::new(self.codec, self.level)?),
+        }))
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        if options.head {
+            return self.inner.get_opts(location, options).await;
+        }
+        if options.range.is_some() {
+            return self.get_range_decoded(location, options).await;
+        }
+
+        let result = self.inner.get_opts(location, options).await?;
+        let meta = result.meta.clone();
+        let range = result.range.clone();
+        let mut attributes = result.attributes.clone();
+        let encoding = attributes
+            .get(&Attribute::ContentEncoding)
+            .map(|e| e.to_string());
+
+        let (prefix, stream) = peek(result.into_stream(), 4).await?;
+        let codec = Codec::sniff(&prefix)
+            .filter(|codec| encoding.as_deref().is_none_or(|e| e == codec.encoding()));
+
+        let stream = match codec {
+            Some(codec) => {
+                attributes.remove(&Attribute::ContentEncoding);
+                decode(stream, Decoder::new(codec)?)
+            }
+            None => stream,
+        };
+
+        Ok(GetResult {
+            payload: GetResultPayload::Stream(stream),
+            meta,
+            range,
+            attributes,
+        })
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        self.inner.head(location).await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        self.inner.delete(location).await
+    }
+
+    fn delete_stream<'a>(
+        &'a self,
+        locations: BoxStream<'a, Result<Path>>,
+    ) -> BoxStream<'a, Result<Path>> {
+        self.inner.delete_stream(locations)
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.inner.list(prefix)
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.inner.list_with_offset(prefix, offset)
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.copy(from, to).await
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.rename(from, to).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.copy_if_not_exists(from, to).await
+    }
+
+    async fn rename_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.rename_if_not_exists(from, to).await
+    }
+}
+
+/// Multipart upload compressing its parts as one stream
+///
+/// Compressed data is sent in parts of at least `PART_SIZE` bytes, so part
+/// boundaries don't line up with the parts written to it.
+#[derive(Debug)]
+struct CompressedUpload {
+    inner: Box<dyn MultipartUpload>,
+    encoder: Option<Encoder>,
+}
+
+impl fmt::Debug for Encoder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Encoder::Gzip(_) => f.write_str("Encoder::Gzip"),
+            Encoder::Zstd(_) => f.write_str("Encoder::Zstd"),
+        }
+    }
+}
+
+#[async_trait]
+impl MultipartUpload for CompressedUpload {
+    fn put_part(&mut self, data: PutPayload) -> UploadPart {
+        let Some(encoder) = self.encoder.as_mut() else {
+            return Box::pin(futures::future::ready(Err(Error::Generic {
+                store: STORE,
+                source: "upload already completed".into(),
+            })));
+        };
+
+        for chunk in data.iter() {
+            if let Err(e) = encoder.write(chunk) {
+                return Box::pin(futures::future::ready(Err(e)));
+            }
+        }
+
+        if encoder.buffered() < PART_SIZE {
+            return Box::pin(futures::future::ready(Ok(())));
+        }
+        let part = encoder.take();
+        self.inner.put_part(PutPayload::from(part))
+    }
+
+    async fn complete(&mut self) -> Result<PutResult> {
+        if let Some(encoder) = self.encoder.take() {
+            let rest = encoder.finish()?;
+            if !rest.is_empty() {
+                self.inner.put_part(PutPayload::from(rest)).await?;
+            }
+        }
+        self.inner.complete().await
+    }
+
+    async fn abort(&mut self) -> Result<()> {
+        self.inner.abort().await
+    }
+}
@@ -0,0 +1,417 @@
+use crate::append::Append;
+use crate::incomplete_uploads::{IncompleteUploadNif, IncompleteUploads};
+use crate::paging::{ListPage, PagedListing};
+use crate::versions::{ObjectVersionNif, Versioning};
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use futures::stream::{self, BoxStream, StreamExt};
+use futures::FutureExt;
+use object_store::multipart::{MultipartStore, PartId};
+use object_store::path::Path;
+use object_store::{
+    DynObjectStore, Error, GetOptions, GetResult, GetResultPayload, ListResult, MultipartId,
+    MultipartUpload, ObjectMeta, ObjectStore, PutMultipartOpts, PutOptions, PutPayload, PutResult,
+    Result, UploadPart,
+};
+use rustler::{Resource, ResourceArc};
+use std::fmt;
+use std::future::Future;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::time::Instant;
+
+/// Store name used for operations that ran past their timeout, matched by
+/// `errors::map_error`
+pub const TIMEOUT_STORE: &str = "Timeout";
+
+/// Store name used for cancelled operations, matched by `errors::map_error`
+pub const CANCELLED_STORE: &str = "Cancelled";
+
+/// Cancellation token created by `new_op_ref` and passed to operations as
+/// their `:op_ref`
+///
+/// Cancelling is permanent: every operation given the token, running or
+/// started later, fails with `:cancelled`.
+pub struct OpRef {
+    cancelled: watch::Sender<bool>,
+}
+
+impl OpRef {
+    pub fn new() -> Self {
+        Self {
+            cancelled: watch::Sender::new(false),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.send_replace(true);
+    }
+
+    /// Wait until the token is cancelled
+    async fn cancelled(&self) {
+        let mut receiver = self.cancelled.subscribe();
+        // The sender lives as long as `self`, so waiting can't fail
+        let _ = receiver.wait_for(|cancelled| *cancelled).await;
+    }
+}
+
+impl Resource for OpRef {}
+
+/// Timeout and cancellation token bounding every call of a `DeadlineStore`,
+/// `DeadlineClient` or bounded `S3Api`
+///
+/// The timeout runs from the creation of the deadline, so one is created per
+/// operation and shared by every client the operation uses.
+#[derive(Clone)]
+pub struct Deadline {
+    timeout: Option<(Duration, Instant)>,
+    op_ref: Option<ResourceArc<OpRef>>,
+}
+
+impl fmt::Debug for Deadline {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Deadline")
+            .field("timeout", &self.timeout.map(|(timeout, _)| timeout))
+            .field("cancellable", &self.op_ref.is_some())
+            .finish()
+    }
+}
+
+impl Deadline {
+    pub fn new(timeout: Option<Duration>, op_ref: Option<ResourceArc<OpRef>>) -> Self {
+        Self {
+            timeout: timeout.map(|timeout| (timeout, Instant::now() + timeout)),
+            op_ref,
+        }
+    }
+
+    /// Run `future` unless the deadline passes or the token is cancelled first
+    ///
+    /// The future is dropped when it loses, which aborts its request.
+    pub(crate) async fn run<T>(&self, future: impl Future<Output = Result<T>>) -> Result<T> {
+        let expired = async {
+            match self.timeout {
+                Some((_, at)) => tokio::time::sleep_until(at).await,
+                None => std::future::pending().await,
+            }
+        };
+        let cancelled = async {
+            match &self.op_ref {
+                Some(op_ref) => op_ref.cancelled().await,
+                None => std::future::pending().await,
+            }
+        };
+
+        tokio::select! {
+            biased;
+            _ = cancelled => Err(Error::Generic {
+                store: CANCELLED_STORE,
+                source: "operation cancelled".into(),
+            }),
+            _ = expired => Err(Error::Generic {
+                store: TIMEOUT_STORE,
+                source: format!(
+                    "operation timed out after {} ms",
+                    self.timeout.map_or(0, |(timeout, _)| timeout.as_millis())
+                )
+                .into(),
+            }),
+            result = future => result,
+        }
+    }
+
+    /// Bound every item of a stream by the deadline, ending it after the
+    /// first error the deadline causes
+    fn stream<'a, T: Send + 'a>(
+        self,
+        stream: BoxStream<'a, Result<T>>,
+    ) -> BoxStream<'a, Result<T>> {
+        stream::unfold(Some((self, stream)), |state| async move {
+            let (deadline, mut stream) = state?;
+            match deadline.run(stream.next().map(Ok)).await {
+                Ok(Some(item)) => Some((item, Some((deadline, stream)))),
+                Ok(None) => None,
+                Err(e) => Some((Err(e), None)),
+            }
+        })
+        .boxed()
+    }
+}
+
+/// Store failing calls that outlive a timeout or whose operation is
+/// cancelled
+///
+/// A handle is created per operation and bounds everything the operation does
+/// through it, including reading the bodies of gets and every page of
+/// listings.
+pub struct DeadlineStore {
+    inner: Arc<DynObjectStore>,
+    deadline: Deadline,
+}
+
+impl DeadlineStore {
+    pub fn new(inner: Arc<DynObjectStore>, deadline: Deadline) -> Self {
+        Self { inner, deadline }
+    }
+}
+
+impl fmt::Debug for DeadlineStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeadlineStore")
+            .field("inner", &self.inner.to_string())
+            .field("deadline", &self.deadline)
+            .finish()
+    }
+}
+
+impl fmt::Display for DeadlineStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DeadlineStore({})", self.inner)
+    }
+}
+
+/// Multipart upload whose parts are bounded by the store's deadline
+#[derive(Debug)]
+struct DeadlineUpload {
+    upload: Box<dyn MultipartUpload>,
+    deadline: Deadline,
+}
+
+#[async_trait]
+impl MultipartUpload for DeadlineUpload {
+    fn put_part(&mut self, data: PutPayload) -> UploadPart {
+        let deadline = self.deadline.clone();
+        let part: BoxFuture<'static, Result<()>> = self.upload.put_part(data);
+
+        async move { deadline.run(part).await }.boxed()
+    }
+
+    async fn complete(&mut self) -> Result<PutResult> {
+        self.deadline.run(self.upload.complete()).await
+    }
+
+    async fn abort(&mut self) -> Result<()> {
+        // Aborting cleans up after a failed upload, so it isn't cut short
+        self.upload.abort().await
+    }
+}
+
+#[async_trait]
+impl ObjectStore for DeadlineStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        self.deadline
+            .run(self.inner.put_opts(location, payload, opts))
+            .await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        let upload = self
+            .deadline
+            .run(self.inner.put_multipart_opts(location, opts))
+            .await?;
+        Ok(Box::new(DeadlineUpload {
+            upload,
+            deadline: self.deadline.clone(),
+        }))
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        let result = self
+            .deadline
+            .run(self.inner.get_opts(location, options))
+            .await?;
+
+        let (meta, range, attributes) = (
+            result.meta.clone(),
+            result.range.clone(),
+            result.attributes.clone(),
+        );
+        let stream = self.deadline.clone().stream(result.into_stream());
+
+        Ok(GetResult {
+            payload: GetResultPayload::Stream(stream),
+            meta,
+            range,
+            attributes,
+        })
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        self.deadline
+            .run(self.inner.get_range(location, range))
+            .await
+    }
+
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<usize>]) -> Result<Vec<Bytes>> {
+        self.deadline
+            .run(self.inner.get_ranges(location, ranges))
+            .await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        self.deadline.run(self.inner.head(location)).await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        self.deadline.run(self.inner.delete(location)).await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.deadline.clone().stream(self.inner.list(prefix))
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.deadline
+            .clone()
+            .stream(self.inner.list_with_offset(prefix, offset))
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        self.deadline
+            .run(self.inner.list_with_delimiter(prefix))
+            .await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        self.deadline.run(self.inner.copy(from, to)).await
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        self.deadline.run(self.inner.rename(from, to)).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.deadline
+            .run(self.inner.copy_if_not_exists(from, to))
+            .await
+    }
+
+    async fn rename_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.deadline
+            .run(self.inner.rename_if_not_exists(from, to))
+            .await
+    }
+}
+
+/// Provider-specific client (low-level multipart uploads, paged listings,
+/// appends, versions, incomplete uploads) whose calls are bounded by a
+/// deadline, like the `DeadlineStore` it is created with
+pub struct DeadlineClient<T: ?Sized> {
+    inner: Arc<T>,
+    deadline: Deadline,
+}
+
+impl<T: ?Sized> DeadlineClient<T> {
+    pub fn new(inner: Arc<T>, deadline: Deadline) -> Self {
+        Self { inner, deadline }
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for DeadlineClient<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("DeadlineClient")
+            .field("deadline", &self.deadline)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl<T: MultipartStore + ?Sized> MultipartStore for DeadlineClient<T> {
+    async fn create_multipart(&self, path: &Path) -> Result<MultipartId> {
+        self.deadline.run(self.inner.create_multipart(path)).await
+    }
+
+    async fn put_part(
+        &self,
+        path: &Path,
+        id: &MultipartId,
+        part_idx: usize,
+        data: PutPayload,
+    ) -> Result<PartId> {
+        self.deadline
+            .run(self.inner.put_part(path, id, part_idx, data))
+            .await
+    }
+
+    async fn complete_multipart(
+        &self,
+        path: &Path,
+        id: &MultipartId,
+        parts: Vec<PartId>,
+    ) -> Result<PutResult> {
+        self.deadline
+            .run(self.inner.complete_multipart(path, id, parts))
+            .await
+    }
+
+    async fn abort_multipart(&self, path: &Path, id: &MultipartId) -> Result<()> {
+        // Aborting cleans up after a failed upload, so it isn't cut short
+        self.inner.abort_multipart(path, id).await
+    }
+}
+
+#[async_trait]
+impl<T: PagedListing + ?Sized> PagedListing for DeadlineClient<T> {
+    async fn list_page(
+        &self,
+        prefix: &Path,
+        delimiter: bool,
+        page_size: usize,
+        token: Option<String>,
+    ) -> Result<ListPage> {
+        self.deadline
+            .run(self.inner.list_page(prefix, delimiter, page_size, token))
+            .await
+    }
+}
+
+#[async_trait]
+impl<T: Append + ?Sized> Append for DeadlineClient<T> {
+    async fn append(&self, path: &Path, data: Bytes) -> Result<u64> {
+        self.deadline.run(self.inner.append(path, data)).await
+    }
+}
+
+#[async_trait]
+impl<T: Versioning + ?Sized> Versioning for DeadlineClient<T> {
+    async fn list_versions(&self, prefix: &Path) -> Result<Vec<ObjectVersionNif>> {
+        self.deadline.run(self.inner.list_versions(prefix)).await
+    }
+
+    async fn delete_version(&self, path: &Path, version: &str) -> Result<()> {
+        self.deadline
+            .run(self.inner.delete_version(path, version))
+            .await
+    }
+}
+
+#[async_trait]
+impl<T: IncompleteUploads + ?Sized> IncompleteUploads for DeadlineClient<T> {
+    async fn list_incomplete_uploads(&self, prefix: &Path) -> Result<Vec<IncompleteUploadNif>> {
+        self.deadline
+            .run(self.inner.list_incomplete_uploads(prefix))
+            .await
+    }
+
+    async fn abort_incomplete_upload(&self, path: &Path, upload_id: &str) -> Result<()> {
+        self.deadline
+            .run(self.inner.abort_incomplete_upload(path, upload_id))
+            .await
+    }
+}
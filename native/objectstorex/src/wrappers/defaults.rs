@@ -0,0 +1,173 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use object_store::path::Path;
+use object_store::{
+    Attributes, DynObjectStore, GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta,
+    ObjectStore, PutMultipartOpts, PutOptions, PutPayload, PutResult, Result, TagSet,
+};
+use percent_encoding::percent_decode_str;
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Store wrapper that adds default attributes and tags to every write
+///
+/// Defaults are merged into each put and multipart upload: an attribute,
+/// metadata key or tag set by the write itself takes precedence over the
+/// default with the same name.
+#[derive(Debug)]
+pub struct DefaultsStore {
+    inner: Arc<DynObjectStore>,
+    attributes: Attributes,
+    tags: Vec<(String, String)>,
+}
+
+impl DefaultsStore {
+    pub fn new(
+        inner: Arc<DynObjectStore>,
+        attributes: Attributes,
+        tags: Vec<(String, String)>,
+    ) -> Self {
+        Self {
+            inner,
+            attributes,
+            tags,
+        }
+    }
+
+    /// Add the default attributes missing from `attributes`
+    fn merge_attributes(&self, mut attributes: Attributes) -> Attributes {
+        for (attribute, value) in self.attributes.iter() {
+            if attributes.get(attribute).is_none() {
+                attributes.insert(attribute.clone(), value.clone());
+            }
+        }
+        attributes
+    }
+
+    /// Add the default tags whose keys are missing from `tags`
+    fn merge_tags(&self, tags: TagSet) -> TagSet {
+        if self.tags.is_empty() {
+            return tags;
+        }
+
+        let mut pairs = decode_tags(&tags);
+        for (key, value) in &self.tags {
+            if !pairs.iter().any(|(existing, _)| existing == key) {
+                pairs.push((key.clone(), value.clone()));
+            }
+        }
+
+        let mut merged = TagSet::default();
+        for (key, value) in &pairs {
+            merged.push(key, value);
+        }
+        merged
+    }
+}
+
+/// Key value pairs of a URL-encoded tag set
+pub(crate) fn decode_tags(tags: &TagSet) -> Vec<(String, String)> {
+    let decode = |s: &str| {
+        percent_decode_str(&s.replace('+', " "))
+            .decode_utf8_lossy()
+            .into_owned()
+    };
+
+    tags.encoded()
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode(key), decode(value))
+        })
+        .collect()
+}
+
+impl fmt::Display for DefaultsStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DefaultsStore({})", self.inner)
+    }
+}
+
+#[async_trait]
+impl ObjectStore for DefaultsStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        let opts = PutOptions {
+            attributes: self.merge_attributes(opts.attributes),
+            tags: self.merge_tags(opts.tags),
+            ..opts
+        };
+        self.inner.put_opts(location, payload, opts).await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        let opts = PutMultipartOpts {
+            attributes: self.merge_attributes(opts.attributes),
+            tags: self.merge_tags(opts.tags),
+        };
+        self.inner.put_multipart_opts(location, opts).await
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        self.inner.get_opts(location, options).await
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        self.inner.get_range(location, range).await
+    }
+
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<usize>]) -> Result<Vec<Bytes>> {
+        self.inner.get_ranges(location, ranges).await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        self.inner.head(location).await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        self.inner.delete(location).await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.inner.list(prefix)
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.inner.list_with_offset(prefix, offset)
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.copy(from, to).await
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.rename(from, to).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.copy_if_not_exists(from, to).await
+    }
+
+    async fn rename_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.rename_if_not_exists(from, to).await
+    }
+}
@@ -0,0 +1,560 @@
+use crate::backend::resolve_range;
+use crate::errors::verification_error;
+use async_trait::async_trait;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use bytes::{Bytes, BytesMut};
+use futures::stream::{self, BoxStream};
+use futures::{StreamExt, TryStreamExt};
+use object_store::path::Path;
+use object_store::{
+    Attribute, AttributeValue, Attributes, DynObjectStore, Error, GetOptions, GetRange, GetResult,
+    GetResultPayload, ListResult, MultipartUpload, ObjectMeta, ObjectStore, PutMultipartOpts,
+    PutOptions, PutPayload, PutResult, Result, UploadPart,
+};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use std::fmt;
+use std::sync::Arc;
+
+const STORE: &str = "Encryption";
+
+/// Plaintext bytes per encrypted segment
+const SEGMENT: usize = 64 * 1024;
+
+/// Authentication tag appended to every segment
+const TAG: usize = 16;
+
+/// Encrypted bytes buffered before a multipart upload sends them as a part
+const PART_SIZE: usize = 5 * 1024 * 1024;
+
+/// Metadata naming the scheme, the key encryption key and the wrapped data key
+const SCHEME_METADATA: &str = "objectstorex-encryption";
+const SCHEME: &str = "aes-256-gcm-64k";
+const KEY_ID_METADATA: &str = "objectstorex-key-id";
+const DATA_KEY_METADATA: &str = "objectstorex-data-key";
+
+fn metadata(name: &'static str) -> Attribute {
+    Attribute::Metadata(name.into())
+}
+
+fn crypto_error(message: &str) -> Error {
+    Error::Generic {
+        store: STORE,
+        source: message.to_string().into(),
+    }
+}
+
+fn aes_key(bytes: &[u8]) -> Result<LessSafeKey> {
+    UnboundKey::new(&AES_256_GCM, bytes)
+        .map(LessSafeKey::new)
+        .map_err(|_| crypto_error("invalid key"))
+}
+
+/// Key encryption keys, by id
+///
+/// The first key wraps the data keys of new objects; the others only unwrap
+/// data keys of existing objects, so keys can be rotated.
+pub struct KeyRing {
+    keys: Vec<(String, LessSafeKey)>,
+}
+
+impl KeyRing {
+    /// Key ring of 32-byte keys, or `None` if there are none or one is
+    /// malformed
+    pub fn new(keys: Vec<(String, Vec<u8>)>) -> Option<Self> {
+        let keys = keys
+            .into_iter()
+            .map(|(id, key)| Some((id, aes_key(&key).ok()?)))
+            .collect::<Option<Vec<_>>>()?;
+
+        (!keys.is_empty()).then_some(Self { keys })
+    }
+
+    /// Generate a data key, returning it and the metadata storing it wrapped
+    fn new_data_key(&self, random: &SystemRandom) -> Result<(LessSafeKey, Attributes)> {
+        let (key_id, key) = &self.keys[0];
+        let mut data_key = vec![0; 32];
+        let mut nonce = [0; NONCE_LEN];
+        random
+            .fill(&mut data_key)
+            .and_then(|_| random.fill(&mut nonce))
+            .map_err(|_| crypto_error("unable to generate a data key"))?;
+
+        let mut wrapped = data_key.clone();
+        key.seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(key_id.as_bytes()),
+            &mut wrapped,
+        )
+        .map_err(|_| crypto_error("unable to wrap the data key"))?;
+
+        let mut attributes = Attributes::new();
+        attributes.insert(metadata(SCHEME_METADATA), SCHEME.into());
+        attributes.insert(metadata(KEY_ID_METADATA), key_id.clone().into());
+        attributes.insert(
+            metadata(DATA_KEY_METADATA),
+            BASE64_STANDARD
+                .encode([&nonce[..], &wrapped].concat())
+                .into(),
+        );
+
+        Ok((aes_key(&data_key)?, attributes))
+    }
+
+    /// Unwrap the data key of an object from its metadata
+    fn data_key(&self, attributes: &Attributes, location: &Path) -> Result<LessSafeKey> {
+        let get = |name| attributes.get(&metadata(name)).map(AttributeValue::as_ref);
+
+        if get(SCHEME_METADATA) != Some(SCHEME) {
+            return Err(verification_error(format!(
+                "{} isn't encrypted with {}",
+                location, SCHEME
+            )));
+        }
+        let key_id = get(KEY_ID_METADATA).unwrap_or_default();
+        let Some((_, key)) = self.keys.iter().find(|(id, _)| id == key_id) else {
+            return Err(verification_error(format!(
+                "{} is encrypted with unknown key {:?}",
+                location, key_id
+            )));
+        };
+
+        let unwrap = || {
+            let wrapped = BASE64_STANDARD.decode(get(DATA_KEY_METADATA)?).ok()?;
+            if wrapped.len() < NONCE_LEN {
+                return None;
+            }
+            let (nonce, sealed) = wrapped.split_at(NONCE_LEN);
+            let mut sealed = sealed.to_vec();
+            let nonce = Nonce::try_assume_unique_for_key(nonce).ok()?;
+            let data_key = key
+                .open_in_place(nonce, Aad::from(key_id.as_bytes()), &mut sealed)
+                .ok()?;
+            aes_key(data_key).ok()
+        };
+        unwrap().ok_or_else(|| {
+            verification_error(format!("Unable to unwrap the data key of {}", location))
+        })
+    }
+}
+
+impl fmt::Debug for KeyRing {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let ids: Vec<&str> = self.keys.iter().map(|(id, _)| id.as_str()).collect();
+        f.debug_struct("KeyRing").field("keys", &ids).finish()
+    }
+}
+
+/// Nonce of segment `index`, marking the last segment of an object
+///
+/// Data keys are never reused, so a counter is enough to keep nonces unique;
+/// the last-segment flag stops truncated objects from decrypting.
+fn segment_nonce(index: u64, last: bool) -> Nonce {
+    let mut nonce = [0; NONCE_LEN];
+    nonce[3..11].copy_from_slice(&index.to_be_bytes());
+    nonce[11] = last as u8;
+    Nonce::assume_unique_for_key(nonce)
+}
+
+/// Number of segments and plaintext size of an object of `stored` bytes
+fn plaintext_size(stored: usize) -> (u64, usize) {
+    let segments = stored.div_ceil(SEGMENT + TAG).max(1);
+    (segments as u64, stored.saturating_sub(segments * TAG))
+}
+
+/// Encrypts data as a sequence of segments
+struct Sealer {
+    key: LessSafeKey,
+    index: u64,
+    /// Plaintext not sealed yet
+    pending: BytesMut,
+    /// Sealed segments not taken yet
+    sealed: Vec<u8>,
+}
+
+impl Sealer {
+    fn new(key: LessSafeKey) -> Self {
+        Self {
+            key,
+            index: 0,
+            pending: BytesMut::new(),
+            sealed: Vec::new(),
+        }
+    }
+
+    fn seal(&mut self, segment: &[u8], last: bool) -> Result<()> {
+        let mut buffer = segment.to_vec();
+        self.key
+            .seal_in_place_append_tag(segment_nonce(self.index, last), Aad::empty(), &mut buffer)
+            .map_err(|_| crypto_error("unable to encrypt"))?;
+        self.sealed.extend_from_slice(&buffer);
+        self.index += 1;
+        Ok(())
+    }
+
+    /// Add plaintext, sealing every segment known not to be the last
+    fn write(&mut self, data: &[u8]) -> Result<()> {
+        self.pending.extend_from_slice(data);
+        while self.pending.len() > SEGMENT {
+            let segment = self.pending.split_to(SEGMENT);
+            self.seal(&segment, false)?;
+        }
+        Ok(())
+    }
+
+    /// Take the sealed segments produced so far
+    fn take(&mut self) -> Vec<u8> {
+        std::mem::take(&mut self.sealed)
+    }
+
+    /// Seal the last segment, returning the sealed data not taken yet
+    fn finish(mut self) -> Result<Vec<u8>> {
+        let last = self.pending.split();
+        self.seal(&last, true)?;
+        Ok(self.sealed)
+    }
+}
+
+/// Decrypts a sequence of segments as their data arrives
+struct Opener {
+    key: LessSafeKey,
+    index: u64,
+    segments: u64,
+    /// Encrypted data not opened yet
+    pending: BytesMut,
+    location: Path,
+}
+
+impl Opener {
+    /// Add encrypted data, returning the plaintext of the segments completed
+    /// by it; at the `end` of the data, the rest is opened as the last segment
+    fn write(&mut self, data: &[u8], end: bool) -> Result<Vec<Bytes>> {
+        self.pending.extend_from_slice(data);
+
+        let mut opened = Vec::new();
+        while self.pending.len() >= SEGMENT + TAG || (end && !self.pending.is_empty()) {
+            let len = self.pending.len().min(SEGMENT + TAG);
+            let mut segment = self.pending.split_to(len);
+            let last = self.index + 1 == self.segments;
+            let plaintext_len = self
+                .key
+                .open_in_place(segment_nonce(self.index, last), Aad::empty(), &mut segment)
+                .map_err(|_| {
+                    verification_error(format!(
+                        "Unable to decrypt segment {} of {}",
+                        self.index, self.location
+                    ))
+                })?
+                .len();
+            segment.truncate(plaintext_len);
+            if !segment.is_empty() {
+                opened.push(segment.freeze());
+            }
+            self.index += 1;
+        }
+        Ok(opened)
+    }
+}
+
+/// Decrypt a stream of encrypted segments
+fn open(
+    stream: BoxStream<'static, Result<Bytes>>,
+    mut opener: Opener,
+) -> BoxStream<'static, Result<Bytes>> {
+    stream
+        .map(Some)
+        .chain(stream::once(async { None }))
+        .map(move |item| match item {
+            Some(Ok(bytes)) => opener.write(&bytes, false),
+            Some(Err(e)) => Err(e),
+            None => opener.write(&[], true),
+        })
+        .map_ok(|opened| stream::iter(opened.into_iter().map(Ok)))
+        .try_flatten()
+        .boxed()
+}
+
+/// Store wrapper encrypting objects on write and decrypting them on read
+///
+/// Envelope encryption: every object gets a random AES-256 data key, stored in
+/// the object's metadata wrapped (AES-256-GCM) with the store's current key
+/// encryption key. Data is encrypted with AES-256-GCM in 64 KiB segments, so
+/// objects can be streamed and range reads only fetch the segments covering
+/// the range. Segment nonces count up and flag the last segment, which detects
+/// reordered, dropped and truncated segments.
+///
+/// Objects without encryption metadata, or encrypted with an unknown key,
+/// fail to read with an integrity error instead of being returned as stored.
+/// The underlying store must keep object metadata, which local stores don't.
+#[derive(Debug)]
+pub struct EncryptedStore {
+    inner: Arc<DynObjectStore>,
+    keys: KeyRing,
+    random: SystemRandom,
+}
+
+impl EncryptedStore {
+    pub fn new(inner: Arc<DynObjectStore>, keys: KeyRing) -> Self {
+        Self {
+            inner,
+            keys,
+            random: SystemRandom::new(),
+        }
+    }
+
+    /// Add the metadata of a new data key to `attributes`, returning a sealer
+    /// encrypting with it
+    fn sealer(&self, attributes: &mut Attributes) -> Result<Sealer> {
+        let (key, metadata) = self.keys.new_data_key(&self.random)?;
+        for (attribute, value) in metadata.iter() {
+            attributes.insert(attribute.clone(), value.clone());
+        }
+        Ok(Sealer::new(key))
+    }
+
+    /// Decrypt a range of an object, fetching only the segments covering it
+    async fn get_range(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        let requested = options.range.clone().expect("range");
+        let head = self
+            .inner
+            .get_opts(
+                location,
+                GetOptions {
+                    head: true,
+                    range: None,
+                    ..options.clone()
+                },
+            )
+            .await?;
+        let key = self.keys.data_key(&head.attributes, location)?;
+        let stored_size = head.meta.size;
+        let (segments, size) = plaintext_size(stored_size);
+        let range = resolve_range(&requested, size, location)?;
+        let attributes = without_encryption(head.attributes);
+        let meta = ObjectMeta { size, ..head.meta };
+
+        if range.is_empty() {
+            return Ok(GetResult {
+                payload: GetResultPayload::Stream(stream::empty().boxed()),
+                meta,
+                range,
+                attributes,
+            });
+        }
+
+        let first = (range.start / SEGMENT) as u64;
+        let stored = first as usize * (SEGMENT + TAG)
+            ..(range.end.div_ceil(SEGMENT) * (SEGMENT + TAG)).min(stored_size);
+        let result = self
+            .inner
+            .get_opts(
+                location,
+                GetOptions {
+                    range: Some(GetRange::Bounded(stored)),
+                    if_match: meta.e_tag.clone().or(options.if_match),
+                    ..options
+                },
+            )
+            .await?;
+
+        let opener = Opener {
+            key,
+            index: first,
+            segments,
+            pending: BytesMut::new(),
+            location: location.clone(),
+        };
+        let skip = range.start - first as usize * SEGMENT;
+        let data = open(result.into_stream(), opener)
+            .try_collect::<Vec<_>>()
+            .await?;
+        let data = Bytes::from(data.concat()).slice(skip..skip + range.len());
+
+        Ok(GetResult {
+            payload: GetResultPayload::Stream(stream::once(async { Ok(data) }).boxed()),
+            meta,
+            range,
+            attributes,
+        })
+    }
+}
+
+/// Attributes without the encryption metadata
+fn without_encryption(mut attributes: Attributes) -> Attributes {
+    for name in [SCHEME_METADATA, KEY_ID_METADATA, DATA_KEY_METADATA] {
+        attributes.remove(&metadata(name));
+    }
+    attributes
+}
+
+impl fmt::Display for EncryptedStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "EncryptedStore({})", self.inner)
+    }
+}
+
+#[async_trait]
+impl ObjectStore for EncryptedStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        mut opts: PutOptions,
+    ) -> Result<PutResult> {
+        let mut sealer = self.sealer(&mut opts.attributes)?;
+        for chunk in payload.iter() {
+            sealer.write(chunk)?;
+        }
+        let payload = PutPayload::from(sealer.finish()?);
+
+        self.inner.put_opts(location, payload, opts).await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        mut opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        let sealer = self.sealer(&mut opts.attributes)?;
+        let upload = self.inner.put_multipart_opts(location, opts).await?;
+
+        Ok(Box::new(EncryptedUpload {
+            inner: upload,
+            sealer: Some(sealer),
+        }))
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        if options.range.is_some() && !options.head {
+            return self.get_range(location, options).await;
+        }
+
+        let result = self.inner.get_opts(location, options).await?;
+        let key = self.keys.data_key(&result.attributes, location)?;
+        let (segments, size) = plaintext_size(result.meta.size);
+        let meta = ObjectMeta {
+            size,
+            ..result.meta.clone()
+        };
+        let attributes = without_encryption(result.attributes.clone());
+
+        let opener = Opener {
+            key,
+            index: 0,
+            segments,
+            pending: BytesMut::new(),
+            location: location.clone(),
+        };
+        let stream = open(result.into_stream(), opener);
+
+        Ok(GetResult {
+            payload: GetResultPayload::Stream(stream),
+            meta,
+            range: 0..size,
+            attributes,
+        })
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        let options = GetOptions {
+            head: true,
+            ..Default::default()
+        };
+        Ok(self.get_opts(location, options).await?.meta)
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        self.inner.delete(location).await
+    }
+
+    fn delete_stream<'a>(
+        &'a self,
+        locations: BoxStream<'a, Result<Path>>,
+    ) -> BoxStream<'a, Result<Path>> {
+        self.inner.delete_stream(locations)
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.inner.list(prefix)
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.inner.list_with_offset(prefix, offset)
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.copy(from, to).await
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.rename(from, to).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.copy_if_not_exists(from, to).await
+    }
+
+    async fn rename_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.rename_if_not_exists(from, to).await
+    }
+}
+
+/// Multipart upload encrypting its parts as one sequence of segments
+///
+/// Encrypted data is sent in parts of at least `PART_SIZE` bytes, so part
+/// boundaries don't line up with the parts written to it.
+struct EncryptedUpload {
+    inner: Box<dyn MultipartUpload>,
+    sealer: Option<Sealer>,
+}
+
+impl fmt::Debug for EncryptedUpload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EncryptedUpload")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl MultipartUpload for EncryptedUpload {
+    fn put_part(&mut self, data: PutPayload) -> UploadPart {
+        let Some(sealer) = self.sealer.as_mut() else {
+            return Box::pin(futures::future::ready(Err(crypto_error(
+                "upload already completed",
+            ))));
+        };
+
+        for chunk in data.iter() {
+            if let Err(e) = sealer.write(chunk) {
+                return Box::pin(futures::future::ready(Err(e)));
+            }
+        }
+
+        if sealer.sealed.len() < PART_SIZE {
+            return Box::pin(futures::future::ready(Ok(())));
+        }
+        let part = sealer.take();
+        self.inner.put_part(PutPayload::from(part))
+    }
+
+    async fn complete(&mut self) -> Result<PutResult> {
+        if let Some(sealer) = self.sealer.take() {
+            let rest = sealer.finish()?;
+            self.inner.put_part(PutPayload::from(rest)).await?;
+        }
+        self.inner.complete().await
+    }
+
+    async fn abort(&mut self) -> Result<()> {
+        self.inner.abort().await
+    }
+}
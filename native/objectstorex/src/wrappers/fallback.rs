@@ -0,0 +1,159 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use object_store::path::Path;
+use object_store::{
+    DynObjectStore, Error, GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta,
+    ObjectStore, PutMultipartOpts, PutOptions, PutPayload, PutResult, Result,
+};
+use std::fmt;
+use std::future::Future;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Whether a read may find the object in a fallback store: it is missing from
+/// the store asked, or the store couldn't be reached
+fn falls_back(error: &Error) -> bool {
+    matches!(
+        error,
+        Error::NotFound { .. } | Error::Generic { .. } | Error::JoinError { .. }
+    )
+}
+
+/// Store reading objects from fallback stores when the primary store doesn't
+/// have them or fails
+///
+/// Gets and heads that fail with `NotFound` or a transport error are retried
+/// on each fallback in order; the first success is returned, otherwise the
+/// primary's error. Conditional reads that don't match aren't retried.
+/// Writes, deletes, listings, copies and renames only use the primary.
+pub struct FallbackStore {
+    primary: Arc<DynObjectStore>,
+    fallbacks: Vec<Arc<DynObjectStore>>,
+}
+
+impl FallbackStore {
+    pub fn new(primary: Arc<DynObjectStore>, fallbacks: Vec<Arc<DynObjectStore>>) -> Self {
+        Self { primary, fallbacks }
+    }
+
+    /// Read from the primary, then from each fallback in turn
+    async fn read<'a, T, F, Fut>(&'a self, read: F) -> Result<T>
+    where
+        F: Fn(&'a DynObjectStore) -> Fut,
+        Fut: Future<Output = Result<T>> + 'a,
+    {
+        let error = match read(self.primary.as_ref()).await {
+            Err(e) if falls_back(&e) => e,
+            result => return result,
+        };
+
+        for fallback in &self.fallbacks {
+            match read(fallback.as_ref()).await {
+                Err(e) if falls_back(&e) => continue,
+                result => return result,
+            }
+        }
+        Err(error)
+    }
+}
+
+impl fmt::Debug for FallbackStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FallbackStore")
+            .field("primary", &self.primary.to_string())
+            .field(
+                "fallbacks",
+                &self
+                    .fallbacks
+                    .iter()
+                    .map(|fallback| fallback.to_string())
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl fmt::Display for FallbackStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "FallbackStore({}", self.primary)?;
+        for fallback in &self.fallbacks {
+            write!(f, ", {}", fallback)?;
+        }
+        write!(f, ")")
+    }
+}
+
+#[async_trait]
+impl ObjectStore for FallbackStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        self.primary.put_opts(location, payload, opts).await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        self.primary.put_multipart_opts(location, opts).await
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        self.read(|store| store.get_opts(location, options.clone()))
+            .await
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        self.read(|store| store.get_range(location, range.clone()))
+            .await
+    }
+
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<usize>]) -> Result<Vec<Bytes>> {
+        self.read(|store| store.get_ranges(location, ranges)).await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        self.read(|store| store.head(location)).await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        self.primary.delete(location).await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.primary.list(prefix)
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.primary.list_with_offset(prefix, offset)
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        self.primary.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        self.primary.copy(from, to).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.primary.copy_if_not_exists(from, to).await
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        self.primary.rename(from, to).await
+    }
+
+    async fn rename_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.primary.rename_if_not_exists(from, to).await
+    }
+}
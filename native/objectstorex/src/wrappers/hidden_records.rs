@@ -0,0 +1,168 @@
+use crate::paging::{ListPage, PagedListing};
+use crate::ttl;
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::future;
+use futures::stream::{BoxStream, StreamExt, TryStreamExt};
+use object_store::path::Path;
+use object_store::{
+    GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta, ObjectStore, PutMultipartOpts,
+    PutOptions, PutPayload, PutResult, Result,
+};
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Store wrapper that leaves the expiry records of `put_with_ttl` out of
+/// listings
+///
+/// Records are only listed when the listing prefix is itself under a record
+/// directory, which is how `expire_now` finds them. Every other call is
+/// delegated to the inner store unchanged.
+pub struct HiddenRecordsStore<T: ?Sized> {
+    inner: Arc<T>,
+}
+
+impl<T: ?Sized> HiddenRecordsStore<T> {
+    pub fn new(inner: Arc<T>) -> Self {
+        Self { inner }
+    }
+}
+
+impl<T: fmt::Debug + ?Sized> fmt::Debug for HiddenRecordsStore<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("HiddenRecordsStore")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<T: fmt::Display + ?Sized> fmt::Display for HiddenRecordsStore<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "HiddenRecordsStore({})", self.inner)
+    }
+}
+
+/// Whether a listing under `prefix` should leave out expiry records
+fn hides(prefix: Option<&Path>) -> bool {
+    !prefix.is_some_and(ttl::is_record)
+}
+
+fn hide<'a>(
+    prefix: Option<&Path>,
+    stream: BoxStream<'a, Result<ObjectMeta>>,
+) -> BoxStream<'a, Result<ObjectMeta>> {
+    if !hides(prefix) {
+        return stream;
+    }
+    stream
+        .try_filter(|meta| future::ready(!ttl::is_record(&meta.location)))
+        .boxed()
+}
+
+#[async_trait]
+impl<T: ObjectStore + ?Sized> ObjectStore for HiddenRecordsStore<T> {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        self.inner.put_opts(location, payload, opts).await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        self.inner.put_multipart_opts(location, opts).await
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        self.inner.get_opts(location, options).await
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        self.inner.get_range(location, range).await
+    }
+
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<usize>]) -> Result<Vec<Bytes>> {
+        self.inner.get_ranges(location, ranges).await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        self.inner.head(location).await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        self.inner.delete(location).await
+    }
+
+    fn delete_stream<'a>(
+        &'a self,
+        locations: BoxStream<'a, Result<Path>>,
+    ) -> BoxStream<'a, Result<Path>> {
+        self.inner.delete_stream(locations)
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        hide(prefix, self.inner.list(prefix))
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'_, Result<ObjectMeta>> {
+        hide(prefix, self.inner.list_with_offset(prefix, offset))
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        let mut result = self.inner.list_with_delimiter(prefix).await?;
+        if hides(prefix) {
+            result
+                .objects
+                .retain(|meta| !ttl::is_record(&meta.location));
+            result.common_prefixes.retain(|p| !ttl::is_record(p));
+        }
+        Ok(result)
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.copy(from, to).await
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.rename(from, to).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.copy_if_not_exists(from, to).await
+    }
+
+    async fn rename_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.rename_if_not_exists(from, to).await
+    }
+}
+
+#[async_trait]
+impl<T: PagedListing + ?Sized> PagedListing for HiddenRecordsStore<T> {
+    async fn list_page(
+        &self,
+        prefix: &Path,
+        delimiter: bool,
+        page_size: usize,
+        token: Option<String>,
+    ) -> Result<ListPage> {
+        let mut page = self
+            .inner
+            .list_page(prefix, delimiter, page_size, token)
+            .await?;
+        if hides(Some(prefix)) {
+            page.objects.retain(|meta| !ttl::is_record(&meta.location));
+            page.common_prefixes.retain(|p| !ttl::is_record(p));
+        }
+        Ok(page)
+    }
+}
@@ -0,0 +1,390 @@
+use crate::append::Append;
+use crate::incomplete_uploads::{IncompleteUploadNif, IncompleteUploads};
+use crate::paging::{ListPage, PagedListing};
+use crate::versions::{ObjectVersionNif, Versioning};
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::{BoxStream, StreamExt};
+use futures::FutureExt;
+use object_store::multipart::{MultipartStore, PartId};
+use object_store::path::Path;
+use object_store::{
+    GetOptions, GetResult, GetResultPayload, ListResult, MultipartId, MultipartUpload, ObjectMeta,
+    ObjectStore, PutMultipartOpts, PutOptions, PutPayload, PutResult, Result, UploadPart,
+};
+use once_cell::sync::Lazy;
+use rustler::{Atom, Env, NifMap};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+struct Entry {
+    operation: &'static str,
+    path: String,
+    started: Instant,
+    bytes: Arc<AtomicU64>,
+}
+
+/// Calls in flight on every tracked store, keyed by id in start order
+static OPERATIONS: Lazy<Mutex<BTreeMap<u64, Entry>>> = Lazy::new(|| Mutex::new(BTreeMap::new()));
+static NEXT_OPERATION: AtomicU64 = AtomicU64::new(0);
+
+/// A call in flight; dropping it removes the call from the registry
+pub(crate) struct Operation {
+    id: u64,
+    bytes: Arc<AtomicU64>,
+}
+
+impl Operation {
+    pub(crate) fn start(operation: &'static str, path: impl ToString) -> Self {
+        let id = NEXT_OPERATION.fetch_add(1, Ordering::Relaxed);
+        let bytes = Arc::new(AtomicU64::new(0));
+        OPERATIONS.lock().unwrap().insert(
+            id,
+            Entry {
+                operation,
+                path: path.to_string(),
+                started: Instant::now(),
+                bytes: bytes.clone(),
+            },
+        );
+        Self { id, bytes }
+    }
+
+    pub(crate) fn transferred(&self, bytes: usize) {
+        self.bytes.fetch_add(bytes as u64, Ordering::Relaxed);
+    }
+}
+
+impl Drop for Operation {
+    fn drop(&mut self) {
+        OPERATIONS.lock().unwrap().remove(&self.id);
+    }
+}
+
+/// A call in flight, as returned by `list_inflight_operations`
+#[derive(Debug, NifMap)]
+pub struct InflightOperationNif {
+    pub id: u64,
+    pub operation: Atom,
+    pub path: String,
+    pub elapsed_ms: u64,
+    pub bytes: u64,
+}
+
+/// Every call in flight, oldest first
+pub fn inflight_operations(env: Env<'_>) -> Vec<InflightOperationNif> {
+    let now = Instant::now();
+    OPERATIONS
+        .lock()
+        .unwrap()
+        .iter()
+        .map(|(id, entry)| InflightOperationNif {
+            id: *id,
+            operation: Atom::from_str(env, entry.operation).unwrap(),
+            path: entry.path.clone(),
+            elapsed_ms: now.duration_since(entry.started).as_millis() as u64,
+            bytes: entry.bytes.load(Ordering::Relaxed),
+        })
+        .collect()
+}
+
+/// Keep `operation` in flight until `stream` is finished or dropped, counting
+/// the bytes of its items with `len`
+fn tracked_stream<'a, T: Send + 'a>(
+    operation: Operation,
+    stream: BoxStream<'a, Result<T>>,
+    len: fn(&T) -> usize,
+) -> BoxStream<'a, Result<T>> {
+    stream
+        .inspect(move |item| {
+            if let Ok(item) = item {
+                operation.transferred(len(item));
+            }
+        })
+        .boxed()
+}
+
+/// Store registering its calls in the global registry read by
+/// `list_inflight_operations`
+///
+/// Every store built by the library is wrapped, including its low-level
+/// multipart, versioning, incomplete upload, paged listing and append APIs,
+/// so the registry shows what each open store is doing. Requests of the S3
+/// API client register themselves. A call
+/// is in flight until its result is returned, or for streaming gets and
+/// listings until the stream is finished or dropped. `bytes` counts downloaded
+/// chunks as they arrive, and payloads of puts and parts once they are sent.
+/// Multipart uploads are one operation from creation until they are
+/// completed, aborted or dropped.
+pub struct InflightStore<T: ?Sized> {
+    inner: Arc<T>,
+}
+
+impl<T: ?Sized> InflightStore<T> {
+    pub fn new(inner: Arc<T>) -> Self {
+        Self { inner }
+    }
+}
+
+impl<T: fmt::Debug + ?Sized> fmt::Debug for InflightStore<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InflightStore")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<T: fmt::Display + ?Sized> fmt::Display for InflightStore<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "InflightStore({})", self.inner)
+    }
+}
+
+#[async_trait]
+impl<T: ObjectStore + ?Sized> ObjectStore for InflightStore<T> {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        let operation = Operation::start("put", location);
+        operation.transferred(payload.content_length());
+        self.inner.put_opts(location, payload, opts).await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        let operation = Operation::start("put_multipart", location);
+        let upload = self.inner.put_multipart_opts(location, opts).await?;
+        Ok(Box::new(InflightUpload {
+            upload,
+            operation: Some(Arc::new(operation)),
+        }))
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        let operation = Operation::start("get", location);
+        let result = self.inner.get_opts(location, options).await?;
+
+        let payload = match result.payload {
+            GetResultPayload::Stream(stream) => {
+                GetResultPayload::Stream(tracked_stream(operation, stream, Bytes::len))
+            }
+            file => file,
+        };
+        Ok(GetResult { payload, ..result })
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        let operation = Operation::start("get_range", location);
+        let bytes = self.inner.get_range(location, range).await?;
+        operation.transferred(bytes.len());
+        Ok(bytes)
+    }
+
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<usize>]) -> Result<Vec<Bytes>> {
+        let _operation = Operation::start("get_ranges", location);
+        self.inner.get_ranges(location, ranges).await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        let _operation = Operation::start("head", location);
+        self.inner.head(location).await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        let _operation = Operation::start("delete", location);
+        self.inner.delete(location).await
+    }
+
+    fn delete_stream<'a>(
+        &'a self,
+        locations: BoxStream<'a, Result<Path>>,
+    ) -> BoxStream<'a, Result<Path>> {
+        let operation = Operation::start("delete_stream", "");
+        tracked_stream(operation, self.inner.delete_stream(locations), |_| 0)
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        let operation = Operation::start("list", prefix.map_or("", Path::as_ref));
+        tracked_stream(operation, self.inner.list(prefix), |_| 0)
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'_, Result<ObjectMeta>> {
+        let operation = Operation::start("list", prefix.map_or("", Path::as_ref));
+        tracked_stream(
+            operation,
+            self.inner.list_with_offset(prefix, offset),
+            |_| 0,
+        )
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        let _operation = Operation::start("list_with_delimiter", prefix.map_or("", Path::as_ref));
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        let _operation = Operation::start("copy", from);
+        self.inner.copy(from, to).await
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        let _operation = Operation::start("rename", from);
+        self.inner.rename(from, to).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        let _operation = Operation::start("copy", from);
+        self.inner.copy_if_not_exists(from, to).await
+    }
+
+    async fn rename_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        let _operation = Operation::start("rename", from);
+        self.inner.rename_if_not_exists(from, to).await
+    }
+}
+
+#[async_trait]
+impl<T: MultipartStore + ?Sized> MultipartStore for InflightStore<T> {
+    async fn create_multipart(&self, path: &Path) -> Result<MultipartId> {
+        let _operation = Operation::start("create_multipart", path);
+        self.inner.create_multipart(path).await
+    }
+
+    async fn put_part(
+        &self,
+        path: &Path,
+        id: &MultipartId,
+        part_idx: usize,
+        data: PutPayload,
+    ) -> Result<PartId> {
+        let operation = Operation::start("put_part", path);
+        operation.transferred(data.content_length());
+        self.inner.put_part(path, id, part_idx, data).await
+    }
+
+    async fn complete_multipart(
+        &self,
+        path: &Path,
+        id: &MultipartId,
+        parts: Vec<PartId>,
+    ) -> Result<PutResult> {
+        let _operation = Operation::start("complete_multipart", path);
+        self.inner.complete_multipart(path, id, parts).await
+    }
+
+    async fn abort_multipart(&self, path: &Path, id: &MultipartId) -> Result<()> {
+        let _operation = Operation::start("abort_multipart", path);
+        self.inner.abort_multipart(path, id).await
+    }
+}
+
+#[async_trait]
+impl<T: Versioning + ?Sized> Versioning for InflightStore<T> {
+    async fn list_versions(&self, prefix: &Path) -> Result<Vec<ObjectVersionNif>> {
+        let _operation = Operation::start("list_versions", prefix);
+        self.inner.list_versions(prefix).await
+    }
+
+    async fn delete_version(&self, path: &Path, version: &str) -> Result<()> {
+        let _operation = Operation::start("delete_version", path);
+        self.inner.delete_version(path, version).await
+    }
+}
+
+#[async_trait]
+impl<T: IncompleteUploads + ?Sized> IncompleteUploads for InflightStore<T> {
+    async fn list_incomplete_uploads(&self, prefix: &Path) -> Result<Vec<IncompleteUploadNif>> {
+        let _operation = Operation::start("list_incomplete_uploads", prefix);
+        self.inner.list_incomplete_uploads(prefix).await
+    }
+
+    async fn abort_incomplete_upload(&self, path: &Path, upload_id: &str) -> Result<()> {
+        let _operation = Operation::start("abort_incomplete_upload", path);
+        self.inner.abort_incomplete_upload(path, upload_id).await
+    }
+}
+
+#[async_trait]
+impl<T: PagedListing + ?Sized> PagedListing for InflightStore<T> {
+    async fn list_page(
+        &self,
+        prefix: &Path,
+        delimiter: bool,
+        page_size: usize,
+        token: Option<String>,
+    ) -> Result<ListPage> {
+        let _operation = Operation::start("list", prefix);
+        self.inner
+            .list_page(prefix, delimiter, page_size, token)
+            .await
+    }
+}
+
+#[async_trait]
+impl<T: Append + ?Sized> Append for InflightStore<T> {
+    async fn append(&self, path: &Path, data: Bytes) -> Result<u64> {
+        let operation = Operation::start("append", path);
+        operation.transferred(data.len());
+        self.inner.append(path, data).await
+    }
+}
+
+/// Multipart upload in flight from its creation until it is finished,
+/// counting the bytes of its parts
+struct InflightUpload {
+    upload: Box<dyn MultipartUpload>,
+    /// The upload's operation, until it is completed or aborted
+    operation: Option<Arc<Operation>>,
+}
+
+impl fmt::Debug for InflightUpload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InflightUpload")
+            .field("upload", &self.upload)
+            .field("id", &self.operation.as_ref().map(|operation| operation.id))
+            .finish()
+    }
+}
+
+#[async_trait]
+impl MultipartUpload for InflightUpload {
+    fn put_part(&mut self, data: PutPayload) -> UploadPart {
+        let operation = self.operation.clone();
+        let len = data.content_length();
+        let part = self.upload.put_part(data);
+
+        async move {
+            if let Some(operation) = operation {
+                operation.transferred(len);
+            }
+            part.await
+        }
+        .boxed()
+    }
+
+    async fn complete(&mut self) -> Result<PutResult> {
+        let result = self.upload.complete().await;
+        self.operation = None;
+        result
+    }
+
+    async fn abort(&mut self) -> Result<()> {
+        let result = self.upload.abort().await;
+        self.operation = None;
+        result
+    }
+}
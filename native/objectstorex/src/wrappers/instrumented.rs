@@ -0,0 +1,410 @@
+use crate::atoms;
+use crate::errors::error_kind;
+use crate::RUNTIME;
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use object_store::path::Path;
+use object_store::{
+    DynObjectStore, GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta, ObjectStore,
+    PutMultipartOpts, PutOptions, PutPayload, PutResult, Result, UploadPart,
+};
+use once_cell::sync::Lazy;
+use rustler::types::map::map_new;
+use rustler::{Atom, Encoder, Env, LocalPid, NifMap, Term};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Totals of one operation across all instrumented stores
+#[derive(Debug, Default, Clone, NifMap)]
+pub struct OperationMetrics {
+    pub count: u64,
+    pub errors: u64,
+    pub bytes: u64,
+    pub duration_us: u64,
+}
+
+/// Global counters read by `get_metrics/0`, keyed by operation name
+static METRICS: Lazy<Mutex<BTreeMap<&'static str, OperationMetrics>>> =
+    Lazy::new(|| Mutex::new(BTreeMap::new()));
+
+/// Record a finished call and send its event to the subscriber, if any
+fn record(
+    subscriber: Option<LocalPid>,
+    operation: &'static str,
+    path: &Path,
+    started: Instant,
+    bytes: u64,
+    result: Atom,
+) {
+    let duration = started.elapsed();
+    let failed = result != atoms::ok();
+
+    {
+        let mut metrics = METRICS.lock().unwrap();
+        let entry = metrics.entry(operation).or_default();
+        entry.count += 1;
+        entry.errors += failed as u64;
+        entry.bytes += bytes;
+        entry.duration_us += duration.as_micros() as u64;
+    }
+
+    if let Some(pid) = subscriber {
+        let path = path.to_string();
+        RUNTIME.send(pid, None, move |env| {
+            encode_event(env, operation, &path, duration, bytes, result)
+        });
+    }
+}
+
+/// `{:objectstorex_event, operation, measurements, metadata}`
+fn encode_event<'a>(
+    env: Env<'a>,
+    operation: &str,
+    path: &str,
+    duration: Duration,
+    bytes: u64,
+    result: Atom,
+) -> Term<'a> {
+    let key = |name: &str| Atom::from_str(env, name).unwrap().encode(env);
+
+    let measurements = map_new(env)
+        .map_put(
+            key("duration_us"),
+            (duration.as_micros() as u64).encode(env),
+        )
+        .unwrap()
+        .map_put(key("bytes"), bytes.encode(env))
+        .unwrap();
+    let metadata = map_new(env)
+        .map_put(key("path"), path.encode(env))
+        .unwrap()
+        .map_put(key("result"), result.encode(env))
+        .unwrap();
+
+    (
+        key("objectstorex_event"),
+        key(operation),
+        measurements,
+        metadata,
+    )
+        .encode(env)
+}
+
+/// `:ok` or the error atom of a result
+fn outcome<T>(result: &Result<T>) -> Atom {
+    match result {
+        Ok(_) => atoms::ok(),
+        Err(e) => error_kind(e),
+    }
+}
+
+/// Snapshot of the global counters as `%{operation => metrics}`
+pub fn encode_metrics(env: Env<'_>) -> Term<'_> {
+    let metrics = METRICS.lock().unwrap().clone();
+
+    metrics
+        .into_iter()
+        .fold(map_new(env), |map, (operation, metrics)| {
+            map.map_put(
+                Atom::from_str(env, operation).unwrap().encode(env),
+                metrics.encode(env),
+            )
+            .unwrap()
+        })
+}
+
+/// Reset the global counters
+pub fn reset_metrics() {
+    METRICS.lock().unwrap().clear();
+}
+
+/// Store wrapper that measures every call
+///
+/// Each call adds its duration, transferred bytes and outcome to global
+/// per-operation counters and, with a subscriber, sends an event to it. Get
+/// durations are measured until the response starts, and count the bytes of
+/// the returned range. Listings are recorded when their stream is dropped.
+pub struct InstrumentedStore {
+    inner: Arc<DynObjectStore>,
+    subscriber: Option<LocalPid>,
+}
+
+impl InstrumentedStore {
+    pub fn new(inner: Arc<DynObjectStore>, subscriber: Option<LocalPid>) -> Self {
+        Self { inner, subscriber }
+    }
+
+    fn record<T>(
+        &self,
+        operation: &'static str,
+        path: &Path,
+        started: Instant,
+        bytes: u64,
+        result: &Result<T>,
+    ) {
+        record(
+            self.subscriber,
+            operation,
+            path,
+            started,
+            bytes,
+            outcome(result),
+        );
+    }
+
+    /// Record a listing when its stream is dropped
+    fn list_stream<'a>(
+        &self,
+        prefix: Option<&Path>,
+        stream: BoxStream<'a, Result<ObjectMeta>>,
+    ) -> BoxStream<'a, Result<ObjectMeta>> {
+        let guard = ListGuard {
+            subscriber: self.subscriber,
+            prefix: prefix.cloned().unwrap_or_default(),
+            started: Instant::now(),
+            result: atoms::ok(),
+        };
+
+        stream
+            .scan(guard, |guard, entry| {
+                if let Err(e) = &entry {
+                    guard.result = error_kind(e);
+                }
+                futures::future::ready(Some(entry))
+            })
+            .boxed()
+    }
+}
+
+impl fmt::Debug for InstrumentedStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InstrumentedStore")
+            .field("inner", &self.inner)
+            .field("subscribed", &self.subscriber.is_some())
+            .finish()
+    }
+}
+
+impl fmt::Display for InstrumentedStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "InstrumentedStore({})", self.inner)
+    }
+}
+
+/// Records a listing when dropped
+struct ListGuard {
+    subscriber: Option<LocalPid>,
+    prefix: Path,
+    started: Instant,
+    result: Atom,
+}
+
+impl Drop for ListGuard {
+    fn drop(&mut self) {
+        record(
+            self.subscriber,
+            "list",
+            &self.prefix,
+            self.started,
+            0,
+            self.result,
+        );
+    }
+}
+
+#[async_trait]
+impl ObjectStore for InstrumentedStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        let started = Instant::now();
+        let size = payload.content_length() as u64;
+        let result = self.inner.put_opts(location, payload, opts).await;
+        self.record("put", location, started, size, &result);
+        result
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        let started = Instant::now();
+        let upload = self.inner.put_multipart_opts(location, opts).await;
+
+        match upload {
+            Ok(upload) => Ok(Box::new(InstrumentedUpload {
+                upload,
+                subscriber: self.subscriber,
+                location: location.clone(),
+                started,
+                bytes: Arc::new(AtomicU64::new(0)),
+            })),
+            Err(e) => {
+                let result = Err(e);
+                self.record("put_multipart", location, started, 0, &result);
+                result
+            }
+        }
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        let started = Instant::now();
+        let operation = if options.head { "head" } else { "get" };
+        let result = self.inner.get_opts(location, options).await;
+        let bytes = match &result {
+            Ok(get) if operation == "get" => (get.range.end - get.range.start) as u64,
+            _ => 0,
+        };
+        self.record(operation, location, started, bytes, &result);
+        result
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        let started = Instant::now();
+        let result = self.inner.get_range(location, range).await;
+        let bytes = result.as_ref().map_or(0, |data| data.len() as u64);
+        self.record("get_range", location, started, bytes, &result);
+        result
+    }
+
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<usize>]) -> Result<Vec<Bytes>> {
+        let started = Instant::now();
+        let result = self.inner.get_ranges(location, ranges).await;
+        let bytes = result
+            .as_ref()
+            .map_or(0, |data| data.iter().map(|range| range.len() as u64).sum());
+        self.record("get_ranges", location, started, bytes, &result);
+        result
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        let started = Instant::now();
+        let result = self.inner.head(location).await;
+        self.record("head", location, started, 0, &result);
+        result
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        let started = Instant::now();
+        let result = self.inner.delete(location).await;
+        self.record("delete", location, started, 0, &result);
+        result
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.list_stream(prefix, self.inner.list(prefix))
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.list_stream(prefix, self.inner.list_with_offset(prefix, offset))
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        let started = Instant::now();
+        let result = self.inner.list_with_delimiter(prefix).await;
+        let prefix = prefix.cloned().unwrap_or_default();
+        self.record("list_with_delimiter", &prefix, started, 0, &result);
+        result
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        let started = Instant::now();
+        let result = self.inner.copy(from, to).await;
+        self.record("copy", from, started, 0, &result);
+        result
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        let started = Instant::now();
+        let result = self.inner.copy_if_not_exists(from, to).await;
+        self.record("copy_if_not_exists", from, started, 0, &result);
+        result
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        let started = Instant::now();
+        let result = self.inner.rename(from, to).await;
+        self.record("rename", from, started, 0, &result);
+        result
+    }
+
+    async fn rename_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        let started = Instant::now();
+        let result = self.inner.rename_if_not_exists(from, to).await;
+        self.record("rename_if_not_exists", from, started, 0, &result);
+        result
+    }
+}
+
+/// Multipart upload recorded as one `put_multipart` call when it completes
+/// or is aborted
+struct InstrumentedUpload {
+    upload: Box<dyn MultipartUpload>,
+    subscriber: Option<LocalPid>,
+    location: Path,
+    started: Instant,
+    bytes: Arc<AtomicU64>,
+}
+
+impl InstrumentedUpload {
+    fn record<T>(&self, result: &Result<T>) {
+        record(
+            self.subscriber,
+            "put_multipart",
+            &self.location,
+            self.started,
+            self.bytes.load(Ordering::SeqCst),
+            outcome(result),
+        );
+    }
+}
+
+impl fmt::Debug for InstrumentedUpload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("InstrumentedUpload")
+            .field("upload", &self.upload)
+            .field("location", &self.location)
+            .finish()
+    }
+}
+
+#[async_trait]
+impl MultipartUpload for InstrumentedUpload {
+    fn put_part(&mut self, data: PutPayload) -> UploadPart {
+        let size = data.content_length() as u64;
+        let bytes = self.bytes.clone();
+        let part = self.upload.put_part(data);
+
+        Box::pin(async move {
+            part.await?;
+            bytes.fetch_add(size, Ordering::SeqCst);
+            Ok(())
+        })
+    }
+
+    async fn complete(&mut self) -> Result<PutResult> {
+        let result = self.upload.complete().await;
+        self.record(&result);
+        result
+    }
+
+    async fn abort(&mut self) -> Result<()> {
+        let result = self.upload.abort().await;
+        self.record(&result);
+        result
+    }
+}
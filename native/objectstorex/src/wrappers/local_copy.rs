@@ -0,0 +1,277 @@
+use crate::atoms;
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use object_store::local::LocalFileSystem;
+use object_store::path::Path;
+use object_store::{
+    DynObjectStore, Error, GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta,
+    ObjectStore, PutMultipartOpts, PutOptions, PutPayload, PutResult, Result,
+};
+use rustler::{Atom, NifResult};
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::ErrorKind;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+const STORE: &str = "LocalFileSystem";
+
+/// How a local store copies files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CopyStrategy {
+    /// Reflink, then hard link, then copy the bytes
+    Auto,
+    /// Reflink, then copy the bytes; copies never share an inode
+    Reflink,
+    /// Always copy the bytes
+    Copy,
+}
+
+impl CopyStrategy {
+    pub fn from_atom(atom: Option<Atom>) -> NifResult<Self> {
+        match atom {
+            None => Ok(CopyStrategy::Auto),
+            Some(a) if a == atoms::auto() => Ok(CopyStrategy::Auto),
+            Some(a) if a == atoms::reflink() => Ok(CopyStrategy::Reflink),
+            Some(a) if a == atoms::copy() => Ok(CopyStrategy::Copy),
+            Some(_) => Err(rustler::Error::BadArg),
+        }
+    }
+}
+
+/// Local store wrapper that copies files with reflinks or hard links
+///
+/// Reflinks (btrfs, XFS, APFS, ReFS) share the data blocks until either file
+/// is modified, and hard links share the whole file, so copies and prefix
+/// copies don't read or write any data. Each method falls back to the next
+/// when the filesystem doesn't support it, down to a plain byte copy.
+///
+/// Like `LocalFileSystem`, copies are staged in a hidden `<to>#<n>` file and
+/// renamed into place, so readers never see a partial file.
+#[derive(Debug)]
+pub struct LocalCopyStore {
+    inner: Arc<DynObjectStore>,
+    fs: Arc<LocalFileSystem>,
+    strategy: CopyStrategy,
+}
+
+impl LocalCopyStore {
+    /// Wrap `inner`, a store writing through `fs`
+    pub fn new(
+        inner: Arc<DynObjectStore>,
+        fs: Arc<LocalFileSystem>,
+        strategy: CopyStrategy,
+    ) -> Self {
+        Self {
+            inner,
+            fs,
+            strategy,
+        }
+    }
+
+    /// Stage a copy of `from` next to `to`, returning the staged path
+    async fn stage(&self, from: &Path, to: &Path) -> Result<(PathBuf, PathBuf)> {
+        let from = self.fs.path_to_filesystem(from)?;
+        let to = self.fs.path_to_filesystem(to)?;
+        let strategy = self.strategy;
+
+        tokio::task::spawn_blocking(move || {
+            let staged = stage_copy(&from, &to, strategy)?;
+            Ok((staged, to))
+        })
+        .await
+        .map_err(|source| Error::JoinError { source })?
+    }
+}
+
+/// Create a copy of `from` at the first free `<to>#<n>` path
+fn stage_copy(
+    from: &std::path::Path,
+    to: &std::path::Path,
+    strategy: CopyStrategy,
+) -> Result<PathBuf> {
+    if !from.is_file() {
+        return Err(Error::NotFound {
+            path: from.display().to_string(),
+            source: "source file not found".into(),
+        });
+    }
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| io_error("create directory", parent, e))?;
+    }
+
+    for id in 0.. {
+        let staged = PathBuf::from(format!("{}#{}", to.display(), id));
+
+        match copy_file(from, &staged, strategy) {
+            Ok(()) => return Ok(staged),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(io_error("copy", from, e)),
+        }
+    }
+    unreachable!()
+}
+
+/// Copy `from` to the new file `to` with the cheapest method that works
+///
+/// Fails with `AlreadyExists` if `to` exists.
+fn copy_file(
+    from: &std::path::Path,
+    to: &std::path::Path,
+    strategy: CopyStrategy,
+) -> std::io::Result<()> {
+    if strategy != CopyStrategy::Copy {
+        match reflink_copy::reflink(from, to) {
+            Err(e) if e.kind() != ErrorKind::AlreadyExists => {}
+            result => return result,
+        }
+    }
+
+    if strategy == CopyStrategy::Auto {
+        match std::fs::hard_link(from, to) {
+            Err(e) if e.kind() != ErrorKind::AlreadyExists => {}
+            result => return result,
+        }
+    }
+
+    let mut source = File::open(from)?;
+    let mut target = OpenOptions::new().write(true).create_new(true).open(to)?;
+    if let Err(e) = std::io::copy(&mut source, &mut target).and_then(|_| target.sync_all()) {
+        let _ = std::fs::remove_file(to);
+        return Err(e);
+    }
+    Ok(())
+}
+
+fn io_error(action: &str, path: &std::path::Path, source: std::io::Error) -> Error {
+    generic(format!(
+        "unable to {} {}: {}",
+        action,
+        path.display(),
+        source
+    ))
+}
+
+fn generic(message: String) -> Error {
+    Error::Generic {
+        store: STORE,
+        source: message.into(),
+    }
+}
+
+impl fmt::Display for LocalCopyStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "LocalCopyStore({})", self.inner)
+    }
+}
+
+#[async_trait]
+impl ObjectStore for LocalCopyStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        self.inner.put_opts(location, payload, opts).await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        self.inner.put_multipart_opts(location, opts).await
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        self.inner.get_opts(location, options).await
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        self.inner.get_range(location, range).await
+    }
+
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<usize>]) -> Result<Vec<Bytes>> {
+        self.inner.get_ranges(location, ranges).await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        self.inner.head(location).await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        self.inner.delete(location).await
+    }
+
+    fn delete_stream<'a>(
+        &'a self,
+        locations: BoxStream<'a, Result<Path>>,
+    ) -> BoxStream<'a, Result<Path>> {
+        self.inner.delete_stream(locations)
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.inner.list(prefix)
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.inner.list_with_offset(prefix, offset)
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        let (staged, to) = self.stage(from, to).await?;
+
+        std::fs::rename(&staged, &to).map_err(|e| {
+            let _ = std::fs::remove_file(&staged);
+            io_error("rename", &staged, e)
+        })
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.rename(from, to).await
+    }
+
+    /// Hard links the staged copy into place, which fails if the destination
+    /// exists; without hard links the destination is checked first, so a
+    /// concurrent writer can still be overwritten
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        let location = to.clone();
+        let (staged, to) = self.stage(from, to).await?;
+
+        let result = match std::fs::hard_link(&staged, &to) {
+            Ok(()) => Ok(()),
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => Err(Error::AlreadyExists {
+                path: location.to_string(),
+                source: Box::new(e),
+            }),
+            Err(_) if to.exists() => Err(Error::AlreadyExists {
+                path: location.to_string(),
+                source: "destination exists".into(),
+            }),
+            Err(_) => {
+                return std::fs::rename(&staged, &to).map_err(|e| {
+                    let _ = std::fs::remove_file(&staged);
+                    io_error("rename", &staged, e)
+                })
+            }
+        };
+
+        let _ = std::fs::remove_file(&staged);
+        result
+    }
+
+    async fn rename_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.rename_if_not_exists(from, to).await
+    }
+}
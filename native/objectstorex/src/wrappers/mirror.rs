@@ -0,0 +1,449 @@
+use crate::atoms;
+use crate::RUNTIME;
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::future::{self, BoxFuture};
+use futures::stream::BoxStream;
+use futures::{FutureExt, TryFutureExt};
+use object_store::path::Path;
+use object_store::{
+    DynObjectStore, Error, GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta,
+    ObjectStore, PutMode, PutMultipartOpts, PutOptions, PutPayload, PutResult, Result, UploadPart,
+};
+use rustler::{Atom, NifResult};
+use std::fmt;
+use std::future::Future;
+use std::ops::Range;
+use std::sync::Arc;
+use tokio::sync::{mpsc, oneshot};
+
+/// When a write through a mirror store counts as done
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Consistency {
+    /// Once the primary and every replica have it
+    All,
+    /// Once the primary has it; replicas are written in the background
+    Primary,
+}
+
+impl Consistency {
+    /// Consistency for `:all` (the default) or `:primary`
+    pub fn from_atom(atom: Option<Atom>) -> NifResult<Self> {
+        match atom {
+            None => Ok(Consistency::All),
+            Some(a) if a == atoms::all() => Ok(Consistency::All),
+            Some(a) if a == atoms::primary() => Ok(Consistency::Primary),
+            Some(_) => Err(rustler::Error::BadArg),
+        }
+    }
+}
+
+/// A write applied to every replica
+type Replication = BoxFuture<'static, Result<()>>;
+
+/// Work for the background replication task
+enum Job {
+    Replicate(Replication),
+    /// Report the first failure since the last flush once earlier jobs ran
+    Flush(oneshot::Sender<Option<String>>),
+}
+
+/// Apply queued writes one at a time, so replicas see them in order
+async fn replicate_in_background(mut jobs: mpsc::UnboundedReceiver<Job>) {
+    let mut failure = None;
+    while let Some(job) = jobs.recv().await {
+        match job {
+            Job::Replicate(write) => {
+                if let Err(e) = write.await {
+                    failure.get_or_insert(e.to_string());
+                }
+            }
+            Job::Flush(done) => {
+                let _ = done.send(failure.take());
+            }
+        }
+    }
+}
+
+/// Writes to the replicas of a mirror store
+struct Replicator {
+    replicas: Vec<Arc<DynObjectStore>>,
+    /// Queue of the background replication task, with primary consistency
+    queue: Option<mpsc::UnboundedSender<Job>>,
+}
+
+impl Replicator {
+    fn new(replicas: Vec<Arc<DynObjectStore>>, consistency: Consistency) -> Self {
+        let queue = match consistency {
+            Consistency::All => None,
+            Consistency::Primary => {
+                let (sender, receiver) = mpsc::unbounded_channel();
+                RUNTIME.spawn(replicate_in_background(receiver));
+                Some(sender)
+            }
+        };
+        Self { replicas, queue }
+    }
+
+    /// Apply `write` to every replica concurrently, waiting for it with all
+    /// consistency and queueing it with primary consistency
+    async fn run(&self, write: impl FnMut(Arc<DynObjectStore>) -> Replication) -> Result<()> {
+        let writes = future::try_join_all(self.replicas.iter().cloned().map(write)).map_ok(|_| ());
+
+        match &self.queue {
+            None => writes.await,
+            Some(queue) => {
+                // The task only stops once every sender is gone
+                let _ = queue.send(Job::Replicate(writes.boxed()));
+                Ok(())
+            }
+        }
+    }
+
+    /// Wait for queued writes, returning the first that failed since the
+    /// last flush
+    async fn flush(&self) -> Result<()> {
+        let Some(queue) = &self.queue else {
+            return Ok(());
+        };
+        let (done, failure) = oneshot::channel();
+        let _ = queue.send(Job::Flush(done));
+
+        match failure.await {
+            Ok(Some(message)) => Err(Error::Generic {
+                store: "MirrorStore",
+                source: format!("replication failed: {}", message).into(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Copy `location` from `primary` to every replica
+    async fn copy_from(&self, primary: &Arc<DynObjectStore>, location: &Path) -> Result<()> {
+        let primary = primary.clone();
+        let location = location.clone();
+
+        self.run(move |replica| {
+            let (primary, location) = (primary.clone(), location.clone());
+            async move {
+                let result = primary.get(&location).await?;
+                let attributes = result.attributes.clone();
+                let data = result.bytes().await?;
+                let opts = PutOptions {
+                    attributes,
+                    ..Default::default()
+                };
+                replica.put_opts(&location, data.into(), opts).await?;
+                Ok(())
+            }
+            .boxed()
+        })
+        .await
+    }
+}
+
+/// Whether a read error means the primary failed, rather than answered
+fn fails_over(error: &Error) -> bool {
+    !matches!(
+        error,
+        Error::NotFound { .. }
+            | Error::NotModified { .. }
+            | Error::Precondition { .. }
+            | Error::AlreadyExists { .. }
+            | Error::InvalidPath { .. }
+            | Error::NotSupported { .. }
+            | Error::NotImplemented
+    )
+}
+
+/// Store writing every change to a primary store and its replicas
+///
+/// Writes go to the primary first, so a rejected conditional put never
+/// reaches the replicas, then to every replica concurrently as unconditional
+/// writes. With `Consistency::All` a write returns once every store has it, or
+/// the first error; with `Consistency::Primary` it returns once the primary
+/// has it, and replicas are written by a background task in write order,
+/// whose first failure is returned by `flush`.
+///
+/// Reads and listings go to the primary. Reads failing for reasons other than
+/// the object's state (missing, unmodified, precondition) are retried on each
+/// replica in turn.
+pub struct MirrorStore {
+    primary: Arc<DynObjectStore>,
+    replicator: Arc<Replicator>,
+}
+
+impl MirrorStore {
+    pub fn new(
+        primary: Arc<DynObjectStore>,
+        replicas: Vec<Arc<DynObjectStore>>,
+        consistency: Consistency,
+    ) -> Self {
+        Self {
+            primary,
+            replicator: Arc::new(Replicator::new(replicas, consistency)),
+        }
+    }
+
+    /// Wait until the replicas have every write made so far
+    pub async fn flush(&self) -> Result<()> {
+        self.replicator.flush().await
+    }
+
+    /// Read from the primary, failing over to the replicas
+    async fn read<'a, T, F, Fut>(&'a self, read: F) -> Result<T>
+    where
+        F: Fn(&'a DynObjectStore) -> Fut,
+        Fut: Future<Output = Result<T>> + 'a,
+    {
+        let error = match read(self.primary.as_ref()).await {
+            Err(e) if fails_over(&e) => e,
+            result => return result,
+        };
+
+        for replica in &self.replicator.replicas {
+            if let Ok(value) = read(replica.as_ref()).await {
+                return Ok(value);
+            }
+        }
+        Err(error)
+    }
+
+    /// Copy or rename an object on every replica
+    async fn move_on_replicas(&self, from: &Path, to: &Path, rename: bool) -> Result<()> {
+        let (from, to) = (from.clone(), to.clone());
+
+        self.replicator
+            .run(move |replica| {
+                let (from, to) = (from.clone(), to.clone());
+                async move {
+                    if rename {
+                        replica.rename(&from, &to).await
+                    } else {
+                        replica.copy(&from, &to).await
+                    }
+                }
+                .boxed()
+            })
+            .await
+    }
+}
+
+impl fmt::Debug for MirrorStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MirrorStore")
+            .field("primary", &self.primary.to_string())
+            .field(
+                "replicas",
+                &self
+                    .replicator
+                    .replicas
+                    .iter()
+                    .map(|replica| replica.to_string())
+                    .collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl fmt::Display for MirrorStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "MirrorStore({}", self.primary)?;
+        for replica in &self.replicator.replicas {
+            write!(f, ", {}", replica)?;
+        }
+        write!(f, ")")
+    }
+}
+
+/// Multipart upload to the primary store, mirrored once it completes
+struct MirrorUpload {
+    location: Path,
+    primary: Arc<DynObjectStore>,
+    upload: Box<dyn MultipartUpload>,
+    /// Uploads of the same parts to every replica, with all consistency
+    replicas: Vec<Box<dyn MultipartUpload>>,
+    replicator: Arc<Replicator>,
+}
+
+impl fmt::Debug for MirrorUpload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MirrorUpload")
+            .field("location", &self.location)
+            .field("replicas", &self.replicas.len())
+            .finish()
+    }
+}
+
+#[async_trait]
+impl MultipartUpload for MirrorUpload {
+    fn put_part(&mut self, data: PutPayload) -> UploadPart {
+        let parts: Vec<UploadPart> = std::iter::once(&mut self.upload)
+            .chain(self.replicas.iter_mut())
+            .map(|upload| upload.put_part(data.clone()))
+            .collect();
+
+        future::try_join_all(parts).map_ok(|_| ()).boxed()
+    }
+
+    async fn complete(&mut self) -> Result<PutResult> {
+        let result = self.upload.complete().await?;
+
+        if self.replicator.queue.is_some() {
+            self.replicator
+                .copy_from(&self.primary, &self.location)
+                .await?;
+        } else {
+            future::try_join_all(self.replicas.iter_mut().map(|upload| upload.complete())).await?;
+        }
+        Ok(result)
+    }
+
+    async fn abort(&mut self) -> Result<()> {
+        let aborted = future::join_all(
+            std::iter::once(&mut self.upload)
+                .chain(self.replicas.iter_mut())
+                .map(|upload| upload.abort()),
+        )
+        .await;
+
+        aborted.into_iter().collect()
+    }
+}
+
+#[async_trait]
+impl ObjectStore for MirrorStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        let result = self
+            .primary
+            .put_opts(location, payload.clone(), opts.clone())
+            .await?;
+
+        let location = location.clone();
+        let opts = PutOptions {
+            mode: PutMode::Overwrite,
+            ..opts
+        };
+        self.replicator
+            .run(move |replica| {
+                let (location, payload, opts) = (location.clone(), payload.clone(), opts.clone());
+                async move {
+                    replica.put_opts(&location, payload, opts).await?;
+                    Ok(())
+                }
+                .boxed()
+            })
+            .await?;
+
+        Ok(result)
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        let upload = self
+            .primary
+            .put_multipart_opts(location, opts.clone())
+            .await?;
+
+        let mut mirrored = MirrorUpload {
+            location: location.clone(),
+            primary: self.primary.clone(),
+            upload,
+            replicas: Vec::new(),
+            replicator: self.replicator.clone(),
+        };
+        if self.replicator.queue.is_none() {
+            for replica in &self.replicator.replicas {
+                match replica.put_multipart_opts(location, opts.clone()).await {
+                    Ok(upload) => mirrored.replicas.push(upload),
+                    Err(e) => {
+                        let _ = mirrored.abort().await;
+                        return Err(e);
+                    }
+                }
+            }
+        }
+
+        Ok(Box::new(mirrored))
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        self.read(|store| store.get_opts(location, options.clone()))
+            .await
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        self.read(|store| store.get_range(location, range.clone()))
+            .await
+    }
+
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<usize>]) -> Result<Vec<Bytes>> {
+        self.read(|store| store.get_ranges(location, ranges)).await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        self.read(|store| store.head(location)).await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        self.primary.delete(location).await?;
+
+        let location = location.clone();
+        self.replicator
+            .run(move |replica| {
+                let location = location.clone();
+                async move {
+                    match replica.delete(&location).await {
+                        Err(Error::NotFound { .. }) => Ok(()),
+                        result => result,
+                    }
+                }
+                .boxed()
+            })
+            .await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.primary.list(prefix)
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.primary.list_with_offset(prefix, offset)
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        self.read(|store| store.list_with_delimiter(prefix)).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        self.primary.copy(from, to).await?;
+        self.move_on_replicas(from, to, false).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.primary.copy_if_not_exists(from, to).await?;
+        self.move_on_replicas(from, to, false).await
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        self.primary.rename(from, to).await?;
+        self.move_on_replicas(from, to, true).await
+    }
+
+    async fn rename_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.primary.rename_if_not_exists(from, to).await?;
+        self.move_on_replicas(from, to, true).await
+    }
+}
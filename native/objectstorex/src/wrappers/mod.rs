@@ -0,0 +1,443 @@
+//! Store wrappers that layer behaviour on top of an existing store handle
+
+pub mod cached;
+pub mod compressed;
+pub mod deadline;
+pub mod defaults;
+pub mod encrypted;
+pub mod fallback;
+pub mod hidden_records;
+pub mod inflight;
+pub mod instrumented;
+pub mod local_copy;
+pub mod mirror;
+pub mod permissions;
+pub mod provider_limits;
+pub mod quota;
+pub mod rate_limit;
+pub mod read_only;
+pub mod request_limit;
+pub mod router;
+pub mod traced;
+
+use crate::append::Append;
+use crate::errors::map_error;
+use crate::incomplete_uploads::IncompleteUploads;
+use crate::operations::put_attributes;
+use crate::paging::PagedListing;
+use crate::shutdown;
+use crate::store::StoreWrapper;
+use crate::types::{AttributesNif, CacheOptionsNif, ThrottleConfigNif};
+use crate::versions::Versioning;
+use crate::RUNTIME;
+use cached::{CacheStore, WritePolicy};
+use compressed::{Codec, CompressedStore};
+use deadline::{Deadline, DeadlineClient, DeadlineStore, OpRef};
+use defaults::DefaultsStore;
+use encrypted::{EncryptedStore, KeyRing};
+use fallback::FallbackStore;
+use hidden_records::HiddenRecordsStore;
+use instrumented::InstrumentedStore;
+use mirror::{Consistency, MirrorStore};
+use object_store::limit::LimitStore;
+use object_store::multipart::MultipartStore;
+use object_store::path::Path;
+use object_store::prefix::PrefixStore;
+use object_store::throttle::{ThrottleConfig, ThrottledStore};
+use object_store::DynObjectStore;
+use quota::QuotaStore;
+use rate_limit::RateLimitStore;
+use read_only::ReadOnlyStore;
+use router::RouterStore;
+use rustler::{Atom, Binary, Encoder, Env, LocalPid, NifResult, ResourceArc, Term};
+use std::sync::Arc;
+use std::time::Duration;
+use traced::TracedStore;
+
+/// Derive a restricted child handle from a store
+///
+/// The child combines, in order, a key prefix the handle can't escape, a
+/// read-only policy and a byte quota. Each restriction is optional; the child
+/// shares the parent's underlying client, so deriving is cheap.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn derive<'a>(
+    env: Env<'a>,
+    store: ResourceArc<StoreWrapper>,
+    prefix: Option<String>,
+    read_only: bool,
+    quota: Option<u64>,
+) -> NifResult<Term<'a>> {
+    shutdown::ensure_running()?;
+    let mut child: Arc<DynObjectStore> = store.inner.clone();
+
+    if let Some(prefix) = prefix {
+        // Expiry records of the child sit at its own root
+        child = Arc::new(HiddenRecordsStore::new(Arc::new(PrefixStore::new(
+            child, prefix,
+        ))));
+    }
+
+    if read_only {
+        child = Arc::new(ReadOnlyStore::new(child));
+    }
+
+    if let Some(limit) = quota {
+        let used = match RUNTIME.block_on(QuotaStore::current_usage(child.as_ref())) {
+            Ok(used) => used,
+            Err(e) => return Ok(map_error(e).to_term(env)),
+        };
+        child = Arc::new(QuotaStore::new(child, limit, used));
+    }
+
+    Ok(ResourceArc::new(StoreWrapper::new(child)).encode(env))
+}
+
+/// Wrap a store with simulated latency per call, byte and listed entry
+///
+/// Meant for tests of timeouts and progress reporting against a fast local or
+/// memory store, and for deliberately slowing down background jobs.
+#[rustler::nif]
+pub fn with_throttle(
+    store: ResourceArc<StoreWrapper>,
+    config: ThrottleConfigNif,
+) -> ResourceArc<StoreWrapper> {
+    let us = Duration::from_micros;
+    let config = ThrottleConfig {
+        wait_delete_per_call: us(config.delete_per_call),
+        wait_get_per_byte: us(config.get_per_byte),
+        wait_get_per_call: us(config.get_per_call),
+        wait_list_per_call: us(config.list_per_call),
+        wait_list_per_entry: us(config.list_per_entry),
+        wait_list_with_delimiter_per_call: us(config.list_with_delimiter_per_call),
+        wait_list_with_delimiter_per_entry: us(config.list_with_delimiter_per_entry),
+        wait_put_per_call: us(config.put_per_call),
+    };
+
+    let child: Arc<DynObjectStore> = Arc::new(ThrottledStore::new(store.inner.clone(), config));
+    ResourceArc::new(StoreWrapper::new(child))
+}
+
+/// Wrap a store so at most `max_requests` requests are outstanding at once
+///
+/// Further requests wait for a slot. Streaming gets and listings hold their
+/// slot until the stream is consumed or dropped.
+#[rustler::nif]
+pub fn with_concurrency_limit(
+    store: ResourceArc<StoreWrapper>,
+    max_requests: usize,
+) -> ResourceArc<StoreWrapper> {
+    let child: Arc<DynObjectStore> = Arc::new(LimitStore::new(store.inner.clone(), max_requests));
+    ResourceArc::new(StoreWrapper::new(child))
+}
+
+/// Wrap a store so every write gets default attributes and tags
+///
+/// Attributes, metadata keys and tags given by a write override the defaults
+/// with the same name.
+#[rustler::nif]
+pub fn with_defaults(
+    store: ResourceArc<StoreWrapper>,
+    attributes: AttributesNif,
+    tags: Vec<(String, String)>,
+) -> ResourceArc<StoreWrapper> {
+    let child: Arc<DynObjectStore> = Arc::new(DefaultsStore::new(
+        store.inner.clone(),
+        put_attributes(attributes),
+        tags,
+    ));
+    ResourceArc::new(StoreWrapper::new(child))
+}
+
+/// Wrap a store so objects are compressed on write and decompressed on read
+///
+/// `codec` is `:gzip` or `:zstd`; `level` defaults to the codec's usual
+/// default (6 for gzip, 3 for zstd).
+#[rustler::nif]
+pub fn with_compression(
+    store: ResourceArc<StoreWrapper>,
+    codec: Atom,
+    level: Option<i32>,
+) -> NifResult<ResourceArc<StoreWrapper>> {
+    let (codec, level) = Codec::from_atom(codec, level)?;
+    let child: Arc<DynObjectStore> =
+        Arc::new(CompressedStore::new(store.inner.clone(), codec, level));
+    Ok(ResourceArc::new(StoreWrapper::new(child)))
+}
+
+/// Wrap a store so objects are encrypted on write and decrypted on read
+///
+/// `keys` are `{key_id, key}` pairs of 32-byte key encryption keys. The first
+/// wraps the data keys of new objects; all of them unwrap existing ones.
+#[rustler::nif]
+pub fn with_encryption(
+    store: ResourceArc<StoreWrapper>,
+    keys: Vec<(String, Binary)>,
+) -> NifResult<ResourceArc<StoreWrapper>> {
+    let keys = keys
+        .into_iter()
+        .map(|(id, key)| (id, key.as_slice().to_vec()))
+        .collect();
+    let keys = KeyRing::new(keys).ok_or(rustler::Error::BadArg)?;
+
+    let child: Arc<DynObjectStore> = Arc::new(EncryptedStore::new(store.inner.clone(), keys));
+    Ok(ResourceArc::new(StoreWrapper::new(child)))
+}
+
+/// Wrap a remote store so reads are cached in a local store
+///
+/// `write_policy` decides whether writes go around the cache (the default),
+/// through it, or to the local store first and back to the remote store in
+/// the background.
+#[rustler::nif]
+pub fn with_cache(
+    remote: ResourceArc<StoreWrapper>,
+    local: ResourceArc<StoreWrapper>,
+    options: CacheOptionsNif,
+) -> NifResult<ResourceArc<StoreWrapper>> {
+    let cache = Arc::new(CacheStore::new(
+        remote.inner.clone(),
+        local.inner.clone(),
+        options.ttl_ms.map(Duration::from_millis),
+        options.max_size,
+        WritePolicy::from_atom(options.write_policy)?,
+    ));
+
+    let mut wrapper = StoreWrapper::new(cache.clone());
+    wrapper.cache = Some(cache);
+    Ok(ResourceArc::new(wrapper))
+}
+
+/// Combine stores into one that routes each location by key prefix
+///
+/// `routes` are `(prefix, store)` pairs; an empty prefix routes everything no
+/// other prefix matches. Repeated prefixes are rejected.
+#[rustler::nif]
+pub fn new_router(
+    routes: Vec<(String, ResourceArc<StoreWrapper>)>,
+) -> NifResult<ResourceArc<StoreWrapper>> {
+    let routes = routes
+        .into_iter()
+        .map(|(prefix, store)| (Path::from(prefix), store.inner.clone()))
+        .collect();
+    let router = RouterStore::new(routes).ok_or(rustler::Error::BadArg)?;
+
+    Ok(ResourceArc::new(StoreWrapper::new(Arc::new(router))))
+}
+
+/// Wait until every write-back write through a cache store has reached the
+/// remote store
+///
+/// Uploads that failed in the background are retried; the first error is
+/// returned. Stores without a cache return `:not_supported`.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn flush_cache(env: Env<'_>, store: ResourceArc<StoreWrapper>) -> NifResult<Term<'_>> {
+    shutdown::ensure_running()?;
+    let Some(cache) = store.cache.clone() else {
+        return Ok(crate::atoms::not_supported().encode(env));
+    };
+
+    match RUNTIME.block_on(cache.flush()) {
+        Ok(()) => Ok(crate::atoms::ok().encode(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
+
+/// Mirror every write to a primary store onto replica stores
+///
+/// `consistency` is `:all` (the default) to finish writes once every store
+/// has them, or `:primary` to finish them once the primary has them and
+/// replicate in the background. At least one replica is required.
+#[rustler::nif]
+pub fn with_mirror(
+    primary: ResourceArc<StoreWrapper>,
+    replicas: Vec<ResourceArc<StoreWrapper>>,
+    consistency: Option<Atom>,
+) -> NifResult<ResourceArc<StoreWrapper>> {
+    if replicas.is_empty() {
+        return Err(rustler::Error::BadArg);
+    }
+    let mirror = Arc::new(MirrorStore::new(
+        primary.inner.clone(),
+        replicas
+            .iter()
+            .map(|replica| replica.inner.clone())
+            .collect(),
+        Consistency::from_atom(consistency)?,
+    ));
+
+    let mut wrapper = StoreWrapper::new(mirror.clone());
+    wrapper.mirror = Some(mirror);
+    Ok(ResourceArc::new(wrapper))
+}
+
+/// Wait until the replicas of a mirror store have every write made so far
+///
+/// Returns the first background replication failure since the last flush.
+/// Stores that aren't mirrors return `:not_supported`.
+#[rustler::nif(schedule = "DirtyCpu")]
+pub fn flush_mirror(env: Env<'_>, store: ResourceArc<StoreWrapper>) -> NifResult<Term<'_>> {
+    shutdown::ensure_running()?;
+    let Some(mirror) = store.mirror.clone() else {
+        return Ok(crate::atoms::not_supported().encode(env));
+    };
+
+    match RUNTIME.block_on(mirror.flush()) {
+        Ok(()) => Ok(crate::atoms::ok().encode(env)),
+        Err(e) => Ok(map_error(e).to_term(env)),
+    }
+}
+
+/// Wrap a store so reads it can't serve are retried on fallback stores
+///
+/// Gets and heads failing with `NotFound` or a transport error try each
+/// fallback in order. At least one fallback is required.
+#[rustler::nif]
+pub fn with_fallback(
+    primary: ResourceArc<StoreWrapper>,
+    fallbacks: Vec<ResourceArc<StoreWrapper>>,
+) -> NifResult<ResourceArc<StoreWrapper>> {
+    if fallbacks.is_empty() {
+        return Err(rustler::Error::BadArg);
+    }
+    let child: Arc<DynObjectStore> = Arc::new(FallbackStore::new(
+        primary.inner.clone(),
+        fallbacks
+            .iter()
+            .map(|fallback| fallback.inner.clone())
+            .collect(),
+    ));
+    Ok(ResourceArc::new(StoreWrapper::new(child)))
+}
+
+/// Wrap a store so its requests and transferred bytes are rate limited
+///
+/// Either limit may be `nil`, but not both; limits must be positive. The
+/// limits are shared by every process using the returned handle.
+#[rustler::nif]
+pub fn with_rate_limit(
+    store: ResourceArc<StoreWrapper>,
+    ops_per_sec: Option<f64>,
+    bytes_per_sec: Option<f64>,
+) -> NifResult<ResourceArc<StoreWrapper>> {
+    let valid = |rate: Option<f64>| rate.is_none_or(|rate| rate.is_finite() && rate > 0.0);
+    if (ops_per_sec.is_none() && bytes_per_sec.is_none())
+        || !valid(ops_per_sec)
+        || !valid(bytes_per_sec)
+    {
+        return Err(rustler::Error::BadArg);
+    }
+
+    let child: Arc<DynObjectStore> = Arc::new(RateLimitStore::new(
+        store.inner.clone(),
+        ops_per_sec,
+        bytes_per_sec,
+    ));
+    Ok(ResourceArc::new(StoreWrapper::new(child)))
+}
+
+/// Wrap a store so every call is measured
+///
+/// Calls add to the global per-operation counters returned by `get_metrics`,
+/// and with a `subscriber` each call also sends it an event.
+#[rustler::nif]
+pub fn with_instrumentation(
+    store: ResourceArc<StoreWrapper>,
+    subscriber: Option<LocalPid>,
+) -> ResourceArc<StoreWrapper> {
+    let child: Arc<DynObjectStore> =
+        Arc::new(InstrumentedStore::new(store.inner.clone(), subscriber));
+    ResourceArc::new(StoreWrapper::new(child))
+}
+
+/// Wrap a store so every call is traced to a logger process
+///
+/// The handle keeps the low-level APIs of the parent (resumable uploads,
+/// signed S3 requests), which are not traced.
+#[rustler::nif]
+pub fn with_tracing(
+    store: ResourceArc<StoreWrapper>,
+    logger: LocalPid,
+) -> ResourceArc<StoreWrapper> {
+    let child: Arc<DynObjectStore> = Arc::new(TracedStore::new(store.inner.clone(), logger));
+    ResourceArc::new(store.observed(child))
+}
+
+/// Wrap a store so its calls fail with `:timeout` once `timeout_ms` has
+/// passed, or with `:cancelled` once `op_ref` is cancelled
+///
+/// The timeout starts now, so Elixir derives a handle per operation. Unlike
+/// tracing handles, the low-level APIs of the parent (resumable uploads,
+/// signed S3 requests, paged listings, appends, ...) are bounded too.
+#[rustler::nif]
+pub fn with_deadline(
+    store: ResourceArc<StoreWrapper>,
+    timeout_ms: Option<u64>,
+    op_ref: Option<ResourceArc<OpRef>>,
+) -> NifResult<ResourceArc<StoreWrapper>> {
+    if timeout_ms.is_none() && op_ref.is_none() {
+        return Err(rustler::Error::BadArg);
+    }
+
+    let deadline = Deadline::new(timeout_ms.map(Duration::from_millis), op_ref);
+    let bound = |store: Arc<DynObjectStore>| -> Arc<DynObjectStore> {
+        Arc::new(DeadlineStore::new(store, deadline.clone()))
+    };
+    let parent = store.detached();
+    Ok(ResourceArc::new(StoreWrapper {
+        inner: bound(parent.inner),
+        checksummed: parent.checksummed.map(bound),
+        multipart: parent.multipart.map(|client| -> Arc<dyn MultipartStore> {
+            Arc::new(DeadlineClient::new(client, deadline.clone()))
+        }),
+        paged: parent.paged.map(|client| -> Arc<dyn PagedListing> {
+            Arc::new(DeadlineClient::new(client, deadline.clone()))
+        }),
+        append: parent.append.map(|client| -> Arc<dyn Append> {
+            Arc::new(DeadlineClient::new(client, deadline.clone()))
+        }),
+        versioning: parent.versioning.map(|client| -> Arc<dyn Versioning> {
+            Arc::new(DeadlineClient::new(client, deadline.clone()))
+        }),
+        uploads: parent.uploads.map(|client| -> Arc<dyn IncompleteUploads> {
+            Arc::new(DeadlineClient::new(client, deadline.clone()))
+        }),
+        s3: parent.s3.map(|s3| Arc::new(s3.bounded(deadline.clone()))),
+        ..parent
+    }))
+}
+
+/// Create a cancellation token for operations given it as `:op_ref`
+#[rustler::nif]
+pub fn new_op_ref() -> ResourceArc<OpRef> {
+    ResourceArc::new(OpRef::new())
+}
+
+/// Cancel every operation given `op_ref`, including ones started later
+#[rustler::nif]
+pub fn cancel_op(op_ref: ResourceArc<OpRef>) -> Atom {
+    op_ref.cancel();
+    crate::atoms::ok()
+}
+
+/// Calls in flight on every store, oldest first
+///
+/// Returns `%{id:, operation:, path:, elapsed_ms:, bytes:}` maps.
+#[rustler::nif]
+pub fn list_inflight_operations(env: Env<'_>) -> Vec<inflight::InflightOperationNif> {
+    inflight::inflight_operations(env)
+}
+
+/// Totals of every operation made through instrumented stores
+///
+/// Returns `%{operation => %{count:, errors:, bytes:, duration_us:}}`.
+#[rustler::nif]
+pub fn get_metrics(env: Env<'_>) -> Term<'_> {
+    instrumented::encode_metrics(env)
+}
+
+/// Reset the counters returned by `get_metrics`
+#[rustler::nif]
+pub fn reset_metrics() -> Atom {
+    instrumented::reset_metrics();
+    crate::atoms::ok()
+}
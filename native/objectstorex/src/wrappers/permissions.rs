@@ -0,0 +1,235 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use object_store::local::LocalFileSystem;
+use object_store::path::Path;
+use object_store::{
+    DynObjectStore, GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta, ObjectStore,
+    PutMultipartOpts, PutOptions, PutPayload, PutResult, Result, UploadPart,
+};
+use std::fmt;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// Mode bits applied to files and directories written through a local store
+#[derive(Debug)]
+struct Permissions {
+    fs: Arc<LocalFileSystem>,
+    root: PathBuf,
+    file_mode: Option<u32>,
+    dir_mode: Option<u32>,
+}
+
+impl Permissions {
+    /// Directories between the store root and `location` that don't exist yet,
+    /// outermost first
+    fn missing_dirs(&self, location: &Path) -> Vec<PathBuf> {
+        if self.dir_mode.is_none() {
+            return Vec::new();
+        }
+
+        let Ok(file) = self.fs.path_to_filesystem(location) else {
+            return Vec::new();
+        };
+
+        let mut missing: Vec<PathBuf> = file
+            .ancestors()
+            .skip(1)
+            .take_while(|dir| dir.starts_with(&self.root) && *dir != self.root && !dir.exists())
+            .map(PathBuf::from)
+            .collect();
+        missing.reverse();
+        missing
+    }
+
+    /// Apply the configured modes to a written object and the directories
+    /// created for it
+    ///
+    /// Modes are set explicitly, so they are not reduced by the process umask.
+    fn apply(&self, location: &Path, created_dirs: &[PathBuf]) -> Result<()> {
+        if let Some(mode) = self.dir_mode {
+            for dir in created_dirs {
+                set_mode(dir, mode)?;
+            }
+        }
+
+        if let Some(mode) = self.file_mode {
+            set_mode(&self.fs.path_to_filesystem(location)?, mode)?;
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(unix)]
+fn set_mode(path: &std::path::Path, mode: u32) -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode)).map_err(|source| {
+        object_store::Error::Generic {
+            store: "LocalFileSystem",
+            source: format!("unable to set mode of {}: {}", path.display(), source).into(),
+        }
+    })
+}
+
+#[cfg(not(unix))]
+fn set_mode(_path: &std::path::Path, _mode: u32) -> Result<()> {
+    Ok(())
+}
+
+/// Local store wrapper that sets file and directory mode bits on every write
+///
+/// Needed when the written files are consumed by other system users, since the
+/// modes `LocalFileSystem` creates depend on the process umask. Mode bits are
+/// ignored on platforms without Unix permissions.
+#[derive(Debug)]
+pub struct PermissionsStore {
+    inner: Arc<DynObjectStore>,
+    permissions: Arc<Permissions>,
+}
+
+impl PermissionsStore {
+    /// Wrap `inner`, a store writing through `fs`
+    pub fn new(
+        inner: Arc<DynObjectStore>,
+        fs: Arc<LocalFileSystem>,
+        root: PathBuf,
+        file_mode: Option<u32>,
+        dir_mode: Option<u32>,
+    ) -> Self {
+        Self {
+            permissions: Arc::new(Permissions {
+                fs,
+                root,
+                file_mode,
+                dir_mode,
+            }),
+            inner,
+        }
+    }
+}
+
+impl fmt::Display for PermissionsStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "PermissionsStore({})", self.inner)
+    }
+}
+
+#[async_trait]
+impl ObjectStore for PermissionsStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        let created = self.permissions.missing_dirs(location);
+        let result = self.inner.put_opts(location, payload, opts).await?;
+        self.permissions.apply(location, &created)?;
+        Ok(result)
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        let created = self.permissions.missing_dirs(location);
+        let upload = self.inner.put_multipart_opts(location, opts).await?;
+
+        Ok(Box::new(PermissionsUpload {
+            inner: upload,
+            location: location.clone(),
+            created,
+            permissions: self.permissions.clone(),
+        }))
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        self.inner.get_opts(location, options).await
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        self.inner.get_range(location, range).await
+    }
+
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<usize>]) -> Result<Vec<Bytes>> {
+        self.inner.get_ranges(location, ranges).await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        self.inner.head(location).await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        self.inner.delete(location).await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.inner.list(prefix)
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.inner.list_with_offset(prefix, offset)
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        let created = self.permissions.missing_dirs(to);
+        self.inner.copy(from, to).await?;
+        self.permissions.apply(to, &created)
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        let created = self.permissions.missing_dirs(to);
+        self.inner.rename(from, to).await?;
+        self.permissions.apply(to, &created)
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        let created = self.permissions.missing_dirs(to);
+        self.inner.copy_if_not_exists(from, to).await?;
+        self.permissions.apply(to, &created)
+    }
+
+    async fn rename_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        let created = self.permissions.missing_dirs(to);
+        self.inner.rename_if_not_exists(from, to).await?;
+        self.permissions.apply(to, &created)
+    }
+}
+
+/// Multipart upload that applies the store's modes once the file is in place
+#[derive(Debug)]
+struct PermissionsUpload {
+    inner: Box<dyn MultipartUpload>,
+    location: Path,
+    created: Vec<PathBuf>,
+    permissions: Arc<Permissions>,
+}
+
+#[async_trait]
+impl MultipartUpload for PermissionsUpload {
+    fn put_part(&mut self, data: PutPayload) -> UploadPart {
+        self.inner.put_part(data)
+    }
+
+    async fn complete(&mut self) -> Result<PutResult> {
+        let result = self.inner.complete().await?;
+        self.permissions.apply(&self.location, &self.created)?;
+        Ok(result)
+    }
+
+    async fn abort(&mut self) -> Result<()> {
+        self.inner.abort().await
+    }
+}
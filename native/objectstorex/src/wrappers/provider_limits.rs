@@ -0,0 +1,377 @@
+use super::defaults::decode_tags;
+use crate::atoms;
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use object_store::path::Path;
+use object_store::{
+    Attribute, Attributes, DynObjectStore, Error, GetOptions, GetResult, ListResult,
+    MultipartUpload, ObjectMeta, ObjectStore, PutMultipartOpts, PutOptions, PutPayload, PutResult,
+    Result, TagSet, UploadPart,
+};
+use rustler::Atom;
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Store name used for limit violations, matched by `errors::map_error`
+pub const LIMITS_STORE: &str = "ProviderLimits";
+
+/// Documented request limits of a provider
+#[derive(Debug, Clone, Copy)]
+pub struct ProviderLimits {
+    /// Maximum object key length in bytes
+    pub max_key_length: usize,
+    /// Maximum total bytes of user metadata keys and values
+    pub max_metadata_bytes: usize,
+    /// Maximum number of tags per object, if the provider supports tags
+    pub max_tags: Option<usize>,
+    /// Maximum tag key and value length in characters
+    pub max_tag_key_length: usize,
+    pub max_tag_value_length: usize,
+    /// Maximum number of parts of a multipart upload
+    pub max_parts: usize,
+    /// Minimum size of every part but the last
+    pub min_part_size: Option<usize>,
+}
+
+/// Amazon S3 limits
+pub const S3: ProviderLimits = ProviderLimits {
+    max_key_length: 1024,
+    max_metadata_bytes: 2 * 1024,
+    max_tags: Some(10),
+    max_tag_key_length: 128,
+    max_tag_value_length: 256,
+    max_parts: 10_000,
+    min_part_size: Some(5 * 1024 * 1024),
+};
+
+/// Azure Blob Storage limits
+pub const AZURE: ProviderLimits = ProviderLimits {
+    max_key_length: 1024,
+    max_metadata_bytes: 8 * 1024,
+    max_tags: Some(10),
+    max_tag_key_length: 128,
+    max_tag_value_length: 256,
+    max_parts: 50_000,
+    min_part_size: None,
+};
+
+/// Google Cloud Storage limits
+pub const GCS: ProviderLimits = ProviderLimits {
+    max_key_length: 1024,
+    max_metadata_bytes: 8 * 1024,
+    max_tags: None,
+    max_tag_key_length: 0,
+    max_tag_value_length: 0,
+    max_parts: 10_000,
+    min_part_size: Some(5 * 1024 * 1024),
+};
+
+/// A provider limit a request would violate
+#[derive(Debug)]
+pub enum LimitViolation {
+    KeyTooLong { length: usize, max: usize },
+    MetadataTooLarge { bytes: usize, max: usize },
+    TooManyTags { count: usize, max: usize },
+    TagTooLong { tag: String, max: usize },
+    TooManyParts { max: usize },
+    PartTooSmall { size: usize, min: usize },
+}
+
+impl LimitViolation {
+    /// Elixir atom naming the violated limit
+    pub fn atom(&self) -> Atom {
+        match self {
+            LimitViolation::KeyTooLong { .. } => atoms::key_too_long(),
+            LimitViolation::MetadataTooLarge { .. } => atoms::metadata_too_large(),
+            LimitViolation::TooManyTags { .. } => atoms::too_many_tags(),
+            LimitViolation::TagTooLong { .. } => atoms::tag_too_long(),
+            LimitViolation::TooManyParts { .. } => atoms::too_many_parts(),
+            LimitViolation::PartTooSmall { .. } => atoms::part_too_small(),
+        }
+    }
+}
+
+impl fmt::Display for LimitViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LimitViolation::KeyTooLong { length, max } => {
+                write!(f, "key of {} bytes exceeds {} bytes", length, max)
+            }
+            LimitViolation::MetadataTooLarge { bytes, max } => {
+                write!(f, "metadata of {} bytes exceeds {} bytes", bytes, max)
+            }
+            LimitViolation::TooManyTags { count, max } => {
+                write!(f, "{} tags exceed {} tags", count, max)
+            }
+            LimitViolation::TagTooLong { tag, max } => {
+                write!(f, "tag {:?} exceeds {} characters", tag, max)
+            }
+            LimitViolation::TooManyParts { max } => {
+                write!(f, "upload exceeds {} parts", max)
+            }
+            LimitViolation::PartTooSmall { size, min } => {
+                write!(
+                    f,
+                    "part of {} bytes is below the {} byte minimum",
+                    size, min
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for LimitViolation {}
+
+impl From<LimitViolation> for Error {
+    fn from(violation: LimitViolation) -> Self {
+        Error::Generic {
+            store: LIMITS_STORE,
+            source: Box::new(violation),
+        }
+    }
+}
+
+impl ProviderLimits {
+    /// Check the key, metadata and tags of a write
+    fn check_write(
+        &self,
+        location: &Path,
+        attributes: &Attributes,
+        tags: &TagSet,
+    ) -> std::result::Result<(), LimitViolation> {
+        let length = location.as_ref().len();
+        if length > self.max_key_length {
+            return Err(LimitViolation::KeyTooLong {
+                length,
+                max: self.max_key_length,
+            });
+        }
+
+        let bytes: usize = attributes
+            .iter()
+            .filter_map(|(attribute, value)| match attribute {
+                Attribute::Metadata(key) => Some(key.len() + value.len()),
+                _ => None,
+            })
+            .sum();
+        if bytes > self.max_metadata_bytes {
+            return Err(LimitViolation::MetadataTooLarge {
+                bytes,
+                max: self.max_metadata_bytes,
+            });
+        }
+
+        self.check_tags(tags)
+    }
+
+    fn check_tags(&self, tags: &TagSet) -> std::result::Result<(), LimitViolation> {
+        let Some(max) = self.max_tags else {
+            return Ok(());
+        };
+
+        let pairs = decode_tags(tags);
+
+        if pairs.len() > max {
+            return Err(LimitViolation::TooManyTags {
+                count: pairs.len(),
+                max,
+            });
+        }
+
+        for (key, value) in pairs {
+            if key.chars().count() > self.max_tag_key_length {
+                return Err(LimitViolation::TagTooLong {
+                    tag: key,
+                    max: self.max_tag_key_length,
+                });
+            }
+            if value.chars().count() > self.max_tag_value_length {
+                return Err(LimitViolation::TagTooLong {
+                    tag: key,
+                    max: self.max_tag_value_length,
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Store wrapper that rejects requests violating known provider limits
+///
+/// Requests fail before anything is sent, with an error naming the limit,
+/// instead of a generic 400 from the provider after the payload was uploaded.
+#[derive(Debug)]
+pub struct ProviderLimitsStore {
+    inner: Arc<DynObjectStore>,
+    limits: ProviderLimits,
+}
+
+impl ProviderLimitsStore {
+    pub fn new(inner: Arc<DynObjectStore>, limits: ProviderLimits) -> Self {
+        Self { inner, limits }
+    }
+
+    fn check_key(&self, location: &Path) -> Result<()> {
+        let length = location.as_ref().len();
+        if length > self.limits.max_key_length {
+            return Err(LimitViolation::KeyTooLong {
+                length,
+                max: self.limits.max_key_length,
+            }
+            .into());
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ProviderLimitsStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ProviderLimitsStore({})", self.inner)
+    }
+}
+
+#[async_trait]
+impl ObjectStore for ProviderLimitsStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        self.limits
+            .check_write(location, &opts.attributes, &opts.tags)?;
+        self.inner.put_opts(location, payload, opts).await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        self.limits
+            .check_write(location, &opts.attributes, &opts.tags)?;
+        let upload = self.inner.put_multipart_opts(location, opts).await?;
+
+        Ok(Box::new(ProviderLimitsUpload {
+            upload,
+            limits: self.limits,
+            parts: 0,
+            last_part_size: None,
+        }))
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        self.inner.get_opts(location, options).await
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        self.inner.get_range(location, range).await
+    }
+
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<usize>]) -> Result<Vec<Bytes>> {
+        self.inner.get_ranges(location, ranges).await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        self.inner.head(location).await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        self.inner.delete(location).await
+    }
+
+    fn delete_stream<'a>(
+        &'a self,
+        locations: BoxStream<'a, Result<Path>>,
+    ) -> BoxStream<'a, Result<Path>> {
+        self.inner.delete_stream(locations)
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.inner.list(prefix)
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.inner.list_with_offset(prefix, offset)
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        self.check_key(to)?;
+        self.inner.copy(from, to).await
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        self.check_key(to)?;
+        self.inner.rename(from, to).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.check_key(to)?;
+        self.inner.copy_if_not_exists(from, to).await
+    }
+
+    async fn rename_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.check_key(to)?;
+        self.inner.rename_if_not_exists(from, to).await
+    }
+}
+
+/// Multipart upload that rejects parts beyond the provider's part limits
+///
+/// A part smaller than the minimum is only an error once another part
+/// follows it, since the last part may be of any size.
+#[derive(Debug)]
+struct ProviderLimitsUpload {
+    upload: Box<dyn MultipartUpload>,
+    limits: ProviderLimits,
+    parts: usize,
+    last_part_size: Option<usize>,
+}
+
+impl ProviderLimitsUpload {
+    fn check_part(&self) -> std::result::Result<(), LimitViolation> {
+        if self.parts >= self.limits.max_parts {
+            return Err(LimitViolation::TooManyParts {
+                max: self.limits.max_parts,
+            });
+        }
+
+        match (self.last_part_size, self.limits.min_part_size) {
+            (Some(size), Some(min)) if size < min => {
+                Err(LimitViolation::PartTooSmall { size, min })
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+#[async_trait]
+impl MultipartUpload for ProviderLimitsUpload {
+    fn put_part(&mut self, data: PutPayload) -> UploadPart {
+        if let Err(violation) = self.check_part() {
+            return Box::pin(futures::future::ready(Err(violation.into())));
+        }
+
+        self.parts += 1;
+        self.last_part_size = Some(data.content_length());
+        self.upload.put_part(data)
+    }
+
+    async fn complete(&mut self) -> Result<PutResult> {
+        self.upload.complete().await
+    }
+
+    async fn abort(&mut self) -> Result<()> {
+        self.upload.abort().await
+    }
+}
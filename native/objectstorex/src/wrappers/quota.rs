@@ -0,0 +1,276 @@
+use crate::ttl;
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use object_store::path::Path;
+use object_store::{
+    DynObjectStore, Error, GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta,
+    ObjectStore, PutMultipartOpts, PutOptions, PutPayload, PutResult, Result, UploadPart,
+};
+use std::fmt;
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+
+/// Store name used for quota errors, matched by `errors::map_error`
+pub const QUOTA_STORE: &str = "Quota";
+
+/// Byte budget shared by a quota store and its multipart uploads
+#[derive(Debug)]
+struct Usage {
+    limit: u64,
+    used: AtomicU64,
+}
+
+impl Usage {
+    /// Reserve `bytes` of the budget, failing if the limit would be exceeded
+    fn reserve(&self, bytes: u64) -> Result<()> {
+        self.used
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |used| {
+                used.checked_add(bytes).filter(|total| *total <= self.limit)
+            })
+            .map(|_| ())
+            .map_err(|used| Error::Generic {
+                store: QUOTA_STORE,
+                source: format!(
+                    "quota of {} bytes exceeded ({} used, {} requested)",
+                    self.limit, used, bytes
+                )
+                .into(),
+            })
+    }
+
+    /// Return `bytes` to the budget
+    fn release(&self, bytes: u64) {
+        let _ = self
+            .used
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |used| {
+                Some(used.saturating_sub(bytes))
+            });
+    }
+}
+
+/// Store wrapper that caps the total bytes stored through it
+///
+/// Usage starts from the size of the objects visible through the inner store
+/// when the wrapper is created. Puts, multipart parts and copies reserve bytes
+/// before anything is sent, overwrites and deletes give back the size of the
+/// replaced object. Writes made through other handles are not tracked, and
+/// neither are the expiry records of `put_with_ttl`.
+#[derive(Debug)]
+pub struct QuotaStore {
+    inner: Arc<DynObjectStore>,
+    usage: Arc<Usage>,
+}
+
+impl QuotaStore {
+    pub fn new(inner: Arc<DynObjectStore>, limit: u64, used: u64) -> Self {
+        Self {
+            inner,
+            usage: Arc::new(Usage {
+                limit,
+                used: AtomicU64::new(used),
+            }),
+        }
+    }
+
+    /// Sum the sizes of all objects currently visible through `store`
+    pub async fn current_usage(store: &DynObjectStore) -> Result<u64> {
+        let mut total = 0u64;
+        let mut stream = store.list(None);
+        while let Some(meta) = stream.next().await {
+            total += meta?.size as u64;
+        }
+        Ok(total)
+    }
+
+    /// Size of the object at `location`, or 0 if it doesn't exist
+    async fn existing_size(&self, location: &Path) -> Result<u64> {
+        match self.inner.head(location).await {
+            Ok(meta) => Ok(meta.size as u64),
+            Err(Error::NotFound { .. }) => Ok(0),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+impl fmt::Display for QuotaStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "QuotaStore({}, {})", self.usage.limit, self.inner)
+    }
+}
+
+#[async_trait]
+impl ObjectStore for QuotaStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        if ttl::is_record(location) {
+            return self.inner.put_opts(location, payload, opts).await;
+        }
+        let size = payload.content_length() as u64;
+        let previous = self.existing_size(location).await?;
+
+        if size > previous {
+            self.usage.reserve(size - previous)?;
+        }
+
+        match self.inner.put_opts(location, payload, opts).await {
+            Ok(result) => {
+                if previous > size {
+                    self.usage.release(previous - size);
+                }
+                Ok(result)
+            }
+            Err(e) => {
+                if size > previous {
+                    self.usage.release(size - previous);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        let previous = self.existing_size(location).await?;
+        let upload = self.inner.put_multipart_opts(location, opts).await?;
+
+        Ok(Box::new(QuotaUpload {
+            upload,
+            usage: self.usage.clone(),
+            reserved: 0,
+            previous,
+        }))
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        self.inner.get_opts(location, options).await
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        self.inner.get_range(location, range).await
+    }
+
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<usize>]) -> Result<Vec<Bytes>> {
+        self.inner.get_ranges(location, ranges).await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        self.inner.head(location).await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        if ttl::is_record(location) {
+            return self.inner.delete(location).await;
+        }
+        let previous = self.existing_size(location).await?;
+        self.inner.delete(location).await?;
+        self.usage.release(previous);
+        Ok(())
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.inner.list(prefix)
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.inner.list_with_offset(prefix, offset)
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        let size = self.inner.head(from).await?.size as u64;
+        let previous = self.existing_size(to).await?;
+
+        if size > previous {
+            self.usage.reserve(size - previous)?;
+        }
+
+        match self.inner.copy(from, to).await {
+            Ok(()) => {
+                if previous > size {
+                    self.usage.release(previous - size);
+                }
+                Ok(())
+            }
+            Err(e) => {
+                if size > previous {
+                    self.usage.release(size - previous);
+                }
+                Err(e)
+            }
+        }
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        let size = self.inner.head(from).await?.size as u64;
+        self.usage.reserve(size)?;
+
+        self.inner
+            .copy_if_not_exists(from, to)
+            .await
+            .inspect_err(|_| self.usage.release(size))
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        let previous = self.existing_size(to).await?;
+        self.inner.rename(from, to).await?;
+        self.usage.release(previous);
+        Ok(())
+    }
+
+    async fn rename_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.inner.rename_if_not_exists(from, to).await
+    }
+}
+
+/// Multipart upload that reserves quota for each part before sending it
+#[derive(Debug)]
+struct QuotaUpload {
+    upload: Box<dyn MultipartUpload>,
+    usage: Arc<Usage>,
+    reserved: u64,
+    previous: u64,
+}
+
+#[async_trait]
+impl MultipartUpload for QuotaUpload {
+    fn put_part(&mut self, data: PutPayload) -> UploadPart {
+        let size = data.content_length() as u64;
+        if let Err(e) = self.usage.reserve(size) {
+            return Box::pin(futures::future::ready(Err(e)));
+        }
+        self.reserved += size;
+        self.upload.put_part(data)
+    }
+
+    async fn complete(&mut self) -> Result<PutResult> {
+        let result = self.upload.complete().await?;
+        // The completed object replaces whatever was stored at the location
+        self.usage.release(self.previous);
+        self.reserved = 0;
+        Ok(result)
+    }
+
+    async fn abort(&mut self) -> Result<()> {
+        self.upload.abort().await?;
+        self.usage.release(self.reserved);
+        self.reserved = 0;
+        Ok(())
+    }
+}
@@ -0,0 +1,292 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::{BoxStream, StreamExt};
+use futures::FutureExt;
+use object_store::path::Path;
+use object_store::{
+    DynObjectStore, GetOptions, GetResult, GetResultPayload, ListResult, MultipartUpload,
+    ObjectMeta, ObjectStore, PutMultipartOpts, PutOptions, PutPayload, PutResult, Result,
+    UploadPart,
+};
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+/// Token bucket refilled at `rate` tokens per second, holding at most one
+/// second's worth
+#[derive(Debug)]
+struct Bucket {
+    rate: f64,
+    capacity: f64,
+    /// Tokens left, negative while callers wait for a refill, and when they
+    /// were counted
+    state: Mutex<(f64, Instant)>,
+}
+
+impl Bucket {
+    fn new(rate: f64) -> Self {
+        let capacity = rate.max(1.0);
+        Self {
+            rate,
+            capacity,
+            state: Mutex::new((capacity, Instant::now())),
+        }
+    }
+
+    /// Take `amount` tokens, waiting until the bucket has refilled enough
+    ///
+    /// The lock is held while waiting, so callers are served in order and an
+    /// amount larger than the bucket only delays the callers behind it.
+    async fn take(&self, amount: f64) {
+        let mut state = self.state.lock().await;
+        let (tokens, counted) = &mut *state;
+
+        let now = Instant::now();
+        *tokens = (*tokens + now.duration_since(*counted).as_secs_f64() * self.rate)
+            .min(self.capacity)
+            - amount;
+        *counted = now;
+
+        if *tokens < 0.0 {
+            tokio::time::sleep(Duration::from_secs_f64(-*tokens / self.rate)).await;
+        }
+    }
+}
+
+/// Request and transfer rates shared by every caller of a store
+#[derive(Debug)]
+struct Limits {
+    ops: Option<Bucket>,
+    bytes: Option<Bucket>,
+}
+
+impl Limits {
+    /// Wait until a request may be made
+    async fn request(&self) {
+        if let Some(ops) = &self.ops {
+            ops.take(1.0).await;
+        }
+    }
+
+    /// Wait until `len` more bytes may be transferred
+    async fn transfer(&self, len: usize) {
+        if let Some(bytes) = &self.bytes {
+            bytes.take(len as f64).await;
+        }
+    }
+}
+
+/// Store limiting the rate of requests and transferred bytes with token
+/// buckets
+///
+/// Every call counts as one request, including a whole listing. Uploaded bytes
+/// are counted before each put or part is sent; downloaded bytes as each chunk
+/// of the response arrives, so a download slows down rather than waiting up
+/// front. Buckets start full and hold one second's worth of tokens, allowing
+/// short bursts up to the rate. Every handle to the store shares the buckets.
+pub struct RateLimitStore {
+    inner: Arc<DynObjectStore>,
+    limits: Arc<Limits>,
+}
+
+impl RateLimitStore {
+    pub fn new(
+        inner: Arc<DynObjectStore>,
+        ops_per_sec: Option<f64>,
+        bytes_per_sec: Option<f64>,
+    ) -> Self {
+        Self {
+            inner,
+            limits: Arc::new(Limits {
+                ops: ops_per_sec.map(Bucket::new),
+                bytes: bytes_per_sec.map(Bucket::new),
+            }),
+        }
+    }
+}
+
+impl fmt::Debug for RateLimitStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RateLimitStore")
+            .field("inner", &self.inner.to_string())
+            .field("ops_per_sec", &self.limits.ops.as_ref().map(|b| b.rate))
+            .field("bytes_per_sec", &self.limits.bytes.as_ref().map(|b| b.rate))
+            .finish()
+    }
+}
+
+impl fmt::Display for RateLimitStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RateLimitStore({})", self.inner)
+    }
+}
+
+/// Multipart upload whose parts count against the store's limits
+#[derive(Debug)]
+struct RateLimitedUpload {
+    upload: Box<dyn MultipartUpload>,
+    limits: Arc<Limits>,
+}
+
+#[async_trait]
+impl MultipartUpload for RateLimitedUpload {
+    fn put_part(&mut self, data: PutPayload) -> UploadPart {
+        let limits = self.limits.clone();
+        let len = data.content_length();
+        // Parts are numbered when put, but not sent until awaited
+        let part = self.upload.put_part(data);
+
+        async move {
+            limits.request().await;
+            limits.transfer(len).await;
+            part.await
+        }
+        .boxed()
+    }
+
+    async fn complete(&mut self) -> Result<PutResult> {
+        self.limits.request().await;
+        self.upload.complete().await
+    }
+
+    async fn abort(&mut self) -> Result<()> {
+        self.limits.request().await;
+        self.upload.abort().await
+    }
+}
+
+#[async_trait]
+impl ObjectStore for RateLimitStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        self.limits.request().await;
+        self.limits.transfer(payload.content_length()).await;
+        self.inner.put_opts(location, payload, opts).await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        self.limits.request().await;
+        let upload = self.inner.put_multipart_opts(location, opts).await?;
+        Ok(Box::new(RateLimitedUpload {
+            upload,
+            limits: self.limits.clone(),
+        }))
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        self.limits.request().await;
+        let result = self.inner.get_opts(location, options).await?;
+        if self.limits.bytes.is_none() {
+            return Ok(result);
+        }
+
+        let (meta, range, attributes) = (
+            result.meta.clone(),
+            result.range.clone(),
+            result.attributes.clone(),
+        );
+        let limits = self.limits.clone();
+        let stream = result
+            .into_stream()
+            .then(move |chunk| {
+                let limits = limits.clone();
+                async move {
+                    if let Ok(bytes) = &chunk {
+                        limits.transfer(bytes.len()).await;
+                    }
+                    chunk
+                }
+            })
+            .boxed();
+
+        Ok(GetResult {
+            payload: GetResultPayload::Stream(stream),
+            meta,
+            range,
+            attributes,
+        })
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        self.limits.request().await;
+        self.limits.transfer(range.len()).await;
+        self.inner.get_range(location, range).await
+    }
+
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<usize>]) -> Result<Vec<Bytes>> {
+        self.limits.request().await;
+        self.limits
+            .transfer(ranges.iter().map(|range| range.len()).sum())
+            .await;
+        self.inner.get_ranges(location, ranges).await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        self.limits.request().await;
+        self.inner.head(location).await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        self.limits.request().await;
+        self.inner.delete(location).await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        let prefix = prefix.cloned();
+        async move {
+            self.limits.request().await;
+            self.inner.list(prefix.as_ref())
+        }
+        .flatten_stream()
+        .boxed()
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'_, Result<ObjectMeta>> {
+        let (prefix, offset) = (prefix.cloned(), offset.clone());
+        async move {
+            self.limits.request().await;
+            self.inner.list_with_offset(prefix.as_ref(), &offset)
+        }
+        .flatten_stream()
+        .boxed()
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        self.limits.request().await;
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        self.limits.request().await;
+        self.inner.copy(from, to).await
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        self.limits.request().await;
+        self.inner.rename(from, to).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.limits.request().await;
+        self.inner.copy_if_not_exists(from, to).await
+    }
+
+    async fn rename_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.limits.request().await;
+        self.inner.rename_if_not_exists(from, to).await
+    }
+}
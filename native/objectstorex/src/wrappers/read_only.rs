@@ -0,0 +1,110 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use object_store::path::Path;
+use object_store::{
+    DynObjectStore, Error, GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta,
+    ObjectStore, PutMultipartOpts, PutOptions, PutPayload, PutResult, Result,
+};
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// Store wrapper that rejects every write with `PermissionDenied`
+///
+/// Reads, heads and listings are delegated to the inner store unchanged.
+#[derive(Debug)]
+pub struct ReadOnlyStore {
+    inner: Arc<DynObjectStore>,
+}
+
+impl ReadOnlyStore {
+    pub fn new(inner: Arc<DynObjectStore>) -> Self {
+        Self { inner }
+    }
+}
+
+impl fmt::Display for ReadOnlyStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "ReadOnlyStore({})", self.inner)
+    }
+}
+
+fn denied(path: &Path) -> Error {
+    Error::PermissionDenied {
+        path: path.to_string(),
+        source: "store handle is read-only".into(),
+    }
+}
+
+#[async_trait]
+impl ObjectStore for ReadOnlyStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        _payload: PutPayload,
+        _opts: PutOptions,
+    ) -> Result<PutResult> {
+        Err(denied(location))
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        _opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        Err(denied(location))
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        self.inner.get_opts(location, options).await
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        self.inner.get_range(location, range).await
+    }
+
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<usize>]) -> Result<Vec<Bytes>> {
+        self.inner.get_ranges(location, ranges).await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        self.inner.head(location).await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        Err(denied(location))
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.inner.list(prefix)
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.inner.list_with_offset(prefix, offset)
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, _from: &Path, to: &Path) -> Result<()> {
+        Err(denied(to))
+    }
+
+    async fn rename(&self, from: &Path, _to: &Path) -> Result<()> {
+        Err(denied(from))
+    }
+
+    async fn copy_if_not_exists(&self, _from: &Path, to: &Path) -> Result<()> {
+        Err(denied(to))
+    }
+
+    async fn rename_if_not_exists(&self, from: &Path, _to: &Path) -> Result<()> {
+        Err(denied(from))
+    }
+}
@@ -0,0 +1,280 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::future::BoxFuture;
+use futures::stream::{BoxStream, Stream, StreamExt};
+use futures::FutureExt;
+use object_store::multipart::{MultipartStore, PartId};
+use object_store::path::Path;
+use object_store::{
+    GetOptions, GetResult, GetResultPayload, ListResult, MultipartId, MultipartUpload, ObjectMeta,
+    ObjectStore, PutMultipartOpts, PutOptions, PutPayload, PutResult, Result, UploadPart,
+};
+use once_cell::sync::OnceCell;
+use std::fmt;
+use std::ops::Range;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{ready, Context, Poll};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+/// Requests in flight across every provider client, if limited
+static REQUESTS: OnceCell<Arc<Semaphore>> = OnceCell::new();
+
+/// Limit the provider requests in flight across all stores to `max`
+///
+/// Set once, when the library is loaded; later calls are ignored.
+pub fn configure(max: usize) {
+    let _ = REQUESTS.set(Arc::new(Semaphore::new(max)));
+}
+
+/// Wait for a request slot, or `None` when requests aren't limited
+pub async fn acquire() -> Option<OwnedSemaphorePermit> {
+    let semaphore = REQUESTS.get()?.clone();
+    Some(
+        semaphore
+            .acquire_owned()
+            .await
+            .expect("request semaphore closed"),
+    )
+}
+
+/// Provider client whose requests share the global request limit
+///
+/// Every provider client is wrapped, so the limit holds however many stores
+/// are open. A slot is held while a request is in flight, and while each
+/// chunk of a response body or listing is awaited. Streams that are open but
+/// not being read don't hold one, so a download piped into an upload can't
+/// starve its own upload.
+pub struct RequestLimitStore<T> {
+    inner: Arc<T>,
+}
+
+impl<T> RequestLimitStore<T> {
+    pub fn new(inner: Arc<T>) -> Self {
+        Self { inner }
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for RequestLimitStore<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RequestLimitStore")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for RequestLimitStore<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RequestLimitStore({})", self.inner)
+    }
+}
+
+/// Stream holding a request slot while each item is awaited
+struct Limited<S> {
+    inner: S,
+    acquire: Option<BoxFuture<'static, Option<OwnedSemaphorePermit>>>,
+    /// Slot acquired for the item being awaited
+    permit: Option<Option<OwnedSemaphorePermit>>,
+}
+
+impl<S> Limited<S> {
+    fn new(inner: S) -> Self {
+        Self {
+            inner,
+            acquire: None,
+            permit: None,
+        }
+    }
+}
+
+impl<S: Stream + Unpin> Stream for Limited<S> {
+    type Item = S::Item;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if this.permit.is_none() {
+            let acquire = this.acquire.get_or_insert_with(|| acquire().boxed());
+            this.permit = Some(ready!(acquire.as_mut().poll(cx)));
+            this.acquire = None;
+        }
+
+        let item = ready!(Pin::new(&mut this.inner).poll_next(cx));
+        this.permit = None;
+        Poll::Ready(item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+fn limited<'a, T: Send + 'a>(stream: BoxStream<'a, T>) -> BoxStream<'a, T> {
+    match REQUESTS.get() {
+        Some(_) => Limited::new(stream).boxed(),
+        None => stream,
+    }
+}
+
+#[async_trait]
+impl<T: ObjectStore> ObjectStore for RequestLimitStore<T> {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        let _permit = acquire().await;
+        self.inner.put_opts(location, payload, opts).await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        let upload = {
+            let _permit = acquire().await;
+            self.inner.put_multipart_opts(location, opts).await?
+        };
+        Ok(Box::new(LimitedUpload { upload }))
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        let result = {
+            let _permit = acquire().await;
+            self.inner.get_opts(location, options).await?
+        };
+
+        let payload = match result.payload {
+            GetResultPayload::Stream(stream) => GetResultPayload::Stream(limited(stream)),
+            file => file,
+        };
+        Ok(GetResult { payload, ..result })
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        let _permit = acquire().await;
+        self.inner.get_range(location, range).await
+    }
+
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<usize>]) -> Result<Vec<Bytes>> {
+        let _permit = acquire().await;
+        self.inner.get_ranges(location, ranges).await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        let _permit = acquire().await;
+        self.inner.head(location).await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        let _permit = acquire().await;
+        self.inner.delete(location).await
+    }
+
+    fn delete_stream<'a>(
+        &'a self,
+        locations: BoxStream<'a, Result<Path>>,
+    ) -> BoxStream<'a, Result<Path>> {
+        limited(self.inner.delete_stream(locations))
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        limited(self.inner.list(prefix))
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'_, Result<ObjectMeta>> {
+        limited(self.inner.list_with_offset(prefix, offset))
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        let _permit = acquire().await;
+        self.inner.list_with_delimiter(prefix).await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        let _permit = acquire().await;
+        self.inner.copy(from, to).await
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        let _permit = acquire().await;
+        self.inner.rename(from, to).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        let _permit = acquire().await;
+        self.inner.copy_if_not_exists(from, to).await
+    }
+
+    async fn rename_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        let _permit = acquire().await;
+        self.inner.rename_if_not_exists(from, to).await
+    }
+}
+
+#[async_trait]
+impl<T: MultipartStore> MultipartStore for RequestLimitStore<T> {
+    async fn create_multipart(&self, path: &Path) -> Result<MultipartId> {
+        let _permit = acquire().await;
+        self.inner.create_multipart(path).await
+    }
+
+    async fn put_part(
+        &self,
+        path: &Path,
+        id: &MultipartId,
+        part_idx: usize,
+        data: PutPayload,
+    ) -> Result<PartId> {
+        let _permit = acquire().await;
+        self.inner.put_part(path, id, part_idx, data).await
+    }
+
+    async fn complete_multipart(
+        &self,
+        path: &Path,
+        id: &MultipartId,
+        parts: Vec<PartId>,
+    ) -> Result<PutResult> {
+        let _permit = acquire().await;
+        self.inner.complete_multipart(path, id, parts).await
+    }
+
+    async fn abort_multipart(&self, path: &Path, id: &MultipartId) -> Result<()> {
+        let _permit = acquire().await;
+        self.inner.abort_multipart(path, id).await
+    }
+}
+
+/// Multipart upload whose part requests share the global request limit
+#[derive(Debug)]
+struct LimitedUpload {
+    upload: Box<dyn MultipartUpload>,
+}
+
+#[async_trait]
+impl MultipartUpload for LimitedUpload {
+    fn put_part(&mut self, data: PutPayload) -> UploadPart {
+        let upload = self.upload.put_part(data);
+        Box::pin(async move {
+            let _permit = acquire().await;
+            upload.await
+        })
+    }
+
+    async fn complete(&mut self) -> Result<PutResult> {
+        let _permit = acquire().await;
+        self.upload.complete().await
+    }
+
+    async fn abort(&mut self) -> Result<()> {
+        let _permit = acquire().await;
+        self.upload.abort().await
+    }
+}
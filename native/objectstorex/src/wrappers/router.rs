@@ -0,0 +1,291 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::{self, BoxStream, StreamExt, TryStreamExt};
+use object_store::path::Path;
+use object_store::{
+    DynObjectStore, Error, GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta,
+    ObjectStore, PutMode, PutMultipartOpts, PutOptions, PutPayload, PutResult, Result,
+};
+use std::fmt;
+use std::ops::Range;
+use std::sync::Arc;
+
+/// A key prefix and the store holding the objects under it
+struct Route {
+    prefix: Path,
+    store: Arc<DynObjectStore>,
+}
+
+/// Store dispatching every operation to one of several stores by key prefix
+///
+/// Each location belongs to the route with the longest prefix it is under,
+/// matched by whole path segments; an empty prefix catches everything else.
+/// Locations are passed to the routed store unchanged. Listings merge the
+/// stores whose routes overlap the listed prefix, keeping only the objects
+/// each route owns. Copies and renames between stores read the object and
+/// write it to the destination store.
+pub struct RouterStore {
+    /// Routes ordered from the most to the least specific prefix
+    routes: Vec<Route>,
+}
+
+impl RouterStore {
+    /// Router over `(prefix, store)` routes, or `None` if a prefix repeats
+    pub fn new(routes: Vec<(Path, Arc<DynObjectStore>)>) -> Option<Self> {
+        let mut routes: Vec<Route> = routes
+            .into_iter()
+            .map(|(prefix, store)| Route { prefix, store })
+            .collect();
+        routes.sort_by_key(|route| std::cmp::Reverse(route.prefix.parts().count()));
+
+        for (i, route) in routes.iter().enumerate() {
+            if routes[i + 1..]
+                .iter()
+                .any(|other| other.prefix == route.prefix)
+            {
+                return None;
+            }
+        }
+
+        Some(Self { routes })
+    }
+
+    /// Index of the route owning `location`
+    fn owner(&self, location: &Path) -> Option<usize> {
+        self.routes
+            .iter()
+            .position(|route| location.prefix_matches(&route.prefix))
+    }
+
+    /// Store holding `location`
+    fn route(&self, location: &Path) -> Result<&Arc<DynObjectStore>> {
+        match self.owner(location) {
+            Some(i) => Ok(&self.routes[i].store),
+            None => Err(Error::Generic {
+                store: "RouterStore",
+                source: format!("no route for {}", location).into(),
+            }),
+        }
+    }
+
+    /// Routes with objects under `prefix`, with the narrowest prefix to list
+    /// each of them with
+    fn overlapping(&self, prefix: Option<&Path>) -> Vec<(usize, Path)> {
+        let prefix = prefix.cloned().unwrap_or_default();
+
+        self.routes
+            .iter()
+            .enumerate()
+            .filter_map(|(i, route)| {
+                if route.prefix.prefix_matches(&prefix) {
+                    Some((i, route.prefix.clone()))
+                } else if prefix.prefix_matches(&route.prefix) {
+                    Some((i, prefix.clone()))
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Copy an object from one store to another, optionally only if the
+    /// destination doesn't exist
+    async fn copy_across(
+        &self,
+        from: &Path,
+        to: &Path,
+        source: &Arc<DynObjectStore>,
+        target: &Arc<DynObjectStore>,
+        mode: PutMode,
+    ) -> Result<()> {
+        let result = source.get(from).await?;
+        let attributes = result.attributes.clone();
+        let data = result.bytes().await?;
+        let opts = PutOptions {
+            mode,
+            attributes,
+            ..Default::default()
+        };
+        target.put_opts(to, data.into(), opts).await?;
+        Ok(())
+    }
+}
+
+impl fmt::Debug for RouterStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map()
+            .entries(
+                self.routes
+                    .iter()
+                    .map(|route| (route.prefix.as_ref(), route.store.to_string())),
+            )
+            .finish()
+    }
+}
+
+impl fmt::Display for RouterStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "RouterStore(")?;
+        for (i, route) in self.routes.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{:?} => {}", route.prefix.as_ref(), route.store)?;
+        }
+        write!(f, ")")
+    }
+}
+
+#[async_trait]
+impl ObjectStore for RouterStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        self.route(location)?
+            .put_opts(location, payload, opts)
+            .await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        self.route(location)?
+            .put_multipart_opts(location, opts)
+            .await
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        self.route(location)?.get_opts(location, options).await
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        self.route(location)?.get_range(location, range).await
+    }
+
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<usize>]) -> Result<Vec<Bytes>> {
+        self.route(location)?.get_ranges(location, ranges).await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        self.route(location)?.head(location).await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        self.route(location)?.delete(location).await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        let listings: Vec<_> = self
+            .overlapping(prefix)
+            .into_iter()
+            .map(|(i, prefix)| {
+                self.routes[i]
+                    .store
+                    .list(Some(&prefix))
+                    .try_filter(move |meta| {
+                        futures::future::ready(self.owner(&meta.location) == Some(i))
+                    })
+            })
+            .collect();
+
+        stream::iter(listings).flatten().boxed()
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'_, Result<ObjectMeta>> {
+        let listings: Vec<_> = self
+            .overlapping(prefix)
+            .into_iter()
+            .map(|(i, prefix)| {
+                self.routes[i]
+                    .store
+                    .list_with_offset(Some(&prefix), offset)
+                    .try_filter(move |meta| {
+                        futures::future::ready(self.owner(&meta.location) == Some(i))
+                    })
+            })
+            .collect();
+
+        stream::iter(listings).flatten().boxed()
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        let mut objects = Vec::new();
+        let mut common_prefixes = Vec::new();
+
+        for (i, _) in self.overlapping(prefix) {
+            let route = &self.routes[i];
+            let listed = route.store.list_with_delimiter(prefix).await?;
+
+            objects.extend(
+                listed
+                    .objects
+                    .into_iter()
+                    .filter(|meta| self.owner(&meta.location) == Some(i)),
+            );
+            // Directories holding this route's prefix, or inside it and not
+            // taken over by a more specific route
+            common_prefixes.extend(
+                listed
+                    .common_prefixes
+                    .into_iter()
+                    .filter(|dir| route.prefix.prefix_matches(dir) || self.owner(dir) == Some(i)),
+            );
+        }
+
+        objects.sort_by(|a, b| a.location.cmp(&b.location));
+        common_prefixes.sort();
+        common_prefixes.dedup();
+
+        Ok(ListResult {
+            common_prefixes,
+            objects,
+        })
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        let (source, target) = (self.route(from)?, self.route(to)?);
+        if Arc::ptr_eq(source, target) {
+            return source.copy(from, to).await;
+        }
+        self.copy_across(from, to, source, target, PutMode::Overwrite)
+            .await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        let (source, target) = (self.route(from)?, self.route(to)?);
+        if Arc::ptr_eq(source, target) {
+            return source.copy_if_not_exists(from, to).await;
+        }
+        self.copy_across(from, to, source, target, PutMode::Create)
+            .await
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        let (source, target) = (self.route(from)?, self.route(to)?);
+        if Arc::ptr_eq(source, target) {
+            return source.rename(from, to).await;
+        }
+        self.copy_across(from, to, source, target, PutMode::Overwrite)
+            .await?;
+        source.delete(from).await
+    }
+
+    async fn rename_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        let (source, target) = (self.route(from)?, self.route(to)?);
+        if Arc::ptr_eq(source, target) {
+            return source.rename_if_not_exists(from, to).await;
+        }
+        self.copy_across(from, to, source, target, PutMode::Create)
+            .await?;
+        source.delete(from).await
+    }
+}
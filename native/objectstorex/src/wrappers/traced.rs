@@ -0,0 +1,418 @@
+use crate::atoms;
+use crate::errors::{error_kind, error_status, reported_status};
+use crate::RUNTIME;
+use async_trait::async_trait;
+use bytes::Bytes;
+use futures::stream::BoxStream;
+use futures::StreamExt;
+use object_store::path::Path;
+use object_store::{
+    DynObjectStore, GetOptions, GetResult, ListResult, MultipartUpload, ObjectMeta, ObjectStore,
+    PutMultipartOpts, PutOptions, PutPayload, PutResult, Result,
+};
+use once_cell::sync::Lazy;
+use rustler::types::map::map_new;
+use rustler::{Atom, Encoder, Env, LocalPid, Term};
+use std::fmt;
+use std::future::Future;
+use std::ops::Range;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use tokio::task::JoinHandle;
+use tracing::field::{Field, Visit};
+use tracing::instrument::WithSubscriber;
+use tracing::span::{Attributes as SpanAttributes, Id, Record};
+use tracing::{Dispatch, Event, Metadata, Subscriber};
+
+/// Source of operation ids, unique across all traced stores
+static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+
+/// Module object_store logs its request retries from
+const RETRY_TARGET: &str = "object_store::client::retry";
+
+/// Trace event sent to a logger
+enum TraceEvent {
+    Start {
+        operation: &'static str,
+        path: String,
+    },
+    Retry {
+        attempt: u64,
+        status: Option<u16>,
+        message: String,
+    },
+    Finish {
+        operation: &'static str,
+        path: String,
+        duration_us: u64,
+        result: Atom,
+        status: Option<u16>,
+    },
+}
+
+/// A traced operation, sending its events to the logger in order
+struct Operation {
+    id: u64,
+    logger: LocalPid,
+    /// Retries object_store has made for the operation so far
+    retries: AtomicU64,
+    /// Send of the previous event, which the next one waits for
+    last_sent: Mutex<Option<JoinHandle<bool>>>,
+}
+
+tokio::task_local! {
+    static CURRENT: Arc<Operation>;
+}
+
+/// Dispatcher traced calls run under, reporting their retries
+///
+/// Scoped to the calls themselves, leaving the application's subscriber
+/// alone.
+static RETRIES: Lazy<Dispatch> = Lazy::new(|| Dispatch::new(RetryReporter));
+
+impl Operation {
+    fn send(&self, event: TraceEvent) {
+        let id = self.id;
+        let mut last_sent = self.last_sent.lock().unwrap();
+        let previous = last_sent.take();
+        *last_sent = Some(RUNTIME.send(self.logger, previous, move |env| {
+            encode_event(env, id, &event)
+        }));
+    }
+}
+
+/// `{:objectstorex_trace, op_id, event, data}`
+fn encode_event<'a>(env: Env<'a>, id: u64, event: &TraceEvent) -> Term<'a> {
+    let key = |name: &str| Atom::from_str(env, name).unwrap().encode(env);
+    let map = |pairs: Vec<(&str, Term<'a>)>| {
+        pairs.into_iter().fold(map_new(env), |map, (name, value)| {
+            map.map_put(key(name), value).unwrap()
+        })
+    };
+
+    let (name, data) = match event {
+        TraceEvent::Start { operation, path } => (
+            "start",
+            map(vec![
+                ("operation", key(operation)),
+                ("path", path.encode(env)),
+            ]),
+        ),
+        TraceEvent::Retry {
+            attempt,
+            status,
+            message,
+        } => (
+            "retry",
+            map(vec![
+                ("attempt", attempt.encode(env)),
+                ("status", status.encode(env)),
+                ("message", message.encode(env)),
+            ]),
+        ),
+        TraceEvent::Finish {
+            operation,
+            path,
+            duration_us,
+            result,
+            status,
+        } => (
+            "finish",
+            map(vec![
+                ("operation", key(operation)),
+                ("path", path.encode(env)),
+                ("duration_us", duration_us.encode(env)),
+                ("result", result.encode(env)),
+                ("status", status.encode(env)),
+            ]),
+        ),
+    };
+
+    (key("objectstorex_trace"), id, key(name), data).encode(env)
+}
+
+/// Reports the retries object_store makes to the logger of the operation
+/// that caused them
+///
+/// object_store has no structured retry events: its retry module logs each
+/// retry, and also dumps response bodies it checks for embedded errors. Only
+/// the retries announce a backoff. Attempts are counted per operation and the
+/// log message is passed on as is.
+struct RetryReporter;
+
+#[derive(Default)]
+struct Message(String);
+
+impl Visit for Message {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.0 = format!("{:?}", value);
+        }
+    }
+}
+
+impl Subscriber for RetryReporter {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.is_event() && metadata.target() == RETRY_TARGET
+    }
+
+    fn new_span(&self, _span: &SpanAttributes<'_>) -> Id {
+        Id::from_u64(1)
+    }
+
+    fn record(&self, _span: &Id, _values: &Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let Ok(operation) = CURRENT.try_with(Arc::clone) else {
+            return;
+        };
+
+        let mut message = Message::default();
+        event.record(&mut message);
+        if !message.0.contains("backing off") {
+            return;
+        }
+
+        operation.send(TraceEvent::Retry {
+            attempt: operation.retries.fetch_add(1, Ordering::Relaxed) + 1,
+            status: reported_status(&message.0),
+            message: message.0,
+        });
+    }
+
+    fn enter(&self, _span: &Id) {}
+
+    fn exit(&self, _span: &Id) {}
+}
+
+/// Store wrapper that reports every call to a logger process
+///
+/// Each call gets an operation id and sends `start`, `retry` (for every retry
+/// object_store makes) and `finish` events with latency, outcome and the HTTP
+/// status, when the response had one. Listings report `finish` when their
+/// stream is dropped and don't report retries.
+pub struct TracedStore {
+    inner: Arc<DynObjectStore>,
+    logger: LocalPid,
+}
+
+impl TracedStore {
+    pub fn new(inner: Arc<DynObjectStore>, logger: LocalPid) -> Self {
+        Self { inner, logger }
+    }
+
+    /// Run `call` as a traced operation
+    async fn traced<T>(
+        &self,
+        operation: &'static str,
+        path: &Path,
+        call: impl Future<Output = Result<T>>,
+    ) -> Result<T> {
+        let current = self.start(operation, path);
+        let started = Instant::now();
+        let result = CURRENT
+            .scope(current.clone(), call)
+            .with_subscriber(RETRIES.clone())
+            .await;
+
+        let (result_atom, status) = match &result {
+            Ok(_) => (atoms::ok(), None),
+            Err(e) => (error_kind(e), error_status(e)),
+        };
+        current.send(TraceEvent::Finish {
+            operation,
+            path: path.to_string(),
+            duration_us: started.elapsed().as_micros() as u64,
+            result: result_atom,
+            status,
+        });
+        result
+    }
+
+    fn start(&self, operation: &'static str, path: &Path) -> Arc<Operation> {
+        let current = Arc::new(Operation {
+            id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
+            logger: self.logger,
+            retries: AtomicU64::new(0),
+            last_sent: Mutex::new(None),
+        });
+        current.send(TraceEvent::Start {
+            operation,
+            path: path.to_string(),
+        });
+        current
+    }
+
+    /// Trace a listing until its stream is dropped
+    fn list_stream<'a>(
+        &self,
+        prefix: Option<&Path>,
+        stream: BoxStream<'a, Result<ObjectMeta>>,
+    ) -> BoxStream<'a, Result<ObjectMeta>> {
+        let prefix = prefix.cloned().unwrap_or_default();
+        let guard = ListGuard {
+            operation: self.start("list", &prefix),
+            prefix,
+            started: Instant::now(),
+            result: atoms::ok(),
+            status: None,
+        };
+
+        stream
+            .scan(guard, |guard, entry| {
+                if let Err(e) = &entry {
+                    guard.result = error_kind(e);
+                    guard.status = error_status(e);
+                }
+                futures::future::ready(Some(entry))
+            })
+            .boxed()
+    }
+}
+
+impl fmt::Debug for TracedStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("TracedStore")
+            .field("inner", &self.inner)
+            .finish()
+    }
+}
+
+impl fmt::Display for TracedStore {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "TracedStore({})", self.inner)
+    }
+}
+
+/// Sends the `finish` event of a listing when dropped
+struct ListGuard {
+    operation: Arc<Operation>,
+    prefix: Path,
+    started: Instant,
+    result: Atom,
+    status: Option<u16>,
+}
+
+impl Drop for ListGuard {
+    fn drop(&mut self) {
+        self.operation.send(TraceEvent::Finish {
+            operation: "list",
+            path: self.prefix.to_string(),
+            duration_us: self.started.elapsed().as_micros() as u64,
+            result: self.result,
+            status: self.status,
+        });
+    }
+}
+
+#[async_trait]
+impl ObjectStore for TracedStore {
+    async fn put_opts(
+        &self,
+        location: &Path,
+        payload: PutPayload,
+        opts: PutOptions,
+    ) -> Result<PutResult> {
+        self.traced(
+            "put",
+            location,
+            self.inner.put_opts(location, payload, opts),
+        )
+        .await
+    }
+
+    async fn put_multipart_opts(
+        &self,
+        location: &Path,
+        opts: PutMultipartOpts,
+    ) -> Result<Box<dyn MultipartUpload>> {
+        self.traced(
+            "put_multipart",
+            location,
+            self.inner.put_multipart_opts(location, opts),
+        )
+        .await
+    }
+
+    async fn get_opts(&self, location: &Path, options: GetOptions) -> Result<GetResult> {
+        let operation = if options.head { "head" } else { "get" };
+        self.traced(operation, location, self.inner.get_opts(location, options))
+            .await
+    }
+
+    async fn get_range(&self, location: &Path, range: Range<usize>) -> Result<Bytes> {
+        self.traced("get_range", location, self.inner.get_range(location, range))
+            .await
+    }
+
+    async fn get_ranges(&self, location: &Path, ranges: &[Range<usize>]) -> Result<Vec<Bytes>> {
+        self.traced(
+            "get_ranges",
+            location,
+            self.inner.get_ranges(location, ranges),
+        )
+        .await
+    }
+
+    async fn head(&self, location: &Path) -> Result<ObjectMeta> {
+        self.traced("head", location, self.inner.head(location))
+            .await
+    }
+
+    async fn delete(&self, location: &Path) -> Result<()> {
+        self.traced("delete", location, self.inner.delete(location))
+            .await
+    }
+
+    fn list(&self, prefix: Option<&Path>) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.list_stream(prefix, self.inner.list(prefix))
+    }
+
+    fn list_with_offset(
+        &self,
+        prefix: Option<&Path>,
+        offset: &Path,
+    ) -> BoxStream<'_, Result<ObjectMeta>> {
+        self.list_stream(prefix, self.inner.list_with_offset(prefix, offset))
+    }
+
+    async fn list_with_delimiter(&self, prefix: Option<&Path>) -> Result<ListResult> {
+        let path = prefix.cloned().unwrap_or_default();
+        self.traced(
+            "list_with_delimiter",
+            &path,
+            self.inner.list_with_delimiter(prefix),
+        )
+        .await
+    }
+
+    async fn copy(&self, from: &Path, to: &Path) -> Result<()> {
+        self.traced("copy", from, self.inner.copy(from, to)).await
+    }
+
+    async fn copy_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.traced(
+            "copy_if_not_exists",
+            from,
+            self.inner.copy_if_not_exists(from, to),
+        )
+        .await
+    }
+
+    async fn rename(&self, from: &Path, to: &Path) -> Result<()> {
+        self.traced("rename", from, self.inner.rename(from, to))
+            .await
+    }
+
+    async fn rename_if_not_exists(&self, from: &Path, to: &Path) -> Result<()> {
+        self.traced(
+            "rename_if_not_exists",
+            from,
+            self.inner.rename_if_not_exists(from, to),
+        )
+        .await
+    }
+}
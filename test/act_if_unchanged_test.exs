@@ -0,0 +1,52 @@
+defmodule ObjectStoreX.ActIfUnchangedTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+    {:ok, meta} = ObjectStoreX.put(store, "file.txt", "data", mode: :create)
+    {:ok, store: store, etag: meta.etag}
+  end
+
+  describe "act_if_unchanged/5" do
+    test "deletes an unchanged object", %{store: store, etag: etag} do
+      assert {:ok, %{atomic: false}} =
+               ObjectStoreX.act_if_unchanged(store, "file.txt", etag, :delete)
+
+      assert {:error, :not_found} = ObjectStoreX.get(store, "file.txt")
+    end
+
+    test "copies an unchanged object", %{store: store, etag: etag} do
+      assert {:ok, _} =
+               ObjectStoreX.act_if_unchanged(store, "file.txt", etag, {:copy, "copy.txt"})
+
+      assert {:ok, "data"} = ObjectStoreX.get(store, "copy.txt")
+      assert {:ok, "data"} = ObjectStoreX.get(store, "file.txt")
+    end
+
+    test "renames an unchanged object", %{store: store, etag: etag} do
+      assert {:ok, _} =
+               ObjectStoreX.act_if_unchanged(store, "file.txt", etag, {:rename, "new.txt"})
+
+      assert {:ok, "data"} = ObjectStoreX.get(store, "new.txt")
+      assert {:error, :not_found} = ObjectStoreX.get(store, "file.txt")
+    end
+
+    test "leaves changed objects alone", %{store: store, etag: etag} do
+      :ok = ObjectStoreX.put(store, "file.txt", "changed")
+
+      for action <- [:delete, {:copy, "copy.txt"}, {:rename, "new.txt"}] do
+        assert {:error, :precondition_failed} =
+                 ObjectStoreX.act_if_unchanged(store, "file.txt", etag, action)
+      end
+
+      assert {:ok, "changed"} = ObjectStoreX.get(store, "file.txt")
+      assert {:error, :not_found} = ObjectStoreX.get(store, "copy.txt")
+      assert {:error, :not_found} = ObjectStoreX.get(store, "new.txt")
+    end
+
+    test "fails for missing objects", %{store: store} do
+      assert {:error, :not_found} =
+               ObjectStoreX.act_if_unchanged(store, "missing.txt", "\"etag\"", :delete)
+    end
+  end
+end
@@ -0,0 +1,83 @@
+defmodule ObjectStoreX.AggregateTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+
+    objects = [
+      {"data/a.csv", 300},
+      {"data/b.csv", 100},
+      {"data/c.json", 200},
+      {"data/nested/d.csv", 50},
+      {"data/logs/e", 25},
+      {"other/f.csv", 10}
+    ]
+
+    for {path, size} <- objects do
+      :ok = ObjectStoreX.put(store, path, String.duplicate("x", size))
+    end
+
+    {:ok, store: store}
+  end
+
+  describe "aggregate/4 by directory" do
+    test "groups by first segment below the prefix", %{store: store} do
+      assert {:ok, summary} = ObjectStoreX.aggregate(store, "data/", :directory)
+
+      assert summary == %{
+               "" => %{count: 3, bytes: 600},
+               "nested" => %{count: 1, bytes: 50},
+               "logs" => %{count: 1, bytes: 25}
+             }
+    end
+
+    test "groups top-level directories without a prefix", %{store: store} do
+      assert {:ok, summary} = ObjectStoreX.aggregate(store, nil, :directory)
+
+      assert summary == %{
+               "data" => %{count: 5, bytes: 675},
+               "other" => %{count: 1, bytes: 10}
+             }
+    end
+  end
+
+  describe "aggregate/4 by extension" do
+    test "groups by file extension", %{store: store} do
+      assert {:ok, summary} = ObjectStoreX.aggregate(store, "data/", :extension)
+
+      assert summary == %{
+               "csv" => %{count: 3, bytes: 450},
+               "json" => %{count: 1, bytes: 200},
+               "" => %{count: 1, bytes: 25}
+             }
+    end
+  end
+
+  describe "aggregate/4 by date" do
+    test "buckets by day by default", %{store: store} do
+      assert {:ok, summary} = ObjectStoreX.aggregate(store, nil, :date)
+
+      today = Date.utc_today() |> Date.to_iso8601()
+      assert %{^today => %{count: 6, bytes: 685}} = summary
+    end
+
+    test "buckets by month and year", %{store: store} do
+      today = Date.utc_today()
+
+      assert {:ok, by_month} = ObjectStoreX.aggregate(store, nil, :date, bucket: :month)
+      assert Map.keys(by_month) == [Calendar.strftime(today, "%Y-%m")]
+
+      assert {:ok, by_year} = ObjectStoreX.aggregate(store, nil, :date, bucket: :year)
+      assert by_year == %{Integer.to_string(today.year) => %{count: 6, bytes: 685}}
+    end
+
+    test "rejects unknown buckets", %{store: store} do
+      assert {:error, _} = ObjectStoreX.aggregate(store, nil, :date, bucket: :week)
+    end
+  end
+
+  test "returns an empty summary for an empty prefix", %{store: store} do
+    assert {:ok, summary} = ObjectStoreX.aggregate(store, "missing/", :extension)
+    assert summary == %{}
+  end
+end
@@ -0,0 +1,48 @@
+defmodule ObjectStoreX.AppendTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    dir = Path.join(System.tmp_dir!(), "objectstorex_append_#{:rand.uniform(1_000_000)}")
+    File.mkdir_p!(dir)
+    on_exit(fn -> File.rm_rf!(dir) end)
+
+    {:ok, local} = ObjectStoreX.new(:local, path: dir)
+    {:ok, memory} = ObjectStoreX.new(:memory)
+    {:ok, dir: dir, local: local, memory: memory}
+  end
+
+  describe "append/4" do
+    test "appends to local files in place", %{dir: dir, local: local} do
+      assert {:ok, 6} = ObjectStoreX.append(local, "logs/app.log", "line1\n")
+      assert {:ok, 12} = ObjectStoreX.append(local, "logs/app.log", "line2\n")
+
+      assert File.read!(Path.join(dir, "logs/app.log")) == "line1\nline2\n"
+      assert {:ok, "line1\nline2\n"} = ObjectStoreX.get(local, "logs/app.log")
+    end
+
+    test "appends to memory objects", %{memory: memory} do
+      :ok = ObjectStoreX.put(memory, "app.log", "a")
+
+      assert {:ok, 2} = ObjectStoreX.append(memory, "app.log", "b")
+      assert {:ok, 3} = ObjectStoreX.append(memory, "new.log", "abc")
+      assert {:ok, "ab"} = ObjectStoreX.get(memory, "app.log")
+    end
+
+    test "keeps every concurrent append", %{local: local, memory: memory} do
+      for store <- [local, memory] do
+        1..20
+        |> Task.async_stream(fn i -> ObjectStoreX.append(store, "events.log", "#{i};") end)
+        |> Enum.each(fn {:ok, result} -> assert {:ok, _} = result end)
+
+        {:ok, data} = ObjectStoreX.get(store, "events.log")
+        entries = data |> String.split(";", trim: true) |> Enum.sort()
+        assert entries == Enum.sort(Enum.map(1..20, &to_string/1))
+      end
+    end
+
+    test "is not supported on wrapped stores", %{memory: memory} do
+      {:ok, prefixed} = ObjectStoreX.with_prefix(memory, "tenant")
+      assert {:error, :not_supported} = ObjectStoreX.append(prefixed, "app.log", "a")
+    end
+  end
+end
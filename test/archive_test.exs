@@ -0,0 +1,144 @@
+defmodule ObjectStoreX.ArchiveTest do
+  use ExUnit.Case, async: true
+
+  @files [{~c"readme.txt", "hello"}, {~c"docs/guide.md", "# Guide"}]
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+    {:ok, dest} = ObjectStoreX.new(:memory)
+    %{store: store, dest: dest}
+  end
+
+  defp tar(files, opts) do
+    file = Path.join(System.tmp_dir!(), "objectstorex_#{System.unique_integer([:positive])}.tar")
+    :ok = :erl_tar.create(String.to_charlist(file), files, opts)
+    data = File.read!(file)
+    File.rm!(file)
+    data
+  end
+
+  defp zip(files) do
+    {:ok, {_name, data}} = :zip.create(~c"archive.zip", files, [:memory])
+    data
+  end
+
+  defp assert_extracted(dest, prefix) do
+    assert {:ok, "hello"} = ObjectStoreX.get(dest, prefix <> "/readme.txt")
+    assert {:ok, "# Guide"} = ObjectStoreX.get(dest, prefix <> "/docs/guide.md")
+  end
+
+  describe "extract_archive/5" do
+    test "extracts gzipped tar archives", %{store: store, dest: dest} do
+      :ok = ObjectStoreX.put(store, "site.tar.gz", tar(@files, [:compressed]))
+
+      assert {:ok, %{files: 2, bytes: 12, skipped: []}} =
+               ObjectStoreX.extract_archive(store, "site.tar.gz", dest, "sites/1")
+
+      assert_extracted(dest, "sites/1")
+    end
+
+    test "extracts plain tar archives", %{store: store, dest: dest} do
+      :ok = ObjectStoreX.put(store, "site.tar", tar(@files, []))
+
+      assert {:ok, %{files: 2}} = ObjectStoreX.extract_archive(store, "site.tar", dest, "out")
+      assert_extracted(dest, "out")
+    end
+
+    test "extracts zip archives", %{store: store, dest: dest} do
+      :ok = ObjectStoreX.put(store, "site.zip", zip(@files))
+
+      assert {:ok, %{files: 2, bytes: 12}} =
+               ObjectStoreX.extract_archive(store, "site.zip", dest, "out")
+
+      assert_extracted(dest, "out")
+    end
+
+    test "takes the format as an option", %{store: store, dest: dest} do
+      :ok = ObjectStoreX.put(store, "upload.bin", zip(@files))
+
+      assert {:error, _} = ObjectStoreX.extract_archive(store, "upload.bin", dest, "out")
+
+      assert {:ok, %{files: 2}} =
+               ObjectStoreX.extract_archive(store, "upload.bin", dest, "out", format: :zip)
+    end
+
+    test "skips names escaping the prefix", %{store: store, dest: dest} do
+      :ok = ObjectStoreX.put(store, "evil.zip", zip([{~c"../evil.txt", "x"} | @files]))
+
+      assert {:ok, %{files: 2, skipped: ["../evil.txt"]}} =
+               ObjectStoreX.extract_archive(store, "evil.zip", dest, "out")
+
+      assert {:error, :not_found} = ObjectStoreX.get(dest, "evil.txt")
+    end
+
+    test "extracts to a local directory", %{store: store} do
+      dir = Path.join(System.tmp_dir!(), "objectstorex_extract_#{System.unique_integer()}")
+      on_exit(fn -> File.rm_rf!(dir) end)
+      :ok = ObjectStoreX.put(store, "site.tgz", tar(@files, [:compressed]))
+
+      assert {:ok, %{files: 2}} = ObjectStoreX.extract_archive(store, "site.tgz", dir)
+      assert File.read!(Path.join(dir, "docs/guide.md")) == "# Guide"
+    end
+
+    test "returns errors for missing and corrupt archives", %{store: store, dest: dest} do
+      assert {:error, :not_found} = ObjectStoreX.extract_archive(store, "missing.zip", dest)
+
+      :ok = ObjectStoreX.put(store, "corrupt.tar.gz", "not an archive")
+      assert {:error, _} = ObjectStoreX.extract_archive(store, "corrupt.tar.gz", dest)
+    end
+  end
+
+  describe "archive_prefix/5" do
+    setup %{store: store} do
+      :ok = ObjectStoreX.put(store, "project/readme.txt", "hello")
+      :ok = ObjectStoreX.put(store, "project/docs/guide.md", "# Guide")
+      :ok = ObjectStoreX.put(store, "other/skip.txt", "not archived")
+      :ok
+    end
+
+    test "writes gzipped tar archives", %{store: store} do
+      assert {:ok, %{files: 2, size: size}} =
+               ObjectStoreX.archive_prefix(store, "project", "exports/project.tar.gz", :tar_gz)
+
+      assert {:ok, data} = ObjectStoreX.get(store, "exports/project.tar.gz")
+      assert byte_size(data) == size
+
+      assert {:ok, files} = :erl_tar.extract({:binary, data}, [:compressed, :memory])
+      assert Enum.sort(files) == [{~c"docs/guide.md", "# Guide"}, {~c"readme.txt", "hello"}]
+    end
+
+    test "writes plain tar archives", %{store: store} do
+      assert {:ok, %{files: 2}} =
+               ObjectStoreX.archive_prefix(store, "project", "exports/project.tar", :tar)
+
+      assert {:ok, data} = ObjectStoreX.get(store, "exports/project.tar")
+      assert {:ok, files} = :erl_tar.extract({:binary, data}, [:memory])
+      assert length(files) == 2
+    end
+
+    test "writes zip archives", %{store: store} do
+      assert {:ok, %{files: 2}} =
+               ObjectStoreX.archive_prefix(store, "project", "exports/project.zip", :zip)
+
+      assert {:ok, data} = ObjectStoreX.get(store, "exports/project.zip")
+      assert {:ok, files} = :zip.unzip(data, [:memory])
+      assert Enum.sort(files) == [{~c"docs/guide.md", "# Guide"}, {~c"readme.txt", "hello"}]
+    end
+
+    test "round-trips through extract_archive/5", %{store: store, dest: dest} do
+      {:ok, _} = ObjectStoreX.archive_prefix(store, "project", "project/backup.zip", :zip)
+      # A second run doesn't include the previous archive
+      assert {:ok, %{files: 2}} =
+               ObjectStoreX.archive_prefix(store, "project", "project/backup.zip", :zip)
+
+      assert {:ok, %{files: 2}} =
+               ObjectStoreX.extract_archive(store, "project/backup.zip", dest, "restored")
+
+      assert_extracted(dest, "restored")
+    end
+
+    test "rejects unknown formats", %{store: store} do
+      assert {:error, _} = ObjectStoreX.archive_prefix(store, "project", "out.rar", :rar)
+    end
+  end
+end
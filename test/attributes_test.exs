@@ -156,4 +156,46 @@ defmodule ObjectStoreX.AttributesTest do
                ObjectStoreX.put(store, path, "new data", mode: :create)
     end
   end
+
+  describe "Custom metadata" do
+    setup do
+      {:ok, store} = ObjectStoreX.new(:memory)
+      {:ok, store: store}
+    end
+
+    test "round-trips custom metadata through head", %{store: store} do
+      assert {:ok, _} =
+               ObjectStoreX.put(store, "photo.jpg", "jpeg",
+                 content_type: "image/jpeg",
+                 metadata: %{"camera" => "x100v", album: "holiday"}
+               )
+
+      assert {:ok, meta} = ObjectStoreX.head(store, "photo.jpg")
+      assert meta.content_type == "image/jpeg"
+      assert meta.metadata == %{"camera" => "x100v", "album" => "holiday"}
+    end
+
+    test "head returns empty metadata when none was set", %{store: store} do
+      :ok = ObjectStoreX.put(store, "plain.txt", "data")
+
+      assert {:ok, %{metadata: metadata}} = ObjectStoreX.head(store, "plain.txt")
+      assert metadata == %{}
+    end
+
+    test "metadata can be set through the Attributes struct", %{store: store} do
+      attributes = %ObjectStoreX.Attributes{metadata: %{"owner" => "team-a"}}
+
+      assert {:ok, _etag, _version} =
+               ObjectStoreX.Native.put_with_attributes(
+                 store,
+                 "struct.txt",
+                 "data",
+                 :overwrite,
+                 attributes,
+                 []
+               )
+
+      assert {:ok, %{metadata: %{"owner" => "team-a"}}} = ObjectStoreX.head(store, "struct.txt")
+    end
+  end
 end
@@ -0,0 +1,158 @@
+defmodule ObjectStoreX.BackendTest do
+  use ExUnit.Case, async: true
+
+  defmodule AgentBackend do
+    @behaviour ObjectStoreX.Backend
+
+    @impl true
+    def init(_arg), do: Agent.start_link(fn -> %{} end)
+
+    @impl true
+    def put(path, data, mode, agent) do
+      Agent.get_and_update(agent, fn objects ->
+        if mode == :create and Map.has_key?(objects, path) do
+          {{:error, :already_exists}, objects}
+        else
+          {:ok, Map.put(objects, path, data)}
+        end
+      end)
+    end
+
+    @impl true
+    def get(path, agent) do
+      case Agent.get(agent, &Map.fetch(&1, path)) do
+        {:ok, data} -> {:ok, data}
+        :error -> {:error, :not_found}
+      end
+    end
+
+    @impl true
+    def head(path, agent) do
+      with {:ok, data} <- get(path, agent) do
+        {:ok, %{location: path, size: byte_size(data), etag: etag(data)}}
+      end
+    end
+
+    @impl true
+    def delete(path, agent), do: Agent.update(agent, &Map.delete(&1, path))
+
+    @impl true
+    def list(prefix, agent) do
+      objects =
+        agent
+        |> Agent.get(& &1)
+        |> Enum.filter(fn {path, _} ->
+          is_nil(prefix) or String.starts_with?(path, prefix <> "/")
+        end)
+        |> Enum.map(fn {path, data} ->
+          %{location: path, size: byte_size(data), last_modified: DateTime.utc_now()}
+        end)
+
+      {:ok, objects}
+    end
+
+    defp etag(data), do: Base.encode16(:crypto.hash(:md5, data), case: :lower)
+  end
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:custom, backend: AgentBackend)
+    {:ok, store: store}
+  end
+
+  test "put, get, head and delete go through the backend", %{store: store} do
+    :ok = ObjectStoreX.put(store, "docs/a.txt", "hello")
+
+    assert {:ok, "hello"} = ObjectStoreX.get(store, "docs/a.txt")
+    assert {:ok, %{size: 5}} = ObjectStoreX.head(store, "docs/a.txt")
+
+    :ok = ObjectStoreX.delete(store, "docs/a.txt")
+    assert {:error, :not_found} = ObjectStoreX.get(store, "docs/a.txt")
+  end
+
+  test "create mode reports existing objects", %{store: store} do
+    assert {:ok, _} = ObjectStoreX.put(store, "lock", "1", mode: :create)
+    assert {:error, :already_exists} = ObjectStoreX.put(store, "lock", "2", mode: :create)
+  end
+
+  test "ranges fall back to get when get_range isn't implemented", %{store: store} do
+    :ok = ObjectStoreX.put(store, "data.bin", "0123456789")
+
+    assert {:ok, "234", _meta} = ObjectStoreX.get(store, "data.bin", range: {2, 5})
+  end
+
+  test "conditional reads use the backend's etags", %{store: store} do
+    :ok = ObjectStoreX.put(store, "file.txt", "data")
+    {:ok, %{etag: etag}} = ObjectStoreX.head(store, "file.txt")
+
+    assert {:error, :not_modified} = ObjectStoreX.get(store, "file.txt", if_none_match: etag)
+    assert {:ok, "data", _meta} = ObjectStoreX.get(store, "file.txt", if_match: etag)
+  end
+
+  test "copy falls back to get and put", %{store: store} do
+    :ok = ObjectStoreX.put(store, "a.txt", "data")
+    :ok = ObjectStoreX.copy(store, "a.txt", "b.txt")
+
+    assert {:ok, "data"} = ObjectStoreX.get(store, "b.txt")
+  end
+
+  test "listing and streaming uploads reuse the native machinery", %{store: store} do
+    :ok = ObjectStoreX.Stream.upload(["part1", "part2"], store, "logs/app.log")
+    :ok = ObjectStoreX.put(store, "other.txt", "x")
+
+    assert [%{location: "logs/app.log", size: 10}] =
+             ObjectStoreX.Stream.list_stream(store, prefix: "logs")
+             |> Enum.to_list()
+  end
+
+  test "derived stores wrap custom backends", %{store: store} do
+    {:ok, child} = ObjectStoreX.derive(store, prefix: "tenant", read_only: true)
+    :ok = ObjectStoreX.put(store, "tenant/file.txt", "data")
+
+    assert {:ok, "data"} = ObjectStoreX.get(child, "file.txt")
+    assert {:error, _} = ObjectStoreX.put(child, "file.txt", "new")
+  end
+
+  test "callback errors are returned as store errors" do
+    {:ok, server} = ObjectStoreX.Backend.start_link(backend: AgentBackend)
+    {:ok, store} = ObjectStoreX.new(:custom, server: server)
+
+    assert {:error, :not_found} = ObjectStoreX.head(store, "missing.txt")
+  end
+
+  test "calls time out when the server doesn't reply" do
+    server = spawn(fn -> Process.sleep(:infinity) end)
+    {:ok, store} = ObjectStoreX.new(:custom, server: server, timeout: 100)
+
+    assert {:error, :timeout} = ObjectStoreX.head(store, "file.txt")
+  end
+
+  test "calls fail once the server exits" do
+    server =
+      spawn(fn ->
+        receive do
+          {:objectstorex_backend, _call_id, _request} -> exit(:boom)
+        end
+      end)
+
+    {:ok, store} = ObjectStoreX.new(:custom, server: server, timeout: 60_000)
+
+    assert {:error, _} = ObjectStoreX.head(store, "file.txt")
+    assert {:error, _} = ObjectStoreX.head(store, "file.txt")
+  end
+
+  test "malformed replies fail the call" do
+    server =
+      spawn(fn ->
+        receive do
+          {:objectstorex_backend, call_id, _request} ->
+            ObjectStoreX.Native.backend_reply(call_id, {:ok, :not_a_meta})
+        end
+
+        Process.sleep(:infinity)
+      end)
+
+    {:ok, store} = ObjectStoreX.new(:custom, server: server, timeout: 60_000)
+
+    assert {:error, _} = ObjectStoreX.head(store, "file.txt")
+  end
+end
@@ -0,0 +1,150 @@
+defmodule ObjectStoreX.BackupTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+    :ok = ObjectStoreX.put(store, "app/a.txt", "alpha")
+    :ok = ObjectStoreX.put(store, "app/nested/b.txt", "bravo")
+    :ok = ObjectStoreX.put(store, "other/c.txt", "charlie")
+    %{store: store}
+  end
+
+  defp sha256(data), do: :crypto.hash(:sha256, data) |> Base.encode16(case: :lower)
+
+  describe "snapshot/4" do
+    test "writes a manifest of the prefix", %{store: store} do
+      assert {:ok, %{manifest: "backups/snap.json", objects: 2, size: 10}} =
+               ObjectStoreX.snapshot(store, "app", "backups/snap.json")
+
+      {:ok, data} = ObjectStoreX.get(store, "backups/snap.json")
+      manifest = Jason.decode!(data)
+
+      assert manifest["prefix"] == "app"
+      assert manifest["size"] == 10
+      assert {:ok, _, _} = DateTime.from_iso8601(manifest["created_at"])
+
+      assert [a, b] = manifest["objects"]
+      assert %{"path" => "a.txt", "size" => 5} = a
+      assert a["sha256"] == sha256("alpha")
+      assert is_binary(a["etag"])
+      assert %{"path" => "nested/b.txt"} = b
+      assert b["sha256"] == sha256("bravo")
+    end
+
+    test "leaves out a manifest under the prefix", %{store: store} do
+      {:ok, _} = ObjectStoreX.snapshot(store, "app", "app/snap.json")
+      assert {:ok, %{objects: 2}} = ObjectStoreX.snapshot(store, "app", "app/snap.json")
+    end
+
+    test "snapshots empty prefixes", %{store: store} do
+      assert {:ok, %{objects: 0, size: 0}} =
+               ObjectStoreX.snapshot(store, "missing", "backups/empty.json")
+    end
+  end
+
+  describe "restore/4" do
+    setup %{store: store} do
+      {:ok, _} = ObjectStoreX.snapshot(store, "app", "backups/snap.json")
+      :ok
+    end
+
+    test "copies objects to another prefix", %{store: store} do
+      assert {:ok, %{restored: 2, bytes: 10, failed: []}} =
+               ObjectStoreX.restore(store, "backups/snap.json", "restored")
+
+      assert {:ok, "alpha"} = ObjectStoreX.get(store, "restored/a.txt")
+      assert {:ok, "bravo"} = ObjectStoreX.get(store, "restored/nested/b.txt")
+    end
+
+    test "copies objects to another store", %{store: store} do
+      {:ok, backup} = ObjectStoreX.new(:memory)
+
+      assert {:ok, %{restored: 2}} = ObjectStoreX.restore(store, "backups/snap.json", backup)
+      assert {:ok, "alpha"} = ObjectStoreX.get(backup, "app/a.txt")
+
+      assert {:ok, %{restored: 2}} =
+               ObjectStoreX.restore(store, "backups/snap.json", {backup, "copy"})
+
+      assert {:ok, "bravo"} = ObjectStoreX.get(backup, "copy/nested/b.txt")
+    end
+
+    test "reports objects changed or deleted since the snapshot", %{store: store} do
+      :ok = ObjectStoreX.put(store, "app/a.txt", "changed")
+      :ok = ObjectStoreX.delete(store, "app/nested/b.txt")
+
+      assert {:ok, %{restored: 0, failed: failed}} =
+               ObjectStoreX.restore(store, "backups/snap.json", "restored")
+
+      assert %{path: "a.txt", reason: :precondition_failed} in failed
+      assert %{path: "nested/b.txt", reason: :not_found} in failed
+      assert {:error, :not_found} = ObjectStoreX.get(store, "restored/a.txt")
+    end
+
+    test "rejects data that doesn't match the manifest", %{store: store} do
+      {:ok, data} = ObjectStoreX.get(store, "backups/snap.json")
+
+      manifest =
+        data
+        |> Jason.decode!()
+        |> update_in(["objects", Access.at(0), "sha256"], fn _ -> sha256("other") end)
+
+      :ok = ObjectStoreX.put(store, "backups/tampered.json", Jason.encode!(manifest))
+
+      assert {:ok, %{restored: 1, failed: [%{path: "a.txt", reason: :checksum_mismatch}]}} =
+               ObjectStoreX.restore(store, "backups/tampered.json", "restored")
+
+      assert {:error, :not_found} = ObjectStoreX.get(store, "restored/a.txt")
+    end
+
+    test "returns errors for missing manifests", %{store: store} do
+      assert {:error, :not_found} = ObjectStoreX.restore(store, "backups/missing.json")
+    end
+  end
+
+  describe "verify_prefix/3" do
+    setup %{store: store} do
+      :ok = ObjectStoreX.put(store, "app/c.txt", "charlie")
+      {:ok, _} = ObjectStoreX.snapshot(store, "app", "backups/snap.json")
+      :ok
+    end
+
+    test "verifies an unchanged prefix", %{store: store} do
+      assert {:ok, %{verified: 3, missing: [], changed: [], corrupted: [], extra: []}} =
+               ObjectStoreX.verify_prefix(store, "backups/snap.json")
+    end
+
+    test "reports missing, changed and extra objects", %{store: store} do
+      :ok = ObjectStoreX.delete(store, "app/a.txt")
+      :ok = ObjectStoreX.put(store, "app/nested/b.txt", "changed")
+      :ok = ObjectStoreX.put(store, "app/new.txt", "new")
+
+      assert {:ok, result} = ObjectStoreX.verify_prefix(store, "backups/snap.json")
+      assert result.verified == 1
+      assert result.missing == ["a.txt"]
+      assert result.changed == ["nested/b.txt"]
+      assert result.corrupted == []
+      assert result.extra == ["new.txt"]
+    end
+
+    test "reports objects that don't match their checksum", %{store: store} do
+      {:ok, data} = ObjectStoreX.get(store, "backups/snap.json")
+
+      manifest =
+        data
+        |> Jason.decode!()
+        |> update_in(["objects", Access.at(1), "sha256"], fn _ -> sha256("other") end)
+
+      :ok = ObjectStoreX.put(store, "backups/tampered.json", Jason.encode!(manifest))
+
+      assert {:ok, %{verified: 2, corrupted: ["c.txt"]}} =
+               ObjectStoreX.verify_prefix(store, "backups/tampered.json")
+    end
+
+    test "ignores a manifest stored under the prefix", %{store: store} do
+      {:ok, _} = ObjectStoreX.snapshot(store, "app", "app/snap.json")
+
+      assert {:ok, %{verified: 3, extra: []}} =
+               ObjectStoreX.verify_prefix(store, "app/snap.json")
+    end
+  end
+end
@@ -0,0 +1,56 @@
+defmodule ObjectStoreX.BenchTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+    {:ok, store: store}
+  end
+
+  describe "bench/3" do
+    test "times writes and reports throughput", %{store: store} do
+      assert {:ok, result} =
+               ObjectStoreX.bench(store, :put, objects: 20, object_size: 1024, iterations: 2)
+
+      assert %{scenario: :put, operations: 40, errors: 0, bytes: 40_960} = result
+      assert result.ops_per_sec > 0
+      assert result.bytes_per_sec > 0
+
+      %{min: min, p50: p50, p90: p90, p99: p99, max: max} = result.latency_us
+      assert min <= p50 and p50 <= p90 and p90 <= p99 and p99 <= max
+    end
+
+    test "reads objects back in full", %{store: store} do
+      assert {:ok, %{scenario: :get, operations: 10, bytes: 5_000}} =
+               ObjectStoreX.bench(store, :get, objects: 10, object_size: 500)
+    end
+
+    test "lists the benchmark prefix once per iteration", %{store: store} do
+      assert {:ok, %{scenario: :list, operations: 3, bytes: 0}} =
+               ObjectStoreX.bench(store, :list, objects: 5, iterations: 3)
+    end
+
+    test "uploads in multipart parts", %{store: store} do
+      assert {:ok, %{scenario: :stream, operations: 2, errors: 0, bytes: 20_000}} =
+               ObjectStoreX.bench(store, :stream,
+                 objects: 2,
+                 object_size: 10_000,
+                 part_size: 4_000
+               )
+    end
+
+    test "deletes its objects unless asked to keep them", %{store: store} do
+      assert {:ok, _} = ObjectStoreX.bench(store, :put, objects: 3, prefix: "bench/a")
+      assert [] = Enum.to_list(ObjectStoreX.Stream.list_stream(store, prefix: "bench/a"))
+
+      assert {:ok, _} =
+               ObjectStoreX.bench(store, :put, objects: 3, prefix: "bench/b", cleanup: false)
+
+      assert store |> ObjectStoreX.Stream.list_stream(prefix: "bench/b") |> Enum.count() == 3
+    end
+
+    test "rejects invalid options", %{store: store} do
+      assert {:error, _} = ObjectStoreX.bench(store, :put, objects: 0)
+      assert {:error, _} = ObjectStoreX.bench(store, :put, concurrency: -1)
+    end
+  end
+end
@@ -0,0 +1,59 @@
+defmodule ObjectStoreX.BufReaderTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+    data = for i <- 0..9_999, into: "", do: <<rem(i, 256)>>
+    :ok = ObjectStoreX.put(store, "data.bin", data)
+    %{store: store, data: data}
+  end
+
+  describe "open_reader/3" do
+    test "reads sequentially across buffer refills", %{store: store, data: data} do
+      {:ok, reader} = ObjectStoreX.open_reader(store, "data.bin", capacity: 1000)
+
+      assert {:ok, first} = ObjectStoreX.reader_read(reader, 1500)
+      assert first == binary_part(data, 0, 1500)
+      assert {:ok, rest} = ObjectStoreX.reader_read(reader, 100_000)
+      assert rest == binary_part(data, 1500, 8500)
+      assert :eof = ObjectStoreX.reader_read(reader, 10)
+    end
+
+    test "seeks like :file.position/2", %{store: store, data: data} do
+      {:ok, reader} = ObjectStoreX.open_reader(store, "data.bin", capacity: 64)
+
+      assert {:ok, 10_000} = ObjectStoreX.reader_seek(reader, {:eof, 0})
+      assert {:ok, 9_990} = ObjectStoreX.reader_seek(reader, {:eof, -10})
+      assert {:ok, tail} = ObjectStoreX.reader_read(reader, 10)
+      assert tail == binary_part(data, 9_990, 10)
+
+      assert {:ok, 100} = ObjectStoreX.reader_seek(reader, 100)
+      assert {:ok, 150} = ObjectStoreX.reader_seek(reader, {:cur, 50})
+      assert {:ok, chunk} = ObjectStoreX.reader_read(reader, 5)
+      assert chunk == binary_part(data, 150, 5)
+
+      assert {:ok, 20_000} = ObjectStoreX.reader_seek(reader, {:bof, 20_000})
+      assert :eof = ObjectStoreX.reader_read(reader, 1)
+
+      assert {:error, _} = ObjectStoreX.reader_seek(reader, {:cur, -30_000})
+      assert {:error, _} = ObjectStoreX.reader_seek(reader, {:sideways, 1})
+    end
+
+    test "reads ranges without moving the position", %{store: store, data: data} do
+      {:ok, reader} = ObjectStoreX.open_reader(store, "data.bin")
+      {:ok, 42} = ObjectStoreX.reader_seek(reader, 42)
+
+      assert {:ok, range} = ObjectStoreX.reader_read_range(reader, 5_000, 16)
+      assert range == binary_part(data, 5_000, 16)
+      assert {:ok, clipped} = ObjectStoreX.reader_read_range(reader, 9_995, 100)
+      assert clipped == binary_part(data, 9_995, 5)
+      assert {:ok, ""} = ObjectStoreX.reader_read_range(reader, 20_000, 10)
+
+      assert {:ok, <<42>>} = ObjectStoreX.reader_read(reader, 1)
+    end
+
+    test "returns not found for missing objects", %{store: store} do
+      assert {:error, :not_found} = ObjectStoreX.open_reader(store, "missing.bin")
+    end
+  end
+end
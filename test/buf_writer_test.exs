@@ -0,0 +1,68 @@
+defmodule ObjectStoreX.BufWriterTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+    %{store: store}
+  end
+
+  describe "open_writer/3" do
+    test "writes small objects with a single put", %{store: store} do
+      assert {:ok, writer} =
+               ObjectStoreX.open_writer(store, "small.json",
+                 content_type: "application/json",
+                 tags: %{"team" => "data"}
+               )
+
+      assert :ok = ObjectStoreX.writer_write(writer, ~s({"a":))
+      assert :ok = ObjectStoreX.writer_flush(writer)
+      assert :ok = ObjectStoreX.writer_write(writer, ["1", "}"])
+
+      # Nothing is written before close
+      assert {:error, :not_found} = ObjectStoreX.get(store, "small.json")
+
+      assert :ok = ObjectStoreX.writer_close(writer)
+      assert {:ok, ~s({"a":1})} = ObjectStoreX.get(store, "small.json")
+      assert {:ok, %{content_type: "application/json"}} = ObjectStoreX.head(store, "small.json")
+    end
+
+    test "switches to a multipart upload past the capacity", %{store: store} do
+      chunks = for i <- 1..5, do: :binary.copy(<<i>>, 1024 * 1024)
+
+      {:ok, writer} =
+        ObjectStoreX.open_writer(store, "large.bin",
+          capacity: 2 * 1024 * 1024,
+          max_concurrency: 2
+        )
+
+      Enum.each(chunks, &(:ok = ObjectStoreX.writer_write(writer, &1)))
+      assert :ok = ObjectStoreX.writer_flush(writer)
+      assert :ok = ObjectStoreX.writer_close(writer)
+
+      assert {:ok, data} = ObjectStoreX.get(store, "large.bin")
+      assert data == Enum.join(chunks)
+    end
+
+    test "abort discards written data", %{store: store} do
+      {:ok, writer} = ObjectStoreX.open_writer(store, "aborted.bin")
+      :ok = ObjectStoreX.writer_write(writer, "data")
+
+      assert :ok = ObjectStoreX.writer_abort(writer)
+      assert {:error, :not_found} = ObjectStoreX.get(store, "aborted.bin")
+    end
+
+    test "closed writers reject further calls", %{store: store} do
+      {:ok, writer} = ObjectStoreX.open_writer(store, "closed.bin")
+      :ok = ObjectStoreX.writer_close(writer)
+
+      assert {:error, _} = ObjectStoreX.writer_write(writer, "more")
+      assert {:error, _} = ObjectStoreX.writer_flush(writer)
+      assert {:error, _} = ObjectStoreX.writer_close(writer)
+      assert {:error, _} = ObjectStoreX.writer_abort(writer)
+    end
+
+    test "rejects a zero capacity", %{store: store} do
+      assert {:error, _} = ObjectStoreX.open_writer(store, "bad.bin", capacity: 0)
+    end
+  end
+end
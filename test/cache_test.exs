@@ -0,0 +1,131 @@
+defmodule ObjectStoreX.CacheTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    {:ok, remote} = ObjectStoreX.new(:memory)
+    {:ok, local} = ObjectStoreX.new(:memory)
+    {:ok, remote: remote, local: local}
+  end
+
+  describe "with_cache/3" do
+    test "copies objects to the local store on first read", %{remote: remote, local: local} do
+      :ok = ObjectStoreX.put(remote, "ref/countries.json", "[]")
+      {:ok, cached} = ObjectStoreX.with_cache(remote, local)
+
+      assert {:error, :not_found} = ObjectStoreX.get(local, "ref/countries.json")
+      assert {:ok, "[]"} = ObjectStoreX.get(cached, "ref/countries.json")
+      assert {:ok, "[]"} = ObjectStoreX.get(local, "ref/countries.json")
+    end
+
+    test "serves entries within the TTL without revalidating", %{remote: remote, local: local} do
+      :ok = ObjectStoreX.put(remote, "a.txt", "v1")
+      {:ok, cached} = ObjectStoreX.with_cache(remote, local, ttl: 60_000)
+
+      assert {:ok, "v1"} = ObjectStoreX.get(cached, "a.txt")
+      :ok = ObjectStoreX.put(remote, "a.txt", "v2")
+      assert {:ok, "v1"} = ObjectStoreX.get(cached, "a.txt")
+      assert {:ok, "1", _meta} = ObjectStoreX.get(cached, "a.txt", range: {1, 1})
+    end
+
+    test "revalidates by ETag without a TTL", %{remote: remote, local: local} do
+      :ok = ObjectStoreX.put(remote, "a.txt", "v1")
+      {:ok, cached} = ObjectStoreX.with_cache(remote, local)
+
+      assert {:ok, "v1"} = ObjectStoreX.get(cached, "a.txt")
+      :ok = ObjectStoreX.put(remote, "a.txt", "v2")
+      assert {:ok, "v2"} = ObjectStoreX.get(cached, "a.txt")
+      assert {:ok, "v2"} = ObjectStoreX.get(local, "a.txt")
+
+      :ok = ObjectStoreX.delete(remote, "a.txt")
+      assert {:error, :not_found} = ObjectStoreX.get(cached, "a.txt")
+      assert {:error, :not_found} = ObjectStoreX.get(local, "a.txt")
+    end
+
+    test "writes through the handle invalidate cached copies", %{remote: remote, local: local} do
+      :ok = ObjectStoreX.put(remote, "a.txt", "v1")
+      {:ok, cached} = ObjectStoreX.with_cache(remote, local, ttl: 60_000)
+
+      assert {:ok, "v1"} = ObjectStoreX.get(cached, "a.txt")
+      :ok = ObjectStoreX.put(cached, "a.txt", "v2")
+      assert {:ok, "v2"} = ObjectStoreX.get(cached, "a.txt")
+
+      :ok = ObjectStoreX.delete(cached, "a.txt")
+      assert {:error, :not_found} = ObjectStoreX.get(cached, "a.txt")
+      assert {:error, :not_found} = ObjectStoreX.get(local, "a.txt")
+    end
+
+    test "evicts least recently read entries beyond max_size", %{remote: remote, local: local} do
+      for name <- ~w(a b c) do
+        :ok = ObjectStoreX.put(remote, "#{name}.bin", String.duplicate(name, 40))
+      end
+
+      :ok = ObjectStoreX.put(remote, "big.bin", String.duplicate("x", 200))
+      {:ok, cached} = ObjectStoreX.with_cache(remote, local, ttl: 60_000, max_size: 100)
+
+      assert {:ok, _} = ObjectStoreX.get(cached, "a.bin")
+      assert {:ok, _} = ObjectStoreX.get(cached, "b.bin")
+      assert {:ok, _} = ObjectStoreX.get(cached, "a.bin")
+      assert {:ok, _} = ObjectStoreX.get(cached, "c.bin")
+
+      assert {:ok, _} = ObjectStoreX.get(local, "a.bin")
+      assert {:error, :not_found} = ObjectStoreX.get(local, "b.bin")
+      assert {:ok, _} = ObjectStoreX.get(local, "c.bin")
+
+      assert {:ok, _} = ObjectStoreX.get(cached, "big.bin")
+      assert {:error, :not_found} = ObjectStoreX.get(local, "big.bin")
+    end
+
+    test "rejects unknown write policies", %{remote: remote, local: local} do
+      assert {:error, _} = ObjectStoreX.with_cache(remote, local, write_policy: :write_sideways)
+    end
+  end
+
+  describe "write policies" do
+    test "write-through caches what was written", %{remote: remote, local: local} do
+      {:ok, cached} = ObjectStoreX.with_cache(remote, local, write_policy: :write_through)
+
+      :ok = ObjectStoreX.put(cached, "a.txt", "data")
+      assert {:ok, "data"} = ObjectStoreX.get(remote, "a.txt")
+      assert {:ok, "data"} = ObjectStoreX.get(local, "a.txt")
+      assert {:ok, "data"} = ObjectStoreX.get(cached, "a.txt")
+    end
+
+    test "write-around leaves the cache alone", %{remote: remote, local: local} do
+      {:ok, cached} = ObjectStoreX.with_cache(remote, local)
+
+      :ok = ObjectStoreX.put(cached, "a.txt", "data")
+      assert {:ok, "data"} = ObjectStoreX.get(remote, "a.txt")
+      assert {:error, :not_found} = ObjectStoreX.get(local, "a.txt")
+    end
+
+    test "write-back acknowledges locally and uploads in the background",
+         %{remote: remote, local: local} do
+      {:ok, slow} = ObjectStoreX.with_throttle(remote, put_per_call: 300)
+      {:ok, cached} = ObjectStoreX.with_cache(slow, local, write_policy: :write_back)
+
+      :ok = ObjectStoreX.put(cached, "a.txt", "v1")
+      :ok = ObjectStoreX.put(cached, "a.txt", "v2")
+      assert {:ok, "v2"} = ObjectStoreX.get(cached, "a.txt")
+      assert {:error, :not_found} = ObjectStoreX.get(remote, "a.txt")
+
+      assert :ok = ObjectStoreX.flush_cache(cached)
+      assert {:ok, "v2"} = ObjectStoreX.get(remote, "a.txt")
+    end
+
+    test "write-back deletes cancel pending uploads", %{remote: remote, local: local} do
+      {:ok, slow} = ObjectStoreX.with_throttle(remote, put_per_call: 300)
+      {:ok, cached} = ObjectStoreX.with_cache(slow, local, write_policy: :write_back)
+
+      :ok = ObjectStoreX.put(cached, "a.txt", "data")
+      assert :ok = ObjectStoreX.delete(cached, "a.txt")
+      assert :ok = ObjectStoreX.flush_cache(cached)
+
+      assert {:error, :not_found} = ObjectStoreX.get(cached, "a.txt")
+      assert {:error, :not_found} = ObjectStoreX.get(remote, "a.txt")
+    end
+
+    test "flush_cache/1 requires a cache store", %{remote: remote} do
+      assert {:error, :not_supported} = ObjectStoreX.flush_cache(remote)
+    end
+  end
+end
@@ -0,0 +1,47 @@
+defmodule ObjectStoreX.CasTest do
+  use ExUnit.Case, async: true
+
+  alias ObjectStoreX.Native
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+    %{store: store}
+  end
+
+  defp sha256(data), do: :crypto.hash(:sha256, data) |> Base.encode16(case: :lower)
+
+  describe "put_cas/3 and get_cas/3" do
+    test "store data under its digest", %{store: store} do
+      assert {:ok, digest} = ObjectStoreX.put_cas(store, ["hello", " world"])
+      assert digest == sha256("hello world")
+
+      assert {:ok, "hello world"} = ObjectStoreX.get_cas(store, digest)
+      path = "cas/#{binary_part(digest, 0, 2)}/#{digest}"
+      assert {:ok, "hello world"} = ObjectStoreX.get(store, path)
+    end
+
+    test "don't upload data that is already stored", %{store: store} do
+      assert {:ok, digest, true} = Native.put_cas(store, "cas", "artifact")
+      assert {:ok, ^digest, false} = Native.put_cas(store, "cas", "artifact")
+    end
+
+    test "use the :prefix option", %{store: store} do
+      {:ok, digest} = ObjectStoreX.put_cas(store, "artifact", prefix: "blobs/sha256")
+
+      assert {:ok, "artifact"} = ObjectStoreX.get_cas(store, digest, prefix: "blobs/sha256")
+      assert {:error, :not_found} = ObjectStoreX.get_cas(store, digest)
+    end
+
+    test "detect modified objects", %{store: store} do
+      {:ok, digest} = ObjectStoreX.put_cas(store, "original")
+      :ok = ObjectStoreX.put(store, "cas/#{binary_part(digest, 0, 2)}/#{digest}", "tampered")
+
+      assert {:error, :integrity_error} = ObjectStoreX.get_cas(store, digest)
+    end
+
+    test "reject invalid digests", %{store: store} do
+      assert {:error, _} = ObjectStoreX.get_cas(store, "../../etc/passwd")
+      assert {:error, _} = ObjectStoreX.get_cas(store, "abc")
+    end
+  end
+end
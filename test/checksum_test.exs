@@ -0,0 +1,151 @@
+defmodule ObjectStoreX.ChecksumTest do
+  use ExUnit.Case, async: true
+
+  @part_size 5 * 1024 * 1024
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+
+    dir = Path.join(System.tmp_dir!(), "objectstorex_checksum_#{:rand.uniform(1_000_000)}")
+    File.mkdir_p!(dir)
+    on_exit(fn -> File.rm_rf!(dir) end)
+
+    {:ok, store: store, dir: dir}
+  end
+
+  defp hex(algorithm, data), do: :crypto.hash(algorithm, data) |> Base.encode16(case: :lower)
+
+  describe "put/4 with :checksum" do
+    test "returns the SHA-256 digest", %{store: store} do
+      assert {:ok, %{checksum: checksum}} =
+               ObjectStoreX.put(store, "file.txt", "hello", checksum: :sha256)
+
+      assert checksum == hex(:sha256, "hello")
+      assert {:ok, "hello"} = ObjectStoreX.get(store, "file.txt")
+    end
+
+    test "returns the MD5 digest", %{store: store} do
+      assert {:ok, %{checksum: checksum}} =
+               ObjectStoreX.put(store, "file.txt", "hello", checksum: :md5)
+
+      assert checksum == hex(:md5, "hello")
+    end
+
+    test "applies the other put options", %{store: store} do
+      assert {:ok, %{checksum: _}} =
+               ObjectStoreX.put(store, "file.json", "{}",
+                 checksum: :sha256,
+                 content_type: "application/json",
+                 mode: :create
+               )
+
+      assert {:ok, %{content_type: "application/json"}} = ObjectStoreX.head(store, "file.json")
+
+      assert {:error, :already_exists} =
+               ObjectStoreX.put(store, "file.json", "{}", checksum: :sha256, mode: :create)
+    end
+
+    test "keeps the idempotency key", %{store: store} do
+      assert {:ok, %{checksum: checksum}} =
+               ObjectStoreX.put(store, "file.txt", "hello",
+                 checksum: :md5,
+                 idempotency_key: "key-1"
+               )
+
+      assert checksum == hex(:md5, "hello")
+      assert {:ok, meta} = ObjectStoreX.head(store, "file.txt")
+      assert meta[:metadata]["objectstorex-idempotency-key"] == "key-1"
+    end
+
+    test "fails instead of dropping an object lock", %{store: store} do
+      assert {:error, :not_supported} =
+               ObjectStoreX.put(store, "file.txt", "hello", checksum: :sha256, legal_hold: true)
+
+      assert {:error, :not_found} = ObjectStoreX.head(store, "file.txt")
+    end
+
+    test "rejects unknown algorithms", %{store: store} do
+      assert {:error, _} = ObjectStoreX.put(store, "file.txt", "hello", checksum: :crc32)
+    end
+  end
+
+  describe "put_from_file/4 with :checksum" do
+    test "returns the digest of a file larger than one part", %{store: store, dir: dir} do
+      source = Path.join(dir, "source.bin")
+      data = :crypto.strong_rand_bytes(@part_size + 1024)
+      File.write!(source, data)
+
+      assert {:ok, checksum} =
+               ObjectStoreX.put_from_file(store, "copy.bin", source, checksum: :sha256)
+
+      assert checksum == hex(:sha256, data)
+      assert {:ok, ^data} = ObjectStoreX.get(store, "copy.bin")
+    end
+
+    test "returns :ok without a checksum", %{store: store, dir: dir} do
+      source = Path.join(dir, "small.txt")
+      File.write!(source, "small")
+
+      assert :ok = ObjectStoreX.put_from_file(store, "small.txt", source)
+    end
+  end
+
+  describe "ObjectStoreX.Stream.upload/4 with :checksum" do
+    test "returns the digest of the uploaded chunks", %{store: store} do
+      chunks = for _ <- 1..3, do: :crypto.strong_rand_bytes(@part_size)
+
+      assert {:ok, checksum} =
+               ObjectStoreX.Stream.upload(chunks, store, "large.bin", checksum: :md5)
+
+      assert checksum == hex(:md5, chunks)
+      assert {:ok, data} = ObjectStoreX.get(store, "large.bin")
+      assert data == IO.iodata_to_binary(chunks)
+    end
+
+    test "can't be resumed", %{store: store} do
+      state = %{upload_id: "1", parts: [], bytes_uploaded: 0}
+
+      assert {:error, message} =
+               ObjectStoreX.Stream.upload(["data"], store, "file.bin",
+                 checksum: :sha256,
+                 resume: state
+               )
+
+      assert message =~ "can't be resumed"
+    end
+  end
+
+  describe "verify_checksum/5" do
+    setup %{store: store} do
+      :ok = ObjectStoreX.put(store, "file.txt", "hello")
+      :ok
+    end
+
+    test "accepts a matching digest", %{store: store} do
+      assert :ok = ObjectStoreX.verify_checksum(store, "file.txt", :sha256, hex(:sha256, "hello"))
+      assert :ok = ObjectStoreX.verify_checksum(store, "file.txt", :md5, hex(:md5, "hello"))
+    end
+
+    test "ignores the case of the digest", %{store: store} do
+      expected = :crypto.hash(:sha256, "hello") |> Base.encode16()
+
+      assert :ok = ObjectStoreX.verify_checksum(store, "file.txt", :sha256, expected)
+    end
+
+    test "reports a mismatching digest", %{store: store} do
+      assert {:error, :checksum_mismatch} =
+               ObjectStoreX.verify_checksum(store, "file.txt", :sha256, hex(:sha256, "other"))
+    end
+
+    test "verifies digests returned by put", %{store: store} do
+      {:ok, %{checksum: checksum}} = ObjectStoreX.put(store, "data.bin", "data", checksum: :md5)
+
+      assert :ok = ObjectStoreX.verify_checksum(store, "data.bin", :md5, checksum)
+    end
+
+    test "returns not_found for missing objects", %{store: store} do
+      assert {:error, :not_found} =
+               ObjectStoreX.verify_checksum(store, "missing.txt", :md5, hex(:md5, ""))
+    end
+  end
+end
@@ -0,0 +1,124 @@
+defmodule ObjectStoreX.ChunkedTest do
+  use ExUnit.Case, async: true
+
+  @sizes [min_chunk_size: 1024, avg_chunk_size: 4096, max_chunk_size: 16_384]
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+    name = "objectstorex_chunked_#{System.unique_integer([:positive])}"
+    dir = Path.join(System.tmp_dir!(), name)
+    File.mkdir_p!(dir)
+    on_exit(fn -> File.rm_rf!(dir) end)
+
+    %{store: store, dir: dir}
+  end
+
+  defp write_file(dir, name, data) do
+    path = Path.join(dir, name)
+    File.write!(path, data)
+    path
+  end
+
+  defp sha256(data), do: :crypto.hash(:sha256, data) |> Base.encode16(case: :lower)
+
+  describe "put_chunked/4 and get_chunked/4" do
+    test "round trip a file through chunks and a manifest", %{store: store, dir: dir} do
+      data = :crypto.strong_rand_bytes(256 * 1024)
+      file = write_file(dir, "source.bin", data)
+
+      assert {:ok, result} = ObjectStoreX.put_chunked(store, "files/source.bin", file, @sizes)
+      assert result.size == byte_size(data)
+      assert result.sha256 == sha256(data)
+      assert result.chunks > 1
+      assert result.uploaded_chunks == result.chunks
+      assert result.uploaded_bytes == byte_size(data)
+
+      {:ok, manifest} = ObjectStoreX.get(store, "files/source.bin")
+      assert %{"chunk_prefix" => "cas", "chunks" => chunks} = Jason.decode!(manifest)
+      assert length(chunks) == result.chunks
+
+      out = Path.join(dir, "restored.bin")
+      assert :ok = ObjectStoreX.get_chunked(store, "files/source.bin", out)
+      assert File.read!(out) == data
+    end
+
+    test "only upload changed chunks on re-upload", %{store: store, dir: dir} do
+      data = :crypto.strong_rand_bytes(256 * 1024)
+      file = write_file(dir, "source.bin", data)
+      {:ok, first} = ObjectStoreX.put_chunked(store, "files/source.bin", file, @sizes)
+
+      <<head::binary-size(100_000), tail::binary>> = data
+      edited = head <> "inserted bytes" <> tail
+      File.write!(file, edited)
+
+      assert {:ok, second} = ObjectStoreX.put_chunked(store, "files/source.bin", file, @sizes)
+      assert second.uploaded_chunks in 1..3
+      assert second.uploaded_bytes < div(byte_size(edited), 4)
+      assert second.chunks >= first.chunks
+
+      out = Path.join(dir, "restored.bin")
+      assert :ok = ObjectStoreX.get_chunked(store, "files/source.bin", out)
+      assert File.read!(out) == edited
+    end
+
+    test "share chunks with put_cas/3", %{store: store, dir: dir} do
+      file = write_file(dir, "small.txt", "small file")
+      {:ok, %{uploaded_chunks: 1}} = ObjectStoreX.put_chunked(store, "files/small", file)
+
+      assert {:ok, "small file"} = ObjectStoreX.get_cas(store, sha256("small file"))
+      assert {:ok, %{uploaded_chunks: 0}} = ObjectStoreX.put_chunked(store, "copy", file)
+    end
+
+    test "chunk empty files", %{store: store, dir: dir} do
+      file = write_file(dir, "empty", "")
+
+      assert {:ok, %{chunks: 0, size: 0}} = ObjectStoreX.put_chunked(store, "files/empty", file)
+
+      out = Path.join(dir, "restored")
+      assert :ok = ObjectStoreX.get_chunked(store, "files/empty", out)
+      assert File.read!(out) == ""
+    end
+
+    test "use the :chunk_prefix option", %{store: store, dir: dir} do
+      file = write_file(dir, "small.txt", "small file")
+      opts = [chunk_prefix: "chunks"]
+
+      {:ok, _} = ObjectStoreX.put_chunked(store, "files/small", file, opts)
+      assert {:ok, "small file"} =
+               ObjectStoreX.get_cas(store, sha256("small file"), prefix: "chunks")
+
+      out = Path.join(dir, "restored")
+      assert :ok = ObjectStoreX.get_chunked(store, "files/small", out)
+      assert File.read!(out) == "small file"
+    end
+
+    test "detect modified chunks and remove the partial file", %{store: store, dir: dir} do
+      file = write_file(dir, "small.txt", "small file")
+      {:ok, _} = ObjectStoreX.put_chunked(store, "files/small", file)
+
+      digest = sha256("small file")
+      :ok = ObjectStoreX.put(store, "cas/#{binary_part(digest, 0, 2)}/#{digest}", "tampered")
+
+      out = Path.join(dir, "restored")
+      assert {:error, :integrity_error} = ObjectStoreX.get_chunked(store, "files/small", out)
+      refute File.exists?(out)
+    end
+
+    test "reject invalid chunk sizes", %{store: store, dir: dir} do
+      file = write_file(dir, "small.txt", "small file")
+
+      assert {:error, _} = ObjectStoreX.put_chunked(store, "files/small", file, min_chunk_size: 1)
+
+      assert {:error, _} =
+               ObjectStoreX.put_chunked(store, "files/small", file,
+                 min_chunk_size: 8192,
+                 avg_chunk_size: 4096
+               )
+    end
+
+    test "return errors for missing manifests", %{store: store, dir: dir} do
+      out = Path.join(dir, "restored")
+      assert {:error, :not_found} = ObjectStoreX.get_chunked(store, "missing", out)
+    end
+  end
+end
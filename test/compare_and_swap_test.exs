@@ -0,0 +1,121 @@
+defmodule ObjectStoreX.CompareAndSwapTest do
+  use ExUnit.Case, async: true
+
+  alias ObjectStoreX.Native
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+    %{store: store}
+  end
+
+  defp increment(nil), do: "1"
+  defp increment(count), do: Integer.to_string(String.to_integer(count) + 1)
+
+  describe "compare_and_swap/5" do
+    test "replaces an unchanged object", %{store: store} do
+      {:ok, meta} = ObjectStoreX.put(store, "file.txt", "v1", mode: :create)
+
+      assert {:ok, %{etag: etag}} =
+               ObjectStoreX.compare_and_swap(store, "file.txt", meta.etag, "v2")
+
+      assert etag != meta.etag
+      assert {:ok, "v2"} = ObjectStoreX.get(store, "file.txt")
+    end
+
+    test "creates a missing object with a nil ETag", %{store: store} do
+      assert {:ok, _} = ObjectStoreX.compare_and_swap(store, "new.txt", nil, ["v", "1"])
+      assert {:ok, "v1"} = ObjectStoreX.get(store, "new.txt")
+
+      assert {:error, :precondition_failed} =
+               ObjectStoreX.compare_and_swap(store, "new.txt", nil, "v2")
+    end
+
+    test "fails when the object changed", %{store: store} do
+      {:ok, meta} = ObjectStoreX.put(store, "file.txt", "v1", mode: :create)
+      :ok = ObjectStoreX.put(store, "file.txt", "other")
+
+      assert {:error, :precondition_failed} =
+               ObjectStoreX.compare_and_swap(store, "file.txt", meta.etag, "v2")
+
+      assert {:ok, "other"} = ObjectStoreX.get(store, "file.txt")
+    end
+
+    test "returns the current state on conflicts", %{store: store} do
+      {:ok, meta} = ObjectStoreX.put(store, "file.txt", "v1", mode: :create)
+      {:ok, %{etag: etag}} = ObjectStoreX.compare_and_swap(store, "file.txt", meta.etag, "v2")
+
+      assert {:conflict, "v2", ^etag} =
+               Native.compare_and_swap(store, "file.txt", meta.etag, "v3", 3)
+
+      assert {:conflict, nil, nil} = Native.compare_and_swap(store, "missing", etag, "v3", 3)
+    end
+
+    test "is not supported by stores without conditional puts" do
+      dir = Path.join(System.tmp_dir!(), "objectstorex_cas_#{System.unique_integer([:positive])}")
+      File.mkdir_p!(dir)
+      on_exit(fn -> File.rm_rf!(dir) end)
+
+      {:ok, store} = ObjectStoreX.new(:local, path: dir)
+      :ok = ObjectStoreX.put(store, "file.txt", "v1")
+      {:ok, meta} = ObjectStoreX.head(store, "file.txt")
+
+      assert {:error, :not_supported} =
+               ObjectStoreX.compare_and_swap(store, "file.txt", meta.etag, "v2")
+    end
+  end
+
+  describe "update/4" do
+    test "creates and updates an object", %{store: store} do
+      assert {:ok, _} = ObjectStoreX.update(store, "counter", &increment/1)
+      assert {:ok, _} = ObjectStoreX.update(store, "counter", &increment/1)
+      assert {:ok, "2"} = ObjectStoreX.get(store, "counter")
+    end
+
+    test "doesn't lose concurrent updates", %{store: store} do
+      1..20
+      |> Task.async_stream(
+        fn _ -> ObjectStoreX.update(store, "counter", &increment/1, max_attempts: 100) end,
+        max_concurrency: 10
+      )
+      |> Enum.each(fn {:ok, result} -> assert {:ok, _} = result end)
+
+      assert {:ok, "20"} = ObjectStoreX.get(store, "counter")
+    end
+
+    test "updates JSON documents", %{store: store} do
+      :ok = ObjectStoreX.put(store, "config.json", Jason.encode!(%{"mode" => "normal"}))
+
+      assert {:ok, _} =
+               ObjectStoreX.update(store, "config.json", fn data ->
+                 data |> Jason.decode!() |> Map.put("mode", "maintenance") |> Jason.encode!()
+               end)
+
+      {:ok, data} = ObjectStoreX.get(store, "config.json")
+      assert %{"mode" => "maintenance"} = Jason.decode!(data)
+    end
+
+    test "aborts without writing", %{store: store} do
+      :ok = ObjectStoreX.put(store, "counter", "5")
+
+      assert {:error, :too_large} =
+               ObjectStoreX.update(store, "counter", fn _ -> {:abort, :too_large} end)
+
+      assert {:ok, "5"} = ObjectStoreX.get(store, "counter")
+    end
+
+    test "gives up after :max_attempts", %{store: store} do
+      :ok = ObjectStoreX.put(store, "counter", "0")
+
+      # Every attempt is overtaken by another write
+      fun = fn data ->
+        :ok = ObjectStoreX.put(store, "counter", data <> "!")
+        "mine"
+      end
+
+      assert {:error, :precondition_failed} =
+               ObjectStoreX.update(store, "counter", fun, max_attempts: 3)
+
+      assert {:ok, "0!!!"} = ObjectStoreX.get(store, "counter")
+    end
+  end
+end
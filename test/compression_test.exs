@@ -0,0 +1,89 @@
+defmodule ObjectStoreX.CompressionTest do
+  use ExUnit.Case, async: true
+
+  @text String.duplicate("2025-01-01T00:00:00Z INFO request handled in 12ms\n", 2000)
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+    {:ok, store: store}
+  end
+
+  for codec <- [:gzip, :zstd] do
+    describe "with_compression/3 using #{codec}" do
+      setup %{store: store} do
+        {:ok, compressed} = ObjectStoreX.with_compression(store, unquote(codec))
+        {:ok, compressed: compressed}
+      end
+
+      test "stores compressed data and reads it back", %{store: store, compressed: compressed} do
+        :ok = ObjectStoreX.put(compressed, "app.log", @text)
+
+        assert {:ok, @text} = ObjectStoreX.get(compressed, "app.log")
+
+        assert {:ok, stored} = ObjectStoreX.get(store, "app.log")
+        assert byte_size(stored) < div(byte_size(@text), 10)
+
+        assert {:ok, %{content_encoding: unquote(to_string(codec))}} =
+                 ObjectStoreX.head(store, "app.log")
+      end
+
+      test "decompresses download streams", %{compressed: compressed} do
+        :ok = ObjectStoreX.put(compressed, "app.log", @text)
+
+        data = ObjectStoreX.Stream.download(compressed, "app.log") |> Enum.join()
+        assert data == @text
+      end
+
+      test "reads ranges of the decompressed data", %{compressed: compressed} do
+        :ok = ObjectStoreX.put(compressed, "app.log", @text)
+
+        assert {:ok, data, _meta} = ObjectStoreX.get(compressed, "app.log", range: {21, 25})
+        assert data == "INFO"
+      end
+
+      test "compresses streamed uploads", %{compressed: compressed} do
+        chunks = for _ <- 1..3, do: String.duplicate(@text, 60)
+
+        assert :ok = ObjectStoreX.Stream.upload(chunks, compressed, "big.log")
+        assert {:ok, data} = ObjectStoreX.get(compressed, "big.log")
+        assert data == IO.iodata_to_binary(chunks)
+      end
+    end
+  end
+
+  test "stores data with its own content encoding as given", %{store: store} do
+    {:ok, compressed} = ObjectStoreX.with_compression(store, :zstd)
+    gzipped = :zlib.gzip(@text)
+
+    {:ok, _} = ObjectStoreX.put(compressed, "app.log.gz", gzipped, content_encoding: "gzip")
+
+    assert {:ok, ^gzipped} = ObjectStoreX.get(store, "app.log.gz")
+  end
+
+  test "returns objects written without compression as stored", %{store: store} do
+    :ok = ObjectStoreX.put(store, "plain.txt", "plain")
+    {:ok, compressed} = ObjectStoreX.with_compression(store, :gzip)
+
+    assert {:ok, "plain"} = ObjectStoreX.get(compressed, "plain.txt")
+  end
+
+  test "works on local stores" do
+    root = Path.join(System.tmp_dir!(), "objectstorex_compression_#{:rand.uniform(1_000_000)}")
+    File.mkdir_p!(root)
+    on_exit(fn -> File.rm_rf!(root) end)
+
+    {:ok, local} = ObjectStoreX.new(:local, path: root)
+    {:ok, compressed} = ObjectStoreX.with_compression(local, :gzip, 9)
+
+    :ok = ObjectStoreX.put(compressed, "app.log", @text)
+
+    assert {:ok, @text} = ObjectStoreX.get(compressed, "app.log")
+    assert :zlib.gunzip(File.read!(Path.join(root, "app.log"))) == @text
+  end
+
+  test "rejects unknown codecs and levels", %{store: store} do
+    assert {:error, _} = ObjectStoreX.with_compression(store, :brotli)
+    assert {:error, _} = ObjectStoreX.with_compression(store, :gzip, 10)
+    assert {:error, _} = ObjectStoreX.with_compression(store, :zstd, 0)
+  end
+end
@@ -0,0 +1,46 @@
+defmodule ObjectStoreX.ConditionalDeleteTest do
+  use ExUnit.Case, async: true
+
+  describe "delete_with_options/4" do
+    test "deletes unconditionally without conditions" do
+      {:ok, store} = ObjectStoreX.new(:memory)
+      :ok = ObjectStoreX.put(store, "file.txt", "data")
+
+      assert :ok = ObjectStoreX.delete_with_options(store, "file.txt", %{})
+      assert {:error, :not_found} = ObjectStoreX.get(store, "file.txt")
+    end
+
+    test "refuses conditions on stores without conditional deletes" do
+      {:ok, store} = ObjectStoreX.new(:memory)
+      :ok = ObjectStoreX.put(store, "file.txt", "data")
+      {:ok, %{etag: etag}} = ObjectStoreX.head(store, "file.txt")
+
+      assert {:error, :not_supported} =
+               ObjectStoreX.delete_with_options(store, "file.txt", %{if_match: etag})
+
+      assert {:error, :not_supported} =
+               ObjectStoreX.delete_with_options(store, "file.txt", version: "v1")
+
+      # The object is left in place
+      assert {:ok, "data"} = ObjectStoreX.get(store, "file.txt")
+    end
+
+    test "sends conditional deletes on S3 stores" do
+      {:ok, store} =
+        ObjectStoreX.new(:s3,
+          bucket: "test",
+          region: "us-east-1",
+          access_key_id: "key",
+          secret_access_key: "secret",
+          endpoint: "http://127.0.0.1:1",
+          allow_http: true
+        )
+
+      # Nothing listens on the endpoint, so the request itself fails
+      assert {:error, reason} =
+               ObjectStoreX.delete_with_options(store, "file.txt", %{if_match: "\"abc\""})
+
+      refute reason == :not_supported
+    end
+  end
+end
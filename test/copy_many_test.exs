@@ -0,0 +1,108 @@
+defmodule ObjectStoreX.CopyManyTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+
+    for i <- 1..50 do
+      :ok = ObjectStoreX.put(store, "src/#{i}.txt", "data #{i}")
+    end
+
+    pairs = for i <- 1..50, do: {"src/#{i}.txt", "dst/#{i}.txt"}
+    {:ok, store: store, pairs: pairs}
+  end
+
+  describe "copy_many/3" do
+    test "copies every pair and keeps the sources", %{store: store, pairs: pairs} do
+      assert {:ok, results} = ObjectStoreX.copy_many(store, pairs, max_concurrency: 8)
+      assert results == Enum.map(pairs, &{&1, :ok})
+
+      for {{from, to}, :ok} <- results do
+        assert {:ok, data} = ObjectStoreX.get(store, from)
+        assert {:ok, ^data} = ObjectStoreX.get(store, to)
+      end
+    end
+
+    test "reports failures per pair", %{store: store} do
+      pairs = [{"src/1.txt", "dst/1.txt"}, {"missing.txt", "dst/missing.txt"}]
+
+      assert {:ok, [{_, :ok}, {_, {:error, :not_found}}]} = ObjectStoreX.copy_many(store, pairs)
+    end
+  end
+
+  describe "rename_many/3" do
+    test "moves every pair", %{store: store, pairs: pairs} do
+      assert {:ok, results} = ObjectStoreX.rename_many(store, pairs)
+      assert results == Enum.map(pairs, &{&1, :ok})
+
+      assert {:error, :not_found} = ObjectStoreX.head(store, "src/1.txt")
+      assert {:ok, "data 1"} = ObjectStoreX.get(store, "dst/1.txt")
+    end
+
+    test "accepts an empty list", %{store: store} do
+      assert {:ok, []} = ObjectStoreX.rename_many(store, [])
+    end
+
+    test "overwrites existing destinations by default", %{store: store} do
+      :ok = ObjectStoreX.put(store, "dst/1.txt", "old")
+
+      assert {:ok, [{_, :ok}]} = ObjectStoreX.rename_many(store, [{"src/1.txt", "dst/1.txt"}])
+      assert {:ok, "data 1"} = ObjectStoreX.get(store, "dst/1.txt")
+    end
+
+    test "fails pairs with existing destinations with on_conflict: :fail", %{store: store} do
+      :ok = ObjectStoreX.put(store, "dst/1.txt", "old")
+      pairs = [{"src/1.txt", "dst/1.txt"}, {"src/2.txt", "dst/2.txt"}]
+
+      assert {:ok, [{_, {:error, :already_exists}}, {_, :ok}]} =
+               ObjectStoreX.rename_many(store, pairs, on_conflict: :fail)
+
+      assert {:ok, "old"} = ObjectStoreX.get(store, "dst/1.txt")
+      assert {:ok, "data 1"} = ObjectStoreX.get(store, "src/1.txt")
+    end
+
+    test "skips pairs with existing destinations with on_conflict: :skip", %{store: store} do
+      :ok = ObjectStoreX.put(store, "dst/1.txt", "old")
+
+      assert {:ok, [{_, :skipped}]} =
+               ObjectStoreX.rename_many(store, [{"src/1.txt", "dst/1.txt"}], on_conflict: :skip)
+
+      assert {:ok, "old"} = ObjectStoreX.get(store, "dst/1.txt")
+      assert {:ok, "data 1"} = ObjectStoreX.get(store, "src/1.txt")
+    end
+
+    test "renames to a free suffixed key with on_conflict: :suffix", %{store: store} do
+      :ok = ObjectStoreX.put(store, "dst/1.txt", "old")
+      :ok = ObjectStoreX.put(store, "dst/1-1.txt", "older")
+      pairs = [{"src/1.txt", "dst/1.txt"}, {"src/2.txt", "dst/2.txt"}]
+
+      assert {:ok, [{_, {:ok, "dst/1-2.txt"}}, {_, :ok}]} =
+               ObjectStoreX.rename_many(store, pairs, on_conflict: :suffix)
+
+      assert {:ok, "old"} = ObjectStoreX.get(store, "dst/1.txt")
+      assert {:ok, "data 1"} = ObjectStoreX.get(store, "dst/1-2.txt")
+    end
+
+    test "suffixes keys without an extension", %{store: store} do
+      :ok = ObjectStoreX.put(store, "dst/README", "old")
+
+      pairs = [{"src/1.txt", "dst/README"}]
+
+      assert {:ok, [{_, {:ok, "dst/README-1"}}]} =
+               ObjectStoreX.rename_many(store, pairs, on_conflict: :suffix)
+    end
+
+    test "reports missing sources per pair with every policy", %{store: store} do
+      pairs = [{"missing.txt", "dst/x.txt"}]
+
+      for policy <- [:overwrite, :fail, :skip, :suffix] do
+        assert {:ok, [{_, {:error, :not_found}}]} =
+                 ObjectStoreX.rename_many(store, pairs, on_conflict: policy)
+      end
+    end
+
+    test "rejects unknown policies", %{store: store} do
+      assert {:error, _} = ObjectStoreX.rename_many(store, [], on_conflict: :merge)
+    end
+  end
+end
@@ -0,0 +1,58 @@
+defmodule ObjectStoreX.CopyPrefixTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+
+    :ok = ObjectStoreX.put(store, "data/a.txt", "aaa")
+    :ok = ObjectStoreX.put(store, "data/nested/b.txt", "bb")
+    :ok = ObjectStoreX.put(store, "other/c.txt", "c")
+
+    {:ok, store: store}
+  end
+
+  test "copies every object under the prefix with rewritten keys", %{store: store} do
+    assert {:ok, %{copied: 2, skipped: 0, errors: []}} =
+             ObjectStoreX.copy_prefix(store, "data", "backup/data")
+
+    assert {:ok, "aaa"} = ObjectStoreX.get(store, "backup/data/a.txt")
+    assert {:ok, "bb"} = ObjectStoreX.get(store, "backup/data/nested/b.txt")
+    assert {:error, :not_found} = ObjectStoreX.head(store, "backup/data/c.txt")
+
+    # Sources are kept
+    assert {:ok, "aaa"} = ObjectStoreX.get(store, "data/a.txt")
+  end
+
+  test "overwrites existing destinations by default", %{store: store} do
+    :ok = ObjectStoreX.put(store, "backup/a.txt", "old")
+
+    assert {:ok, %{copied: 2}} = ObjectStoreX.copy_prefix(store, "data", "backup")
+    assert {:ok, "aaa"} = ObjectStoreX.get(store, "backup/a.txt")
+  end
+
+  test "skips existing destinations with on_existing: :skip", %{store: store} do
+    :ok = ObjectStoreX.put(store, "backup/a.txt", "old")
+
+    assert {:ok, %{copied: 1, skipped: 1, errors: []}} =
+             ObjectStoreX.copy_prefix(store, "data", "backup", on_existing: :skip)
+
+    assert {:ok, "old"} = ObjectStoreX.get(store, "backup/a.txt")
+    assert {:ok, "bb"} = ObjectStoreX.get(store, "backup/nested/b.txt")
+  end
+
+  test "reports progress in bytes", %{store: store} do
+    assert {:ok, _} =
+             ObjectStoreX.copy_prefix(store, "data", "backup",
+               progress_pid: self(),
+               progress_id: "copy"
+             )
+
+    assert_receive {:progress, "copy", 0, 5}
+    assert_receive {:progress, "copy", 5, 5}
+  end
+
+  test "copies nothing for an empty prefix", %{store: store} do
+    assert {:ok, %{copied: 0, skipped: 0, errors: []}} =
+             ObjectStoreX.copy_prefix(store, "missing", "backup")
+  end
+end
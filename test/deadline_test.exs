@@ -0,0 +1,80 @@
+defmodule ObjectStoreX.DeadlineTest do
+  use ExUnit.Case, async: true
+
+  alias ObjectStoreX.FakeS3
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+    :ok = ObjectStoreX.put(store, "file.txt", "data")
+    {:ok, slow} = ObjectStoreX.with_throttle(store, get_per_call: 2_000, put_per_call: 2_000)
+    {:ok, store: store, slow: slow}
+  end
+
+  describe ":timeout_ms" do
+    test "fails operations that take longer with :timeout", %{slow: slow} do
+      {elapsed, result} = :timer.tc(fn -> ObjectStoreX.get(slow, "file.txt", timeout_ms: 50) end)
+
+      assert {:error, {:timeout, details}} = result
+      assert [source | _] = details.sources
+      assert source =~ "timed out after 50 ms"
+      assert elapsed < 1_000_000
+      assert ObjectStoreX.Error.retryable?({:timeout, details})
+    end
+
+    test "bounds writes", %{slow: slow} do
+      assert {:error, {:timeout, _}} = ObjectStoreX.put(slow, "new.txt", "data", timeout_ms: 50)
+    end
+
+    test "lets faster operations finish", %{store: store} do
+      assert {:ok, "data"} = ObjectStoreX.get(store, "file.txt", timeout_ms: 5_000)
+      assert {:ok, %{size: 4}} = ObjectStoreX.head(store, "file.txt", timeout_ms: 5_000)
+    end
+  end
+
+  describe ":op_ref" do
+    test "cancel_op/1 cancels running operations", %{slow: slow} do
+      op_ref = ObjectStoreX.new_op_ref()
+      task = Task.async(fn -> ObjectStoreX.get(slow, "file.txt", op_ref: op_ref) end)
+
+      Process.sleep(100)
+      assert :ok = ObjectStoreX.cancel_op(op_ref)
+      assert {:error, :cancelled} = Task.await(task, 1_000)
+    end
+
+    test "cancelled tokens fail later operations", %{store: store} do
+      op_ref = ObjectStoreX.new_op_ref()
+      :ok = ObjectStoreX.cancel_op(op_ref)
+
+      assert {:error, :cancelled} = ObjectStoreX.get(store, "file.txt", op_ref: op_ref)
+      assert {:error, :cancelled} = ObjectStoreX.delete(store, "file.txt", op_ref: op_ref)
+      assert {:ok, "data"} = ObjectStoreX.get(store, "file.txt")
+    end
+
+    test "cancelled tokens bound appends", %{store: store} do
+      op_ref = ObjectStoreX.new_op_ref()
+      :ok = ObjectStoreX.cancel_op(op_ref)
+
+      assert {:error, :cancelled} = ObjectStoreX.append(store, "log.txt", "line", op_ref: op_ref)
+      assert {:error, :not_found} = ObjectStoreX.head(store, "log.txt")
+    end
+
+    test "cancelled tokens bound checksummed puts and signed S3 requests" do
+      store = FakeS3.store()
+      op_ref = ObjectStoreX.new_op_ref()
+      :ok = ObjectStoreX.cancel_op(op_ref)
+
+      assert {:error, :cancelled} =
+               ObjectStoreX.put(store, "a.txt", "data", checksum: :sha256, op_ref: op_ref)
+
+      assert {:error, :cancelled} = ObjectStoreX.get_tags(store, "a.txt", op_ref: op_ref)
+      refute_receive {:request, _}, 100
+    end
+
+    test "uncancelled tokens don't affect operations", %{store: store} do
+      op_ref = ObjectStoreX.new_op_ref()
+
+      opts = [op_ref: op_ref, timeout_ms: 5_000]
+      assert {:ok, "data"} = ObjectStoreX.get(store, "file.txt", opts)
+    end
+  end
+end
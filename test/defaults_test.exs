@@ -0,0 +1,63 @@
+defmodule ObjectStoreX.DefaultsTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+    {:ok, store: store}
+  end
+
+  describe "with_defaults/2" do
+    test "applies default attributes to plain puts", %{store: store} do
+      {:ok, child} =
+        ObjectStoreX.with_defaults(store, cache_control: "max-age=60", content_encoding: "gzip")
+
+      :ok = ObjectStoreX.put(child, "file.txt", "data")
+
+      assert {:ok, meta} = ObjectStoreX.head(store, "file.txt")
+      assert meta[:cache_control] == "max-age=60"
+      assert meta[:content_encoding] == "gzip"
+    end
+
+    test "attributes of the put override defaults", %{store: store} do
+      {:ok, child} = ObjectStoreX.with_defaults(store, cache_control: "max-age=60")
+
+      {:ok, _} =
+        ObjectStoreX.put(child, "file.txt", "data",
+          cache_control: "no-cache",
+          content_type: "text/plain"
+        )
+
+      assert {:ok, meta} = ObjectStoreX.head(store, "file.txt")
+      assert meta[:cache_control] == "no-cache"
+      assert meta[:content_type] == "text/plain"
+    end
+
+    test "merges metadata per key", %{store: store} do
+      {:ok, child} =
+        ObjectStoreX.with_defaults(store, metadata: %{"owner" => "ops", "tier" => "hot"})
+
+      {:ok, _} = ObjectStoreX.put(child, "file.txt", "data", metadata: %{"tier" => "cold"})
+
+      assert {:ok, meta} = ObjectStoreX.head(store, "file.txt")
+      assert meta[:metadata] == %{"owner" => "ops", "tier" => "cold"}
+    end
+
+    test "applies defaults to streamed uploads", %{store: store} do
+      {:ok, child} = ObjectStoreX.with_defaults(store, content_type: "application/x-ndjson")
+
+      :ok = ObjectStoreX.Stream.upload(["{}\n", "{}\n"], child, "events.ndjson")
+
+      assert {:ok, meta} = ObjectStoreX.head(store, "events.ndjson")
+      assert meta[:content_type] == "application/x-ndjson"
+    end
+
+    test "leaves the parent store unchanged", %{store: store} do
+      {:ok, _child} = ObjectStoreX.with_defaults(store, cache_control: "max-age=60")
+
+      :ok = ObjectStoreX.put(store, "file.txt", "data")
+
+      assert {:ok, meta} = ObjectStoreX.head(store, "file.txt")
+      assert meta[:cache_control] == nil
+    end
+  end
+end
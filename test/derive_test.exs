@@ -0,0 +1,110 @@
+defmodule ObjectStoreX.DeriveTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+    {:ok, store: store}
+  end
+
+  describe "derive/2 with prefix" do
+    test "scopes paths under the prefix", %{store: store} do
+      {:ok, tenant} = ObjectStoreX.derive(store, prefix: "tenants/acme")
+
+      assert :ok = ObjectStoreX.put(tenant, "report.csv", "a,b")
+      assert {:ok, "a,b"} = ObjectStoreX.get(store, "tenants/acme/report.csv")
+      assert {:ok, "a,b"} = ObjectStoreX.get(tenant, "report.csv")
+    end
+
+    test "listings are relative to the prefix", %{store: store} do
+      assert :ok = ObjectStoreX.put(store, "tenants/acme/one.txt", "1")
+      assert :ok = ObjectStoreX.put(store, "tenants/other/two.txt", "2")
+
+      {:ok, tenant} = ObjectStoreX.derive(store, %{prefix: "tenants/acme"})
+
+      locations = ObjectStoreX.Stream.list_stream(tenant) |> Enum.map(& &1.location)
+      assert locations == ["one.txt"]
+    end
+  end
+
+  describe "with_prefix/2" do
+    test "returns a handle scoped under the prefix", %{store: store} do
+      {:ok, tenant} = ObjectStoreX.with_prefix(store, "tenants/acme")
+
+      assert :ok = ObjectStoreX.put(tenant, "report.csv", "a,b")
+      assert {:ok, "a,b"} = ObjectStoreX.get(store, "tenants/acme/report.csv")
+    end
+
+    test "can't escape the prefix", %{store: store} do
+      assert :ok = ObjectStoreX.put(store, "tenants/other/secret.txt", "secret")
+      {:ok, tenant} = ObjectStoreX.with_prefix(store, "tenants/acme")
+
+      assert {:error, _} = ObjectStoreX.get(tenant, "../other/secret.txt")
+      assert :ok = ObjectStoreX.put(tenant, "../other/secret.txt", "overwritten")
+      assert {:ok, "secret"} = ObjectStoreX.get(store, "tenants/other/secret.txt")
+    end
+  end
+
+  describe "derive/2 with read_only" do
+    test "allows reads and rejects writes", %{store: store} do
+      assert :ok = ObjectStoreX.put(store, "data.txt", "data")
+      {:ok, reader} = ObjectStoreX.derive(store, read_only: true)
+
+      assert {:ok, "data"} = ObjectStoreX.get(reader, "data.txt")
+      assert {:ok, %{size: 4}} = ObjectStoreX.head(reader, "data.txt")
+
+      assert {:error, :permission_denied} = ObjectStoreX.put(reader, "data.txt", "new")
+      assert {:error, :permission_denied} = ObjectStoreX.delete(reader, "data.txt")
+      assert {:error, :permission_denied} = ObjectStoreX.copy(reader, "data.txt", "copy.txt")
+      assert {:error, :permission_denied} = ObjectStoreX.rename(reader, "data.txt", "new.txt")
+
+      assert {:ok, "data"} = ObjectStoreX.get(store, "data.txt")
+    end
+  end
+
+  describe "derive/2 with quota" do
+    test "rejects writes beyond the quota", %{store: store} do
+      {:ok, tenant} = ObjectStoreX.derive(store, prefix: "t", quota: 10)
+
+      assert :ok = ObjectStoreX.put(tenant, "a.bin", "12345678")
+      assert {:error, :quota_exceeded} = ObjectStoreX.put(tenant, "b.bin", "12345")
+      assert {:error, :not_found} = ObjectStoreX.get(store, "t/b.bin")
+    end
+
+    test "overwrites and deletes release quota", %{store: store} do
+      {:ok, tenant} = ObjectStoreX.derive(store, quota: 10)
+
+      assert :ok = ObjectStoreX.put(tenant, "a.bin", "12345678")
+      assert :ok = ObjectStoreX.put(tenant, "a.bin", "1234")
+      assert :ok = ObjectStoreX.put(tenant, "b.bin", "123456")
+
+      assert :ok = ObjectStoreX.delete(tenant, "a.bin")
+      assert :ok = ObjectStoreX.put(tenant, "c.bin", "1234")
+    end
+
+    test "counts existing objects", %{store: store} do
+      assert :ok = ObjectStoreX.put(store, "t/existing.bin", "123456")
+      {:ok, tenant} = ObjectStoreX.derive(store, prefix: "t", quota: 10)
+
+      assert {:error, :quota_exceeded} = ObjectStoreX.put(tenant, "new.bin", "12345")
+    end
+
+    test "applies to streaming uploads", %{store: store} do
+      {:ok, tenant} = ObjectStoreX.derive(store, quota: 1024)
+
+      result =
+        Stream.repeatedly(fn -> String.duplicate("x", 6 * 1024 * 1024) end)
+        |> Stream.take(1)
+        |> ObjectStoreX.Stream.upload(tenant, "big.bin")
+
+      assert {:error, _reason} = result
+    end
+  end
+
+  test "restrictions combine", %{store: store} do
+    assert :ok = ObjectStoreX.put(store, "t/a.txt", "data")
+    {:ok, child} = ObjectStoreX.derive(store, prefix: "t", read_only: true, quota: 100)
+
+    assert {:ok, "data"} = ObjectStoreX.get(child, "a.txt")
+    assert {:error, :permission_denied} = ObjectStoreX.put(child, "b.txt", "data")
+  end
+end
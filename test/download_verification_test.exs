@@ -0,0 +1,122 @@
+defmodule ObjectStoreX.DownloadVerificationTest do
+  use ExUnit.Case, async: true
+
+  # Objects have the MD5 of "hello" as ETag, like single-part S3 uploads;
+  # "corrupt.txt" returns different bytes than the ETag was computed from
+  defmodule Md5ETagBackend do
+    @behaviour ObjectStoreX.Backend
+
+    @impl true
+    def init(arg), do: {:ok, arg}
+
+    @impl true
+    def put(_path, _data, _mode, _arg), do: {:error, :not_supported}
+
+    @impl true
+    def get("corrupt.txt", _arg), do: {:ok, "hellp"}
+    def get(_path, _arg), do: {:ok, "hello"}
+
+    @impl true
+    def get_range(_path, start, stop, _arg), do: {:ok, binary_part("hello", start, stop - start)}
+
+    @impl true
+    def head(path, _arg) do
+      etag = :crypto.hash(:md5, "hello") |> Base.encode16(case: :lower)
+      {:ok, %{location: path, size: 5, etag: ~s("#{etag}")}}
+    end
+
+    @impl true
+    def delete(_path, _arg), do: :ok
+
+    @impl true
+    def list(_prefix, _arg), do: {:ok, []}
+  end
+
+  defp hex(algorithm, data), do: :crypto.hash(algorithm, data) |> Base.encode16(case: :lower)
+
+  setup do
+    dir = Path.join(System.tmp_dir!(), "objectstorex_verify_#{:rand.uniform(1_000_000)}")
+    File.mkdir_p!(dir)
+    on_exit(fn -> File.rm_rf!(dir) end)
+
+    {:ok, dir: dir}
+  end
+
+  describe "get/3 with verify: :etag" do
+    setup do
+      {:ok, store} = ObjectStoreX.new(:custom, backend: Md5ETagBackend, arg: nil)
+      {:ok, store: store}
+    end
+
+    test "returns data matching its MD5 ETag", %{store: store} do
+      assert {:ok, "hello", _meta} = ObjectStoreX.get(store, "file.txt", verify: :etag)
+    end
+
+    test "fails on data not matching its MD5 ETag", %{store: store} do
+      assert {:error, :integrity_error} = ObjectStoreX.get(store, "corrupt.txt", verify: :etag)
+    end
+
+    test "get_to_file removes a file not matching its MD5 ETag", %{store: store, dir: dir} do
+      target = Path.join(dir, "corrupt.txt")
+
+      assert {:error, :integrity_error} =
+               ObjectStoreX.get_to_file(store, "corrupt.txt", target, verify: :etag)
+
+      refute File.exists?(target)
+    end
+
+    test "returns data unchecked when the ETag isn't a digest", %{dir: dir} do
+      {:ok, store} = ObjectStoreX.new(:memory)
+      :ok = ObjectStoreX.put(store, "file.txt", "hello")
+      target = Path.join(dir, "file.txt")
+
+      assert {:ok, "hello", _meta} = ObjectStoreX.get(store, "file.txt", verify: :etag)
+      assert :ok = ObjectStoreX.get_to_file(store, "file.txt", target, verify: :etag)
+    end
+  end
+
+  describe "get/3 and get_to_file/4 with a digest" do
+    setup do
+      {:ok, store} = ObjectStoreX.new(:memory)
+      :ok = ObjectStoreX.put(store, "file.txt", "hello")
+      {:ok, store: store}
+    end
+
+    test "return data matching the digest", %{store: store, dir: dir} do
+      target = Path.join(dir, "file.txt")
+
+      for algorithm <- [:md5, :sha256] do
+        verify = {algorithm, hex(algorithm, "hello")}
+
+        assert {:ok, "hello", _meta} = ObjectStoreX.get(store, "file.txt", verify: verify)
+        assert :ok = ObjectStoreX.get_to_file(store, "file.txt", target, verify: verify)
+        assert File.read!(target) == "hello"
+      end
+    end
+
+    test "verify CRC32C digests", %{store: store} do
+      {:ok, %{checksum: crc}} = ObjectStoreX.put(store, "crc.txt", "123456789", checksum: :crc32c)
+
+      assert crc == "e3069283"
+      assert {:ok, _, _} = ObjectStoreX.get(store, "crc.txt", verify: {:crc32c, crc})
+    end
+
+    test "fail on a mismatching digest", %{store: store, dir: dir} do
+      target = Path.join(dir, "file.txt")
+      verify = {:sha256, hex(:sha256, "other")}
+
+      assert {:error, :integrity_error} = ObjectStoreX.get(store, "file.txt", verify: verify)
+
+      assert {:error, :integrity_error} =
+               ObjectStoreX.get_to_file(store, "file.txt", target, verify: verify)
+
+      refute File.exists?(target)
+    end
+
+    test "can't be combined with ranges", %{store: store} do
+      verify = {:md5, hex(:md5, "hello")}
+
+      assert {:error, _} = ObjectStoreX.get(store, "file.txt", verify: verify, range: {0, 2})
+    end
+  end
+end
@@ -0,0 +1,82 @@
+defmodule ObjectStoreX.EncryptionTest do
+  use ExUnit.Case, async: true
+
+  @key :crypto.strong_rand_bytes(32)
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+    {:ok, secure} = ObjectStoreX.with_encryption(store, {"primary", @key})
+    {:ok, store: store, secure: secure}
+  end
+
+  test "stores ciphertext and reads plaintext back", %{store: store, secure: secure} do
+    :ok = ObjectStoreX.put(secure, "secret.txt", "attack at dawn")
+
+    assert {:ok, "attack at dawn"} = ObjectStoreX.get(secure, "secret.txt")
+
+    assert {:ok, stored} = ObjectStoreX.get(store, "secret.txt")
+    refute stored =~ "attack"
+    assert {:ok, %{size: 14}} = ObjectStoreX.head(secure, "secret.txt")
+  end
+
+  test "encrypts empty objects", %{secure: secure} do
+    :ok = ObjectStoreX.put(secure, "empty", "")
+
+    assert {:ok, ""} = ObjectStoreX.get(secure, "empty")
+  end
+
+  test "reads ranges across segments", %{secure: secure} do
+    data = :crypto.strong_rand_bytes(300_000)
+    :ok = ObjectStoreX.put(secure, "data.bin", data)
+
+    assert {:ok, part, _meta} = ObjectStoreX.get(secure, "data.bin", range: {65_000, 140_000})
+    assert part == binary_part(data, 65_000, 75_000)
+
+    streamed = ObjectStoreX.Stream.download(secure, "data.bin") |> Enum.join()
+    assert streamed == data
+  end
+
+  test "encrypts streamed uploads", %{secure: secure} do
+    chunks = for _ <- 1..3, do: :crypto.strong_rand_bytes(5 * 1024 * 1024)
+
+    assert :ok = ObjectStoreX.Stream.upload(chunks, secure, "large.bin")
+    assert {:ok, data} = ObjectStoreX.get(secure, "large.bin")
+    assert data == IO.iodata_to_binary(chunks)
+  end
+
+  test "decrypts with rotated keys", %{store: store, secure: secure} do
+    :ok = ObjectStoreX.put(secure, "old.txt", "old")
+
+    new_key = :crypto.strong_rand_bytes(32)
+    {:ok, rotated} = ObjectStoreX.with_encryption(store, [{"next", new_key}, {"primary", @key}])
+    :ok = ObjectStoreX.put(rotated, "new.txt", "new")
+
+    assert {:ok, "old"} = ObjectStoreX.get(rotated, "old.txt")
+    assert {:ok, "new"} = ObjectStoreX.get(rotated, "new.txt")
+    assert {:error, :integrity_error} = ObjectStoreX.get(secure, "new.txt")
+  end
+
+  test "rejects tampered data", %{store: store, secure: secure} do
+    :ok = ObjectStoreX.put(secure, "secret.txt", "attack at dawn")
+    {:ok, %{metadata: metadata}} = ObjectStoreX.head(store, "secret.txt")
+    {:ok, <<first, rest::binary>>} = ObjectStoreX.get(store, "secret.txt")
+
+    {:ok, _} =
+      ObjectStoreX.put(store, "secret.txt", <<Bitwise.bxor(first, 1), rest::binary>>,
+        metadata: metadata
+      )
+
+    assert {:error, :integrity_error} = ObjectStoreX.get(secure, "secret.txt")
+  end
+
+  test "rejects objects that aren't encrypted", %{store: store, secure: secure} do
+    :ok = ObjectStoreX.put(store, "plain.txt", "plain")
+
+    assert {:error, :integrity_error} = ObjectStoreX.get(secure, "plain.txt")
+  end
+
+  test "rejects malformed keys", %{store: store} do
+    assert {:error, _} = ObjectStoreX.with_encryption(store, {"short", "too short"})
+    assert {:error, _} = ObjectStoreX.with_encryption(store, [])
+  end
+end
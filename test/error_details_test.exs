@@ -0,0 +1,92 @@
+defmodule ObjectStoreX.ErrorDetailsTest do
+  use ExUnit.Case, async: true
+
+  alias ObjectStoreX.{Error, FakeS3}
+
+  # Reply to one request with the given status line, headers and S3 error code
+  defp respond(status, headers, code) do
+    body =
+      "<?xml version=\"1.0\" encoding=\"UTF-8\"?>" <>
+        "<Error><Code>#{code}</Code><Message>Failed</Message></Error>"
+
+    FakeS3.store(status, headers, body)
+  end
+
+  describe "errors of object_store requests" do
+    test "429 responses are :throttled with status and code" do
+      store = respond("429 Too Many Requests", [], "SlowDown")
+
+      assert {:error, {:throttled, details}} = ObjectStoreX.get(store, "a.txt")
+      assert %{status: 429, code: "SlowDown", retry_after_ms: nil} = details
+    end
+
+    test "429 responses without a throttling code are :throttled by their status" do
+      store = FakeS3.store("429 Too Many Requests", [], "")
+
+      assert {:error, {:throttled, details}} = ObjectStoreX.get(store, "a.txt")
+      assert %{status: 429, code: nil} = details
+    end
+
+    test "other client errors are :error with status and code" do
+      store = respond("400 Bad Request", [], "InvalidRequest")
+
+      assert {:error, {:error, details}} = ObjectStoreX.get(store, "a.txt")
+      assert %{status: 400, code: "InvalidRequest"} = details
+    end
+
+    test "not found errors stay plain atoms" do
+      store = respond("404 Not Found", [], "NoSuchKey")
+
+      assert {:error, :not_found} = ObjectStoreX.get(store, "a.txt")
+    end
+  end
+
+  describe "generic errors" do
+    test "include the error message and its sources" do
+      {:ok, store} = ObjectStoreX.new(:memory)
+      :ok = ObjectStoreX.put(store, "bad.json", "not json")
+
+      assert {:error, {:error, details}} = ObjectStoreX.join(store, "bad.json", "joined.txt")
+      assert %{status: nil, code: nil, retry_after_ms: nil} = details
+      assert details.message =~ "Split"
+      assert [source | _] = details.sources
+      assert source =~ "Invalid split manifest bad.json"
+    end
+
+    test "of HTTP requests quote the response body" do
+      store = respond("400 Bad Request", [], "InvalidRequest")
+
+      assert {:error, {:error, details}} = ObjectStoreX.get(store, "a.txt")
+      assert Enum.any?(details.sources, &(&1 =~ "<Code>InvalidRequest</Code>"))
+    end
+  end
+
+  describe "errors of requests sent by the library" do
+    test "throttling codes are :throttled with the Retry-After hint" do
+      store = respond("503 Service Unavailable", [{"retry-after", "2"}], "SlowDown")
+
+      assert {:error, {:throttled, details}} =
+               ObjectStoreX.act_if_unchanged(store, "a.txt", "\"etag\"", {:copy, "b.txt"})
+
+      assert %{status: 503, code: "SlowDown", retry_after_ms: 2000} = details
+      assert Error.retryable?({:throttled, details})
+      assert Error.retry_after({:throttled, details}) == 2000
+    end
+
+    test "RequestTimeout codes are :timeout" do
+      store = respond("400 Bad Request", [], "RequestTimeout")
+
+      assert {:error, {:timeout, %{status: 400, code: "RequestTimeout"}}} =
+               ObjectStoreX.act_if_unchanged(store, "a.txt", "\"etag\"", {:copy, "b.txt"})
+    end
+
+    test "5xx responses are :server_error" do
+      store = respond("500 Internal Server Error", [], "InternalError")
+
+      assert {:error, {:server_error, details}} =
+               ObjectStoreX.act_if_unchanged(store, "a.txt", "\"etag\"", {:copy, "b.txt"})
+
+      assert %{status: 500, code: "InternalError", retry_after_ms: nil} = details
+    end
+  end
+end
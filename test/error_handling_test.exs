@@ -13,6 +13,8 @@ defmodule ObjectStoreX.ErrorHandlingTest do
       assert ObjectStoreX.Errors.describe(:not_modified) =~ "not modified"
       assert ObjectStoreX.Errors.describe(:not_supported) =~ "not supported"
       assert ObjectStoreX.Errors.describe(:permission_denied) =~ "permission"
+      assert ObjectStoreX.Errors.describe(:throttled) =~ "slow down"
+      assert ObjectStoreX.Errors.describe(:server_error) =~ "failed to handle"
     end
 
     test "describe handles unknown error atoms" do
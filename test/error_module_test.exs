@@ -15,6 +15,13 @@ defmodule ObjectStoreX.ErrorModuleTest do
       assert Error.format_error(:timeout) == "Operation timed out"
       assert Error.format_error(:network_error) == "Network error"
       assert Error.format_error(:invalid_input) == "Invalid input parameters"
+      assert Error.format_error(:checksum_mismatch) == "Size or checksum mismatch"
+      assert Error.format_error(:integrity_error) == "Downloaded data failed verification"
+      assert Error.format_error(:invalid_json) == "Object is not valid JSON"
+      assert Error.format_error(:key_too_long) == "Object key too long for this provider"
+      assert Error.format_error(:too_many_parts) == "Too many upload parts for this provider"
+      assert Error.format_error(:throttled) == "Request throttled by the provider"
+      assert Error.format_error(:server_error) == "Provider server error"
     end
 
     test "format_error handles unknown errors with details" do
@@ -45,6 +52,9 @@ defmodule ObjectStoreX.ErrorModuleTest do
       assert Error.retryable?(:timeout) == true
       assert Error.retryable?(:network_error) == true
       assert Error.retryable?(:precondition_failed) == true
+      assert Error.retryable?(:integrity_error) == true
+      assert Error.retryable?(:throttled) == true
+      assert Error.retryable?(:server_error) == true
     end
 
     test "retryable? returns false for permanent errors" do
@@ -54,6 +64,8 @@ defmodule ObjectStoreX.ErrorModuleTest do
       assert Error.retryable?(:permission_denied) == false
       assert Error.retryable?(:not_supported) == false
       assert Error.retryable?(:invalid_input) == false
+      assert Error.retryable?(:too_many_tags) == false
+      assert Error.retryable?(:invalid_json) == false
     end
 
     test "retryable? returns false for unknown errors" do
@@ -66,6 +78,26 @@ defmodule ObjectStoreX.ErrorModuleTest do
       assert Error.retryable?({:network_error, %{message: "Connection reset"}}) == true
       assert Error.retryable?({:not_found, %{path: "missing.txt"}}) == false
     end
+
+    test "retryable? handles errors with HTTP details" do
+      details = %{status: 429, code: "SlowDown", retry_after_ms: 1000}
+      assert Error.retryable?({:throttled, details}) == true
+      assert Error.retryable?({:server_error, %{details | status: 503}}) == true
+      assert Error.retryable?({:error, %{details | status: 400}}) == false
+    end
+  end
+
+  describe "retry_after/1" do
+    test "returns the Retry-After hint in milliseconds" do
+      assert Error.retry_after({:throttled, %{status: 503, retry_after_ms: 1500}}) == 1500
+    end
+
+    test "returns nil without a hint" do
+      assert Error.retry_after({:throttled, %{status: 429, retry_after_ms: nil}}) == nil
+      assert Error.retry_after({:timeout, %{path: "file.txt"}}) == nil
+      assert Error.retry_after(:throttled) == nil
+      assert Error.retry_after("error") == nil
+    end
   end
 
   describe "OBX004_1A_T3: Test error context includes operation details" do
@@ -285,6 +317,8 @@ defmodule ObjectStoreX.ErrorModuleTest do
       assert Error.map_error(:not_found) == :not_found
       assert Error.map_error(:timeout) == :timeout
       assert Error.map_error(:network_error) == :network_error
+      assert Error.map_error(:throttled) == :throttled
+      assert Error.map_error(:server_error) == :server_error
     end
   end
 end
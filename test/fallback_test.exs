@@ -0,0 +1,55 @@
+defmodule ObjectStoreX.FallbackTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    {:ok, primary} = ObjectStoreX.new(:memory)
+    {:ok, first} = ObjectStoreX.new(:memory)
+    {:ok, second} = ObjectStoreX.new(:memory)
+    {:ok, store} = ObjectStoreX.with_fallback(primary, [first, second])
+    {:ok, primary: primary, first: first, second: second, store: store}
+  end
+
+  describe "with_fallback/2" do
+    test "prefers the primary", %{primary: primary, first: first, store: store} do
+      :ok = ObjectStoreX.put(primary, "a.txt", "primary")
+      :ok = ObjectStoreX.put(first, "a.txt", "fallback")
+
+      assert {:ok, "primary"} = ObjectStoreX.get(store, "a.txt")
+    end
+
+    test "reads missing objects from the fallbacks in order", %{
+      first: first,
+      second: second,
+      store: store
+    } do
+      :ok = ObjectStoreX.put(first, "a.txt", "first")
+      :ok = ObjectStoreX.put(second, "a.txt", "second")
+      :ok = ObjectStoreX.put(second, "b.txt", "second")
+
+      assert {:ok, "first"} = ObjectStoreX.get(store, "a.txt")
+      assert {:ok, "second"} = ObjectStoreX.get(store, "b.txt")
+      assert {:ok, %{size: 6}} = ObjectStoreX.head(store, "b.txt")
+      assert {:ok, ["sec"]} = ObjectStoreX.get_ranges(store, "b.txt", [{0, 3}])
+    end
+
+    test "returns not found when no store has the object", %{store: store} do
+      assert {:error, :not_found} = ObjectStoreX.get(store, "missing.txt")
+      assert {:error, :not_found} = ObjectStoreX.head(store, "missing.txt")
+    end
+
+    test "writes and lists the primary only", %{primary: primary, first: first, store: store} do
+      :ok = ObjectStoreX.put(first, "old.txt", "old")
+      :ok = ObjectStoreX.put(store, "new.txt", "new")
+
+      assert {:ok, "new"} = ObjectStoreX.get(primary, "new.txt")
+      assert {:error, :not_found} = ObjectStoreX.get(first, "new.txt")
+
+      locations = store |> ObjectStoreX.Stream.list_stream() |> Enum.map(& &1.location)
+      assert locations == ["new.txt"]
+    end
+
+    test "requires a fallback", %{primary: primary} do
+      assert {:error, _} = ObjectStoreX.with_fallback(primary, [])
+    end
+  end
+end
@@ -0,0 +1,34 @@
+defmodule ObjectStoreX.GetManyTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+    {:ok, store: store}
+  end
+
+  describe "get_many/3" do
+    test "returns results in the order of the paths", %{store: store} do
+      paths = for i <- 1..100, do: "blobs/#{i}.json"
+      Enum.each(paths, &(:ok = ObjectStoreX.put(store, &1, ~s({"path": "#{&1}"}))))
+
+      assert {:ok, results} = ObjectStoreX.get_many(store, paths, max_concurrency: 8)
+
+      assert Enum.map(results, &elem(&1, 0)) == paths
+
+      for {path, result} <- results do
+        assert result == {:ok, ~s({"path": "#{path}"})}
+      end
+    end
+
+    test "reports failures per path", %{store: store} do
+      :ok = ObjectStoreX.put(store, "a.txt", "a")
+
+      assert {:ok, results} = ObjectStoreX.get_many(store, ["a.txt", "missing.txt"])
+      assert results == [{"a.txt", {:ok, "a"}}, {"missing.txt", {:error, :not_found}}]
+    end
+
+    test "accepts an empty list", %{store: store} do
+      assert {:ok, []} = ObjectStoreX.get_many(store, [])
+    end
+  end
+end
@@ -91,6 +91,38 @@ defmodule ObjectStoreX.GetOptionsTest do
       end
     end
 
+    test "conditional timestamps accept milliseconds and RFC 3339 strings", %{store: store} do
+      :ok = ObjectStoreX.put(store, "test.txt", "data")
+      past = DateTime.add(DateTime.utc_now(), -3600, :second)
+      future = DateTime.add(DateTime.utc_now(), 3600, :second)
+
+      for since <- [
+            {DateTime.to_unix(past, :millisecond), :millisecond},
+            {DateTime.to_unix(past), :second},
+            DateTime.to_iso8601(past),
+            "2000-01-01T00:00:00.250+02:00"
+          ] do
+        assert {:ok, "data", _meta} =
+                 ObjectStoreX.get(store, "test.txt", if_modified_since: since)
+      end
+
+      assert {:error, :not_modified} =
+               ObjectStoreX.get(store, "test.txt",
+                 if_modified_since: {DateTime.to_unix(future, :millisecond), :millisecond}
+               )
+
+      assert {:error, :precondition_failed} =
+               ObjectStoreX.get(store, "test.txt", if_unmodified_since: DateTime.to_iso8601(past))
+    end
+
+    test "invalid conditional timestamps return errors", %{store: store} do
+      :ok = ObjectStoreX.put(store, "test.txt", "data")
+
+      for since <- ["yesterday", "2025-01-01T00:00:00", {1, :hour}, 9_999_999_999_999, 1.5] do
+        assert {:error, _} = ObjectStoreX.get(store, "test.txt", if_modified_since: since)
+      end
+    end
+
     @tag :OBX003_2A_T7
     test "get with range option", %{store: store} do
       # Put an object with some data
@@ -102,6 +134,47 @@ defmodule ObjectStoreX.GetOptionsTest do
       assert data == "0123456789"
     end
 
+    test "ranged get reports the satisfied range and total size", %{store: store} do
+      :ok = ObjectStoreX.put(store, "test.txt", "0123456789ABCDEFGHIJ")
+
+      {:ok, data, meta} = ObjectStoreX.get(store, "test.txt", range: {15, 40})
+      assert data == "FGHIJ"
+      assert meta.range == {15, 20}
+      assert meta.total_size == 20
+    end
+
+    test "unranged get omits range metadata", %{store: store} do
+      :ok = ObjectStoreX.put(store, "test.txt", "0123456789")
+
+      {:ok, _data, meta} = ObjectStoreX.get(store, "test.txt", if_none_match: "\"other\"")
+      refute Map.has_key?(meta, :range)
+      refute Map.has_key?(meta, :total_size)
+    end
+
+    test "ranged conditional get returns attributes", %{store: store} do
+      {:ok, put} =
+        ObjectStoreX.put(store, "test.json", ~s({"key":"value"}),
+          content_type: "application/json",
+          metadata: %{"owner" => "ops"}
+        )
+
+      {:ok, data, meta} =
+        ObjectStoreX.get(store, "test.json", if_match: put.etag, range: {1, 6})
+
+      assert data == ~s("key")
+      assert meta.range == {1, 6}
+      assert meta.total_size == 15
+      assert meta.content_type == "application/json"
+      assert meta.metadata == %{"owner" => "ops"}
+    end
+
+    test "head-only get returns the same metadata as head/3", %{store: store} do
+      {:ok, _} = ObjectStoreX.put(store, "test.txt", "data", cache_control: "no-cache")
+
+      {:ok, head} = ObjectStoreX.head(store, "test.txt")
+      assert {:ok, "", ^head} = ObjectStoreX.get(store, "test.txt", head: true)
+    end
+
     @tag :OBX003_2A_T8
     test "get with head: true returns metadata only", %{store: store} do
       # Put an object
@@ -0,0 +1,38 @@
+defmodule ObjectStoreX.GetWithMetadataTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+    %{store: store}
+  end
+
+  describe "get_with_metadata/3" do
+    test "returns data with metadata and attributes", %{store: store} do
+      {:ok, _} =
+        ObjectStoreX.put(store, "report.json", ~s({"ok":true}),
+          content_type: "application/json",
+          cache_control: "max-age=60",
+          metadata: %{"owner" => "ops"}
+        )
+
+      assert {:ok, ~s({"ok":true}), meta} = ObjectStoreX.get_with_metadata(store, "report.json")
+      assert meta.location == "report.json"
+      assert meta.size == 11
+      assert meta.content_type == "application/json"
+      assert meta.cache_control == "max-age=60"
+      assert meta.metadata == %{"owner" => "ops"}
+      assert is_binary(meta.etag)
+    end
+
+    test "returns the same metadata as head/3", %{store: store} do
+      :ok = ObjectStoreX.put(store, "file.txt", "data")
+
+      {:ok, head} = ObjectStoreX.head(store, "file.txt")
+      assert {:ok, "data", ^head} = ObjectStoreX.get_with_metadata(store, "file.txt")
+    end
+
+    test "returns :not_found for missing objects", %{store: store} do
+      assert {:error, :not_found} = ObjectStoreX.get_with_metadata(store, "missing.txt")
+    end
+  end
+end
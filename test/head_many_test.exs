@@ -0,0 +1,41 @@
+defmodule ObjectStoreX.HeadManyTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+    {:ok, store: store}
+  end
+
+  describe "head_many/3" do
+    test "returns metadata in the order of the paths", %{store: store} do
+      paths = for i <- 1..100, do: "manifest/#{i}.bin"
+      Enum.each(paths, &(:ok = ObjectStoreX.put(store, &1, String.duplicate("x", 10))))
+
+      assert {:ok, results} = ObjectStoreX.head_many(store, paths, max_concurrency: 8)
+
+      assert Enum.map(results, &elem(&1, 0)) == paths
+
+      for {path, result} <- results do
+        assert {:ok, %{location: ^path, size: 10}} = result
+      end
+    end
+
+    test "matches the metadata of head/3", %{store: store} do
+      {:ok, _} = ObjectStoreX.put(store, "a.json", "{}", content_type: "application/json")
+
+      assert {:ok, [{"a.json", {:ok, meta}}]} = ObjectStoreX.head_many(store, ["a.json"])
+      assert {:ok, ^meta} = ObjectStoreX.head(store, "a.json")
+    end
+
+    test "reports failures per path", %{store: store} do
+      :ok = ObjectStoreX.put(store, "a.txt", "a")
+
+      assert {:ok, [{"a.txt", {:ok, _}}, {"missing.txt", {:error, :not_found}}]} =
+               ObjectStoreX.head_many(store, ["a.txt", "missing.txt"])
+    end
+
+    test "accepts an empty list", %{store: store} do
+      assert {:ok, []} = ObjectStoreX.head_many(store, [])
+    end
+  end
+end
@@ -0,0 +1,52 @@
+defmodule ObjectStoreX.HttpStoreTest do
+  use ExUnit.Case, async: true
+
+  describe "new(:http, ...)" do
+    test "creates a store for HTTP and HTTPS URLs" do
+      assert {:ok, store} = ObjectStoreX.new(:http, url: "https://example.com/files")
+      assert is_reference(store)
+
+      assert {:ok, _store} =
+               ObjectStoreX.new(:http,
+                 url: "http://localhost:8080",
+                 headers: %{"authorization" => "Bearer token"}
+               )
+    end
+
+    test "rejects invalid URLs and headers" do
+      assert {:error, message} = ObjectStoreX.new(:http, url: "not a url")
+      assert message =~ "HTTP build error"
+
+      assert {:error, _} =
+               ObjectStoreX.new(:http, url: "https://example.com", headers: [{"bad header", "x"}])
+    end
+
+    test "reads objects from an HTTP server" do
+      root = Path.join(System.tmp_dir!(), "objectstorex_http_#{:rand.uniform(1_000_000)}")
+      File.mkdir_p!(Path.join(root, "dir"))
+      File.write!(Path.join(root, "dir/file.txt"), "served over http")
+
+      :ok = Application.ensure_started(:inets)
+
+      {:ok, server} =
+        :inets.start(:httpd,
+          port: 0,
+          bind_address: {127, 0, 0, 1},
+          server_name: ~c"objectstorex-test",
+          server_root: String.to_charlist(root),
+          document_root: String.to_charlist(root)
+        )
+
+      try do
+        port = :httpd.info(server)[:port]
+        {:ok, store} = ObjectStoreX.new(:http, url: "http://127.0.0.1:#{port}")
+
+        assert {:ok, "served over http"} = ObjectStoreX.get(store, "dir/file.txt")
+        assert {:error, :not_found} = ObjectStoreX.get(store, "dir/missing.txt")
+      after
+        :inets.stop(:httpd, server)
+        File.rm_rf!(root)
+      end
+    end
+  end
+end
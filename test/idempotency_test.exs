@@ -0,0 +1,69 @@
+defmodule ObjectStoreX.IdempotencyTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+    {:ok, store: store}
+  end
+
+  describe "put/4 with :idempotency_key" do
+    test "stores the object", %{store: store} do
+      assert {:ok, %{etag: _}} =
+               ObjectStoreX.put(store, "file.txt", "data", idempotency_key: "key-1")
+
+      assert {:ok, "data"} = ObjectStoreX.get(store, "file.txt")
+    end
+
+    test "a retried create with the same key succeeds", %{store: store} do
+      opts = [mode: :create, idempotency_key: "key-1"]
+
+      assert {:ok, first} = ObjectStoreX.put(store, "file.txt", "data", opts)
+      assert {:ok, ^first} = ObjectStoreX.put(store, "file.txt", "data", opts)
+    end
+
+    test "a create with a different key still conflicts", %{store: store} do
+      {:ok, _} = ObjectStoreX.put(store, "file.txt", "data", mode: :create, idempotency_key: "a")
+
+      assert {:error, :already_exists} =
+               ObjectStoreX.put(store, "file.txt", "data", mode: :create, idempotency_key: "b")
+    end
+
+    test "a stale update retried with the same key succeeds", %{store: store} do
+      {:ok, meta} = ObjectStoreX.put(store, "file.txt", "v1", mode: :create)
+      opts = [mode: {:update, meta}, idempotency_key: "update-1"]
+
+      assert {:ok, updated} = ObjectStoreX.put(store, "file.txt", "v2", opts)
+      assert {:ok, ^updated} = ObjectStoreX.put(store, "file.txt", "v2", opts)
+      assert {:ok, "v2"} = ObjectStoreX.get(store, "file.txt")
+    end
+
+    test "true derives the key from path and data", %{store: store} do
+      opts = [mode: :create, idempotency_key: true]
+
+      assert {:ok, _} = ObjectStoreX.put(store, "file.txt", "data", opts)
+      assert {:ok, _} = ObjectStoreX.put(store, "file.txt", "data", opts)
+      assert {:error, :already_exists} = ObjectStoreX.put(store, "file.txt", "other", opts)
+    end
+
+    test "keeps user metadata alongside the key", %{store: store} do
+      {:ok, _} =
+        ObjectStoreX.put(store, "file.txt", "data",
+          metadata: %{"owner" => "alice"},
+          idempotency_key: "key-1"
+        )
+
+      assert {:ok, meta} = ObjectStoreX.head(store, "file.txt")
+      assert meta[:metadata]["owner"] == "alice"
+    end
+
+    test "also returns a requested checksum", %{store: store} do
+      opts = [mode: :create, idempotency_key: "key-1", checksum: :sha256]
+      checksum = :crypto.hash(:sha256, "data") |> Base.encode16(case: :lower)
+
+      assert {:ok, %{checksum: ^checksum} = first} =
+               ObjectStoreX.put(store, "file.txt", "data", opts)
+
+      assert {:ok, ^first} = ObjectStoreX.put(store, "file.txt", "data", opts)
+    end
+  end
+end
@@ -0,0 +1,144 @@
+defmodule ObjectStoreX.IncompleteUploadsTest do
+  use ExUnit.Case, async: true
+
+  alias ObjectStoreX.FakeS3
+
+  describe "list_incomplete_uploads/3 and abort_incomplete_uploads/4" do
+    test "are not supported by stores without an uploads API" do
+      {:ok, store} = ObjectStoreX.new(:memory)
+
+      assert {:error, :not_supported} = ObjectStoreX.list_incomplete_uploads(store, "")
+      assert {:error, :not_supported} = ObjectStoreX.abort_incomplete_uploads(store, "", 3600)
+    end
+
+    test "are not supported by derived stores" do
+      {:ok, store} = ObjectStoreX.new(:s3, bucket: "test", region: "us-east-1")
+      {:ok, child} = ObjectStoreX.derive(store, prefix: "tenant/")
+
+      assert {:error, :not_supported} = ObjectStoreX.list_incomplete_uploads(child)
+    end
+
+    test "reject invalid ages" do
+      {:ok, store} = ObjectStoreX.new(:memory)
+      assert {:error, _} = ObjectStoreX.abort_incomplete_uploads(store, "", -1)
+    end
+  end
+
+  describe "list_incomplete_uploads/3 on S3" do
+    test "decodes the uploads under the prefix" do
+      page =
+        uploads_page(
+          upload("videos/a.mp4", "u1", "2024-01-01T00:00:00.000Z") <>
+            upload("videos/b.mp4", "u2", "2024-01-02T12:30:00.000Z"),
+          nil
+        )
+
+      store = uploads_store([{"200 OK", [], page}])
+
+      assert {:ok, [first, second]} = ObjectStoreX.list_incomplete_uploads(store, "videos")
+      assert %{location: "videos/a.mp4", upload_id: "u1"} = first
+      assert first.initiated == "2024-01-01 00:00:00 UTC"
+      assert %{location: "videos/b.mp4", upload_id: "u2"} = second
+      assert second.initiated == "2024-01-02 12:30:00 UTC"
+
+      assert_receive {:request, %{request_line: request_line}}
+      assert request_line == "GET /test/?uploads&prefix=videos%2F HTTP/1.1"
+    end
+
+    test "follows the key and upload id markers of truncated pages" do
+      first =
+        uploads_page(
+          upload("videos/a.mp4", "u1", "2024-01-01T00:00:00.000Z"),
+          {"videos/a.mp4", "u1"}
+        )
+
+      last = uploads_page(upload("videos/b.mp4", "u2", "2024-01-02T00:00:00.000Z"), nil)
+      store = uploads_store([{"200 OK", [], first}, {"200 OK", [], last}])
+
+      assert {:ok, uploads} = ObjectStoreX.list_incomplete_uploads(store, "videos")
+      assert Enum.map(uploads, & &1.upload_id) == ["u1", "u2"]
+
+      assert_receive {:request, %{request_line: "GET /test/?uploads&prefix=videos%2F HTTP/1.1"}}
+      assert_receive {:request, %{request_line: request_line}}
+
+      assert request_line ==
+               "GET /test/?uploads&prefix=videos%2F&key-marker=videos%2Fa.mp4" <>
+                 "&upload-id-marker=u1 HTTP/1.1"
+    end
+  end
+
+  describe "abort_incomplete_uploads/4 on S3" do
+    test "aborts only the uploads initiated before the cutoff" do
+      recent = DateTime.utc_now() |> DateTime.to_iso8601()
+
+      list =
+        uploads_page(
+          upload("videos/old.mp4", "u1", "2024-01-01T00:00:00.000Z") <>
+            upload("videos/new.mp4", "u2", recent) <>
+            upload("videos/older.mp4", "u3/+", "2023-06-01T00:00:00.000Z"),
+          nil
+        )
+
+      store =
+        uploads_store([
+          {"200 OK", [], list},
+          {"204 No Content", [], ""},
+          {"204 No Content", [], ""}
+        ])
+
+      assert {:ok, aborted} = ObjectStoreX.abort_incomplete_uploads(store, "videos", 3600)
+      assert Enum.map(aborted, & &1.upload_id) == ["u1", "u3/+"]
+
+      assert_receive {:request, %{request_line: "GET /test/?uploads&prefix=videos%2F HTTP/1.1"}}
+
+      assert_receive {:request, %{request_line: old}}
+      assert old == "DELETE /test/videos/old.mp4?uploadId=u1 HTTP/1.1"
+      assert_receive {:request, %{request_line: older}}
+      assert older == "DELETE /test/videos/older.mp4?uploadId=u3%2F%2B HTTP/1.1"
+
+      refute_receive {:request, _}, 100
+    end
+  end
+
+  defp uploads_store(responses) do
+    endpoint = FakeS3.serve(responses)
+    {:ok, store} = ObjectStoreX.new(:s3, FakeS3.options() ++ [endpoint: endpoint])
+    store
+  end
+
+  defp upload(key, upload_id, initiated) do
+    """
+    <Upload>
+      <Key>#{key}</Key>
+      <UploadId>#{upload_id}</UploadId>
+      <Initiated>#{initiated}</Initiated>
+      <StorageClass>STANDARD</StorageClass>
+    </Upload>
+    """
+  end
+
+  # A ListMultipartUploadsResult page, truncated when `{key, upload_id}`
+  # markers are given
+  defp uploads_page(uploads, markers) do
+    next =
+      case markers do
+        {key, upload_id} ->
+          "<NextKeyMarker>#{key}</NextKeyMarker>" <>
+            "<NextUploadIdMarker>#{upload_id}</NextUploadIdMarker>"
+
+        nil ->
+          ""
+      end
+
+    """
+    <?xml version="1.0" encoding="UTF-8"?>
+    <ListMultipartUploadsResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+      <Bucket>test</Bucket>
+      <Prefix>videos/</Prefix>
+      <IsTruncated>#{markers != nil}</IsTruncated>
+      #{next}
+      #{uploads}
+    </ListMultipartUploadsResult>
+    """
+  end
+end
@@ -0,0 +1,74 @@
+defmodule ObjectStoreX.InflightTest do
+  use ExUnit.Case, async: true
+
+  # The registry is global, so tests only look at their own paths
+  defp inflight(path) do
+    Enum.filter(ObjectStoreX.list_inflight_operations(), &(&1.path == path))
+  end
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+    path = "inflight-#{System.unique_integer([:positive])}.bin"
+    :ok = ObjectStoreX.put(store, path, :binary.copy("x", 500))
+    {:ok, store: store, path: path}
+  end
+
+  test "lists running downloads with their bytes", %{store: store, path: path} do
+    # Throttling per byte keeps the download's stream open after its chunk
+    {:ok, slow} = ObjectStoreX.with_throttle(store, get_per_byte: 2_000)
+    task = Task.async(fn -> ObjectStoreX.get(slow, path) end)
+
+    Process.sleep(200)
+    assert [%{id: id, operation: :get, bytes: 500, elapsed_ms: elapsed}] = inflight(path)
+    assert is_integer(id)
+    assert elapsed >= 100
+
+    assert {:ok, _} = Task.await(task, 5_000)
+    assert inflight(path) == []
+  end
+
+  test "finished operations are not listed", %{store: store, path: path} do
+    assert {:ok, _} = ObjectStoreX.get(store, path)
+    assert {:ok, _} = ObjectStoreX.head(store, path)
+    assert inflight(path) == []
+  end
+
+  test "running operations have distinct ids", %{store: store, path: path} do
+    {:ok, slow} = ObjectStoreX.with_throttle(store, get_per_byte: 2_000)
+    tasks = for _ <- 1..2, do: Task.async(fn -> ObjectStoreX.get(slow, path) end)
+
+    Process.sleep(200)
+    assert [%{id: first}, %{id: second}] = inflight(path)
+    assert first < second
+
+    Enum.each(tasks, &Task.await(&1, 5_000))
+  end
+
+  test "lists requests of the S3 API client", %{path: path} do
+    # Accept the connection but never answer it
+    {:ok, listen} = :gen_tcp.listen(0, [:binary, active: false, reuseaddr: true])
+    {:ok, port} = :inet.port(listen)
+
+    spawn_link(fn ->
+      {:ok, _socket} = :gen_tcp.accept(listen)
+      Process.sleep(:infinity)
+    end)
+
+    {:ok, store} =
+      ObjectStoreX.new(:s3,
+        bucket: "test",
+        region: "us-east-1",
+        access_key_id: "key",
+        secret_access_key: "secret",
+        endpoint: "http://127.0.0.1:#{port}",
+        allow_http: true
+      )
+
+    task = Task.async(fn -> ObjectStoreX.get_tags(store, path) end)
+
+    Process.sleep(200)
+    assert [%{operation: :s3_request}] = inflight(path)
+
+    Task.shutdown(task, :brutal_kill)
+  end
+end
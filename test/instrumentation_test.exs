@@ -0,0 +1,63 @@
+defmodule ObjectStoreX.InstrumentationTest do
+  # Metrics are global, so tests in this module can't run concurrently
+  use ExUnit.Case, async: false
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+    ObjectStoreX.reset_metrics()
+    {:ok, store: store}
+  end
+
+  describe "with_instrumentation/2" do
+    test "sends an event for every call", %{store: store} do
+      {:ok, store} = ObjectStoreX.with_instrumentation(store, pid: self())
+
+      :ok = ObjectStoreX.put(store, "file.txt", "data")
+      assert_receive {:objectstorex_event, :put, %{bytes: 4, duration_us: _}, metadata}
+      assert metadata == %{path: "file.txt", result: :ok}
+
+      {:ok, "data"} = ObjectStoreX.get(store, "file.txt")
+      assert_receive {:objectstorex_event, :get, %{bytes: 4}, %{result: :ok}}
+
+      {:error, :not_found} = ObjectStoreX.head(store, "missing.txt")
+      assert_receive {:objectstorex_event, :head, _, %{path: "missing.txt", result: :not_found}}
+    end
+
+    test "records listings once consumed", %{store: store} do
+      {:ok, store} = ObjectStoreX.with_instrumentation(store, pid: self())
+      :ok = ObjectStoreX.put(store, "dir/a.txt", "a")
+
+      assert [_] = store |> ObjectStoreX.Stream.list_stream(prefix: "dir") |> Enum.to_list()
+      assert_receive {:objectstorex_event, :list, _, %{path: "dir", result: :ok}}
+    end
+
+    test "doesn't instrument the parent store", %{store: store} do
+      {:ok, _instrumented} = ObjectStoreX.with_instrumentation(store, pid: self())
+
+      :ok = ObjectStoreX.put(store, "file.txt", "data")
+      refute_receive {:objectstorex_event, _, _, _}, 100
+    end
+  end
+
+  describe "get_metrics/0" do
+    test "sums calls per operation", %{store: store} do
+      {:ok, store} = ObjectStoreX.with_instrumentation(store)
+
+      :ok = ObjectStoreX.put(store, "a.txt", "aa")
+      :ok = ObjectStoreX.put(store, "b.txt", "bbb")
+      {:error, :not_found} = ObjectStoreX.get(store, "missing.txt")
+
+      metrics = ObjectStoreX.get_metrics()
+      assert %{count: 2, errors: 0, bytes: 5, duration_us: _} = metrics.put
+      assert %{count: 1, errors: 1, bytes: 0} = metrics.get
+    end
+
+    test "reset_metrics/0 clears the counters", %{store: store} do
+      {:ok, store} = ObjectStoreX.with_instrumentation(store)
+      :ok = ObjectStoreX.put(store, "file.txt", "data")
+
+      assert :ok = ObjectStoreX.reset_metrics()
+      assert ObjectStoreX.get_metrics() == %{}
+    end
+  end
+end
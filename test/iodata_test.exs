@@ -0,0 +1,62 @@
+defmodule ObjectStoreX.IodataTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+    %{store: store}
+  end
+
+  @iodata ["hello", [?,, ?\s], ["wor", ["ld" | "!"]], []]
+
+  describe "put/4 with iodata" do
+    test "uploads nested iolists", %{store: store} do
+      assert :ok = ObjectStoreX.put(store, "file.txt", @iodata)
+      assert {:ok, "hello, world!"} = ObjectStoreX.get(store, "file.txt")
+    end
+
+    test "uploads empty iolists", %{store: store} do
+      assert :ok = ObjectStoreX.put(store, "empty.txt", [[], ""])
+      assert {:ok, ""} = ObjectStoreX.get(store, "empty.txt")
+    end
+
+    test "uploads iodata with options", %{store: store} do
+      assert {:ok, %{etag: _}} = ObjectStoreX.put(store, "create.txt", @iodata, mode: :create)
+
+      assert {:ok, _} =
+               ObjectStoreX.put(store, "typed.txt", @iodata, content_type: "text/plain")
+
+      assert {:ok, %{checksum: checksum}} =
+               ObjectStoreX.put(store, "summed.txt", @iodata, checksum: :sha256)
+
+      assert checksum ==
+               :crypto.hash(:sha256, "hello, world!") |> Base.encode16(case: :lower)
+
+      for path <- ["create.txt", "typed.txt", "summed.txt"] do
+        assert {:ok, "hello, world!"} = ObjectStoreX.get(store, path)
+      end
+    end
+
+    test "uploads deeply left-nested iolists", %{store: store} do
+      data = Enum.reduce(1..1_000_000, [], fn _, acc -> [acc, "x"] end)
+
+      assert :ok = ObjectStoreX.put(store, "nested.txt", data)
+      assert {:ok, :binary.copy("x", 1_000_000)} == ObjectStoreX.get(store, "nested.txt")
+    end
+
+    test "rejects data that isn't iodata", %{store: store} do
+      assert {:error, _} = ObjectStoreX.put(store, "file.txt", ?a)
+      assert {:error, _} = ObjectStoreX.put(store, "file.txt", [:atom])
+      assert {:error, _} = ObjectStoreX.put(store, "file.txt", [256])
+      assert {:error, _} = ObjectStoreX.put(store, "file.txt", ["a" | :tail])
+    end
+  end
+
+  describe "Stream.upload/4 with iodata" do
+    test "uploads iodata chunks", %{store: store} do
+      chunks = Stream.map(1..3, fn i -> ["line ", Integer.to_string(i), ?\n] end)
+
+      assert :ok = ObjectStoreX.Stream.upload(chunks, store, "lines.txt")
+      assert {:ok, "line 1\nline 2\nline 3\n"} = ObjectStoreX.get(store, "lines.txt")
+    end
+  end
+end
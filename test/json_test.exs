@@ -0,0 +1,120 @@
+defmodule ObjectStoreX.JsonTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+    %{store: store}
+  end
+
+  describe "get_json/3 and put_json/4" do
+    test "round trip documents", %{store: store} do
+      document = %{
+        "name" => "app",
+        "replicas" => 3,
+        "ratio" => 0.5,
+        "enabled" => true,
+        "owner" => nil,
+        "tags" => ["a", "b"],
+        "limits" => %{"cpu" => 2}
+      }
+
+      assert {:ok, %{etag: etag}} = ObjectStoreX.put_json(store, "config.json", document)
+      assert {:ok, ^document, ^etag} = ObjectStoreX.get_json(store, "config.json")
+
+      {:ok, data} = ObjectStoreX.get(store, "config.json")
+      assert Jason.decode!(data) == document
+    end
+
+    test "encode atoms and atom keys as strings", %{store: store} do
+      {:ok, _} = ObjectStoreX.put_json(store, "doc.json", %{mode: :maintenance, 1 => "one"})
+
+      assert {:ok, %{"mode" => "maintenance", "1" => "one"}, _} =
+               ObjectStoreX.get_json(store, "doc.json")
+    end
+
+    test "reject values that aren't JSON", %{store: store} do
+      assert {:error, _} = ObjectStoreX.put_json(store, "doc.json", %{pid: self()})
+      assert {:error, _} = ObjectStoreX.put_json(store, "doc.json", {:tuple})
+    end
+
+    test "return :invalid_json for malformed documents", %{store: store} do
+      :ok = ObjectStoreX.put(store, "broken.json", "{\"key\":")
+
+      assert {:error, :invalid_json} = ObjectStoreX.get_json(store, "broken.json")
+      assert {:error, :not_found} = ObjectStoreX.get_json(store, "missing.json")
+    end
+
+    test "only overwrite unchanged documents with :update", %{store: store} do
+      {:ok, _} = ObjectStoreX.put_json(store, "doc.json", %{"v" => 1})
+      {:ok, _, etag} = ObjectStoreX.get_json(store, "doc.json")
+      {:ok, _} = ObjectStoreX.put_json(store, "doc.json", %{"v" => 2})
+
+      assert {:error, :precondition_failed} =
+               ObjectStoreX.put_json(store, "doc.json", %{"v" => 3},
+                 mode: {:update, %{etag: etag}}
+               )
+    end
+  end
+
+  describe "merge_json/4" do
+    test "create missing documents from the patch", %{store: store} do
+      assert {:ok, %{"a" => 1}, etag} = ObjectStoreX.merge_json(store, "doc.json", %{a: 1})
+      assert {:ok, %{"a" => 1}, ^etag} = ObjectStoreX.get_json(store, "doc.json")
+    end
+
+    test "apply JSON Merge Patches", %{store: store} do
+      {:ok, _} =
+        ObjectStoreX.put_json(store, "doc.json", %{
+          "title" => "Hello",
+          "author" => %{"given" => "John", "family" => "Doe"},
+          "tags" => ["example", "sample"]
+        })
+
+      patch = %{"title" => "Hi", "author" => %{"family" => nil}, "tags" => ["example"]}
+
+      assert {:ok, merged, _} = ObjectStoreX.merge_json(store, "doc.json", patch)
+
+      assert merged == %{
+               "title" => "Hi",
+               "author" => %{"given" => "John"},
+               "tags" => ["example"]
+             }
+
+      assert {:ok, ^merged, _} = ObjectStoreX.get_json(store, "doc.json")
+    end
+
+    test "doesn't lose concurrent merges", %{store: store} do
+      merge = fn i ->
+        ObjectStoreX.merge_json(store, "doc.json", %{"key#{i}" => i}, max_attempts: 100)
+      end
+
+      1..20
+      |> Task.async_stream(merge, max_concurrency: 10)
+      |> Enum.each(fn {:ok, result} -> assert {:ok, _, _} = result end)
+
+      {:ok, document, _} = ObjectStoreX.get_json(store, "doc.json")
+      assert map_size(document) == 20
+    end
+
+    test "merge without CAS on stores without conditional puts" do
+      name = "objectstorex_json_#{System.unique_integer([:positive])}"
+      dir = Path.join(System.tmp_dir!(), name)
+      File.mkdir_p!(dir)
+      on_exit(fn -> File.rm_rf!(dir) end)
+
+      {:ok, store} = ObjectStoreX.new(:local, path: dir)
+      {:ok, _} = ObjectStoreX.put_json(store, "doc.json", %{"a" => 1})
+
+      assert {:error, :not_supported} = ObjectStoreX.merge_json(store, "doc.json", %{b: 2})
+
+      assert {:ok, %{"a" => 1, "b" => 2}, _} =
+               ObjectStoreX.merge_json(store, "doc.json", %{b: 2}, cas: false)
+    end
+
+    test "return :invalid_json for malformed documents", %{store: store} do
+      :ok = ObjectStoreX.put(store, "broken.json", "not json")
+
+      assert {:error, :invalid_json} = ObjectStoreX.merge_json(store, "broken.json", %{a: 1})
+    end
+  end
+end
@@ -0,0 +1,65 @@
+defmodule ObjectStoreX.LargeSizesTest do
+  use ExUnit.Case, async: true
+
+  @five_gib 5 * 1024 * 1024 * 1024
+  @four_gib 4 * 1024 * 1024 * 1024
+
+  # Every object is 5 GiB; ranges are generated, so nothing large is allocated
+  defmodule HugeBackend do
+    @behaviour ObjectStoreX.Backend
+
+    @impl true
+    def init(test_pid), do: {:ok, test_pid}
+
+    @impl true
+    def put(_path, _data, _mode, _test_pid), do: {:error, :not_supported}
+
+    @impl true
+    def get(_path, _test_pid), do: {:error, :not_supported}
+
+    @impl true
+    def get_range(_path, start, stop, test_pid) do
+      send(test_pid, {:range, start, stop})
+      {:ok, :binary.copy("x", stop - start)}
+    end
+
+    @impl true
+    def head(path, _test_pid), do: {:ok, %{location: path, size: 5 * 1024 * 1024 * 1024}}
+
+    @impl true
+    def delete(_path, _test_pid), do: :ok
+
+    @impl true
+    def list(_prefix, _test_pid) do
+      {:ok, [%{location: "huge.bin", size: 5 * 1024 * 1024 * 1024}]}
+    end
+  end
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:custom, backend: HugeBackend, arg: self())
+    {:ok, store: store}
+  end
+
+  test "head reports sizes above 4 GiB", %{store: store} do
+    assert {:ok, %{size: @five_gib}} = ObjectStoreX.head(store, "huge.bin")
+
+    assert {:ok, [{"huge.bin", {:ok, %{size: @five_gib}}}]} =
+             ObjectStoreX.head_many(store, ["huge.bin"])
+  end
+
+  test "ranged gets past 4 GiB keep their offsets", %{store: store} do
+    start = @four_gib + 10
+
+    assert {:ok, "xxxxx", meta} = ObjectStoreX.get(store, "huge.bin", range: {start, start + 5})
+    assert meta.range == {start, start + 5}
+    assert meta.total_size == @five_gib
+    assert_received {:range, ^start, _}
+  end
+
+  test "get_ranges past 4 GiB keep their offsets", %{store: store} do
+    start = @five_gib - 3
+
+    assert {:ok, ["xxx"]} = ObjectStoreX.get_ranges(store, "huge.bin", [{start, @five_gib}])
+    assert_received {:range, ^start, @five_gib}
+  end
+end
@@ -0,0 +1,35 @@
+defmodule ObjectStoreX.LastModifiedTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+    before = DateTime.utc_now()
+    :ok = ObjectStoreX.put(store, "data/file.txt", "data")
+    %{store: store, before: before, after: DateTime.utc_now()}
+  end
+
+  defp assert_written(meta, before, after_put) do
+    assert {:ok, modified} = DateTime.from_unix(meta.last_modified_us, :microsecond)
+    assert DateTime.compare(modified, before) != :lt
+    assert DateTime.compare(modified, after_put) != :gt
+  end
+
+  describe ":last_modified_us" do
+    test "is returned by head/3 and get/3", %{store: store, before: before, after: after_put} do
+      {:ok, meta} = ObjectStoreX.head(store, "data/file.txt")
+      assert is_binary(meta.last_modified)
+      assert_written(meta, before, after_put)
+
+      {:ok, "data", get_meta} = ObjectStoreX.get(store, "data/file.txt", if_none_match: "\"x\"")
+      assert get_meta.last_modified_us == meta.last_modified_us
+    end
+
+    test "is returned by listings", %{store: store, before: before, after: after_put} do
+      [meta] = ObjectStoreX.Stream.list_stream(store, prefix: "data/") |> Enum.to_list()
+      assert_written(meta, before, after_put)
+
+      {:ok, [meta], _prefixes} = ObjectStoreX.list_with_delimiter(store, prefix: "data/")
+      assert_written(meta, before, after_put)
+    end
+  end
+end
@@ -0,0 +1,33 @@
+defmodule ObjectStoreX.ListModifiedSinceTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+    :ok = ObjectStoreX.put(store, "events/old.json", "{}")
+    {:ok, store: store}
+  end
+
+  describe "list_modified_since/4" do
+    test "returns objects modified at or after a time, oldest first", %{store: store} do
+      past = DateTime.add(DateTime.utc_now(), -3600)
+      :ok = ObjectStoreX.put(store, "events/b.json", "{}")
+      :ok = ObjectStoreX.put(store, "events/a.json", "{}")
+
+      assert {:ok, objects} = ObjectStoreX.list_modified_since(store, "events/", past)
+      assert length(objects) == 3
+      assert hd(objects).location == "events/old.json"
+    end
+
+    test "leaves out older objects", %{store: store} do
+      future = DateTime.add(DateTime.utc_now(), 3600)
+      assert {:ok, []} = ObjectStoreX.list_modified_since(store, "events/", future)
+    end
+
+    test "accepts Unix timestamps", %{store: store} do
+      now = System.os_time(:second)
+
+      assert {:ok, [%{location: "events/old.json"}]} =
+               ObjectStoreX.list_modified_since(store, nil, now - 60)
+    end
+  end
+end
@@ -243,4 +243,130 @@ defmodule ObjectStoreX.ListTest do
              end)
     end
   end
+
+  describe ":page_size" do
+    setup do
+      {:ok, store} = ObjectStoreX.new(:memory)
+
+      for i <- 1..25, do: :ok = ObjectStoreX.put(store, "paged/#{i}.txt", "data")
+      :ok = ObjectStoreX.put(store, "paged/nested/file.txt", "data")
+
+      {:ok, store: store}
+    end
+
+    test "is ignored by stores that don't page", %{store: store} do
+      listed =
+        ObjectStoreX.Stream.list_stream(store, prefix: "paged", page_size: 10)
+        |> Enum.to_list()
+
+      assert length(listed) == 26
+
+      assert {:ok, objects, ["paged/nested"]} =
+               ObjectStoreX.list_with_delimiter(store, prefix: "paged", page_size: 10)
+
+      assert length(objects) == 25
+    end
+
+    test "must be positive", %{store: store} do
+      assert {:error, _} = ObjectStoreX.list_with_delimiter(store, page_size: 0)
+
+      assert_raise ArgumentError, fn ->
+        ObjectStoreX.Stream.list_stream(store, page_size: 0) |> Enum.to_list()
+      end
+    end
+  end
+
+  describe ":batch_size" do
+    setup do
+      {:ok, store} = ObjectStoreX.new(:memory)
+      for i <- 1..25, do: :ok = ObjectStoreX.put(store, "batched/#{i}.txt", "data")
+      {:ok, store: store}
+    end
+
+    test "delivers objects in batches", %{store: store} do
+      {:ok, list_id} =
+        ObjectStoreX.Native.start_list_stream(store, "batched", nil, 10, nil, self())
+
+      assert_receive {:objects, ^list_id, first}
+      assert_receive {:objects, ^list_id, second}
+      assert_receive {:objects, ^list_id, last}
+      assert_receive {:done, ^list_id}
+
+      assert Enum.map([first, second, last], &length/1) == [10, 10, 5]
+      refute_received {:object, ^list_id, _}
+    end
+
+    test "streams the same objects as unbatched listings", %{store: store} do
+      unbatched = ObjectStoreX.Stream.list_stream(store, prefix: "batched") |> Enum.to_list()
+
+      for batch_size <- [1, 7, 25, 100] do
+        batched =
+          ObjectStoreX.Stream.list_stream(store, prefix: "batched", batch_size: batch_size)
+          |> Enum.to_list()
+
+        assert Enum.sort_by(batched, & &1.location) == Enum.sort_by(unbatched, & &1.location)
+      end
+    end
+
+    test "must be positive", %{store: store} do
+      assert_raise ArgumentError, fn ->
+        ObjectStoreX.Stream.list_stream(store, batch_size: 0) |> Enum.to_list()
+      end
+    end
+  end
+
+  describe "list stream filters" do
+    setup do
+      {:ok, store} = ObjectStoreX.new(:memory)
+
+      :ok = ObjectStoreX.put(store, "logs/2026/01/app.log.gz", String.duplicate("x", 100))
+      :ok = ObjectStoreX.put(store, "logs/2026/01/app.log", "x")
+      :ok = ObjectStoreX.put(store, "logs/2026/big.gz", String.duplicate("x", 1000))
+      :ok = ObjectStoreX.put(store, "logs/top.gz", "x")
+      :ok = ObjectStoreX.put(store, "data/report.json", "{}")
+
+      {:ok, store: store}
+    end
+
+    defp locations(store, opts) do
+      store
+      |> ObjectStoreX.Stream.list_stream(opts)
+      |> Enum.map(& &1.location)
+      |> Enum.sort()
+    end
+
+    test "matches globs against the whole location", %{store: store} do
+      assert locations(store, glob: "logs/*.gz") == ["logs/top.gz"]
+
+      assert locations(store, glob: "logs/**/*.gz") ==
+               ["logs/2026/01/app.log.gz", "logs/2026/big.gz", "logs/top.gz"]
+
+      assert locations(store, glob: "data/report.???*") == ["data/report.json"]
+    end
+
+    test "matches regular expressions and suffixes", %{store: store} do
+      assert locations(store, regex: "/\\d{2}/") ==
+               ["logs/2026/01/app.log", "logs/2026/01/app.log.gz"]
+
+      assert locations(store, suffix: ".json") == ["data/report.json"]
+    end
+
+    test "combines size and time filters", %{store: store} do
+      assert locations(store, suffix: ".gz", min_size: 50, max_size: 500) ==
+               ["logs/2026/01/app.log.gz"]
+
+      past = DateTime.add(DateTime.utc_now(), -3600)
+      assert length(locations(store, modified_after: past)) == 5
+      assert locations(store, modified_before: past) == []
+    end
+
+    test "works with batches", %{store: store} do
+      assert locations(store, prefix: "logs", suffix: ".gz", batch_size: 2) ==
+               ["logs/2026/01/app.log.gz", "logs/2026/big.gz", "logs/top.gz"]
+    end
+
+    test "rejects invalid patterns", %{store: store} do
+      assert_raise ErlangError, fn -> locations(store, regex: "(") end
+    end
+  end
 end
@@ -0,0 +1,94 @@
+defmodule ObjectStoreX.LocalCopyTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    root = Path.join(System.tmp_dir!(), "objectstorex_local_copy_#{:rand.uniform(1_000_000)}")
+    File.mkdir_p!(root)
+    on_exit(fn -> File.rm_rf!(root) end)
+
+    {:ok, root: root}
+  end
+
+  for strategy <- [:auto, :reflink, :copy] do
+    describe "with copy_strategy: #{inspect(strategy)}" do
+      setup %{root: root} do
+        {:ok, store} = ObjectStoreX.new(:local, path: root, copy_strategy: unquote(strategy))
+        {:ok, store: store}
+      end
+
+      test "copies stay independent of their source", %{store: store} do
+        :ok = ObjectStoreX.put(store, "source.txt", "original")
+        :ok = ObjectStoreX.copy(store, "source.txt", "nested/copy.txt")
+
+        :ok = ObjectStoreX.put(store, "source.txt", "changed")
+
+        assert {:ok, "original"} = ObjectStoreX.get(store, "nested/copy.txt")
+        assert {:ok, "changed"} = ObjectStoreX.get(store, "source.txt")
+      end
+
+      test "copy replaces an existing destination", %{store: store} do
+        :ok = ObjectStoreX.put(store, "a.txt", "a")
+        :ok = ObjectStoreX.put(store, "b.txt", "b")
+
+        assert :ok = ObjectStoreX.copy(store, "a.txt", "b.txt")
+        assert {:ok, "a"} = ObjectStoreX.get(store, "b.txt")
+      end
+
+      test "copy_if_not_exists keeps an existing destination", %{store: store} do
+        :ok = ObjectStoreX.put(store, "a.txt", "a")
+        :ok = ObjectStoreX.put(store, "b.txt", "b")
+
+        assert {:error, :already_exists} =
+                 ObjectStoreX.copy_if_not_exists(store, "a.txt", "b.txt")
+        assert {:ok, "b"} = ObjectStoreX.get(store, "b.txt")
+
+        assert :ok = ObjectStoreX.copy_if_not_exists(store, "a.txt", "c.txt")
+        assert {:ok, "a"} = ObjectStoreX.get(store, "c.txt")
+      end
+    end
+  end
+
+  test "copy_prefix snapshots a directory tree", %{root: root} do
+    {:ok, store} = ObjectStoreX.new(:local, path: root)
+
+    for i <- 1..20, do: :ok = ObjectStoreX.put(store, "data/#{rem(i, 4)}/#{i}.txt", "#{i}")
+
+    assert {:ok, %{copied: 20, errors: []}} =
+             ObjectStoreX.copy_prefix(store, "data", "snapshots/1")
+
+    :ok = ObjectStoreX.put(store, "data/1/1.txt", "changed")
+
+    assert {:ok, "1"} = ObjectStoreX.get(store, "snapshots/1/1/1.txt")
+    assert {:ok, "20"} = ObjectStoreX.get(store, "snapshots/1/0/20.txt")
+  end
+
+  test "leaves no staged files behind", %{root: root} do
+    {:ok, store} = ObjectStoreX.new(:local, path: root)
+    :ok = ObjectStoreX.put(store, "a.txt", "a")
+    :ok = ObjectStoreX.put(store, "b.txt", "b")
+
+    :ok = ObjectStoreX.copy(store, "a.txt", "c.txt")
+    {:error, :already_exists} = ObjectStoreX.copy_if_not_exists(store, "a.txt", "b.txt")
+
+    assert Enum.sort(File.ls!(root)) == ["a.txt", "b.txt", "c.txt"]
+  end
+
+  test "copying a missing object returns not_found", %{root: root} do
+    {:ok, store} = ObjectStoreX.new(:local, path: root)
+
+    assert {:error, :not_found} = ObjectStoreX.copy(store, "missing.txt", "copy.txt")
+  end
+
+  test "keeps configured modes on copies", %{root: root} do
+    {:ok, store} = ObjectStoreX.new(:local, path: root, file_mode: 0o640)
+    :ok = ObjectStoreX.put(store, "a.txt", "a")
+    :ok = ObjectStoreX.copy(store, "a.txt", "b.txt")
+
+    assert %{mode: mode} = File.stat!(Path.join(root, "b.txt"))
+    assert Bitwise.band(mode, 0o777) == 0o640
+  end
+
+  test "rejects unknown strategies", %{root: root} do
+    assert {:error, _} = ObjectStoreX.new(:local, path: root, copy_strategy: :symlink)
+  end
+end
@@ -0,0 +1,72 @@
+defmodule ObjectStoreX.LocalOptionsTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    root = Path.join(System.tmp_dir!(), "objectstorex_local_#{:rand.uniform(1_000_000)}")
+    on_exit(fn -> File.rm_rf!(root) end)
+
+    {:ok, root: root}
+  end
+
+  describe ":create_path" do
+    test "fails for a missing root by default", %{root: root} do
+      assert {:error, message} = ObjectStoreX.new(:local, path: root)
+      assert message =~ "Local FS error"
+    end
+
+    test "creates a missing root", %{root: root} do
+      path = Path.join(root, "nested/store")
+
+      assert {:ok, store} = ObjectStoreX.new(:local, path: path, create_path: true)
+      assert File.dir?(path)
+
+      :ok = ObjectStoreX.put(store, "file.txt", "data")
+      assert File.read!(Path.join(path, "file.txt")) == "data"
+    end
+  end
+
+  describe ":automatic_cleanup" do
+    setup %{root: root} do
+      File.mkdir_p!(root)
+      :ok
+    end
+
+    test "keeps empty directories by default", %{root: root} do
+      {:ok, store} = ObjectStoreX.new(:local, path: root)
+
+      :ok = ObjectStoreX.put(store, "a/b/file.txt", "data")
+      :ok = ObjectStoreX.delete(store, "a/b/file.txt")
+
+      assert File.dir?(Path.join(root, "a/b"))
+    end
+
+    test "removes directories left empty by deletes", %{root: root} do
+      {:ok, store} = ObjectStoreX.new(:local, path: root, automatic_cleanup: true)
+
+      :ok = ObjectStoreX.put(store, "a/b/file.txt", "data")
+      :ok = ObjectStoreX.put(store, "a/keep.txt", "data")
+      :ok = ObjectStoreX.delete(store, "a/b/file.txt")
+
+      refute File.exists?(Path.join(root, "a/b"))
+      assert File.exists?(Path.join(root, "a/keep.txt"))
+      assert File.dir?(root)
+    end
+  end
+
+  describe ":no_prefix" do
+    test "addresses the whole filesystem", %{root: root} do
+      File.mkdir_p!(root)
+      File.write!(Path.join(root, "file.txt"), "absolute")
+
+      assert {:ok, store} = ObjectStoreX.new(:local, no_prefix: true)
+
+      location = root |> Path.join("file.txt") |> Path.relative_to("/")
+      assert {:ok, "absolute"} = ObjectStoreX.get(store, location)
+    end
+
+    test "can't be combined with :path", %{root: root} do
+      assert {:error, message} = ObjectStoreX.new(:local, path: root, no_prefix: true)
+      assert message =~ "mutually exclusive"
+    end
+  end
+end
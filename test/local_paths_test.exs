@@ -0,0 +1,56 @@
+defmodule ObjectStoreX.LocalPathsTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    root = Path.join(System.tmp_dir!(), "objectstorex_paths_#{:rand.uniform(1_000_000)}")
+    File.mkdir_p!(root)
+    on_exit(fn -> File.rm_rf!(root) end)
+
+    {:ok, root: root}
+  end
+
+  test "accepts roots with trailing separators", %{root: root} do
+    {:ok, store} = ObjectStoreX.new(:local, path: root <> "/")
+
+    :ok = ObjectStoreX.put(store, "file.txt", "data")
+    assert File.read!(Path.join(root, "file.txt")) == "data"
+  end
+
+  test "resolves relative roots against the current directory", %{root: root} do
+    relative = Path.relative_to(root, File.cwd!())
+
+    if relative != root do
+      {:ok, store} = ObjectStoreX.new(:local, path: relative)
+
+      :ok = ObjectStoreX.put(store, "file.txt", "data")
+      assert File.read!(Path.join(root, "file.txt")) == "data"
+    end
+  end
+
+  test "handles deep prefixes beyond the Windows MAX_PATH", %{root: root} do
+    {:ok, store} = ObjectStoreX.new(:local, path: root)
+
+    prefix = Enum.map_join(1..20, "/", fn i -> "level-#{i}-#{String.duplicate("x", 12)}" end)
+    location = prefix <> "/file.txt"
+    assert String.length(Path.join(root, location)) > 260
+
+    :ok = ObjectStoreX.put(store, location, "deep")
+    assert {:ok, "deep"} = ObjectStoreX.get(store, location)
+
+    assert [%{location: ^location}] =
+             ObjectStoreX.Stream.list_stream(store, prefix: "level-1-xxxxxxxxxxxx/")
+             |> Enum.to_list()
+  end
+
+  test "returns locations with forward slashes", %{root: root} do
+    {:ok, store} = ObjectStoreX.new(:local, path: root)
+
+    :ok = ObjectStoreX.put(store, "a/b/c.txt", "data")
+
+    locations =
+      ObjectStoreX.Stream.list_stream(store)
+      |> Enum.map(& &1.location)
+
+    assert locations == ["a/b/c.txt"]
+  end
+end
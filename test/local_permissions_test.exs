@@ -0,0 +1,62 @@
+defmodule ObjectStoreX.LocalPermissionsTest do
+  use ExUnit.Case, async: true
+
+  import Bitwise
+
+  setup do
+    root = Path.join(System.tmp_dir!(), "objectstorex_modes_#{:rand.uniform(1_000_000)}")
+    File.mkdir_p!(root)
+    on_exit(fn -> File.rm_rf!(root) end)
+
+    {:ok, root: root}
+  end
+
+  defp mode(path), do: File.stat!(path).mode &&& 0o777
+
+  describe "file and directory modes" do
+    test "applies file mode to written objects", %{root: root} do
+      {:ok, store} = ObjectStoreX.new(:local, path: root, file_mode: 0o640)
+
+      :ok = ObjectStoreX.put(store, "report.csv", "a,b")
+
+      assert mode(Path.join(root, "report.csv")) == 0o640
+    end
+
+    test "applies dir mode to created directories", %{root: root} do
+      {:ok, store} = ObjectStoreX.new(:local, path: root, file_mode: 0o644, dir_mode: 0o750)
+
+      :ok = ObjectStoreX.put(store, "exports/2025/data.bin", "data")
+
+      assert mode(Path.join(root, "exports")) == 0o750
+      assert mode(Path.join(root, "exports/2025")) == 0o750
+      assert mode(Path.join(root, "exports/2025/data.bin")) == 0o644
+    end
+
+    test "modes are not reduced by the umask", %{root: root} do
+      {:ok, store} = ObjectStoreX.new(:local, path: root, file_mode: 0o666)
+
+      :ok = ObjectStoreX.put(store, "shared.txt", "data")
+
+      assert mode(Path.join(root, "shared.txt")) == 0o666
+    end
+
+    test "applies modes to copies and streamed uploads", %{root: root} do
+      {:ok, store} = ObjectStoreX.new(:local, path: root, file_mode: 0o600)
+
+      :ok = ObjectStoreX.put(store, "source.txt", "data")
+      :ok = ObjectStoreX.copy(store, "source.txt", "copy.txt")
+      assert mode(Path.join(root, "copy.txt")) == 0o600
+
+      :ok = ObjectStoreX.Stream.upload(["part1", "part2"], store, "streamed.txt")
+
+      assert mode(Path.join(root, "streamed.txt")) == 0o600
+    end
+
+    test "stores without modes keep default behaviour", %{root: root} do
+      {:ok, store} = ObjectStoreX.new(:local, path: root)
+
+      :ok = ObjectStoreX.put(store, "plain.txt", "data")
+      assert {:ok, "data"} = ObjectStoreX.get(store, "plain.txt")
+    end
+  end
+end
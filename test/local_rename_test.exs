@@ -0,0 +1,44 @@
+defmodule ObjectStoreX.LocalRenameTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    root = Path.join(System.tmp_dir!(), "objectstorex_rename_#{:rand.uniform(1_000_000)}")
+    File.mkdir_p!(root)
+    on_exit(fn -> File.rm_rf!(root) end)
+
+    {:ok, store} = ObjectStoreX.new(:local, path: root)
+    {:ok, store: store, root: root}
+  end
+
+  test "reports the rename strategy on request", %{store: store} do
+    :ok = ObjectStoreX.put(store, "old.txt", "data")
+
+    assert {:ok, :rename} =
+             ObjectStoreX.rename(store, "old.txt", "new.txt", return_strategy: true)
+
+    assert {:ok, "data"} = ObjectStoreX.get(store, "new.txt")
+    assert {:error, :not_found} = ObjectStoreX.get(store, "old.txt")
+  end
+
+  test "falls back to copy and delete across devices", %{store: store, root: root} do
+    # Link a directory on another filesystem (tmpfs) into the store root
+    other = "/dev/shm/objectstorex_rename_#{:rand.uniform(1_000_000)}"
+
+    if File.dir?("/dev/shm") and File.mkdir_p(other) == :ok do
+      on_exit(fn -> File.rm_rf!(other) end)
+      File.ln_s!(other, Path.join(root, "mounted"))
+
+      cross_device? = File.stat!(root).major_device != File.stat!(other).major_device
+
+      data = :binary.copy("x", 6 * 1024 * 1024)
+      :ok = ObjectStoreX.put(store, "big.bin", data)
+
+      assert {:ok, strategy} =
+               ObjectStoreX.rename(store, "big.bin", "mounted/big.bin", return_strategy: true)
+
+      assert strategy == if(cross_device?, do: :copy_delete, else: :rename)
+      assert File.read!(Path.join(other, "big.bin")) == data
+      assert {:error, :not_found} = ObjectStoreX.get(store, "big.bin")
+    end
+  end
+end
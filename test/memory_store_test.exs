@@ -0,0 +1,43 @@
+defmodule ObjectStoreX.MemoryStoreTest do
+  use ExUnit.Case, async: true
+
+  describe "new(:memory, opts)" do
+    test "seeds the store with initial objects" do
+      {:ok, store} =
+        ObjectStoreX.new(:memory, objects: %{"a.txt" => "alpha", "dir/b.txt" => ["be", "ta"]})
+
+      assert {:ok, "alpha"} = ObjectStoreX.get(store, "a.txt")
+      assert {:ok, "beta"} = ObjectStoreX.get(store, "dir/b.txt")
+    end
+
+    test "unnamed stores are independent" do
+      {:ok, first} = ObjectStoreX.new(:memory, [])
+      {:ok, second} = ObjectStoreX.new(:memory, [])
+
+      :ok = ObjectStoreX.put(first, "file.txt", "data")
+      assert {:error, :not_found} = ObjectStoreX.get(second, "file.txt")
+    end
+
+    test "stores with the same name share contents across processes" do
+      name = "shared-#{System.unique_integer([:positive])}"
+      {:ok, store} = ObjectStoreX.new(:memory, name: name)
+
+      :ok = ObjectStoreX.put(store, "file.txt", "shared")
+
+      task = Task.async(fn -> ObjectStoreX.new(:memory, name: name) end)
+      {:ok, other} = Task.await(task)
+
+      assert {:ok, "shared"} = ObjectStoreX.get(other, "file.txt")
+    end
+
+    test "seeds a named store only when it is created" do
+      name = "seeded-#{System.unique_integer([:positive])}"
+      {:ok, store} = ObjectStoreX.new(:memory, name: name, objects: %{"file.txt" => "seed"})
+
+      :ok = ObjectStoreX.put(store, "file.txt", "changed")
+      {:ok, again} = ObjectStoreX.new(:memory, name: name, objects: %{"file.txt" => "seed"})
+
+      assert {:ok, "changed"} = ObjectStoreX.get(again, "file.txt")
+    end
+  end
+end
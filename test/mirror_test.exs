@@ -0,0 +1,120 @@
+defmodule ObjectStoreX.MirrorTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    {:ok, primary} = ObjectStoreX.new(:memory)
+    {:ok, replica_a} = ObjectStoreX.new(:memory)
+    {:ok, replica_b} = ObjectStoreX.new(:memory)
+    {:ok, primary: primary, replicas: [replica_a, replica_b]}
+  end
+
+  describe "with_mirror/3 with consistency: :all" do
+    test "writes to every store", %{primary: primary, replicas: replicas} do
+      {:ok, mirror} = ObjectStoreX.with_mirror(primary, replicas)
+
+      :ok = ObjectStoreX.put(mirror, "a.txt", "a")
+      :ok = ObjectStoreX.copy(mirror, "a.txt", "b.txt")
+      :ok = ObjectStoreX.rename(mirror, "b.txt", "c.txt")
+
+      for store <- [primary | replicas] do
+        assert {:ok, "a"} = ObjectStoreX.get(store, "a.txt")
+        assert {:error, :not_found} = ObjectStoreX.get(store, "b.txt")
+        assert {:ok, "a"} = ObjectStoreX.get(store, "c.txt")
+      end
+
+      :ok = ObjectStoreX.delete(mirror, "a.txt")
+
+      for store <- [primary | replicas] do
+        assert {:error, :not_found} = ObjectStoreX.get(store, "a.txt")
+      end
+
+      assert :ok = ObjectStoreX.flush_mirror(mirror)
+    end
+
+    test "mirrors streamed uploads", %{primary: primary, replicas: replicas} do
+      {:ok, mirror} = ObjectStoreX.with_mirror(primary, replicas)
+
+      {:ok, stream} = ObjectStoreX.put_stream(mirror, "big.bin")
+      :ok = ObjectStoreX.write_put_stream(stream, "part one, ")
+      :ok = ObjectStoreX.write_put_stream(stream, "part two")
+      {:ok, _} = ObjectStoreX.finish_put_stream(stream)
+
+      for store <- [primary | replicas] do
+        assert {:ok, "part one, part two"} = ObjectStoreX.get(store, "big.bin")
+      end
+    end
+
+    test "checks conditional puts against the primary only", %{
+      primary: primary,
+      replicas: [replica | _] = replicas
+    } do
+      {:ok, mirror} = ObjectStoreX.with_mirror(primary, replicas)
+      :ok = ObjectStoreX.put(replica, "a.txt", "stale")
+
+      assert :ok = ObjectStoreX.put(mirror, "a.txt", "new", mode: :create)
+      assert {:ok, "new"} = ObjectStoreX.get(replica, "a.txt")
+
+      assert {:error, :already_exists} = ObjectStoreX.put(mirror, "a.txt", "x", mode: :create)
+      assert {:ok, "new"} = ObjectStoreX.get(replica, "a.txt")
+    end
+
+    test "returns replica failures", %{primary: primary, replicas: [replica | _]} do
+      {:ok, reader} = ObjectStoreX.derive(replica, read_only: true)
+      {:ok, mirror} = ObjectStoreX.with_mirror(primary, [reader])
+
+      assert {:error, :permission_denied} = ObjectStoreX.put(mirror, "a.txt", "a")
+      # The primary was written before the replica failed
+      assert {:ok, "a"} = ObjectStoreX.get(primary, "a.txt")
+    end
+
+    test "reads from the primary", %{primary: primary, replicas: [replica | _] = replicas} do
+      {:ok, mirror} = ObjectStoreX.with_mirror(primary, replicas)
+      :ok = ObjectStoreX.put(replica, "only-replica.txt", "r")
+
+      assert {:error, :not_found} = ObjectStoreX.get(mirror, "only-replica.txt")
+      assert [] = mirror |> ObjectStoreX.Stream.list_stream() |> Enum.to_list()
+    end
+  end
+
+  describe "with_mirror/3 with consistency: :primary" do
+    test "replicates in the background", %{primary: primary, replicas: replicas} do
+      {:ok, mirror} = ObjectStoreX.with_mirror(primary, replicas, consistency: :primary)
+
+      for i <- 1..20, do: :ok = ObjectStoreX.put(mirror, "a.txt", "v#{i}")
+      :ok = ObjectStoreX.put(mirror, "b.txt", "b")
+      :ok = ObjectStoreX.delete(mirror, "b.txt")
+
+      {:ok, stream} = ObjectStoreX.put_stream(mirror, "big.bin")
+      :ok = ObjectStoreX.write_put_stream(stream, "streamed")
+      {:ok, _} = ObjectStoreX.finish_put_stream(stream)
+
+      assert {:ok, "v20"} = ObjectStoreX.get(primary, "a.txt")
+      assert :ok = ObjectStoreX.flush_mirror(mirror)
+
+      for replica <- replicas do
+        assert {:ok, "v20"} = ObjectStoreX.get(replica, "a.txt")
+        assert {:error, :not_found} = ObjectStoreX.get(replica, "b.txt")
+        assert {:ok, "streamed"} = ObjectStoreX.get(replica, "big.bin")
+      end
+    end
+
+    test "reports replica failures on flush", %{primary: primary, replicas: [replica | _]} do
+      {:ok, reader} = ObjectStoreX.derive(replica, read_only: true)
+      {:ok, mirror} = ObjectStoreX.with_mirror(primary, [reader], consistency: :primary)
+
+      assert :ok = ObjectStoreX.put(mirror, "a.txt", "a")
+      assert {:error, _} = ObjectStoreX.flush_mirror(mirror)
+      # Failures are reported once
+      assert :ok = ObjectStoreX.flush_mirror(mirror)
+    end
+  end
+
+  test "flush_mirror/1 is not supported on other stores", %{primary: primary} do
+    assert {:error, :not_supported} = ObjectStoreX.flush_mirror(primary)
+  end
+
+  test "rejects invalid options", %{primary: primary, replicas: replicas} do
+    assert {:error, _} = ObjectStoreX.with_mirror(primary, [])
+    assert {:error, _} = ObjectStoreX.with_mirror(primary, replicas, consistency: :quorum)
+  end
+end
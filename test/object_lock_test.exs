@@ -0,0 +1,149 @@
+defmodule ObjectStoreX.ObjectLockTest do
+  use ExUnit.Case, async: true
+
+  alias ObjectStoreX.FakeS3
+
+  @until ~U[2030-01-01 00:00:00.000Z]
+
+  describe "put/4" do
+    test "locks the object in the upload request" do
+      store = FakeS3.store("200 OK", [{"etag", "\"abc\""}, {"x-amz-version-id", "v1"}])
+
+      assert {:ok, %{etag: "\"abc\"", version: "v1"}} =
+               ObjectStoreX.put(store, "ledger.csv", "a,b",
+                 retention: {:compliance, @until},
+                 legal_hold: true,
+                 content_type: "text/csv"
+               )
+
+      assert_received {:request, %{request_line: "PUT /test/ledger.csv HTTP/1.1" <> _} = request}
+      assert request.body == "a,b"
+      assert request.headers["x-amz-object-lock-mode"] == "COMPLIANCE"
+      assert request.headers["x-amz-object-lock-retain-until-date"] == "2030-01-01T00:00:00.000Z"
+      assert request.headers["x-amz-object-lock-legal-hold"] == "ON"
+      assert request.headers["content-md5"] == Base.encode64(:crypto.hash(:md5, "a,b"))
+      assert request.headers["content-type"] == "text/csv"
+    end
+
+    test "keeps create mode" do
+      store = FakeS3.store("412 Precondition Failed")
+
+      assert {:error, :already_exists} =
+               ObjectStoreX.put(store, "ledger.csv", "a,b", mode: :create, legal_hold: true)
+
+      assert_received {:request, %{headers: %{"if-none-match" => "*"}}}
+    end
+
+    test "combines with :checksum" do
+      store = FakeS3.store("200 OK", [{"etag", "\"abc\""}])
+      digest = :crypto.hash(:sha256, "a,b")
+      checksum = Base.encode16(digest, case: :lower)
+
+      assert {:ok, %{etag: "\"abc\"", checksum: ^checksum}} =
+               ObjectStoreX.put(store, "ledger.csv", "a,b", legal_hold: true, checksum: :sha256)
+
+      assert_received {:request, request}
+      assert request.headers["x-amz-object-lock-legal-hold"] == "ON"
+      assert request.headers["x-amz-checksum-sha256"] == Base.encode64(digest)
+    end
+
+    test "combines with :idempotency_key" do
+      store = FakeS3.store("200 OK", [{"etag", "\"abc\""}])
+
+      assert {:ok, %{etag: "\"abc\""}} =
+               ObjectStoreX.put(store, "ledger.csv", "a,b",
+                 retention: {:governance, @until},
+                 idempotency_key: "key-1"
+               )
+
+      assert_received {:request, request}
+      assert request.headers["x-amz-object-lock-mode"] == "GOVERNANCE"
+      assert request.headers["x-amz-meta-objectstorex-idempotency-key"] == "key-1"
+    end
+
+    test "is not supported on other providers" do
+      {:ok, store} = ObjectStoreX.new(:memory)
+
+      assert {:error, :not_supported} =
+               ObjectStoreX.put(store, "a.txt", "data", retention: {:governance, @until})
+    end
+  end
+
+  describe "retention" do
+    test "get_retention/3 reads the mode and date" do
+      body =
+        "<Retention><Mode>GOVERNANCE</Mode>" <>
+          "<RetainUntilDate>2030-01-01T00:00:00.000Z</RetainUntilDate></Retention>"
+
+      store = FakeS3.store("200 OK", [], body)
+
+      assert {:ok, {:governance, @until}} = ObjectStoreX.get_retention(store, "a.txt")
+      assert_received {:request, %{request_line: "GET /test/a.txt?retention HTTP/1.1"}}
+    end
+
+    test "get_retention/3 returns nil for objects without retention" do
+      body = "<Error><Code>NoSuchObjectLockConfiguration</Code></Error>"
+      store = FakeS3.store("404 Not Found", [], body)
+
+      assert {:ok, nil} = ObjectStoreX.get_retention(store, "a.txt")
+    end
+
+    test "put_retention/4 sends the retention and the bypass header" do
+      store = FakeS3.store("200 OK")
+
+      assert :ok =
+               ObjectStoreX.put_retention(store, "a.txt", {:governance, @until},
+                 bypass_governance: true
+               )
+
+      assert_received {:request, request}
+      assert request.request_line == "PUT /test/a.txt?retention HTTP/1.1"
+      assert request.body =~ "<Mode>GOVERNANCE</Mode>"
+      assert request.body =~ "<RetainUntilDate>2030-01-01T00:00:00.000Z</RetainUntilDate>"
+      assert request.headers["x-amz-bypass-governance-retention"] == "true"
+      assert request.headers["content-md5"] == Base.encode64(:crypto.hash(:md5, request.body))
+    end
+
+    test "put_retention/4 reports refused changes" do
+      body = "<Error><Code>AccessDenied</Code></Error>"
+      store = FakeS3.store("403 Forbidden", [], body)
+
+      assert {:error, :permission_denied} =
+               ObjectStoreX.put_retention(store, "a.txt", {:compliance, @until})
+    end
+  end
+
+  describe "legal hold" do
+    test "get_legal_hold/3 reads the status" do
+      store = FakeS3.store("200 OK", [], "<LegalHold><Status>ON</Status></LegalHold>")
+
+      assert {:ok, true} = ObjectStoreX.get_legal_hold(store, "a.txt")
+      assert_received {:request, %{request_line: "GET /test/a.txt?legal-hold HTTP/1.1"}}
+    end
+
+    test "get_legal_hold/3 is false for objects never held" do
+      body = "<Error><Code>NoSuchObjectLockConfiguration</Code></Error>"
+      store = FakeS3.store("404 Not Found", [], body)
+
+      assert {:ok, false} = ObjectStoreX.get_legal_hold(store, "a.txt")
+    end
+
+    test "put_legal_hold/4 sends the status" do
+      store = FakeS3.store("200 OK")
+
+      assert :ok = ObjectStoreX.put_legal_hold(store, "a.txt", false)
+      assert_received {:request, %{request_line: "PUT /test/a.txt?legal-hold HTTP/1.1"} = request}
+      assert request.body =~ "<Status>OFF</Status>"
+    end
+  end
+
+  test "other providers return :not_supported" do
+    {:ok, store} = ObjectStoreX.new(:memory)
+    retention = {:governance, @until}
+
+    assert {:error, :not_supported} = ObjectStoreX.get_retention(store, "a.txt")
+    assert {:error, :not_supported} = ObjectStoreX.put_retention(store, "a.txt", retention)
+    assert {:error, :not_supported} = ObjectStoreX.get_legal_hold(store, "a.txt")
+    assert {:error, :not_supported} = ObjectStoreX.put_legal_hold(store, "a.txt", true)
+  end
+end
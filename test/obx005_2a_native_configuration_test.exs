@@ -24,19 +24,31 @@ defmodule OBX005_2A_NativeConfigurationTest do
   describe "OBX005_2A_T2: All NIF functions are defined" do
     test "provider builder NIFs are defined" do
       assert function_exported?(ObjectStoreX.Native, :new_s3, 5)
+      assert function_exported?(ObjectStoreX.Native, :new_s3_with_options, 6)
       assert function_exported?(ObjectStoreX.Native, :new_azure, 3)
+      assert function_exported?(ObjectStoreX.Native, :new_azure_with_token, 4)
       assert function_exported?(ObjectStoreX.Native, :new_gcs, 2)
+      assert function_exported?(ObjectStoreX.Native, :new_gcs_with_token, 3)
+      assert function_exported?(ObjectStoreX.Native, :token_reply, 2)
+      assert function_exported?(ObjectStoreX.Native, :new_http, 2)
+      assert function_exported?(ObjectStoreX.Native, :new_memory_with_options, 2)
       assert function_exported?(ObjectStoreX.Native, :new_local, 1)
+      assert function_exported?(ObjectStoreX.Native, :new_local_with_options, 2)
       assert function_exported?(ObjectStoreX.Native, :new_memory, 0)
+      assert function_exported?(ObjectStoreX.Native, :new_backend, 1)
+      assert function_exported?(ObjectStoreX.Native, :backend_reply, 2)
     end
 
     test "basic operation NIFs are defined" do
       assert function_exported?(ObjectStoreX.Native, :put, 3)
       assert function_exported?(ObjectStoreX.Native, :put_with_mode, 4)
       assert function_exported?(ObjectStoreX.Native, :put_with_attributes, 6)
+      assert function_exported?(ObjectStoreX.Native, :put_with_options, 7)
       assert function_exported?(ObjectStoreX.Native, :get, 2)
       assert function_exported?(ObjectStoreX.Native, :get_with_options, 3)
+      assert function_exported?(ObjectStoreX.Native, :get_with_metadata, 2)
       assert function_exported?(ObjectStoreX.Native, :delete, 2)
+      assert function_exported?(ObjectStoreX.Native, :delete_with_options, 3)
       assert function_exported?(ObjectStoreX.Native, :head, 2)
     end
 
@@ -49,7 +61,16 @@ defmodule OBX005_2A_NativeConfigurationTest do
       assert :rename in function_names
       assert :copy_if_not_exists in function_names
       assert :rename_if_not_exists in function_names
+      assert :act_if_unchanged in function_names
+      assert :compare_and_swap in function_names
+      assert :get_json in function_names
+      assert :put_json in function_names
+      assert :merge_json in function_names
       assert :get_ranges in function_names
+      assert :get_many in function_names
+      assert :head_many in function_names
+      assert :copy_many in function_names
+      assert :rename_many in function_names
       assert :delete_many in function_names
     end
 
@@ -59,10 +80,116 @@ defmodule OBX005_2A_NativeConfigurationTest do
 
       assert :start_download_stream in function_names
       assert :cancel_download_stream in function_names
+      assert :start_credit_download_stream in function_names
+      assert :start_download_stream_with_options in function_names
+      assert :grant_download_credit in function_names
       assert :start_upload_session in function_names
+      assert :start_resumable_upload_session in function_names
       assert :upload_chunk in function_names
       assert :complete_upload in function_names
       assert :abort_upload in function_names
+      assert :resume_upload_session in function_names
+      assert :upload_session_state in function_names
+      assert :start_upload_stream in function_names
+      assert :write_upload_stream in function_names
+      assert :finish_upload_stream in function_names
+      assert :abort_upload_stream in function_names
+    end
+
+    test "listing snapshot NIFs are defined" do
+      assert function_exported?(ObjectStoreX.Native, :snapshot_listing, 2)
+      assert function_exported?(ObjectStoreX.Native, :snapshot_count, 1)
+      assert function_exported?(ObjectStoreX.Native, :snapshot_query, 2)
+      assert function_exported?(ObjectStoreX.Native, :snapshot_aggregate, 2)
+      assert function_exported?(ObjectStoreX.Native, :aggregate, 4)
+      assert function_exported?(ObjectStoreX.Native, :top_n, 4)
+      assert function_exported?(ObjectStoreX.Native, :stat_prefix, 2)
+      assert function_exported?(ObjectStoreX.Native, :list_modified_since, 3)
+      assert function_exported?(ObjectStoreX.Native, :sample, 4)
+      assert function_exported?(ObjectStoreX.Native, :bench, 3)
+      assert function_exported?(ObjectStoreX.Native, :put_with_progress, 4)
+      assert function_exported?(ObjectStoreX.Native, :get_with_progress, 3)
+      assert function_exported?(ObjectStoreX.Native, :put_from_file, 5)
+      assert function_exported?(ObjectStoreX.Native, :verify_checksum, 4)
+      assert function_exported?(ObjectStoreX.Native, :start_checksum_upload_session, 3)
+      assert function_exported?(ObjectStoreX.Native, :start_list_stream, 6)
+      assert function_exported?(ObjectStoreX.Native, :list_with_delimiter, 3)
+      assert function_exported?(ObjectStoreX.Native, :get_to_file, 5)
+      assert function_exported?(ObjectStoreX.Native, :get_spilling, 6)
+      assert function_exported?(ObjectStoreX.Native, :copy_prefix, 6)
+      assert function_exported?(ObjectStoreX.Native, :extract_archive, 5)
+      assert function_exported?(ObjectStoreX.Native, :archive_prefix, 4)
+      assert function_exported?(ObjectStoreX.Native, :put_cas, 3)
+      assert function_exported?(ObjectStoreX.Native, :get_cas, 3)
+      assert function_exported?(ObjectStoreX.Native, :put_chunked, 7)
+      assert function_exported?(ObjectStoreX.Native, :get_chunked, 3)
+      assert function_exported?(ObjectStoreX.Native, :snapshot, 3)
+      assert function_exported?(ObjectStoreX.Native, :restore, 4)
+      assert function_exported?(ObjectStoreX.Native, :verify_prefix, 2)
+      assert function_exported?(ObjectStoreX.Native, :get_tags, 2)
+      assert function_exported?(ObjectStoreX.Native, :put_tags, 3)
+      assert function_exported?(ObjectStoreX.Native, :get_retention, 2)
+      assert function_exported?(ObjectStoreX.Native, :put_retention, 4)
+      assert function_exported?(ObjectStoreX.Native, :get_legal_hold, 2)
+      assert function_exported?(ObjectStoreX.Native, :put_legal_hold, 3)
+      assert function_exported?(ObjectStoreX.Native, :restore_object, 4)
+      assert function_exported?(ObjectStoreX.Native, :restore_status, 2)
+      assert function_exported?(ObjectStoreX.Native, :put_with_ttl, 4)
+      assert function_exported?(ObjectStoreX.Native, :expire_now, 2)
+      assert function_exported?(ObjectStoreX.Native, :presign_post, 3)
+      assert function_exported?(ObjectStoreX.Native, :list_versions, 2)
+      assert function_exported?(ObjectStoreX.Native, :start_put_stream, 5)
+      assert function_exported?(ObjectStoreX.Native, :put_stream_write, 2)
+      assert function_exported?(ObjectStoreX.Native, :put_stream_finish, 1)
+      assert function_exported?(ObjectStoreX.Native, :put_stream_abort, 1)
+      assert function_exported?(ObjectStoreX.Native, :open_writer, 6)
+      assert function_exported?(ObjectStoreX.Native, :writer_write, 2)
+      assert function_exported?(ObjectStoreX.Native, :writer_flush, 1)
+      assert function_exported?(ObjectStoreX.Native, :writer_close, 1)
+      assert function_exported?(ObjectStoreX.Native, :writer_abort, 1)
+      assert function_exported?(ObjectStoreX.Native, :open_reader, 3)
+      assert function_exported?(ObjectStoreX.Native, :reader_read, 2)
+      assert function_exported?(ObjectStoreX.Native, :reader_seek, 3)
+      assert function_exported?(ObjectStoreX.Native, :reader_read_range, 3)
+      assert function_exported?(ObjectStoreX.Native, :patch, 5)
+      assert function_exported?(ObjectStoreX.Native, :append, 3)
+      assert function_exported?(ObjectStoreX.Native, :split, 4)
+      assert function_exported?(ObjectStoreX.Native, :join, 4)
+      assert function_exported?(ObjectStoreX.Native, :delete_version, 3)
+      assert function_exported?(ObjectStoreX.Native, :list_incomplete_uploads, 2)
+      assert function_exported?(ObjectStoreX.Native, :abort_incomplete_uploads, 3)
+    end
+
+    test "store wrapper NIFs are defined" do
+      assert function_exported?(ObjectStoreX.Native, :derive, 4)
+      assert function_exported?(ObjectStoreX.Native, :with_throttle, 2)
+      assert function_exported?(ObjectStoreX.Native, :with_concurrency_limit, 2)
+      assert function_exported?(ObjectStoreX.Native, :with_defaults, 3)
+      assert function_exported?(ObjectStoreX.Native, :with_compression, 3)
+      assert function_exported?(ObjectStoreX.Native, :with_encryption, 2)
+      assert function_exported?(ObjectStoreX.Native, :with_cache, 3)
+      assert function_exported?(ObjectStoreX.Native, :flush_cache, 1)
+      assert function_exported?(ObjectStoreX.Native, :new_router, 1)
+      assert function_exported?(ObjectStoreX.Native, :with_mirror, 3)
+      assert function_exported?(ObjectStoreX.Native, :flush_mirror, 1)
+      assert function_exported?(ObjectStoreX.Native, :with_fallback, 2)
+      assert function_exported?(ObjectStoreX.Native, :with_rate_limit, 3)
+      assert function_exported?(ObjectStoreX.Native, :with_instrumentation, 2)
+      assert function_exported?(ObjectStoreX.Native, :with_tracing, 2)
+      assert function_exported?(ObjectStoreX.Native, :with_deadline, 3)
+      assert function_exported?(ObjectStoreX.Native, :new_op_ref, 0)
+      assert function_exported?(ObjectStoreX.Native, :cancel_op, 1)
+      assert function_exported?(ObjectStoreX.Native, :get_metrics, 0)
+      assert function_exported?(ObjectStoreX.Native, :reset_metrics, 0)
+      assert function_exported?(ObjectStoreX.Native, :list_inflight_operations, 0)
+      assert function_exported?(ObjectStoreX.Native, :shutdown, 2)
+    end
+
+    test "credential profile NIFs are defined" do
+      assert function_exported?(ObjectStoreX.Native, :register_profile, 3)
+      assert function_exported?(ObjectStoreX.Native, :remove_profile, 2)
+      assert function_exported?(ObjectStoreX.Native, :list_profiles, 1)
+      assert function_exported?(ObjectStoreX.Native, :select_profile, 2)
     end
 
     test "list operation NIFs are defined" do
@@ -134,6 +261,8 @@ defmodule OBX005_2A_NativeConfigurationTest do
       # We can't actually test the unloaded state, but we verify
       # the functions exist and have the right signature
       assert function_exported?(ObjectStoreX.Native, :new_memory, 0)
+      assert function_exported?(ObjectStoreX.Native, :new_backend, 1)
+      assert function_exported?(ObjectStoreX.Native, :backend_reply, 2)
     end
 
     test "memory store can perform basic operations" do
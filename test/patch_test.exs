@@ -0,0 +1,64 @@
+defmodule ObjectStoreX.PatchTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+    {:ok, store: store}
+  end
+
+  describe "patch/5" do
+    test "replaces a region in the middle of an object", %{store: store} do
+      :ok = ObjectStoreX.put(store, "file.txt", "hello world")
+
+      assert {:ok, %{etag: _}} = ObjectStoreX.patch(store, "file.txt", 6, "there")
+      assert {:ok, "hello there"} = ObjectStoreX.get(store, "file.txt")
+    end
+
+    test "grows the object when the patch ends past its end", %{store: store} do
+      :ok = ObjectStoreX.put(store, "file.txt", "hello")
+
+      assert {:ok, _} = ObjectStoreX.patch(store, "file.txt", 3, "p me")
+      assert {:ok, "help me"} = ObjectStoreX.get(store, "file.txt")
+    end
+
+    test "fills a gap past the end with zeros", %{store: store} do
+      :ok = ObjectStoreX.put(store, "file.bin", "ab")
+
+      assert {:ok, _} = ObjectStoreX.patch(store, "file.bin", 4, "cd")
+      assert {:ok, <<"ab", 0, 0, "cd">>} = ObjectStoreX.get(store, "file.bin")
+    end
+
+    test "patches objects spanning several chunks", %{store: store} do
+      data = :binary.copy("a", 20 * 1024 * 1024)
+      :ok = ObjectStoreX.put(store, "large.bin", data)
+
+      offset = 9 * 1024 * 1024
+      assert {:ok, _} = ObjectStoreX.patch(store, "large.bin", offset, "XYZ")
+
+      assert {:ok, patched} = ObjectStoreX.get(store, "large.bin")
+      assert byte_size(patched) == byte_size(data)
+      assert binary_part(patched, offset - 1, 5) == "aXYZa"
+    end
+
+    test "keeps attributes", %{store: store} do
+      {:ok, _} = ObjectStoreX.put(store, "doc.txt", "draft", content_type: "text/plain")
+
+      assert {:ok, _} = ObjectStoreX.patch(store, "doc.txt", 0, "final")
+      assert {:ok, meta} = ObjectStoreX.head(store, "doc.txt")
+      assert meta[:content_type] == "text/plain"
+    end
+
+    test "checks :if_match", %{store: store} do
+      :ok = ObjectStoreX.put(store, "file.txt", "hello")
+
+      assert {:error, :precondition_failed} =
+               ObjectStoreX.patch(store, "file.txt", 0, "j", if_match: "\"stale\"")
+
+      assert {:ok, "hello"} = ObjectStoreX.get(store, "file.txt")
+    end
+
+    test "fails for missing objects", %{store: store} do
+      assert {:error, :not_found} = ObjectStoreX.patch(store, "missing.txt", 0, "data")
+    end
+  end
+end
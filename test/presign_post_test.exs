@@ -0,0 +1,87 @@
+defmodule ObjectStoreX.PresignPostTest do
+  use ExUnit.Case, async: true
+
+  @s3 [
+    bucket: "uploads",
+    region: "eu-west-1",
+    access_key_id: "AKIDEXAMPLE",
+    secret_access_key: "secret",
+    endpoint: "http://127.0.0.1:9000"
+  ]
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:s3, @s3)
+    {:ok, store: store}
+  end
+
+  defp policy(fields), do: fields["policy"] |> Base.decode64!() |> Jason.decode!()
+
+  defp hmac(key, data), do: :crypto.mac(:hmac, :sha256, key, data)
+
+  test "signs the policy with the store's credentials", %{store: store} do
+    assert {:ok, %{url: url, fields: fields}} = ObjectStoreX.presign_post(store, "a/b.txt")
+
+    assert url == "http://127.0.0.1:9000/uploads"
+    assert fields["key"] == "a/b.txt"
+    assert fields["x-amz-algorithm"] == "AWS4-HMAC-SHA256"
+
+    [key_id, date, "eu-west-1", "s3", "aws4_request"] =
+      String.split(fields["x-amz-credential"], "/")
+
+    assert key_id == "AKIDEXAMPLE"
+    assert String.starts_with?(fields["x-amz-date"], date)
+
+    signing_key =
+      Enum.reduce([date, "eu-west-1", "s3", "aws4_request"], "AWS4secret", &hmac(&2, &1))
+
+    assert fields["x-amz-signature"] ==
+             Base.encode16(hmac(signing_key, fields["policy"]), case: :lower)
+  end
+
+  test "limits the form to the bucket, key and signing fields", %{store: store} do
+    {:ok, %{fields: fields}} = ObjectStoreX.presign_post(store, "a/b.txt", expires_in: 60)
+    %{"expiration" => expiration, "conditions" => conditions} = policy(fields)
+
+    assert %{"bucket" => "uploads"} in conditions
+    assert %{"key" => "a/b.txt"} in conditions
+    assert %{"x-amz-credential" => fields["x-amz-credential"]} in conditions
+    assert %{"x-amz-date" => fields["x-amz-date"]} in conditions
+
+    {:ok, expiration, 0} = DateTime.from_iso8601(expiration)
+    assert DateTime.diff(expiration, DateTime.utc_now()) in 55..60
+  end
+
+  test "adds prefix, content type and size conditions", %{store: store} do
+    {:ok, %{fields: fields}} =
+      ObjectStoreX.presign_post(store, "avatars/${filename}",
+        key_prefix: "avatars/",
+        content_type: {:starts_with, "image/"},
+        content_length_range: {1, 1_000}
+      )
+
+    conditions = policy(fields)["conditions"]
+
+    assert ["starts-with", "$key", "avatars/"] in conditions
+    assert ["starts-with", "$Content-Type", "image/"] in conditions
+    assert ["content-length-range", 1, 1_000] in conditions
+    refute Map.has_key?(fields, "Content-Type")
+  end
+
+  test "requires exact content types in the form", %{store: store} do
+    {:ok, %{fields: fields}} =
+      ObjectStoreX.presign_post(store, "a.csv", content_type: "text/csv")
+
+    assert fields["Content-Type"] == "text/csv"
+    assert %{"Content-Type" => "text/csv"} in policy(fields)["conditions"]
+  end
+
+  test "rejects keys outside the prefix", %{store: store} do
+    assert {:error, _} = ObjectStoreX.presign_post(store, "other/a.txt", key_prefix: "avatars/")
+  end
+
+  test "other providers return :not_supported" do
+    {:ok, store} = ObjectStoreX.new(:memory)
+
+    assert {:error, :not_supported} = ObjectStoreX.presign_post(store, "a.txt")
+  end
+end
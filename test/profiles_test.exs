@@ -0,0 +1,83 @@
+defmodule ObjectStoreX.ProfilesTest do
+  use ExUnit.Case, async: true
+
+  describe "Credential profiles" do
+    setup do
+      {:ok, store} = ObjectStoreX.new(:memory)
+      {:ok, archive} = ObjectStoreX.new(:memory)
+      :ok = ObjectStoreX.register_profile(store, :archive, archive)
+      {:ok, store: store, archive: archive}
+    end
+
+    test "operations use the selected profile", %{store: store, archive: archive} do
+      assert :ok = ObjectStoreX.put(store, "file.txt", "archived", profile: :archive)
+
+      assert {:ok, "archived"} = ObjectStoreX.get(archive, "file.txt")
+      assert {:ok, "archived"} = ObjectStoreX.get(store, "file.txt", profile: :archive)
+      assert {:error, :not_found} = ObjectStoreX.get(store, "file.txt")
+    end
+
+    test "profile option works with other options", %{store: store, archive: archive} do
+      assert {:ok, %{etag: _}} =
+               ObjectStoreX.put(store, "doc.json", "{}",
+                 profile: "archive",
+                 content_type: "application/json"
+               )
+
+      assert {:ok, meta} = ObjectStoreX.head(archive, "doc.json")
+      assert meta[:content_type] == "application/json"
+
+      assert {:ok, "{}", _meta} =
+               ObjectStoreX.get(store, "doc.json", profile: :archive, if_match: meta[:etag])
+    end
+
+    test "metadata and copy operations accept a profile", %{store: store} do
+      assert :ok = ObjectStoreX.put(store, "a.txt", "data", profile: :archive)
+      assert {:ok, %{size: 4}} = ObjectStoreX.head(store, "a.txt", profile: :archive)
+      assert :ok = ObjectStoreX.copy(store, "a.txt", "b.txt", profile: :archive)
+      assert :ok = ObjectStoreX.rename(store, "b.txt", "c.txt", profile: :archive)
+
+      assert {:ok, objects, []} = ObjectStoreX.list_with_delimiter(store, profile: :archive)
+      assert Enum.map(objects, & &1.location) |> Enum.sort() == ["a.txt", "c.txt"]
+
+      assert :ok = ObjectStoreX.delete(store, "a.txt", profile: :archive)
+      assert {:error, :not_found} = ObjectStoreX.head(store, "a.txt", profile: :archive)
+    end
+
+    test "streams accept a profile", %{store: store, archive: archive} do
+      assert :ok =
+               ["chunk1", "chunk2"]
+               |> ObjectStoreX.Stream.upload(store, "streamed.bin", profile: :archive)
+
+      assert {:ok, "chunk1chunk2"} = ObjectStoreX.get(archive, "streamed.bin")
+
+      assert "chunk1chunk2" ==
+               ObjectStoreX.Stream.download(store, "streamed.bin", profile: :archive)
+               |> Enum.join()
+
+      assert [%{location: "streamed.bin"}] =
+               ObjectStoreX.Stream.list_stream(store, profile: :archive) |> Enum.to_list()
+    end
+
+    test "unknown profiles return an error", %{store: store} do
+      assert {:error, :profile_not_found} = ObjectStoreX.get(store, "x", profile: :missing)
+      assert {:error, :profile_not_found} = ObjectStoreX.put(store, "x", "y", profile: :missing)
+      assert {:error, :profile_not_found} = ObjectStoreX.delete(store, "x", profile: :missing)
+
+      assert_raise RuntimeError, ~r/profile_not_found/, fn ->
+        ObjectStoreX.Stream.download(store, "x", profile: :missing) |> Enum.to_list()
+      end
+    end
+
+    test "profiles can be listed and removed", %{store: store} do
+      {:ok, other} = ObjectStoreX.new(:memory)
+      assert :ok = ObjectStoreX.register_profile(store, "reader", other)
+
+      assert {:ok, ["archive", "reader"]} = ObjectStoreX.list_profiles(store)
+
+      assert :ok = ObjectStoreX.remove_profile(store, :reader)
+      assert {:error, :not_found} = ObjectStoreX.remove_profile(store, :reader)
+      assert {:ok, ["archive"]} = ObjectStoreX.list_profiles(store)
+    end
+  end
+end
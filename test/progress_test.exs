@@ -0,0 +1,99 @@
+defmodule ObjectStoreX.ProgressTest do
+  use ExUnit.Case, async: true
+
+  @part_size 5 * 1024 * 1024
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+
+    dir = Path.join(System.tmp_dir!(), "objectstorex_progress_#{:rand.uniform(1_000_000)}")
+    File.mkdir_p!(dir)
+    on_exit(fn -> File.rm_rf!(dir) end)
+
+    {:ok, store: store, dir: dir}
+  end
+
+  describe "put/4 with :progress_pid" do
+    test "reports progress per part", %{store: store} do
+      data = :binary.copy("x", 2 * @part_size + 10)
+      total = byte_size(data)
+
+      assert :ok =
+               ObjectStoreX.put(store, "big.bin", data,
+                 progress_pid: self(),
+                 progress_interval: 0
+               )
+
+      assert_received {:progress, "big.bin", 0, ^total}
+      assert_received {:progress, "big.bin", @part_size, ^total}
+      assert_received {:progress, "big.bin", ^total, ^total}
+
+      assert {:ok, ^data} = ObjectStoreX.get(store, "big.bin")
+    end
+
+    test "uses a custom progress id", %{store: store} do
+      assert :ok =
+               ObjectStoreX.put(store, "small.txt", "data",
+                 progress_pid: self(),
+                 progress_id: "op-1"
+               )
+
+      assert_received {:progress, "op-1", 4, 4}
+    end
+
+    test "reports completion when combined with other options", %{store: store} do
+      assert {:ok, _} =
+               ObjectStoreX.put(store, "typed.json", "{}",
+                 content_type: "application/json",
+                 progress_pid: self()
+               )
+
+      assert_received {:progress, "typed.json", 2, 2}
+    end
+  end
+
+  describe "get/3 with :progress_pid" do
+    test "reports progress and returns the data", %{store: store} do
+      :ok = ObjectStoreX.put(store, "file.txt", "Hello, progress!")
+
+      assert {:ok, "Hello, progress!"} = ObjectStoreX.get(store, "file.txt", progress_pid: self())
+      assert_received {:progress, "file.txt", 0, 16}
+      assert_received {:progress, "file.txt", 16, 16}
+    end
+
+    test "returns not_found without progress", %{store: store} do
+      assert {:error, :not_found} = ObjectStoreX.get(store, "missing.txt", progress_pid: self())
+      refute_received {:progress, _, _, _}
+    end
+  end
+
+  describe "put_from_file/4 and get_to_file/4" do
+    test "round-trips a file larger than one part", %{store: store, dir: dir} do
+      source = Path.join(dir, "source.bin")
+      target = Path.join(dir, "target.bin")
+      data = :crypto.strong_rand_bytes(@part_size + 1024)
+      File.write!(source, data)
+
+      assert :ok = ObjectStoreX.put_from_file(store, "copy.bin", source, progress_pid: self())
+      assert_received {:progress, "copy.bin", total, total}
+      assert total == byte_size(data)
+
+      assert :ok = ObjectStoreX.get_to_file(store, "copy.bin", target)
+      assert File.read!(target) == data
+    end
+
+    test "returns not_found and leaves no file", %{store: store, dir: dir} do
+      target = Path.join(dir, "missing.bin")
+
+      assert {:error, :not_found} = ObjectStoreX.get_to_file(store, "missing.bin", target)
+      refute File.exists?(target)
+    end
+
+    test "returns an error for a missing source file", %{store: store, dir: dir} do
+      assert {:error, message} =
+               ObjectStoreX.put_from_file(store, "x.bin", Path.join(dir, "nope.bin"))
+
+      assert message =~ "File error"
+    end
+  end
+end
@@ -0,0 +1,61 @@
+defmodule ObjectStoreX.ProviderLimitsTest do
+  use ExUnit.Case, async: true
+
+  # Nothing listens on the endpoint, so any request that is actually sent fails
+  # with a generic error instead of a limit violation
+  setup do
+    {:ok, store} =
+      ObjectStoreX.new(:s3,
+        bucket: "test",
+        region: "us-east-1",
+        access_key_id: "key",
+        secret_access_key: "secret",
+        endpoint: "http://127.0.0.1:1",
+        allow_http: true
+      )
+
+    {:ok, store: store}
+  end
+
+  test "rejects keys longer than 1024 bytes", %{store: store} do
+    key = String.duplicate("k", 1025)
+
+    assert {:error, :key_too_long} = ObjectStoreX.put(store, key, "data")
+    assert {:error, :key_too_long} = ObjectStoreX.copy(store, "source.txt", key)
+  end
+
+  test "rejects metadata over 2 KiB", %{store: store} do
+    metadata = %{"note" => String.duplicate("m", 2048)}
+
+    assert {:error, :metadata_too_large} =
+             ObjectStoreX.put(store, "file.txt", "data", metadata: metadata)
+  end
+
+  test "rejects more than 10 tags", %{store: store} do
+    tags = Map.new(1..11, &{"tag#{&1}", "value"})
+
+    assert {:error, :too_many_tags} = ObjectStoreX.put(store, "file.txt", "data", tags: tags)
+  end
+
+  test "rejects tag values longer than 256 characters", %{store: store} do
+    tags = %{"env" => String.duplicate("v", 257)}
+
+    assert {:error, :tag_too_long} = ObjectStoreX.put(store, "file.txt", "data", tags: tags)
+  end
+
+  test "sends requests within the limits", %{store: store} do
+    assert {:error, reason} =
+             ObjectStoreX.put(store, "file.txt", "data",
+               metadata: %{"note" => "short"},
+               tags: %{"env" => "test"}
+             )
+
+    refute reason in [:key_too_long, :metadata_too_large, :too_many_tags, :tag_too_long]
+  end
+
+  test "doesn't apply provider limits to memory stores" do
+    {:ok, store} = ObjectStoreX.new(:memory)
+
+    assert :ok = ObjectStoreX.put(store, String.duplicate("k", 1025), "data")
+  end
+end
@@ -0,0 +1,58 @@
+defmodule ObjectStoreX.PutStreamTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+    %{store: store}
+  end
+
+  describe "put_stream/3" do
+    test "writes chunks with attributes and tags", %{store: store} do
+      assert {:ok, stream} =
+               ObjectStoreX.put_stream(store, "data.json",
+                 content_type: "application/json",
+                 metadata: %{"source" => "export"},
+                 tags: %{"team" => "data"}
+               )
+
+      assert :ok = ObjectStoreX.write_put_stream(stream, ~s({"a":))
+      assert :ok = ObjectStoreX.write_put_stream(stream, ["1", "}"])
+      assert {:ok, %{etag: _}} = ObjectStoreX.finish_put_stream(stream)
+
+      assert {:ok, ~s({"a":1})} = ObjectStoreX.get(store, "data.json")
+      assert {:ok, meta} = ObjectStoreX.head(store, "data.json")
+      assert meta[:content_type] == "application/json"
+    end
+
+    test "applies create mode on finish", %{store: store} do
+      :ok = ObjectStoreX.put(store, "exists.txt", "original")
+
+      {:ok, stream} = ObjectStoreX.put_stream(store, "exists.txt", mode: :create)
+      :ok = ObjectStoreX.write_put_stream(stream, "replacement")
+
+      assert {:error, :already_exists} = ObjectStoreX.finish_put_stream(stream)
+      assert {:ok, "original"} = ObjectStoreX.get(store, "exists.txt")
+    end
+
+    test "switches to a multipart upload for large overwrites", %{store: store} do
+      chunk = :binary.copy("x", 1024 * 1024)
+
+      {:ok, stream} = ObjectStoreX.put_stream(store, "large.bin", content_type: "text/plain")
+      for _ <- 1..7, do: :ok = ObjectStoreX.write_put_stream(stream, chunk)
+
+      assert {:ok, _} = ObjectStoreX.finish_put_stream(stream)
+      assert {:ok, meta} = ObjectStoreX.head(store, "large.bin")
+      assert meta[:size] == 7 * 1024 * 1024
+      assert meta[:content_type] == "text/plain"
+    end
+
+    test "abort discards written data", %{store: store} do
+      {:ok, stream} = ObjectStoreX.put_stream(store, "aborted.txt")
+      :ok = ObjectStoreX.write_put_stream(stream, "data")
+
+      assert :ok = ObjectStoreX.abort_put_stream(stream)
+      assert {:error, :not_found} = ObjectStoreX.get(store, "aborted.txt")
+      assert {:error, _} = ObjectStoreX.write_put_stream(stream, "more")
+    end
+  end
+end
@@ -0,0 +1,63 @@
+defmodule ObjectStoreX.RateLimitTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+    {:ok, store: store}
+  end
+
+  defp elapsed_ms(fun) do
+    {us, _} = :timer.tc(fun)
+    div(us, 1000)
+  end
+
+  describe "with_rate_limit/3" do
+    test "limits requests per second", %{store: store} do
+      {:ok, limited} = ObjectStoreX.with_rate_limit(store, 10, nil)
+
+      # The first 10 requests use the initial burst, the next 5 wait for refills
+      ms = elapsed_ms(fn -> for i <- 1..15, do: :ok = ObjectStoreX.put(limited, "#{i}", "x") end)
+
+      assert ms >= 400
+      assert {:ok, "x"} = ObjectStoreX.get(store, "15")
+    end
+
+    test "limits transferred bytes per second", %{store: store} do
+      {:ok, limited} = ObjectStoreX.with_rate_limit(store, nil, 1_000)
+      data = :binary.copy("x", 1_500)
+
+      ms = elapsed_ms(fn -> :ok = ObjectStoreX.put(limited, "a.bin", data) end)
+      assert ms >= 400
+
+      ms = elapsed_ms(fn -> assert {:ok, ^data} = ObjectStoreX.get(limited, "a.bin") end)
+      assert ms >= 1_300
+    end
+
+    test "shares limits between processes", %{store: store} do
+      {:ok, limited} = ObjectStoreX.with_rate_limit(store, 5, nil)
+
+      ms =
+        elapsed_ms(fn ->
+          1..10
+          |> Task.async_stream(&ObjectStoreX.put(limited, "#{&1}", "x"))
+          |> Stream.run()
+        end)
+
+      assert ms >= 900
+    end
+
+    test "passes through without waiting under the limits", %{store: store} do
+      {:ok, limited} = ObjectStoreX.with_rate_limit(store, 1_000, 1_000_000)
+
+      ms = elapsed_ms(fn -> :ok = ObjectStoreX.put(limited, "a.txt", "a") end)
+      assert ms < 100
+      assert {:ok, "a"} = ObjectStoreX.get(limited, "a.txt")
+    end
+
+    test "rejects invalid limits", %{store: store} do
+      assert {:error, _} = ObjectStoreX.with_rate_limit(store, nil, nil)
+      assert {:error, _} = ObjectStoreX.with_rate_limit(store, 0, nil)
+      assert {:error, _} = ObjectStoreX.with_rate_limit(store, 10, -1)
+    end
+  end
+end
@@ -0,0 +1,49 @@
+defmodule ObjectStoreX.RequestLimitTest do
+  use ExUnit.Case, async: false
+
+  alias ObjectStoreX.NativeConfig
+
+  setup do
+    previous = Application.get_env(:objectstorex, :max_concurrent_requests)
+
+    on_exit(fn ->
+      if previous do
+        Application.put_env(:objectstorex, :max_concurrent_requests, previous)
+      else
+        Application.delete_env(:objectstorex, :max_concurrent_requests)
+      end
+    end)
+  end
+
+  describe "NativeConfig.load_data/0" do
+    test "leaves requests unlimited by default" do
+      Application.delete_env(:objectstorex, :max_concurrent_requests)
+      assert %{max_concurrent_requests: nil} = NativeConfig.load_data()
+    end
+
+    test "passes a configured limit to the NIF" do
+      Application.put_env(:objectstorex, :max_concurrent_requests, 64)
+      assert %{max_concurrent_requests: 64} = NativeConfig.load_data()
+    end
+
+    test "rejects limits that aren't positive integers" do
+      for max <- [0, -1, "64", 1.5] do
+        Application.put_env(:objectstorex, :max_concurrent_requests, max)
+        assert_raise ArgumentError, fn -> NativeConfig.load_data() end
+      end
+    end
+  end
+
+  test "concurrent requests across stores complete" do
+    {:ok, a} = ObjectStoreX.new(:memory)
+    {:ok, b} = ObjectStoreX.new(:memory)
+
+    1..50
+    |> Task.async_stream(fn i ->
+      store = if rem(i, 2) == 0, do: a, else: b
+      :ok = ObjectStoreX.put(store, "file#{i}.txt", "data #{i}")
+      ObjectStoreX.get(store, "file#{i}.txt")
+    end)
+    |> Enum.each(fn {:ok, result} -> assert {:ok, "data " <> _} = result end)
+  end
+end
@@ -0,0 +1,32 @@
+defmodule ObjectStoreX.RequestPayerTest do
+  use ExUnit.Case, async: true
+
+  alias ObjectStoreX.FakeS3
+
+  @s3 FakeS3.options()
+
+  test "new(:s3, request_payer: true) creates a store" do
+    assert {:ok, store} = ObjectStoreX.new(:s3, Keyword.put(@s3, :request_payer, true))
+    assert is_reference(store)
+  end
+
+  test "requests to requester-pays buckets agree to pay" do
+    endpoint = FakeS3.start()
+    {:ok, store} = ObjectStoreX.new(:s3, @s3 ++ [endpoint: endpoint, request_payer: true])
+
+    assert {:ok, _} = ObjectStoreX.act_if_unchanged(store, "a.txt", "\"etag\"", {:copy, "b.txt"})
+
+    assert_receive {:request, %{headers: headers}}
+    assert headers["x-amz-request-payer"] == "requester"
+  end
+
+  test "requests don't agree to pay by default" do
+    endpoint = FakeS3.start()
+    {:ok, store} = ObjectStoreX.new(:s3, @s3 ++ [endpoint: endpoint, request_payer: false])
+
+    assert {:ok, _} = ObjectStoreX.act_if_unchanged(store, "a.txt", "\"etag\"", {:copy, "b.txt"})
+
+    assert_receive {:request, %{headers: headers}}
+    refute Map.has_key?(headers, "x-amz-request-payer")
+  end
+end
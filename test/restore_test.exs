@@ -0,0 +1,84 @@
+defmodule ObjectStoreX.RestoreTest do
+  use ExUnit.Case, async: true
+
+  alias ObjectStoreX.FakeS3
+
+  describe "restore_object/5" do
+    test "requests a restore with the tier and days" do
+      store = FakeS3.store("202 Accepted")
+
+      assert :ok = ObjectStoreX.restore_object(store, "archive/2019.tar", :bulk, 7)
+
+      assert_received {:request, request}
+      assert request.request_line == "POST /test/archive/2019.tar?restore HTTP/1.1"
+      assert request.body =~ "<Days>7</Days>"
+      assert request.body =~ "<Tier>Bulk</Tier>"
+    end
+
+    test "succeeds for objects already being restored" do
+      body = "<Error><Code>RestoreAlreadyInProgress</Code></Error>"
+      store = FakeS3.store("409 Conflict", [], body)
+
+      assert :ok = ObjectStoreX.restore_object(store, "archive/2019.tar", :standard, 1)
+    end
+
+    test "reports other errors" do
+      body = "<Error><Code>InvalidObjectState</Code></Error>"
+      store = FakeS3.store("403 Forbidden", [], body)
+
+      assert {:error, :permission_denied} =
+               ObjectStoreX.restore_object(store, "archive/2019.tar", :expedited, 1)
+    end
+  end
+
+  describe "restore_status/3" do
+    test "reports running restores" do
+      restore = "ongoing-request=\"true\""
+      headers = [{"x-amz-storage-class", "GLACIER"}, {"x-amz-restore", restore}]
+      store = FakeS3.store("200 OK", headers)
+
+      assert {:ok, %{status: :in_progress, storage_class: "GLACIER", expires_at: nil}} =
+               ObjectStoreX.restore_status(store, "archive/2019.tar")
+
+      assert_received {:request, %{request_line: "HEAD /test/archive/2019.tar HTTP/1.1"}}
+    end
+
+    test "reports restored copies with their expiry" do
+      restore = "ongoing-request=\"false\", expiry-date=\"Fri, 21 Dec 2029 00:00:00 GMT\""
+      headers = [{"x-amz-storage-class", "DEEP_ARCHIVE"}, {"x-amz-restore", restore}]
+      store = FakeS3.store("200 OK", headers)
+
+      assert {:ok, %{status: :restored, storage_class: "DEEP_ARCHIVE", expires_at: expires_at}} =
+               ObjectStoreX.restore_status(store, "archive/2019.tar")
+
+      assert expires_at == ~U[2029-12-21 00:00:00.000Z]
+    end
+
+    test "reports archived objects without a restore" do
+      store = FakeS3.store("200 OK", [{"x-amz-storage-class", "GLACIER"}])
+
+      assert {:ok, %{status: :archived, expires_at: nil}} =
+               ObjectStoreX.restore_status(store, "archive/2019.tar")
+    end
+
+    test "reports objects outside archive storage classes as available" do
+      store = FakeS3.store("200 OK")
+
+      assert {:ok, %{status: :available, storage_class: "STANDARD"}} =
+               ObjectStoreX.restore_status(store, "file.txt")
+    end
+
+    test "reports missing objects" do
+      store = FakeS3.store("404 Not Found")
+
+      assert {:error, :not_found} = ObjectStoreX.restore_status(store, "missing.txt")
+    end
+  end
+
+  test "other providers return :not_supported" do
+    {:ok, store} = ObjectStoreX.new(:memory)
+
+    assert {:error, :not_supported} = ObjectStoreX.restore_object(store, "a.txt", :standard, 1)
+    assert {:error, :not_supported} = ObjectStoreX.restore_status(store, "a.txt")
+  end
+end
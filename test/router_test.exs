@@ -0,0 +1,66 @@
+defmodule ObjectStoreX.RouterTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    {:ok, hot} = ObjectStoreX.new(:memory)
+    {:ok, cold} = ObjectStoreX.new(:memory)
+    {:ok, router} = ObjectStoreX.new_router([{"", cold}, {"hot", hot}])
+    {:ok, hot: hot, cold: cold, router: router}
+  end
+
+  describe "new_router/1" do
+    test "routes keys by longest prefix", %{hot: hot, cold: cold, router: router} do
+      :ok = ObjectStoreX.put(router, "hot/a.txt", "a")
+      :ok = ObjectStoreX.put(router, "hotter/b.txt", "b")
+
+      assert {:ok, "a"} = ObjectStoreX.get(hot, "hot/a.txt")
+      assert {:error, :not_found} = ObjectStoreX.get(cold, "hot/a.txt")
+      assert {:ok, "b"} = ObjectStoreX.get(cold, "hotter/b.txt")
+
+      assert {:ok, "a"} = ObjectStoreX.get(router, "hot/a.txt")
+      assert {:ok, %{size: 1}} = ObjectStoreX.head(router, "hotter/b.txt")
+      assert :ok = ObjectStoreX.delete(router, "hot/a.txt")
+      assert {:error, :not_found} = ObjectStoreX.get(hot, "hot/a.txt")
+    end
+
+    test "merges listings and hides shadowed objects", %{cold: cold, router: router} do
+      :ok = ObjectStoreX.put(router, "hot/a.txt", "a")
+      :ok = ObjectStoreX.put(router, "data/b.txt", "b")
+      # Written directly, so the hot route shadows it
+      :ok = ObjectStoreX.put(cold, "hot/stale.txt", "x")
+
+      locations = router |> ObjectStoreX.Stream.list_stream() |> Enum.map(& &1.location)
+      assert Enum.sort(locations) == ["data/b.txt", "hot/a.txt"]
+
+      assert {:ok, [], prefixes} = ObjectStoreX.list_with_delimiter(router)
+      assert prefixes == ["data", "hot"]
+
+      hot_locations =
+        router |> ObjectStoreX.Stream.list_stream(prefix: "hot") |> Enum.map(& &1.location)
+
+      assert hot_locations == ["hot/a.txt"]
+    end
+
+    test "copies and renames between stores", %{hot: hot, cold: cold, router: router} do
+      :ok = ObjectStoreX.put(router, "hot/a.txt", "a")
+
+      assert :ok = ObjectStoreX.copy(router, "hot/a.txt", "archive/a.txt")
+      assert {:ok, "a"} = ObjectStoreX.get(cold, "archive/a.txt")
+
+      assert :ok = ObjectStoreX.rename(router, "hot/a.txt", "archive/b.txt")
+      assert {:ok, "a"} = ObjectStoreX.get(cold, "archive/b.txt")
+      assert {:error, :not_found} = ObjectStoreX.get(hot, "hot/a.txt")
+    end
+
+    test "fails for keys without a route", %{hot: hot} do
+      {:ok, router} = ObjectStoreX.new_router([{"hot", hot}])
+
+      assert {:error, _} = ObjectStoreX.put(router, "cold/a.txt", "a")
+      assert [] = router |> ObjectStoreX.Stream.list_stream() |> Enum.to_list()
+    end
+
+    test "rejects repeated prefixes", %{hot: hot, cold: cold} do
+      assert {:error, _} = ObjectStoreX.new_router([{"hot", hot}, {"hot/", cold}])
+    end
+  end
+end
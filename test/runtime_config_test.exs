@@ -0,0 +1,69 @@
+defmodule ObjectStoreX.RuntimeConfigTest do
+  use ExUnit.Case, async: false
+
+  alias ObjectStoreX.NativeConfig
+
+  setup do
+    previous = Application.get_env(:objectstorex, :runtime)
+
+    on_exit(fn ->
+      if previous do
+        Application.put_env(:objectstorex, :runtime, previous)
+      else
+        Application.delete_env(:objectstorex, :runtime)
+      end
+    end)
+  end
+
+  describe "NativeConfig.load_data/0 runtime settings" do
+    test "keep the runtime defaults when unset" do
+      Application.delete_env(:objectstorex, :runtime)
+
+      assert %{runtime: %{worker_threads: nil, max_blocking_threads: nil, thread_name: nil}} =
+               NativeConfig.load_data()
+    end
+
+    test "pass configured settings to the NIF" do
+      Application.put_env(:objectstorex, :runtime,
+        worker_threads: 4,
+        max_blocking_threads: 32,
+        thread_name: "objectstorex"
+      )
+
+      assert %{
+               runtime: %{
+                 worker_threads: 4,
+                 max_blocking_threads: 32,
+                 thread_name: "objectstorex"
+               }
+             } = NativeConfig.load_data()
+    end
+
+    test "fill in unset settings" do
+      Application.put_env(:objectstorex, :runtime, worker_threads: 2)
+
+      assert %{runtime: %{worker_threads: 2, max_blocking_threads: nil, thread_name: nil}} =
+               NativeConfig.load_data()
+    end
+
+    test "reject invalid settings" do
+      for runtime <- [
+            [worker_threads: 0],
+            [max_blocking_threads: "8"],
+            [thread_name: ""],
+            [thread_name: :objectstorex],
+            [workers: 4],
+            %{worker_threads: 4}
+          ] do
+        Application.put_env(:objectstorex, :runtime, runtime)
+        assert_raise ArgumentError, fn -> NativeConfig.load_data() end
+      end
+    end
+  end
+
+  test "the runtime serves requests" do
+    {:ok, store} = ObjectStoreX.new(:memory)
+    :ok = ObjectStoreX.put(store, "a.txt", "a")
+    assert {:ok, "a"} = ObjectStoreX.get(store, "a.txt")
+  end
+end
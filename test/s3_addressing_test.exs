@@ -0,0 +1,53 @@
+defmodule ObjectStoreX.S3AddressingTest do
+  use ExUnit.Case, async: true
+
+  alias ObjectStoreX.FakeS3
+
+  @s3 Keyword.delete(FakeS3.options(), :allow_http)
+
+  test "plain http endpoints are only used with allow_http" do
+    endpoint = FakeS3.start("200 OK", [{"etag", "\"etag\""}])
+    {:ok, store} = ObjectStoreX.new(:s3, @s3 ++ [endpoint: endpoint])
+    assert {:error, _} = ObjectStoreX.put(store, "a.txt", "data")
+
+    {:ok, store} = ObjectStoreX.new(:s3, @s3 ++ [endpoint: endpoint, allow_http: true])
+    assert :ok = ObjectStoreX.put(store, "a.txt", "data")
+    assert_receive {:request, %{request_line: "PUT /test/a.txt HTTP/1.1"}}
+  end
+
+  test "provider API requests share the store's client options" do
+    endpoint = FakeS3.start("200 OK", [{"etag", "\"etag\""}])
+    {:ok, store} = ObjectStoreX.new(:s3, @s3 ++ [endpoint: endpoint])
+
+    assert {:error, _} =
+             ObjectStoreX.act_if_unchanged(store, "a.txt", "\"etag\"", {:copy, "b.txt"})
+
+    refute_receive {:request, _}, 100
+  end
+
+  test "virtual-hosted-style requests leave the bucket out of the path" do
+    endpoint = FakeS3.start("200 OK", [{"etag", "\"etag\""}])
+
+    {:ok, store} =
+      ObjectStoreX.new(
+        :s3,
+        @s3 ++ [endpoint: endpoint, allow_http: true, virtual_hosted_style: true]
+      )
+
+    assert :ok = ObjectStoreX.put(store, "a.txt", "data")
+    assert_receive {:request, %{request_line: "PUT /a.txt HTTP/1.1"}}
+  end
+
+  test "server-side copies use the store's addressing style" do
+    endpoint = FakeS3.start("200 OK", [{"etag", "\"etag\""}])
+
+    {:ok, store} =
+      ObjectStoreX.new(
+        :s3,
+        @s3 ++ [endpoint: endpoint, allow_http: true, virtual_hosted_style: true]
+      )
+
+    assert {:ok, _} = ObjectStoreX.act_if_unchanged(store, "a.txt", "\"etag\"", {:copy, "b.txt"})
+    assert_receive {:request, %{request_line: "PUT /b.txt HTTP/1.1"}}
+  end
+end
@@ -0,0 +1,56 @@
+defmodule ObjectStoreX.SampleTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+
+    for i <- 1..20 do
+      name = String.pad_leading("#{i}", 2, "0")
+      :ok = ObjectStoreX.put(store, "data/#{name}.csv", "id,value\n#{i},x\n")
+    end
+
+    :ok = ObjectStoreX.put(store, "other/skip.csv", "other")
+    {:ok, store: store}
+  end
+
+  describe "sample/5" do
+    test "returns n objects with their first bytes", %{store: store} do
+      assert {:ok, samples} = ObjectStoreX.sample(store, "data/", 5, 8)
+      assert length(samples) == 5
+
+      for {meta, data} <- samples do
+        assert String.starts_with?(meta.location, "data/")
+        assert data == "id,value"
+      end
+
+      locations = Enum.map(samples, fn {meta, _} -> meta.location end)
+      assert locations == Enum.sort(locations)
+      assert locations == Enum.uniq(locations)
+    end
+
+    test "returns every object when n exceeds the object count", %{store: store} do
+      assert {:ok, samples} = ObjectStoreX.sample(store, "data/", 50, 2)
+      assert length(samples) == 20
+    end
+
+    test "returns whole objects smaller than bytes_per_object", %{store: store} do
+      assert {:ok, [{meta, data}]} = ObjectStoreX.sample(store, "other/", 1, 1024)
+      assert meta.location == "other/skip.csv"
+      assert data == "other"
+    end
+
+    test "picks different objects across calls", %{store: store} do
+      picks =
+        for _ <- 1..10, into: MapSet.new() do
+          {:ok, [{meta, _}]} = ObjectStoreX.sample(store, "data/", 1, 0)
+          meta.location
+        end
+
+      assert MapSet.size(picks) > 1
+    end
+
+    test "returns an empty list for n = 0", %{store: store} do
+      assert {:ok, []} = ObjectStoreX.sample(store, "data/", 0, 10)
+    end
+  end
+end
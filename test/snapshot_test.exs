@@ -0,0 +1,89 @@
+defmodule ObjectStoreX.SnapshotTest do
+  use ExUnit.Case, async: true
+  doctest ObjectStoreX.SnapshotQuery
+
+  alias ObjectStoreX.Snapshot
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+
+    objects = [
+      {"data/a.csv", 300},
+      {"data/b.csv", 100},
+      {"data/c.json", 200},
+      {"data/nested/d.csv", 50},
+      {"other/e.csv", 10}
+    ]
+
+    for {path, size} <- objects do
+      :ok = ObjectStoreX.put(store, path, String.duplicate("x", size))
+    end
+
+    {:ok, snapshot} = Snapshot.new(store, prefix: "data/")
+    {:ok, store: store, snapshot: snapshot}
+  end
+
+  describe "Snapshot.new/2" do
+    test "materializes the listing", %{snapshot: snapshot} do
+      assert Snapshot.count(snapshot) == 4
+    end
+
+    test "is not affected by later writes", %{store: store, snapshot: snapshot} do
+      :ok = ObjectStoreX.put(store, "data/late.csv", "late")
+      assert Snapshot.count(snapshot) == 4
+    end
+  end
+
+  describe "Snapshot.query/2" do
+    test "returns all objects by default", %{snapshot: snapshot} do
+      assert {:ok, 4, page} = Snapshot.query(snapshot)
+      assert length(page) == 4
+      assert Enum.all?(page, &Map.has_key?(&1, :location))
+    end
+
+    test "filters by suffix and size", %{snapshot: snapshot} do
+      assert {:ok, 2, page} = Snapshot.query(snapshot, suffix: ".csv", min_size: 100)
+      assert page |> Enum.map(& &1.location) |> Enum.sort() == ["data/a.csv", "data/b.csv"]
+    end
+
+    test "sorts and paginates", %{snapshot: snapshot} do
+      assert {:ok, 4, [first, second]} =
+               Snapshot.query(snapshot, sort_by: :size, order: :desc, limit: 2)
+
+      assert first.location == "data/a.csv"
+      assert second.location == "data/c.json"
+
+      assert {:ok, 4, [third]} =
+               Snapshot.query(snapshot, sort_by: :size, order: :desc, offset: 2, limit: 1)
+
+      assert third.location == "data/b.csv"
+    end
+
+    test "accepts a SnapshotQuery struct", %{snapshot: snapshot} do
+      query = %ObjectStoreX.SnapshotQuery{prefix: "data/nested/"}
+      assert {:ok, 1, [%{location: "data/nested/d.csv"}]} = Snapshot.query(snapshot, query)
+    end
+
+    test "filters by modification time", %{snapshot: snapshot} do
+      future = DateTime.utc_now() |> DateTime.add(3600)
+      assert {:ok, 0, []} = Snapshot.query(snapshot, modified_after: future)
+      assert {:ok, 4, _page} = Snapshot.query(snapshot, modified_before: future)
+    end
+  end
+
+  describe "Snapshot.aggregate/2" do
+    test "summarizes matching objects", %{snapshot: snapshot} do
+      assert {:ok, summary} = Snapshot.aggregate(snapshot, suffix: ".csv")
+      assert summary.count == 3
+      assert summary.total_bytes == 450
+      assert summary.min_size == 50
+      assert summary.max_size == 300
+      assert is_binary(summary.newest)
+    end
+
+    test "handles empty matches", %{snapshot: snapshot} do
+      assert {:ok, %{count: 0, total_bytes: 0, min_size: nil, newest: nil}} =
+               Snapshot.aggregate(snapshot, suffix: ".parquet")
+    end
+  end
+end
@@ -0,0 +1,77 @@
+defmodule ObjectStoreX.SpillTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+    dir = Path.join(System.tmp_dir!(), "objectstorex_spill_#{:rand.uniform(1_000_000)}")
+    File.mkdir_p!(dir)
+    on_exit(fn -> File.rm_rf!(dir) end)
+    {:ok, store: store, dir: dir}
+  end
+
+  describe "get/3 with :spill_threshold" do
+    test "returns objects up to the threshold in memory", %{store: store, dir: dir} do
+      :ok = ObjectStoreX.put(store, "small.bin", "0123456789")
+
+      assert {:ok, "0123456789"} =
+               ObjectStoreX.get(store, "small.bin", spill_threshold: 10, spill_dir: dir)
+
+      assert File.ls!(dir) == []
+    end
+
+    test "spills larger objects into a file", %{store: store, dir: dir} do
+      data = :crypto.strong_rand_bytes(100_000)
+      :ok = ObjectStoreX.put(store, "large.bin", data)
+
+      assert {:ok, {:file, path}} =
+               ObjectStoreX.get(store, "large.bin", spill_threshold: 1024, spill_dir: dir)
+
+      assert Path.dirname(path) == dir
+      assert File.read!(path) == data
+    end
+
+    test "verifies spilled data", %{store: store, dir: dir} do
+      {:ok, %{checksum: sha256}} = ObjectStoreX.put(store, "a.bin", "data", checksum: :sha256)
+      opts = [spill_threshold: 0, spill_dir: dir]
+
+      assert {:ok, {:file, _}} =
+               ObjectStoreX.get(store, "a.bin", [verify: {:sha256, sha256}] ++ opts)
+
+      wrong = String.duplicate("0", 64)
+
+      assert {:error, :integrity_error} =
+               ObjectStoreX.get(store, "a.bin", [verify: {:sha256, wrong}] ++ opts)
+
+      assert length(File.ls!(dir)) == 1
+    end
+
+    test "reports progress", %{store: store, dir: dir} do
+      :ok = ObjectStoreX.put(store, "a.bin", "data")
+
+      assert {:ok, {:file, _}} =
+               ObjectStoreX.get(store, "a.bin",
+                 spill_threshold: 0,
+                 spill_dir: dir,
+                 progress_pid: self()
+               )
+
+      assert_receive {:progress, "a.bin", 4, 4}
+    end
+
+    test "returns store errors", %{store: store, dir: dir} do
+      assert {:error, :not_found} =
+               ObjectStoreX.get(store, "missing.bin", spill_threshold: 0, spill_dir: dir)
+
+      assert File.ls!(dir) == []
+    end
+
+    test "rejects options it can't honor", %{store: store, dir: dir} do
+      :ok = ObjectStoreX.put(store, "a.bin", "data")
+
+      assert {:error, _} = ObjectStoreX.get(store, "a.bin", spill_threshold: -1)
+      assert {:error, _} = ObjectStoreX.get(store, "a.bin", spill_threshold: 0, range: {0, 2})
+      assert {:error, _} =
+               ObjectStoreX.get(store, "a.bin", spill_threshold: 0, spill_dir: "#{dir}/nope")
+    end
+  end
+end
@@ -0,0 +1,105 @@
+defmodule ObjectStoreX.SplitTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+    {:ok, store: store}
+  end
+
+  describe "split/5" do
+    test "splits an object into parts with a manifest", %{store: store} do
+      :ok = ObjectStoreX.put(store, "data.txt", "abcdefghij")
+
+      assert {:ok, result} = ObjectStoreX.split(store, "data.txt", "data.parts", 4)
+
+      assert result.manifest == "data.parts/manifest.json"
+      assert result.parts == [
+               "data.parts/part-00000",
+               "data.parts/part-00001",
+               "data.parts/part-00002"
+             ]
+      assert result.size == 10
+      assert result.sha256 == Base.encode16(:crypto.hash(:sha256, "abcdefghij"), case: :lower)
+
+      assert {:ok, "abcd"} = ObjectStoreX.get(store, "data.parts/part-00000")
+      assert {:ok, "efgh"} = ObjectStoreX.get(store, "data.parts/part-00001")
+      assert {:ok, "ij"} = ObjectStoreX.get(store, "data.parts/part-00002")
+
+      assert {:ok, manifest} = ObjectStoreX.get(store, result.manifest)
+      assert manifest =~ ~s("source": "data.txt")
+      assert manifest =~ result.sha256
+    end
+
+    test "splits objects larger than one chunk per part", %{store: store} do
+      data = :crypto.strong_rand_bytes(20 * 1024 * 1024)
+      :ok = ObjectStoreX.put(store, "large.bin", data)
+
+      assert {:ok, %{parts: parts}} =
+               ObjectStoreX.split(store, "large.bin", "large.parts", 12 * 1024 * 1024)
+
+      assert length(parts) == 2
+
+      joined =
+        parts
+        |> Enum.map(fn part ->
+          {:ok, part_data} = ObjectStoreX.get(store, part)
+          part_data
+        end)
+        |> IO.iodata_to_binary()
+
+      assert joined == data
+    end
+
+    test "fails for missing objects", %{store: store} do
+      assert {:error, :not_found} = ObjectStoreX.split(store, "missing", "parts", 10)
+    end
+  end
+
+  describe "join/4" do
+    test "reassembles a split object", %{store: store} do
+      data = :crypto.strong_rand_bytes(1000)
+      :ok = ObjectStoreX.put(store, "data.bin", data)
+      {:ok, %{manifest: manifest}} = ObjectStoreX.split(store, "data.bin", "data.parts", 300)
+
+      assert {:ok, %{etag: _}} = ObjectStoreX.join(store, manifest, "joined.bin")
+      assert {:ok, ^data} = ObjectStoreX.get(store, "joined.bin")
+    end
+
+    test "reassembles parts larger than one chunk", %{store: store} do
+      data = :crypto.strong_rand_bytes(20 * 1024 * 1024)
+      :ok = ObjectStoreX.put(store, "large.bin", data)
+
+      {:ok, %{manifest: manifest}} =
+        ObjectStoreX.split(store, "large.bin", "large.parts", 12 * 1024 * 1024)
+
+      assert {:ok, _} = ObjectStoreX.join(store, manifest, "joined.bin")
+      assert {:ok, ^data} = ObjectStoreX.get(store, "joined.bin")
+    end
+
+    test "rejects parts with a different size", %{store: store} do
+      :ok = ObjectStoreX.put(store, "data.txt", "abcdefghij")
+      {:ok, %{manifest: manifest}} = ObjectStoreX.split(store, "data.txt", "data.parts", 4)
+      :ok = ObjectStoreX.put(store, "data.parts/part-00001", "efg")
+
+      assert {:error, :checksum_mismatch} = ObjectStoreX.join(store, manifest, "joined.txt")
+      assert {:error, :not_found} = ObjectStoreX.get(store, "joined.txt")
+    end
+
+    test "rejects corrupted parts", %{store: store} do
+      :ok = ObjectStoreX.put(store, "data.txt", "abcdefghij")
+      {:ok, %{manifest: manifest}} = ObjectStoreX.split(store, "data.txt", "data.parts", 4)
+      :ok = ObjectStoreX.put(store, "data.parts/part-00001", "EFGH")
+
+      assert {:error, :checksum_mismatch} = ObjectStoreX.join(store, manifest, "joined.txt")
+      assert {:error, :not_found} = ObjectStoreX.get(store, "joined.txt")
+    end
+
+    test "fails for missing or invalid manifests", %{store: store} do
+      assert {:error, :not_found} = ObjectStoreX.join(store, "missing.json", "joined.txt")
+
+      :ok = ObjectStoreX.put(store, "bad.json", "not json")
+      assert {:error, {:error, details}} = ObjectStoreX.join(store, "bad.json", "joined.txt")
+      assert details.message =~ "Invalid split manifest bad.json"
+    end
+  end
+end
@@ -0,0 +1,79 @@
+defmodule ObjectStoreX.SSETest do
+  use ExUnit.Case, async: true
+
+  alias ObjectStoreX.FakeS3
+
+  @s3 FakeS3.options()
+
+  describe "new(:s3, sse: ...)" do
+    test "accepts every encryption mode" do
+      for sse <- [
+            :s3,
+            :kms,
+            {:kms, "arn:aws:kms:us-east-1:111122223333:key/test"},
+            :dsse_kms,
+            {:dsse_kms, "test-key"},
+            {:customer_key, :crypto.strong_rand_bytes(32)}
+          ] do
+        assert {:ok, store} = ObjectStoreX.new(:s3, Keyword.put(@s3, :sse, sse))
+        assert is_reference(store)
+      end
+
+      assert {:ok, _} = ObjectStoreX.new(:s3, @s3 ++ [sse: :kms, sse_bucket_key: true])
+    end
+
+    test "rejects unknown modes and customer keys that aren't 256 bits" do
+      assert {:error, _} = ObjectStoreX.new(:s3, Keyword.put(@s3, :sse, :aes))
+      assert {:error, _} = ObjectStoreX.new(:s3, Keyword.put(@s3, :sse, {:customer_key, "short"}))
+    end
+
+    test "requests SSE-KMS for server-side copies" do
+      endpoint = FakeS3.start()
+
+      {:ok, store} =
+        ObjectStoreX.new(
+          :s3,
+          @s3 ++ [endpoint: endpoint, sse: {:kms, "my-key"}, sse_bucket_key: true]
+        )
+
+      assert {:ok, %{atomic: true}} =
+               ObjectStoreX.act_if_unchanged(store, "a.txt", "\"etag\"", {:copy, "b.txt"})
+
+      assert_receive {:request, %{headers: headers}}
+      assert headers["x-amz-server-side-encryption"] == "aws:kms"
+      assert headers["x-amz-server-side-encryption-aws-kms-key-id"] == "my-key"
+      assert headers["x-amz-server-side-encryption-bucket-key-enabled"] == "true"
+    end
+
+    test "sends the customer key for the source and destination of copies" do
+      endpoint = FakeS3.start()
+      key = :crypto.strong_rand_bytes(32)
+      encoded = Base.encode64(key)
+      digest = Base.encode64(:crypto.hash(:md5, key))
+
+      {:ok, store} =
+        ObjectStoreX.new(:s3, @s3 ++ [endpoint: endpoint, sse: {:customer_key, key}])
+
+      assert {:ok, _} =
+               ObjectStoreX.act_if_unchanged(store, "a.txt", "\"etag\"", {:copy, "b.txt"})
+
+      assert_receive {:request, %{headers: headers}}
+      assert headers["x-amz-server-side-encryption-customer-algorithm"] == "AES256"
+      assert headers["x-amz-server-side-encryption-customer-key"] == encoded
+      assert headers["x-amz-server-side-encryption-customer-key-md5"] == digest
+      assert headers["x-amz-copy-source-server-side-encryption-customer-key"] == encoded
+      assert headers["x-amz-copy-source-server-side-encryption-customer-key-md5"] == digest
+    end
+
+    test "sends no encryption headers without :sse" do
+      endpoint = FakeS3.start()
+      {:ok, store} = ObjectStoreX.new(:s3, @s3 ++ [endpoint: endpoint])
+
+      assert {:ok, _} =
+               ObjectStoreX.act_if_unchanged(store, "a.txt", "\"etag\"", {:copy, "b.txt"})
+
+      assert_receive {:request, %{headers: headers}}
+      refute Enum.any?(Map.keys(headers), &String.contains?(&1, "server-side-encryption"))
+    end
+  end
+end
@@ -0,0 +1,37 @@
+defmodule ObjectStoreX.StatPrefixTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+
+    for {path, size} <- [{"data/a.bin", 300}, {"data/b.bin", 100}, {"other/c.bin", 1000}] do
+      :ok = ObjectStoreX.put(store, path, String.duplicate("x", size))
+    end
+
+    {:ok, store: store}
+  end
+
+  describe "stat_prefix/3" do
+    test "summarizes the objects under a prefix", %{store: store} do
+      # Written last, so it's the newest object
+      Process.sleep(10)
+      :ok = ObjectStoreX.put(store, "data/nested/d.bin", String.duplicate("x", 50))
+
+      assert {:ok, stats} = ObjectStoreX.stat_prefix(store, "data/")
+      assert %{count: 3, bytes: 450} = stats
+      assert stats.largest.location == "data/a.bin"
+      assert stats.largest.size == 300
+      assert stats.newest.location == "data/nested/d.bin"
+    end
+
+    test "covers the whole store without a prefix", %{store: store} do
+      assert {:ok, %{count: 3, bytes: 1400, largest: %{location: "other/c.bin"}}} =
+               ObjectStoreX.stat_prefix(store, nil)
+    end
+
+    test "returns nil objects for an empty prefix", %{store: store} do
+      assert {:ok, %{count: 0, bytes: 0, largest: nil, newest: nil}} =
+               ObjectStoreX.stat_prefix(store, "missing/")
+    end
+  end
+end
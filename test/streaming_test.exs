@@ -1,7 +1,31 @@
 defmodule ObjectStoreX.StreamingTest do
   use ExUnit.Case
+
+  alias ObjectStoreX.Native
   doctest ObjectStoreX.Stream
 
+  defmodule DenyingBackend do
+    @behaviour ObjectStoreX.Backend
+
+    @impl true
+    def init(_arg), do: {:ok, nil}
+
+    @impl true
+    def put(_path, _data, _mode, _state), do: {:error, :permission_denied}
+
+    @impl true
+    def get(_path, _state), do: {:error, :not_found}
+
+    @impl true
+    def head(_path, _state), do: {:error, :not_found}
+
+    @impl true
+    def delete(_path, _state), do: :ok
+
+    @impl true
+    def list(_prefix, _state), do: {:ok, []}
+  end
+
   describe "OBX002_1A: Download Streaming Tests" do
     setup do
       {:ok, store} = ObjectStoreX.new(:memory)
@@ -153,6 +177,73 @@ defmodule ObjectStoreX.StreamingTest do
     end
   end
 
+  describe "Stream receiver monitoring" do
+    # Throttled stores keep a stream's request open for 10 seconds, so the
+    # request only leaves the in-flight registry early if the task is aborted
+    setup do
+      {:ok, store} = ObjectStoreX.new(:memory)
+      {:ok, slow} = ObjectStoreX.with_throttle(store, get_per_byte: 20, list_per_entry: 2_000)
+      {:ok, store: store, slow: slow}
+    end
+
+    test "download stream stops when the receiver exits", %{store: store, slow: slow} do
+      assert :ok = ObjectStoreX.put(store, "monitored.bin", String.duplicate("x", 500))
+
+      receiver = spawn(fn -> Process.sleep(:infinity) end)
+      ref = Process.monitor(receiver)
+
+      assert {:ok, stream_id} =
+               ObjectStoreX.Native.start_download_stream(slow, "monitored.bin", receiver)
+
+      assert wait_until(fn -> inflight(:get, "monitored.bin") != [] end)
+
+      Process.exit(receiver, :kill)
+      assert_receive {:DOWN, ^ref, :process, ^receiver, :killed}
+
+      # The task is aborted, dropping its request long before the throttle ends
+      assert wait_until(fn -> inflight(:get, "monitored.bin") == [] end)
+
+      # Cancelling an already cleaned-up stream is a no-op
+      assert :ok = ObjectStoreX.Native.cancel_download_stream(stream_id)
+    end
+
+    test "download stream to a dead receiver is aborted immediately", %{
+      store: store,
+      slow: slow
+    } do
+      assert :ok = ObjectStoreX.put(store, "dead.txt", "data")
+
+      receiver = spawn(fn -> :ok end)
+      ref = Process.monitor(receiver)
+      assert_receive {:DOWN, ^ref, :process, ^receiver, _}
+
+      assert {:ok, _stream_id} =
+               ObjectStoreX.Native.start_download_stream(slow, "dead.txt", receiver)
+
+      # No task is spawned, so the get never starts
+      refute wait_until(fn -> inflight(:get, "dead.txt") != [] end, 10)
+    end
+
+    test "list stream stops when the receiver exits", %{store: store, slow: slow} do
+      for i <- 1..5 do
+        assert :ok = ObjectStoreX.put(store, "monitored/#{i}.txt", "data")
+      end
+
+      receiver = spawn(fn -> Process.sleep(:infinity) end)
+      ref = Process.monitor(receiver)
+
+      assert {:ok, _list_id} =
+               ObjectStoreX.Native.start_list_stream(slow, "monitored/", nil, nil, nil, receiver)
+
+      assert wait_until(fn -> inflight(:list, "monitored") != [] end)
+
+      Process.exit(receiver, :kill)
+      assert_receive {:DOWN, ^ref, :process, ^receiver, :killed}
+
+      assert wait_until(fn -> inflight(:list, "monitored") == [] end)
+    end
+  end
+
   describe "OBX002_1A: Stream Integration Tests" do
     setup do
       {:ok, store} = ObjectStoreX.new(:memory)
@@ -239,6 +330,246 @@ defmodule ObjectStoreX.StreamingTest do
     end
   end
 
+  describe "Credit-based downloads" do
+    setup do
+      {:ok, store} = ObjectStoreX.new(:memory)
+      {:ok, store: store}
+    end
+
+    test "chunks are only sent once credit is granted", %{store: store} do
+      assert :ok = ObjectStoreX.put(store, "credit.txt", "flow controlled")
+
+      {:ok, stream_id} = Native.start_credit_download_stream(store, "credit.txt", self(), 0)
+      refute_receive {:chunk, ^stream_id, _}, 100
+
+      assert :ok = Native.grant_download_credit(stream_id, 1)
+      assert_receive {:chunk, ^stream_id, "flow controlled"}
+      assert_receive {:done, ^stream_id}
+
+      Native.cancel_download_stream(stream_id)
+    end
+
+    test "download/3 with :credit returns all data", %{store: store} do
+      test_data = String.duplicate("credit ", 1000)
+      assert :ok = ObjectStoreX.put(store, "credit.txt", test_data)
+
+      chunks =
+        ObjectStoreX.Stream.download(store, "credit.txt", credit: 1)
+        |> Enum.to_list()
+
+      assert IO.iodata_to_binary(chunks) == test_data
+    end
+
+    test "download_to/4 writes into a File.Stream", %{store: store} do
+      test_data = String.duplicate("Sink test data\n", 1000)
+      assert :ok = ObjectStoreX.put(store, "sink.txt", test_data)
+
+      temp_path =
+        Path.join(System.tmp_dir!(), "objectstorex_sink_#{:rand.uniform(1_000_000)}.txt")
+
+      try do
+        assert :ok = ObjectStoreX.Stream.download_to(store, "sink.txt", File.stream!(temp_path))
+        assert File.read!(temp_path) == test_data
+      after
+        File.rm(temp_path)
+      end
+    end
+
+    test "download_to/4 returns errors for missing objects", %{store: store} do
+      assert {:error, _reason} = ObjectStoreX.Stream.download_to(store, "missing.txt", [])
+    end
+  end
+
+  describe "Framed downloads" do
+    setup do
+      {:ok, store} = ObjectStoreX.new(:memory)
+      {:ok, store: store}
+    end
+
+    test "download/3 with :frame_size emits exact frames", %{store: store} do
+      test_data = :crypto.strong_rand_bytes(10_000)
+      assert :ok = ObjectStoreX.put(store, "framed.bin", test_data)
+
+      frames =
+        ObjectStoreX.Stream.download(store, "framed.bin", frame_size: 3000)
+        |> Enum.to_list()
+
+      assert Enum.map(frames, &byte_size/1) == [3000, 3000, 3000, 1000]
+      assert IO.iodata_to_binary(frames) == test_data
+    end
+
+    test "frames span the chunks of the source" do
+      root = Path.join(System.tmp_dir!(), "objectstorex_frames_#{:rand.uniform(1_000_000)}")
+      File.mkdir_p!(root)
+
+      try do
+        {:ok, store} = ObjectStoreX.new(:local, path: root)
+        test_data = :crypto.strong_rand_bytes(100_000)
+        assert :ok = ObjectStoreX.put(store, "framed.bin", test_data)
+
+        frames =
+          ObjectStoreX.Stream.download(store, "framed.bin", frame_size: 7000, credit: 1)
+          |> Enum.to_list()
+
+        assert Enum.all?(Enum.drop(frames, -1), &(byte_size(&1) == 7000))
+        assert byte_size(List.last(frames)) == rem(100_000, 7000)
+        assert IO.iodata_to_binary(frames) == test_data
+      after
+        File.rm_rf!(root)
+      end
+    end
+
+    test "objects that fit one frame are sent whole", %{store: store} do
+      assert :ok = ObjectStoreX.put(store, "small.txt", "small")
+
+      frames =
+        ObjectStoreX.Stream.download(store, "small.txt", frame_size: 1024)
+        |> Enum.to_list()
+
+      assert frames == ["small"]
+    end
+  end
+
+  describe "Gzip downloads" do
+    setup do
+      {:ok, store} = ObjectStoreX.new(:memory)
+      {:ok, store: store}
+    end
+
+    test "decompresses concatenated gzip members", %{store: store} do
+      lines = for i <- 1..3, do: String.duplicate("batch #{i}\n", 1000)
+      assert :ok = ObjectStoreX.put(store, "app.log.gz", Enum.map_join(lines, &:zlib.gzip/1))
+
+      chunks =
+        ObjectStoreX.Stream.download(store, "app.log.gz", decompress: :gzip)
+        |> Enum.to_list()
+
+      assert IO.iodata_to_binary(chunks) == Enum.join(lines)
+    end
+
+    test "frames decompressed data", %{store: store} do
+      data = String.duplicate("0123456789", 1000)
+      assert :ok = ObjectStoreX.put(store, "data.gz", :zlib.gzip(data))
+
+      frames =
+        ObjectStoreX.Stream.download(store, "data.gz", decompress: :gzip, frame_size: 4096)
+        |> Enum.to_list()
+
+      assert Enum.map(frames, &byte_size/1) == [4096, 4096, 1808]
+      assert IO.iodata_to_binary(frames) == data
+    end
+
+    test "raises on data that isn't gzip", %{store: store} do
+      assert :ok = ObjectStoreX.put(store, "plain.txt", "not gzip")
+
+      assert_raise RuntimeError, ~r/Stream error/, fn ->
+        ObjectStoreX.Stream.download(store, "plain.txt", decompress: :gzip) |> Enum.to_list()
+      end
+    end
+  end
+
+  describe "Conditional downloads" do
+    setup do
+      {:ok, store} = ObjectStoreX.new(:memory)
+      assert :ok = ObjectStoreX.put(store, "cached.txt", "cached data")
+      {:ok, %{etag: etag}} = ObjectStoreX.head(store, "cached.txt")
+      {:ok, store: store, etag: etag}
+    end
+
+    test "sends :not_modified when the ETag matches", %{store: store, etag: etag} do
+      {:ok, stream_id} =
+        Native.start_download_stream_with_options(store, "cached.txt", self(), %{
+          credit: nil,
+          frame_size: nil,
+          decompress: nil,
+          if_none_match: etag,
+          if_modified_since: nil
+        })
+
+      assert_receive {:not_modified, ^stream_id}
+      refute_receive {:chunk, ^stream_id, _}, 100
+      refute_received {:error, ^stream_id, _}
+    end
+
+    test "download/3 raises on a matching ETag", %{store: store, etag: etag} do
+      assert_raise ObjectStoreX.Stream.NotModifiedError, fn ->
+        ObjectStoreX.Stream.download(store, "cached.txt", if_none_match: etag) |> Enum.to_list()
+      end
+    end
+
+    test "download/3 streams the object when the ETag differs", %{store: store} do
+      chunks =
+        ObjectStoreX.Stream.download(store, "cached.txt", if_none_match: "\"stale\"")
+        |> Enum.to_list()
+
+      assert IO.iodata_to_binary(chunks) == "cached data"
+    end
+
+    test "download_to/4 returns {:error, :not_modified}", %{store: store, etag: etag} do
+      assert {:error, :not_modified} =
+               ObjectStoreX.Stream.download_to(store, "cached.txt", [], if_none_match: etag)
+    end
+
+    test "download/3 raises when unmodified since a time", %{store: store} do
+      later = DateTime.add(DateTime.utc_now(), 3600)
+
+      assert_raise ObjectStoreX.Stream.NotModifiedError, fn ->
+        ObjectStoreX.Stream.download(store, "cached.txt", if_modified_since: later)
+        |> Enum.to_list()
+      end
+    end
+
+    test "download/3 streams the object when modified since a time", %{store: store} do
+      earlier = DateTime.to_unix(DateTime.utc_now()) - 3600
+
+      chunks =
+        ObjectStoreX.Stream.download(store, "cached.txt", if_modified_since: earlier)
+        |> Enum.to_list()
+
+      assert IO.iodata_to_binary(chunks) == "cached data"
+    end
+
+    test "download_if_changed/3 returns {:error, :not_modified} without a stream", %{
+      store: store,
+      etag: etag
+    } do
+      assert {:error, :not_modified} =
+               ObjectStoreX.Stream.download_if_changed(store, "cached.txt", if_none_match: etag)
+
+      refute_received {:chunk, _, _}
+    end
+
+    test "download_if_changed/3 streams a changed object", %{store: store} do
+      assert :ok = ObjectStoreX.put(store, "cached.txt", String.duplicate("x", 100))
+
+      assert {:ok, stream} =
+               ObjectStoreX.Stream.download_if_changed(store, "cached.txt",
+                 if_none_match: "\"stale\"",
+                 frame_size: 30
+               )
+
+      chunks = Enum.to_list(stream)
+      assert Enum.map(chunks, &byte_size/1) == [30, 30, 30, 10]
+      assert IO.iodata_to_binary(chunks) == String.duplicate("x", 100)
+    end
+
+    test "download_if_changed/3 returns an empty stream for an empty object", %{store: store} do
+      assert :ok = ObjectStoreX.put(store, "empty.txt", "")
+
+      assert {:ok, stream} =
+               ObjectStoreX.Stream.download_if_changed(store, "empty.txt",
+                 if_none_match: "\"stale\""
+               )
+
+      assert Enum.to_list(stream) == []
+    end
+
+    test "download_if_changed/3 returns errors instead of raising", %{store: store} do
+      assert {:error, _reason} =
+               ObjectStoreX.Stream.download_if_changed(store, "missing.txt", if_none_match: "x")
+    end
+  end
+
   describe "OBX002_3A: Upload Streaming Tests" do
     setup do
       {:ok, store} = ObjectStoreX.new(:memory)
@@ -272,7 +603,8 @@ defmodule ObjectStoreX.StreamingTest do
       assert :ok = ObjectStoreX.Native.upload_chunk(session, chunk2)
 
       # Complete the upload
-      assert :ok = ObjectStoreX.Native.complete_upload(session)
+      assert {:ok, etag, _version} = ObjectStoreX.Native.complete_upload(session)
+      assert is_binary(etag)
 
       # Verify the uploaded data
       {:ok, data} = ObjectStoreX.get(store, "chunk_buffer.bin")
@@ -362,6 +694,18 @@ defmodule ObjectStoreX.StreamingTest do
       assert byte_size(result) == 12 * 1024 * 1024
     end
 
+    test "parts assembled from many chunks keep their order", %{store: store} do
+      {:ok, session} = ObjectStoreX.Native.start_upload_session(store, "segments.bin")
+
+      # 700KB chunks don't divide the 5MB part size, so parts end mid-chunk
+      chunks = for _ <- 1..12, do: :crypto.strong_rand_bytes(700 * 1024)
+      Enum.each(chunks, &(:ok = ObjectStoreX.Native.upload_chunk(session, &1)))
+
+      assert {:ok, _etag, _version} = ObjectStoreX.Native.complete_upload(session)
+      assert {:ok, result} = ObjectStoreX.get(store, "segments.bin")
+      assert result == IO.iodata_to_binary(chunks)
+    end
+
     test "OBX002_3A_T8: Test abort_upload cancels upload", %{store: store} do
       # Start upload session
       {:ok, session} = ObjectStoreX.Native.start_upload_session(store, "abort_test.bin")
@@ -415,6 +759,324 @@ defmodule ObjectStoreX.StreamingTest do
     end
   end
 
+  describe "Upload session errors" do
+    setup do
+      {:ok, store} = ObjectStoreX.new(:custom, backend: DenyingBackend)
+      {:ok, store: store}
+    end
+
+    test "upload/4 returns the mapped error", %{store: store} do
+      assert {:error, :permission_denied} =
+               ["a", "b"] |> ObjectStoreX.Stream.upload(store, "denied.bin")
+    end
+
+    test "session NIFs return error atoms instead of raising", %{store: store} do
+      {:ok, session} = Native.start_upload_session(store, "denied.bin")
+      assert :ok = Native.upload_chunk(session, "data")
+      assert :permission_denied = Native.complete_upload(session)
+    end
+  end
+
+  describe "Push-style upload streaming" do
+    setup do
+      {:ok, store} = ObjectStoreX.new(:memory)
+      {:ok, store: store}
+    end
+
+    test "uploads pushed chunks and reports completion", %{store: store} do
+      {:ok, upload} = ObjectStoreX.Stream.start_upload_stream(store, "pushed.txt")
+
+      assert :ok = ObjectStoreX.Stream.write_upload_stream(upload, "Hello, ")
+      assert :ok = ObjectStoreX.Stream.write_upload_stream(upload, ["push", "ed!"])
+
+      assert {:ok, %{etag: _, version: _}} = ObjectStoreX.Stream.finish_upload_stream(upload)
+      assert {:ok, "Hello, pushed!"} = ObjectStoreX.get(store, "pushed.txt")
+    end
+
+    test "sends a part_uploaded message per part", %{store: store} do
+      {:ok, %{id: id} = upload} =
+        ObjectStoreX.Stream.start_upload_stream(store, "parts.bin", part_size: 1024)
+
+      chunk = String.duplicate("x", 1000)
+
+      for _ <- 1..5 do
+        assert :ok = ObjectStoreX.Stream.write_upload_stream(upload, chunk)
+      end
+
+      assert {:ok, _meta} = ObjectStoreX.Stream.finish_upload_stream(upload)
+
+      for part <- 1..5 do
+        assert_received {:part_uploaded, ^id, ^part}
+      end
+
+      assert {:ok, data} = ObjectStoreX.get(store, "parts.bin")
+      assert data == String.duplicate(chunk, 5)
+    end
+
+    test "uploads parts while more chunks are awaited", %{store: store} do
+      {:ok, %{id: id} = upload} =
+        ObjectStoreX.Stream.start_upload_stream(store, "early.bin",
+          part_size: 1024,
+          max_concurrency: 4
+        )
+
+      assert :ok = ObjectStoreX.Stream.write_upload_stream(upload, String.duplicate("x", 1024))
+      assert_receive {:part_uploaded, ^id, 1}
+
+      assert {:ok, _meta} = ObjectStoreX.Stream.finish_upload_stream(upload)
+    end
+
+    test "progress messages can go to another process", %{store: store} do
+      parent = self()
+
+      receiver =
+        spawn(fn ->
+          receive do
+            msg -> send(parent, {:forwarded, msg})
+          end
+        end)
+
+      {:ok, %{id: id} = upload} =
+        ObjectStoreX.Stream.start_upload_stream(store, "other.txt", receiver: receiver)
+
+      assert :ok = ObjectStoreX.Stream.write_upload_stream(upload, "data")
+      assert :ok = Native.finish_upload_stream(upload.ref)
+
+      assert_receive {:forwarded, {:part_uploaded, ^id, 1}}
+    end
+
+    test "abort discards the upload", %{store: store} do
+      {:ok, %{id: id} = upload} = ObjectStoreX.Stream.start_upload_stream(store, "aborted.txt")
+
+      assert :ok = ObjectStoreX.Stream.write_upload_stream(upload, "data")
+      assert :ok = ObjectStoreX.Stream.abort_upload_stream(upload)
+
+      assert_receive {:upload_error, ^id, "upload aborted"}
+      assert {:error, :closed} = ObjectStoreX.Stream.write_upload_stream(upload, "more")
+      assert {:error, :not_found} = ObjectStoreX.get(store, "aborted.txt")
+    end
+    test "writes to a full queue return :busy and announce when to retry", %{store: store} do
+      {:ok, slow} = ObjectStoreX.with_throttle(store, put_per_call: 50)
+
+      {:ok, %{id: id} = upload} =
+        ObjectStoreX.Stream.start_upload_stream(slow, "busy.bin",
+          part_size: 1,
+          max_concurrency: 1
+        )
+
+      results = for _ <- 1..20, do: Native.write_upload_stream(upload.ref, "x")
+
+      assert :busy in results
+      assert_receive {:upload_ready, ^id}, 5_000
+
+      assert :ok = ObjectStoreX.Stream.write_upload_stream(upload, "y")
+      assert {:ok, _meta} = ObjectStoreX.Stream.finish_upload_stream(upload)
+
+      written = Enum.count(results, &(&1 == :ok))
+      assert {:ok, data} = ObjectStoreX.get(store, "busy.bin")
+      assert data == String.duplicate("x", written) <> "y"
+    end
+  end
+
+  describe "Resumable upload sessions" do
+    setup do
+      {:ok, store} = ObjectStoreX.new(:memory)
+      {:ok, store: store}
+    end
+
+    @part_size 5 * 1024 * 1024
+
+    test "session state records uploaded parts", %{store: store} do
+      {:ok, session} = Native.start_resumable_upload_session(store, "stateful.bin")
+
+      assert %{parts: [], bytes_uploaded: 0} = Native.upload_session_state(session)
+
+      assert :ok = Native.upload_chunk(session, :binary.copy("a", @part_size))
+      assert %{upload_id: _, parts: [_], bytes_uploaded: @part_size} =
+               Native.upload_session_state(session)
+
+      assert :ok = Native.abort_upload(session)
+    end
+
+    test "resumes an upload from saved state", %{store: store} do
+      first = :binary.copy("a", @part_size)
+      rest = "tail"
+
+      {:ok, session} = Native.start_resumable_upload_session(store, "resumed.bin")
+      assert :ok = Native.upload_chunk(session, first)
+      state = Native.upload_session_state(session)
+
+      # Simulate losing the original session
+      {:ok, resumed} = Native.resume_upload_session(store, "resumed.bin", state)
+      assert :ok = Native.upload_chunk(resumed, rest)
+      assert {:ok, _etag, _version} = Native.complete_upload(resumed)
+
+      assert {:ok, data} = ObjectStoreX.get(store, "resumed.bin")
+      assert data == first <> rest
+    end
+
+    test "resumes from bytes_uploaded after chunks unaligned with parts", %{store: store} do
+      data = :crypto.strong_rand_bytes(2 * @part_size + 1234)
+      chunk = 3_000_000
+
+      {:ok, session} = Native.start_resumable_upload_session(store, "unaligned.bin")
+
+      for offset <- [0, chunk, 2 * chunk] do
+        assert :ok = Native.upload_chunk(session, binary_part(data, offset, chunk))
+      end
+
+      # The first two chunks form a part, the third is only buffered and is
+      # lost with the session
+      state = Native.upload_session_state(session)
+      assert %{parts: [_], bytes_uploaded: bytes_uploaded} = state
+      assert bytes_uploaded == 2 * chunk
+
+      {:ok, resumed} = Native.resume_upload_session(store, "unaligned.bin", state)
+      rest = binary_part(data, bytes_uploaded, byte_size(data) - bytes_uploaded)
+      assert :ok = Native.upload_chunk(resumed, rest)
+      assert {:ok, _etag, _version} = Native.complete_upload(resumed)
+
+      assert {:ok, ^data} = ObjectStoreX.get(store, "unaligned.bin")
+    end
+
+    test "upload/4 reports state and resumes with :resume", %{store: store} do
+      parent = self()
+      chunks = [:binary.copy("a", @part_size), :binary.copy("b", @part_size), "c"]
+
+      {:ok, session} = Native.start_resumable_upload_session(store, "crashed.bin")
+      assert :ok = Native.upload_chunk(session, hd(chunks))
+      state = Native.upload_session_state(session)
+
+      assert :ok =
+               chunks
+               |> Stream.drop(div(state.bytes_uploaded, @part_size))
+               |> ObjectStoreX.Stream.upload(store, "crashed.bin",
+                 resume: state,
+                 on_state: &send(parent, {:state, &1})
+               )
+
+      assert_received {:state, %{parts: [_, _], bytes_uploaded: bytes}}
+      assert bytes == 2 * @part_size
+
+      assert {:ok, data} = ObjectStoreX.get(store, "crashed.bin")
+      assert data == Enum.join(chunks)
+    end
+
+    test "ordinary sessions go through the store's wrappers and aren't resumable" do
+      {:ok, store} = ObjectStoreX.new(:memory, trace: self())
+      {:ok, session} = Native.start_upload_session(store, "plain.bin")
+
+      assert_receive {:objectstorex_trace, _, :finish, %{operation: :put_multipart}}
+      assert :not_supported = Native.upload_session_state(session)
+
+      assert :ok = Native.upload_chunk(session, "data")
+      assert {:ok, _etag, _version} = Native.complete_upload(session)
+      assert {:ok, "data"} = ObjectStoreX.get(store, "plain.bin")
+    end
+
+    test "local stores don't support resuming" do
+      dir = Path.join(System.tmp_dir!(), "objectstorex_resume_#{:rand.uniform(1_000_000)}")
+      File.mkdir_p!(dir)
+      on_exit(fn -> File.rm_rf!(dir) end)
+
+      {:ok, store} = ObjectStoreX.new(:local, path: dir)
+      {:ok, session} = Native.start_upload_session(store, "local.bin")
+
+      assert :not_supported = Native.upload_session_state(session)
+      assert :ok = Native.abort_upload(session)
+      assert :not_supported = Native.start_resumable_upload_session(store, "local.bin")
+
+      state = %{upload_id: "id", parts: [], bytes_uploaded: 0}
+
+      assert {:error, :not_supported} =
+               ObjectStoreX.Stream.upload(["data"], store, "local.bin", resume: state)
+    end
+  end
+
+  describe "Dropped upload sessions" do
+    setup do
+      dir = Path.join(System.tmp_dir!(), "objectstorex_dropped_#{:rand.uniform(1_000_000)}")
+      File.mkdir_p!(dir)
+      on_exit(fn -> File.rm_rf!(dir) end)
+
+      {:ok, store} = ObjectStoreX.new(:local, path: dir)
+      {:ok, store: store, dir: dir}
+    end
+
+    test "abort the upload when the owning process dies", %{store: store, dir: dir} do
+      start_and_crash(fn ->
+        {:ok, session} = Native.start_upload_session(store, "crashed.bin")
+        :ok = Native.upload_chunk(session, "partial")
+      end)
+
+      # Local uploads are staged in a file next to the destination
+      assert wait_until(fn -> File.ls!(dir) == [] end)
+    end
+
+    test "keep uploads that are finished", %{store: store, dir: dir} do
+      start_and_crash(fn ->
+        {:ok, session} = Native.start_upload_session(store, "done.bin")
+        :ok = Native.upload_chunk(session, "done")
+        {:ok, _etag, _version} = Native.complete_upload(session)
+      end)
+
+      :erlang.garbage_collect()
+      assert File.ls!(dir) == ["done.bin"]
+    end
+
+    test "keep uploads whose state was saved" do
+      {:ok, store} = ObjectStoreX.new(:memory)
+      part = :binary.copy("a", 5 * 1024 * 1024)
+
+      state =
+        start_and_crash(fn ->
+          {:ok, session} = Native.start_resumable_upload_session(store, "saved.bin")
+          :ok = Native.upload_chunk(session, part)
+          Native.upload_session_state(session)
+        end)
+
+      :erlang.garbage_collect()
+      Process.sleep(50)
+
+      {:ok, resumed} = Native.resume_upload_session(store, "saved.bin", state)
+      assert {:ok, _etag, _version} = Native.complete_upload(resumed)
+      assert {:ok, ^part} = ObjectStoreX.get(store, "saved.bin")
+    end
+  end
+
+  # Run `fun` in a process that exits abnormally afterwards, returning its result
+  defp start_and_crash(fun) do
+    parent = self()
+
+    {pid, ref} =
+      spawn_monitor(fn ->
+        send(parent, {:result, fun.()})
+        exit(:crash)
+      end)
+
+    assert_receive {:result, result}
+    assert_receive {:DOWN, ^ref, :process, ^pid, :crash}
+    result
+  end
+
+  # In-flight store operations of one kind on `path`
+  defp inflight(operation, path) do
+    Enum.filter(
+      ObjectStoreX.list_inflight_operations(),
+      &(&1.operation == operation and &1.path == path)
+    )
+  end
+
+  defp wait_until(fun, attempts \\ 50) do
+    cond do
+      fun.() -> true
+      attempts == 0 -> false
+      true ->
+        Process.sleep(20)
+        wait_until(fun, attempts - 1)
+    end
+  end
+
   describe "OBX002_3A: Upload Integration Tests" do
     setup do
       {:ok, store} = ObjectStoreX.new(:memory)
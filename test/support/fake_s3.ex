@@ -0,0 +1,80 @@
+defmodule ObjectStoreX.FakeS3 do
+  @moduledoc false
+
+  # A one-shot S3 endpoint on a local port, for tests of requests that can't
+  # be checked against the memory store. It accepts a single request, sends
+  # `{:request, %{request_line: line, headers: headers, body: body}}` to the
+  # process that started it, with lowercased header names, and replies with
+  # the given status line, headers and body. `serve/1` does the same for a
+  # fixed sequence of requests, one response each.
+
+  @options [
+    bucket: "test",
+    region: "us-east-1",
+    access_key_id: "key",
+    secret_access_key: "secret",
+    allow_http: true
+  ]
+
+  @doc "S3 options of the stores returned by `store/3`, without the endpoint."
+  def options, do: @options
+
+  @doc "Start a fake endpoint and return its URL."
+  def start(status \\ "200 OK", headers \\ [], body \\ "") do
+    serve([{status, headers, body}])
+  end
+
+  @doc """
+  Start a fake endpoint that answers one request with each
+  `{status, headers, body}` response, in order, and return its URL.
+  """
+  def serve(responses) do
+    {:ok, listen} = :gen_tcp.listen(0, [:binary, active: false, reuseaddr: true])
+    {:ok, port} = :inet.port(listen)
+    test = self()
+
+    spawn_link(fn ->
+      Enum.each(responses, fn {status, headers, body} ->
+        {:ok, socket} = :gen_tcp.accept(listen)
+        send(test, {:request, read_request(socket)})
+        :ok = :gen_tcp.send(socket, response(status, headers, body))
+        :gen_tcp.close(socket)
+      end)
+
+      :gen_tcp.close(listen)
+    end)
+
+    "http://127.0.0.1:#{port}"
+  end
+
+  @doc "Start a fake endpoint and return an S3 store using it."
+  def store(status \\ "200 OK", headers \\ [], body \\ "") do
+    endpoint = start(status, headers, body)
+    {:ok, store} = ObjectStoreX.new(:s3, @options ++ [endpoint: endpoint])
+    store
+  end
+
+  defp response(status, headers, body) do
+    "HTTP/1.1 #{status}\r\ncontent-type: application/xml\r\n" <>
+      Enum.map_join(headers, fn {name, value} -> "#{name}: #{value}\r\n" end) <>
+      "content-length: #{byte_size(body)}\r\nconnection: close\r\n\r\n" <> body
+  end
+
+  defp read_request(socket, acc \\ "") do
+    with [head, body] <- String.split(acc, "\r\n\r\n", parts: 2),
+         [request_line | header_lines] = String.split(head, "\r\n"),
+         headers = Map.new(header_lines, &parse_header/1),
+         true <- byte_size(body) >= String.to_integer(Map.get(headers, "content-length", "0")) do
+      %{request_line: request_line, headers: headers, body: body}
+    else
+      _ ->
+        {:ok, data} = :gen_tcp.recv(socket, 0, 5_000)
+        read_request(socket, acc <> data)
+    end
+  end
+
+  defp parse_header(line) do
+    [name, value] = String.split(line, ":", parts: 2)
+    {String.downcase(name), String.trim(value)}
+  end
+end
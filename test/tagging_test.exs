@@ -0,0 +1,46 @@
+defmodule ObjectStoreX.TaggingTest do
+  use ExUnit.Case, async: true
+
+  describe "get_tags/3 and put_tags/4" do
+    test "are not supported by the memory store" do
+      {:ok, store} = ObjectStoreX.new(:memory)
+      :ok = ObjectStoreX.put(store, "file.txt", "data")
+
+      assert {:error, :not_supported} = ObjectStoreX.get_tags(store, "file.txt")
+      assert {:error, :not_supported} = ObjectStoreX.put_tags(store, "file.txt", %{"a" => "b"})
+    end
+
+    test "are not supported by derived stores" do
+      {:ok, store} = ObjectStoreX.new(:s3, bucket: "test", region: "us-east-1")
+      {:ok, child} = ObjectStoreX.derive(store, prefix: "tenant/")
+
+      assert {:error, :not_supported} = ObjectStoreX.get_tags(child, "file.txt")
+    end
+
+    test "use the S3 tagging API on S3 stores" do
+      {:ok, store} =
+        ObjectStoreX.new(:s3,
+          bucket: "test",
+          region: "us-east-1",
+          access_key_id: "key",
+          secret_access_key: "secret",
+          endpoint: "http://127.0.0.1:1",
+          allow_http: true
+        )
+
+      # Nothing listens on the endpoint, so the request itself fails
+      assert {:error, reason} = ObjectStoreX.get_tags(store, "file.txt")
+      refute reason == :not_supported
+
+      assert {:error, reason} = ObjectStoreX.put_tags(store, "file.txt", env: "test")
+      refute reason == :not_supported
+    end
+  end
+
+  test "put/4 accepts tags on providers without tagging" do
+    {:ok, store} = ObjectStoreX.new(:memory)
+
+    assert {:ok, _} = ObjectStoreX.put(store, "tagged.txt", "data", tags: %{"env" => "test"})
+    assert {:ok, "data"} = ObjectStoreX.get(store, "tagged.txt")
+  end
+end
@@ -0,0 +1,62 @@
+defmodule ObjectStoreX.ThrottleTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+    {:ok, store: store}
+  end
+
+  describe "with_throttle/2" do
+    test "shares data with the parent store", %{store: store} do
+      {:ok, throttled} = ObjectStoreX.with_throttle(store, put_per_call: 1)
+
+      assert :ok = ObjectStoreX.put(throttled, "file.txt", "data")
+      assert {:ok, "data"} = ObjectStoreX.get(store, "file.txt")
+    end
+
+    test "delays calls by the configured latency", %{store: store} do
+      :ok = ObjectStoreX.put(store, "file.txt", "data")
+      {:ok, throttled} = ObjectStoreX.with_throttle(store, %{get_per_call: 100})
+
+      {micros, {:ok, "data"}} = :timer.tc(fn -> ObjectStoreX.get(throttled, "file.txt") end)
+      assert micros >= 100_000
+    end
+
+    test "applies per-byte latency", %{store: store} do
+      :ok = ObjectStoreX.put(store, "file.bin", :binary.copy("x", 1000))
+      {:ok, throttled} = ObjectStoreX.with_throttle(store, get_per_byte: 0.1)
+
+      {micros, {:ok, _data}} = :timer.tc(fn -> ObjectStoreX.get(throttled, "file.bin") end)
+      assert micros >= 100_000
+    end
+
+    test "rejects unknown options", %{store: store} do
+      assert {:error, message} = ObjectStoreX.with_throttle(store, get_delay: 10)
+      assert message =~ "get_delay"
+    end
+  end
+
+  describe "with_concurrency_limit/2" do
+    test "serializes requests beyond the limit", %{store: store} do
+      {:ok, slow} = ObjectStoreX.with_throttle(store, put_per_call: 100)
+      {:ok, limited} = ObjectStoreX.with_concurrency_limit(slow, 1)
+
+      {micros, results} =
+        :timer.tc(fn ->
+          1..3
+          |> Task.async_stream(&ObjectStoreX.put(limited, "file#{&1}.txt", "data"))
+          |> Enum.map(fn {:ok, result} -> result end)
+        end)
+
+      assert results == [:ok, :ok, :ok]
+      assert micros >= 300_000
+    end
+
+    test "shares data with the parent store", %{store: store} do
+      {:ok, limited} = ObjectStoreX.with_concurrency_limit(store, 4)
+
+      assert :ok = ObjectStoreX.put(limited, "file.txt", "data")
+      assert {:ok, "data"} = ObjectStoreX.get(store, "file.txt")
+    end
+  end
+end
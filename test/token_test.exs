@@ -0,0 +1,95 @@
+defmodule ObjectStoreX.TokenTest do
+  use ExUnit.Case, async: true
+
+  alias ObjectStoreX.TokenServer
+
+  defp expired, do: DateTime.add(DateTime.utc_now(), -60)
+  defp valid, do: DateTime.add(DateTime.utc_now(), 3600)
+
+  describe "new/2 with bearer tokens" do
+    test "creates GCS and Azure stores from a token" do
+      assert {:ok, store} = ObjectStoreX.new(:gcs, bucket: "test", token: "ya29.token")
+      assert is_reference(store)
+
+      assert {:ok, _} = ObjectStoreX.new(:gcs, bucket: "test", token: {"ya29.token", valid()})
+
+      assert {:ok, _} =
+               ObjectStoreX.new(:azure,
+                 account: "account",
+                 container: "container",
+                 token: {"eyJ0", DateTime.to_unix(valid())}
+               )
+    end
+
+    test "fails requests once a token without refresh has expired" do
+      {:ok, store} = ObjectStoreX.new(:gcs, bucket: "test", token: {"ya29.token", expired()})
+
+      assert {:error, reason} = ObjectStoreX.list_versions(store)
+      refute reason == :not_supported
+    end
+
+    test "asks the token function for a token before the first request" do
+      test = self()
+
+      token_fun = fn ->
+        send(test, :token_requested)
+        {:error, :broker_down}
+      end
+
+      {:ok, store} = ObjectStoreX.new(:gcs, bucket: "test", token_fun: token_fun)
+
+      assert {:error, _} = ObjectStoreX.list_versions(store)
+      assert_receive :token_requested
+    end
+
+    test "refreshes an expired token through a named token server" do
+      test = self()
+
+      {:ok, _} =
+        TokenServer.start_link(
+          token_fun: fn ->
+            send(test, :token_requested)
+            {:error, "no token"}
+          end,
+          name: __MODULE__.Server
+        )
+
+      {:ok, store} =
+        ObjectStoreX.new(:gcs,
+          bucket: "test",
+          token: {"ya29.token", expired()},
+          token_server: __MODULE__.Server
+        )
+
+      assert {:error, _} = ObjectStoreX.list_versions(store)
+      assert_receive :token_requested
+    end
+
+    test "rejects invalid token options" do
+      assert {:error, _} = ObjectStoreX.new(:gcs, bucket: "test", token_fun: :not_a_function)
+      assert {:error, :not_found} = ObjectStoreX.new(:gcs, bucket: "test", token_server: :missing)
+
+      assert {:error, _} =
+               ObjectStoreX.new(:gcs,
+                 bucket: "test",
+                 token_fun: fn -> {:ok, "t"} end,
+                 token_server: :missing
+               )
+    end
+  end
+
+  describe "TokenServer.fetch/1" do
+    test "normalizes token function results" do
+      expires_at = valid()
+      unix = DateTime.to_unix(expires_at)
+
+      assert {:ok, {"t", ^unix}} = TokenServer.fetch(fn -> {:ok, "t", expires_at} end)
+      assert {:ok, {"t", 123}} = TokenServer.fetch(fn -> {:ok, "t", 123} end)
+      assert {:ok, {"t", nil}} = TokenServer.fetch(fn -> {:ok, "t"} end)
+      assert {:error, :denied} = TokenServer.fetch(fn -> {:error, :denied} end)
+      assert {:error, "{:http, 500}"} = TokenServer.fetch(fn -> {:error, {:http, 500}} end)
+      assert {:error, "Invalid token function result: :ok"} = TokenServer.fetch(fn -> :ok end)
+      assert {:error, "boom"} = TokenServer.fetch(fn -> raise "boom" end)
+    end
+  end
+end
@@ -0,0 +1,54 @@
+defmodule ObjectStoreX.TopNTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+
+    objects = [
+      {"data/a.bin", 300},
+      {"data/b.bin", 100},
+      {"data/c.bin", 200},
+      {"data/nested/d.bin", 50},
+      {"other/e.bin", 1000}
+    ]
+
+    for {path, size} <- objects do
+      :ok = ObjectStoreX.put(store, path, String.duplicate("x", size))
+    end
+
+    {:ok, store: store}
+  end
+
+  describe "top_n/4 by size" do
+    test "returns the largest objects first", %{store: store} do
+      assert {:ok, objects} = ObjectStoreX.top_n(store, "data/", 2)
+
+      assert Enum.map(objects, & &1.location) == ["data/a.bin", "data/c.bin"]
+      assert Enum.map(objects, & &1.size) == [300, 200]
+    end
+
+    test "returns everything when n exceeds the object count", %{store: store} do
+      assert {:ok, objects} = ObjectStoreX.top_n(store, nil, 10)
+      assert length(objects) == 5
+      assert hd(objects).location == "other/e.bin"
+    end
+
+    test "returns nothing for n = 0", %{store: store} do
+      assert {:ok, []} = ObjectStoreX.top_n(store, nil, 0)
+    end
+  end
+
+  describe "top_n/4 by last_modified" do
+    test "returns the most recently modified objects first", %{store: store} do
+      Process.sleep(10)
+      :ok = ObjectStoreX.put(store, "data/newest.bin", "new")
+
+      assert {:ok, [newest]} = ObjectStoreX.top_n(store, "data/", 1, by: :last_modified)
+      assert newest.location == "data/newest.bin"
+    end
+  end
+
+  test "rejects unknown sort keys", %{store: store} do
+    assert {:error, _} = ObjectStoreX.top_n(store, nil, 3, by: :name)
+  end
+end
@@ -0,0 +1,82 @@
+defmodule ObjectStoreX.TracingTest do
+  use ExUnit.Case, async: true
+
+  describe "new/2 with :trace" do
+    test "sends start and finish events with an operation id" do
+      {:ok, store} = ObjectStoreX.new(:memory, trace: self())
+
+      :ok = ObjectStoreX.put(store, "file.txt", "data")
+
+      assert_receive {:objectstorex_trace, id, :start, %{operation: :put, path: "file.txt"}}
+
+      assert_receive {:objectstorex_trace, ^id, :finish, finish}
+      assert %{operation: :put, path: "file.txt", result: :ok, status: nil} = finish
+      assert is_integer(finish.duration_us)
+    end
+
+    test "sends the events of a request in order" do
+      {:ok, store} = ObjectStoreX.new(:memory, trace: self())
+
+      for i <- 1..20, do: :ok = ObjectStoreX.put(store, "file#{i}.txt", "data")
+
+      events =
+        for _ <- 1..40 do
+          assert_receive {:objectstorex_trace, id, event, _}
+          {id, event}
+        end
+
+      for {id, :finish} <- events do
+        assert Enum.find_index(events, &(&1 == {id, :start})) <
+                 Enum.find_index(events, &(&1 == {id, :finish}))
+      end
+    end
+
+    test "gives every request its own id" do
+      {:ok, store} = ObjectStoreX.new(:memory, trace: self())
+
+      :ok = ObjectStoreX.put(store, "file.txt", "data")
+      {:ok, "data"} = ObjectStoreX.get(store, "file.txt")
+
+      assert_receive {:objectstorex_trace, put_id, :finish, %{operation: :put}}
+      assert_receive {:objectstorex_trace, get_id, :finish, %{operation: :get}}
+      assert put_id != get_id
+    end
+
+    test "reports failed requests" do
+      {:ok, store} = ObjectStoreX.new(:memory, trace: self())
+
+      {:error, :not_found} = ObjectStoreX.head(store, "missing.txt")
+
+      assert_receive {:objectstorex_trace, _, :finish,
+                      %{operation: :head, path: "missing.txt", result: :not_found}}
+    end
+
+    test "reports the HTTP status of failed object_store requests" do
+      endpoint = ObjectStoreX.FakeS3.start("400 Bad Request", [], "<Error></Error>")
+      opts = ObjectStoreX.FakeS3.options() ++ [endpoint: endpoint, trace: self()]
+      {:ok, store} = ObjectStoreX.new(:s3, opts)
+
+      assert {:error, {:error, _details}} = ObjectStoreX.get(store, "a.txt")
+
+      assert_receive {:objectstorex_trace, _, :finish,
+                      %{operation: :get, result: :error, status: 400}}
+    end
+
+    test "traces local stores" do
+      root = Path.join(System.tmp_dir!(), "objectstorex_trace_#{:rand.uniform(1_000_000)}")
+      on_exit(fn -> File.rm_rf!(root) end)
+
+      {:ok, store} = ObjectStoreX.new(:local, path: root, create_path: true, trace: self())
+      :ok = ObjectStoreX.put(store, "file.txt", "data")
+
+      assert_receive {:objectstorex_trace, _, :finish, %{operation: :put, result: :ok}}
+    end
+
+    test "untraced stores send nothing" do
+      {:ok, store} = ObjectStoreX.new(:memory, [])
+
+      :ok = ObjectStoreX.put(store, "file.txt", "data")
+      refute_receive {:objectstorex_trace, _, _, _}, 100
+    end
+  end
+end
@@ -0,0 +1,94 @@
+defmodule ObjectStoreX.TtlTest do
+  use ExUnit.Case, async: true
+
+  setup do
+    {:ok, store} = ObjectStoreX.new(:memory)
+    {:ok, store: store}
+  end
+
+  test "expire_now/3 deletes objects whose TTL has passed", %{store: store} do
+    :ok = ObjectStoreX.put_with_ttl(store, "sessions/old.json", "{}", 0)
+    :ok = ObjectStoreX.put_with_ttl(store, "sessions/new.json", "{}", 3600)
+    :ok = ObjectStoreX.put(store, "sessions/kept.json", "{}")
+
+    assert {:ok, ["sessions/old.json"]} = ObjectStoreX.expire_now(store, "sessions")
+
+    assert {:error, :not_found} = ObjectStoreX.get(store, "sessions/old.json")
+    assert {:ok, "{}"} = ObjectStoreX.get(store, "sessions/new.json")
+    assert {:ok, "{}"} = ObjectStoreX.get(store, "sessions/kept.json")
+    assert {:error, :not_found} = ObjectStoreX.head(store, ".objectstorex-ttl/sessions/old.json")
+    assert {:ok, _} = ObjectStoreX.head(store, ".objectstorex-ttl/sessions/new.json")
+  end
+
+  test "only covers the given prefix", %{store: store} do
+    :ok = ObjectStoreX.put_with_ttl(store, "a/file.txt", "data", 0)
+    :ok = ObjectStoreX.put_with_ttl(store, "b/file.txt", "data", 0)
+
+    assert {:ok, ["a/file.txt"]} = ObjectStoreX.expire_now(store, "a")
+    assert {:ok, ["b/file.txt"]} = ObjectStoreX.expire_now(store)
+  end
+
+  test "objects overwritten since are not expired", %{store: store} do
+    :ok = ObjectStoreX.put_with_ttl(store, "file.txt", "old", 0)
+    :ok = ObjectStoreX.put(store, "file.txt", "new")
+
+    assert {:ok, []} = ObjectStoreX.expire_now(store)
+    assert {:ok, "new"} = ObjectStoreX.get(store, "file.txt")
+    assert {:error, :not_found} = ObjectStoreX.head(store, ".objectstorex-ttl/file.txt")
+  end
+
+  test "records of deleted objects are removed", %{store: store} do
+    :ok = ObjectStoreX.put_with_ttl(store, "file.txt", "data", 0)
+    :ok = ObjectStoreX.delete(store, "file.txt")
+
+    assert {:ok, []} = ObjectStoreX.expire_now(store)
+    assert {:error, :not_found} = ObjectStoreX.head(store, ".objectstorex-ttl/file.txt")
+  end
+
+  test "records are left out of listings", %{store: store} do
+    :ok = ObjectStoreX.put_with_ttl(store, "sessions/a.json", "{}", 3600)
+
+    assert ["sessions/a.json"] =
+             ObjectStoreX.Stream.list_stream(store) |> Enum.map(& &1.location)
+
+    assert {:ok, [], prefixes} = ObjectStoreX.list_with_delimiter(store)
+    refute Enum.any?(prefixes, &String.starts_with?(&1, ".objectstorex-ttl"))
+  end
+
+  test "keys with a record segment below the root are listed", %{store: store} do
+    :ok = ObjectStoreX.put(store, "data/.objectstorex-ttl/user.txt", "mine")
+
+    assert ["data/.objectstorex-ttl/user.txt"] =
+             ObjectStoreX.Stream.list_stream(store) |> Enum.map(& &1.location)
+
+    assert {:ok, [], ["data/.objectstorex-ttl"]} =
+             ObjectStoreX.list_with_delimiter(store, prefix: "data")
+  end
+
+  test "records of derived handles are left out of their listings", %{store: store} do
+    {:ok, tenant} = ObjectStoreX.derive(store, prefix: "t")
+    :ok = ObjectStoreX.put_with_ttl(tenant, "a.json", "{}", 3600)
+
+    assert ["a.json"] = ObjectStoreX.Stream.list_stream(tenant) |> Enum.map(& &1.location)
+  end
+
+  test "records are not counted against quotas", %{store: store} do
+    {:ok, tenant} = ObjectStoreX.derive(store, prefix: "t", quota: 10)
+
+    assert :ok = ObjectStoreX.put_with_ttl(tenant, "a.bin", "12345678", 0)
+    assert {:ok, ["a.bin"]} = ObjectStoreX.expire_now(tenant)
+    assert :ok = ObjectStoreX.put(tenant, "b.bin", "1234567890")
+  end
+
+  test "works on local stores" do
+    dir = Path.join(System.tmp_dir!(), "objectstorex_ttl_#{System.unique_integer([:positive])}")
+    File.mkdir_p!(dir)
+    on_exit(fn -> File.rm_rf!(dir) end)
+
+    {:ok, store} = ObjectStoreX.new(:local, path: dir)
+    :ok = ObjectStoreX.put_with_ttl(store, "logs/today.log", "line", 0)
+
+    assert {:ok, ["logs/today.log"]} = ObjectStoreX.expire_now(store, "logs")
+    refute File.exists?(Path.join(dir, "logs/today.log"))
+  end
+end
@@ -0,0 +1,152 @@
+defmodule ObjectStoreX.VersionsTest do
+  use ExUnit.Case, async: true
+
+  alias ObjectStoreX.FakeS3
+
+  @page """
+  <Version>
+    <Key>reports/a.csv</Key>
+    <VersionId>v3</VersionId>
+    <IsLatest>true</IsLatest>
+    <LastModified>2024-01-03T00:00:00.000Z</LastModified>
+    <ETag>"e3"</ETag>
+    <Size>300</Size>
+  </Version>
+  <DeleteMarker>
+    <Key>reports/b.csv</Key>
+    <VersionId>v2</VersionId>
+    <IsLatest>true</IsLatest>
+    <LastModified>2024-01-02T00:00:00.000Z</LastModified>
+  </DeleteMarker>
+  <Version>
+    <Key>reports/a.csv</Key>
+    <VersionId>v1</VersionId>
+    <IsLatest>false</IsLatest>
+    <LastModified>2024-01-01T00:00:00.000Z</LastModified>
+    <ETag>"e1"</ETag>
+    <Size>100</Size>
+  </Version>
+  """
+
+  describe "list_versions/3 and delete_version/4" do
+    test "are not supported by stores without versioning" do
+      {:ok, store} = ObjectStoreX.new(:memory)
+      :ok = ObjectStoreX.put(store, "file.txt", "data")
+
+      assert {:error, :not_supported} = ObjectStoreX.list_versions(store, "")
+      assert {:error, :not_supported} = ObjectStoreX.delete_version(store, "file.txt", "v1")
+      assert {:ok, "data"} = ObjectStoreX.get(store, "file.txt")
+    end
+
+    test "are not supported by derived stores" do
+      {:ok, store} = ObjectStoreX.new(:s3, bucket: "test", region: "us-east-1")
+      {:ok, child} = ObjectStoreX.derive(store, prefix: "tenant/")
+
+      assert {:error, :not_supported} = ObjectStoreX.list_versions(child)
+    end
+  end
+
+  describe "list_versions/3 on S3" do
+    test "decodes versions and delete markers" do
+      store = versions_store([{"200 OK", [], versions_page(@page, nil)}])
+
+      assert {:ok, [current, deleted, previous]} = ObjectStoreX.list_versions(store, "reports")
+
+      assert_receive {:request, %{request_line: request_line}}
+      assert request_line == "GET /test/?versions&prefix=reports%2F HTTP/1.1"
+
+      assert %{
+               location: "reports/a.csv",
+               version: "v3",
+               is_latest: true,
+               delete_marker: false,
+               size: 300,
+               etag: "\"e3\"",
+               last_modified: "2024-01-03 00:00:00 UTC"
+             } = current
+
+      assert %{
+               location: "reports/b.csv",
+               version: "v2",
+               is_latest: true,
+               delete_marker: true,
+               size: 0,
+               etag: nil
+             } = deleted
+
+      assert %{location: "reports/a.csv", version: "v1", is_latest: false, size: 100} = previous
+    end
+
+    test "follows the key and version id markers of truncated pages" do
+      first = versions_page(@page, {"reports/a b.csv", "v1"})
+
+      last =
+        versions_page(
+          """
+          <Version>
+            <Key>reports/c.csv</Key>
+            <VersionId>v9</VersionId>
+            <IsLatest>true</IsLatest>
+            <LastModified>2024-01-04T00:00:00.000Z</LastModified>
+            <ETag>"e9"</ETag>
+            <Size>900</Size>
+          </Version>
+          """,
+          nil
+        )
+
+      store = versions_store([{"200 OK", [], first}, {"200 OK", [], last}])
+
+      assert {:ok, versions} = ObjectStoreX.list_versions(store, "reports")
+      assert Enum.map(versions, & &1.version) == ["v3", "v2", "v1", "v9"]
+
+      assert_receive {:request, %{request_line: "GET /test/?versions&prefix=reports%2F HTTP/1.1"}}
+      assert_receive {:request, %{request_line: request_line}}
+
+      assert request_line ==
+               "GET /test/?versions&prefix=reports%2F&key-marker=reports%2Fa%20b.csv" <>
+                 "&version-id-marker=v1 HTTP/1.1"
+    end
+  end
+
+  describe "delete_version/4 on S3" do
+    test "deletes the given version id" do
+      store = versions_store([{"204 No Content", [], ""}])
+
+      assert :ok = ObjectStoreX.delete_version(store, "reports/a.csv", "v1/2+3")
+
+      assert_receive {:request, %{request_line: request_line}}
+      assert request_line == "DELETE /test/reports/a.csv?versionId=v1%2F2%2B3 HTTP/1.1"
+    end
+  end
+
+  defp versions_store(responses) do
+    endpoint = FakeS3.serve(responses)
+    {:ok, store} = ObjectStoreX.new(:s3, FakeS3.options() ++ [endpoint: endpoint])
+    store
+  end
+
+  # A ListVersionsResult page, truncated when `{key, version}` markers are given
+  defp versions_page(entries, markers) do
+    next =
+      case markers do
+        {key, version} ->
+          "<NextKeyMarker>#{key}</NextKeyMarker>" <>
+            "<NextVersionIdMarker>#{version}</NextVersionIdMarker>"
+
+        nil ->
+          ""
+      end
+
+    """
+    <?xml version="1.0" encoding="UTF-8"?>
+    <ListVersionsResult xmlns="http://s3.amazonaws.com/doc/2006-03-01/">
+      <Name>test</Name>
+      <Prefix>reports/</Prefix>
+      <IsTruncated>#{markers != nil}</IsTruncated>
+      #{next}
+      #{entries}
+    </ListVersionsResult>
+    """
+  end
+end